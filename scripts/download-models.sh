@@ -4,6 +4,7 @@
 # Usage: ./download-models.sh [model_name]
 # Example: ./download-models.sh base
 # Or: ./download-models.sh all
+# Quantized: ./download-models.sh medium-q5_0
 
 set -e
 
@@ -55,8 +56,11 @@ if [ "$MODEL" = "all" ]; then
     download_model "small"
     download_model "medium"
     download_model "large"
+    download_model "large-v2"
+    download_model "large-v3"
+    download_model "large-v3-turbo"
     echo "🎉 All models downloaded!"
-elif [ "$MODEL" = "tiny" ] || [ "$MODEL" = "base" ] || [ "$MODEL" = "small" ] || [ "$MODEL" = "medium" ] || [ "$MODEL" = "large" ]; then
+elif [[ "$MODEL" =~ ^(tiny|base|small|medium|tiny\.en|base\.en|small\.en|medium\.en|large|large-v2|large-v3|large-v3-turbo)(-q5_0|-q5_1|-q8_0)?$ ]]; then
     download_model "$MODEL"
     echo "🎉 Model downloaded!"
 else
@@ -69,8 +73,16 @@ else
     echo "  base    - 142 MB  (recommended for testing)"
     echo "  small   - 466 MB  (good balance)"
     echo "  medium  - 1.5 GB  (high accuracy)"
-    echo "  large   - 2.9 GB  (best accuracy, slowest)"
+    echo "  large   - 2.9 GB  (original large-v1)"
+    echo "  large-v2        - 2.9 GB"
+    echo "  large-v3        - 2.9 GB  (best accuracy, slowest)"
+    echo "  large-v3-turbo  - 1.5 GB  (near large-v3 accuracy, much faster)"
+    echo "  tiny.en / base.en / small.en / medium.en"
+    echo "          - English-only variants (same sizes, more accurate on English)"
     echo "  all     - Download all models"
+    echo ""
+    echo "Quantized variants: append -q5_0, -q5_1 or -q8_0 (e.g. medium-q5_0)."
+    echo "Not every size is published in every quantization."
     exit 1
 fi
 
@@ -81,5 +93,7 @@ echo "  base:   ~142 MB"
 echo "  small:  ~466 MB"
 echo "  medium: ~1.5 GB"
 echo "  large:  ~2.9 GB"
+echo "  large-v2 / large-v3: ~2.9 GB"
+echo "  large-v3-turbo: ~1.5 GB"
 echo ""
 echo "💡 Tip: Start with 'base' model for testing!"
@@ -0,0 +1,181 @@
+//! `GET /files/{video_id}/{format}`: transcript files over HTTP.
+//!
+//! Remote MCP clients get server-local paths back from the tools, which
+//! they can't open. This route serves the same files from the default
+//! output directory, as stored (decrypted if encryption at rest is on).
+//! `srt` and `vtt` that weren't written are rendered from the segments in
+//! the JSON output. It sits behind the same API keys as
+//! `/mcp`, and a key limited to a tool allowlist needs `read_transcript`.
+
+use axum::{
+    Json, Router,
+    extract::Path,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde_json::json;
+use std::path::PathBuf;
+
+use crate::mcp::policy;
+use crate::transcriber::store;
+use crate::transcriber::subtitles;
+use crate::transcriber::types::{OutputFormat, Segment};
+use crate::utils::encryption;
+use crate::utils::paths::{get_default_output_dir, transcript_files};
+
+pub fn router() -> Router {
+    Router::new().route("/files/{video_id}/{format}", get(get_file))
+}
+
+async fn get_file(
+    Path((video_id, format)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    match policy::headers_key(&headers) {
+        Err(msg) => return error(StatusCode::UNAUTHORIZED, msg),
+        Ok(Some(key)) if !key.allows("read_transcript") => {
+            return error(StatusCode::FORBIDDEN, "this key may not read transcripts");
+        }
+        Ok(_) => {}
+    }
+
+    let Some(output_format) = content_type(&format).and_then(|_| format.parse().ok()) else {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "format must be txt, json, md, srt or vtt",
+        );
+    };
+    let Some(base) = find_transcript(&get_default_output_dir(), &video_id) else {
+        return error(StatusCode::NOT_FOUND, "no transcript for this video id");
+    };
+
+    // Titles may hold dots, so the extension is appended, not replaced.
+    let stored = store::file(&base, output_format);
+    let body = match format.as_str() {
+        "srt" | "vtt" if !stored.exists() => match read_segments(&base) {
+            Ok(segments) if format == "srt" => subtitles::srt(&segments).into_bytes(),
+            Ok(segments) => subtitles::vtt(&segments).into_bytes(),
+            Err(msg) => return error(StatusCode::NOT_FOUND, &msg),
+        },
+        _ => match encryption::read(&stored) {
+            Ok(bytes) => bytes,
+            Err(_) => return error(StatusCode::NOT_FOUND, "file not found"),
+        },
+    };
+
+    attachment(&video_id, &format, body)
+}
+
+/// MIME type of an output `format`, if it is one.
+pub(super) fn content_type(format: &str) -> Option<&'static str> {
+    match format {
+        "txt" => Some("text/plain; charset=utf-8"),
+        "md" => Some("text/markdown; charset=utf-8"),
+        "json" => Some("application/json"),
+        "srt" => Some("application/x-subrip; charset=utf-8"),
+        "vtt" => Some("text/vtt; charset=utf-8"),
+        _ => None,
+    }
+}
+
+/// `body` as a download named `<name>.<format>`.
+pub(super) fn attachment(name: &str, format: &str, body: Vec<u8>) -> Response {
+    let filename: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    let disposition = format!("attachment; filename=\"{}.{}\"", filename, format);
+    let content_type = content_type(format).unwrap_or("application/octet-stream");
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (
+                header::CONTENT_DISPOSITION,
+                HeaderValue::from_str(&disposition)
+                    .unwrap_or_else(|_| HeaderValue::from_static("attachment")),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+fn error(status: StatusCode, msg: &str) -> Response {
+    (status, Json(json!({ "error": msg }))).into_response()
+}
+
+/// Output files are named `<video_id>-<title>.<ext>`, possibly in an
+/// `output_layout` subdirectory. Returns the newest matching full (not
+/// clipped) transcript in any format, without its extension.
+fn find_transcript(output_dir: &std::path::Path, video_id: &str) -> Option<PathBuf> {
+    let prefix = format!("{}-", video_id);
+    transcript_files(output_dir)
+        .into_iter()
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with(&prefix)
+                && !name.contains(".clip-")
+                && !name.ends_with(".partial.json")
+        })
+        .max_by_key(|path| path.metadata().and_then(|m| m.modified()).ok())
+        .map(|path| path.with_extension(""))
+}
+
+fn read_segments(base: &std::path::Path) -> Result<Vec<Segment>, String> {
+    let json = encryption::read_to_string(&store::file(base, OutputFormat::Json))
+        .map_err(|_| "file not found".to_string())?;
+    let value: serde_json::Value =
+        serde_json::from_str(&json).map_err(|_| "unreadable JSON output".to_string())?;
+    let segments = value
+        .get("segments")
+        .ok_or("this transcript predates stored segments; re-transcribe for srt/vtt")?;
+    serde_json::from_value(segments.clone()).map_err(|_| "unreadable segments".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_newest_full_transcript_by_video_id() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "abc123-My Talk.json",
+            "abc123-My Talk.txt",
+            "abc123-My Talk.clip-0-60.json",
+            "abc123-My Talk.pdf",
+            "srtonly-Clip.srt",
+            "abc1234-Other.json",
+            "YouTube/2024/nested/nested-Deep.md",
+        ] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "{}").unwrap();
+        }
+        assert_eq!(
+            find_transcript(dir.path(), "abc123"),
+            Some(dir.path().join("abc123-My Talk"))
+        );
+        assert_eq!(
+            find_transcript(dir.path(), "srtonly"),
+            Some(dir.path().join("srtonly-Clip"))
+        );
+        assert_eq!(
+            find_transcript(dir.path(), "nested"),
+            Some(dir.path().join("YouTube/2024/nested/nested-Deep"))
+        );
+        assert_eq!(find_transcript(dir.path(), "zzz"), None);
+    }
+
+    #[test]
+    fn reads_segments_of_titles_with_dots() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = r#"{"segments": [{"start_ms": 0, "end_ms": 1000, "text": "Hi"}]}"#;
+        std::fs::write(dir.path().join("abc-Node.js Crash Course.json"), json).unwrap();
+        std::fs::write(dir.path().join("abc-Node.json"), "{}").unwrap();
+
+        let base = dir.path().join("abc-Node.js Crash Course");
+        assert_eq!(read_segments(&base).unwrap().len(), 1);
+    }
+}
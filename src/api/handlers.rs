@@ -1,31 +1,45 @@
 use axum::{
     Json,
+    body::Bytes,
     extract::{Multipart, Path, State},
     http::{HeaderMap, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
 };
+use futures_util::{Stream, stream};
+use serde::Deserialize;
 use serde_json::{Value, json};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tempfile::TempDir;
-use tokio::io::AsyncWriteExt;
-use tokio::sync::Mutex;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 use uuid::Uuid;
 
-use crate::api::jobs::{Job, JobRequest, JobResult, JobStatus, JobStore, parse_model};
+use crate::api::jobs::{Job, JobRequest, JobResult, JobStatus, JobStore};
+use crate::api::transcriptions::TranscriptionStore;
+use crate::api::uploads::{self, ChunkError, UploadSession, UploadStore};
 use crate::auth::{AuthUser, JwksCache};
 use crate::credits::{self, CreditStore, is_valid_device_id};
 use crate::llm::summarize_and_diagram;
-use crate::transcriber::{TranscriberEngine, TranscriptionOptions};
-use crate::utils::paths::get_default_output_dir;
+use crate::mcp::queue::JobQueue;
+use crate::transcriber::audio;
+use crate::transcriber::progress::Progress;
+use crate::transcriber::types::{AudioTrackSelection, ClipRange, DecodingOptions};
+use crate::transcriber::usage::UsageMeter;
+use crate::transcriber::webhook;
 use axum::extract::FromRef;
 
 #[derive(Clone)]
 pub struct AppState {
     pub jobs: JobStore,
-    pub engine: Arc<Mutex<TranscriberEngine>>,
+    /// Resumable uploads still receiving chunks.
+    pub uploads: UploadStore,
+    /// Jobs started through `/api/transcriptions`.
+    pub transcriptions: TranscriptionStore,
+    /// The job slots and engine shared with the MCP sessions.
+    pub queue: Arc<JobQueue>,
     pub credits: CreditStore,
     /// Cached Supabase JWKS for verifying incoming auth tokens. Cloned cheaply
     /// (Arc) on every request. `None` only when SUPABASE_URL isn't set, in
@@ -56,6 +70,9 @@ pub async fn get_me(AuthUser(claims): AuthUser) -> (StatusCode, Json<Value>) {
 
 const DEVICE_ID_HEADER: &str = "x-device-id";
 
+/// Byte position a resumable upload chunk starts at (as in tus).
+const UPLOAD_OFFSET_HEADER: &str = "upload-offset";
+
 /// Public wrapper so the Stripe checkout handler resolves the same identity
 /// (authenticated account preferred, else device id) as the job handlers —
 /// ensuring a purchase credits the account a signed-in user is actually using.
@@ -151,6 +168,13 @@ pub async fn create_job(
         Err(e) => return e,
     };
 
+    if let Err(e) = ClipRange::new(req.clip.start_time, req.clip.end_time) {
+        return bad_request(&e.to_string());
+    }
+    if let Err(e) = check_request(&req) {
+        return bad_request(&e);
+    }
+
     // Reserve a credit upfront. Refunded later if the pipeline ends in
     // Failed or Cancelled. Atomic — concurrent requests can't both pass at
     // balance=1.
@@ -158,35 +182,9 @@ pub async fn create_job(
         return payment_required(0);
     }
 
-    let job_id = Uuid::new_v4();
-    let now = now_unix();
-    let cancel = CancellationToken::new();
-
-    let job = Job {
-        id: job_id,
-        status: JobStatus::Queued,
-        url: req.url.clone(),
-        device_id: device_id.clone(),
-        created_at: now,
-        updated_at: now,
-        result: None,
-        error: None,
-        cancel: cancel.clone(),
-    };
-
-    {
-        let mut store = state.jobs.lock().await;
-        store.insert(job_id, job);
-    }
-
-    info!("Created job {} for url {}", job_id, req.url);
-
-    let store = state.jobs.clone();
-    let engine = state.engine.clone();
-    let credit_store = state.credits.clone();
-    tokio::spawn(async move {
-        run_with_cancel(job_id, req, engine, store, credit_store, device_id, cancel).await
-    });
+    let url = req.url.clone();
+    let job_id = start_job(&state, req, device_id, None).await;
+    info!("Created job {} for url {}", job_id, url);
 
     (StatusCode::ACCEPTED, Json(json!({ "job_id": job_id })))
 }
@@ -230,13 +228,17 @@ pub async fn claim_account(
         credits::ClaimOutcome::AlreadyClaimed { balance } => {
             (balance, "already claimed".to_string())
         }
-        credits::ClaimOutcome::Migrated { from_device, balance } => (
+        credits::ClaimOutcome::Migrated {
+            from_device,
+            balance,
+        } => (
             balance,
             format!("migrated {from_device} credits from this device"),
         ),
-        credits::ClaimOutcome::Seeded { balance } => {
-            (balance, format!("welcome — {balance} free credits to start"))
-        }
+        credits::ClaimOutcome::Seeded { balance } => (
+            balance,
+            format!("welcome — {balance} free credits to start"),
+        ),
     };
     (
         StatusCode::OK,
@@ -256,6 +258,34 @@ pub async fn get_job(
         .ok_or(StatusCode::NOT_FOUND)
 }
 
+/// GET /api/jobs/{id}/events — the job's progress as Server-Sent Events.
+/// A `progress` event carries the stage, and while transcribing the percent
+/// done and latest segment. One is sent on connecting and then on every
+/// change, and the stream ends after the job finishes.
+pub async fn job_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, StatusCode> {
+    let rx = state
+        .jobs
+        .lock()
+        .await
+        .get(&id)
+        .map(|job| job.progress.subscribe())
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let events = stream::unfold((Some(rx), true), |(rx, first)| async move {
+        let mut rx = rx?;
+        if !first && rx.changed().await.is_err() {
+            return None;
+        }
+        let snapshot = rx.borrow_and_update().clone();
+        let event = Event::default().event("progress").json_data(&snapshot);
+        let rx = (!snapshot.stage.is_finished()).then_some(rx);
+        Some((event, (rx, false)))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
 /// Best-effort cancellation. Idempotent: hitting cancel on a completed,
 /// failed, or already-cancelled job is fine (returns the current status).
 /// Calling .cancel() on a token whose select! arm has already resolved is a
@@ -267,8 +297,12 @@ pub async fn cancel_job(
     let store = state.jobs.lock().await;
     let job = store.get(&id).ok_or(StatusCode::NOT_FOUND)?;
     job.cancel.cancel();
-    info!("Cancel signalled for job {} (current status: {:?})", id, job.status);
-    Ok(Json(json!({ "ok": true, "status": job.status })))
+    info!(
+        "Cancel signalled for job {} (current status: {:?})",
+        id,
+        job.progress.stage()
+    );
+    Ok(Json(json!({ "ok": true, "status": job.progress.stage() })))
 }
 
 pub async fn upload_job(
@@ -288,7 +322,6 @@ pub async fn upload_job(
         return payment_required(0);
     }
 
-    let cancel = CancellationToken::new();
     let mut saved_path: Option<PathBuf> = None;
     // RAII guard around the upload's tempdir. When this is dropped — at the
     // end of the spawned pipeline task — the tempdir + file are wiped. This
@@ -299,6 +332,17 @@ pub async fn upload_job(
     let mut original_filename: Option<String> = None;
     let mut model_str: Option<String> = None;
     let mut language: Option<String> = None;
+    let mut chunk_minutes: Option<u32> = None;
+    let mut gpu: Option<bool> = None;
+    let mut audio_track: Option<String> = None;
+    let mut min_audio_quality: Option<u8> = None;
+    let mut skip_non_speech: Option<bool> = None;
+    let mut skip_silence: Option<bool> = None;
+    let mut audio_filters: Option<String> = None;
+    let mut source_note: Option<String> = None;
+    let mut webhook_url: Option<String> = None;
+    let mut decoding = DecodingOptions::default();
+    let mut hotwords: Vec<String> = Vec::new();
 
     // Stream each field. The "file" field gets streamed to disk so we don't
     // hold a multi-GB upload in RAM.
@@ -314,10 +358,7 @@ pub async fn upload_job(
         let name = field.name().unwrap_or("").to_string();
         match name.as_str() {
             "file" => {
-                let raw_name = field
-                    .file_name()
-                    .unwrap_or("upload.bin")
-                    .to_string();
+                let raw_name = field.file_name().unwrap_or("upload.bin").to_string();
                 let safe_name = sanitize_filename(&raw_name);
 
                 // Use a tempfile::TempDir so the directory + file are wiped
@@ -363,6 +404,49 @@ pub async fn upload_job(
             }
             "model" => model_str = field.text().await.ok(),
             "language" => language = field.text().await.ok(),
+            "chunk_minutes" => {
+                chunk_minutes = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "gpu" => gpu = field.text().await.ok().and_then(|s| s.trim().parse().ok()),
+            "audio_track" => audio_track = field.text().await.ok(),
+            "skip_non_speech" => {
+                skip_non_speech = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "skip_silence" => {
+                skip_silence = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "audio_filters" | "preprocess" => audio_filters = field.text().await.ok(),
+            "beam_size" => {
+                decoding.beam_size = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "best_of" => {
+                decoding.best_of = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "temperature" => {
+                decoding.temperature = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "entropy_threshold" => {
+                decoding.entropy_threshold =
+                    field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "no_speech_threshold" => {
+                decoding.no_speech_threshold =
+                    field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "alternatives" => {
+                decoding.alternatives = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "min_audio_quality" => {
+                min_audio_quality = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "source_note" | "consent" => source_note = field.text().await.ok(),
+            "webhook_url" => webhook_url = field.text().await.ok().filter(|u| !u.is_empty()),
+            // Comma-separated.
+            "hotwords" => {
+                if let Ok(text) = field.text().await {
+                    hotwords.extend(text.split(',').map(str::to_string));
+                }
+            }
             _ => {
                 // Drain unknown fields so the parser stays happy.
                 let _ = field.bytes().await;
@@ -376,17 +460,325 @@ pub async fn upload_job(
     };
     let url = path.to_string_lossy().to_string();
 
+    let req = JobRequest {
+        url: url.clone(),
+        model: model_str,
+        language,
+        chunk_minutes,
+        gpu,
+        audio_track,
+        min_audio_quality,
+        skip_non_speech,
+        skip_silence,
+        audio_filters,
+        decoding,
+        hotwords,
+        prefer_captions: None,
+        source_note,
+        clip: Default::default(),
+        webhook_url,
+    };
+    if let Err(e) = check_request(&req) {
+        credits::refund(&state.credits, &device_id).await;
+        return bad_request(&e);
+    }
+    let job_id = start_job(&state, req, device_id, saved_tempdir).await;
+    info!(
+        "Created upload job {} for file {} ({})",
+        job_id,
+        original_filename.as_deref().unwrap_or("?"),
+        url
+    );
+
+    (StatusCode::ACCEPTED, Json(json!({ "job_id": job_id })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewUpload {
+    pub filename: String,
+    pub size: u64,
+}
+
+/// POST /api/uploads — open a resumable upload for a file of `size` bytes.
+/// Checks the balance up front so nobody sends gigabytes only to be
+/// refused; the credit itself is reserved when the upload completes.
+pub async fn create_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<NewUpload>,
+) -> (StatusCode, Json<Value>) {
+    let device_id = match resolve_identity(&state, &headers).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    if req.size == 0 || req.size > uploads::MAX_UPLOAD_BYTES {
+        return bad_request(&format!(
+            "size must be between 1 and {} bytes",
+            uploads::MAX_UPLOAD_BYTES
+        ));
+    }
+    let balance = credits::balance(&state.credits, &device_id).await;
+    if balance <= 0 {
+        return payment_required(balance);
+    }
+
+    let dir = match tempfile::Builder::new()
+        .prefix("transcriber-upload-")
+        .tempdir()
+    {
+        Ok(t) => t,
+        Err(e) => return server_error(&format!("tempdir: {}", e)),
+    };
+    let filename = sanitize_filename(&req.filename);
+    let path = dir.path().join(&filename);
+    if let Err(e) = tokio::fs::File::create(&path).await {
+        return server_error(&format!("file create: {}", e));
+    }
+
+    let session = UploadSession {
+        id: Uuid::new_v4(),
+        device_id,
+        filename,
+        size: req.size,
+        offset: 0,
+        writing: Default::default(),
+        updated_at: now_unix(),
+        dir,
+        path,
+    };
+    let status = session.status();
+    info!(
+        "Opened upload {} for {} ({} bytes)",
+        session.id, session.filename, session.size
+    );
+    let mut sessions = state.uploads.lock().await;
+    uploads::expire(&mut sessions, now_unix());
+    sessions.insert(session.id, session);
+
+    (StatusCode::CREATED, Json(json!(status)))
+}
+
+/// GET /api/uploads/{id} — how many bytes have arrived, i.e. where to resume.
+pub async fn get_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> (StatusCode, Json<Value>) {
+    let device_id = match resolve_identity(&state, &headers).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    match state.uploads.lock().await.get(&id) {
+        Some(s) if s.device_id == device_id => (StatusCode::OK, Json(json!(s.status()))),
+        _ => upload_not_found(),
+    }
+}
+
+/// PATCH /api/uploads/{id} — append the body at the `Upload-Offset` header,
+/// which must equal the bytes received so far. A mismatch answers 409 with
+/// the server's offset, so the client can resume from there.
+pub async fn upload_chunk(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    let device_id = match resolve_identity(&state, &headers).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    let Some(offset) = headers
+        .get(UPLOAD_OFFSET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    else {
+        return bad_request("missing or invalid Upload-Offset header");
+    };
+
+    // Held until the offset is updated, and released if this request is
+    // dropped mid-write.
+    let (path, _writing) = {
+        let sessions = state.uploads.lock().await;
+        let session = match sessions.get(&id) {
+            Some(s) if s.device_id == device_id => s,
+            _ => return upload_not_found(),
+        };
+        match session.begin_chunk(offset, body.len()) {
+            Ok(writing) => (session.path.clone(), writing),
+            Err(e) => {
+                let status = match e {
+                    ChunkError::Busy | ChunkError::WrongOffset => StatusCode::CONFLICT,
+                    ChunkError::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+                    ChunkError::PastEnd => StatusCode::BAD_REQUEST,
+                };
+                return (
+                    status,
+                    Json(json!({ "error": e.to_string(), "offset": session.offset })),
+                );
+            }
+        }
+    };
+
+    let written = append_chunk(&path, offset, &body).await;
+
+    let mut sessions = state.uploads.lock().await;
+    let Some(session) = sessions.get_mut(&id) else {
+        return upload_not_found();
+    };
+    if let Err(e) = written {
+        return server_error(&format!("write: {}", e));
+    }
+    session.offset += body.len() as u64;
+    session.updated_at = now_unix();
+    (StatusCode::OK, Json(json!(session.status())))
+}
+
+/// Write `chunk` at `offset`. On failure the file is cut back to `offset`,
+/// so a retry of the same chunk starts clean.
+async fn append_chunk(path: &std::path::Path, offset: u64, chunk: &[u8]) -> std::io::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    let result = async {
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+        file.write_all(chunk).await?;
+        file.flush().await
+    }
+    .await;
+    if result.is_err() {
+        let _ = file.set_len(offset).await;
+    }
+    result
+}
+
+/// DELETE /api/uploads/{id} — abandon an upload and discard its bytes.
+pub async fn cancel_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> (StatusCode, Json<Value>) {
+    let device_id = match resolve_identity(&state, &headers).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    let mut sessions = state.uploads.lock().await;
+    match sessions.get(&id) {
+        Some(s) if s.device_id == device_id && !s.is_busy() => {
+            sessions.remove(&id);
+            info!("Upload {} cancelled", id);
+            (StatusCode::OK, Json(json!({ "ok": true })))
+        }
+        Some(s) if s.device_id == device_id => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "a chunk is still being written" })),
+        ),
+        _ => upload_not_found(),
+    }
+}
+
+/// POST /api/uploads/{id}/complete — start a job on the assembled file. The
+/// body takes the same options as `POST /api/jobs`, minus `url`.
+pub async fn complete_upload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    body: Option<Json<Value>>,
+) -> (StatusCode, Json<Value>) {
+    let device_id = match resolve_identity(&state, &headers).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+
+    let mut options = match body {
+        Some(Json(Value::Object(map))) => map,
+        Some(Json(Value::Null)) | None => Default::default(),
+        Some(_) => return bad_request("options must be a JSON object"),
+    };
+    let session = {
+        let mut sessions = state.uploads.lock().await;
+        match sessions.get(&id) {
+            Some(s) if s.device_id == device_id => {
+                if s.is_busy() || !s.is_complete() {
+                    return (
+                        StatusCode::CONFLICT,
+                        Json(json!({
+                            "error": "upload isn't complete yet",
+                            "offset": s.offset,
+                            "size": s.size,
+                        })),
+                    );
+                }
+            }
+            _ => return upload_not_found(),
+        }
+        sessions.remove(&id).expect("checked above")
+    };
+
+    options.insert(
+        "url".to_string(),
+        Value::String(session.path.to_string_lossy().to_string()),
+    );
+    let req: JobRequest = match serde_json::from_value(Value::Object(options)) {
+        Ok(req) => req,
+        Err(e) => {
+            state.uploads.lock().await.insert(id, session);
+            return bad_request(&format!("invalid options: {}", e));
+        }
+    };
+    if let Err(e) = ClipRange::new(req.clip.start_time, req.clip.end_time) {
+        state.uploads.lock().await.insert(id, session);
+        return bad_request(&e.to_string());
+    }
+    if let Err(e) = check_request(&req) {
+        state.uploads.lock().await.insert(id, session);
+        return bad_request(&e);
+    }
+    if credits::reserve(&state.credits, &device_id).await.is_err() {
+        // Keep the bytes: the upload can still be completed after a top-up.
+        state.uploads.lock().await.insert(id, session);
+        return payment_required(0);
+    }
+
+    let filename = session.filename.clone();
+    let job_id = start_job(&state, req, device_id, Some(session.dir)).await;
+    info!(
+        "Created upload job {} for file {} (resumable upload {})",
+        job_id, filename, id
+    );
+
+    (StatusCode::ACCEPTED, Json(json!({ "job_id": job_id })))
+}
+
+fn upload_not_found() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "upload not found" })),
+    )
+}
+
+/// Register a queued job for `req` and run it in the background.
+/// `upload_dir` is moved into the spawned task: the TempDir's Drop runs when
+/// the task ends (success, failure, panic, cancellation), removing the
+/// uploaded file and its parent directory from /tmp. Dropping it any
+/// earlier would delete the file before the pipeline reads it.
+async fn start_job(
+    state: &AppState,
+    req: JobRequest,
+    device_id: String,
+    upload_dir: Option<TempDir>,
+) -> Uuid {
     let job_id = Uuid::new_v4();
     let now = now_unix();
+    let cancel = CancellationToken::new();
+
     let job = Job {
         id: job_id,
-        status: JobStatus::Queued,
-        url: url.clone(),
+        progress: Progress::new(JobStatus::Queued),
+        url: req.url.clone(),
         device_id: device_id.clone(),
         created_at: now,
         updated_at: now,
         result: None,
         error: None,
+        usage: None,
         cancel: cancel.clone(),
     };
 
@@ -395,35 +787,17 @@ pub async fn upload_job(
         store.insert(job_id, job);
     }
 
-    info!(
-        "Created upload job {} for file {} ({})",
-        job_id,
-        original_filename.as_deref().unwrap_or("?"),
-        url
-    );
-
-    let req = JobRequest {
-        url,
-        model: model_str,
-        language,
-    };
     let store = state.jobs.clone();
-    let engine = state.engine.clone();
+    let queue = state.queue.clone();
     let credit_store = state.credits.clone();
-    // Move `saved_tempdir` into the spawned task. The TempDir's Drop runs
-    // when the task ends (success, failure, panic, cancellation) — at which
-    // point the uploaded file and its parent directory are removed from
-    // /tmp. Without the move, the TempDir would drop here at the end of
-    // `upload_job`, deleting the file before the pipeline reads it.
     tokio::spawn(async move {
-        let _upload_guard = saved_tempdir;
-        run_with_cancel(job_id, req, engine, store, credit_store, device_id, cancel).await
+        let _upload_guard = upload_dir;
+        run_with_cancel(job_id, req, queue, store, credit_store, device_id, cancel).await
     });
-
-    (StatusCode::ACCEPTED, Json(json!({ "job_id": job_id })))
+    job_id
 }
 
-fn sanitize_filename(name: &str) -> String {
+pub(super) fn sanitize_filename(name: &str) -> String {
     let cleaned: String = name
         .chars()
         .map(|c| match c {
@@ -438,6 +812,23 @@ fn sanitize_filename(name: &str) -> String {
     }
 }
 
+/// Reject unusable `audio_filters` or `webhook_url` before a credit is
+/// spent on them.
+pub(super) fn check_request(req: &JobRequest) -> Result<(), String> {
+    if let Some(spec) = &req.audio_filters {
+        audio::filter_chain(spec).map_err(|e| format!("invalid audio_filters: {:#}", e))?;
+    }
+    if let Some(track) = &req.audio_track {
+        track
+            .parse::<AudioTrackSelection>()
+            .map_err(|e| e.to_string())?;
+    }
+    if let Some(url) = &req.webhook_url {
+        webhook::check_url(url).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
 fn bad_request(msg: &str) -> (StatusCode, Json<Value>) {
     (StatusCode::BAD_REQUEST, Json(json!({ "error": msg })))
 }
@@ -460,51 +851,70 @@ fn server_error(msg: &str) -> (StatusCode, Json<Value>) {
 async fn run_with_cancel(
     job_id: Uuid,
     req: JobRequest,
-    engine: Arc<Mutex<TranscriberEngine>>,
+    queue: Arc<JobQueue>,
     store: JobStore,
     credit_store: CreditStore,
     device_id: String,
     cancel: CancellationToken,
 ) {
-    tokio::select! {
+    let meter = UsageMeter::start();
+    let bytes_downloaded = tokio::select! {
         _ = cancel.cancelled() => {
             info!("Job {} cancelled by client", job_id);
             mark_cancelled(&store, job_id).await;
             // Refund the credit we reserved at create_job time.
             credits::refund(&credit_store, &device_id).await;
+            0
         }
-        _ = run_pipeline(job_id, req, engine, store.clone(), credit_store.clone(), device_id.clone()) => {
+        bytes = run_pipeline(job_id, req, queue, store.clone(), credit_store.clone(), device_id.clone()) => {
             // run_pipeline set Complete (kept the reservation) or Failed
             // (refunded inside).
+            bytes
         }
+    };
+
+    let usage = meter.finish(bytes_downloaded);
+    info!("Job {} used {}", job_id, usage.summary());
+    if let Some(job) = store.lock().await.get_mut(&job_id) {
+        job.usage = Some(usage);
     }
 }
 
+/// Returns the bytes downloaded for the job's resource accounting.
 async fn run_pipeline(
     job_id: Uuid,
     req: JobRequest,
-    engine: Arc<Mutex<TranscriberEngine>>,
+    queue: Arc<JobQueue>,
     store: JobStore,
     credit_store: CreditStore,
     device_id: String,
-) {
-    let model = parse_model(req.model.as_deref());
-    let options = TranscriptionOptions {
-        url: req.url.clone(),
-        output_dir: get_default_output_dir().to_string_lossy().to_string(),
-        model,
-        language: req.language.clone(),
+) -> u64 {
+    let mut options = req.options();
+
+    // Queued until a slot frees up; MCP sessions wait in the same line.
+    let job = queue
+        .job(options.model.unwrap_or_default(), &options.url)
+        .await;
+    let engine = match queue.acquire_quietly(job).await {
+        Ok(slot) => slot,
+        Err(e) => {
+            error!("Job {} not admitted: {}", job_id, e.message);
+            mark_failed(&store, job_id, e.message.to_string()).await;
+            credits::refund(&credit_store, &device_id).await;
+            return 0;
+        }
     };
-
     update_status(&store, job_id, JobStatus::Downloading).await;
 
-    // The existing engine handles download → audio extraction → whisper as one call.
-    // Status flips to Transcribing right before the whisper step starts inside engine.
-    update_status(&store, job_id, JobStatus::Transcribing).await;
-    let transcription = {
-        let eng = engine.lock().await;
-        eng.transcribe(options).await
-    };
+    // The existing engine handles download → audio extraction → whisper as
+    // one call, and moves the job to Transcribing when whisper starts.
+    options.progress = store
+        .lock()
+        .await
+        .get(&job_id)
+        .map(|job| job.progress.clone());
+    let transcription = engine.transcribe(options).await;
+    drop(engine);
 
     let transcription = match transcription {
         Ok(t) => t,
@@ -512,9 +922,10 @@ async fn run_pipeline(
             error!("Transcription failed for job {}: {:#}", job_id, e);
             mark_failed(&store, job_id, format!("{:#}", e)).await;
             credits::refund(&credit_store, &device_id).await;
-            return;
+            return 0;
         }
     };
+    let bytes_downloaded = transcription.usage.bytes_downloaded;
 
     update_status(&store, job_id, JobStatus::Summarizing).await;
     let llm = match summarize_and_diagram(&transcription.transcript, &transcription.metadata).await
@@ -524,7 +935,7 @@ async fn run_pipeline(
             error!("LLM step failed for job {}: {:#}", job_id, e);
             mark_failed(&store, job_id, format!("{:#}", e)).await;
             credits::refund(&credit_store, &device_id).await;
-            return;
+            return bytes_downloaded;
         }
     };
 
@@ -535,24 +946,29 @@ async fn run_pipeline(
         summary_md: llm.summary_md,
         mermaid_src: llm.mermaid_src,
         key_points: llm.key_points,
-        model_used: transcription.model_used.as_str().to_string(),
+        model_used: transcription.model_label(),
+        gpu_used: transcription.gpu_used,
+        audio_quality: transcription.audio_quality.clone(),
+        non_speech: transcription.non_speech.clone(),
+        detected_language: transcription.detected_language.clone(),
     };
 
     {
         let mut store = store.lock().await;
         if let Some(job) = store.get_mut(&job_id) {
-            job.status = JobStatus::Complete;
+            job.progress.set_stage(JobStatus::Complete);
             job.result = Some(result);
             job.updated_at = now_unix();
         }
     }
     info!("Job {} complete", job_id);
+    bytes_downloaded
 }
 
 async fn update_status(store: &JobStore, job_id: Uuid, status: JobStatus) {
     let mut store = store.lock().await;
     if let Some(job) = store.get_mut(&job_id) {
-        job.status = status;
+        job.progress.set_stage(status);
         job.updated_at = now_unix();
     }
 }
@@ -562,11 +978,8 @@ async fn mark_failed(store: &JobStore, job_id: Uuid, error: String) {
     if let Some(job) = store.get_mut(&job_id) {
         // Don't overwrite a terminal status if the job was already cancelled
         // (e.g. cancel arrived just as the pipeline was returning an error).
-        if !matches!(
-            job.status,
-            JobStatus::Complete | JobStatus::Failed | JobStatus::Cancelled
-        ) {
-            job.status = JobStatus::Failed;
+        if !job.progress.stage().is_finished() {
+            job.progress.set_stage(JobStatus::Failed);
             job.error = Some(error);
             job.updated_at = now_unix();
         }
@@ -579,11 +992,8 @@ async fn mark_cancelled(store: &JobStore, job_id: Uuid) {
         // Only flip to Cancelled if the job is still in-flight — otherwise we'd
         // clobber a Complete result that landed in the race window between the
         // pipeline finishing and the cancel arriving.
-        if !matches!(
-            job.status,
-            JobStatus::Complete | JobStatus::Failed | JobStatus::Cancelled
-        ) {
-            job.status = JobStatus::Cancelled;
+        if !job.progress.stage().is_finished() {
+            job.progress.set_stage(JobStatus::Cancelled);
             job.updated_at = now_unix();
         }
     }
@@ -1,11 +1,20 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::transcriber::types::{Segment, VideoMetadata, WhisperModel};
+use crate::transcriber::TranscriptionOptions;
+use crate::transcriber::audio;
+use crate::transcriber::classify::AudioRegion;
+use crate::transcriber::progress::Progress;
+use crate::transcriber::quality::AudioQuality;
+use crate::transcriber::types::{
+    ClipRange, DecodingOptions, DetectedLanguage, Segment, VideoMetadata, WhisperModel,
+};
+use crate::transcriber::usage::ResourceUsage;
+use crate::utils::paths::get_default_output_dir;
 
 pub type JobStore = Arc<Mutex<HashMap<Uuid, Job>>>;
 
@@ -13,17 +22,8 @@ pub fn new_store() -> JobStore {
     Arc::new(Mutex::new(HashMap::new()))
 }
 
-#[derive(Debug, Clone, Copy, Serialize)]
-#[serde(rename_all = "snake_case")]
-pub enum JobStatus {
-    Queued,
-    Downloading,
-    Transcribing,
-    Summarizing,
-    Complete,
-    Failed,
-    Cancelled,
-}
+/// A job's status is the stage its progress is at.
+pub use crate::transcriber::progress::Stage as JobStatus;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct JobRequest {
@@ -32,6 +32,75 @@ pub struct JobRequest {
     pub model: Option<String>,
     #[serde(default)]
     pub language: Option<String>,
+    #[serde(default)]
+    pub chunk_minutes: Option<u32>,
+    #[serde(default)]
+    pub gpu: Option<bool>,
+    /// Track number ("2") or "all" for multi-track uploads.
+    #[serde(default)]
+    pub audio_track: Option<String>,
+    #[serde(default)]
+    pub min_audio_quality: Option<u8>,
+    #[serde(default)]
+    pub skip_non_speech: Option<bool>,
+    #[serde(default)]
+    pub skip_silence: Option<bool>,
+    /// Presets and / or raw ffmpeg filters, as for the MCP tools.
+    #[serde(default, alias = "preprocess")]
+    pub audio_filters: Option<String>,
+    #[serde(default)]
+    pub prefer_captions: Option<bool>,
+    /// Basis for recording / transcribing, kept in the transcript metadata.
+    #[serde(default, alias = "consent")]
+    pub source_note: Option<String>,
+    /// `start_time` / `end_time` at the top level of the request.
+    #[serde(default, flatten)]
+    pub clip: ClipRange,
+    /// `beam_size`, `best_of`, `temperature`, `entropy_threshold`,
+    /// `no_speech_threshold` at the top level of the request.
+    #[serde(default, flatten)]
+    pub decoding: DecodingOptions,
+    /// Names and terms to expect, as for the MCP tools.
+    #[serde(default)]
+    pub hotwords: Vec<String>,
+    /// Where to POST the outcome, as for the MCP tools.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn serialize_stage<S: Serializer>(progress: &Progress, serializer: S) -> Result<S::Ok, S::Error> {
+    progress.stage().serialize(serializer)
+}
+
+impl JobRequest {
+    /// Engine options for the request, writing to the default output
+    /// directory. The request must have been checked (see
+    /// `handlers::check_request`).
+    pub fn options(&self) -> TranscriptionOptions {
+        TranscriptionOptions {
+            url: self.url.clone(),
+            output_dir: get_default_output_dir().to_string_lossy().to_string(),
+            model: parse_model(self.model.as_deref()),
+            language: self.language.clone(),
+            chunk_minutes: self.chunk_minutes,
+            gpu: self.gpu,
+            audio_track: self.audio_track.as_deref().and_then(|s| s.parse().ok()),
+            min_audio_quality: self.min_audio_quality,
+            skip_non_speech: self.skip_non_speech,
+            skip_silence: self.skip_silence,
+            audio_filter: self
+                .audio_filters
+                .as_deref()
+                .and_then(|spec| audio::filter_chain(spec).ok().flatten()),
+            decoding: self.decoding.clone(),
+            hotwords: self.hotwords.clone(),
+            prefer_captions: self.prefer_captions,
+            source_note: self.source_note.clone(),
+            clip: self.clip,
+            webhook_url: self.webhook_url.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -43,12 +112,24 @@ pub struct JobResult {
     pub mermaid_src: String,
     pub key_points: Vec<String>,
     pub model_used: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu_used: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_quality: Option<AudioQuality>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub non_speech: Vec<AudioRegion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<DetectedLanguage>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct Job {
     pub id: Uuid,
-    pub status: JobStatus,
+    /// Stage, and while transcribing the percent done and latest segment,
+    /// as streamed by `GET /api/jobs/{id}/events`. Serialized as the
+    /// stage alone.
+    #[serde(rename = "status", serialize_with = "serialize_stage")]
+    pub progress: Progress,
     pub url: String,
     /// Device that owns this job — used to refund the credit on failure /
     /// cancellation. Not exposed to the client (different devices polling the
@@ -64,6 +145,10 @@ pub struct Job {
     pub result: Option<JobResult>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
+    /// Resources the job used, set once it finishes (whether it completed
+    /// or failed), so usage can be attributed to the device that ran it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
     /// Per-job cancellation signal. Cloned into the spawned pipeline task and
     /// fired by `DELETE /api/jobs/{id}`. Dropping the in-flight futures (Modal
     /// HTTP request, OpenRouter HTTP request) closes their TCP connections so
@@ -72,7 +157,8 @@ pub struct Job {
     pub cancel: CancellationToken,
 }
 
-pub fn parse_model(s: Option<&str>) -> WhisperModel {
+/// Parse the requested model. `None` (absent or unrecognised) lets the
+/// platform profile pick, falling back to base.
+pub fn parse_model(s: Option<&str>) -> Option<WhisperModel> {
     s.and_then(|m| m.parse::<WhisperModel>().ok())
-        .unwrap_or(WhisperModel::Base)
 }
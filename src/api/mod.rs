@@ -1,11 +1,15 @@
+pub mod files;
 pub mod handlers;
 pub mod jobs;
+pub mod openai;
 pub mod stripe;
+pub mod transcriptions;
+pub mod uploads;
 
 use axum::{
     Router,
     extract::DefaultBodyLimit,
-    routing::{get, post},
+    routing::{get, patch, post},
 };
 
 pub use handlers::AppState;
@@ -15,17 +19,41 @@ pub use jobs::new_store;
 // limit is mostly to refuse genuinely insane uploads.
 const UPLOAD_MAX_BYTES: usize = 2 * 1024 * 1024 * 1024;
 
-pub fn router(state: AppState) -> Router {
-    Router::new()
-        .route("/jobs", post(handlers::create_job))
+/// In `read_only` mode the job-creating endpoints aren't mounted at all.
+pub fn router(state: AppState, read_only: bool) -> Router {
+    let mut router = Router::new()
         .route(
             "/jobs/{id}",
             get(handlers::get_job).delete(handlers::cancel_job),
         )
+        .route("/jobs/{id}/events", get(handlers::job_events))
+        .route("/transcriptions/{id}", get(transcriptions::get))
         .route(
-            "/jobs/upload",
-            post(handlers::upload_job).layer(DefaultBodyLimit::max(UPLOAD_MAX_BYTES)),
-        )
+            "/transcriptions/{id}/files/{format}",
+            get(transcriptions::get_file),
+        );
+    if !read_only {
+        router = router
+            .route("/transcriptions", post(transcriptions::create))
+            .route("/jobs", post(handlers::create_job))
+            .route(
+                "/jobs/upload",
+                post(handlers::upload_job).layer(DefaultBodyLimit::max(UPLOAD_MAX_BYTES)),
+            );
+        router = router
+            .route("/uploads", post(handlers::create_upload))
+            .route(
+                "/uploads/{id}",
+                get(handlers::get_upload)
+                    .delete(handlers::cancel_upload)
+                    .merge(
+                        patch(handlers::upload_chunk)
+                            .layer(DefaultBodyLimit::max(uploads::MAX_CHUNK_BYTES)),
+                    ),
+            )
+            .route("/uploads/{id}/complete", post(handlers::complete_upload));
+    }
+    router
         .route("/balance", get(handlers::get_balance))
         .route("/me", get(handlers::get_me))
         .route("/auth/claim", post(handlers::claim_account))
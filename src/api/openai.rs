@@ -0,0 +1,279 @@
+//! `POST /v1/audio/transcriptions`: OpenAI's transcription API, served by
+//! the local engine.
+//!
+//! Tools built on an OpenAI client can point their base URL at this server
+//! and transcribe without changes. The request is OpenAI's multipart form:
+//! `file`, `model`, `language`, `prompt` (taken as hotwords, one per
+//! comma), `temperature` and `response_format` (`json`, `text`, `srt`,
+//! `vtt` or `verbose_json`). `model` names a Whisper model such as `small`;
+//! OpenAI's `whisper-1` and other names fall back to the default model.
+//! The answer comes back when the transcription is done, with jobs queued
+//! behind the same slots as MCP sessions and the REST API. Errors use
+//! OpenAI's `{"error": {"message", "type"}}` shape.
+//!
+//! The route sits behind the same API keys as `/mcp`, sent as the OpenAI
+//! key, and a key limited to a tool allowlist needs `transcribe_video`.
+
+use axum::{
+    Json, Router,
+    extract::{DefaultBodyLimit, Multipart, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::post,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tracing::{error, info};
+
+use crate::api::jobs::parse_model;
+use crate::mcp::policy;
+use crate::mcp::queue::JobQueue;
+use crate::transcriber::TranscriptionOptions;
+use crate::transcriber::subtitles;
+use crate::transcriber::types::TranscriptionResult;
+use crate::utils::paths::get_default_output_dir;
+
+pub fn router(queue: Arc<JobQueue>) -> Router {
+    Router::new()
+        .route(
+            "/v1/audio/transcriptions",
+            post(transcribe).layer(DefaultBodyLimit::max(super::UPLOAD_MAX_BYTES)),
+        )
+        .with_state(queue)
+}
+
+/// What `response_format` asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseFormat {
+    Json,
+    Text,
+    Srt,
+    Vtt,
+    VerboseJson,
+}
+
+impl ResponseFormat {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "json" => Some(Self::Json),
+            "text" => Some(Self::Text),
+            "srt" => Some(Self::Srt),
+            "vtt" => Some(Self::Vtt),
+            "verbose_json" => Some(Self::VerboseJson),
+            _ => None,
+        }
+    }
+}
+
+async fn transcribe(
+    State(queue): State<Arc<JobQueue>>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Response {
+    match policy::headers_key(&headers) {
+        Err(msg) => return error(StatusCode::UNAUTHORIZED, msg),
+        Ok(Some(key)) if !key.allows("transcribe_video") => {
+            return error(StatusCode::FORBIDDEN, "this key may not transcribe");
+        }
+        Ok(_) => {}
+    }
+
+    // Wiped when the request ends, like REST uploads.
+    let dir = match tempfile::Builder::new()
+        .prefix("transcriber-upload-")
+        .tempdir()
+    {
+        Ok(dir) => dir,
+        Err(e) => return server_error(&format!("tempdir: {}", e)),
+    };
+    let mut path = None;
+    let mut options = TranscriptionOptions {
+        output_dir: get_default_output_dir().to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let mut format = ResponseFormat::Json;
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return bad_request(&format!("multipart error: {}", e)),
+        };
+        match field.name().unwrap_or("") {
+            "file" => {
+                let name = super::handlers::sanitize_filename(field.file_name().unwrap_or(""));
+                let file_path = dir.path().join(name);
+                let mut file = match tokio::fs::File::create(&file_path).await {
+                    Ok(f) => f,
+                    Err(e) => return server_error(&format!("file create: {}", e)),
+                };
+                loop {
+                    match field.chunk().await {
+                        Ok(Some(chunk)) => {
+                            if let Err(e) = file.write_all(&chunk).await {
+                                return server_error(&format!("write: {}", e));
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => return bad_request(&format!("read chunk: {}", e)),
+                    }
+                }
+                if let Err(e) = file.flush().await {
+                    return server_error(&format!("flush: {}", e));
+                }
+                path = Some(file_path);
+            }
+            "model" => options.model = parse_model(field.text().await.ok().as_deref()),
+            "language" => {
+                options.language = field.text().await.ok().filter(|l| !l.trim().is_empty())
+            }
+            "prompt" => {
+                if let Ok(text) = field.text().await {
+                    options.hotwords = text
+                        .split(',')
+                        .map(|word| word.trim().to_string())
+                        .filter(|word| !word.is_empty())
+                        .collect();
+                }
+            }
+            "temperature" => {
+                options.decoding.temperature =
+                    field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "response_format" => {
+                let text = field.text().await.unwrap_or_default();
+                match ResponseFormat::parse(&text) {
+                    Some(f) => format = f,
+                    None => {
+                        return bad_request(
+                            "response_format must be json, text, srt, vtt or verbose_json",
+                        );
+                    }
+                }
+            }
+            _ => {
+                let _ = field.bytes().await;
+            }
+        }
+    }
+    let Some(path) = path else {
+        return bad_request("missing 'file' field");
+    };
+    options.url = path.to_string_lossy().to_string();
+
+    let job = queue
+        .job(options.model.unwrap_or_default(), &options.url)
+        .await;
+    let engine = match queue.acquire_quietly(job).await {
+        Ok(slot) => slot,
+        Err(e) => return error(StatusCode::SERVICE_UNAVAILABLE, &e.message),
+    };
+    let language = options.language.clone();
+    let result = match engine.transcribe(options).await {
+        Ok(result) => result,
+        Err(e) => {
+            error!("OpenAI-compatible transcription failed: {:#}", e);
+            return server_error(&format!("{:#}", e));
+        }
+    };
+    drop(engine);
+    drop(dir);
+    info!(
+        "Transcribed {} for /v1/audio/transcriptions",
+        result.metadata.title
+    );
+    respond(&result, language.as_deref(), format)
+}
+
+fn respond(
+    result: &TranscriptionResult,
+    language: Option<&str>,
+    format: ResponseFormat,
+) -> Response {
+    let text = |body: String, content_type: &'static str| {
+        (
+            [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+            body,
+        )
+            .into_response()
+    };
+    match format {
+        ResponseFormat::Json => Json(json!({ "text": result.transcript })).into_response(),
+        ResponseFormat::Text => text(result.transcript.clone(), "text/plain; charset=utf-8"),
+        ResponseFormat::Srt => text(
+            subtitles::srt(&result.segments),
+            "application/x-subrip; charset=utf-8",
+        ),
+        ResponseFormat::Vtt => text(subtitles::vtt(&result.segments), "text/vtt; charset=utf-8"),
+        ResponseFormat::VerboseJson => {
+            let language = result
+                .detected_language
+                .as_ref()
+                .map(|d| d.code.as_str())
+                .or(language.filter(|l| !l.eq_ignore_ascii_case("auto")));
+            let segments: Vec<_> = result
+                .segments
+                .iter()
+                .enumerate()
+                .map(|(id, s)| {
+                    json!({
+                        "id": id,
+                        "start": s.start_ms as f64 / 1000.0,
+                        "end": s.end_ms as f64 / 1000.0,
+                        "text": s.text,
+                    })
+                })
+                .collect();
+            let duration = result
+                .segments
+                .last()
+                .map_or(result.metadata.duration as f64, |s| {
+                    s.end_ms as f64 / 1000.0
+                });
+            Json(json!({
+                "task": "transcribe",
+                "language": language,
+                "duration": duration,
+                "text": result.transcript,
+                "segments": segments,
+            }))
+            .into_response()
+        }
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    let kind = if status.is_server_error() {
+        "server_error"
+    } else {
+        "invalid_request_error"
+    };
+    (
+        status,
+        Json(json!({ "error": { "message": message, "type": kind } })),
+    )
+        .into_response()
+}
+
+fn bad_request(message: &str) -> Response {
+    error(StatusCode::BAD_REQUEST, message)
+}
+
+fn server_error(message: &str) -> Response {
+    error(StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_response_formats() {
+        assert_eq!(ResponseFormat::parse("srt"), Some(ResponseFormat::Srt));
+        assert_eq!(
+            ResponseFormat::parse("verbose_json"),
+            Some(ResponseFormat::VerboseJson)
+        );
+        assert_eq!(ResponseFormat::parse("xml"), None);
+    }
+}
@@ -84,7 +84,10 @@ pub async fn create_checkout(
     let price_id = match std::env::var(&price_env) {
         Ok(s) if s.starts_with("price_") => s,
         _ => {
-            error!("checkout requested pack {} but {} is unset/invalid", req.pack, price_env);
+            error!(
+                "checkout requested pack {} but {} is unset/invalid",
+                req.pack, price_env
+            );
             return (
                 StatusCode::SERVICE_UNAVAILABLE,
                 Json(json!({ "error": format!("pack {} not configured", req.pack) })),
@@ -193,7 +196,10 @@ pub async fn webhook(
         }
     };
 
-    let sig_header = match headers.get("stripe-signature").and_then(|v| v.to_str().ok()) {
+    let sig_header = match headers
+        .get("stripe-signature")
+        .and_then(|v| v.to_str().ok())
+    {
         Some(s) => s,
         None => {
             return (
@@ -216,7 +222,10 @@ pub async fn webhook(
         Ok(v) => v,
         Err(e) => {
             error!("Stripe webhook body wasn't JSON: {}", e);
-            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "bad json" })));
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "bad json" })),
+            );
         }
     };
 
@@ -239,7 +248,11 @@ pub async fn webhook(
     let identity = metadata
         .and_then(|m| m.get("identity"))
         .and_then(|v| v.as_str())
-        .or_else(|| metadata.and_then(|m| m.get("device_id")).and_then(|v| v.as_str()))
+        .or_else(|| {
+            metadata
+                .and_then(|m| m.get("device_id"))
+                .and_then(|v| v.as_str())
+        })
         .map(str::to_string)
         .or_else(|| {
             session
@@ -252,29 +265,33 @@ pub async fn webhook(
         .and_then(|m| m.get("credits"))
         .and_then(|v| v.as_str());
 
-    let (identity, credit_amount) = match (identity, credits_str.and_then(|s| s.parse::<i32>().ok())) {
-        (Some(d), Some(c)) if c > 0 => (d, c),
-        _ => {
-            error!(
-                "checkout.session.completed missing identity / credits in metadata: {}",
-                event
-            );
-            // Acknowledge so Stripe stops retrying — we can't recover this one
-            // automatically, but a 200 prevents the event from clogging the
-            // retry queue.
-            return (
-                StatusCode::OK,
-                Json(json!({ "warning": "session lacked metadata" })),
-            );
-        }
-    };
+    let (identity, credit_amount) =
+        match (identity, credits_str.and_then(|s| s.parse::<i32>().ok())) {
+            (Some(d), Some(c)) if c > 0 => (d, c),
+            _ => {
+                error!(
+                    "checkout.session.completed missing identity / credits in metadata: {}",
+                    event
+                );
+                // Acknowledge so Stripe stops retrying — we can't recover this one
+                // automatically, but a 200 prevents the event from clogging the
+                // retry queue.
+                return (
+                    StatusCode::OK,
+                    Json(json!({ "warning": "session lacked metadata" })),
+                );
+            }
+        };
 
     let new_balance = credits::add(&state.credits, &identity, credit_amount).await;
     info!(
         "Stripe webhook credited {} with {} credits (new balance: {})",
         identity, credit_amount, new_balance
     );
-    (StatusCode::OK, Json(json!({ "ok": true, "balance": new_balance })))
+    (
+        StatusCode::OK,
+        Json(json!({ "ok": true, "balance": new_balance })),
+    )
 }
 
 /// Verify a Stripe-Signature header against the raw request body and a
@@ -312,7 +329,9 @@ fn verify_signature(secret: &[u8], body: &[u8], header: &str) -> bool {
         if k != "v1" {
             continue;
         }
-        let Ok(provided) = decode_hex(v) else { continue };
+        let Ok(provided) = decode_hex(v) else {
+            continue;
+        };
         if provided.len() == expected.len() && constant_time_eq(&provided, &expected) {
             return true;
         }
@@ -0,0 +1,362 @@
+//! `/api/transcriptions`: transcription jobs for scripts, CI and other
+//! clients that don't speak MCP.
+//!
+//! `POST /api/transcriptions` takes the body of `POST /api/jobs` and starts
+//! a job on the shared queue, without the credits, identity headers and
+//! LLM summary of the web app's job endpoints. `GET
+//! /api/transcriptions/{id}` reports its status and, once complete, the
+//! transcript, segments and metadata. `GET
+//! /api/transcriptions/{id}/files/{format}` downloads one of the files it
+//! wrote (`txt`, `json`, `md`, `srt` or `vtt`; subtitles are rendered from
+//! the segments when they weren't written).
+//!
+//! The routes take the same API keys as `/mcp`: starting a job needs
+//! `transcribe_video` in a key's tool allowlist, and reading one needs
+//! `read_transcript`. A job is only visible to the key that started it.
+//! Finished jobs are forgotten after a day; their files stay.
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::AppState;
+use super::files::{attachment, content_type};
+use super::handlers::check_request;
+use super::jobs::JobRequest;
+use crate::mcp::policy;
+use crate::mcp::queue::JobQueue;
+use crate::transcriber::subtitles;
+use crate::transcriber::types::{DetectedLanguage, Segment, TranscriptionResult, VideoMetadata};
+use crate::utils::encryption;
+
+pub type TranscriptionStore = Arc<Mutex<HashMap<Uuid, Transcription>>>;
+
+pub fn new_store() -> TranscriptionStore {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Finished jobs are dropped this long after they finish.
+pub const FINISHED_TTL_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionStatus {
+    Queued,
+    Running,
+    Complete,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcription {
+    pub id: Uuid,
+    pub status: TranscriptionStatus,
+    pub url: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<TranscriptionOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Name of the API key that started the job; `None` when no keys are
+    /// configured.
+    #[serde(skip)]
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptionOutput {
+    pub transcript: String,
+    pub segments: Vec<Segment>,
+    pub metadata: VideoMetadata,
+    pub model_used: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detected_language: Option<DetectedLanguage>,
+    /// Formats that can be downloaded from `files/{format}`.
+    pub formats: Vec<&'static str>,
+    /// Where each format was written.
+    #[serde(skip)]
+    pub files: BTreeMap<&'static str, String>,
+    /// URLs of the copies the output sink uploaded, by format.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub uploaded: BTreeMap<&'static str, String>,
+}
+
+impl TranscriptionOutput {
+    fn new(result: TranscriptionResult) -> Self {
+        let model_used = result.model_label();
+        let files: BTreeMap<_, _> = result
+            .files
+            .0
+            .into_iter()
+            .map(|(format, path)| (format.as_str(), path))
+            .collect();
+        let mut formats: Vec<_> = files.keys().copied().collect();
+        for subtitle in ["srt", "vtt"] {
+            if !files.contains_key(subtitle) {
+                formats.push(subtitle);
+            }
+        }
+        Self {
+            model_used,
+            transcript: result.transcript,
+            segments: result.segments,
+            metadata: result.metadata,
+            detected_language: result.detected_language,
+            formats,
+            files,
+            uploaded: result
+                .uploaded
+                .0
+                .into_iter()
+                .map(|(format, url)| (format.as_str(), url))
+                .collect(),
+        }
+    }
+}
+
+/// Drop jobs that finished more than [`FINISHED_TTL_SECS`] ago.
+pub fn expire(store: &mut HashMap<Uuid, Transcription>, now: i64) {
+    store.retain(|_, t| {
+        matches!(
+            t.status,
+            TranscriptionStatus::Queued | TranscriptionStatus::Running
+        ) || now - t.updated_at < FINISHED_TTL_SECS
+    });
+}
+
+/// The key a request was made with, if it may use `tool`. `Ok(None)`
+/// means no keys are configured.
+fn authorize(headers: &HeaderMap, tool: &str) -> Result<Option<String>, Failure> {
+    match policy::headers_key(headers) {
+        Err(msg) => Err(failure(StatusCode::UNAUTHORIZED, msg)),
+        Ok(Some(key)) if !key.allows(tool) => Err(failure(
+            StatusCode::FORBIDDEN,
+            &format!("this key may not use {}", tool),
+        )),
+        Ok(key) => Ok(key.map(|k| k.name)),
+    }
+}
+
+/// POST /api/transcriptions — start a job; answers 202 with its id.
+pub async fn create(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<JobRequest>,
+) -> Response {
+    let owner = match authorize(&headers, "transcribe_video") {
+        Ok(owner) => owner,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = check_request(&req) {
+        return error(StatusCode::BAD_REQUEST, &e);
+    }
+
+    let id = Uuid::new_v4();
+    let now = now_unix();
+    let transcription = Transcription {
+        id,
+        status: TranscriptionStatus::Queued,
+        url: req.url.clone(),
+        created_at: now,
+        updated_at: now,
+        result: None,
+        error: None,
+        owner,
+    };
+    {
+        let mut store = state.transcriptions.lock().await;
+        expire(&mut store, now);
+        store.insert(id, transcription.clone());
+    }
+    info!("Created transcription {} for {}", id, req.url);
+    tokio::spawn(run(
+        id,
+        req,
+        state.queue.clone(),
+        state.transcriptions.clone(),
+    ));
+
+    (StatusCode::ACCEPTED, Json(transcription)).into_response()
+}
+
+async fn run(id: Uuid, req: JobRequest, queue: Arc<JobQueue>, store: TranscriptionStore) {
+    let options = req.options();
+    let job = queue
+        .job(options.model.unwrap_or_default(), &options.url)
+        .await;
+    let outcome = match queue.acquire_quietly(job).await {
+        Ok(engine) => {
+            set_status(&store, id, TranscriptionStatus::Running).await;
+            engine
+                .transcribe(options)
+                .await
+                .map_err(|e| format!("{:#}", e))
+        }
+        Err(e) => Err(e.message.to_string()),
+    };
+
+    let mut store = store.lock().await;
+    let Some(transcription) = store.get_mut(&id) else {
+        return;
+    };
+    transcription.updated_at = now_unix();
+    match outcome {
+        Ok(result) => {
+            transcription.status = TranscriptionStatus::Complete;
+            transcription.result = Some(TranscriptionOutput::new(result));
+            info!("Transcription {} complete", id);
+        }
+        Err(e) => {
+            error!("Transcription {} failed: {}", id, e);
+            transcription.status = TranscriptionStatus::Failed;
+            transcription.error = Some(e);
+        }
+    }
+}
+
+async fn set_status(store: &TranscriptionStore, id: Uuid, status: TranscriptionStatus) {
+    if let Some(transcription) = store.lock().await.get_mut(&id) {
+        transcription.status = status;
+        transcription.updated_at = now_unix();
+    }
+}
+
+/// The job `id` if the request's key may see it.
+async fn find(state: &AppState, headers: &HeaderMap, id: Uuid) -> Result<Transcription, Failure> {
+    let owner = authorize(headers, "read_transcript")?;
+    let mut store = state.transcriptions.lock().await;
+    expire(&mut store, now_unix());
+    store
+        .get(&id)
+        .filter(|t| t.owner == owner)
+        .cloned()
+        .ok_or_else(|| failure(StatusCode::NOT_FOUND, "no such transcription"))
+}
+
+/// GET /api/transcriptions/{id} — status, and the transcript once complete.
+pub async fn get(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Response {
+    match find(&state, &headers, id).await {
+        Ok(transcription) => Json(transcription).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /api/transcriptions/{id}/files/{format} — one of the job's files.
+pub async fn get_file(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((id, format)): Path<(Uuid, String)>,
+) -> Response {
+    let transcription = match find(&state, &headers, id).await {
+        Ok(transcription) => transcription,
+        Err(e) => return e.into_response(),
+    };
+    if content_type(&format).is_none() {
+        return error(
+            StatusCode::BAD_REQUEST,
+            "format must be txt, json, md, srt or vtt",
+        );
+    }
+    let Some(output) = transcription.result else {
+        return error(StatusCode::CONFLICT, "the transcription isn't complete");
+    };
+
+    let body = match (output.files.get(format.as_str()), format.as_str()) {
+        (Some(path), _) => match encryption::read(std::path::Path::new(path)) {
+            Ok(bytes) => bytes,
+            Err(_) => return error(StatusCode::NOT_FOUND, "file not found"),
+        },
+        (None, "srt") => subtitles::srt(&output.segments).into_bytes(),
+        (None, "vtt") => subtitles::vtt(&output.segments).into_bytes(),
+        (None, _) => return error(StatusCode::NOT_FOUND, "this format wasn't written"),
+    };
+    attachment(&output.metadata.video_id, &format, body)
+}
+
+/// An error answer, as the other REST handlers give.
+type Failure = (StatusCode, Json<Value>);
+
+fn failure(status: StatusCode, msg: &str) -> Failure {
+    (status, Json(json!({ "error": msg })))
+}
+
+fn error(status: StatusCode, msg: &str) -> Response {
+    failure(status, msg).into_response()
+}
+
+fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcription(status: TranscriptionStatus, updated_at: i64) -> Transcription {
+        Transcription {
+            id: Uuid::new_v4(),
+            status,
+            url: "https://example.com/talk".to_string(),
+            created_at: 0,
+            updated_at,
+            result: None,
+            error: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn forgets_finished_jobs_after_a_day() {
+        let now = FINISHED_TTL_SECS * 2;
+        let mut store = HashMap::new();
+        for t in [
+            transcription(TranscriptionStatus::Complete, 0),
+            transcription(TranscriptionStatus::Failed, now - 60),
+            transcription(TranscriptionStatus::Running, 0),
+        ] {
+            store.insert(t.id, t);
+        }
+        expire(&mut store, now);
+        let mut left: Vec<_> = store.values().map(|t| t.status).collect();
+        left.sort_by_key(|s| *s as u8);
+        assert_eq!(
+            left,
+            vec![TranscriptionStatus::Running, TranscriptionStatus::Failed]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_audio_tracks() {
+        let request = |track: &str| -> JobRequest {
+            serde_json::from_value(serde_json::json!({
+                "url": "https://example.com/talk",
+                "audio_track": track,
+            }))
+            .unwrap()
+        };
+        assert!(check_request(&request("2")).is_ok());
+        assert!(check_request(&request("stereo")).is_ok());
+        let err = check_request(&request("0")).unwrap_err();
+        assert!(err.contains("Invalid audio track: 0"), "{}", err);
+        assert!(check_request(&request("second")).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Resumable chunked uploads for large local recordings.
+//!
+//! A multi-GB upload over a flaky connection rarely survives as one
+//! multipart request. Instead the client opens a session with the file's
+//! name and size, sends the bytes as a series of `PATCH` chunks each
+//! carrying its `Upload-Offset`, and after a dropped connection asks where
+//! the server got to and carries on from there. Once every byte is in, the
+//! assembled file becomes an ordinary upload job.
+//!
+//! Sessions live in memory next to the jobs; their bytes go to a
+//! `transcriber-upload-*` temp dir, which the startup sweep cleans up if the
+//! process dies mid-upload.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use uuid::Uuid;
+
+pub type UploadStore = Arc<Mutex<HashMap<Uuid, UploadSession>>>;
+
+pub fn new_store() -> UploadStore {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Largest file a session may announce.
+pub const MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024 * 1024;
+
+/// Largest single chunk. A chunk that fails part-way is resent whole, so
+/// this also bounds what a dropped connection costs.
+pub const MAX_CHUNK_BYTES: usize = 64 * 1024 * 1024;
+
+/// Sessions with no chunk for this long are dropped, with their bytes.
+pub const SESSION_TTL_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug)]
+pub struct UploadSession {
+    pub id: Uuid,
+    /// Identity that opened the session; only it may add to or finish it.
+    pub device_id: String,
+    pub filename: String,
+    pub size: u64,
+    /// Bytes received so far, which is where the next chunk must start.
+    pub offset: u64,
+    /// Held while a chunk is being written, so two can't interleave. A
+    /// request dropped mid-write releases it with the guard.
+    pub writing: Arc<Mutex<()>>,
+    pub updated_at: i64,
+    pub dir: TempDir,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadStatus {
+    pub upload_id: Uuid,
+    pub filename: String,
+    pub size: u64,
+    pub offset: u64,
+    pub max_chunk_bytes: usize,
+}
+
+impl UploadSession {
+    pub fn status(&self) -> UploadStatus {
+        UploadStatus {
+            upload_id: self.id,
+            filename: self.filename.clone(),
+            size: self.size,
+            offset: self.offset,
+            max_chunk_bytes: MAX_CHUNK_BYTES,
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.offset == self.size
+    }
+
+    /// Whether a chunk is being written.
+    pub fn is_busy(&self) -> bool {
+        self.writing.try_lock().is_err()
+    }
+
+    /// Start appending `len` bytes at `offset`, if they may be appended
+    /// now. Other chunks are refused until the guard is dropped.
+    pub fn begin_chunk(&self, offset: u64, len: usize) -> Result<OwnedMutexGuard<()>, ChunkError> {
+        let guard = self
+            .writing
+            .clone()
+            .try_lock_owned()
+            .map_err(|_| ChunkError::Busy)?;
+        self.check_chunk(offset, len)?;
+        Ok(guard)
+    }
+
+    /// Whether `len` bytes at `offset` fit where the upload left off.
+    fn check_chunk(&self, offset: u64, len: usize) -> Result<(), ChunkError> {
+        if offset != self.offset {
+            return Err(ChunkError::WrongOffset);
+        }
+        if len > MAX_CHUNK_BYTES {
+            return Err(ChunkError::TooLarge);
+        }
+        if offset + len as u64 > self.size {
+            return Err(ChunkError::PastEnd);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChunkError {
+    Busy,
+    WrongOffset,
+    TooLarge,
+    PastEnd,
+}
+
+impl std::fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ChunkError::Busy => "another chunk for this upload is still being written",
+            ChunkError::WrongOffset => "Upload-Offset doesn't match the bytes received so far",
+            ChunkError::TooLarge => "chunk is larger than max_chunk_bytes",
+            ChunkError::PastEnd => "chunk runs past the announced size",
+        })
+    }
+}
+
+/// Drop sessions idle longer than [`SESSION_TTL_SECS`]; their temp dirs go
+/// with them.
+pub fn expire(sessions: &mut HashMap<Uuid, UploadSession>, now: i64) {
+    sessions.retain(|_, s| s.is_busy() || now - s.updated_at < SESSION_TTL_SECS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(size: u64, offset: u64, updated_at: i64) -> UploadSession {
+        let dir = tempfile::tempdir().unwrap();
+        UploadSession {
+            id: Uuid::new_v4(),
+            device_id: "device".to_string(),
+            filename: "talk.mkv".to_string(),
+            size,
+            offset,
+            writing: Arc::default(),
+            updated_at,
+            path: dir.path().join("talk.mkv"),
+            dir,
+        }
+    }
+
+    #[test]
+    fn chunks_must_continue_where_the_upload_left_off() {
+        let s = session(100, 40, 0);
+        assert_eq!(s.check_chunk(40, 60), Ok(()));
+        assert_eq!(s.check_chunk(0, 10), Err(ChunkError::WrongOffset));
+        assert_eq!(s.check_chunk(40, 61), Err(ChunkError::PastEnd));
+        let writing = s.begin_chunk(40, 10).unwrap();
+        assert!(s.is_busy());
+        assert_eq!(s.begin_chunk(40, 10).err(), Some(ChunkError::Busy));
+        // Dropped, as when the request is cancelled mid-write.
+        drop(writing);
+        assert!(!s.is_busy());
+        assert!(s.begin_chunk(40, 10).is_ok());
+
+        let mut sessions = HashMap::new();
+        let stale = session(10, 0, 0);
+        let fresh = session(10, 0, SESSION_TTL_SECS);
+        let (stale_id, stale_dir) = (stale.id, stale.dir.path().to_path_buf());
+        sessions.insert(stale.id, stale);
+        sessions.insert(fresh.id, fresh);
+        expire(&mut sessions, SESSION_TTL_SECS + 1);
+        assert_eq!(sessions.len(), 1);
+        assert!(!sessions.contains_key(&stale_id));
+        assert!(!stale_dir.exists());
+    }
+}
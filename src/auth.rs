@@ -112,6 +112,7 @@ impl JwksCache {
     }
 
     /// Return the current cached JWKS, refreshing if stale or absent.
+    #[allow(clippy::collapsible_if)]
     async fn current(&self) -> Result<JwkSet> {
         {
             let state = self.state.read().await;
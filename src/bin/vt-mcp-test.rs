@@ -0,0 +1,697 @@
+//! `vt-mcp-test`: end-to-end check of a built server.
+//!
+//! Starts `video-transcriber-mcp` over stdio or Streamable HTTP, drives the
+//! tool surface against a generated audio fixture and asserts on what comes
+//! back and what lands on disk. URL sources are served by a local mock
+//! media server (a direct `.wav` link and a one-episode RSS feed), so
+//! nothing is fetched from the internet and yt-dlp isn't needed. ffmpeg and
+//! the chosen Whisper model must be installed, as for a real run.
+//!
+//! Built only with the `test-harness` feature:
+//!
+//! ```bash
+//! cargo build --release --features test-harness
+//! target/release/vt-mcp-test --transport http --model tiny
+//! ```
+//!
+//! Exits non-zero if any check fails.
+
+use anyhow::{Context, Result, bail};
+use axum::{Router, http::header, routing::get};
+use clap::{Parser, ValueEnum};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Longest any single request may take; transcriptions of the fixture take
+/// seconds, but the first load of a model can be slow.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Transport {
+    Stdio,
+    Http,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "vt-mcp-test",
+    about = "End-to-end test of a video-transcriber-mcp build"
+)]
+struct Args {
+    #[arg(long, value_enum, default_value = "stdio")]
+    transport: Transport,
+
+    /// Server binary. Default: `video-transcriber-mcp` next to this one.
+    #[arg(long)]
+    server: Option<PathBuf>,
+
+    /// Whisper model used for the transcriptions; must be downloaded.
+    #[arg(long, default_value = "tiny")]
+    model: String,
+
+    /// Pass the server's log output through.
+    #[arg(long)]
+    verbose: bool,
+
+    /// Keep the temporary input and output directories for inspection.
+    #[arg(long)]
+    keep: bool,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let server = match &args.server {
+        Some(path) => path.clone(),
+        None => std::env::current_exe()?.with_file_name(format!(
+            "video-transcriber-mcp{}",
+            std::env::consts::EXE_SUFFIX
+        )),
+    };
+    if !server.exists() {
+        bail!(
+            "Server binary not found at {} (build it, or pass --server)",
+            server.display()
+        );
+    }
+
+    let work = tempfile::Builder::new().prefix("vt-mcp-test-").tempdir()?;
+    let input_dir = work.path().join("input");
+    let output_dir = work.path().join("output");
+    std::fs::create_dir_all(&input_dir)?;
+    std::fs::create_dir_all(&output_dir)?;
+    let fixture = input_dir.join("fixture.wav");
+    std::fs::write(&fixture, fixture_wav())?;
+
+    let media = serve_mock_media().await?;
+    let mut client = Client::start(args.transport, &server, args.verbose).await?;
+    let mut run = Run {
+        client: &mut client,
+        failures: Vec::new(),
+        passed: 0,
+    };
+    let env = Env {
+        model: args.model.clone(),
+        fixture,
+        output_dir: output_dir.clone(),
+        media,
+    };
+    run_checks(&mut run, &env).await;
+
+    let failures = std::mem::take(&mut run.failures);
+    let passed = run.passed;
+    client.shutdown().await;
+
+    if args.keep {
+        println!("Kept {}", work.keep().display());
+    } else {
+        drop(work);
+    }
+    println!("\n{} passed, {} failed", passed, failures.len());
+    if !failures.is_empty() {
+        for (name, err) in &failures {
+            println!("  ✗ {}: {:#}", name, err);
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+struct Env {
+    model: String,
+    fixture: PathBuf,
+    output_dir: PathBuf,
+    /// Base URL of the mock media server.
+    media: String,
+}
+
+struct Run<'a> {
+    client: &'a mut Client,
+    failures: Vec<(String, anyhow::Error)>,
+    passed: usize,
+}
+
+impl Run<'_> {
+    fn record(&mut self, name: &str, outcome: Result<()>) {
+        match outcome {
+            Ok(()) => {
+                println!("  ✓ {}", name);
+                self.passed += 1;
+            }
+            Err(e) => {
+                println!("  ✗ {}", name);
+                self.failures.push((name.to_string(), e));
+            }
+        }
+    }
+
+    /// Call `tool`, failing on a protocol error or an `isError` result.
+    /// Returns the text content and the structured content, if any.
+    async fn call(&mut self, tool: &str, arguments: Value) -> Result<(String, Option<Value>)> {
+        let result = self
+            .client
+            .request(
+                "tools/call",
+                json!({ "name": tool, "arguments": arguments }),
+            )
+            .await?;
+        let text = result["content"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|c| c["text"].as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .unwrap_or_default();
+        if result["isError"].as_bool() == Some(true) {
+            bail!("{} returned an error: {}", tool, text);
+        }
+        Ok((text, result.get("structuredContent").cloned()))
+    }
+}
+
+async fn run_checks(run: &mut Run<'_>, env: &Env) {
+    let out = env.output_dir.to_string_lossy().to_string();
+
+    let outcome = run
+        .client
+        .request(
+            "initialize",
+            json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": { "name": "vt-mcp-test", "version": env!("CARGO_PKG_VERSION") }
+            }),
+        )
+        .await;
+    let initialized = outcome.is_ok();
+    run.record("initialize", outcome.map(|_| ()));
+    if !initialized {
+        return;
+    }
+    let outcome = run.client.notify("notifications/initialized").await;
+    run.record("initialized notification", outcome);
+
+    let outcome = async {
+        let tools = run.client.request("tools/list", json!({})).await?;
+        let names: Vec<&str> = tools["tools"]
+            .as_array()
+            .context("tools/list has no tools array")?
+            .iter()
+            .filter_map(|t| t["name"].as_str())
+            .collect();
+        for expected in [
+            "transcribe_video",
+            "transcribe_batch",
+            "transcribe_podcast",
+            "list_transcripts",
+            "read_transcript",
+            "redact_range",
+            "delete_transcript",
+            "get_metrics",
+        ] {
+            if !names.contains(&expected) {
+                bail!("{} is missing from tools/list", expected);
+            }
+        }
+        Ok(())
+    }
+    .await;
+    run.record("tools/list", outcome);
+
+    let outcome = run.call("check_dependencies", json!({})).await.map(|_| ());
+    run.record("check_dependencies", outcome);
+
+    let outcome = async {
+        let (_, metrics) = run.call("get_metrics", json!({})).await?;
+        let metrics = metrics.context("no structured content")?;
+        if metrics["transcriptions_started"].as_u64().is_none() {
+            bail!("transcriptions_started missing from {}", metrics);
+        }
+        Ok(())
+    }
+    .await;
+    run.record("get_metrics", outcome);
+
+    let outcome = async {
+        let (text, _) = run
+            .call(
+                "transcribe_video",
+                json!({
+                    "url": env.fixture,
+                    "output_dir": out,
+                    "model": env.model,
+                    "language": "en",
+                    "formats": ["txt", "json", "srt"],
+                }),
+            )
+            .await?;
+        expect_contains(&text, "transcribed successfully")?;
+        let base = env.output_dir.join("fixture-fixture");
+        for ext in ["txt", "json", "srt"] {
+            expect_file(&base.with_extension(ext))?;
+        }
+        if base.with_extension("md").exists() {
+            bail!("md was written although formats left it out");
+        }
+        let json: Value =
+            serde_json::from_str(&std::fs::read_to_string(base.with_extension("json"))?)?;
+        if !json["segments"].is_array() {
+            bail!("JSON output has no segments array");
+        }
+        Ok(())
+    }
+    .await;
+    run.record("transcribe_video (local file, chosen formats)", outcome);
+
+    let outcome = async {
+        let (text, _) = run
+            .call(
+                "transcribe_video",
+                json!({
+                    "url": format!("{}/remote.wav", env.media),
+                    "output_dir": out,
+                    "model": env.model,
+                    "language": "en",
+                }),
+            )
+            .await?;
+        expect_contains(&text, "transcribed successfully")?;
+        for ext in ["txt", "json", "md"] {
+            expect_file(&env.output_dir.join("remote-remote").with_extension(ext))?;
+        }
+        Ok(())
+    }
+    .await;
+    run.record("transcribe_video (direct link)", outcome);
+
+    let outcome = async {
+        let (text, _) = run
+            .call(
+                "transcribe_podcast",
+                json!({
+                    "feed_url": format!("{}/feed.xml", env.media),
+                    "output_dir": out,
+                    "model": env.model,
+                    "language": "en",
+                }),
+            )
+            .await?;
+        expect_contains(&text, "✅ Fixture episode")
+    }
+    .await;
+    run.record("transcribe_podcast", outcome);
+
+    let outcome = async {
+        let (text, _) = run
+            .call(
+                "transcribe_batch",
+                json!({
+                    "urls": [format!("{}/batch.wav", env.media)],
+                    "output_dir": out,
+                    "model": env.model,
+                    "language": "en",
+                }),
+            )
+            .await?;
+        expect_contains(&text, "✅")?;
+        expect_file(&env.output_dir.join("batch-batch.txt"))
+    }
+    .await;
+    run.record("transcribe_batch", outcome);
+
+    let outcome = async {
+        let (text, _) = run
+            .call("list_transcripts", json!({ "output_dir": out }))
+            .await?;
+        for id in ["fixture", "remote", "batch"] {
+            expect_contains(&text, id)?;
+        }
+        Ok(())
+    }
+    .await;
+    run.record("list_transcripts", outcome);
+
+    let outcome = run
+        .call("get_latest_transcript", json!({ "output_dir": out }))
+        .await
+        .map(|_| ());
+    run.record("get_latest_transcript", outcome);
+
+    let outcome = async {
+        let (text, _) = run
+            .call(
+                "read_transcript",
+                json!({ "path": "fixture-fixture.txt", "output_dir": out }),
+            )
+            .await?;
+        let stored = std::fs::read_to_string(env.output_dir.join("fixture-fixture.txt"))?;
+        expect_contains(&text, stored.trim())
+    }
+    .await;
+    run.record("read_transcript", outcome);
+
+    let outcome = run
+        .call(
+            "redact_range",
+            json!({
+                "path": "fixture-fixture.json",
+                "start_time": 0,
+                "end_time": 1,
+                "output_dir": out,
+            }),
+        )
+        .await
+        .map(|_| ());
+    run.record("redact_range", outcome);
+
+    let outcome = async {
+        let (_, metrics) = run.call("get_metrics", json!({})).await?;
+        let completed = metrics
+            .and_then(|m| m["transcriptions_completed"].as_u64())
+            .unwrap_or(0);
+        if completed < 4 {
+            bail!(
+                "expected at least 4 completed transcriptions, got {}",
+                completed
+            );
+        }
+        Ok(())
+    }
+    .await;
+    run.record("get_metrics after transcribing", outcome);
+
+    let outcome = async {
+        run.call(
+            "delete_transcript",
+            json!({ "video_id": "fixture", "output_dir": out }),
+        )
+        .await?;
+        if env.output_dir.join("fixture-fixture.txt").exists() {
+            bail!("fixture transcript still on disk");
+        }
+        Ok(())
+    }
+    .await;
+    run.record("delete_transcript", outcome);
+
+    let outcome = async {
+        run.call(
+            "delete_all_transcripts",
+            json!({ "confirm": true, "output_dir": out }),
+        )
+        .await?;
+        if env.output_dir.join("remote-remote.txt").exists() {
+            bail!("transcripts still on disk");
+        }
+        Ok(())
+    }
+    .await;
+    run.record("delete_all_transcripts", outcome);
+}
+
+fn expect_contains(text: &str, needle: &str) -> Result<()> {
+    if !text.contains(needle) {
+        bail!("expected {:?} in:\n{}", needle, text);
+    }
+    Ok(())
+}
+
+fn expect_file(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        bail!("{} was not written", path.display());
+    }
+    Ok(())
+}
+
+/// Two seconds of a quiet 440 Hz tone: 16 kHz mono 16-bit PCM WAV, the
+/// format Whisper works on, so ffmpeg's conversion is trivial.
+fn fixture_wav() -> Vec<u8> {
+    const RATE: u32 = 16_000;
+    let samples: Vec<i16> = (0..RATE * 2)
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            ((t * 440.0 * std::f32::consts::TAU).sin() * 3000.0) as i16
+        })
+        .collect();
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&RATE.to_le_bytes());
+    wav.extend_from_slice(&(RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for s in samples {
+        wav.extend_from_slice(&s.to_le_bytes());
+    }
+    wav
+}
+
+/// Serve the fixture as direct media links plus an RSS feed pointing at
+/// it, standing in for remote hosts. Returns the base URL.
+async fn serve_mock_media() -> Result<String> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let base = format!("http://{}", listener.local_addr()?);
+    let feed = format!(
+        r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Fixture Show</title>
+<item><title>Fixture episode</title><guid>fixture-1</guid>
+<pubDate>Mon, 01 Jan 2024 00:00:00 GMT</pubDate>
+<enclosure url="{}/episode.wav" type="audio/wav" length="64044"/></item>
+</channel></rss>"#,
+        base
+    );
+    let wav = || async { ([(header::CONTENT_TYPE, "audio/wav")], fixture_wav()) };
+    let app = Router::new()
+        .route("/remote.wav", get(wav))
+        .route("/batch.wav", get(wav))
+        .route("/episode.wav", get(wav))
+        .route(
+            "/feed.xml",
+            get(move || async move { ([(header::CONTENT_TYPE, "application/rss+xml")], feed) }),
+        );
+    tokio::spawn(async move {
+        let _ = axum::serve(listener, app).await;
+    });
+    Ok(base)
+}
+
+/// A minimal MCP client: JSON-RPC over the server's stdio, or over
+/// Streamable HTTP with JSON or SSE responses.
+enum Client {
+    Stdio {
+        child: Child,
+        stdin: ChildStdin,
+        stdout: Lines<BufReader<ChildStdout>>,
+        next_id: u64,
+    },
+    Http {
+        child: Child,
+        http: reqwest::Client,
+        url: String,
+        session: Option<String>,
+        next_id: u64,
+    },
+}
+
+impl Client {
+    async fn start(transport: Transport, server: &Path, verbose: bool) -> Result<Self> {
+        let mut command = Command::new(server);
+        command
+            .stderr(if verbose {
+                Stdio::inherit()
+            } else {
+                Stdio::null()
+            })
+            .kill_on_drop(true);
+        match transport {
+            Transport::Stdio => {
+                let mut child = command
+                    .args(["--transport", "stdio"])
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .spawn()
+                    .with_context(|| format!("Failed to start {}", server.display()))?;
+                let stdin = child.stdin.take().context("no stdin")?;
+                let stdout = BufReader::new(child.stdout.take().context("no stdout")?).lines();
+                Ok(Client::Stdio {
+                    child,
+                    stdin,
+                    stdout,
+                    next_id: 1,
+                })
+            }
+            Transport::Http => {
+                let port = {
+                    let probe = std::net::TcpListener::bind("127.0.0.1:0")?;
+                    probe.local_addr()?.port()
+                };
+                let child = command
+                    .args(["--transport", "http", "--host", "127.0.0.1"])
+                    .args(["--port", &port.to_string()])
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .with_context(|| format!("Failed to start {}", server.display()))?;
+                let addr = format!("127.0.0.1:{}", port);
+                let mut ready = false;
+                for _ in 0..150 {
+                    if tokio::net::TcpStream::connect(&addr).await.is_ok() {
+                        ready = true;
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+                if !ready {
+                    bail!("HTTP server didn't start listening on {}", addr);
+                }
+                Ok(Client::Http {
+                    child,
+                    http: reqwest::Client::new(),
+                    url: format!("http://{}/mcp", addr),
+                    session: None,
+                    next_id: 1,
+                })
+            }
+        }
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        tokio::time::timeout(REQUEST_TIMEOUT, self.exchange(method, params))
+            .await
+            .with_context(|| format!("{} timed out", method))?
+    }
+
+    async fn exchange(&mut self, method: &str, params: Value) -> Result<Value> {
+        let id = match self {
+            Client::Stdio { next_id, .. } | Client::Http { next_id, .. } => {
+                *next_id += 1;
+                *next_id
+            }
+        };
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let response = match self {
+            Client::Stdio { stdin, stdout, .. } => {
+                stdin.write_all(format!("{}\n", message).as_bytes()).await?;
+                stdin.flush().await?;
+                loop {
+                    let line = stdout.next_line().await?.context("server closed stdout")?;
+                    let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    // Skip notifications (progress, logging) on the way.
+                    if value["id"] == id && value.get("method").is_none() {
+                        break value;
+                    }
+                }
+            }
+            Client::Http {
+                http, url, session, ..
+            } => {
+                let reply = post(http, url, session.as_deref(), &message).await?;
+                if let Some(id) = reply
+                    .headers()
+                    .get("mcp-session-id")
+                    .and_then(|v| v.to_str().ok())
+                {
+                    *session = Some(id.to_string());
+                }
+                let is_sse = reply
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|t| t.starts_with("text/event-stream"));
+                let body = reply.text().await?;
+                if is_sse {
+                    body.lines()
+                        .filter_map(|l| l.strip_prefix("data:"))
+                        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+                        .find(|v| v["id"] == id && v.get("method").is_none())
+                        .context("no response in the event stream")?
+                } else {
+                    serde_json::from_str(&body).context("response isn't JSON")?
+                }
+            }
+        };
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        Ok(response["result"].clone())
+    }
+
+    async fn notify(&mut self, method: &str) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method });
+        match self {
+            Client::Stdio { stdin, .. } => {
+                stdin.write_all(format!("{}\n", message).as_bytes()).await?;
+                stdin.flush().await?;
+            }
+            Client::Http {
+                http, url, session, ..
+            } => {
+                post(http, url, session.as_deref(), &message).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn shutdown(self) {
+        let mut child = match self {
+            Client::Stdio {
+                mut child, stdin, ..
+            } => {
+                // Closing stdin ends a stdio server.
+                drop(stdin);
+                if tokio::time::timeout(Duration::from_secs(5), child.wait())
+                    .await
+                    .is_ok()
+                {
+                    return;
+                }
+                child
+            }
+            Client::Http { child, .. } => child,
+        };
+        let _ = child.kill().await;
+    }
+}
+
+async fn post(
+    http: &reqwest::Client,
+    url: &str,
+    session: Option<&str>,
+    message: &Value,
+) -> Result<reqwest::Response> {
+    let mut request = http
+        .post(url)
+        .header(header::ACCEPT, "application/json, text/event-stream")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("mcp-protocol-version", PROTOCOL_VERSION)
+        .body(message.to_string());
+    if let Some(session) = session {
+        request = request.header("mcp-session-id", session);
+    }
+    let reply = request.send().await?;
+    if !reply.status().is_success() {
+        bail!(
+            "HTTP {}: {}",
+            reply.status(),
+            reply.text().await.unwrap_or_default()
+        );
+    }
+    Ok(reply)
+}
@@ -0,0 +1,801 @@
+//! Optional user configuration file.
+//!
+//! Most knobs are environment variables, but structured settings that don't
+//! fit in an env var live in a JSON file at [`get_config_path`]
+//! (`VIDEO_TRANSCRIBER_CONFIG`, else
+//! `~/.config/video-transcriber-mcp/config.json`). The file is optional and
+//! re-read on every transcription, so edits apply without a restart.
+//!
+//! ```json
+//! {
+//!   "profiles": {
+//!     "TikTok":  { "model": "tiny", "language": "auto" },
+//!     "YouTube": { "model": "small", "chunk_minutes": 20, "hotwords": ["Kubernetes"] }
+//!   },
+//!   "platform_rules": [
+//!     { "domain": "videos.example.org", "name": "PeerTube" }
+//!   ],
+//!   "audio_profiles": {
+//!     "My Noisy Podcast": { "highpass_hz": 80, "denoise_db": 15, "gain_db": 3 }
+//!   },
+//!   "api_keys": [
+//!     { "name": "support", "key": "…", "tools": ["list_transcripts", "get_latest_transcript"] }
+//!   ],
+//!   "retention": { "max_age_days": 30, "max_total_gb": 5 },
+//!   "on_disconnect": { "action": "cancel", "grace_seconds": 60 },
+//!   "sessions": { "store": "sqlite", "ttl_minutes": 1440 },
+//!   "cors": { "allowed_origins": ["https://dash.example.com"] },
+//!   "rate_limit": { "requests_per_minute": 60, "burst": 20, "by": "api_key", "max_concurrent_requests": 64 },
+//!   "admission": { "max_large_model_hours": 6, "max_concurrent_jobs": 4 },
+//!   "workers": {
+//!     "max_concurrent_downloads": 2, "max_concurrent_transcriptions": 2, "model_memory_budget_mb": 6000,
+//!     "devices": [{ "device": "cuda:0" }, { "device": "cuda:1" }, { "device": "cpu", "jobs": 2 }]
+//!   },
+//!   "output_layout": "{platform}/{year}/{video_id}", "output_root": "/srv/transcripts",
+//!   "language_detection": { "min_confidence": 0.5, "language": "en" },
+//!   "audio_cache_format": "opus", "cookies_dir": "/srv/cookies",
+//!   "summarizer": { "model_path": "qwen2.5-3b-instruct-q4_k_m.gguf" },
+//!   "glossary": { "cube ernetes": "Kubernetes", "post gress": "Postgres" },
+//!   "embeddings": { "url": "http://localhost:11434/v1/embeddings", "model": "nomic-embed-text" },
+//!   "whisper_api": { "base_url": "https://api.openai.com/v1", "model": "whisper-1" },
+//!   "webhook": { "url": "https://n8n.example.com/webhook/transcripts", "secret": "…" },
+//!   "notifiers": [{ "type": "slack", "url": "https://hooks.slack.com/services/…" }],
+//!   "output_sink": { "type": "webdav", "url": "https://cloud.example.org/remote.php/dav/files/me/transcripts", "username": "me" }
+//! }
+//! ```
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tracing::warn;
+
+use crate::transcriber::types::{
+    AudioFormat, Device, LanguageFallback, TranscriptionOptions, WhisperModel,
+};
+use crate::utils::paths::get_config_path;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    /// Option profiles keyed by platform name as reported in
+    /// `VideoMetadata::platform` ("YouTube", "TikTok", "Local File", or the
+    /// yt-dlp extractor name for other sites). Matched case-insensitively.
+    #[serde(default)]
+    pub profiles: HashMap<String, PlatformProfile>,
+    /// Extra domain → platform name rules, checked before the built-in
+    /// table so self-hosted instances (PeerTube, Kaltura, …) get a
+    /// meaningful name and existing names can be overridden.
+    #[serde(default)]
+    pub platform_rules: Vec<PlatformRule>,
+    /// Audio preprocessing keyed by channel name (`VideoMetadata::channel`,
+    /// matched case-insensitively), for recurring sources whose recordings
+    /// share the same hum, hiss or level problems.
+    #[serde(default)]
+    pub audio_profiles: HashMap<String, AudioProfile>,
+    /// Bearer keys for the HTTP MCP endpoint. When any are configured,
+    /// `/mcp` requires one, and each key can be limited to a tool allowlist.
+    #[serde(default)]
+    pub api_keys: Vec<ApiKey>,
+    /// Automatic deletion of old transcripts and downloaded audio, enforced
+    /// by a background task while the server runs. Off when absent.
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+    /// What happens to a running tool call when its HTTP MCP session
+    /// disconnects. Defaults to letting it finish.
+    #[serde(default)]
+    pub on_disconnect: DisconnectPolicy,
+    /// Where HTTP MCP sessions are kept so a client can resume one after
+    /// its connection drops; see `mcp::sessions`. Off when absent. Read
+    /// when the server starts.
+    #[serde(default)]
+    pub sessions: Option<SessionPolicy>,
+    /// Which browser origins may call the HTTP endpoints; see
+    /// `http::cors_layer`. Any origin when absent. Read when the server
+    /// starts.
+    #[serde(default)]
+    pub cors: Option<CorsPolicy>,
+    /// Per-client and overall request limits for the HTTP endpoints; see
+    /// `rate_limit`. One request a second after a burst of 20, per IP and
+    /// on `/api` only, when absent. Read when the server starts.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitPolicy>,
+    /// Default cookies for yt-dlp, used when a request doesn't bring its
+    /// own; see `downloader::cookies_args`. Take precedence over the
+    /// `YT_DLP_COOKIES*` environment variables.
+    #[serde(default)]
+    pub cookies_file: Option<String>,
+    #[serde(default)]
+    pub cookies_from_browser: Option<String>,
+    /// Directory a request's own `cookies_file` must be in. Without it a
+    /// request can't name a cookies file; see
+    /// `downloader::request_cookies_file`.
+    #[serde(default)]
+    pub cookies_dir: Option<PathBuf>,
+    /// HTTP or SOCKS proxy for downloads, used when a request doesn't name
+    /// one; see `downloader::proxy_url`.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Limits on the work the job queue accepts. Nothing is turned away by
+    /// default.
+    #[serde(default)]
+    pub admission: AdmissionPolicy,
+    /// Limits on downloads and Whisper runs within running jobs; see
+    /// `transcriber::scheduler`. None by default.
+    #[serde(default)]
+    pub workers: WorkerLimits,
+    /// Subdirectory of the output directory each transcript goes in, as a
+    /// template over `{platform}`, `{channel}`, `{year}`, `{month}` and
+    /// `{video_id}`. The date is the upload date (`undated` when unknown).
+    /// Absent means everything goes directly in the output directory.
+    #[serde(default)]
+    pub output_layout: Option<String>,
+    /// Directory the transcript-reading tools are confined to: an
+    /// `output_dir` they are given must be this or inside it. Defaults to
+    /// the default output directory.
+    #[serde(default)]
+    pub output_root: Option<PathBuf>,
+    /// Second look and fallback language for auto-detection that comes out
+    /// unsure. Off when absent: the best guess is used.
+    #[serde(default)]
+    pub language_detection: Option<LanguageFallback>,
+    /// Codec for downloaded and extracted audio: `original` (default, the
+    /// source's stream as is), `mp3`, `opus` for small archives or `wav`
+    /// for fidelity.
+    #[serde(default)]
+    pub audio_cache_format: AudioFormat,
+    /// Local llama.cpp model for `summarize_transcript`, for clients that
+    /// can't take MCP sampling requests. Needs a build with the `llama`
+    /// feature.
+    #[serde(default)]
+    pub summarizer: Option<LocalSummarizer>,
+    /// Misrecognition → canonical spelling replacements applied to every
+    /// transcript. A request's own `glossary` entries win.
+    #[serde(default)]
+    pub glossary: BTreeMap<String, String>,
+    /// Embeddings endpoint for `semantic_search`. Off when absent.
+    #[serde(default)]
+    pub embeddings: Option<EmbeddingsConfig>,
+    /// OpenAI-compatible Whisper API to transcribe with instead of local
+    /// whisper.cpp; see `transcriber::whisper_api`. Off when absent.
+    /// `REMOTE_WHISPER_URL` takes precedence.
+    #[serde(default)]
+    pub whisper_api: Option<WhisperApiConfig>,
+    /// Signed POST when a transcription finishes or fails; see
+    /// `transcriber::webhook`. Only for requests with a `webhook_url` when
+    /// absent.
+    #[serde(default)]
+    pub webhook: Option<WebhookConfig>,
+    /// Slack and Discord channels to tell when transcriptions finish; see
+    /// `transcriber::notifier`.
+    #[serde(default)]
+    pub notifiers: Vec<Notifier>,
+    /// Environment variables for yt-dlp and ffmpeg, which otherwise only
+    /// see a few of the server's; see `utils::process`.
+    #[serde(default)]
+    pub subprocess_env: BTreeMap<String, String>,
+    /// Run yt-dlp and ffmpeg in a sandbox that can only write to their
+    /// temp directory; see `utils::process`.
+    #[serde(default)]
+    pub sandbox_subprocesses: bool,
+    /// Where outputs are written; see `transcriber::sink`.
+    #[serde(default)]
+    pub output_sink: OutputSink,
+}
+
+/// Destination of transcription outputs.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OutputSink {
+    /// The output directory.
+    #[default]
+    Local,
+    Webdav(WebDavConfig),
+    S3(S3Config),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebDavConfig {
+    /// Collection the output directory maps to.
+    pub url: String,
+    /// Basic auth user; none for an open server.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password, else `WEBDAV_PASSWORD`.
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Config {
+    /// S3 API endpoint, e.g. `https://s3.eu-west-1.amazonaws.com` or
+    /// MinIO's `http://localhost:9000`.
+    pub endpoint: String,
+    pub bucket: String,
+    /// Else `AWS_REGION`, else `us-east-1` (what MinIO expects).
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Prepended to each object key, e.g. `transcripts/`.
+    #[serde(default)]
+    pub prefix: String,
+    /// Else `AWS_ACCESS_KEY_ID`.
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// Else `AWS_SECRET_ACCESS_KEY`.
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+    /// Address objects as `endpoint/bucket/key`, as MinIO needs, rather
+    /// than `bucket.endpoint/key`.
+    #[serde(default = "default_path_style")]
+    pub path_style: bool,
+    /// Write the outputs to the output directory as well and upload
+    /// copies, rather than only uploading them.
+    #[serde(default = "default_keep_local")]
+    pub keep_local: bool,
+}
+
+fn default_path_style() -> bool {
+    true
+}
+
+fn default_keep_local() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingsConfig {
+    /// OpenAI-compatible embeddings endpoint, e.g.
+    /// `https://api.openai.com/v1/embeddings`, or Ollama's
+    /// `http://localhost:11434/v1/embeddings` to keep transcripts local.
+    pub url: String,
+    pub model: String,
+    /// Bearer token, else `EMBEDDINGS_API_KEY`. Local servers need none.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WhisperApiConfig {
+    /// Base URL of the API, up to and including `/v1`, e.g.
+    /// `https://api.openai.com/v1`; `/audio/transcriptions` is appended.
+    pub base_url: String,
+    #[serde(default = "default_whisper_api_model")]
+    pub model: String,
+    /// Bearer token, else `WHISPER_API_KEY`. Local servers need none.
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+fn default_whisper_api_model() -> String {
+    "whisper-1".to_string()
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WebhookConfig {
+    /// Receiver for requests that don't name their own.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// HMAC-SHA256 key for `X-Webhook-Signature`, else `WEBHOOK_SECRET`.
+    /// Unsigned when neither is set.
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Notifier {
+    #[serde(rename = "type")]
+    pub kind: NotifierKind,
+    /// The channel's incoming webhook URL.
+    pub url: String,
+    /// Post failed transcriptions too.
+    #[serde(default = "default_notify_failures")]
+    pub failures: bool,
+}
+
+fn default_notify_failures() -> bool {
+    true
+}
+
+/// Message format of a [`Notifier`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifierKind {
+    Slack,
+    Discord,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(not(feature = "llama"), allow(dead_code))]
+pub struct LocalSummarizer {
+    /// GGUF model file, absolute or relative to the Whisper models
+    /// directory.
+    pub model_path: String,
+    /// llama.cpp's CLI, looked up on `PATH` unless a path is given.
+    #[serde(default = "default_llama_binary")]
+    pub binary: String,
+    /// Context window in tokens. The transcript is cut to fit it.
+    #[serde(default = "default_context_tokens")]
+    pub context_tokens: u32,
+    /// Use the local model even when the client supports sampling, so
+    /// transcripts never leave the server.
+    #[serde(default)]
+    pub prefer_local: bool,
+}
+
+fn default_llama_binary() -> String {
+    "llama-cli".to_string()
+}
+
+fn default_context_tokens() -> u32 {
+    8192
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdmissionPolicy {
+    /// Reject new large-model jobs while this many hours of audio for large
+    /// models are already queued or running.
+    #[serde(default)]
+    pub max_large_model_hours: Option<f64>,
+    /// Jobs that may transcribe at once, across every session and the REST
+    /// API. One when absent.
+    #[serde(default)]
+    pub max_concurrent_jobs: Option<usize>,
+}
+
+impl AdmissionPolicy {
+    pub fn concurrent_jobs(&self) -> usize {
+        self.max_concurrent_jobs.unwrap_or(1).max(1)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WorkerLimits {
+    /// Jobs that may download at once.
+    #[serde(default)]
+    pub max_concurrent_downloads: Option<usize>,
+    /// Jobs that may run Whisper at once.
+    #[serde(default)]
+    pub max_concurrent_transcriptions: Option<usize>,
+    /// Memory, in MB, the models of jobs running Whisper may take together.
+    #[serde(default)]
+    pub model_memory_budget_mb: Option<u32>,
+    /// Memory each model takes, in MB, keyed by model name (`large-v3`,
+    /// `medium-q5_0`), where the estimate from its file is off.
+    #[serde(default)]
+    pub model_memory_mb: HashMap<String, u32>,
+    /// Devices local Whisper jobs are spread over, in order of preference.
+    /// Empty leaves every job on the `gpu` default.
+    #[serde(default)]
+    pub devices: Vec<DeviceSlots>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceSlots {
+    pub device: Device,
+    /// Jobs the device runs at once.
+    #[serde(default = "default_device_jobs")]
+    pub jobs: usize,
+}
+
+fn default_device_jobs() -> usize {
+    1
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DisconnectPolicy {
+    #[serde(default)]
+    pub action: DisconnectAction,
+    /// With `cancel`, how long the call may keep running after the
+    /// disconnect; it is kept if it finishes in time.
+    #[serde(default)]
+    pub grace_seconds: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisconnectAction {
+    /// Finish in the background. The response is lost but the outputs are
+    /// saved as usual.
+    #[default]
+    Continue,
+    /// Stop once the grace period is over.
+    Cancel,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionPolicy {
+    #[serde(default)]
+    pub store: SessionStoreKind,
+    /// How long a stored session can be resumed after it started or was
+    /// last resumed.
+    #[serde(default = "default_session_ttl")]
+    pub ttl_minutes: u64,
+    /// Minutes without traffic before a session is closed in memory (and
+    /// has to be resumed from the store). rmcp's five when absent.
+    #[serde(default)]
+    pub idle_minutes: Option<u64>,
+    /// SQLite file for `sqlite`. Defaults to
+    /// `~/.cache/video-transcriber-mcp/sessions.sqlite`.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CorsPolicy {
+    /// Origins such as `https://dash.example.com`; `*` allows any. None
+    /// when empty.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// Request headers browsers may send; `*` allows any. The ones the
+    /// server reads (`Authorization`, `Content-Type`, `Mcp-Session-Id`, …)
+    /// when absent.
+    #[serde(default)]
+    pub allowed_headers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitPolicy {
+    /// Steady rate each client is allowed once its burst is spent.
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u64,
+    /// Requests a client can make at once before the rate applies.
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    #[serde(default)]
+    pub by: RateLimitKey,
+    /// Limit `/mcp`, `/v1`, `/files` and `/metrics` as well as `/api`.
+    #[serde(default)]
+    pub all_routes: bool,
+    /// Requests handled at once across all clients; more are answered
+    /// 503. No cap when absent.
+    #[serde(default)]
+    pub max_concurrent_requests: Option<usize>,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: default_requests_per_minute(),
+            burst: default_burst(),
+            by: RateLimitKey::default(),
+            all_routes: false,
+            max_concurrent_requests: None,
+        }
+    }
+}
+
+fn default_requests_per_minute() -> u64 {
+    60
+}
+
+fn default_burst() -> u32 {
+    20
+}
+
+/// What a rate-limited client is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// Each IP, from the proxy headers or the connection.
+    #[default]
+    Ip,
+    /// Each config API key; requests without one by IP.
+    ApiKey,
+}
+
+fn default_session_ttl() -> u64 {
+    24 * 60
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStoreKind {
+    /// Kept while the server runs.
+    #[default]
+    Memory,
+    /// Kept in a SQLite file, across restarts.
+    Sqlite,
+}
+
+/// Limits for the retention sweep. Either or both may be set; with neither,
+/// nothing is deleted.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RetentionPolicy {
+    /// Delete transcripts and cached audio last modified more than this
+    /// many days ago.
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    /// Then delete the oldest files until transcripts and cached audio
+    /// together fit in this many GB.
+    #[serde(default)]
+    pub max_total_gb: Option<f64>,
+    /// Transcript directory to enforce on. Defaults to the default output
+    /// directory.
+    #[serde(default)]
+    pub output_dir: Option<PathBuf>,
+    #[serde(default = "default_retention_interval")]
+    pub interval_minutes: u64,
+}
+
+fn default_retention_interval() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiKey {
+    /// Label for logs, so usage can be told apart without logging the key.
+    pub name: String,
+    pub key: String,
+    /// Tools this key may list and call. Absent means all of them.
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, tool: &str) -> bool {
+        self.tools
+            .as_ref()
+            .is_none_or(|tools| tools.iter().any(|t| t == tool))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlatformRule {
+    /// Domain to match. Also matches its subdomains; a leading `*.` is
+    /// accepted and ignored.
+    pub domain: String,
+    /// Platform name reported in metadata and used for profile lookup.
+    pub name: String,
+}
+
+/// Defaults applied to every transcription from one platform. Each field
+/// only fills in what the request left unset — an explicit `model` or
+/// `language` in the tool call always wins.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlatformProfile {
+    #[serde(default)]
+    pub model: Option<WhisperModel>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub chunk_minutes: Option<u32>,
+    #[serde(default)]
+    pub gpu: Option<bool>,
+    #[serde(default)]
+    pub skip_non_speech: Option<bool>,
+    #[serde(default)]
+    pub skip_silence: Option<bool>,
+    /// Hotwords for requests that list none of their own.
+    #[serde(default)]
+    pub hotwords: Vec<String>,
+}
+
+/// ffmpeg preprocessing applied while decoding a source's audio for
+/// whisper. Every field is optional; an empty profile changes nothing.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AudioProfile {
+    /// Cut rumble / hum below this frequency.
+    #[serde(default)]
+    pub highpass_hz: Option<u32>,
+    /// Cut hiss above this frequency.
+    #[serde(default)]
+    pub lowpass_hz: Option<u32>,
+    #[serde(default)]
+    pub eq: Vec<EqBand>,
+    /// Noise reduction strength in dB (ffmpeg `afftdn`); 10–20 suits most
+    /// background hiss.
+    #[serde(default)]
+    pub denoise_db: Option<f32>,
+    /// Volume change in dB, applied last.
+    #[serde(default)]
+    pub gain_db: Option<f32>,
+}
+
+/// One peaking EQ band.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EqBand {
+    pub freq_hz: u32,
+    pub gain_db: f32,
+    /// Band width as a Q factor.
+    #[serde(default = "default_eq_q")]
+    pub q: f32,
+}
+
+fn default_eq_q() -> f32 {
+    1.0
+}
+
+impl Config {
+    /// Load the config file. A missing file is the normal case and yields
+    /// the defaults; a malformed one is logged and ignored rather than
+    /// failing every transcription.
+    pub fn load() -> Self {
+        let path = get_config_path();
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(_) => return Self::default(),
+        };
+        match serde_json::from_str(&raw) {
+            Ok(config) => config,
+            Err(e) => {
+                warn!("Ignoring invalid config file {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Profile for `platform`, if one is configured.
+    pub fn profile_for(&self, platform: &str) -> Option<(&str, &PlatformProfile)> {
+        self.profiles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(platform))
+            .map(|(name, profile)| (name.as_str(), profile))
+    }
+
+    /// The configured key matching `token`. Compares every key in full so
+    /// timing doesn't leak how much of a guess was right.
+    pub fn api_key(&self, token: &str) -> Option<&ApiKey> {
+        self.api_keys.iter().fold(None, |found, k| {
+            let matches = constant_time_eq(k.key.as_bytes(), token.as_bytes());
+            found.or(matches.then_some(k))
+        })
+    }
+
+    /// Audio profile for `channel`, if one is configured.
+    pub fn audio_profile_for(&self, channel: &str) -> Option<(&str, &AudioProfile)> {
+        self.audio_profiles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(channel))
+            .map(|(name, profile)| (name.as_str(), profile))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl AudioProfile {
+    /// The profile as an ffmpeg `-af` filter chain, or `None` if it sets
+    /// nothing.
+    pub fn filter_chain(&self) -> Option<String> {
+        let mut filters = Vec::new();
+        if let Some(hz) = self.highpass_hz {
+            filters.push(format!("highpass=f={}", hz));
+        }
+        if let Some(hz) = self.lowpass_hz {
+            filters.push(format!("lowpass=f={}", hz));
+        }
+        for band in &self.eq {
+            filters.push(format!(
+                "equalizer=f={}:t=q:w={}:g={}",
+                band.freq_hz, band.q, band.gain_db
+            ));
+        }
+        if let Some(db) = self.denoise_db {
+            filters.push(format!("afftdn=nr={}", db.clamp(0.01, 97.0)));
+        }
+        if let Some(db) = self.gain_db {
+            filters.push(format!("volume={}dB", db));
+        }
+        (!filters.is_empty()).then(|| filters.join(","))
+    }
+}
+
+impl PlatformProfile {
+    /// Fill the unset fields of `options` from this profile.
+    pub fn apply(&self, options: &mut TranscriptionOptions) {
+        if options.model.is_none() {
+            options.model = self.model;
+        }
+        if options.language.is_none() {
+            options.language = self.language.clone();
+        }
+        if options.chunk_minutes.is_none() {
+            options.chunk_minutes = self.chunk_minutes;
+        }
+        if options.gpu.is_none() {
+            options.gpu = self.gpu;
+        }
+        if options.skip_non_speech.is_none() {
+            options.skip_non_speech = self.skip_non_speech;
+        }
+        if options.skip_silence.is_none() {
+            options.skip_silence = self.skip_silence;
+        }
+        if options.hotwords.is_empty() {
+            options.hotwords = self.hotwords.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcriber::types::ModelSize;
+
+    fn options() -> TranscriptionOptions {
+        TranscriptionOptions {
+            url: "https://www.tiktok.com/@a/video/1".to_string(),
+            output_dir: "/tmp".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn profile_lookup_is_case_insensitive() {
+        let config: Config =
+            serde_json::from_str(r#"{"profiles": {"tiktok": {"model": "tiny"}}}"#).unwrap();
+        let (name, profile) = config.profile_for("TikTok").unwrap();
+        assert_eq!(name, "tiktok");
+        assert_eq!(profile.model, Some(WhisperModel::from(ModelSize::Tiny)));
+        assert!(config.profile_for("YouTube").is_none());
+    }
+
+    #[test]
+    fn profile_fills_only_unset_fields() {
+        let profile = PlatformProfile {
+            model: Some(WhisperModel::from(ModelSize::Tiny)),
+            language: Some("en".to_string()),
+            chunk_minutes: Some(10),
+            gpu: None,
+            skip_non_speech: None,
+            skip_silence: Some(true),
+            hotwords: vec!["Kubernetes".to_string()],
+        };
+        let mut opts = options();
+        opts.model = Some(WhisperModel::from(ModelSize::Medium));
+        opts.hotwords = vec!["PostgreSQL".to_string()];
+        profile.apply(&mut opts);
+        assert_eq!(opts.model, Some(WhisperModel::from(ModelSize::Medium)));
+        assert_eq!(opts.language.as_deref(), Some("en"));
+        assert_eq!(opts.chunk_minutes, Some(10));
+        assert_eq!(opts.gpu, None);
+        assert_eq!(opts.skip_silence, Some(true));
+        assert_eq!(opts.hotwords, ["PostgreSQL"]);
+    }
+
+    #[test]
+    fn audio_profile_builds_filter_chain() {
+        let config: Config = serde_json::from_str(
+            r#"{"audio_profiles": {"My Podcast": {
+                "highpass_hz": 80,
+                "eq": [{"freq_hz": 3000, "gain_db": 2.5}],
+                "denoise_db": 15,
+                "gain_db": -1.5
+            }}}"#,
+        )
+        .unwrap();
+        let (_, profile) = config.audio_profile_for("my podcast").unwrap();
+        assert_eq!(
+            profile.filter_chain().as_deref(),
+            Some("highpass=f=80,equalizer=f=3000:t=q:w=1:g=2.5,afftdn=nr=15,volume=-1.5dB")
+        );
+        assert_eq!(AudioProfile::default().filter_chain(), None);
+    }
+
+    #[test]
+    fn audio_cache_format_defaults_to_original() {
+        assert_eq!(Config::default().audio_cache_format, AudioFormat::Original);
+        let config: Config = serde_json::from_str(r#"{"audio_cache_format": "opus"}"#).unwrap();
+        assert_eq!(config.audio_cache_format, AudioFormat::Opus);
+        assert_eq!(config.audio_cache_format.yt_dlp_args()[1], "opus");
+        assert!(serde_json::from_str::<Config>(r#"{"audio_cache_format": "aiff"}"#).is_err());
+    }
+
+    #[test]
+    fn api_keys_carry_tool_allowlists() {
+        let config: Config = serde_json::from_str(
+            r#"{"api_keys": [
+                {"name": "admin", "key": "k-admin"},
+                {"name": "support", "key": "k-support", "tools": ["list_transcripts"]}
+            ]}"#,
+        )
+        .unwrap();
+        let admin = config.api_key("k-admin").unwrap();
+        assert!(admin.allows("delete_all_transcripts"));
+        let support = config.api_key("k-support").unwrap();
+        assert_eq!(support.name, "support");
+        assert!(support.allows("list_transcripts"));
+        assert!(!support.allows("transcribe_video"));
+        assert!(config.api_key("k-suppor").is_none());
+        assert!(config.api_key("").is_none());
+    }
+}
@@ -78,6 +78,7 @@ pub struct FileState {
 /// - `DATABASE_URL` set → connect Postgres, ensure schema, migrate any
 ///   existing `credits.json` into the table (one-time), return `Db`.
 /// - else → load the JSON file, return `File`.
+#[allow(clippy::collapsible_if)]
 pub async fn new_store() -> CreditStore {
     if let Ok(url) = std::env::var("DATABASE_URL") {
         if !url.trim().is_empty() {
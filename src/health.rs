@@ -0,0 +1,129 @@
+//! Liveness and readiness probes for container orchestrators.
+//!
+//! `GET /healthz` answers 200 whenever the process is serving requests.
+//! `GET /readyz` answers 200 once jobs can run: yt-dlp and ffmpeg start,
+//! the subprocess sandbox works if one is configured, and a Whisper model
+//! is installed (or transcription goes to a remote backend). Otherwise it
+//! answers 503, and the JSON body says which check failed. Neither needs
+//! an API key, so Kubernetes and Docker healthchecks work without one.
+
+use axum::{
+    Json, Router,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::transcriber::types::WhisperModel;
+use crate::transcriber::whisper;
+use crate::utils::managed;
+use crate::utils::paths::get_models_dir;
+use crate::utils::process;
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Readiness {
+    pub yt_dlp: bool,
+    pub ffmpeg: bool,
+    /// `None` when sandboxing is off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<bool>,
+    /// Transcription goes to `REMOTE_WHISPER_URL` or `whisper_api`.
+    pub remote_whisper: bool,
+    /// Models in the models directory.
+    pub models: Vec<String>,
+}
+
+impl Readiness {
+    pub fn check() -> Self {
+        Self {
+            yt_dlp: runs(&managed::program("yt-dlp"), "--version"),
+            ffmpeg: runs("ffmpeg", "-version"),
+            sandbox: process::sandbox_status().map(|status| status.is_ok()),
+            remote_whisper: whisper::is_remote(),
+            models: installed_models(&get_models_dir()),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.yt_dlp
+            && self.ffmpeg
+            && self.sandbox != Some(false)
+            && (self.remote_whisper || !self.models.is_empty())
+    }
+}
+
+fn runs(program: &str, arg: &str) -> bool {
+    std::process::Command::new(program)
+        .arg(arg)
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn installed_models(models_dir: &Path) -> Vec<String> {
+    WhisperModel::all()
+        .into_iter()
+        .filter(|model| models_dir.join(model.model_filename()).is_file())
+        .map(|model| model.to_string())
+        .collect()
+}
+
+async fn readyz() -> Response {
+    let readiness = match tokio::task::spawn_blocking(Readiness::check).await {
+        Ok(readiness) => readiness,
+        Err(_) => return StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    };
+    let status = if readiness.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(serde_json::json!({ "ready": readiness.is_ready(), "checks": readiness })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_tools_and_a_model() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("ggml-base.bin"), b"").unwrap();
+        std::fs::write(dir.path().join("ggml-nonsense.bin"), b"").unwrap();
+        let models = installed_models(dir.path());
+        assert_eq!(models, vec!["base".to_string()]);
+
+        let mut readiness = Readiness {
+            yt_dlp: true,
+            ffmpeg: true,
+            sandbox: None,
+            remote_whisper: false,
+            models,
+        };
+        assert!(readiness.is_ready());
+        readiness.sandbox = Some(false);
+        assert!(!readiness.is_ready());
+        readiness.sandbox = Some(true);
+        readiness.models.clear();
+        assert!(!readiness.is_ready());
+        readiness.remote_whisper = true;
+        assert!(readiness.is_ready());
+        readiness.ffmpeg = false;
+        assert!(!readiness.is_ready());
+    }
+}
@@ -0,0 +1,285 @@
+//! The HTTP side of the server as a `tower::Service`, for embedding in an
+//! existing axum or hyper server.
+//!
+//! [`VideoTranscriberService`] serves what `--transport http` serves:
+//! Streamable HTTP MCP at `/mcp`, the REST API under `/api`, OpenAI's
+//! `/v1/audio/transcriptions`, transcript files under `/files`,
+//! `/metrics` and the `/healthz` and `/readyz` probes. The config file's
+//! API keys gate everything but `/api` and the probes as they do in the
+//! server. CORS and the rate limits are the server's own and aren't
+//! included: add what your stack needs around the service, or around the
+//! REST API alone with [`Builder::map_api`]. [`cors_layer`] builds the
+//! server's CORS from the config file's `cors` section, and
+//! [`crate::rate_limit`] its limits.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! use video_transcriber_mcp::http::VideoTranscriberService;
+//! use video_transcriber_mcp::mcp::queue::JobQueue;
+//!
+//! let service = VideoTranscriberService::builder(JobQueue::shared())
+//!     .read_only(true)
+//!     .build()
+//!     .await?;
+//! let app: axum::Router = axum::Router::new().nest_service("/transcriber", service);
+//! # let _ = app;
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::{Context as _, Result};
+use axum::Router;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{HeaderName, HeaderValue, header};
+use axum::response::Response;
+use axum::routing::future::RouteFuture;
+use rmcp::transport::streamable_http_server::{StreamableHttpServerConfig, StreamableHttpService};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_http::cors::{AllowHeaders, AllowOrigin, Any, CorsLayer};
+
+use crate::api::{self, AppState};
+use crate::auth::JwksCache;
+use crate::config::{Config, CorsPolicy};
+use crate::credits;
+use crate::health;
+use crate::mcp::queue::JobQueue;
+use crate::mcp::{self, VideoTranscriberServer};
+use crate::metrics;
+
+/// Builds a [`VideoTranscriberService`].
+pub struct Builder {
+    queue: Arc<JobQueue>,
+    read_only: bool,
+    map_api: Option<Box<dyn FnOnce(Router) -> Router + Send>>,
+}
+
+impl Builder {
+    /// Serve only the listing and reading tools and endpoints (see
+    /// `--read-only`).
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Wrap the REST API's router, e.g. in a rate limit, before it is
+    /// nested under `/api`.
+    pub fn map_api(mut self, map: impl FnOnce(Router) -> Router + Send + 'static) -> Self {
+        self.map_api = Some(Box::new(map));
+        self
+    }
+
+    pub async fn build(self) -> Result<VideoTranscriberService> {
+        // One VideoTranscriberServer per MCP session, all on the queue the
+        // REST API shares.
+        let mcp_queue = self.queue.clone();
+        let read_only = self.read_only;
+        // Sessions a client can resume after its connection drops, if the
+        // config file keeps them (see mcp::sessions).
+        let sessions = Config::load().sessions;
+        let mut http_config = StreamableHttpServerConfig::default();
+        http_config.session_store = mcp::sessions::store(sessions.as_ref())?;
+        let mcp_service = StreamableHttpService::new(
+            move || Ok(VideoTranscriberServer::with_queue(mcp_queue.clone()).read_only(read_only)),
+            mcp::sessions::manager(sessions.as_ref()).into(),
+            http_config,
+        );
+
+        // Supabase JWKS cache for verifying user auth tokens. Falls back to a
+        // placeholder URL if SUPABASE_URL isn't configured — the cache will
+        // simply fail to fetch and every auth-requiring endpoint will 401,
+        // which is the correct behavior for a misconfigured deployment.
+        let supabase_url = std::env::var("SUPABASE_URL").unwrap_or_else(|_| {
+            tracing::warn!(
+                "SUPABASE_URL is not set — /api/me and other auth-required endpoints will reject all requests"
+            );
+            "https://invalid.supabase.invalid".to_string()
+        });
+
+        // REST API state shared across all jobs
+        let app_state = AppState {
+            jobs: api::new_store(),
+            uploads: api::uploads::new_store(),
+            transcriptions: api::transcriptions::new_store(),
+            queue: self.queue.clone(),
+            credits: credits::new_store().await,
+            jwks: JwksCache::new(&supabase_url),
+        };
+        let mut api_router = api::router(app_state, read_only);
+        if let Some(map) = self.map_api {
+            api_router = map(api_router);
+        }
+
+        // API keys from the config file, when any are configured, gate /mcp,
+        // /v1, /files and /metrics and limit which tools each key sees (see
+        // mcp::policy).
+        let mut mcp_router = Router::new().nest_service("/mcp", mcp_service);
+        if !read_only {
+            mcp_router = mcp_router.merge(api::openai::router(self.queue.clone()));
+        }
+        let mcp_router = mcp_router
+            .merge(api::files::router())
+            .merge(metrics::router())
+            .layer(axum::middleware::from_fn(mcp::policy::require_api_key));
+
+        Ok(VideoTranscriberService {
+            router: Router::new()
+                .nest("/api", api_router)
+                .merge(mcp_router)
+                .merge(health::router()),
+        })
+    }
+}
+
+/// The server's HTTP endpoints as one `tower::Service`.
+#[derive(Clone)]
+pub struct VideoTranscriberService {
+    router: Router,
+}
+
+impl VideoTranscriberService {
+    /// A builder for the service, running jobs on `queue`.
+    pub fn builder(queue: Arc<JobQueue>) -> Builder {
+        Builder {
+            queue,
+            read_only: false,
+            map_api: None,
+        }
+    }
+
+    /// The service as an axum router, to merge or nest into another.
+    pub fn into_router(self) -> Router {
+        self.router
+    }
+}
+
+impl tower::Service<Request> for VideoTranscriberService {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = RouteFuture<Infallible>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        tower::Service::<Request<Body>>::poll_ready(&mut self.router, cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        tower::Service::call(&mut self.router, req)
+    }
+}
+
+/// Request headers the endpoints read, allowed when `cors` doesn't list
+/// its own.
+const CORS_HEADERS: [&str; 7] = [
+    "authorization",
+    "content-type",
+    "mcp-session-id",
+    "mcp-protocol-version",
+    "last-event-id",
+    "x-device-id",
+    "upload-offset",
+];
+
+/// CORS for the HTTP endpoints: any origin without a `cors` section,
+/// otherwise its origins and headers. Either way browsers can read the
+/// `Mcp-Session-Id` a session starts with and the file names downloads
+/// come with.
+pub fn cors_layer(policy: Option<&CorsPolicy>) -> Result<CorsLayer> {
+    let layer = CorsLayer::new().allow_methods(Any).expose_headers([
+        HeaderName::from_static("mcp-session-id"),
+        header::CONTENT_DISPOSITION,
+    ]);
+    let Some(policy) = policy else {
+        return Ok(layer.allow_origin(Any).allow_headers(Any));
+    };
+
+    let origin = if policy.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = policy
+            .allowed_origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o.trim_end_matches('/'))
+                    .with_context(|| format!("Invalid CORS origin: {}", o))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    let headers = match &policy.allowed_headers {
+        Some(headers) if headers.iter().any(|h| h == "*") => AllowHeaders::any(),
+        Some(headers) => AllowHeaders::list(
+            headers
+                .iter()
+                .map(|h| {
+                    HeaderName::try_from(h.as_str())
+                        .with_context(|| format!("Invalid CORS header: {}", h))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        None => AllowHeaders::list(CORS_HEADERS.map(HeaderName::from_static)),
+    };
+    Ok(layer.allow_origin(origin).allow_headers(headers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Method, StatusCode};
+    use tower::ServiceExt;
+
+    async fn preflight(layer: CorsLayer, origin: &str) -> Option<HeaderValue> {
+        let app = Router::new()
+            .route("/mcp", axum::routing::post(|| async { "ok" }))
+            .layer(layer);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method(Method::OPTIONS)
+                    .uri("/mcp")
+                    .header(header::ORIGIN, origin)
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "mcp-session-id")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .cloned()
+    }
+
+    #[tokio::test]
+    async fn allows_only_listed_origins() {
+        let policy = CorsPolicy {
+            allowed_origins: vec!["https://dash.example.com/".to_string()],
+            allowed_headers: None,
+        };
+        let allowed = preflight(
+            cors_layer(Some(&policy)).unwrap(),
+            "https://dash.example.com",
+        )
+        .await;
+        assert_eq!(allowed.unwrap(), "https://dash.example.com");
+        let other = preflight(cors_layer(Some(&policy)).unwrap(), "https://evil.example").await;
+        assert!(other.is_none());
+
+        let any = preflight(cors_layer(None).unwrap(), "https://evil.example").await;
+        assert_eq!(any.unwrap(), "*");
+
+        let bad_origin = CorsPolicy {
+            allowed_origins: vec!["https://dash.example.com\n".to_string()],
+            allowed_headers: None,
+        };
+        assert!(cors_layer(Some(&bad_origin)).is_err());
+        let bad_header = CorsPolicy {
+            allowed_origins: vec!["*".to_string()],
+            allowed_headers: Some(vec!["not a header".to_string()]),
+        };
+        assert!(cors_layer(Some(&bad_header)).is_err());
+    }
+}
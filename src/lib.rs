@@ -1,9 +1,17 @@
 pub mod api;
 pub mod auth;
+pub mod config;
 pub mod credits;
+pub mod health;
+pub mod http;
 pub mod llm;
 pub mod mcp;
+pub mod metrics;
+pub mod rate_limit;
+pub mod retention;
 pub mod transcriber;
 pub mod utils;
 
+pub use transcriber::sink::{MemorySink, TranscriptSink};
+pub use transcriber::store::TranscriptStore;
 pub use transcriber::{TranscriberEngine, TranscriptionOptions, WhisperModel};
@@ -1,31 +1,31 @@
 use anyhow::Result;
-use clap::{Parser, ValueEnum};
-use rmcp::{
-    ServiceExt,
-    transport::{stdio, streamable_http_server::StreamableHttpService},
-};
+use clap::{Parser, Subcommand, ValueEnum};
+use rmcp::{ServiceExt, transport::stdio};
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tower_governor::{
-    GovernorLayer, governor::GovernorConfigBuilder, key_extractor::SmartIpKeyExtractor,
-};
-use tower_http::cors::{Any, CorsLayer};
 use tracing::Level;
 
 mod api;
 mod auth;
+mod config;
+mod health;
+mod http;
 mod llm;
 mod mcp;
+mod metrics;
+mod rate_limit;
+mod retention;
+mod service;
 mod transcriber;
 mod utils;
 
-use api::AppState;
+use http::VideoTranscriberService;
 use mcp::VideoTranscriberServer;
+use mcp::queue::JobQueue;
 use transcriber::TranscriberEngine;
 use video_transcriber_mcp::credits;
 
 /// Transport mode for the MCP server
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
 enum Transport {
     /// Standard I/O transport (default for local CLI usage)
     Stdio,
@@ -37,23 +37,101 @@ enum Transport {
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Transport mode to use
-    #[arg(short, long, value_enum, default_value = "stdio")]
-    transport: Transport,
+    /// Transport mode to use; `stdio,http` serves both from one process
+    #[arg(
+        short,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "stdio"
+    )]
+    transport: Vec<Transport>,
 
     /// Host address for HTTP transport
-    #[arg(long, default_value = "127.0.0.1")]
+    #[arg(long, global = true, default_value = "127.0.0.1")]
     host: String,
 
     /// Port for HTTP transport
-    #[arg(short, long, default_value = "8080")]
+    #[arg(short, long, global = true, default_value = "8080")]
     port: u16,
+
+    /// Serve an existing transcript archive only: hide and reject the tools
+    /// (and REST endpoints) that transcribe or delete
+    #[arg(long, global = true)]
+    read_only: bool,
+
+    /// Transcribe every media file in this folder, print a summary and exit
+    /// instead of starting a server. Files already transcribed are skipped.
+    #[arg(long, value_name = "DIR")]
+    transcribe_dir: Option<std::path::PathBuf>,
+
+    /// With --transcribe-dir: where transcripts go (default: ~/Downloads/video-transcripts)
+    #[arg(long, value_name = "DIR", requires = "transcribe_dir")]
+    output_dir: Option<String>,
+
+    /// With --transcribe-dir: whisper model to use
+    #[arg(long, requires = "transcribe_dir")]
+    model: Option<transcriber::WhisperModel>,
+
+    /// With --transcribe-dir: comma-separated file extensions to pick up
+    #[arg(long, value_delimiter = ',', requires = "transcribe_dir")]
+    extensions: Vec<String>,
+
+    /// With --transcribe-dir: also scan subfolders
+    #[arg(long, requires = "transcribe_dir")]
+    recursive: bool,
+
+    /// Render every output format from the golden fixture in DIR (default
+    /// tests/golden), diff against the golden files and exit
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "tests/golden")]
+    verify_formats: Option<std::path::PathBuf>,
+
+    /// With --verify-formats: rewrite the golden files from the current output
+    #[arg(long, requires = "verify_formats")]
+    bless: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the HTTP server in the background from login (systemd user unit,
+    /// launchd agent or Windows scheduled task)
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ServiceAction {
+    /// Register and start the service with the given --host, --port and --read-only
+    Install,
+    /// Stop and remove the service
+    Uninstall,
+    /// Show what the service manager says about the service
+    Status,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
+    if let Some(Command::Service { action }) = &args.command {
+        let report = match action {
+            ServiceAction::Install => service::install(&service::ServiceSpec::current(
+                &args.host,
+                args.port,
+                args.read_only,
+            )?)?,
+            ServiceAction::Uninstall => service::uninstall()?,
+            ServiceAction::Status => service::status()?,
+        };
+        println!("{}", report);
+        return Ok(());
+    }
+
     // Initialize logging to stderr so stdout is clean for MCP (stdio mode)
     tracing_subscriber::fmt()
         .with_max_level(Level::INFO)
@@ -62,7 +140,7 @@ async fn main() -> Result<()> {
         .with_thread_ids(false)
         .with_file(false)
         .with_line_number(false)
-        .with_ansi(matches!(args.transport, Transport::Http)) // Enable ANSI for HTTP mode
+        .with_ansi(!args.transport.contains(&Transport::Stdio)) // Enable ANSI for HTTP mode
         .init();
 
     tracing::info!(
@@ -71,17 +149,122 @@ async fn main() -> Result<()> {
     );
     tracing::info!("Powered by whisper.cpp - 6x faster than Python whisper!");
 
-    match args.transport {
-        Transport::Stdio => run_stdio_transport().await,
-        Transport::Http => run_http_transport(&args.host, args.port).await,
+    if let Some(dir) = &args.transcribe_dir {
+        return transcribe_directory(dir, &args).await;
+    }
+
+    if let Some(dir) = &args.verify_formats {
+        return verify_formats(dir, args.bless);
+    }
+
+    #[cfg(feature = "auto-download")]
+    match utils::managed::ensure_yt_dlp().await {
+        Ok(done) => tracing::info!("{}", done),
+        Err(e) => tracing::warn!("Couldn't set up yt-dlp, URLs will fail: {:#}", e),
+    }
+    #[cfg(feature = "auto-download")]
+    if let Err(e) = utils::managed::check_ffmpeg() {
+        tracing::warn!("{:#}; transcription will fail without it", e);
+    }
+
+    if args.read_only {
+        tracing::info!("Read-only mode: transcription and deletion are disabled");
+    }
+
+    // One job queue (and so one engine and model cache) for every transport.
+    let queue = mcp::queue::JobQueue::shared();
+    // Retention from the config file, if any, runs for the server's life.
+    if !args.read_only {
+        retention::spawn();
+    }
+
+    let stdio = args.transport.contains(&Transport::Stdio);
+    let http = args.transport.contains(&Transport::Http);
+    match (stdio, http) {
+        (true, true) => {
+            // The local client owns the process: when it hangs up, HTTP
+            // clients lose the server too.
+            tokio::select! {
+                result = run_stdio_transport(queue.clone(), args.read_only) => result,
+                result = run_http_transport(&args.host, args.port, queue, args.read_only) => result,
+            }
+        }
+        (false, true) => run_http_transport(&args.host, args.port, queue, args.read_only).await,
+        _ => run_stdio_transport(queue, args.read_only).await,
+    }
+}
+
+/// One-shot `--transcribe-dir` mode: run the folder as a batch and print
+/// the same summary the `transcribe_directory` tool returns.
+async fn transcribe_directory(dir: &std::path::Path, args: &Args) -> Result<()> {
+    let extensions: Vec<String> = if args.extensions.is_empty() {
+        transcriber::batch::DEFAULT_MEDIA_EXTENSIONS
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    } else {
+        args.extensions
+            .iter()
+            .map(|e| e.trim().trim_start_matches('.').to_string())
+            .collect()
+    };
+    let output_dir = args.output_dir.clone().unwrap_or_else(|| {
+        utils::paths::get_default_output_dir()
+            .to_string_lossy()
+            .to_string()
+    });
+    let options = transcriber::TranscriptionOptions {
+        output_dir,
+        model: args.model,
+        ..Default::default()
+    };
+
+    let engine = TranscriberEngine::new();
+    let run = transcriber::batch::run_directory(
+        &engine,
+        dir,
+        &extensions,
+        args.recursive,
+        &options,
+        Default::default(),
+    )
+    .await?;
+    println!(
+        "{}",
+        mcp::server_rmcp::format_directory_run(&run, &options.output_dir)
+    );
+    Ok(())
+}
+
+/// One-shot `--verify-formats` mode: fail if any output format drifted
+/// from its golden file.
+fn verify_formats(dir: &std::path::Path, bless: bool) -> Result<()> {
+    let mismatches = transcriber::golden::verify(dir, bless)?;
+    if bless {
+        println!("Rewrote the golden files in {}", dir.display());
+        return Ok(());
+    }
+    if mismatches.is_empty() {
+        println!(
+            "All output formats match the golden files in {}",
+            dir.display()
+        );
+        return Ok(());
+    }
+    for mismatch in &mismatches {
+        println!("{}", mismatch);
     }
+    anyhow::bail!(
+        "{} output format(s) differ from the golden files (rerun with --bless if intended)",
+        mismatches.len()
+    )
 }
 
 /// Run the MCP server with stdio transport (for local CLI usage)
-async fn run_stdio_transport() -> Result<()> {
+async fn run_stdio_transport(queue: Arc<JobQueue>, read_only: bool) -> Result<()> {
     tracing::info!("Starting stdio transport...");
 
-    let server = VideoTranscriberServer::new();
+    let server = VideoTranscriberServer::with_queue(queue).read_only(read_only);
     let service = server.serve(stdio()).await?;
 
     // Wait for shutdown
@@ -94,7 +277,7 @@ async fn run_stdio_transport() -> Result<()> {
 /// process (SIGKILL, OOM, machine replacement, etc.). The normal case is
 /// handled by `TempDir`'s Drop in the upload handler — this is the
 /// belt-and-braces backstop. Runs once at HTTP-transport startup; only
-/// matters when `/api/jobs/upload` is reachable.
+/// matters when `/api/jobs/upload` or `/api/uploads` is reachable.
 fn sweep_stale_uploads() {
     let temp = std::env::temp_dir();
     let entries = match std::fs::read_dir(&temp) {
@@ -128,9 +311,12 @@ fn sweep_stale_uploads() {
 }
 
 /// Run the MCP server with Streamable HTTP transport (for remote access)
-async fn run_http_transport(host: &str, port: u16) -> Result<()> {
-    use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
-
+async fn run_http_transport(
+    host: &str,
+    port: u16,
+    queue: Arc<JobQueue>,
+    read_only: bool,
+) -> Result<()> {
     // Run once at startup. New uploads land in tempfile-managed dirs whose
     // Drop cleans them up automatically; this sweep covers prior processes
     // that died without unwinding.
@@ -138,74 +324,47 @@ async fn run_http_transport(host: &str, port: u16) -> Result<()> {
 
     tracing::info!("Starting Streamable HTTP transport on {}:{}...", host, port);
 
-    // MCP service (per-session VideoTranscriberServer)
-    let mcp_service = StreamableHttpService::new(
-        || Ok(VideoTranscriberServer::new()),
-        LocalSessionManager::default().into(),
-        Default::default(),
-    );
+    // Any origin unless the config file lists them; browser-based MCP
+    // clients and dashboards call from their own.
+    let config = config::Config::load();
+    let cors = http::cors_layer(config.cors.as_ref())?;
 
-    // Supabase JWKS cache for verifying user auth tokens. Falls back to a
-    // placeholder URL if SUPABASE_URL isn't configured — the cache will
-    // simply fail to fetch and every auth-requiring endpoint will 401,
-    // which is the correct behavior for a misconfigured deployment.
-    let supabase_url = std::env::var("SUPABASE_URL").unwrap_or_else(|_| {
-        tracing::warn!(
-            "SUPABASE_URL is not set — /api/me and other auth-required endpoints will reject all requests"
-        );
-        "https://invalid.supabase.invalid".to_string()
-    });
-    let jwks = auth::JwksCache::new(&supabase_url);
-
-    // REST API state shared across all jobs
-    let app_state = AppState {
-        jobs: api::new_store(),
-        engine: Arc::new(Mutex::new(TranscriberEngine::new())),
-        credits: credits::new_store().await,
-        jwks,
-    };
-    let api_router = api::router(app_state);
-
-    // Permissive CORS for local dev — clients are typically browser-based.
-    // Tighten in production deployments.
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
-
-    // Per-IP rate limit on the /api/* surface. Tuned to accommodate the
-    // web/extension's job-polling pattern (~24 req/min while a job runs)
-    // while blocking abusive bursts. Pairs with Modal + OpenRouter spending
-    // caps for defence-in-depth: this throttles request frequency, the
-    // dashboards cap aggregate cost.
-    //
-    //   - per_second: steady-state allowance
-    //   - burst_size: initial allowance before throttling kicks in
+    // Per-client rate limit, on the /api/* surface unless the config file
+    // widens it. The default is tuned to accommodate the web/extension's
+    // job-polling pattern (~24 req/min while a job runs) while blocking
+    // abusive bursts. Pairs with Modal + OpenRouter spending caps for
+    // defence-in-depth: this throttles request frequency, the dashboards cap
+    // aggregate cost.
     //
     // A misbehaving client hitting POST /api/jobs at full speed gets ~20
     // requests through immediately, then 1 per second thereafter — bounded
     // and visible in logs.
-    // `SmartIpKeyExtractor` reads the standard proxy headers (X-Forwarded-For,
-    // X-Real-IP, Forwarded) and falls back to the connection's peer IP — which
+    // Clients are keyed by the standard proxy headers (X-Forwarded-For,
+    // X-Real-IP, Forwarded), falling back to the connection's peer IP — which
     // is what we want behind Fly's edge proxy, where the connecting IP is
-    // always Fly's internal loopback. With the default `PeerIpKeyExtractor`
-    // every real-world user would share a single bucket (and the extractor
-    // would also 500 because `axum::serve(...)` doesn't inject `ConnectInfo`
-    // unless we ask).
-    let governor_conf = Arc::new(
-        GovernorConfigBuilder::default()
-            .per_second(1)
-            .burst_size(20)
-            .key_extractor(SmartIpKeyExtractor)
-            .finish()
-            .expect("failed to build rate limit config"),
-    );
-    let governor_layer = GovernorLayer::new(governor_conf);
+    // always Fly's internal loopback. Keying by peer IP alone, every
+    // real-world user would share a single bucket (see rate_limit).
+    let limits = config.rate_limit.unwrap_or_default();
+    let api_limits = (!limits.all_routes).then(|| limits.clone());
 
-    let router = axum::Router::new()
-        .nest("/api", api_router.layer(governor_layer))
-        .nest_service("/mcp", mcp_service)
-        .layer(cors);
+    // MCP, the REST API, files and metrics, one job queue shared with the
+    // stdio client, if any (see http).
+    let mut router = VideoTranscriberService::builder(queue)
+        .read_only(read_only)
+        .map_api(move |api| match api_limits {
+            Some(limits) => rate_limit::limit_rate(api, &limits),
+            None => api,
+        })
+        .build()
+        .await?
+        .into_router();
+    if limits.all_routes {
+        router = rate_limit::limit_rate(router, &limits);
+    }
+    if let Some(max) = limits.max_concurrent_requests {
+        router = rate_limit::limit_concurrency(router, max);
+    }
+    let router = router.layer(cors);
 
     let addr = format!("{}:{}", host, port);
     let tcp_listener = tokio::net::TcpListener::bind(&addr).await?;
@@ -214,6 +373,9 @@ async fn run_http_transport(host: &str, port: u16) -> Result<()> {
     tracing::info!("Server ready");
     tracing::info!("  MCP:  http://{}/mcp", addr);
     tracing::info!("  REST: http://{}/api/jobs", addr);
+    tracing::info!("  Files: http://{}/files/{{video_id}}/{{format}}", addr);
+    tracing::info!("  Metrics: http://{}/metrics", addr);
+    tracing::info!("  Health: http://{}/healthz, /readyz", addr);
     tracing::info!("=================================================");
 
     // `into_make_service_with_connect_info::<SocketAddr>()` is required for
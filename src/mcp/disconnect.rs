@@ -0,0 +1,97 @@
+//! What happens to a long tool call when its client goes away.
+//!
+//! rmcp runs each request in its own task and cancels the request's token
+//! both when the client sends `notifications/cancelled` and when the session
+//! ends (closed by the client, or dropped by the HTTP session manager). The
+//! two are told apart by the peer channel: once the session is gone it is
+//! closed too. An explicit cancellation stops the work straight away; a
+//! disconnect applies `on_disconnect` from the config file (see
+//! [`DisconnectPolicy`]).
+//!
+//! Stopping means dropping the work future at its current `.await`, which
+//! ends downloads and remote requests. As with REST job cancellation, a
+//! local whisper decode already under way finishes before the drop lands.
+
+use rmcp::{
+    model::{ErrorCode, ErrorData},
+    service::{RequestContext, RoleServer},
+};
+use std::future::Future;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::config::{Config, DisconnectAction, DisconnectPolicy};
+
+/// Run `work` for `tool`, stopping it as the client's cancellation or
+/// disconnect and the configured policy demand.
+pub async fn supervise<T>(
+    context: &RequestContext<RoleServer>,
+    tool: &str,
+    work: impl Future<Output = T>,
+) -> Result<T, ErrorData> {
+    tokio::pin!(work);
+    tokio::select! {
+        result = &mut work => return Ok(result),
+        _ = context.ct.cancelled() => {}
+    }
+
+    if !context.peer.is_transport_closed() {
+        info!("{} cancelled by the client", tool);
+        return Err(cancelled(tool, "cancelled by the client"));
+    }
+
+    let policy = Config::load().on_disconnect;
+    match grace_period(&policy) {
+        None => {
+            info!(
+                "Client disconnected; finishing {} in the background (outputs are still saved)",
+                tool
+            );
+            Ok(work.await)
+        }
+        Some(grace) => {
+            info!(
+                "Client disconnected; cancelling {} unless it finishes within {}s",
+                tool,
+                grace.as_secs()
+            );
+            tokio::select! {
+                result = &mut work => Ok(result),
+                _ = tokio::time::sleep(grace) => {
+                    warn!("Cancelled {} after its client disconnected", tool);
+                    Err(cancelled(tool, "client disconnected"))
+                }
+            }
+        }
+    }
+}
+
+/// How long a disconnected call may keep running: `None` for as long as it
+/// takes.
+fn grace_period(policy: &DisconnectPolicy) -> Option<Duration> {
+    match policy.action {
+        DisconnectAction::Continue => None,
+        DisconnectAction::Cancel => Some(Duration::from_secs(policy.grace_seconds)),
+    }
+}
+
+fn cancelled(tool: &str, why: &str) -> ErrorData {
+    ErrorData::new(
+        ErrorCode::INTERNAL_ERROR,
+        format!("{} was stopped: {}", tool, why),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grace_period_follows_the_action() {
+        let policy: DisconnectPolicy =
+            serde_json::from_str(r#"{ "action": "cancel", "grace_seconds": 30 }"#).unwrap();
+        assert_eq!(grace_period(&policy), Some(Duration::from_secs(30)));
+        assert_eq!(grace_period(&DisconnectPolicy::default()), None);
+    }
+}
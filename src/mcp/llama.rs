@@ -0,0 +1,186 @@
+//! Transcript summaries and suggested titles from a local llama.cpp model,
+//! for clients that can't take MCP sampling requests.
+//!
+//! llama.cpp's CLI is run like ffmpeg and yt-dlp rather than linked in:
+//! llama.cpp and whisper.cpp each bundle their own ggml, and the two clash
+//! in one binary. The prompt and answer handling are shared with
+//! [`super::sampling`].
+
+use anyhow::{Context, Result};
+use async_process::{Command, Stdio};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::sampling::{
+    MAX_TOKENS, SUGGEST_MAX_TOKENS, SUGGEST_PROMPT, SYSTEM_PROMPT, finish, parse_suggestion,
+    user_prompt,
+};
+use crate::config::LocalSummarizer;
+use crate::transcriber::types::{Segment, Suggestion, VideoMetadata};
+use crate::utils::paths::get_models_dir;
+
+/// Tokens kept free for the instructions and the title lines.
+const PROMPT_OVERHEAD_TOKENS: u32 = 512;
+
+/// Summarize a transcript with the configured model.
+pub async fn summarize(
+    config: &LocalSummarizer,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> Result<String> {
+    let answer = complete(config, SYSTEM_PROMPT, MAX_TOKENS, metadata, segments).await?;
+    finish(&answer.text, metadata, answer.cut_at, &answer.model)
+}
+
+/// Suggest a title, description and tags with the configured model.
+pub async fn suggest(
+    config: &LocalSummarizer,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> Result<Suggestion> {
+    let answer = complete(
+        config,
+        SUGGEST_PROMPT,
+        SUGGEST_MAX_TOKENS,
+        metadata,
+        segments,
+    )
+    .await?;
+    parse_suggestion(&answer.text, &answer.model)
+}
+
+struct Answer {
+    text: String,
+    /// Where the transcript was cut to fit the context, if it was.
+    cut_at: Option<u64>,
+    /// The model file's name, as `<name> (local)`.
+    model: String,
+}
+
+/// Run the model on the transcript with `system_prompt`, answering in at
+/// most `max_tokens`.
+async fn complete(
+    config: &LocalSummarizer,
+    system_prompt: &str,
+    max_tokens: u32,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> Result<Answer> {
+    let model = model_path(&get_models_dir(), &config.model_path)?;
+    // About three characters per token, leaving room for the answer.
+    let budget = config
+        .context_tokens
+        .saturating_sub(max_tokens + PROMPT_OVERHEAD_TOKENS) as usize
+        * 3;
+    let (prompt, cut_at) = user_prompt(metadata, segments, budget)?;
+
+    // Long transcripts don't fit in one command-line argument.
+    let mut prompt_file = tempfile::NamedTempFile::new()?;
+    prompt_file.write_all(prompt.as_bytes())?;
+
+    info!("🦙 Running {}", model.display());
+    let output = Command::new(&config.binary)
+        .arg("-m")
+        .arg(&model)
+        .args([
+            "-c",
+            &config.context_tokens.to_string(),
+            "-n",
+            &max_tokens.to_string(),
+            "--temp",
+            "0.2",
+            "-sys",
+            system_prompt,
+            "--single-turn",
+            "--no-display-prompt",
+            "-f",
+        ])
+        .arg(prompt_file.path())
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}. Is llama.cpp installed?", config.binary))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(5).collect();
+        anyhow::bail!(
+            "{} failed: {}",
+            config.binary,
+            tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+        );
+    }
+
+    let name = model
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(Answer {
+        text: clean_output(&String::from_utf8_lossy(&output.stdout)),
+        cut_at,
+        model: format!("{} (local)", name),
+    })
+}
+
+/// The configured model file, resolved like a custom Whisper `model_path`:
+/// absolute, or relative to the models directory.
+fn model_path(models_dir: &Path, configured: &str) -> Result<PathBuf> {
+    let path = Path::new(configured);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        models_dir.join(path)
+    };
+    if !path.is_file() {
+        anyhow::bail!("Summarizer model not found: {}", path.display());
+    }
+    let mut magic = [0u8; 4];
+    std::fs::File::open(&path)
+        .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut magic))
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    if &magic != b"GGUF" {
+        anyhow::bail!("{} is not a GGUF model", path.display());
+    }
+    Ok(path)
+}
+
+/// The answer alone, without the end-of-text marker and the `>` prompt
+/// the CLI prints around it.
+fn clean_output(raw: &str) -> String {
+    raw.replace("[end of text]", "")
+        .lines()
+        .filter(|line| line.trim() != ">")
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_and_checks_gguf_models() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("llm.gguf"), b"GGUF\x03\x00\x00\x00").unwrap();
+        std::fs::write(dir.path().join("ggml-base.bin"), b"lmgg").unwrap();
+
+        assert_eq!(
+            model_path(dir.path(), "llm.gguf").unwrap(),
+            dir.path().join("llm.gguf")
+        );
+        let absolute = dir.path().join("llm.gguf");
+        assert!(model_path(Path::new("/nowhere"), absolute.to_str().unwrap()).is_ok());
+        assert!(model_path(dir.path(), "ggml-base.bin").is_err());
+        assert!(model_path(dir.path(), "missing.gguf").is_err());
+    }
+
+    #[test]
+    fn strips_cli_decoration() {
+        assert_eq!(
+            clean_output("## Summary\n- Point\n\n> \n [end of text]\n"),
+            "## Summary\n- Point"
+        );
+    }
+}
@@ -1,3 +1,10 @@
+pub mod disconnect;
+#[cfg(feature = "llama")]
+pub mod llama;
+pub mod policy;
+pub mod queue;
+pub mod sampling;
 pub mod server_rmcp;
+pub mod sessions;
 
 pub use server_rmcp::VideoTranscriberServer;
@@ -0,0 +1,71 @@
+//! Per-key tool access for the HTTP MCP endpoint.
+//!
+//! Keys and their tool allowlists come from `api_keys` in the config file.
+//! [`require_api_key`] turns away requests without a known key before they
+//! reach the MCP service. The server then looks the key up again from the
+//! request headers that rmcp passes along, and uses it to filter
+//! `tools/list` and to gate `tools/call`. With no keys configured, or over
+//! stdio, every tool is available.
+
+use axum::{
+    Json,
+    extract::Request,
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION, request::Parts},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rmcp::{
+    model::{ErrorCode, ErrorData},
+    service::{RequestContext, RoleServer},
+};
+use serde_json::json;
+use tracing::warn;
+
+use crate::config::{ApiKey, Config};
+
+/// Axum middleware for `/mcp` and `/files`: 401 unless the request carries a configured
+/// key, when any are configured.
+pub async fn require_api_key(req: Request, next: Next) -> Response {
+    let config = Config::load();
+    if config.api_keys.is_empty() || key_for(&config, req.headers()).is_some() {
+        return next.run(req).await;
+    }
+    warn!("Rejected MCP request with a missing or unknown API key");
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "error": "missing or unknown API key" })),
+    )
+        .into_response()
+}
+
+/// Key a tool request was made with. `Ok(None)` means no policy applies.
+pub fn request_key(context: &RequestContext<RoleServer>) -> Result<Option<ApiKey>, ErrorData> {
+    let Some(parts) = context.extensions.get::<Parts>() else {
+        return Ok(None);
+    };
+    // The middleware already checked the key, but the config may have
+    // changed since the session started.
+    headers_key(&parts.headers)
+        .map_err(|msg| ErrorData::new(ErrorCode::INVALID_REQUEST, msg.to_string(), None))
+}
+
+/// Key carried by plain HTTP request headers, for routes outside `/mcp`.
+/// `Ok(None)` means no keys are configured.
+pub fn headers_key(headers: &HeaderMap) -> Result<Option<ApiKey>, &'static str> {
+    let config = Config::load();
+    if config.api_keys.is_empty() {
+        return Ok(None);
+    }
+    key_for(&config, headers)
+        .cloned()
+        .map(Some)
+        .ok_or("Missing or unknown API key")
+}
+
+fn key_for<'a>(config: &'a Config, headers: &HeaderMap) -> Option<&'a ApiKey> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::auth::extract_bearer_token)?;
+    config.api_key(token)
+}
@@ -0,0 +1,414 @@
+//! The transcription slots, and where a waiting call stands in line for one.
+//!
+//! One engine serves every session, `admission.max_concurrent_jobs` jobs at
+//! a time (one by default). A call that finds them all busy would otherwise
+//! sit silent until its turn, which a client can't tell apart from a hung
+//! server. While it waits, a call that asked for progress
+//! (`_meta.progressToken`) gets a notification every
+//! [`PROGRESS_INTERVAL`] like `queued, position 2, est. start in 12 min`.
+//! The estimate is the mean of the last few job durations, handed out to
+//! the slots as they free up, less what running jobs have already used.
+//!
+//! With `admission.max_large_model_hours` in the config file, a new
+//! large-model job is turned away while that much large-model audio is
+//! already queued or running, rather than left to wait behind it.
+
+use rmcp::{
+    model::{ErrorCode, ErrorData, ProgressNotificationParam},
+    service::{RequestContext, RoleServer},
+};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::config::{AdmissionPolicy, Config};
+use crate::metrics;
+use crate::transcriber::{TranscriberEngine, WhisperModel};
+
+/// How often a waiting call hears where it is.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Finished jobs kept for the start-time estimate.
+const HISTORY: usize = 10;
+
+pub struct JobQueue {
+    engine: TranscriberEngine,
+    // tokio's semaphore hands out permits in the order they were asked
+    // for, so the ticket line below matches the order calls actually start in.
+    slots: Semaphore,
+    line: std::sync::Mutex<Line>,
+}
+
+/// What admission needs to know about a job.
+#[derive(Debug, Clone, Copy)]
+pub struct Job {
+    pub model: WhisperModel,
+    /// Audio length, when known up front.
+    pub audio_secs: Option<u64>,
+}
+
+#[derive(Default)]
+struct Line {
+    /// How many jobs may run at once; 0 counts as 1.
+    slots: usize,
+    next_ticket: u64,
+    waiting: VecDeque<(u64, Job)>,
+    running: Vec<(u64, Instant, Job)>,
+    recent: VecDeque<Duration>,
+}
+
+impl Line {
+    /// Mean duration of recent jobs, if any have finished.
+    fn typical_job(&self) -> Option<Duration> {
+        let n = self.recent.len() as u32;
+        (n > 0).then(|| self.recent.iter().sum::<Duration>() / n)
+    }
+
+    /// When a job with `ahead` jobs waiting before it should start.
+    fn eta(&self, ahead: usize, now: Instant) -> Option<Duration> {
+        self.typical_job().map(|job| {
+            // When each slot frees up: idle ones now.
+            let mut free: Vec<Duration> = self
+                .running
+                .iter()
+                .map(|(_, since, _)| job.saturating_sub(now.saturating_duration_since(*since)))
+                .collect();
+            free.resize(free.len().max(self.slots).max(1), Duration::ZERO);
+            // Each job ahead takes the first slot to free up.
+            for _ in 0..ahead {
+                if let Some(first) = free.iter_mut().min() {
+                    *first += job;
+                }
+            }
+            free.into_iter().min().unwrap_or_default()
+        })
+    }
+
+    fn status(&self, ticket: u64, now: Instant) -> Option<String> {
+        let ahead = self.waiting.iter().position(|&(t, _)| t == ticket)?;
+        Some(queued_message(ahead + 1, self.eta(ahead, now)))
+    }
+
+    /// Hours of large-model audio queued or running.
+    fn large_model_hours(&self) -> f64 {
+        let secs: u64 = self
+            .waiting
+            .iter()
+            .map(|(_, job)| job)
+            .chain(self.running.iter().map(|(_, _, job)| job))
+            .filter(|job| job.model.size.is_large())
+            .filter_map(|job| job.audio_secs)
+            .sum();
+        secs as f64 / 3600.0
+    }
+
+    fn admit(&self, job: &Job, policy: &AdmissionPolicy, now: Instant) -> Result<(), ErrorData> {
+        let Some(limit) = policy.max_large_model_hours else {
+            return Ok(());
+        };
+        let queued = self.large_model_hours();
+        if !job.model.size.is_large() || queued < limit {
+            return Ok(());
+        }
+        let eta = self.eta(self.waiting.len(), now);
+        let mut message = format!(
+            "Server overloaded: {:.1} h of large-model audio already queued (limit {} h). \
+             Retry with a smaller model, or later",
+            queued, limit
+        );
+        if let Some(eta) = eta {
+            message.push_str(&format!(" (est. {} min)", minutes(eta)));
+        }
+        Err(ErrorData::new(
+            ErrorCode::INTERNAL_ERROR,
+            message,
+            Some(json!({
+                "reason": "overloaded",
+                "queued_large_model_hours": queued,
+                "limit_hours": limit,
+                "retry_after_secs": eta.map(|eta| eta.as_secs()),
+            })),
+        ))
+    }
+}
+
+fn minutes(d: Duration) -> u64 {
+    d.as_secs().div_ceil(60).max(1)
+}
+
+fn queued_message(position: usize, eta: Option<Duration>) -> String {
+    match eta {
+        Some(eta) => format!(
+            "queued, position {}, est. start in {} min",
+            position,
+            minutes(eta)
+        ),
+        None => format!("queued, position {}", position),
+    }
+}
+
+/// The engine, held for the length of one job.
+pub struct Slot<'a> {
+    _turn: SemaphorePermit<'a>,
+    queue: &'a JobQueue,
+    ticket: u64,
+    started: Instant,
+}
+
+impl Deref for Slot<'_> {
+    type Target = TranscriberEngine;
+
+    fn deref(&self) -> &TranscriberEngine {
+        &self.queue.engine
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let mut line = self.queue.line();
+        line.running.retain(|&(t, _, _)| t != self.ticket);
+        metrics::global().job_running(false);
+        line.recent.push_back(self.started.elapsed());
+        if line.recent.len() > HISTORY {
+            line.recent.pop_front();
+        }
+    }
+}
+
+/// Takes a ticket out of the line if its call gives up waiting.
+struct Ticket<'a> {
+    queue: &'a JobQueue,
+    id: u64,
+    job: Job,
+}
+
+/// Where a call lands on joining the line.
+enum Entry<'a> {
+    Started(Slot<'a>),
+    Waiting(Ticket<'a>),
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        self.queue.line().waiting.retain(|&(t, _)| t != self.id);
+        metrics::global().job_dequeued();
+    }
+}
+
+impl JobQueue {
+    pub fn shared() -> Arc<Self> {
+        let slots = Config::load().admission.concurrent_jobs();
+        Arc::new(Self {
+            engine: TranscriberEngine::new(),
+            slots: Semaphore::new(slots),
+            line: std::sync::Mutex::new(Line {
+                slots,
+                ..Default::default()
+            }),
+        })
+    }
+
+    fn line(&self) -> std::sync::MutexGuard<'_, Line> {
+        self.line.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The engine for a quick call that doesn't count as a job.
+    pub fn engine(&self) -> &TranscriberEngine {
+        &self.engine
+    }
+
+    /// Describe a job on `url`. Its length is only probed when admission
+    /// control would count it.
+    pub async fn job(&self, model: WhisperModel, url: &str) -> Job {
+        let counted =
+            model.size.is_large() && Config::load().admission.max_large_model_hours.is_some();
+        let audio_secs = if counted {
+            self.engine.probe_duration(url).await.ok()
+        } else {
+            None
+        };
+        Job { model, audio_secs }
+    }
+
+    /// Wait for a slot, telling the client where it stands meanwhile.
+    /// Fails at once if admission control turns `job` away.
+    pub async fn acquire(
+        &self,
+        context: &RequestContext<RoleServer>,
+        job: Job,
+    ) -> Result<Slot<'_>, ErrorData> {
+        match self.enter(job)? {
+            Entry::Started(slot) => Ok(slot),
+            Entry::Waiting(ticket) => {
+                let turn = self.wait(context, &ticket).await;
+                Ok(self.start(ticket, turn))
+            }
+        }
+    }
+
+    /// Wait for a slot without progress notifications, for the REST API.
+    pub async fn acquire_quietly(&self, job: Job) -> Result<Slot<'_>, ErrorData> {
+        match self.enter(job)? {
+            Entry::Started(slot) => Ok(slot),
+            Entry::Waiting(ticket) => {
+                let turn = self
+                    .slots
+                    .acquire()
+                    .await
+                    .expect("the job semaphore is never closed");
+                Ok(self.start(ticket, turn))
+            }
+        }
+    }
+
+    /// Take a free slot, or a place in line.
+    fn enter(&self, job: Job) -> Result<Entry<'_>, ErrorData> {
+        let policy = Config::load().admission;
+        let mut line = self.line();
+        line.admit(&job, &policy, Instant::now())?;
+        let id = line.next_ticket;
+        line.next_ticket += 1;
+        if let Ok(turn) = self.slots.try_acquire() {
+            return Ok(Entry::Started(self.slot_for(&mut line, id, job, turn)));
+        }
+        line.waiting.push_back((id, job));
+        metrics::global().job_queued();
+        Ok(Entry::Waiting(Ticket {
+            queue: self,
+            id,
+            job,
+        }))
+    }
+
+    fn start<'a>(&'a self, ticket: Ticket<'a>, turn: SemaphorePermit<'a>) -> Slot<'a> {
+        let (id, job) = (ticket.id, ticket.job);
+        drop(ticket);
+        self.slot_for(&mut self.line(), id, job, turn)
+    }
+
+    fn slot_for<'a>(
+        &'a self,
+        line: &mut Line,
+        ticket: u64,
+        job: Job,
+        turn: SemaphorePermit<'a>,
+    ) -> Slot<'a> {
+        line.running.push((ticket, Instant::now(), job));
+        metrics::global().job_running(true);
+        Slot {
+            _turn: turn,
+            queue: self,
+            ticket,
+            started: Instant::now(),
+        }
+    }
+
+    async fn wait(
+        &self,
+        context: &RequestContext<RoleServer>,
+        ticket: &Ticket<'_>,
+    ) -> SemaphorePermit<'_> {
+        let token = context.meta.get_progress_token();
+        let waiting_since = Instant::now();
+        let mut ticks = tokio::time::interval(PROGRESS_INTERVAL);
+        let turn = self.slots.acquire();
+        tokio::pin!(turn);
+        loop {
+            tokio::select! {
+                turn = &mut turn => return turn.expect("the job semaphore is never closed"),
+                _ = ticks.tick() => {
+                    let Some(token) = token.clone() else { continue };
+                    let Some(message) = self.line().status(ticket.id, Instant::now()) else {
+                        continue;
+                    };
+                    // Seconds spent waiting: always increasing, no total.
+                    let progress = waiting_since.elapsed().as_secs_f64();
+                    let _ = context
+                        .peer
+                        .notify_progress(
+                            ProgressNotificationParam::new(token, progress).with_message(message),
+                        )
+                        .await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(model: &str, audio_secs: u64) -> Job {
+        Job {
+            model: model.parse().unwrap(),
+            audio_secs: Some(audio_secs),
+        }
+    }
+
+    #[test]
+    fn estimates_start_from_recent_jobs() {
+        let now = Instant::now();
+        let mut line = Line {
+            waiting: [7, 8, 9]
+                .into_iter()
+                .map(|t| (t, job("base", 60)))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(line.status(8, now).as_deref(), Some("queued, position 2"));
+        assert_eq!(line.status(1, now), None);
+
+        line.recent = VecDeque::from([Duration::from_secs(240), Duration::from_secs(360)]);
+        line.running = vec![(1, now - Duration::from_secs(120), job("base", 60))];
+        // 3 min left on the running job, plus two 5-minute jobs ahead.
+        assert_eq!(
+            line.status(9, now).as_deref(),
+            Some("queued, position 3, est. start in 13 min")
+        );
+        line.running = vec![(1, now - Duration::from_secs(900), job("base", 60))];
+        assert_eq!(
+            line.status(7, now).as_deref(),
+            Some("queued, position 1, est. start in 1 min")
+        );
+
+        // Two slots: the jobs ahead split between them.
+        line.slots = 2;
+        line.running = vec![
+            (1, now - Duration::from_secs(120), job("base", 60)),
+            (2, now - Duration::from_secs(240), job("base", 60)),
+        ];
+        assert_eq!(
+            line.status(9, now).as_deref(),
+            Some("queued, position 3, est. start in 6 min")
+        );
+    }
+
+    #[test]
+    fn turns_away_large_model_jobs_over_the_limit() {
+        let now = Instant::now();
+        let policy = AdmissionPolicy {
+            max_large_model_hours: Some(2.0),
+            ..Default::default()
+        };
+        let mut line = Line {
+            running: vec![(0, now, job("large-v3", 3600))],
+            waiting: VecDeque::from([(1, job("medium", 7200)), (2, job("large-v3-q5_0", 1800))]),
+            ..Default::default()
+        };
+        assert_eq!(line.large_model_hours(), 1.5);
+        assert!(line.admit(&job("large-v3", 600), &policy, now).is_ok());
+
+        line.waiting.push_back((3, job("large-v2", 1800)));
+        let err = line.admit(&job("large-v3", 600), &policy, now).unwrap_err();
+        assert_eq!(err.data.unwrap()["reason"], "overloaded");
+        assert!(line.admit(&job("small", 600), &policy, now).is_ok());
+        assert!(
+            line.admit(&job("large-v3", 600), &AdmissionPolicy::default(), now)
+                .is_ok()
+        );
+    }
+}
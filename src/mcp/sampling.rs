@@ -0,0 +1,335 @@
+//! Transcript summaries and suggested titles written by the connected
+//! client's LLM.
+//!
+//! MCP sampling (`sampling/createMessage`) lets the server ask the client
+//! for a completion, so summaries need no model or API key on the server.
+//! The client picks the model and may show the request to the user before
+//! running it. The transcript goes out as `[MM:SS]` lines, and the markers
+//! the model quotes back are turned into links to the moment in the video.
+//! Suggestions (title, description, tags) are asked for as a JSON object.
+//! The local llama.cpp summarizer (`llama` feature) reuses the same prompts
+//! and answer handling.
+
+use anyhow::{Context, Result};
+use rmcp::{
+    model::{CreateMessageRequestParams, Role, SamplingMessage, SamplingMessageContent},
+    service::{Peer, RoleServer},
+};
+
+use crate::transcriber::links;
+use crate::transcriber::outputs::marker;
+use crate::transcriber::types::{Segment, Suggestion, VideoMetadata, parse_time};
+
+/// Transcript text sent to the client at most. Longer transcripts are cut
+/// and the summary says where.
+const MAX_TRANSCRIPT_CHARS: usize = 120_000;
+
+/// Segments are merged into lines covering about this long, so the prompt
+/// carries a marker every half minute rather than every few seconds.
+const LINE_MS: u64 = 30_000;
+
+/// Longest summary asked for, in tokens.
+pub(super) const MAX_TOKENS: u32 = 1_500;
+
+pub(super) const SYSTEM_PROMPT: &str = "You summarize video transcripts. Answer in the transcript's language, \
+in Markdown with exactly two sections:\n\
+## Summary\n\
+5 to 10 bullet points covering the main points in order.\n\
+## Key quotes\n\
+3 to 5 bullets, each `[MM:SS] \"exact words\"`, quoting the transcript verbatim \
+and copying the marker of the line the quote comes from.\n\
+Do not add anything else.";
+
+/// Longest suggestion asked for, in tokens.
+pub(super) const SUGGEST_MAX_TOKENS: u32 = 600;
+
+pub(super) const SUGGEST_PROMPT: &str = "You write publishing metadata for videos from their transcripts. \
+Answer in the transcript's language with one JSON object and nothing else:\n\
+{\"title\": \"...\", \"description\": \"...\", \"tags\": [\"...\"]}\n\
+title: an accurate, engaging title of at most 70 characters.\n\
+description: one paragraph of 2 to 4 sentences on what the video covers.\n\
+tags: 5 to 10 short keywords a viewer would search for.";
+
+/// Tags kept from a suggestion.
+const MAX_TAGS: usize = 15;
+
+/// Whether the client declared the sampling capability when it connected.
+pub fn is_supported(peer: &Peer<RoleServer>) -> bool {
+    peer.peer_info()
+        .is_some_and(|info| info.capabilities.sampling.is_some())
+}
+
+/// Ask the client's LLM to summarize a transcript. Returns the summary
+/// with its quote markers linked where the platform supports it.
+pub async fn summarize(
+    peer: &Peer<RoleServer>,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> Result<String> {
+    let (prompt, cut_at) = user_prompt(metadata, segments, MAX_TRANSCRIPT_CHARS)?;
+    let (text, model) = complete(peer, SYSTEM_PROMPT, prompt, MAX_TOKENS).await?;
+    finish(&text, metadata, cut_at, &model)
+}
+
+/// Ask the client's LLM for a title, description and tags.
+pub async fn suggest(
+    peer: &Peer<RoleServer>,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> Result<Suggestion> {
+    let (prompt, _) = user_prompt(metadata, segments, MAX_TRANSCRIPT_CHARS)?;
+    let (text, model) = complete(peer, SUGGEST_PROMPT, prompt, SUGGEST_MAX_TOKENS).await?;
+    parse_suggestion(&text, &model)
+}
+
+/// One sampling request. Returns the answer's text and the model, as
+/// `<model> via the client`.
+async fn complete(
+    peer: &Peer<RoleServer>,
+    system_prompt: &str,
+    prompt: String,
+    max_tokens: u32,
+) -> Result<(String, String)> {
+    let params = CreateMessageRequestParams::new(
+        vec![SamplingMessage::new(
+            Role::User,
+            SamplingMessageContent::text(prompt),
+        )],
+        max_tokens,
+    )
+    .with_system_prompt(system_prompt);
+
+    let result = peer
+        .create_message(params)
+        .await
+        .context("The client's sampling request failed")?;
+    let text = result
+        .message
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            SamplingMessageContent::Text(t) => Some(t.text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok((text, format!("{} via the client", result.model)))
+}
+
+/// The request to summarize, with the transcript cut to `max_chars`. Also
+/// returns where it was cut, if it was.
+pub(super) fn user_prompt(
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+    max_chars: usize,
+) -> Result<(String, Option<u64>)> {
+    let (transcript, cut_at) = transcript_lines(segments, max_chars);
+    if transcript.is_empty() {
+        anyhow::bail!("The transcript has no text to summarize");
+    }
+    let prompt = format!(
+        "Title: {}\nChannel: {}\n\nTranscript:\n{}",
+        metadata.title, metadata.channel, transcript
+    );
+    Ok((prompt, cut_at))
+}
+
+/// The model's answer as shown to the user: markers linked, plus notes on
+/// a cut transcript and on which model wrote it.
+pub(super) fn finish(
+    text: &str,
+    metadata: &VideoMetadata,
+    cut_at: Option<u64>,
+    model: &str,
+) -> Result<String> {
+    if text.trim().is_empty() {
+        anyhow::bail!("The model ({}) returned no text", model);
+    }
+    let mut summary = link_markers(text.trim(), metadata);
+    if let Some(ms) = cut_at {
+        summary.push_str(&format!(
+            "\n\n_The transcript was too long to send in full; this covers up to {}._",
+            marker(ms)
+        ));
+    }
+    summary.push_str(&format!("\n\n_Summarized by {}._", model));
+    Ok(summary)
+}
+
+/// The suggestion in a model's answer to [`SUGGEST_PROMPT`]. Tolerates a
+/// code fence or chatter around the JSON object; tags are trimmed of `#`
+/// and deduplicated.
+pub(super) fn parse_suggestion(text: &str, model: &str) -> Result<Suggestion> {
+    #[derive(serde::Deserialize)]
+    struct Answer {
+        title: String,
+        #[serde(default)]
+        description: String,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    let object = text
+        .find('{')
+        .zip(text.rfind('}'))
+        .filter(|(open, close)| open < close)
+        .map(|(open, close)| &text[open..=close])
+        .with_context(|| format!("The model ({}) didn't answer with a JSON object", model))?;
+    let answer: Answer = serde_json::from_str(object)
+        .with_context(|| format!("The model ({}) answered with malformed JSON", model))?;
+    let title = answer.title.trim();
+    if title.is_empty() {
+        anyhow::bail!("The model ({}) suggested no title", model);
+    }
+    let mut tags: Vec<String> = Vec::new();
+    for tag in &answer.tags {
+        let tag = tag.trim().trim_start_matches('#').trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags.truncate(MAX_TAGS);
+    Ok(Suggestion {
+        title: title.to_string(),
+        description: answer.description.trim().to_string(),
+        tags,
+        model: model.to_string(),
+    })
+}
+
+/// The transcript as `[MM:SS] text` lines of about [`LINE_MS`] each, cut
+/// to `max_chars`. Also returns where it was cut, if it was.
+fn transcript_lines(segments: &[Segment], max_chars: usize) -> (String, Option<u64>) {
+    let mut lines: Vec<(u64, String)> = Vec::new();
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match lines.last_mut() {
+            Some((start, line)) if segment.start_ms < *start + LINE_MS => {
+                line.push(' ');
+                line.push_str(text);
+            }
+            _ => lines.push((segment.start_ms, text.to_string())),
+        }
+    }
+
+    let mut out = String::new();
+    for (start, line) in lines {
+        let line = format!("[{}] {}\n", marker(start), line);
+        if out.len() + line.len() > max_chars {
+            return (out, Some(start));
+        }
+        out.push_str(&line);
+    }
+    (out, None)
+}
+
+/// Turn each `[MM:SS]` or `[H:MM:SS]` marker in `text` into a Markdown
+/// link to that moment, when the platform has links.
+fn link_markers(text: &str, metadata: &VideoMetadata) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let link = after.find(']').and_then(|close| {
+            let stamp = &after[..close];
+            let is_marker = stamp.contains(':')
+                && stamp.chars().all(|c| c.is_ascii_digit() || c == ':')
+                && !after[close + 1..].starts_with('(');
+            let ms = parse_time(stamp).ok().filter(|_| is_marker)? * 1000.0;
+            let url = links::at(metadata, ms as u64)?;
+            Some((close, format!("[{}]({})", stamp, url)))
+        });
+        match link {
+            Some((close, linked)) => {
+                out.push_str(&linked);
+                rest = &after[close + 1..];
+            }
+            None => {
+                out.push('[');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms: start_ms + 5_000,
+            text: format!(" {}", text),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merges_segments_into_half_minute_lines() {
+        let segments = [
+            segment(0, "Welcome."),
+            segment(10_000, "Today: Rust."),
+            segment(31_000, "First, ownership."),
+        ];
+        let (text, cut) = transcript_lines(&segments, 1_000);
+        assert_eq!(
+            text,
+            "[00:00] Welcome. Today: Rust.\n[00:31] First, ownership.\n"
+        );
+        assert_eq!(cut, None);
+
+        let (text, cut) = transcript_lines(&segments, 40);
+        assert_eq!(text, "[00:00] Welcome. Today: Rust.\n");
+        assert_eq!(cut, Some(31_000));
+    }
+
+    #[test]
+    fn links_quote_markers() {
+        let metadata = VideoMetadata {
+            video_id: "abc123".to_string(),
+            title: "Talk".to_string(),
+            channel: "Chan".to_string(),
+            duration: 120,
+            upload_date: "20240501".to_string(),
+            platform: "YouTube".to_string(),
+            url: "https://youtu.be/abc123".to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        };
+        assert_eq!(
+            link_markers("- [01:05] \"Hi\" [note] [1:00:00](x)", &metadata),
+            "- [01:05](https://www.youtube.com/watch?v=abc123&t=65s) \"Hi\" [note] [1:00:00](x)"
+        );
+        let local = VideoMetadata {
+            platform: "Local File".to_string(),
+            ..metadata
+        };
+        assert_eq!(link_markers("[01:05] Hi", &local), "[01:05] Hi");
+    }
+
+    #[test]
+    fn parses_suggestions_around_chatter() {
+        let answer = "Sure!\n```json\n{\"title\": \" Rust in 10 Minutes \", \
+                      \"description\": \"A tour.\", \"tags\": [\"#rust\", \"Rust\", \" \", \"ownership\"]}\n```";
+        assert_eq!(
+            parse_suggestion(answer, "m").unwrap(),
+            Suggestion {
+                title: "Rust in 10 Minutes".to_string(),
+                description: "A tour.".to_string(),
+                tags: vec!["rust".to_string(), "ownership".to_string()],
+                model: "m".to_string(),
+            }
+        );
+        assert!(parse_suggestion("No idea.", "m").is_err());
+        assert!(parse_suggestion("{\"title\": \"\"}", "m").is_err());
+    }
+}
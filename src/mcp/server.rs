@@ -183,7 +183,7 @@ impl McpServer {
         let model = args["model"]
             .as_str()
             .and_then(|s| s.parse::<WhisperModel>().ok())
-            .unwrap_or(WhisperModel::Base);
+            .unwrap_or_default();
 
         let language = args["language"]
             .as_str()
@@ -2,19 +2,64 @@ use anyhow::Result;
 use rmcp::{
     ServerHandler,
     model::*,
-    service::{RequestContext, RoleServer},
+    service::{Peer, RequestContext, RoleServer},
 };
 use serde_json::json;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tracing::info;
-
-use crate::transcriber::{TranscriberEngine, TranscriptionOptions, WhisperModel};
-use crate::utils::paths::get_default_output_dir;
+use tracing::{info, warn};
+
+#[cfg(feature = "llama")]
+use super::llama;
+use super::queue::{Job, JobQueue};
+use super::{disconnect, policy, sampling};
+use crate::config::{ApiKey, Config};
+use crate::metrics;
+use crate::transcriber::audio;
+use crate::transcriber::batch::{self, BatchItemStatus, BatchOptions, BatchReport};
+use crate::transcriber::downloader::{self, AUDIO_DIR_PREFIX, Feed};
+use crate::transcriber::glossary::Glossary;
+use crate::transcriber::grammar::Grammar;
+use crate::transcriber::library::{Entry as LibraryEntry, Library};
+use crate::transcriber::links;
+use crate::transcriber::outputs;
+use crate::transcriber::query::{self, Query};
+use crate::transcriber::redact;
+use crate::transcriber::refresh::{self, Refreshed};
+use crate::transcriber::search;
+use crate::transcriber::semantic;
+use crate::transcriber::store;
+use crate::transcriber::topics;
+use crate::transcriber::types::{
+    AudioTrackSelection, ClipRange, DecodingOptions, Device, IfExists, MdTimestamps, OutputFiles,
+    OutputFormat, Segment, SpeakerTurns, Suggestion, TranscriptionResult, VideoMetadata,
+};
+use crate::transcriber::webhook;
+use crate::transcriber::{TranscriptionOptions, WhisperModel};
+use crate::utils::encryption;
+use crate::utils::paths::{get_default_output_dir, remove_emptied_dirs};
+
+/// Tools that transcribe or delete. Hidden and rejected in read-only mode.
+const WRITE_TOOLS: &[&str] = &[
+    "transcribe_video",
+    "transcribe_batch",
+    "transcribe_directory",
+    "transcribe_podcast",
+    "retry_failed",
+    "redact_range",
+    "suggest_metadata",
+    "refresh_metadata",
+    "delete_transcript",
+    "cleanup_old_transcripts",
+    "delete_all_transcripts",
+];
 
 #[derive(Clone)]
 pub struct VideoTranscriberServer {
-    transcriber: Arc<Mutex<TranscriberEngine>>,
+    transcriber: Arc<JobQueue>,
+    /// Only expose listing / reading tools — for serving an existing
+    /// transcript archive to people who shouldn't run or delete anything.
+    read_only: bool,
 }
 
 impl Default for VideoTranscriberServer {
@@ -25,10 +70,26 @@ impl Default for VideoTranscriberServer {
 
 impl VideoTranscriberServer {
     pub fn new() -> Self {
+        Self::with_queue(JobQueue::shared())
+    }
+
+    /// A server whose jobs wait in `queue`, shared with other sessions.
+    pub fn with_queue(queue: Arc<JobQueue>) -> Self {
         Self {
-            transcriber: Arc::new(Mutex::new(TranscriberEngine::new())),
+            transcriber: queue,
+            read_only: false,
         }
     }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Whether `tool` shows up in `tools/list` for this server and key.
+    fn tool_allowed(&self, tool: &str, key: Option<&ApiKey>) -> bool {
+        !(self.read_only && WRITE_TOOLS.contains(&tool)) && key.is_none_or(|k| k.allows(tool))
+    }
 }
 
 impl ServerHandler for VideoTranscriberServer {
@@ -43,6 +104,14 @@ impl ServerHandler for VideoTranscriberServer {
              Transcribes videos from 1000+ platforms or local files - 6x faster than Python whisper!"
                 .into(),
         );
+        if self.read_only
+            && let Some(instructions) = info.instructions.as_mut()
+        {
+            instructions.push_str(
+                "\nThis server is read-only: transcripts can be listed and read, \
+                 but not created or deleted.",
+            );
+        }
         info.capabilities = ServerCapabilities::builder().enable_tools().build();
         info
     }
@@ -50,166 +119,753 @@ impl ServerHandler for VideoTranscriberServer {
     async fn list_tools(
         &self,
         _request: Option<PaginatedRequestParams>,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, ErrorData> {
-        Ok(ListToolsResult {
-            tools: vec![
-                // rmcp 1.x marked Tool as #[non_exhaustive], so we construct
-                // via Tool::new(name, description, input_schema) instead of a
-                // struct expression. Cleaner anyway — drops a lot of
-                // `..: None` boilerplate per tool.
-                Tool::new(
-                    "transcribe_video",
-                    "Transcribe videos from 1000+ platforms (YouTube, Vimeo, TikTok, Twitter, etc.) or local video files using whisper.cpp (4-10x faster than Python whisper!). Downloads/extracts audio and generates transcript in TXT, JSON, and Markdown formats.",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {
-                                "url": {
-                                    "type": "string",
-                                    "description": "Video URL from any supported platform OR absolute/relative path to a local video file (mp4, avi, mov, mkv, etc.)"
-                                },
-                                "output_dir": {
-                                    "type": "string",
-                                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
-                                },
-                                "model": {
-                                    "type": "string",
-                                    "enum": ["tiny", "base", "small", "medium", "large"],
-                                    "description": "Whisper model to use. Larger models are more accurate but slower. Default: 'base'"
-                                },
-                                "language": {
-                                    "type": "string",
-                                    "description": "Language code (ISO 639-1: en, es, fr, de, etc.) or 'auto' for automatic detection. Default: 'auto'"
-                                }
+        let key = policy::request_key(&context)?;
+        // Every size plus its quantized variants (`medium-q5_0`, …).
+        let model_names: Vec<String> = WhisperModel::all()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        // Split in parts, as one json! literal this long needs a higher
+        // recursion limit.
+        let transcribe_video_properties = merge_objects([
+            json!({
+                "url": {
+                    "type": "string",
+                    "description": "Video URL from any supported platform OR absolute/relative path to a local video file (mp4, avi, mov, mkv, etc.)"
+                },
+                "output_dir": {
+                    "type": "string",
+                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                },
+                "model": {
+                    "type": "string",
+                    "enum": model_names,
+                    "description": "Whisper model to use. Larger models are more accurate but slower; quantized variants (-q5_0, -q5_1, -q8_0) use less memory and run faster on CPU. Default: the platform profile's model if configured, else 'base'"
+                },
+                "model_path": {
+                    "type": "string",
+                    "description": "Optional. Path to a ggml Whisper model file not in the built-in list (fine-tuned, distil-whisper, ...): absolute, or relative to the models directory. Overrides 'model'"
+                },
+                "language": {
+                    "type": "string",
+                    "description": "Language code (ISO 639-1: en, es, fr, de, etc.) or 'auto' for automatic detection. Default: 'auto'"
+                },
+                "chunk_minutes": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional. Split audio longer than this many minutes at silence boundaries and transcribe the chunks in parallel. Speeds up multi-hour videos on many-core machines. Default: single pass"
+                },
+                "gpu": {
+                    "type": "boolean",
+                    "description": "Optional. Run Whisper on the GPU (when the server was built with CUDA/Metal/Vulkan). Default: server setting, normally on"
+                },
+                "device": {
+                    "type": "string",
+                    "description": "Optional. Device to run local Whisper on: 'cpu', or a GPU of the server's backend by index, e.g. 'cuda:0', 'cuda:1', 'vulkan:0', 'metal'. Overrides gpu. Default: a free device from the server's workers.devices list, else the gpu setting"
+                }
+            }),
+            json!({
+                "audio_track": {
+                    "type": "string",
+                    "description": "Optional, local files only. For files with several audio tracks (e.g. OBS recordings with mic and desktop audio on separate tracks): a track number ('1' = first audio track) or 'all' to transcribe each track separately, labelled in the output. 'stereo' transcribes the left and right channels of a two-channel call recording separately and interleaves them as Caller and Agent turns. Default: track 1"
+                },
+                "min_audio_quality": {
+                    "type": "number",
+                    "description": "Optional. Abort before transcribing if the audio quality score (0-100, from noise, clipping and bandwidth checks) is below this. Default: only warn"
+                },
+                "skip_non_speech": {
+                    "type": "boolean",
+                    "description": "Optional. Detect long music / applause stretches and leave them out of the transcript instead of producing '[Music]' lines (useful for concerts, gaming streams). Default: false (regions are still reported)"
+                },
+                "skip_silence": {
+                    "type": "boolean",
+                    "description": "Optional. Cut silences of 2s or more out of the audio before local Whisper (timestamps still match the source), which speeds up lectures and meetings with long pauses. Default: false"
+                },
+                "prefer_captions": {
+                    "type": "boolean",
+                    "description": "Optional, URLs only. Use the platform's own captions (uploaded or auto-generated, e.g. on YouTube) when they exist in the requested or video's language, skipping the audio download and Whisper. Seconds instead of minutes. Default: false"
+                },
+                "beam_size": {
+                    "type": "number",
+                    "description": "Optional. Use beam search with this width (e.g. 5) instead of greedy decoding. More accurate on noisy audio, slower. Default: greedy"
+                },
+                "best_of": {
+                    "type": "number",
+                    "description": "Optional. Candidates sampled per temperature fallback in greedy mode. Default: 1"
+                },
+                "temperature": {
+                    "type": "number",
+                    "description": "Optional. Initial sampling temperature; 0 is deterministic. Default: 0"
+                },
+                "entropy_threshold": {
+                    "type": "number",
+                    "description": "Optional. Re-decode segments whose output entropy exceeds this (catches repetition loops). Default: 2.4"
+                },
+                "no_speech_threshold": {
+                    "type": "number",
+                    "description": "Optional. Probability above which a window is treated as silence. Default: 0.6"
+                },
+                "alternatives": {
+                    "type": "number",
+                    "description": "Optional, local Whisper only. For words Whisper is unsure of (token probability under 0.5), decode their segment this many more times (beam search, then rising temperatures) and list what those decodes heard instead, up to this many per word (max 5). Saved as uncertain_words on the segments in the JSON output, for human review. Slower on unclear audio. Default: off"
+                },
+                "grammar": {
+                    "type": "string",
+                    "description": "Optional, local Whisper only. Regular expression every segment's text must follow, to constrain decoding for spelled-out codes, numeric readouts or a command vocabulary, e.g. '(?i)(yes|no|stop)( (yes|no|stop))*' or '[0-9]+( [0-9]+)*'. Matched from the start of each segment's trimmed text. Default: unconstrained"
+                }
+            }),
+            json!({
+                "start_time": {
+                    "type": ["string", "number"],
+                    "description": "Optional. Transcribe from this point: seconds or '[hh:]mm:ss', e.g. '1:05:00'. Only that part is downloaded (URLs) or extracted (local files); timestamps stay relative to the full video. Default: the beginning"
+                },
+                "end_time": {
+                    "type": ["string", "number"],
+                    "description": "Optional. Stop transcribing at this point: seconds or '[hh:]mm:ss'. Default: the end"
+                },
+                "source_note": {
+                    "type": "string",
+                    "description": "Optional. Free-form note on the basis for recording and transcribing (e.g. 'All participants consented on 2024-05-01', 'Public keynote'). Saved in the JSON metadata and shown in the Markdown output. 'consent' is accepted as an alias"
+                }
+            }),
+            json!({
+                "stream_audio": {
+                    "type": "boolean",
+                    "description": "Optional, URLs only. Pipe the download through ffmpeg straight into local Whisper, which starts on the first chunk (chunk_minutes, default 5) before the download is done. No audio file is kept, and skip_silence, skip_non_speech and min_audio_quality don't apply. Default: false"
+                },
+                "live_from_start": {
+                    "type": "boolean",
+                    "description": "Optional, live streams only. Capture from the stream's beginning, as far back as the platform keeps it, instead of from now. Live streams are always streamed into local Whisper in 1-minute chunks (chunk_minutes) and written to the outputs as they are decoded. Default: false"
+                },
+                "max_live_minutes": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional, live streams only. Stop capturing after this many minutes if the stream hasn't ended. Default: 240"
+                },
+                "summarize": {
+                    "type": "boolean",
+                    "description": "Optional. Append a summary with key quotes, written by your own LLM via MCP sampling (the client may ask you to approve it) or the server's local model. Default: false"
+                },
+                "suggest_metadata": {
+                    "type": "boolean",
+                    "description": "Optional. Also suggest a title, a one-paragraph description and tags for republishing the video, written like summaries and saved in the JSON output as 'suggested'. Default: false"
+                }
+            }),
+            common_option_properties(),
+        ]);
+        let mut tools = vec![
+            // rmcp 1.x marked Tool as #[non_exhaustive], so we construct
+            // via Tool::new(name, description, input_schema) instead of a
+            // struct expression. Cleaner anyway — drops a lot of
+            // `..: None` boilerplate per tool.
+            Tool::new(
+                "transcribe_video",
+                "Transcribe videos from 1000+ platforms (YouTube, Vimeo, TikTok, Twitter, etc.) or local video files using whisper.cpp (4-10x faster than Python whisper!). Downloads/extracts audio and generates transcript in TXT, JSON, and Markdown formats.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": transcribe_video_properties,
+                        "required": ["url"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "transcribe_batch",
+                "Transcribe several videos (URLs or local files) one after another with shared options. Failures don't stop the batch. Returns a batch id whose report records each item's outcome; pass it to retry_failed to re-run only the failed items.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": merge_objects([json!({
+                            "urls": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Video URLs and/or local file paths to transcribe"
                             },
-                            "required": ["url"]
-                        }))
-                        .unwrap(),
-                    ),
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            },
+                            "model": {
+                                "type": "string",
+                                "enum": model_names,
+                                "description": "Whisper model to use for every item. Default: 'base'"
+                            },
+                            "language": {
+                                "type": "string",
+                                "description": "Language code (ISO 639-1) or 'auto'. Default: 'auto'"
+                            },
+                            "schedule": {
+                                "type": "string",
+                                "enum": ["fifo", "shortest-first", "longest-first"],
+                                "description": "Order to run items in. 'shortest-first' probes each source's duration up front so quick results land early. Default: 'fifo' (as listed)"
+                            }
+                        }), common_option_properties()]),
+                        "required": ["urls"]
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "check_dependencies",
-                    "Check if all required dependencies (yt-dlp, ffmpeg, whisper models) are installed",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {}
-                        }))
-                        .unwrap(),
-                    ),
+            ),
+            Tool::new(
+                "transcribe_directory",
+                "Transcribe every video/audio file in a local folder as a batch, skipping files that already have a transcript in the output directory. Returns a per-file summary and a batch id usable with retry_failed.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": merge_objects([json!({
+                            "directory": {
+                                "type": "string",
+                                "description": "Folder to scan for media files"
+                            },
+                            "extensions": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": format!("File extensions to pick up, without the dot. Default: {}", batch::DEFAULT_MEDIA_EXTENSIONS.join(", "))
+                            },
+                            "recursive": {
+                                "type": "boolean",
+                                "description": "Also scan subfolders. Default: false"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            },
+                            "model": {
+                                "type": "string",
+                                "enum": model_names,
+                                "description": "Whisper model to use for every file. Default: 'base'"
+                            },
+                            "language": {
+                                "type": "string",
+                                "description": "Language code (ISO 639-1) or 'auto'. Default: 'auto'"
+                            },
+                            "schedule": {
+                                "type": "string",
+                                "enum": ["fifo", "shortest-first", "longest-first"],
+                                "description": "Order to run files in. Default: 'fifo' (path order)"
+                            }
+                        }), common_option_properties()]),
+                        "required": ["directory"]
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "list_supported_sites",
-                    "List all video platforms supported by yt-dlp (1000+ sites including YouTube, Vimeo, TikTok, Twitter, Facebook, Instagram, educational platforms, and more)",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {}
-                        }))
-                        .unwrap(),
-                    ),
+            ),
+            Tool::new(
+                "transcribe_podcast",
+                "Transcribe episodes of a podcast from its RSS or Atom feed: the latest N, or the ones named. Episode title, publication date and show name go into the transcript metadata. With list_only, just lists the feed's episodes.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": merge_objects([json!({
+                            "feed_url": {
+                                "type": "string",
+                                "description": "URL of the podcast's RSS or Atom feed"
+                            },
+                            "latest": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "description": "Transcribe the newest this many episodes. Ignored when 'episodes' is given. Default: 1"
+                            },
+                            "episodes": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Optional. Episodes to transcribe, each by guid, audio URL or exact title (as shown by list_only)"
+                            },
+                            "list_only": {
+                                "type": "boolean",
+                                "description": "List the feed's episodes without transcribing. Default: false"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            },
+                            "model": {
+                                "type": "string",
+                                "enum": model_names,
+                                "description": "Whisper model to use for every episode. Default: 'base'"
+                            },
+                            "language": {
+                                "type": "string",
+                                "description": "Language code (ISO 639-1) or 'auto'. Default: 'auto'"
+                            },
+                            "source_note": {
+                                "type": "string",
+                                "description": "Optional. Free-form note on the basis for transcribing, saved with every episode. 'consent' is accepted as an alias"
+                            }
+                        }), common_option_properties()]),
+                        "required": ["feed_url"]
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "list_transcripts",
-                    "List all available transcripts in the output directory, sorted by modification time (newest first)",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {
-                                "output_dir": {
-                                    "type": "string",
-                                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
-                                },
-                                "limit": {
-                                    "type": "number",
-                                    "description": "Optional limit on number of transcripts to return (newest first). If not specified, returns all transcripts."
-                                }
+            ),
+            Tool::new(
+                "retry_failed",
+                "Re-run only the failed (or never-reached) items of a previous transcribe_batch run, identified by its batch id. Results are written back into the same batch report. Options default to the batch's original ones, and any given here replace them (e.g. a smaller model or another proxy).",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": merge_objects([json!({
+                            "batch_id": {
+                                "type": "string",
+                                "description": "Batch id returned by transcribe_batch"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Output directory the batch was run with. Defaults to {}", get_default_output_dir().display())
+                            },
+                            "model": {
+                                "type": "string",
+                                "enum": model_names,
+                                "description": "Optional model override for the retried items"
+                            },
+                            "language": {
+                                "type": "string",
+                                "description": "Optional language override for the retried items"
+                            },
+                            "schedule": {
+                                "type": "string",
+                                "enum": ["fifo", "shortest-first", "longest-first"],
+                                "description": "Optional scheduling override. Default: the batch's original policy"
                             }
-                        }))
-                        .unwrap(),
-                    ),
+                        }), common_option_properties()]),
+                        "required": ["batch_id"]
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "get_latest_transcript",
-                    "Get the path and details of the most recently created/modified transcript. Useful to avoid accidentally reading old transcripts.",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {
-                                "output_dir": {
-                                    "type": "string",
-                                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
-                                }
+            ),
+            Tool::new(
+                "check_dependencies",
+                "Check if all required dependencies (yt-dlp, ffmpeg, whisper models) are installed",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {}
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "get_metrics",
+                "Server throughput counters since startup: transcriptions started / completed / failed, audio and processing seconds, bytes downloaded, and jobs waiting or running. The same numbers as the HTTP /metrics endpoint, as JSON",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {}
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "list_supported_sites",
+                "List all video platforms supported by yt-dlp (1000+ sites including YouTube, Vimeo, TikTok, Twitter, Facebook, Instagram, educational platforms, and more)",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {}
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "list_transcripts",
+                "List all available transcripts in the output directory with their title, channel, model, language and word count, newest first",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            },
+                            "limit": {
+                                "type": "number",
+                                "description": "Optional limit on number of transcripts to return (newest first). If not specified, returns all transcripts."
                             }
-                        }))
-                        .unwrap(),
-                    ),
+                        }
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "delete_transcript",
-                    "Delete a specific transcript by video ID. This removes all associated files (txt, json, md).",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {
-                                "video_id": {
-                                    "type": "string",
-                                    "description": "The video ID of the transcript to delete (e.g., 'dQw4w9WgXcQ')"
-                                },
-                                "output_dir": {
-                                    "type": "string",
-                                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
-                                }
+            ),
+            Tool::new(
+                "get_latest_transcript",
+                "Get the path and details of the most recently created/modified transcript. Useful to avoid accidentally reading old transcripts.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "read_transcript",
+                "Read a transcript file (txt, md or json) from the output directory, decrypting it if the server stores transcripts encrypted. Use the paths shown by list_transcripts / get_latest_transcript.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "Path of the transcript file, or its file name within the output directory"
                             },
-                            "required": ["video_id"]
-                        }))
-                        .unwrap(),
-                    ),
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["path"]
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "cleanup_old_transcripts",
-                    "Delete transcripts older than a specified number of days. Helps manage disk space.",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {
-                                "days": {
-                                    "type": "number",
-                                    "description": "Delete transcripts older than this many days (e.g., 30 for month-old transcripts)"
-                                },
-                                "output_dir": {
-                                    "type": "string",
-                                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
-                                }
+            ),
+            Tool::new(
+                "search_in_transcript",
+                "Search one transcript for a word or phrase (case-insensitive) and list each matching segment with its timestamp, to answer questions like 'when do they mention X'. Give the video_id or the path of one of its files.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "query": {
+                                "type": "string",
+                                "description": "Word or phrase to look for. Matched within single segments"
                             },
-                            "required": ["days"]
-                        }))
-                        .unwrap(),
-                    ),
+                            "video_id": {
+                                "type": "string",
+                                "description": "Video ID of the transcript; its newest transcript with a JSON output is searched"
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "Path of a transcript file (txt, md or json), or its file name within the output directory. Used instead of video_id"
+                            },
+                            "limit": {
+                                "type": "number",
+                                "description": "Optional. Most hits to return, earliest first. Default: 50"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["query"]
+                    }))
+                    .unwrap(),
                 ),
-                Tool::new(
-                    "delete_all_transcripts",
-                    "Delete ALL transcripts in the output directory. Use with caution - this cannot be undone!",
-                    Arc::new(
-                        serde_json::from_value(json!({
-                            "type": "object",
-                            "properties": {
-                                "output_dir": {
-                                    "type": "string",
-                                    "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
-                                },
-                                "confirm": {
-                                    "type": "boolean",
-                                    "description": "Must be set to true to confirm deletion of all transcripts"
-                                }
+            ),
+            Tool::new(
+                "search_transcripts",
+                "Search every stored transcript for a word or phrase (case-insensitive), optionally only those from one platform or channel or uploaded in a date range, and list each matching segment with its timestamp and a link to the moment. With export_results the hits are also written to a CSV or Markdown report, for media monitoring.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "query": {
+                                "type": "string",
+                                "description": "Word or phrase to look for. Matched within single segments"
                             },
-                            "required": ["confirm"]
-                        }))
-                        .unwrap(),
-                    ),
+                            "platform": {
+                                "type": "string",
+                                "description": "Optional. Only transcripts from this platform, e.g. 'YouTube' or 'Local File'"
+                            },
+                            "channel": {
+                                "type": "string",
+                                "description": "Optional. Only transcripts whose channel name contains this (case-insensitive)"
+                            },
+                            "since": {
+                                "type": "string",
+                                "description": "Optional. Only videos uploaded on or after this date, YYYY-MM-DD. Transcripts without an upload date (local files) are left out"
+                            },
+                            "until": {
+                                "type": "string",
+                                "description": "Optional. Only videos uploaded on or before this date, YYYY-MM-DD"
+                            },
+                            "limit": {
+                                "type": "number",
+                                "description": "Optional. Most hits to list, newest transcript first. Exports include every hit. Default: 50"
+                            },
+                            "export_results": {
+                                "type": "string",
+                                "enum": ["csv", "md"],
+                                "description": "Optional. Also write all hits, with video links and timestamps, to a report in the output directory's reports/ folder. Not available on a read-only server"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["query"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "query_transcripts",
+                "Filter the transcript library with a small SQL-like query and get the matching transcripts back as structured rows (title, channel, platform, upload date, duration, word count, topics, …), to answer questions like 'which talks from 2024 mention Rust macros' in one call. Example: platform = YouTube AND year = 2024 AND mentions \"rust macros\" ORDER BY words DESC LIMIT 10",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "query": {
+                                "type": "string",
+                                "description": format!("Conditions joined with AND: a field, an operator (= != < <= > >= or ~ for contains) and a value, quoted if it has spaces. Fields: {}. date is YYYY-MM-DD; duration takes seconds, 45m, 1h or 1:30:00. mentions \"phrase\" keeps transcripts that say the phrase. Optionally followed by ORDER BY field [ASC|DESC] and LIMIT n (default {}). Text matches ignore case. Empty lists the newest transcripts", query::Field::NAMES.join(", "), query::DEFAULT_LIMIT)
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["query"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "semantic_search",
+                "Search all transcripts by meaning rather than exact words, e.g. 'where do they discuss hiring junior engineers', and list the most relevant passages with timestamps. Needs an embeddings endpoint in the server's config file. Use search_in_transcript for exact words.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "query": {
+                                "type": "string",
+                                "description": "What to look for, in your own words"
+                            },
+                            "video_id": {
+                                "type": "string",
+                                "description": "Optional. Only search this video's transcripts"
+                            },
+                            "limit": {
+                                "type": "number",
+                                "description": "Optional. Most passages to return, best first. Default: 10"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["query"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "get_transcript_topics",
+                "Browse transcripts by topic. With a video_id or path, list that transcript's main topics (key phrases) with how often and when each first comes up. With topic, list the transcripts about it. With neither, list the most common topics across all transcripts. Topics are extracted from English transcripts only.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_id": {
+                                "type": "string",
+                                "description": "Optional. Video ID of the transcript; its newest transcript with a JSON output is used"
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "Optional. Path of a transcript file (txt, md or json), or its file name within the output directory. Used instead of video_id"
+                            },
+                            "topic": {
+                                "type": "string",
+                                "description": "Optional. List the transcripts with a topic containing this (case-insensitive), newest first"
+                            },
+                            "limit": {
+                                "type": "number",
+                                "description": "Optional. Most topics or transcripts to list. Default: 30"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "summarize_transcript",
+                "Summarize a stored transcript as bullet points plus key quotes with timestamps. The summary is written by the calling client's LLM via MCP sampling, or by the server's local model when one is configured. Give the video_id or the path of one of its files; defaults to the newest transcript.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_id": {
+                                "type": "string",
+                                "description": "Optional. Video ID of the transcript; its newest transcript with a JSON output is summarized"
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "Optional. Path of a transcript file (txt, md or json), or its file name within the output directory. Used instead of video_id"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "suggest_metadata",
+                "Suggest a title, a one-paragraph description and tags for a stored transcript, for creators republishing the content. Written by the calling client's LLM via MCP sampling, or by the server's local model when one is configured, and saved in the transcript's JSON output as 'suggested'; later calls return the saved suggestion. Give the video_id or the path of one of its files; defaults to the newest transcript.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_id": {
+                                "type": "string",
+                                "description": "Optional. Video ID of the transcript; its newest transcript with a JSON output is used"
+                            },
+                            "path": {
+                                "type": "string",
+                                "description": "Optional. Path of a transcript file (txt, md or json), or its file name within the output directory. Used instead of video_id"
+                            },
+                            "regenerate": {
+                                "type": "boolean",
+                                "description": "Optional. Ask for a new suggestion even if one is saved, replacing it. Default: false"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "refresh_metadata",
+                "Fetch the current title, channel, upload date and duration of already transcribed videos and update the index and the JSON and Markdown outputs where they changed, without transcribing again. Fills in upload dates that were missing when a transcript was saved. File names keep their old title. Local files and direct media links are skipped.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_id": {
+                                "type": "string",
+                                "description": "Optional. Refresh only this video's transcripts"
+                            },
+                            "missing_upload_date": {
+                                "type": "boolean",
+                                "description": "Optional. Only transcripts saved without an upload date. Default: false"
+                            },
+                            "limit": {
+                                "type": "number",
+                                "description": format!("Optional. Most transcripts to refresh, newest first; each costs a yt-dlp call. Default: {}", refresh::DEFAULT_LIMIT)
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        }
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "redact_range",
+                "Blank out everything said between two timestamps in a stored transcript: the overlapping segments become [REDACTED] in the txt, json and md files. Optionally also silences that range in an audio file. For removing sensitive material captured by accident.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "path": {
+                                "type": "string",
+                                "description": "Any of the transcript's files (txt, md or json), as a path or file name within the output directory"
+                            },
+                            "start_time": {
+                                "type": ["string", "number"],
+                                "description": "Start of the range: seconds or '[hh:]mm:ss'. Default: the beginning"
+                            },
+                            "end_time": {
+                                "type": ["string", "number"],
+                                "description": "End of the range: seconds or '[hh:]mm:ss'. Default: the end"
+                            },
+                            "audio_path": {
+                                "type": "string",
+                                "description": "Optional. Audio file to silence the same range in, in place. Must be in the output directory or the server's downloaded-audio cache"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["path"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "delete_transcript",
+                "Delete a specific transcript by video ID. This removes all associated files (txt, json, md).",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "video_id": {
+                                "type": "string",
+                                "description": "The video ID of the transcript to delete (e.g., 'dQw4w9WgXcQ')"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["video_id"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+            Tool::new(
+                "cleanup_old_transcripts",
+                "Delete transcripts older than a specified number of days. Helps manage disk space.",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "days": {
+                                "type": "number",
+                                "description": "Delete transcripts older than this many days (e.g., 30 for month-old transcripts)"
+                            },
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            }
+                        },
+                        "required": ["days"]
+                    }))
+                    .unwrap(),
                 ),
-            ],
+            ),
+            Tool::new(
+                "delete_all_transcripts",
+                "Delete ALL transcripts in the output directory. Use with caution - this cannot be undone!",
+                Arc::new(
+                    serde_json::from_value(json!({
+                        "type": "object",
+                        "properties": {
+                            "output_dir": {
+                                "type": "string",
+                                "description": format!("Optional output directory path. Defaults to {}", get_default_output_dir().display())
+                            },
+                            "confirm": {
+                                "type": "boolean",
+                                "description": "Must be set to true to confirm deletion of all transcripts"
+                            }
+                        },
+                        "required": ["confirm"]
+                    }))
+                    .unwrap(),
+                ),
+            ),
+        ];
+        tools.retain(|tool| self.tool_allowed(&tool.name, key.as_ref()));
+        Ok(ListToolsResult {
+            tools,
             next_cursor: None,
             meta: None,
         })
@@ -218,8 +874,28 @@ impl ServerHandler for VideoTranscriberServer {
     async fn call_tool(
         &self,
         request: CallToolRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, ErrorData> {
+        if self.read_only && WRITE_TOOLS.contains(&request.name.as_ref()) {
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_REQUEST,
+                format!(
+                    "Tool {} is not available: this server is read-only",
+                    request.name
+                ),
+                None,
+            ));
+        }
+        if let Some(key) = policy::request_key(&context)?
+            && !key.allows(&request.name)
+        {
+            warn!("API key '{}' may not call {}", key.name, request.name);
+            return Err(ErrorData::new(
+                ErrorCode::INVALID_REQUEST,
+                format!("Tool {} is not allowed for this API key", request.name),
+                None,
+            ));
+        }
         match request.name.as_ref() {
             "transcribe_video" => {
                 let args = request.arguments.as_ref().ok_or_else(|| {
@@ -251,52 +927,288 @@ impl ServerHandler for VideoTranscriberServer {
                 let model = args
                     .get("model")
                     .and_then(|v| v.as_str())
-                    .and_then(|s| s.parse::<WhisperModel>().ok())
-                    .unwrap_or(WhisperModel::Base);
+                    .and_then(|s| s.parse::<WhisperModel>().ok());
+
+                let model_path = args
+                    .get("model_path")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
 
                 let language = args
                     .get("language")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string());
 
-                let options = TranscriptionOptions {
+                let chunk_minutes = args
+                    .get("chunk_minutes")
+                    .filter(|v| !v.is_null())
+                    .map(|v| {
+                        v.as_u64()
+                            .filter(|&n| n >= 1)
+                            .and_then(|n| u32::try_from(n).ok())
+                            .ok_or_else(|| {
+                                ErrorData::new(
+                                    ErrorCode::INVALID_PARAMS,
+                                    format!(
+                                        "chunk_minutes must be a whole number of minutes, got {}",
+                                        v
+                                    ),
+                                    None,
+                                )
+                            })
+                    })
+                    .transpose()?;
+
+                let gpu = args.get("gpu").and_then(|v| v.as_bool());
+
+                let device = args
+                    .get("device")
+                    .and_then(|v| v.as_str())
+                    .map(str::parse::<Device>)
+                    .transpose()
+                    .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+                let audio_track = args
+                    .get("audio_track")
+                    .and_then(|v| {
+                        v.as_u64()
+                            .map(|n| n.to_string())
+                            .or(v.as_str().map(String::from))
+                    })
+                    .map(|s| s.parse::<AudioTrackSelection>())
+                    .transpose()
+                    .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+                let min_audio_quality = args
+                    .get("min_audio_quality")
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n.min(100) as u8);
+
+                let skip_non_speech = args.get("skip_non_speech").and_then(|v| v.as_bool());
+                let skip_silence = args.get("skip_silence").and_then(|v| v.as_bool());
+                let prefer_captions = args.get("prefer_captions").and_then(|v| v.as_bool());
+                let source_note = args
+                    .get("source_note")
+                    .or_else(|| args.get("consent"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                let clip = parse_clip(args)?;
+
+                let decoding = DecodingOptions {
+                    beam_size: args
+                        .get("beam_size")
+                        .and_then(|v| v.as_u64())
+                        .map(|n| n as u32),
+                    best_of: args
+                        .get("best_of")
+                        .and_then(|v| v.as_u64())
+                        .map(|n| n as u32),
+                    temperature: args
+                        .get("temperature")
+                        .and_then(|v| v.as_f64())
+                        .map(|n| n as f32),
+                    entropy_threshold: args
+                        .get("entropy_threshold")
+                        .and_then(|v| v.as_f64())
+                        .map(|n| n as f32),
+                    no_speech_threshold: args
+                        .get("no_speech_threshold")
+                        .and_then(|v| v.as_f64())
+                        .map(|n| n as f32),
+                    grammar: parse_grammar(args)?,
+                    // Turned on by the engine when speaker_turns asks for it.
+                    speaker_turns: false,
+                    alternatives: args
+                        .get("alternatives")
+                        .and_then(|v| v.as_u64())
+                        .map(|n| n as u32),
+                    // Set by the engine from hotwords.
+                    prompt: None,
+                };
+
+                let mut options = TranscriptionOptions {
                     url,
                     output_dir,
                     model,
+                    model_path,
                     language,
+                    chunk_minutes,
+                    gpu,
+                    device,
+                    audio_track,
+                    min_audio_quality,
+                    skip_non_speech,
+                    skip_silence,
+                    decoding,
+                    prefer_captions,
+                    source_note,
+                    clip,
+                    stream_audio: args
+                        .get("stream_audio")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    live_from_start: args
+                        .get("live_from_start")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    max_live_minutes: args
+                        .get("max_live_minutes")
+                        .and_then(|v| v.as_u64())
+                        .filter(|m| *m > 0)
+                        .map(|m| m as u32),
+                    ..parse_common_options(args)?
                 };
 
+                let summarize = args
+                    .get("summarize")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let suggest = args
+                    .get("suggest_metadata")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                // Checked up front rather than after a long transcription.
+                if (summarize || suggest) && !can_summarize(&context.peer) {
+                    return Err(sampling_unsupported());
+                }
+                let language_requested = options.language.clone();
+
                 info!("🎬 Starting transcription...");
 
-                let transcriber = self.transcriber.lock().await;
-                match transcriber.transcribe(options).await {
+                // A transcript to reuse doesn't need to wait for the engine.
+                let mut existing = None;
+                if !options.force {
+                    match self.transcriber.engine().find_existing(&options).await {
+                        Ok(found) => existing = found,
+                        Err(e) => warn!("Could not look for an existing transcript: {:#}", e),
+                    }
+                    // Already looked; don't look again once queued.
+                    options.force = true;
+                }
+                let outcome = match existing {
+                    Some(result) => Ok(result),
+                    None => {
+                        let job = self
+                            .transcriber
+                            .job(options.model.unwrap_or_default(), &options.url)
+                            .await;
+                        let transcriber = self.transcriber.acquire(&context, job).await?;
+                        disconnect::supervise(
+                            &context,
+                            "transcribe_video",
+                            transcriber.transcribe(options),
+                        )
+                        .await?
+                    }
+                };
+                match outcome {
                     Ok(result) => {
-                        let text = format!(
-                            "✅ Video transcribed successfully!\n\n\
+                        let mut text = format!(
+                            "{}\n\n\
                             **Video Details:**\n\
                             - Title: {}\n\
                             - Platform: {}\n\
-                            - Duration: {}s\n\n\
+                            - Duration: {}s\n{}{}\n\
                             **Transcription Settings:**\n\
-                            - Model: {:?}\n\
-                            - Engine: whisper.cpp (Rust)\n\n\
-                            **Output Files:**\n\
-                            - Text: {}\n\
-                            - JSON: {}\n\
-                            - Markdown: {}\n\n\
+                            - Model: {}\n\
+                            - Language: {}\n\
+                            - Engine: whisper.cpp (Rust)\n\
+                            - GPU: {}\n{}{}{}\
+                            - Resources: {}\n\n\
+                            **Output Files:**\n{}{}{}\n\
                             **Transcript Preview:**\n\
                             {}\n\n\
                             **Full transcript has {} words.**",
+                            if result.cached {
+                                "♻️ Returned the cached transcript (cached: true); nothing was transcribed. Pass force: true to transcribe again."
+                            } else {
+                                "✅ Video transcribed successfully!"
+                            },
                             result.metadata.title,
                             result.metadata.platform,
                             result.metadata.duration,
-                            result.model_used,
-                            result.files.txt,
-                            result.files.json,
-                            result.files.md,
+                            result
+                                .metadata
+                                .source_note
+                                .as_deref()
+                                .map(|note| format!("- Source note: {}\n", note))
+                                .unwrap_or_default(),
+                            if clip.is_set() {
+                                format!("- Clip: {}\n", clip)
+                            } else {
+                                String::new()
+                            },
+                            result.model_label(),
+                            match &result.detected_language {
+                                Some(lang) if lang.is_detected() => {
+                                    format!("{} (auto-detected)", lang)
+                                }
+                                Some(lang) => lang.to_string(),
+                                None => language_requested
+                                    .clone()
+                                    .unwrap_or_else(|| "auto".to_string()),
+                            },
+                            match result.gpu_used {
+                                Some(true) => "yes",
+                                Some(false) => "no (CPU)",
+                                None if result.cached => "n/a (existing transcript)",
+                                None => "remote worker",
+                            },
+                            format_audio_tracks(&result),
+                            format_audio_quality(&result),
+                            format_non_speech(&result),
+                            result.usage.summary(),
+                            format_output_files(&result.files),
+                            format_chapter_files(&result.chapter_files),
+                            format_uploaded_files(&result.uploaded),
                             result.transcript_preview,
                             result.word_count
                         );
+                        if summarize {
+                            text.push_str("\n\n**Summary:**\n");
+                            // The transcript is saved either way, so a failed
+                            // summary is reported rather than failing the call.
+                            match write_summary(&context.peer, &result.metadata, &result.segments)
+                                .await
+                            {
+                                Ok(summary) => text.push_str(&summary),
+                                Err(e) => {
+                                    warn!("Summarizing failed: {:#}", e);
+                                    text.push_str(&format!(
+                                        "⚠️ Could not summarize: {:#}. Try summarize_transcript later.",
+                                        e
+                                    ));
+                                }
+                            }
+                        }
+                        if suggest {
+                            text.push_str("\n\n**Suggested Metadata:**\n");
+                            let suggested = match result.files.get(OutputFormat::Json) {
+                                Some(json) => {
+                                    let base = std::path::Path::new(json).with_extension("");
+                                    save_suggestion(
+                                        &context.peer,
+                                        &base,
+                                        &result.metadata,
+                                        &result.segments,
+                                    )
+                                    .await
+                                }
+                                None => Err(anyhow::anyhow!(
+                                    "suggestions are saved in the JSON output, which wasn't written"
+                                )),
+                            };
+                            match suggested {
+                                Ok(suggestion) => text.push_str(&format_suggestion(&suggestion)),
+                                Err(e) => {
+                                    warn!("Suggesting metadata failed: {:#}", e);
+                                    text.push_str(&format!(
+                                        "⚠️ Could not suggest metadata: {:#}. Try suggest_metadata later.",
+                                        e
+                                    ));
+                                }
+                            }
+                        }
 
                         Ok(CallToolResult::success(vec![Content::text(text)]))
                     }
@@ -308,349 +1220,1213 @@ impl ServerHandler for VideoTranscriberServer {
                 }
             }
 
-            "check_dependencies" => {
-                let transcriber = self.transcriber.lock().await;
-                match transcriber.check_dependencies() {
-                    Ok(status) => {
-                        let text = format!("✅ Dependency Check:\n\n{}", status);
-                        Ok(CallToolResult::success(vec![Content::text(text)]))
-                    }
-                    Err(e) => Err(ErrorData::new(
-                        ErrorCode::INTERNAL_ERROR,
-                        format!("Dependency check failed: {}", e),
+            "transcribe_batch" => {
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
                         None,
-                    )),
-                }
-            }
-
-            "list_supported_sites" => {
-                let text = "📺 Supported Video Platforms (1000+ total)\n\n\
-                    **Popular platforms include:**\n\
-                    - YouTube\n\
-                    - Vimeo\n\
-                    - TikTok\n\
-                    - Twitter/X\n\
-                    - Facebook\n\
-                    - Instagram\n\
-                    - Twitch\n\
-                    - Dailymotion\n\
-                    - Reddit\n\
-                    - LinkedIn\n\
-                    - Many educational and conference platforms\n\n\
-                    **Total: 1000+ supported extractors**\n\n\
-                    You can transcribe videos from any of these platforms!";
+                    )
+                })?;
 
-                Ok(CallToolResult::success(vec![Content::text(text)]))
-            }
+                let urls: Vec<String> = args
+                    .get("urls")
+                    .and_then(|v| v.as_array())
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(|s| s.to_string())
+                            .collect()
+                    })
+                    .filter(|urls: &Vec<String>| !urls.is_empty())
+                    .ok_or_else(|| {
+                        ErrorData::new(
+                            ErrorCode::INVALID_PARAMS,
+                            "Missing or empty 'urls' parameter".to_string(),
+                            None,
+                        )
+                    })?;
 
-            "list_transcripts" => {
-                use std::collections::HashMap;
-                use std::fs;
-                use std::path::PathBuf;
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| get_default_output_dir().to_string_lossy().to_string());
 
-                let output_dir = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("output_dir"))
+                let model = args
+                    .get("model")
                     .and_then(|v| v.as_str())
-                    .map(PathBuf::from)
-                    .unwrap_or_else(get_default_output_dir);
+                    .and_then(|s| s.parse::<WhisperModel>().ok());
 
-                let limit = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("limit"))
-                    .and_then(|v| v.as_u64())
-                    .map(|n| n as usize);
+                let language = args
+                    .get("language")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
 
-                if !output_dir.exists() {
-                    let text = format!(
-                        "📂 No transcripts directory found at: {}\n\nTranscribe your first video to create it!",
-                        output_dir.display()
-                    );
-                    return Ok(CallToolResult::success(vec![Content::text(text)]));
-                }
+                let schedule = parse_schedule(args)?.unwrap_or_default();
 
-                let mut video_groups: HashMap<String, Vec<String>> = HashMap::new();
+                let options = TranscriptionOptions {
+                    url: String::new(),
+                    output_dir,
+                    model,
+                    language,
+                    ..parse_common_options(args)?
+                };
+                let mut report =
+                    BatchReport::new(urls, BatchOptions::from_options(&options), schedule);
+
+                info!(
+                    "📚 Starting batch {} ({} items, {:?})...",
+                    report.batch_id,
+                    report.items.len(),
+                    report.schedule
+                );
 
-                if let Ok(entries) = fs::read_dir(&output_dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let job = Job {
+                    model: options.model.unwrap_or_default(),
+                    audio_secs: Some(report.items.iter().filter_map(|i| i.duration).sum()),
+                };
+                let transcriber = self.transcriber.acquire(&context, job).await?;
+                let indices: Vec<usize> = (0..report.items.len()).collect();
+                disconnect::supervise(
+                    &context,
+                    "transcribe_batch",
+                    batch::run_items(&transcriber, &mut report, &indices, &options),
+                )
+                .await?
+                .map_err(|e| {
+                    ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Batch failed: {}", e),
+                        None,
+                    )
+                })?;
 
-                        if filename.ends_with(".txt") || filename.ends_with(".md") {
-                            let video_id =
-                                filename.split('-').next().unwrap_or("unknown").to_string();
-                            video_groups
-                                .entry(video_id)
-                                .or_default()
-                                .push(filename.to_string());
-                        }
-                    }
-                }
+                Ok(CallToolResult::success(vec![Content::text(
+                    format_batch_report(&report, &options.output_dir),
+                )]))
+            }
 
-                if video_groups.is_empty() {
-                    let text = format!(
-                        "📂 No transcripts found in {}\n\nTranscribe a video to get started!",
-                        output_dir.display()
+            "transcribe_directory" => {
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+
+                let directory =
+                    args.get("directory")
+                        .and_then(|v| v.as_str())
+                        .ok_or_else(|| {
+                            ErrorData::new(
+                                ErrorCode::INVALID_PARAMS,
+                                "Missing required parameter: directory".to_string(),
+                                None,
+                            )
+                        })?;
+
+                let extensions: Vec<String> = args
+                    .get("extensions")
+                    .and_then(|v| v.as_array())
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(|s| s.trim_start_matches('.').to_string())
+                            .collect()
+                    })
+                    .filter(|exts: &Vec<String>| !exts.is_empty())
+                    .unwrap_or_else(|| {
+                        batch::DEFAULT_MEDIA_EXTENSIONS
+                            .iter()
+                            .map(|e| e.to_string())
+                            .collect()
+                    });
+                let recursive = args
+                    .get("recursive")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| get_default_output_dir().to_string_lossy().to_string());
+
+                let model = args
+                    .get("model")
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| s.parse::<WhisperModel>().ok());
+
+                let language = args
+                    .get("language")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+
+                let schedule = parse_schedule(args)?.unwrap_or_default();
+
+                let options = TranscriptionOptions {
+                    url: String::new(),
+                    output_dir,
+                    model,
+                    language,
+                    ..parse_common_options(args)?
+                };
+
+                let job = Job {
+                    model: options.model.unwrap_or_default(),
+                    audio_secs: None,
+                };
+                let transcriber = self.transcriber.acquire(&context, job).await?;
+                let run = disconnect::supervise(
+                    &context,
+                    "transcribe_directory",
+                    batch::run_directory(
+                        &transcriber,
+                        std::path::Path::new(directory),
+                        &extensions,
+                        recursive,
+                        &options,
+                        schedule,
+                    ),
+                )
+                .await?
+                .map_err(|e| {
+                    ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Directory batch failed: {:#}", e),
+                        None,
+                    )
+                })?;
+
+                Ok(CallToolResult::success(vec![Content::text(
+                    format_directory_run(&run, &options.output_dir),
+                )]))
+            }
+
+            "transcribe_podcast" => {
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+
+                let feed_url = args
+                    .get("feed_url")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ErrorData::new(
+                            ErrorCode::INVALID_PARAMS,
+                            "Missing required parameter: feed_url".to_string(),
+                            None,
+                        )
+                    })?;
+
+                let latest = args
+                    .get("latest")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(1)
+                    .max(1) as usize;
+                let wanted: Vec<String> = args
+                    .get("episodes")
+                    .and_then(|v| v.as_array())
+                    .map(|a| {
+                        a.iter()
+                            .filter_map(|v| v.as_str())
+                            .map(|s| s.to_string())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let list_only = args
+                    .get("list_only")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let common = parse_common_options(args)?;
+
+                let feed = self
+                    .transcriber
+                    .engine()
+                    .fetch_feed(feed_url, common.proxy.as_deref())
+                    .await
+                    .map_err(|e| {
+                        ErrorData::new(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("Failed to read feed: {:#}", e),
+                            None,
+                        )
+                    })?;
+                if list_only {
+                    return Ok(CallToolResult::success(vec![Content::text(format_feed(
+                        &feed,
+                    ))]));
+                }
+                let episodes = feed.select(&wanted, latest).map_err(|e| {
+                    ErrorData::new(ErrorCode::INVALID_PARAMS, format!("{:#}", e), None)
+                })?;
+
+                let options = TranscriptionOptions {
+                    url: String::new(),
+                    output_dir: args
+                        .get("output_dir")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| get_default_output_dir().to_string_lossy().to_string()),
+                    model: args
+                        .get("model")
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| s.parse::<WhisperModel>().ok()),
+                    language: args
+                        .get("language")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string()),
+                    source_note: args
+                        .get("source_note")
+                        .or_else(|| args.get("consent"))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string()),
+                    ..common
+                };
+
+                info!(
+                    "🎙️  Transcribing {} episode(s) of {}",
+                    episodes.len(),
+                    feed.title
+                );
+                let job = Job {
+                    model: options.model.unwrap_or_default(),
+                    audio_secs: Some(episodes.iter().filter_map(|e| e.duration).sum()),
+                };
+                let transcriber = self.transcriber.acquire(&context, job).await?;
+                let mut lines = Vec::new();
+                for (i, episode) in episodes.into_iter().enumerate() {
+                    let title = episode.title.clone();
+                    let outcome = disconnect::supervise(
+                        &context,
+                        "transcribe_podcast",
+                        transcriber.transcribe(TranscriptionOptions {
+                            url: episode.audio_url.clone(),
+                            episode: Some(episode),
+                            ..options.clone()
+                        }),
+                    )
+                    .await?;
+                    lines.push(match outcome {
+                        Ok(result) => format!(
+                            "{}. ✅ {}\n   Transcript: {}",
+                            i + 1,
+                            title,
+                            result.files.primary()
+                        ),
+                        Err(e) => {
+                            warn!("Podcast episode failed ({}): {:#}", title, e);
+                            format!("{}. ❌ {}\n   Error: {:#}", i + 1, title, e)
+                        }
+                    });
+                }
+
+                Ok(CallToolResult::success(vec![Content::text(format!(
+                    "🎙️ {}\n\n{}",
+                    feed.title,
+                    lines.join("\n\n")
+                ))]))
+            }
+
+            "retry_failed" => {
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+
+                let batch_id = args
+                    .get("batch_id")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ErrorData::new(
+                            ErrorCode::INVALID_PARAMS,
+                            "Missing 'batch_id' parameter".to_string(),
+                            None,
+                        )
+                    })?;
+
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| get_default_output_dir().to_string_lossy().to_string());
+
+                let mut report = BatchReport::load(std::path::Path::new(&output_dir), batch_id)
+                    .map_err(|e| {
+                        ErrorData::new(ErrorCode::INVALID_PARAMS, format!("{:#}", e), None)
+                    })?;
+
+                let indices = report.retryable_indices();
+                if indices.is_empty() {
+                    let text = format!(
+                        "✅ Nothing to retry — every item in batch {} already completed.",
+                        report.batch_id
                     );
                     return Ok(CallToolResult::success(vec![Content::text(text)]));
                 }
 
-                // Collect video data with timestamps for sorting
-                let mut video_data: Vec<(String, Vec<String>, u64, PathBuf)> = Vec::new();
+                if let Some(schedule) = parse_schedule(args)? {
+                    report.schedule = schedule;
+                }
 
-                for (video_id, files) in video_groups.iter() {
-                    let main_file = files
-                        .iter()
-                        .find(|f| f.ends_with(".txt"))
-                        .unwrap_or(&files[0]);
-
-                    let full_path = output_dir.join(main_file);
-
-                    if let Ok(metadata) = fs::metadata(&full_path) {
-                        let modified = metadata
-                            .modified()
-                            .ok()
-                            .and_then(|t| {
-                                use std::time::SystemTime;
-                                let duration = t.duration_since(SystemTime::UNIX_EPOCH).ok()?;
-                                Some(duration.as_secs())
-                            })
-                            .unwrap_or(0);
+                let mut options = report.options.to_options(output_dir);
+                if let Some(model) = args
+                    .get("model")
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| s.parse::<WhisperModel>().ok())
+                {
+                    options.model = Some(model);
+                }
+                if let Some(language) = args.get("language").and_then(|v| v.as_str()) {
+                    options.language = Some(language.to_string());
+                }
+                apply_common_options(args, &mut options)?;
+
+                info!(
+                    "🔁 Retrying {} item(s) of batch {}...",
+                    indices.len(),
+                    report.batch_id
+                );
 
-                        video_data.push((video_id.clone(), files.clone(), modified, full_path));
+                let job = Job {
+                    model: options.model.unwrap_or_default(),
+                    audio_secs: Some(
+                        indices
+                            .iter()
+                            .filter_map(|&i| report.items[i].duration)
+                            .sum(),
+                    ),
+                };
+                let transcriber = self.transcriber.acquire(&context, job).await?;
+                disconnect::supervise(
+                    &context,
+                    "retry_failed",
+                    batch::run_items(&transcriber, &mut report, &indices, &options),
+                )
+                .await?
+                .map_err(|e| {
+                    ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Retry failed: {}", e),
+                        None,
+                    )
+                })?;
+
+                Ok(CallToolResult::success(vec![Content::text(
+                    format_batch_report(&report, &options.output_dir),
+                )]))
+            }
+
+            "get_metrics" => {
+                let snapshot = serde_json::to_value(metrics::global().snapshot())
+                    .map_err(|e| ErrorData::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+                Ok(CallToolResult::structured(snapshot))
+            }
+
+            "check_dependencies" => {
+                let transcriber = self.transcriber.engine();
+                match transcriber.check_dependencies() {
+                    Ok(status) => {
+                        let text = format!("✅ Dependency Check:\n\n{}", status);
+                        Ok(CallToolResult::success(vec![Content::text(text)]))
                     }
+                    Err(e) => Err(ErrorData::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Dependency check failed: {}", e),
+                        None,
+                    )),
                 }
+            }
 
-                // Sort by modification time (newest first)
-                video_data.sort_by_key(|b| std::cmp::Reverse(b.2));
+            "list_supported_sites" => {
+                let text = "📺 Supported Video Platforms (1000+ total)\n\n\
+                    **Popular platforms include:**\n\
+                    - YouTube\n\
+                    - Vimeo\n\
+                    - TikTok\n\
+                    - Twitter/X\n\
+                    - Facebook\n\
+                    - Instagram\n\
+                    - Twitch\n\
+                    - Dailymotion\n\
+                    - Reddit\n\
+                    - LinkedIn\n\
+                    - Many educational and conference platforms\n\n\
+                    **Total: 1000+ supported extractors**\n\n\
+                    You can transcribe videos from any of these platforms!";
 
-                // Apply limit if specified
-                let videos_to_show = if let Some(lim) = limit {
-                    &video_data[..video_data.len().min(lim)]
-                } else {
-                    &video_data[..]
-                };
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
 
-                let mut list_items = Vec::new();
-                for (i, (video_id, files, modified, full_path)) in videos_to_show.iter().enumerate()
-                {
-                    let main_file = files
-                        .iter()
-                        .find(|f| f.ends_with(".txt"))
-                        .unwrap_or(&files[0]);
+            "list_transcripts" => {
+                use std::path::PathBuf;
 
-                    if let Ok(metadata) = fs::metadata(full_path) {
-                        let size_kb = metadata.len() as f64 / 1024.0;
+                let output_dir = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("output_dir"))
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
 
-                        let title = main_file
-                            .replace(&format!("{}-", video_id), "")
-                            .replace(".txt", "")
-                            .replace(".md", "")
-                            .replace(".json", "")
-                            .replace("-", " ");
+                let limit = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("limit"))
+                    .and_then(|v| v.as_u64())
+                    .map(|n| n as usize);
 
-                        let extensions: Vec<&str> = files
-                            .iter()
-                            .filter_map(|f| f.split('.').next_back())
-                            .collect();
+                if !output_dir.exists() {
+                    let text = format!(
+                        "📂 No transcripts directory found at: {}\n\nTranscribe your first video to create it!",
+                        output_dir.display()
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
 
-                        list_items.push(format!(
-                            "{}. **{}**\n   Video ID: {}\n   Files: {} ({})\n   Size: {:.2} KB\n   Modified: {}\n   Path: {}",
-                            i + 1,
-                            title,
-                            video_id,
-                            files.len(),
-                            extensions.join(", "),
-                            size_kb,
-                            format_timestamp(*modified),
-                            full_path.display()
-                        ));
-                    }
+                let library = Library::open(&output_dir).map_err(library_error)?;
+                let total_count = library.list(None).map_err(library_error)?.len();
+                let entries = library.list(limit).map_err(library_error)?;
+
+                if entries.is_empty() {
+                    let text = format!(
+                        "📂 No transcripts found in {}\n\nTranscribe a video to get started!",
+                        output_dir.display()
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
                 }
 
-                let total_count = video_data.len();
-                let showing_count = videos_to_show.len();
+                let list_items: Vec<String> = entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| format!("{}. {}", i + 1, format_entry(entry, &output_dir)))
+                    .collect();
 
-                let summary = if showing_count < total_count {
+                let summary = if entries.len() < total_count {
                     format!(
                         "showing {} most recent out of {} total",
-                        showing_count, total_count
+                        entries.len(),
+                        total_count
                     )
                 } else {
                     format!("{} videos", total_count)
                 };
-
+
+                let text = format!(
+                    "📚 Available transcripts ({}):\n\n{}\n\n💡 Tip: You can read any transcript by passing the file path shown above to read_transcript.",
+                    summary,
+                    list_items.join("\n\n")
+                );
+
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "get_latest_transcript" => {
+                use std::path::PathBuf;
+
+                let output_dir = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("output_dir"))
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+
+                if !output_dir.exists() {
+                    let text = format!(
+                        "📂 No transcripts directory found at: {}\n\nTranscribe your first video to create it!",
+                        output_dir.display()
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+
+                let latest = Library::open(&output_dir)
+                    .and_then(|library| library.list(Some(1)))
+                    .map_err(library_error)?;
+                let Some(entry) = latest.first() else {
+                    let text = format!(
+                        "📂 No transcripts found in {}\n\nTranscribe a video to get started!",
+                        output_dir.display()
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                };
+
+                let text = format!(
+                    "📄 **Latest Transcript:**\n\n\
+                    {}\n\n\
+                    **File Paths:**\n{}\n\
+                    💡 Tip: Pass the text file path above to read_transcript to read or summarize this transcript.",
+                    format_entry(entry, &output_dir),
+                    format_output_files(&entry.files(&output_dir))
+                );
+
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "read_transcript" => {
+                use std::path::PathBuf;
+
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+                let path = args.get("path").and_then(|v| v.as_str()).ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing required parameter: path".to_string(),
+                        None,
+                    )
+                })?;
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+
+                check_output_root(&output_dir)?;
+                let path = transcript_file(&output_dir, path)
+                    .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+                let text = encryption::read_to_string(&path).map_err(|e| {
+                    ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("{:#}", e), None)
+                })?;
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "search_in_transcript" => {
+                use std::path::PathBuf;
+
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+                let invalid = |msg: String| ErrorData::new(ErrorCode::INVALID_PARAMS, msg, None);
+                let query = args
+                    .get("query")
+                    .and_then(|v| v.as_str())
+                    .filter(|q| !q.trim().is_empty())
+                    .ok_or_else(|| invalid("Missing required parameter: query".to_string()))?;
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(50, |n| n as usize);
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+
+                if args.get("path").is_none() && args.get("video_id").is_none() {
+                    return Err(invalid("Give video_id or path".to_string()));
+                }
+                let stored = load_stored_transcript(args, &output_dir)?;
+
+                let hits = search::search(&stored.segments, query);
+                if hits.is_empty() {
+                    let text = format!(
+                        "🔎 No mention of \"{}\" in **{}** ({}).",
+                        query.trim(),
+                        stored.metadata.title,
+                        stored.metadata.video_id
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+                let lines: Vec<String> = hits
+                    .iter()
+                    .take(limit)
+                    .map(|hit| {
+                        format!(
+                            "- [{}] ({}s){} {}{}",
+                            outputs::marker(hit.start_ms),
+                            hit.start_ms / 1000,
+                            hit.track
+                                .map(|n| format!(" Track {}:", n))
+                                .unwrap_or_default(),
+                            hit.text,
+                            links::at(&stored.metadata, hit.start_ms)
+                                .map(|link| format!("\n  {}", link))
+                                .unwrap_or_default()
+                        )
+                    })
+                    .collect();
+                let more = match hits.len().saturating_sub(limit) {
+                    0 => String::new(),
+                    n => format!("\n… and {} more (raise limit to see them)", n),
+                };
+                let text = format!(
+                    "🔎 {} hit(s) for \"{}\" in **{}** ({}):\n\n{}{}\n\nTimes are from the start of the video.",
+                    hits.len(),
+                    query.trim(),
+                    stored.metadata.title,
+                    stored.metadata.video_id,
+                    lines.join("\n"),
+                    more
+                );
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "query_transcripts" => {
+                use std::path::PathBuf;
+
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+                let text = args.get("query").and_then(|v| v.as_str()).ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing required parameter: query".to_string(),
+                        None,
+                    )
+                })?;
+                let query: Query = text.parse().map_err(|e: anyhow::Error| {
+                    ErrorData::new(ErrorCode::INVALID_PARAMS, format!("query: {:#}", e), None)
+                })?;
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+
+                let rows = query.run(&output_dir).map_err(library_error)?;
+                Ok(CallToolResult::structured(json!({
+                    "query": text,
+                    "count": rows.len(),
+                    "rows": rows,
+                })))
+            }
+
+            "search_transcripts" => {
+                use std::path::PathBuf;
+
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+                let invalid = |msg: String| ErrorData::new(ErrorCode::INVALID_PARAMS, msg, None);
+                let query = args
+                    .get("query")
+                    .and_then(|v| v.as_str())
+                    .filter(|q| !q.trim().is_empty())
+                    .ok_or_else(|| invalid("Missing required parameter: query".to_string()))?;
+                let text_arg = |name: &str| {
+                    args.get(name)
+                        .and_then(|v| v.as_str())
+                        .filter(|s| !s.trim().is_empty())
+                };
+                let date = |name: &str| {
+                    text_arg(name)
+                        .map(search::parse_date)
+                        .transpose()
+                        .map_err(|e| invalid(format!("{}: {:#}", name, e)))
+                };
+                let facets = search::Facets {
+                    platform: text_arg("platform").map(str::to_string),
+                    channel: text_arg("channel").map(str::to_string),
+                    since: date("since")?,
+                    until: date("until")?,
+                };
+                let export = text_arg("export_results")
+                    .map(str::parse::<search::ReportFormat>)
+                    .transpose()
+                    .map_err(|e| invalid(e.to_string()))?;
+                if export.is_some() && self.read_only {
+                    return Err(ErrorData::new(
+                        ErrorCode::INVALID_REQUEST,
+                        "export_results is not available: this server is read-only".to_string(),
+                        None,
+                    ));
+                }
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(50, |n| n as usize);
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+
+                let matches =
+                    search::search_library(&output_dir, query, &facets).map_err(library_error)?;
+                let filters = facets
+                    .describe()
+                    .map(|f| format!(" ({})", f))
+                    .unwrap_or_default();
+                if matches.is_empty() {
+                    let text = format!(
+                        "🔎 No mention of \"{}\" in the transcripts in {}{}.",
+                        query.trim(),
+                        output_dir.display(),
+                        filters
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+
+                let mut lines: Vec<String> = Vec::new();
+                let mut current = None;
+                for m in matches.iter().take(limit) {
+                    if current != Some(&m.entry.base) {
+                        current = Some(&m.entry.base);
+                        lines.push(format!(
+                            "\n**{}** ({}, {} on {}, uploaded {})",
+                            m.entry.title,
+                            m.entry.video_id,
+                            m.entry.channel,
+                            m.entry.platform,
+                            if m.entry.upload_date.is_empty() {
+                                "unknown"
+                            } else {
+                                &m.entry.upload_date
+                            }
+                        ));
+                    }
+                    lines.push(format!(
+                        "- [{}] ({}s) {}{}",
+                        outputs::marker(m.hit.start_ms),
+                        m.hit.start_ms / 1000,
+                        m.hit.text,
+                        links::at(&m.entry.metadata(), m.hit.start_ms)
+                            .map(|link| format!("\n  {}", link))
+                            .unwrap_or_default()
+                    ));
+                }
+                let more = match matches.len().saturating_sub(limit) {
+                    0 => String::new(),
+                    n => format!("\n… and {} more (raise limit to see them)", n),
+                };
+                let report = match export {
+                    Some(format) => {
+                        let path = search::export(&output_dir, query, &facets, &matches, format)
+                            .map_err(|e| {
+                                ErrorData::new(
+                                    ErrorCode::INTERNAL_ERROR,
+                                    format!("Failed to write the report: {:#}", e),
+                                    None,
+                                )
+                            })?;
+                        format!("\n\n📄 Report with all hits: {}", path.display())
+                    }
+                    None => String::new(),
+                };
+                let text = format!(
+                    "🔎 {} hit(s) for \"{}\"{}:\n{}{}{}\n\nTimes are from the start of each video.",
+                    matches.len(),
+                    query.trim(),
+                    filters,
+                    lines.join("\n"),
+                    more,
+                    report
+                );
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "semantic_search" => {
+                use std::path::PathBuf;
+
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+                let invalid = |msg: String| ErrorData::new(ErrorCode::INVALID_PARAMS, msg, None);
+                let query = args
+                    .get("query")
+                    .and_then(|v| v.as_str())
+                    .filter(|q| !q.trim().is_empty())
+                    .ok_or_else(|| invalid("Missing required parameter: query".to_string()))?;
+                let video_id = args.get("video_id").and_then(|v| v.as_str());
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(10, |n| n as usize);
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+
+                let config = Config::load().embeddings.ok_or_else(|| {
+                    invalid(
+                        "Semantic search needs an embeddings endpoint: set `embeddings` in the config file"
+                            .to_string(),
+                    )
+                })?;
+                let embedder = semantic::Embedder::new(config).map_err(|e| {
+                    ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("{:#}", e), None)
+                })?;
+                let hits = semantic::search(&output_dir, &embedder, query, video_id, limit)
+                    .await
+                    .map_err(|e| {
+                        ErrorData::new(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("Semantic search failed: {:#}", e),
+                            None,
+                        )
+                    })?;
+                if hits.is_empty() {
+                    let text = format!(
+                        "🧭 No transcripts to search in {}{}.",
+                        output_dir.display(),
+                        video_id
+                            .map(|id| format!(" for video {}", id))
+                            .unwrap_or_default()
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+                let lines: Vec<String> = hits
+                    .iter()
+                    .map(|hit| {
+                        let metadata = hit.entry.metadata();
+                        let start = hit.passage.start_ms;
+                        format!(
+                            "- **{}** ({}) [{}] ({}s), similarity {:.2}{}\n  {}",
+                            hit.entry.title,
+                            hit.entry.video_id,
+                            outputs::marker(start),
+                            start / 1000,
+                            hit.score,
+                            links::at(&metadata, start)
+                                .map(|link| format!("\n  {}", link))
+                                .unwrap_or_default(),
+                            hit.passage.text
+                        )
+                    })
+                    .collect();
+                let text = format!(
+                    "🧭 {} passage(s) closest to \"{}\":\n\n{}",
+                    hits.len(),
+                    query.trim(),
+                    lines.join("\n\n")
+                );
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "get_transcript_topics" => {
+                use std::path::PathBuf;
+
+                let no_args = serde_json::Map::new();
+                let args = request.arguments.as_ref().unwrap_or(&no_args);
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(30, |n| n as usize);
+
+                if args.get("path").is_some() || args.get("video_id").is_some() {
+                    let stored = load_stored_transcript(args, &output_dir)?;
+                    let found = stored.topics_or_extract();
+                    if found.is_empty() {
+                        let text = format!(
+                            "🏷️ No topics found in **{}** ({}). Topics are extracted from English transcripts with repeated key phrases.",
+                            stored.metadata.title, stored.metadata.video_id
+                        );
+                        return Ok(CallToolResult::success(vec![Content::text(text)]));
+                    }
+                    let lines: Vec<String> = found
+                        .iter()
+                        .take(limit)
+                        .map(|topic| {
+                            let first = outputs::marker(topic.first_ms);
+                            format!(
+                                "- **{}**: {} mention(s), first at {}",
+                                topic.term,
+                                topic.mentions,
+                                links::at(&stored.metadata, topic.first_ms)
+                                    .map(|link| format!("[{}]({})", first, link))
+                                    .unwrap_or(first)
+                            )
+                        })
+                        .collect();
+                    let text = format!(
+                        "🏷️ Topics of **{}** ({}), most central first:\n\n{}",
+                        stored.metadata.title,
+                        stored.metadata.video_id,
+                        lines.join("\n")
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+
+                let entries = Library::open(&output_dir)
+                    .and_then(|library| library.list(None))
+                    .map_err(library_error)?;
+                if let Some(topic) = args
+                    .get("topic")
+                    .and_then(|v| v.as_str())
+                    .map(|t| t.trim().to_lowercase())
+                    .filter(|t| !t.is_empty())
+                {
+                    let matching: Vec<String> = entries
+                        .iter()
+                        .filter(|e| e.topics.iter().any(|t| t.to_lowercase().contains(&topic)))
+                        .take(limit)
+                        .enumerate()
+                        .map(|(i, entry)| {
+                            format!(
+                                "{}. {}\n   Topics: {}",
+                                i + 1,
+                                format_entry(entry, &output_dir),
+                                entry.topics.join(", ")
+                            )
+                        })
+                        .collect();
+                    let text = if matching.is_empty() {
+                        format!("🏷️ No transcripts with a topic matching \"{}\".", topic)
+                    } else {
+                        format!(
+                            "🏷️ Transcripts about \"{}\":\n\n{}",
+                            topic,
+                            matching.join("\n\n")
+                        )
+                    };
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+
+                let tally = topics::tally(entries.iter().map(|e| e.topics.as_slice()));
+                if tally.is_empty() {
+                    let text = format!(
+                        "🏷️ No topics in the transcripts in {} yet.",
+                        output_dir.display()
+                    );
+                    return Ok(CallToolResult::success(vec![Content::text(text)]));
+                }
+                let lines: Vec<String> = tally
+                    .iter()
+                    .take(limit)
+                    .map(|(term, count)| format!("- {} ({} transcript(s))", term, count))
+                    .collect();
+                let text = format!(
+                    "🏷️ Most common topics across {} transcripts:\n\n{}\n\n💡 Pass one as topic to list its transcripts.",
+                    entries.len(),
+                    lines.join("\n")
+                );
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "summarize_transcript" => {
+                use std::path::PathBuf;
+
+                if !can_summarize(&context.peer) {
+                    return Err(sampling_unsupported());
+                }
+                let no_args = serde_json::Map::new();
+                let args = request.arguments.as_ref().unwrap_or(&no_args);
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+                let stored = load_stored_transcript(args, &output_dir)?;
+
+                info!(
+                    "📝 Asking the client to summarize {}",
+                    stored.metadata.title
+                );
+                let summary = write_summary(&context.peer, &stored.metadata, &stored.segments)
+                    .await
+                    .map_err(|e| {
+                        ErrorData::new(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("Summarizing failed: {:#}", e),
+                            None,
+                        )
+                    })?;
+                let text = format!(
+                    "📝 **{}** ({})\n\n{}",
+                    stored.metadata.title, stored.metadata.video_id, summary
+                );
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
+
+            "suggest_metadata" => {
+                use std::path::PathBuf;
+
+                let no_args = serde_json::Map::new();
+                let args = request.arguments.as_ref().unwrap_or(&no_args);
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
+                let regenerate = args
+                    .get("regenerate")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let base = stored_transcript_base(args, &output_dir)?;
+                let stored = load_stored_transcript(args, &output_dir)?;
+
+                let (suggestion, saved) = match stored.suggested {
+                    Some(suggestion) if !regenerate => (suggestion, true),
+                    _ => {
+                        if !can_summarize(&context.peer) {
+                            return Err(sampling_unsupported());
+                        }
+                        info!("🏷️ Asking for metadata for {}", stored.metadata.title);
+                        let suggestion = save_suggestion(
+                            &context.peer,
+                            &base,
+                            &stored.metadata,
+                            &stored.segments,
+                        )
+                        .await
+                        .map_err(|e| {
+                            ErrorData::new(
+                                ErrorCode::INTERNAL_ERROR,
+                                format!("Suggesting metadata failed: {:#}", e),
+                                None,
+                            )
+                        })?;
+                        (suggestion, false)
+                    }
+                };
                 let text = format!(
-                    "📚 Available transcripts ({}):\n\n{}\n\n💡 Tip: You can read any transcript by asking me to read the file path shown above.",
-                    summary,
-                    list_items.join("\n\n")
+                    "🏷️ **{}** ({})\n\n{}\n\n{}",
+                    stored.metadata.title,
+                    stored.metadata.video_id,
+                    format_suggestion(&suggestion),
+                    if saved {
+                        "💡 Saved earlier; pass regenerate: true for a new one."
+                    } else {
+                        "💾 Saved in the JSON output as 'suggested'."
+                    }
                 );
-
                 Ok(CallToolResult::success(vec![Content::text(text)]))
             }
 
-            "get_latest_transcript" => {
-                use std::collections::HashMap;
-                use std::fs;
+            "refresh_metadata" => {
                 use std::path::PathBuf;
 
-                let output_dir = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("output_dir"))
+                let no_args = serde_json::Map::new();
+                let args = request.arguments.as_ref().unwrap_or(&no_args);
+                let output_dir = args
+                    .get("output_dir")
                     .and_then(|v| v.as_str())
                     .map(PathBuf::from)
                     .unwrap_or_else(get_default_output_dir);
+                let video_id = args.get("video_id").and_then(|v| v.as_str());
+                let missing_upload_date = args
+                    .get("missing_upload_date")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(refresh::DEFAULT_LIMIT, |n| n as usize);
+
+                let refreshed = self
+                    .transcriber
+                    .engine()
+                    .refresh_metadata(
+                        &output_dir,
+                        |entry| {
+                            video_id.is_none_or(|id| entry.video_id == id)
+                                && (!missing_upload_date || entry.upload_date.is_empty())
+                        },
+                        limit,
+                    )
+                    .await
+                    .map_err(library_error)?;
+                let text = format_refreshed(&refreshed);
+                Ok(CallToolResult::success(vec![Content::text(text)]))
+            }
 
-                if !output_dir.exists() {
-                    let text = format!(
-                        "📂 No transcripts directory found at: {}\n\nTranscribe your first video to create it!",
-                        output_dir.display()
-                    );
-                    return Ok(CallToolResult::success(vec![Content::text(text)]));
-                }
-
-                let mut video_groups: HashMap<String, Vec<String>> = HashMap::new();
-
-                if let Ok(entries) = fs::read_dir(&output_dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                        if filename.ends_with(".txt") || filename.ends_with(".md") {
-                            let video_id =
-                                filename.split('-').next().unwrap_or("unknown").to_string();
-                            video_groups
-                                .entry(video_id)
-                                .or_default()
-                                .push(filename.to_string());
-                        }
-                    }
-                }
-
-                if video_groups.is_empty() {
-                    let text = format!(
-                        "📂 No transcripts found in {}\n\nTranscribe a video to get started!",
-                        output_dir.display()
-                    );
-                    return Ok(CallToolResult::success(vec![Content::text(text)]));
-                }
-
-                // Find the most recent transcript
-                let mut latest_transcript: Option<(String, Vec<String>, u64, PathBuf)> = None;
-
-                for (video_id, files) in video_groups.iter() {
-                    let main_file = files
-                        .iter()
-                        .find(|f| f.ends_with(".txt"))
-                        .unwrap_or(&files[0]);
-
-                    let full_path = output_dir.join(main_file);
-
-                    if let Ok(metadata) = fs::metadata(&full_path) {
-                        let modified = metadata
-                            .modified()
-                            .ok()
-                            .and_then(|t| {
-                                use std::time::SystemTime;
-                                let duration = t.duration_since(SystemTime::UNIX_EPOCH).ok()?;
-                                Some(duration.as_secs())
-                            })
-                            .unwrap_or(0);
+            "redact_range" => {
+                use std::path::PathBuf;
 
-                        if let Some((_, _, latest_time, _)) = &latest_transcript {
-                            if modified > *latest_time {
-                                latest_transcript =
-                                    Some((video_id.clone(), files.clone(), modified, full_path));
-                            }
-                        } else {
-                            latest_transcript =
-                                Some((video_id.clone(), files.clone(), modified, full_path));
-                        }
-                    }
+                let args = request.arguments.as_ref().ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing arguments".to_string(),
+                        None,
+                    )
+                })?;
+                let path = args.get("path").and_then(|v| v.as_str()).ok_or_else(|| {
+                    ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Missing required parameter: path".to_string(),
+                        None,
+                    )
+                })?;
+                let range = parse_clip(args)?;
+                if !range.is_set() {
+                    return Err(ErrorData::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Give start_time, end_time or both".to_string(),
+                        None,
+                    ));
                 }
+                let output_dir = args
+                    .get("output_dir")
+                    .and_then(|v| v.as_str())
+                    .map(PathBuf::from)
+                    .unwrap_or_else(get_default_output_dir);
 
-                if let Some((video_id, files, modified, full_path)) = latest_transcript {
-                    let main_file = files
-                        .iter()
-                        .find(|f| f.ends_with(".txt"))
-                        .unwrap_or(&files[0]);
-
-                    if let Ok(metadata) = fs::metadata(&full_path) {
-                        let size_kb = metadata.len() as f64 / 1024.0;
-
-                        let title = main_file
-                            .replace(&format!("{}-", video_id), "")
-                            .replace(".txt", "")
-                            .replace(".md", "")
-                            .replace(".json", "")
-                            .replace("-", " ");
-
-                        let extensions: Vec<&str> = files
-                            .iter()
-                            .filter_map(|f| f.split('.').next_back())
-                            .collect();
-
-                        // Find paths for all file types
-                        let txt_path = output_dir.join(
-                            files
-                                .iter()
-                                .find(|f| f.ends_with(".txt"))
-                                .unwrap_or(&files[0]),
-                        );
-                        let md_path = files
-                            .iter()
-                            .find(|f| f.ends_with(".md"))
-                            .map(|f| output_dir.join(f));
-                        let json_path = files
-                            .iter()
-                            .find(|f| f.ends_with(".json"))
-                            .map(|f| output_dir.join(f));
-
-                        let mut file_paths = format!("- Text: {}", txt_path.display());
-                        if let Some(md) = md_path {
-                            file_paths.push_str(&format!("\n- Markdown: {}", md.display()));
-                        }
-                        if let Some(json) = json_path {
-                            file_paths.push_str(&format!("\n- JSON: {}", json.display()));
-                        }
-
-                        let text = format!(
-                            "📄 **Latest Transcript:**\n\n\
-                            **Title:** {}\n\
-                            **Video ID:** {}\n\
-                            **Modified:** {}\n\
-                            **Size:** {:.2} KB\n\
-                            **Files:** {} ({})\n\n\
-                            **File Paths:**\n{}\n\n\
-                            💡 Tip: Use the text file path above to read or summarize this transcript.",
-                            title,
-                            video_id,
-                            format_timestamp(modified),
-                            size_kb,
-                            files.len(),
-                            extensions.join(", "),
-                            file_paths
-                        );
+                let invalid = |e: anyhow::Error| {
+                    ErrorData::new(ErrorCode::INVALID_PARAMS, format!("{:#}", e), None)
+                };
+                check_output_root(&output_dir)?;
+                let path = transcript_file(&output_dir, path).map_err(invalid)?;
+                let audio_path = args
+                    .get("audio_path")
+                    .and_then(|v| v.as_str())
+                    .map(|p| redactable_audio(&output_dir, p))
+                    .transpose()
+                    .map_err(invalid)?;
 
-                        Ok(CallToolResult::success(vec![Content::text(text)]))
-                    } else {
-                        Err(ErrorData::new(
-                            ErrorCode::INTERNAL_ERROR,
-                            "Failed to read latest transcript metadata".to_string(),
-                            None,
-                        ))
-                    }
+                let redaction = redact::redact_range(&path, &range).map_err(|e| {
+                    ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("{:#}", e), None)
+                })?;
+                let mut text = if redaction.segments == 0 {
+                    format!("Nothing left to redact between {}.", range)
                 } else {
-                    let text = "📂 No transcripts found.".to_string();
-                    Ok(CallToolResult::success(vec![Content::text(text)]))
+                    format!(
+                        "🔒 Redacted {} segment(s) between {} in:\n{}",
+                        redaction.segments,
+                        range,
+                        redaction
+                            .files
+                            .iter()
+                            .map(|f| format!("- {}", f.display()))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    )
+                };
+                if let Some(audio) = audio_path {
+                    redact::mute_audio(&audio, &range).await.map_err(|e| {
+                        ErrorData::new(ErrorCode::INTERNAL_ERROR, format!("{:#}", e), None)
+                    })?;
+                    text.push_str(&format!("\n\n🔇 Silenced the range in {}", audio.display()));
                 }
+                Ok(CallToolResult::success(vec![Content::text(text)]))
             }
 
             "delete_transcript" => {
-                use std::fs;
                 use std::path::PathBuf;
 
                 let args = request.arguments.as_ref().ok_or_else(|| {
@@ -683,20 +2459,15 @@ impl ServerHandler for VideoTranscriberServer {
                     return Ok(CallToolResult::success(vec![Content::text(text)]));
                 }
 
-                // Find all files matching the video_id
-                let mut deleted_files = Vec::new();
-                if let Ok(entries) = fs::read_dir(&output_dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-                        if filename.starts_with(&format!("{}-", video_id))
-                            && fs::remove_file(&path).is_ok()
-                        {
-                            deleted_files.push(path.display().to_string());
-                        }
-                    }
-                }
+                let entries = indexed_transcripts(&output_dir).map_err(library_error)?;
+                let deleted_files: Vec<String> = delete_outputs(
+                    &output_dir,
+                    entries.iter().filter(|e| e.video_id == video_id),
+                    |_| true,
+                )
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
 
                 if deleted_files.is_empty() {
                     let text = format!("⚠️ No transcripts found for video ID: {}", video_id);
@@ -749,21 +2520,16 @@ impl ServerHandler for VideoTranscriberServer {
                 }
 
                 let cutoff_time = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
-                let mut deleted_files = Vec::new();
-
-                if let Ok(entries) = fs::read_dir(&output_dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-
-                        if let Ok(metadata) = fs::metadata(&path)
-                            && let Ok(modified) = metadata.modified()
-                            && modified < cutoff_time
-                            && fs::remove_file(&path).is_ok()
-                        {
-                            deleted_files.push(path.display().to_string());
-                        }
-                    }
-                }
+                let entries = indexed_transcripts(&output_dir).map_err(library_error)?;
+                let deleted_files: Vec<String> =
+                    delete_outputs(&output_dir, entries.iter(), |path| {
+                        fs::metadata(path)
+                            .and_then(|metadata| metadata.modified())
+                            .is_ok_and(|modified| modified < cutoff_time)
+                    })
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
 
                 if deleted_files.is_empty() {
                     let text = format!("✅ No transcripts older than {} days found.", days);
@@ -784,7 +2550,6 @@ impl ServerHandler for VideoTranscriberServer {
             }
 
             "delete_all_transcripts" => {
-                use std::fs;
                 use std::path::PathBuf;
 
                 let args = request.arguments.as_ref().ok_or_else(|| {
@@ -816,15 +2581,8 @@ impl ServerHandler for VideoTranscriberServer {
                     return Ok(CallToolResult::success(vec![Content::text(text)]));
                 }
 
-                let mut deleted_count = 0;
-                if let Ok(entries) = fs::read_dir(&output_dir) {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        if path.is_file() && fs::remove_file(&path).is_ok() {
-                            deleted_count += 1;
-                        }
-                    }
-                }
+                let entries = indexed_transcripts(&output_dir).map_err(library_error)?;
+                let deleted_count = delete_outputs(&output_dir, entries.iter(), |_| true).len();
 
                 if deleted_count == 0 {
                     let text = "📂 No transcripts found to delete.".to_string();
@@ -848,8 +2606,965 @@ impl ServerHandler for VideoTranscriberServer {
     }
 }
 
+/// "- Audio tracks: …" settings line for multi-track sources, else empty.
+fn format_audio_tracks(result: &TranscriptionResult) -> String {
+    let container = result
+        .container
+        .as_ref()
+        .map(|c| format!("- Container: {}\n", c))
+        .unwrap_or_default();
+    if result.audio_tracks.len() < 2 {
+        let details = result
+            .audio_tracks
+            .first()
+            .map(|t| t.details())
+            .filter(|d| !d.is_empty())
+            .map(|d| format!("- Audio: {}\n", d))
+            .unwrap_or_default();
+        return container + &details;
+    }
+    let tracks: Vec<String> = result.audio_tracks.iter().map(|t| t.label()).collect();
+    let transcribed: Vec<String> = result
+        .transcribed_tracks
+        .iter()
+        .map(|n| n.to_string())
+        .collect();
+    format!(
+        "{}- Audio tracks: {} (transcribed: {})\n",
+        container,
+        tracks.join(", "),
+        transcribed.join(", ")
+    )
+}
+
+/// "- Audio quality: …" settings line plus any warnings, else empty.
+fn format_audio_quality(result: &TranscriptionResult) -> String {
+    let Some(q) = &result.audio_quality else {
+        return String::new();
+    };
+    let mut text = format!("- Audio quality: {}\n", q.summary());
+    for w in &q.warnings {
+        text.push_str(&format!("  ⚠️ {}\n", w));
+    }
+    text
+}
+
+/// "- Non-speech: …" settings line when music / applause was found.
+fn format_non_speech(result: &TranscriptionResult) -> String {
+    if result.non_speech.is_empty() {
+        return String::new();
+    }
+    let regions: Vec<String> = result
+        .non_speech
+        .iter()
+        .map(|r| format!("{:?} {}s–{}s", r.class, r.start_ms / 1000, r.end_ms / 1000))
+        .collect();
+    format!("- Non-speech: {}\n", regions.join(", "))
+}
+
+/// Optional `schedule` argument of the batch tools.
+fn parse_schedule(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<batch::SchedulingPolicy>, ErrorData> {
+    args.get("schedule")
+        .and_then(|v| v.as_str())
+        .map(|s| s.parse())
+        .transpose()
+        .map_err(|e: anyhow::Error| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+/// `start_time` / `end_time` as seconds or `[hh:]mm:ss` strings.
+fn parse_clip(args: &serde_json::Map<String, serde_json::Value>) -> Result<ClipRange, ErrorData> {
+    let invalid = |e: String| ErrorData::new(ErrorCode::INVALID_PARAMS, e, None);
+    let clip: ClipRange = serde_json::from_value(json!({
+        "start_time": args.get("start_time"),
+        "end_time": args.get("end_time"),
+    }))
+    .map_err(|e| invalid(e.to_string()))?;
+    ClipRange::new(clip.start_time, clip.end_time).map_err(|e| invalid(e.to_string()))
+}
+
+/// The options of `common_option_properties` that `args` gives, on top of
+/// the defaults.
+fn parse_common_options(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<TranscriptionOptions, ErrorData> {
+    let mut options = TranscriptionOptions::default();
+    apply_common_options(args, &mut options)?;
+    Ok(options)
+}
+
+/// Replace the options of `common_option_properties` in `options` with the
+/// ones `args` gives, leaving the rest, e.g. a batch's saved options on a
+/// retry.
+fn apply_common_options(
+    args: &serde_json::Map<String, serde_json::Value>,
+    options: &mut TranscriptionOptions,
+) -> Result<(), ErrorData> {
+    let given = |key: &str| args.get(key).is_some_and(|v| !v.is_null());
+    if given("cookies_file") {
+        options.cookies_file = parse_cookies_file(args)?;
+    }
+    if let Some(browser) = args.get("cookies_from_browser").and_then(|v| v.as_str()) {
+        options.cookies_from_browser = Some(browser.to_string());
+    }
+    if given("proxy") {
+        options.proxy = parse_proxy(args)?;
+    }
+    if given("formats") {
+        options.formats = parse_formats(args)?;
+    }
+    if given("if_exists") {
+        options.if_exists = parse_if_exists(args)?;
+    }
+    if given("md_timestamps") {
+        options.md_timestamps = parse_md_timestamps(args)?;
+    }
+    if given("speaker_turns") {
+        options.speaker_turns = parse_speaker_turns(args)?;
+    }
+    if given("audio_filters") || given("preprocess") {
+        options.audio_filter = parse_audio_filters(args)?;
+    }
+    if given("glossary") {
+        options.glossary = parse_glossary(args)?;
+    }
+    if given("hotwords") {
+        options.hotwords = parse_hotwords(args)?;
+    }
+    if given("webhook_url") {
+        options.webhook_url = parse_webhook_url(args)?;
+    }
+    for (key, flag) in [
+        ("live_output", &mut options.live_output),
+        ("split_chapters", &mut options.split_chapters),
+        ("force", &mut options.force),
+    ] {
+        if let Some(value) = args.get(key).and_then(|v| v.as_bool()) {
+            *flag = value;
+        }
+    }
+    Ok(())
+}
+
+/// `formats`, checked; empty when not given.
+fn parse_formats(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<OutputFormat>, ErrorData> {
+    let Some(value) = args.get("formats") else {
+        return Ok(Vec::new());
+    };
+    let invalid = |msg: String| ErrorData::new(ErrorCode::INVALID_PARAMS, msg, None);
+    let names = value
+        .as_array()
+        .ok_or_else(|| invalid("formats must be an array of strings".to_string()))?;
+    let mut formats = Vec::new();
+    for name in names {
+        let format = name
+            .as_str()
+            .ok_or_else(|| invalid("formats must be an array of strings".to_string()))?
+            .parse::<OutputFormat>()
+            .map_err(|e| invalid(e.to_string()))?;
+        if !formats.contains(&format) {
+            formats.push(format);
+        }
+    }
+    if formats.is_empty() {
+        return Err(invalid("formats must name at least one format".to_string()));
+    }
+    Ok(formats)
+}
+
+fn parse_if_exists(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<IfExists, ErrorData> {
+    args.get("if_exists")
+        .and_then(|v| v.as_str())
+        .map(|s| s.parse())
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e: anyhow::Error| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+fn parse_md_timestamps(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<MdTimestamps, ErrorData> {
+    args.get("md_timestamps")
+        .and_then(|v| v.as_str())
+        .map(|s| s.parse())
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e: anyhow::Error| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+fn parse_speaker_turns(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<SpeakerTurns, ErrorData> {
+    args.get("speaker_turns")
+        .and_then(|v| v.as_str())
+        .map(|s| s.parse())
+        .transpose()
+        .map(Option::unwrap_or_default)
+        .map_err(|e: anyhow::Error| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+/// The transcript a tool's `path` or `video_id` names, else the newest one,
+/// loaded from its JSON output.
+fn load_stored_transcript(
+    args: &serde_json::Map<String, serde_json::Value>,
+    output_dir: &std::path::Path,
+) -> Result<store::StoredTranscript, ErrorData> {
+    let base = stored_transcript_base(args, output_dir)?;
+    store::load(&base).map_err(|e| {
+        ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            format!("{:#} (segment timings are read from the JSON output)", e),
+            None,
+        )
+    })
+}
+
+/// The base path of the transcript [`load_stored_transcript`] loads.
+fn stored_transcript_base(
+    args: &serde_json::Map<String, serde_json::Value>,
+    output_dir: &std::path::Path,
+) -> Result<std::path::PathBuf, ErrorData> {
+    let invalid = |msg: String| ErrorData::new(ErrorCode::INVALID_PARAMS, msg, None);
+    check_output_root(output_dir)?;
+    if let Some(path) = args.get("path").and_then(|v| v.as_str()) {
+        transcript_file(output_dir, path)
+            .map(|file| file.with_extension(""))
+            .map_err(|e| invalid(e.to_string()))
+    } else {
+        let video_id = args.get("video_id").and_then(|v| v.as_str());
+        let entries = indexed_transcripts(output_dir).map_err(library_error)?;
+        entries
+            .into_iter()
+            .filter(|e| video_id.is_none_or(|id| e.video_id == id))
+            .map(|e| output_dir.join(e.base))
+            .find(|base| store::file(base, OutputFormat::Json).is_file())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "No transcript with a JSON output{} in {}",
+                    video_id
+                        .map(|id| format!(" for video {}", id))
+                        .unwrap_or_default(),
+                    output_dir.display()
+                ))
+            })
+    }
+}
+
+/// Whether a summary can be written: by the client's LLM, or by a
+/// configured local model.
+fn can_summarize(peer: &Peer<RoleServer>) -> bool {
+    sampling::is_supported(peer) || Config::load().summarizer.is_some()
+}
+
+/// Summarize with the client's LLM via MCP sampling, or with the local
+/// llama.cpp model when the client can't sample or the config prefers it.
+async fn write_summary(
+    peer: &Peer<RoleServer>,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> anyhow::Result<String> {
+    match Config::load().summarizer {
+        Some(local) if local.prefer_local || !sampling::is_supported(peer) => {
+            #[cfg(feature = "llama")]
+            return llama::summarize(&local, metadata, segments).await;
+            #[cfg(not(feature = "llama"))]
+            anyhow::bail!(
+                "A local summarizer ({}) is configured, but this server was built without the `llama` feature",
+                local.model_path
+            );
+        }
+        _ => sampling::summarize(peer, metadata, segments).await,
+    }
+}
+
+/// Suggest a title, description and tags the way [`write_summary`]
+/// summarizes, and save them in the JSON output under `base`.
+async fn save_suggestion(
+    peer: &Peer<RoleServer>,
+    base: &std::path::Path,
+    metadata: &VideoMetadata,
+    segments: &[Segment],
+) -> anyhow::Result<Suggestion> {
+    let suggestion = match Config::load().summarizer {
+        Some(local) if local.prefer_local || !sampling::is_supported(peer) => {
+            #[cfg(feature = "llama")]
+            {
+                llama::suggest(&local, metadata, segments).await?
+            }
+            #[cfg(not(feature = "llama"))]
+            anyhow::bail!(
+                "A local summarizer ({}) is configured, but this server was built without the `llama` feature",
+                local.model_path
+            );
+        }
+        _ => sampling::suggest(peer, metadata, segments).await?,
+    };
+    store::save_suggestion(base, &suggestion)?;
+    Ok(suggestion)
+}
+
+fn format_suggestion(suggestion: &Suggestion) -> String {
+    format!(
+        "- Title: {}\n- Description: {}\n- Tags: {}\n\n_Suggested by {}._",
+        suggestion.title,
+        suggestion.description,
+        suggestion.tags.join(", "),
+        suggestion.model
+    )
+}
+
+fn format_refreshed(refreshed: &[Refreshed]) -> String {
+    if refreshed.is_empty() {
+        return "📂 No transcripts of video pages to refresh.".to_string();
+    }
+    let changed: Vec<&Refreshed> = refreshed.iter().filter(|r| !r.changes.is_empty()).collect();
+    let failed: Vec<&Refreshed> = refreshed.iter().filter(|r| r.error.is_some()).collect();
+    let mut text = format!(
+        "🔄 Checked {} transcript(s): {} updated, {} unchanged, {} failed\n",
+        refreshed.len(),
+        changed.len(),
+        refreshed.len() - changed.len() - failed.len(),
+        failed.len()
+    );
+    for r in changed {
+        let changes: Vec<String> = r
+            .changes
+            .iter()
+            .map(|c| format!("{}: \"{}\" → \"{}\"", c.field, c.from, c.to))
+            .collect();
+        text.push_str(&format!(
+            "\n- **{}** ({}): {}",
+            r.title,
+            r.video_id,
+            changes.join("; ")
+        ));
+    }
+    for r in failed {
+        text.push_str(&format!(
+            "\n- ⚠️ {} ({}): {}",
+            r.title,
+            r.video_id,
+            r.error.as_deref().unwrap_or_default()
+        ));
+    }
+    text
+}
+
+/// An `ErrorData` for a client that can't take sampling requests when no
+/// local summarizer is configured either.
+fn sampling_unsupported() -> ErrorData {
+    ErrorData::new(
+        ErrorCode::INVALID_REQUEST,
+        "Summaries and suggestions are written by the client's LLM via MCP sampling, which this client does not support, and no local `summarizer` is configured".to_string(),
+        None,
+    )
+}
+
+/// `grammar`, compiled once here so a bad pattern fails the call up front.
+fn parse_grammar(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<String>, ErrorData> {
+    let Some(pattern) = args.get("grammar").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    Grammar::new(pattern)
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, format!("{:#}", e), None))?;
+    Ok(Some(pattern.to_string()))
+}
+
+/// `audio_filters` (or `preprocess`) with presets expanded.
+fn parse_audio_filters(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<String>, ErrorData> {
+    let Some(spec) = args
+        .get("audio_filters")
+        .or_else(|| args.get("preprocess"))
+        .and_then(|v| v.as_str())
+    else {
+        return Ok(None);
+    };
+    audio::filter_chain(spec)
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+/// `glossary`, checked so a bad entry fails the call rather than the
+/// transcription.
+fn parse_glossary(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<BTreeMap<String, String>, ErrorData> {
+    let Some(value) = args.get("glossary") else {
+        return Ok(BTreeMap::new());
+    };
+    let invalid = |message: String| ErrorData::new(ErrorCode::INVALID_PARAMS, message, None);
+    let glossary: BTreeMap<String, String> = serde_json::from_value(value.clone())
+        .map_err(|_| invalid("glossary must be an object of strings".to_string()))?;
+    Glossary::new(&glossary).map_err(|e| invalid(format!("{:#}", e)))?;
+    Ok(glossary)
+}
+
+/// `hotwords`, an array of strings.
+fn parse_hotwords(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Vec<String>, ErrorData> {
+    let Some(value) = args.get("hotwords") else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_value(value.clone()).map_err(|_| {
+        ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            "hotwords must be an array of strings".to_string(),
+            None,
+        )
+    })
+}
+
+/// `cookies_file`, resolved in the config file's `cookies_dir`.
+fn parse_cookies_file(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<String>, ErrorData> {
+    let Some(name) = args
+        .get("cookies_file")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let path = downloader::request_cookies_file(name)
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, format!("{:#}", e), None))?;
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+/// `proxy`, checked so a typo fails the call rather than every download.
+fn parse_proxy(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<String>, ErrorData> {
+    let Some(proxy) = args.get("proxy").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    reqwest::Proxy::all(proxy).map_err(|e| {
+        ErrorData::new(
+            ErrorCode::INVALID_PARAMS,
+            format!("Invalid proxy {}: {}", proxy, e),
+            None,
+        )
+    })?;
+    Ok(Some(proxy.to_string()))
+}
+
+/// `webhook_url`, checked so a typo fails the call rather than the
+/// notification.
+fn parse_webhook_url(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<String>, ErrorData> {
+    let Some(url) = args.get("webhook_url").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    webhook::check_url(url)
+        .map(|_| Some(url.to_string()))
+        .map_err(|e| ErrorData::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))
+}
+
+/// Where `split_chapters` put the per-chapter transcripts, if it wrote any.
+fn format_chapter_files(chapters: &[OutputFiles]) -> String {
+    let Some(first) = chapters.first() else {
+        return String::new();
+    };
+    let dir = std::path::Path::new(first.primary())
+        .parent()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    format!("- Chapters: {} transcript(s) in {}\n", chapters.len(), dir)
+}
+
+fn format_output_files(files: &OutputFiles) -> String {
+    files
+        .0
+        .iter()
+        .map(|(format, path)| {
+            let label = match format {
+                OutputFormat::Txt => "Text",
+                OutputFormat::Json => "JSON",
+                OutputFormat::Md => "Markdown",
+                OutputFormat::Srt => "SRT",
+                OutputFormat::Vtt => "WebVTT",
+            };
+            format!("- {}: {}\n", label, path)
+        })
+        .collect()
+}
+
+/// Where the output sink uploaded copies of the outputs, if it did.
+fn format_uploaded_files(files: &OutputFiles) -> String {
+    files
+        .0
+        .iter()
+        .map(|(format, url)| format!("- Uploaded {}: {}\n", format.as_str(), url))
+        .collect()
+}
+
+/// Human-readable summary of a batch report for tool responses.
+fn format_batch_report(report: &BatchReport, output_dir: &str) -> String {
+    let items = report
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let detail = match item.status {
+                BatchItemStatus::Complete => format!(
+                    "✅ {}\n   Transcript: {}",
+                    item.title.as_deref().unwrap_or(&item.url),
+                    item.transcript_path.as_deref().unwrap_or("?")
+                ),
+                BatchItemStatus::Failed => format!(
+                    "❌ {}\n   Error: {}",
+                    item.url,
+                    item.error.as_deref().unwrap_or("unknown error")
+                ),
+                BatchItemStatus::Pending => format!("⏸️ {} (not attempted)", item.url),
+            };
+            let usage = item
+                .usage
+                .as_ref()
+                .map(|u| format!("\n   Resources: {}", u.summary()))
+                .unwrap_or_default();
+            format!(
+                "{}. {} (attempts: {}){}",
+                i + 1,
+                detail,
+                item.attempts,
+                usage
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let failed = report.count(BatchItemStatus::Failed);
+    let tip = if failed > 0 {
+        format!(
+            "\n\n💡 Tip: call retry_failed with batch_id '{}' to re-run the {} failed item(s).",
+            report.batch_id, failed
+        )
+    } else {
+        String::new()
+    };
+
+    format!(
+        "📚 Batch {}: {} complete, {} failed, {} total\nResources: {}\n\n{}\n\nReport: {}{}",
+        report.batch_id,
+        report.count(BatchItemStatus::Complete),
+        failed,
+        report.items.len(),
+        report.total_usage().summary(),
+        items,
+        batch::report_path(std::path::Path::new(output_dir), &report.batch_id)
+            .map(|p| p.display().to_string())
+            .unwrap_or_default(),
+        tip
+    )
+}
+
+/// An error unless `output_dir` is the configured `output_root` (else the
+/// default output directory) or inside it. The tools reading transcripts
+/// only look there, so a client can't aim them at other directories on
+/// the server.
+fn check_output_root(output_dir: &std::path::Path) -> Result<(), ErrorData> {
+    let root = Config::load()
+        .output_root
+        .unwrap_or_else(get_default_output_dir);
+    if is_within(&root, output_dir) {
+        return Ok(());
+    }
+    Err(ErrorData::new(
+        ErrorCode::INVALID_PARAMS,
+        format!(
+            "Output directory not found or not inside {}: {}",
+            root.display(),
+            output_dir.display()
+        ),
+        None,
+    ))
+}
+
+/// Whether `dir` exists and is `root` or inside it, however either is
+/// spelled.
+fn is_within(root: &std::path::Path, dir: &std::path::Path) -> bool {
+    match (root.canonicalize(), dir.canonicalize()) {
+        (Ok(root), Ok(dir)) => dir.starts_with(root),
+        _ => false,
+    }
+}
+
+/// `name` resolved to a transcript file inside `output_dir`. Only
+/// txt / md / json files under that directory (including the
+/// subdirectories an `output_layout` creates) that its library indexes as
+/// a transcript's outputs are served, however the path is spelled, so
+/// `output_dir` can't be pointed at other files on the server. The index
+/// is only read: a directory without one has no transcripts to serve.
+fn transcript_file(output_dir: &std::path::Path, name: &str) -> Result<std::path::PathBuf> {
+    let dir = output_dir
+        .canonicalize()
+        .map_err(|_| anyhow::anyhow!("Output directory not found: {}", output_dir.display()))?;
+    let path = dir
+        .join(name)
+        .canonicalize()
+        .map_err(|_| anyhow::anyhow!("Transcript not found: {}", name))?;
+    let is_transcript = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e, "txt" | "md" | "json"));
+    if !path.starts_with(&dir) || !is_transcript || !is_indexed(&dir, &path)? {
+        anyhow::bail!("Not a transcript in {}: {}", dir.display(), name);
+    }
+    Ok(path)
+}
+
+/// Whether `path` is one of the outputs the library of `dir` lists.
+fn is_indexed(dir: &std::path::Path, path: &std::path::Path) -> Result<bool> {
+    let path = path.to_string_lossy();
+    Ok(indexed_transcripts(dir)?
+        .iter()
+        .any(|entry| entry.files(dir).0.values().any(|file| *file == path)))
+}
+
+/// Delete the output files of `entries` in `output_dir` that `doomed`
+/// picks, then the directories that left empty. Only what the library
+/// lists is touched, not other files that happen to share an extension.
+/// Returns the files deleted.
+fn delete_outputs<'a>(
+    output_dir: &std::path::Path,
+    entries: impl Iterator<Item = &'a LibraryEntry>,
+    doomed: impl Fn(&std::path::Path) -> bool,
+) -> Vec<std::path::PathBuf> {
+    let deleted: Vec<std::path::PathBuf> = entries
+        .flat_map(|entry| store::files(&output_dir.join(&entry.base)).0.into_values())
+        .map(std::path::PathBuf::from)
+        .filter(|path| doomed(path) && std::fs::remove_file(path).is_ok())
+        .collect();
+    remove_emptied_dirs(output_dir, &deleted);
+    deleted
+}
+
+/// The transcripts the library of `dir` lists, newest first, read without
+/// creating or syncing the index.
+fn indexed_transcripts(dir: &std::path::Path) -> Result<Vec<LibraryEntry>> {
+    match Library::open_read_only(dir)? {
+        Some(library) => library.list(None),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// An audio file `redact_range` may rewrite: one in the output directory or
+/// in a downloaded-audio temp dir, not anywhere on the server.
+fn redactable_audio(output_dir: &std::path::Path, path: &str) -> Result<std::path::PathBuf> {
+    let path = output_dir
+        .join(path)
+        .canonicalize()
+        .map_err(|_| anyhow::anyhow!("Audio file not found: {}", path))?;
+    let in_output_dir = output_dir
+        .canonicalize()
+        .is_ok_and(|dir| path.starts_with(dir));
+    let in_audio_cache = path.parent().is_some_and(|dir| {
+        dir.file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with(AUDIO_DIR_PREFIX))
+            && std::env::temp_dir()
+                .canonicalize()
+                .is_ok_and(|tmp| dir.parent() == Some(tmp.as_path()))
+    });
+    if !path.is_file() || !(in_output_dir || in_audio_cache) {
+        anyhow::bail!(
+            "audio_path must be a file in the output directory or the downloaded-audio cache"
+        );
+    }
+    Ok(path)
+}
+
+/// Batch summary plus the files skipped as already transcribed.
+pub fn format_directory_run(run: &batch::DirectoryRun, output_dir: &str) -> String {
+    let mut text = format_batch_report(&run.report, output_dir);
+    if !run.skipped.is_empty() {
+        text.push_str(&format!(
+            "\n\n⏭️ Skipped {} already transcribed file(s):\n{}",
+            run.skipped.len(),
+            run.skipped
+                .iter()
+                .map(|f| format!("- {}", f))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+    text
+}
+
+fn format_feed(feed: &Feed) -> String {
+    let episodes = feed
+        .episodes
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            format!(
+                "{}. {}\n   Published: {}\n   Guid: {}",
+                i + 1,
+                e.title,
+                e.published.as_deref().unwrap_or("unknown"),
+                e.guid
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    format!(
+        "🎙️ {} ({} episodes, newest first)\n\n{}",
+        feed.title,
+        feed.episodes.len(),
+        episodes
+    )
+}
+
+/// A library entry as listed by `list_transcripts`.
+fn format_entry(entry: &LibraryEntry, output_dir: &std::path::Path) -> String {
+    let files = entry.files(output_dir);
+    let extensions: Vec<&str> = entry.formats.iter().map(|f| f.as_str()).collect();
+    let size_kb = files
+        .0
+        .values()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|m| m.len())
+        .sum::<u64>() as f64
+        / 1024.0;
+    format!(
+        "**{}**\n   Video ID: {}\n   Channel: {} ({})\n   Model: {}, language: {}, {} words\n   Files: {} ({})\n   Size: {:.2} KB\n   Saved: {}\n   Path: {}",
+        entry.title,
+        entry.video_id,
+        entry.channel,
+        entry.platform,
+        entry.model,
+        entry.language.as_deref().unwrap_or("unknown"),
+        entry.word_count,
+        extensions.len(),
+        extensions.join(", "),
+        size_kb,
+        format_timestamp(entry.saved_at),
+        files.primary()
+    )
+}
+
+fn library_error(e: anyhow::Error) -> ErrorData {
+    ErrorData::new(
+        ErrorCode::INTERNAL_ERROR,
+        format!("Failed to read the transcript library: {:#}", e),
+        None,
+    )
+}
+
 fn format_timestamp(timestamp: u64) -> String {
     use chrono::{DateTime, TimeZone, Utc};
     let dt: DateTime<Utc> = Utc.timestamp_opt(timestamp as i64, 0).unwrap();
     dt.format("%Y-%m-%d").to_string()
 }
+
+/// Schema of the options every transcribing tool takes, read by
+/// `parse_common_options`. Merged into each tool's own properties.
+fn common_option_properties() -> serde_json::Value {
+    merge_objects([
+        json!({
+            "cookies_file": {
+                "type": "string",
+                "description": "Optional. Name of a Netscape-format cookies file in the server's configured cookies_dir, for age-restricted, members-only or private videos. yt-dlp gets a copy. Default: the config file's, else YT_DLP_COOKIES"
+            },
+            "cookies_from_browser": {
+                "type": "string",
+                "description": "Optional. Browser on the server to read cookies from (e.g. 'firefox', 'chrome:Profile 1'). Ignored when cookies_file is given. Default: the config file's, else YT_DLP_COOKIES_FROM_BROWSER"
+            },
+            "proxy": {
+                "type": "string",
+                "description": "Optional. HTTP or SOCKS proxy for downloads, e.g. 'http://proxy.corp:3128' or 'socks5://127.0.0.1:1080'. Default: the config file's, else DOWNLOAD_PROXY"
+            }
+        }),
+        json!({
+            "formats": {
+                "type": "array",
+                "items": { "type": "string", "enum": ["txt", "json", "md", "srt", "vtt"] },
+                "description": "Optional. Files to write, e.g. ['txt', 'srt']. Default: ['txt', 'json', 'md']"
+            },
+            "if_exists": {
+                "type": "string",
+                "enum": ["overwrite", "skip", "version"],
+                "description": "Optional. When this video already has a transcript: 'overwrite' it (default), 'skip' to return it as is if the same model made it, or 'version' to keep it and write '<name>-2'"
+            },
+            "audio_filters": {
+                "type": "string",
+                "description": "Optional. ffmpeg preprocessing before local Whisper: a preset ('phone-call', 'lecture-hall', 'denoise', 'rnnoise', 'loudnorm'), a raw '-af' chain such as 'highpass=f=80,volume=2dB', or both comma-separated. Overrides the channel's audio profile"
+            },
+            "glossary": {
+                "type": "object",
+                "additionalProperties": { "type": "string" },
+                "description": "Optional. Misrecognition → canonical spelling replacements applied to every output, e.g. {\"cube ernetes\": \"Kubernetes\"}. Case-insensitive, whole words only; added to the config file's glossary. Replacements are listed in the JSON output"
+            },
+            "hotwords": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Optional. Names and terms to expect, e.g. [\"Kubernetes\", \"PostgreSQL\"]. Given to local Whisper as its initial prompt to favour those spellings, then restored in every output when written in another case or split at capitals ('Postgre SQL'). Glossary entries win. Default: the platform profile's hotwords"
+            },
+            "live_output": {
+                "type": "boolean",
+                "description": "Optional. Append segments to the txt and srt outputs while local Whisper runs, so a long transcript can be read before it's done; the finished files replace them at the end. Not with encryption at rest or several audio tracks. Default: false"
+            },
+            "split_chapters": {
+                "type": "boolean",
+                "description": "Optional. Also write one transcript per chapter, timed from the chapter's start, to a <name>-chapters folder next to the outputs. Uses the video's chapters, else generates roughly 10-minute ones named after their topics. Default: false"
+            },
+            "webhook_url": {
+                "type": "string",
+                "description": "Optional. URL to POST a JSON summary to when each transcription finishes or fails (metadata, output paths or the error), must be a public http(s) host and is not signed. Default: the config file's webhook.url, signed with the server's webhook secret if it has one"
+            },
+            "md_timestamps": {
+                "type": "string",
+                "enum": ["off", "paragraph", "segment"],
+                "description": "Optional. [MM:SS] markers in the Markdown output: 'paragraph' marks where each paragraph starts, 'segment' puts every segment on its own marked line. Default: 'off'"
+            },
+            "speaker_turns": {
+                "type": "string",
+                "enum": ["off", "speaker", "qa"],
+                "description": "Optional. Lay out the txt and Markdown outputs by speaker: 'speaker' for 'Speaker 1 (MM:SS): …' turns, 'qa' for interviews, with the first speaker's turns as 'Q:' and the rest as 'A:'. Speakers come from the tracks of a multi-track file transcribed with audio_track 'all', or from a tinydiarize model (e.g. model_path 'ggml-small.en-tdrz.bin'). Default: 'off'"
+            },
+            "force": {
+                "type": "boolean",
+                "description": "Optional. Transcribe even if the same request was already transcribed; by default the cached transcript is returned. Default: false"
+            }
+        }),
+    ])
+}
+
+/// The entries of several JSON objects in one, for tool schemas too long to
+/// write as a single `json!` literal.
+fn merge_objects(parts: impl IntoIterator<Item = serde_json::Value>) -> serde_json::Value {
+    let mut merged = serde_json::Map::new();
+    for part in parts {
+        if let serde_json::Value::Object(map) = part {
+            merged.extend(map);
+        }
+    }
+    serde_json::Value::Object(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A transcript saved as `<name>.json` and `<name>.txt` in `dir`.
+    fn save_transcript(dir: &std::path::Path, name: &str) {
+        let json = json!({
+            "metadata": {
+                "video_id": "abc", "title": "Talk", "channel": "", "duration": 60,
+                "upload_date": "", "platform": "YouTube", "url": "https://youtu.be/abc",
+            },
+            "transcript": "Hello there.",
+            "segments": [],
+            "model": "base",
+        });
+        std::fs::write(dir.join(format!("{}.json", name)), json.to_string()).unwrap();
+        std::fs::write(dir.join(format!("{}.txt", name)), "Hello there.").unwrap();
+    }
+
+    #[test]
+    fn serves_only_indexed_transcripts() {
+        let dir = tempfile::tempdir().unwrap();
+        save_transcript(dir.path(), "abc-Talk");
+        std::fs::write(dir.path().join("config.json"), r#"{"api_keys":[]}"#).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "secret").unwrap();
+
+        // Reading neither creates an index nor builds one from the files.
+        assert!(transcript_file(dir.path(), "abc-Talk.txt").is_err());
+        assert!(!dir.path().join(".library.sqlite").exists());
+        Library::open(dir.path()).unwrap();
+
+        let file = transcript_file(dir.path(), "abc-Talk.txt").unwrap();
+        assert_eq!(file.file_name().unwrap(), "abc-Talk.txt");
+        assert!(transcript_file(dir.path(), "abc-Talk.json").is_ok());
+        assert!(transcript_file(dir.path(), "config.json").is_err());
+        assert!(transcript_file(dir.path(), "notes.txt").is_err());
+        assert!(transcript_file(dir.path(), "../abc-Talk.txt").is_err());
+    }
+
+    #[test]
+    fn serves_transcripts_in_layout_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("YouTube").join("Some Channel");
+        std::fs::create_dir_all(&nested).unwrap();
+        save_transcript(&nested, "abc-Talk");
+        Library::open(dir.path()).unwrap();
+
+        let file = transcript_file(dir.path(), "YouTube/Some Channel/abc-Talk.txt").unwrap();
+        assert_eq!(file, nested.canonicalize().unwrap().join("abc-Talk.txt"));
+        let absolute = nested.join("abc-Talk.json");
+        assert!(transcript_file(dir.path(), absolute.to_str().unwrap()).is_ok());
+        // Not outside the output directory, even when that holds a library.
+        assert!(transcript_file(&nested, "../../abc-Talk.txt").is_err());
+    }
+
+    #[test]
+    fn deletes_only_indexed_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("YouTube").join("2024");
+        std::fs::create_dir_all(&nested).unwrap();
+        save_transcript(&nested, "abc-Talk");
+        Library::open(dir.path()).unwrap();
+        let notes = dir.path().join("notes").join("todo.txt");
+        std::fs::create_dir_all(notes.parent().unwrap()).unwrap();
+        std::fs::write(&notes, "keep me").unwrap();
+        std::fs::create_dir_all(dir.path().join(".hidden")).unwrap();
+
+        let entries = indexed_transcripts(dir.path()).unwrap();
+        let deleted = delete_outputs(dir.path(), entries.iter(), |_| true);
+        assert_eq!(deleted.len(), 2);
+        assert!(!dir.path().join("YouTube").exists());
+        assert!(notes.exists());
+        assert!(dir.path().join(".hidden").exists());
+        assert!(dir.path().join(".library.sqlite").exists());
+    }
+
+    #[test]
+    fn confines_output_dirs_to_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("transcripts");
+        std::fs::create_dir_all(root.join("YouTube")).unwrap();
+
+        assert!(is_within(&root, &root));
+        assert!(is_within(&root, &root.join("YouTube")));
+        assert!(is_within(&root, &root.join("YouTube/..")));
+        assert!(!is_within(&root, dir.path()));
+        assert!(!is_within(&root, &root.join("..")));
+        assert!(!is_within(&root, &root.join("missing")));
+    }
+
+    #[test]
+    fn common_options_override_only_what_is_given() {
+        let saved = BatchOptions {
+            language: Some("de".into()),
+            proxy: Some("http://proxy.corp:3128".into()),
+            formats: vec![OutputFormat::Srt],
+            hotwords: vec!["Kubernetes".into()],
+            split_chapters: true,
+            ..Default::default()
+        };
+        let mut options = saved.to_options("/out".into());
+        let args = json!({
+            "batch_id": "b",
+            "proxy": "socks5://127.0.0.1:1080",
+            "split_chapters": false,
+        });
+        apply_common_options(args.as_object().unwrap(), &mut options).unwrap();
+        assert_eq!(options.language.as_deref(), Some("de"));
+        assert_eq!(options.proxy.as_deref(), Some("socks5://127.0.0.1:1080"));
+        assert!(!options.split_chapters);
+        assert_eq!(options.formats, [OutputFormat::Srt]);
+        assert_eq!(options.hotwords, ["Kubernetes"]);
+
+        let bad = json!({ "proxy": "not a proxy" });
+        assert!(apply_common_options(bad.as_object().unwrap(), &mut options).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Resuming HTTP MCP sessions after their connection drops.
+//!
+//! rmcp keeps each Streamable HTTP session in memory and closes it after
+//! `idle_minutes` without traffic (five by default). A client that comes
+//! back later, after a network blip or a server restart, gets a 404 for
+//! its `Mcp-Session-Id` and has to start over. With a `sessions` store in
+//! the config file, the client's `initialize` parameters are saved when
+//! the session starts. A request for a session rmcp no longer has then
+//! re-creates it from the store and replays the handshake, so the client
+//! carries on as if nothing happened. The client's capabilities, such as
+//! sampling and progress, are restored with it.
+//!
+//! `memory` keeps the parameters while the server runs; `sqlite` keeps
+//! them across restarts. A stored session can be resumed for `ttl_minutes`
+//! after it started or was last resumed. Closing a session (an HTTP
+//! DELETE) removes it from the store.
+
+use anyhow::{Context, Result};
+use rmcp::transport::streamable_http_server::session::{
+    SessionState, SessionStore, SessionStoreError, local::LocalSessionManager,
+};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::{SessionPolicy, SessionStoreKind};
+use crate::utils::paths::get_sessions_path;
+
+/// rmcp's session manager, closing sessions after the configured idle time.
+pub fn manager(policy: Option<&SessionPolicy>) -> LocalSessionManager {
+    let mut manager = LocalSessionManager::default();
+    if let Some(minutes) = policy.and_then(|p| p.idle_minutes) {
+        manager.session_config.keep_alive = Some(Duration::from_secs(minutes * 60));
+    }
+    manager
+}
+
+/// The configured session store, if any.
+pub fn store(policy: Option<&SessionPolicy>) -> Result<Option<Arc<dyn SessionStore>>> {
+    let Some(policy) = policy else {
+        return Ok(None);
+    };
+    let ttl = Duration::from_secs(policy.ttl_minutes * 60);
+    Ok(Some(match policy.store {
+        SessionStoreKind::Memory => Arc::new(MemoryStore::new(ttl)),
+        SessionStoreKind::Sqlite => {
+            let path = policy.path.clone().unwrap_or_else(get_sessions_path);
+            Arc::new(SqliteStore::open(&path, ttl)?)
+        }
+    }))
+}
+
+/// Sessions kept while the server runs.
+pub struct MemoryStore {
+    ttl: Duration,
+    sessions: Mutex<HashMap<String, (Instant, SessionState)>>,
+}
+
+impl MemoryStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Instant, SessionState)>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionStore for MemoryStore {
+    async fn load(&self, session_id: &str) -> Result<Option<SessionState>, SessionStoreError> {
+        let mut sessions = self.sessions();
+        let ttl = self.ttl;
+        sessions.retain(|_, (saved, _)| saved.elapsed() < ttl);
+        Ok(sessions.get_mut(session_id).map(|(saved, state)| {
+            *saved = Instant::now();
+            state.clone()
+        }))
+    }
+
+    async fn store(&self, session_id: &str, state: &SessionState) -> Result<(), SessionStoreError> {
+        self.sessions()
+            .insert(session_id.to_string(), (Instant::now(), state.clone()));
+        Ok(())
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<(), SessionStoreError> {
+        self.sessions().remove(session_id);
+        Ok(())
+    }
+}
+
+/// Sessions kept in a SQLite file, across restarts.
+pub struct SqliteStore {
+    ttl: Duration,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path, ttl: Duration) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Can't create {}", dir.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Can't open the session store {}", path.display()))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS sessions (
+                 id TEXT PRIMARY KEY,
+                 state TEXT NOT NULL,
+                 saved_at INTEGER NOT NULL
+             )",
+        )?;
+        Ok(Self {
+            ttl,
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+#[async_trait::async_trait]
+impl SessionStore for SqliteStore {
+    async fn load(&self, session_id: &str) -> Result<Option<SessionState>, SessionStoreError> {
+        let conn = self.conn();
+        let now = now_secs();
+        conn.execute(
+            "DELETE FROM sessions WHERE saved_at < ?1",
+            params![now - self.ttl.as_secs() as i64],
+        )?;
+        let state: Option<String> = conn
+            .query_row(
+                "SELECT state FROM sessions WHERE id = ?1",
+                params![session_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(state) = state else {
+            return Ok(None);
+        };
+        conn.execute(
+            "UPDATE sessions SET saved_at = ?2 WHERE id = ?1",
+            params![session_id, now],
+        )?;
+        Ok(Some(serde_json::from_str(&state)?))
+    }
+
+    async fn store(&self, session_id: &str, state: &SessionState) -> Result<(), SessionStoreError> {
+        self.conn().execute(
+            "INSERT OR REPLACE INTO sessions (id, state, saved_at) VALUES (?1, ?2, ?3)",
+            params![session_id, serde_json::to_string(state)?, now_secs()],
+        )?;
+        Ok(())
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<(), SessionStoreError> {
+        self.conn()
+            .execute("DELETE FROM sessions WHERE id = ?1", params![session_id])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::{ClientCapabilities, Implementation, InitializeRequestParams};
+
+    fn state() -> SessionState {
+        SessionState::new(InitializeRequestParams::new(
+            ClientCapabilities::default(),
+            Implementation::new("test-client", "1.0"),
+        ))
+    }
+
+    #[tokio::test]
+    async fn sqlite_sessions_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.sqlite");
+        let store = SqliteStore::open(&path, Duration::from_secs(3600)).unwrap();
+        store.store("abc", &state()).await.unwrap();
+        drop(store);
+
+        let store = SqliteStore::open(&path, Duration::from_secs(3600)).unwrap();
+        let loaded = store.load("abc").await.unwrap().unwrap();
+        assert_eq!(loaded.initialize_params.client_info.name, "test-client");
+        store.delete("abc").await.unwrap();
+        assert!(store.load("abc").await.unwrap().is_none());
+
+        // Past the TTL, a session is gone.
+        let expired = SqliteStore::open(&path, Duration::ZERO).unwrap();
+        expired.store("old", &state()).await.unwrap();
+        expired
+            .conn()
+            .execute("UPDATE sessions SET saved_at = 0", [])
+            .unwrap();
+        assert!(expired.load("old").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn memory_sessions_expire() {
+        let store = MemoryStore::new(Duration::from_secs(3600));
+        store.store("abc", &state()).await.unwrap();
+        assert!(store.load("abc").await.unwrap().is_some());
+        assert!(store.load("other").await.unwrap().is_none());
+
+        let expired = MemoryStore::new(Duration::ZERO);
+        expired.store("abc", &state()).await.unwrap();
+        assert!(expired.load("abc").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,233 @@
+//! Process-wide counters for monitoring throughput.
+//!
+//! Over HTTP they are scraped as Prometheus text from `GET /metrics`; the
+//! `get_metrics` tool returns the same [`Snapshot`] as JSON, for stdio
+//! users and agents with no scrape target. Counters start at zero with the
+//! process. A transcription whose caller stopped it counts as started but
+//! neither completed nor failed.
+
+use axum::{
+    Router,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use serde::Serialize;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::mcp::policy;
+use crate::transcriber::types::TranscriptionResult;
+
+pub struct Metrics {
+    started: AtomicU64,
+    completed: AtomicU64,
+    failed: AtomicU64,
+    audio_ms: AtomicU64,
+    processing_ms: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    waiting: AtomicU64,
+    running: AtomicU64,
+}
+
+static METRICS: Metrics = Metrics {
+    started: AtomicU64::new(0),
+    completed: AtomicU64::new(0),
+    failed: AtomicU64::new(0),
+    audio_ms: AtomicU64::new(0),
+    processing_ms: AtomicU64::new(0),
+    bytes_downloaded: AtomicU64::new(0),
+    waiting: AtomicU64::new(0),
+    running: AtomicU64::new(0),
+};
+
+pub fn global() -> &'static Metrics {
+    &METRICS
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    pub transcriptions_started: u64,
+    pub transcriptions_completed: u64,
+    pub transcriptions_failed: u64,
+    /// Audio covered by completed transcripts, first segment to last.
+    pub audio_seconds: f64,
+    /// Wall time spent in transcriptions, completed or failed.
+    pub processing_seconds: f64,
+    pub bytes_downloaded: u64,
+    /// Calls waiting for the transcription slot right now.
+    pub jobs_waiting: u64,
+    /// Calls holding the transcription slot right now.
+    pub jobs_running: u64,
+}
+
+impl Metrics {
+    pub fn transcription_started(&self) {
+        self.started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn transcription_finished(
+        &self,
+        result: &anyhow::Result<TranscriptionResult>,
+        took: Duration,
+    ) {
+        self.processing_ms
+            .fetch_add(took.as_millis() as u64, Ordering::Relaxed);
+        match result {
+            Ok(result) => {
+                self.completed.fetch_add(1, Ordering::Relaxed);
+                let start = result.segments.iter().map(|s| s.start_ms).min();
+                let end = result.segments.iter().map(|s| s.end_ms).max();
+                if let (Some(start), Some(end)) = (start, end) {
+                    self.audio_ms
+                        .fetch_add(end.saturating_sub(start), Ordering::Relaxed);
+                }
+                self.bytes_downloaded
+                    .fetch_add(result.usage.bytes_downloaded, Ordering::Relaxed);
+            }
+            Err(_) => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn job_queued(&self) {
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn job_dequeued(&self) {
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn job_running(&self, running: bool) {
+        if running {
+            self.running.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.running.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            transcriptions_started: self.started.load(Ordering::Relaxed),
+            transcriptions_completed: self.completed.load(Ordering::Relaxed),
+            transcriptions_failed: self.failed.load(Ordering::Relaxed),
+            audio_seconds: self.audio_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+            processing_seconds: self.processing_ms.load(Ordering::Relaxed) as f64 / 1000.0,
+            bytes_downloaded: self.bytes_downloaded.load(Ordering::Relaxed),
+            jobs_waiting: self.waiting.load(Ordering::Relaxed),
+            jobs_running: self.running.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Prometheus text exposition format.
+    pub fn prometheus(&self) -> String {
+        let metrics: [(&str, &str, &str, f64); 8] = [
+            (
+                "transcriptions_started_total",
+                "counter",
+                "Transcriptions started.",
+                self.transcriptions_started as f64,
+            ),
+            (
+                "transcriptions_completed_total",
+                "counter",
+                "Transcriptions that produced a transcript.",
+                self.transcriptions_completed as f64,
+            ),
+            (
+                "transcriptions_failed_total",
+                "counter",
+                "Transcriptions that ended in an error.",
+                self.transcriptions_failed as f64,
+            ),
+            (
+                "audio_seconds_total",
+                "counter",
+                "Audio covered by completed transcripts.",
+                self.audio_seconds,
+            ),
+            (
+                "processing_seconds_total",
+                "counter",
+                "Wall time spent in transcriptions.",
+                self.processing_seconds,
+            ),
+            (
+                "downloaded_bytes_total",
+                "counter",
+                "Bytes downloaded for completed transcriptions.",
+                self.bytes_downloaded as f64,
+            ),
+            (
+                "jobs_waiting",
+                "gauge",
+                "Calls waiting for the transcription slot.",
+                self.jobs_waiting as f64,
+            ),
+            (
+                "jobs_running",
+                "gauge",
+                "Calls holding the transcription slot.",
+                self.jobs_running as f64,
+            ),
+        ];
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(out, "# HELP video_transcriber_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE video_transcriber_{} {}", name, kind);
+            let _ = writeln!(out, "video_transcriber_{} {}", name, value);
+        }
+        out
+    }
+}
+
+pub fn router() -> Router {
+    Router::new().route("/metrics", get(prometheus))
+}
+
+/// Takes the same API keys as `/mcp`; a key with a tool list needs
+/// `get_metrics` in it.
+async fn prometheus(headers: HeaderMap) -> Response {
+    match policy::headers_key(&headers) {
+        Err(msg) => return (StatusCode::UNAUTHORIZED, msg).into_response(),
+        Ok(Some(key)) if !key.allows("get_metrics") => {
+            return (StatusCode::FORBIDDEN, "this key may not read metrics").into_response();
+        }
+        Ok(_) => {}
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        global().snapshot().prometheus(),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_prometheus_text() {
+        let snapshot = Snapshot {
+            transcriptions_started: 3,
+            transcriptions_completed: 2,
+            transcriptions_failed: 1,
+            audio_seconds: 90.5,
+            processing_seconds: 12.25,
+            bytes_downloaded: 1024,
+            jobs_waiting: 0,
+            jobs_running: 1,
+        };
+        let text = snapshot.prometheus();
+        assert!(text.contains(
+            "# TYPE video_transcriber_transcriptions_started_total counter\n\
+             video_transcriber_transcriptions_started_total 3\n"
+        ));
+        assert!(text.contains("video_transcriber_audio_seconds_total 90.5\n"));
+        assert!(text.contains("# TYPE video_transcriber_jobs_running gauge\n"));
+    }
+}
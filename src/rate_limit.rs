@@ -0,0 +1,150 @@
+//! Request limits for `--transport http`, from the config file's
+//! `rate_limit` section.
+//!
+//! Each client gets `burst` requests at once and then `requests_per_minute`,
+//! with the rest answered 429. Clients are told apart by IP (from the proxy
+//! headers, else the peer address) or, with `by: "api_key"`, by the config
+//! API key they send, so users behind one NAT don't share a budget.
+//! Requests without a known key still count against their IP, so made-up
+//! tokens don't get fresh budgets. The limit covers `/api` unless
+//! `all_routes` is set.
+//!
+//! `max_concurrent_requests` caps the requests being handled at once, for
+//! all clients together. Past it the server answers 503 straight away
+//! instead of queueing, so one client can't tie up every connection. The
+//! health probes are exempt.
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tower_governor::{
+    GovernorError, GovernorLayer,
+    governor::GovernorConfigBuilder,
+    key_extractor::{KeyExtractor, SmartIpKeyExtractor},
+};
+use tracing::warn;
+
+use crate::config::{Config, RateLimitKey, RateLimitPolicy};
+
+/// Buckets requests by client, as `rate_limit.by` says.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientKey {
+    by: RateLimitKey,
+}
+
+impl KeyExtractor for ClientKey {
+    type Key = String;
+
+    fn extract<T>(&self, req: &axum::http::Request<T>) -> Result<String, GovernorError> {
+        if self.by == RateLimitKey::ApiKey
+            && let Some(name) = api_key_name(req.headers())
+        {
+            return Ok(format!("key:{}", name));
+        }
+        SmartIpKeyExtractor
+            .extract(req)
+            .map(|ip| format!("ip:{}", ip))
+    }
+}
+
+/// Name of the config API key the request carries, if it is a known one.
+fn api_key_name(headers: &HeaderMap) -> Option<String> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(crate::auth::extract_bearer_token)?;
+    Config::load().api_key(token).map(|key| key.name.clone())
+}
+
+/// `router` with the per-client limit in `policy`.
+pub fn limit_rate(router: Router, policy: &RateLimitPolicy) -> Router {
+    let period = Duration::from_nanos(60_000_000_000 / policy.requests_per_minute.max(1));
+    let config = GovernorConfigBuilder::default()
+        .period(period)
+        .burst_size(policy.burst.max(1))
+        .key_extractor(ClientKey { by: policy.by })
+        .finish()
+        .expect("period and burst size are non-zero");
+    router.layer(GovernorLayer::new(config))
+}
+
+/// `router`, answering 503 while `max` requests are being handled.
+pub fn limit_concurrency(router: Router, max: usize) -> Router {
+    let permits = Arc::new(Semaphore::new(max.max(1)));
+    router.layer(axum::middleware::from_fn_with_state(permits, admit))
+}
+
+async fn admit(State(permits): State<Arc<Semaphore>>, req: Request, next: Next) -> Response {
+    if matches!(req.uri().path(), "/healthz" | "/readyz") {
+        return next.run(req).await;
+    }
+    let Ok(_permit) = permits.try_acquire_owned() else {
+        warn!("Rejected {} with every request slot busy", req.uri().path());
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, HeaderValue::from_static("1"))],
+            "too many concurrent requests",
+        )
+            .into_response();
+    };
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn request(path: &str, ip: &str) -> Request {
+        Request::builder()
+            .uri(path)
+            .header("x-forwarded-for", ip)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn limits_each_client_separately() {
+        let policy = RateLimitPolicy {
+            requests_per_minute: 1,
+            burst: 2,
+            ..Default::default()
+        };
+        let app = limit_rate(
+            Router::new().route("/jobs", get(|| async { "ok" })),
+            &policy,
+        );
+        let status = |ip: &'static str| {
+            let app = app.clone();
+            async move { app.oneshot(request("/jobs", ip)).await.unwrap().status() }
+        };
+        assert_eq!(status("203.0.113.1").await, StatusCode::OK);
+        assert_eq!(status("203.0.113.1").await, StatusCode::OK);
+        assert_eq!(status("203.0.113.1").await, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(status("203.0.113.2").await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn sheds_requests_past_the_concurrency_cap() {
+        let permits = Arc::new(Semaphore::new(1));
+        // Hold the only slot, as a long-running request would.
+        let _held = permits.clone().try_acquire_owned().unwrap();
+        let app = Router::new()
+            .route("/jobs", get(|| async { "ok" }))
+            .route("/healthz", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(permits, admit));
+        let jobs = app.clone().oneshot(request("/jobs", "203.0.113.1")).await;
+        assert_eq!(jobs.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+        let probe = app.oneshot(request("/healthz", "203.0.113.1")).await;
+        assert_eq!(probe.unwrap().status(), StatusCode::OK);
+    }
+}
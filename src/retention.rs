@@ -0,0 +1,320 @@
+//! Retention: automatic deletion of old transcripts and downloaded audio.
+//!
+//! Configured by `retention` in the config file (see [`RetentionPolicy`]).
+//! A background task started with each server re-reads the config every
+//! `interval_minutes` and applies the policy. It covers the transcript
+//! files of the output directory, including `output_layout` subdirectories,
+//! the audio yt-dlp left in [`AUDIO_DIR_PREFIX`] temp directories, and the
+//! audio failed jobs kept for a resume (see `transcriber::stages`).
+//! Batch reports and the audit log are never touched.
+//!
+//! Every deletion is appended to `<output_dir>/audit/retention.jsonl` as one
+//! JSON object per line, so operators can show what was removed and why.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+use crate::config::{Config, RetentionPolicy};
+use crate::transcriber::downloader::AUDIO_DIR_PREFIX;
+use crate::transcriber::stages;
+use crate::utils::paths::{get_default_output_dir, remove_emptied_dirs, transcript_files};
+
+/// Subdirectory of the output directory holding the audit log.
+pub const AUDIT_DIR: &str = "audit";
+
+/// Files modified this recently are never deleted for size, so audio that a
+/// running job is still reading (or a transcript being written) survives.
+const IN_FLIGHT_GRACE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq)]
+struct FileEntry {
+    path: PathBuf,
+    bytes: u64,
+    modified: SystemTime,
+    kind: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Reason {
+    MaxAge,
+    MaxTotalSize,
+}
+
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    time: String,
+    path: String,
+    kind: &'a str,
+    bytes: u64,
+    reason: Reason,
+}
+
+/// Spawn the retention loop. Cheap when no policy is configured: each tick
+/// just loads the config and goes back to sleep.
+pub fn spawn() {
+    tokio::spawn(async {
+        loop {
+            let policy = Config::load().retention;
+            if let Some(policy) = &policy {
+                let policy = policy.clone();
+                match tokio::task::spawn_blocking(move || enforce(&policy)).await {
+                    Ok(Ok(0)) => {}
+                    Ok(Ok(n)) => info!("🧹 Retention removed {} file(s)", n),
+                    Ok(Err(e)) => warn!("Retention sweep failed: {:#}", e),
+                    Err(e) => warn!("Retention sweep panicked: {}", e),
+                }
+            }
+            let minutes = policy.map_or(60, |p| p.interval_minutes.max(1));
+            tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+        }
+    });
+}
+
+/// Apply `policy` once. Returns how many files were deleted.
+pub fn enforce(policy: &RetentionPolicy) -> Result<usize> {
+    let output_dir = policy
+        .output_dir
+        .clone()
+        .unwrap_or_else(get_default_output_dir);
+    sweep(policy, &output_dir, &std::env::temp_dir())
+}
+
+fn sweep(policy: &RetentionPolicy, output_dir: &Path, temp: &Path) -> Result<usize> {
+    let mut files = file_entries(transcript_files(output_dir), "transcript");
+    for dir in audio_dirs(temp).into_iter().chain(stages::dirs(output_dir)) {
+        let audio = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path());
+        files.extend(file_entries(audio, "audio"));
+    }
+
+    let doomed = plan(files, policy, SystemTime::now());
+    if doomed.is_empty() {
+        return Ok(0);
+    }
+
+    let audit_path = output_dir.join(AUDIT_DIR).join("retention.jsonl");
+    let mut deleted = Vec::new();
+    for (file, reason) in doomed {
+        if let Err(e) = std::fs::remove_file(&file.path) {
+            warn!("Retention could not delete {}: {}", file.path.display(), e);
+            continue;
+        }
+        audit(&audit_path, &file, reason)?;
+        deleted.push(file.path);
+    }
+    // The hidden stages directory is pruned on its own.
+    let stages_dir = output_dir.join(stages::STAGES_DIR);
+    let (staged, transcripts): (Vec<_>, Vec<_>) = deleted
+        .iter()
+        .cloned()
+        .partition(|path| path.starts_with(&stages_dir));
+    remove_emptied_dirs(output_dir, &transcripts);
+    remove_emptied_dirs(&stages_dir, &staged);
+    Ok(deleted.len())
+}
+
+/// Which files to delete, oldest first: everything past `max_age_days`, then
+/// the oldest of the rest until the total fits in `max_total_gb`.
+fn plan(
+    mut files: Vec<FileEntry>,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+) -> Vec<(FileEntry, Reason)> {
+    files.sort_by_key(|f| f.modified);
+    let mut doomed = Vec::new();
+
+    if let Some(days) = policy.max_age_days {
+        let cutoff = now - Duration::from_secs(days * 24 * 60 * 60);
+        let expired = files.partition_point(|f| f.modified < cutoff);
+        doomed.extend(files.drain(..expired).map(|f| (f, Reason::MaxAge)));
+    }
+
+    if let Some(gb) = policy.max_total_gb {
+        let limit = (gb.max(0.0) * 1024.0 * 1024.0 * 1024.0) as u64;
+        let mut total: u64 = files.iter().map(|f| f.bytes).sum();
+        for file in files {
+            if total <= limit {
+                break;
+            }
+            if now.duration_since(file.modified).unwrap_or_default() < IN_FLIGHT_GRACE {
+                continue;
+            }
+            total -= file.bytes;
+            doomed.push((file, Reason::MaxTotalSize));
+        }
+    }
+    doomed
+}
+
+/// The regular files among `paths`.
+fn file_entries(paths: impl IntoIterator<Item = PathBuf>, kind: &'static str) -> Vec<FileEntry> {
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let meta = std::fs::metadata(&path).ok()?;
+            meta.is_file().then(|| FileEntry {
+                path,
+                bytes: meta.len(),
+                modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                kind,
+            })
+        })
+        .collect()
+}
+
+fn audio_dirs(temp: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(temp) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_string_lossy()
+                .starts_with(AUDIO_DIR_PREFIX)
+        })
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect()
+}
+
+fn audit(audit_path: &Path, file: &FileEntry, reason: Reason) -> Result<()> {
+    if let Some(dir) = audit_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let entry = AuditEntry {
+        time: chrono::Utc::now().to_rfc3339(),
+        path: file.path.display().to_string(),
+        kind: file.kind,
+        bytes: file.bytes,
+        reason,
+    };
+    let mut log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_path)
+        .with_context(|| format!("Failed to open audit log {}", audit_path.display()))?;
+    writeln!(log, "{}", serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn file(name: &str, bytes: u64, age: Duration, now: SystemTime) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(name),
+            bytes,
+            modified: now - age,
+            kind: "transcript",
+        }
+    }
+
+    fn policy(max_age_days: Option<u64>, max_total_gb: Option<f64>) -> RetentionPolicy {
+        RetentionPolicy {
+            max_age_days,
+            max_total_gb,
+            output_dir: None,
+            interval_minutes: 60,
+        }
+    }
+
+    fn names(plan: Vec<(FileEntry, Reason)>) -> Vec<(String, Reason)> {
+        plan.into_iter()
+            .map(|(f, r)| (f.path.display().to_string(), r))
+            .collect()
+    }
+
+    #[test]
+    fn deletes_by_age_then_oldest_first_by_size() {
+        let now = SystemTime::now();
+        let gb = 1024 * 1024 * 1024;
+        let files = vec![
+            file("new.txt", gb, Duration::from_secs(60), now),
+            file("ancient.txt", 10, 40 * DAY, now),
+            file("old.txt", gb, 5 * DAY, now),
+            file("older.txt", gb, 6 * DAY, now),
+        ];
+
+        assert_eq!(
+            names(plan(files.clone(), &policy(Some(30), None), now)),
+            [("ancient.txt".to_string(), Reason::MaxAge)]
+        );
+        // 3 GB left, 1 GB allowed: the two oldest go, the in-flight one stays.
+        assert_eq!(
+            names(plan(files.clone(), &policy(Some(30), Some(1.0)), now)),
+            [
+                ("ancient.txt".to_string(), Reason::MaxAge),
+                ("older.txt".to_string(), Reason::MaxTotalSize),
+                ("old.txt".to_string(), Reason::MaxTotalSize),
+            ]
+        );
+        assert!(plan(files, &policy(None, None), now).is_empty());
+    }
+
+    #[test]
+    fn sweep_deletes_transcripts_and_audio_and_audits() {
+        let output = tempfile::tempdir().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let audio_dir = temp.path().join(format!("{}abc", AUDIO_DIR_PREFIX));
+        std::fs::create_dir(&audio_dir).unwrap();
+
+        std::fs::create_dir(output.path().join("YouTube")).unwrap();
+        let stage_dir = output.path().join(stages::STAGES_DIR).join("abc");
+        std::fs::create_dir_all(&stage_dir).unwrap();
+        let old = output.path().join("YouTube").join("old.txt");
+        let fresh = output.path().join("fresh.txt");
+        let audio = audio_dir.join("video.mp3");
+        let staged = stage_dir.join("video.webm");
+        for (path, contents) in [
+            (&old, "old"),
+            (&fresh, "fresh"),
+            (&audio, "mp3"),
+            (&staged, "webm"),
+        ] {
+            std::fs::write(path, contents).unwrap();
+        }
+        for path in [&old, &audio, &staged] {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(SystemTime::now() - 10 * DAY)
+                .unwrap();
+        }
+
+        assert_eq!(
+            sweep(&policy(Some(7), None), output.path(), temp.path()).unwrap(),
+            3
+        );
+        assert!(!old.exists() && !audio.exists());
+        assert!(!output.path().join("YouTube").exists());
+        assert!(!stage_dir.exists());
+        assert!(fresh.exists());
+
+        let audit =
+            std::fs::read_to_string(output.path().join("audit").join("retention.jsonl")).unwrap();
+        let entries: Vec<serde_json::Value> = audit
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|e| e["reason"] == "max_age"));
+        assert!(
+            entries
+                .iter()
+                .any(|e| e["kind"] == "audio" && e["bytes"] == 3)
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! `service install|uninstall|status`: keep the HTTP server running in the
+//! background for the current user, started at login and restarted if it
+//! exits, without writing unit files by hand.
+//!
+//! Linux gets a systemd user unit, macOS a launchd agent and Windows a
+//! scheduled task run at logon (a Windows service proper would need the
+//! server to speak the service control protocol). The service runs this
+//! binary with `--transport http` and the host, port and `--read-only` the
+//! install was given. On Linux and macOS it also gets the install's `PATH`,
+//! so yt-dlp and ffmpeg are found as they are in a shell, the config file's
+//! location, and the settings in [`CARRIED_ENV`] that are set. Secrets such
+//! as API keys aren't written to the unit; set them in the unit by hand or
+//! in the config file. A Windows task inherits the user's environment.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::utils::paths::get_config_path;
+
+/// Name of the unit, agent label suffix and task.
+pub const NAME: &str = "video-transcriber-mcp";
+
+const LAUNCHD_LABEL: &str = "com.video-transcriber-mcp";
+
+/// Environment variables copied into the unit when set. Everything the
+/// server reads that isn't a credential.
+const CARRIED_ENV: &[&str] = &[
+    "REMOTE_WHISPER_URL",
+    "WHISPER_MODEL_CACHE_SIZE",
+    "WHISPER_USE_GPU",
+    "RNNOISE_MODEL",
+    "DOWNLOAD_PROXY",
+    "YT_DLP_COOKIES",
+    "YT_DLP_COOKIES_FROM_BROWSER",
+    "TRANSCRIPT_ENCRYPTION_KEY_FILE",
+    "CREDITS_DB_PATH",
+    "SUPABASE_URL",
+    "LLM_MODEL",
+];
+
+/// What the service runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceSpec {
+    pub exe: PathBuf,
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+impl ServiceSpec {
+    /// This binary serving HTTP on `host:port`, with this process's
+    /// settings.
+    pub fn current(host: &str, port: u16, read_only: bool) -> Result<Self> {
+        let exe = std::env::current_exe().context("Can't tell where this binary is")?;
+        let mut args = vec![
+            "--transport".to_string(),
+            "http".to_string(),
+            "--host".to_string(),
+            host.to_string(),
+            "--port".to_string(),
+            port.to_string(),
+        ];
+        if read_only {
+            args.push("--read-only".to_string());
+        }
+        let mut env: Vec<(String, String)> = Vec::new();
+        if let Ok(path) = std::env::var("PATH") {
+            env.push(("PATH".to_string(), path));
+        }
+        let config = std::path::absolute(get_config_path())?;
+        env.push((
+            "VIDEO_TRANSCRIBER_CONFIG".to_string(),
+            config.to_string_lossy().into_owned(),
+        ));
+        for name in CARRIED_ENV {
+            if let Ok(value) = std::env::var(name) {
+                env.push((name.to_string(), value));
+            }
+        }
+        Ok(Self { exe, args, env })
+    }
+}
+
+/// Register and start the service. Returns what was done.
+pub fn install(spec: &ServiceSpec) -> Result<String> {
+    match std::env::consts::OS {
+        "macos" => {
+            let plist = launchd_plist_path()?;
+            write_file(&plist, &launchd_plist(spec))?;
+            // Reloading picks up a changed plist.
+            let _ = run("launchctl", &["unload", &plist.to_string_lossy()]);
+            run("launchctl", &["load", "-w", &plist.to_string_lossy()])?;
+            Ok(format!(
+                "Installed and started launchd agent {}",
+                plist.display()
+            ))
+        }
+        "windows" => {
+            run(
+                "schtasks",
+                &[
+                    "/Create",
+                    "/F",
+                    "/SC",
+                    "ONLOGON",
+                    "/TN",
+                    NAME,
+                    "/TR",
+                    &task_command(spec),
+                ],
+            )?;
+            run("schtasks", &["/Run", "/TN", NAME])?;
+            Ok(format!("Installed and started scheduled task {}", NAME))
+        }
+        _ => {
+            let unit = systemd_unit_path()?;
+            write_file(&unit, &systemd_unit(spec))?;
+            run("systemctl", &["--user", "daemon-reload"])?;
+            run("systemctl", &["--user", "enable", "--now", NAME])?;
+            // A running service keeps the old settings until restarted.
+            run("systemctl", &["--user", "restart", NAME])?;
+            Ok(format!(
+                "Installed and started systemd user unit {}\n\
+                 To keep it running while logged out: loginctl enable-linger",
+                unit.display()
+            ))
+        }
+    }
+}
+
+/// Stop and remove the service.
+pub fn uninstall() -> Result<String> {
+    match std::env::consts::OS {
+        "macos" => {
+            let plist = launchd_plist_path()?;
+            let _ = run("launchctl", &["unload", "-w", &plist.to_string_lossy()]);
+            remove_file(&plist)?;
+            Ok(format!("Removed launchd agent {}", plist.display()))
+        }
+        "windows" => {
+            let _ = run("schtasks", &["/End", "/TN", NAME]);
+            run("schtasks", &["/Delete", "/F", "/TN", NAME])?;
+            Ok(format!("Removed scheduled task {}", NAME))
+        }
+        _ => {
+            let unit = systemd_unit_path()?;
+            let _ = run("systemctl", &["--user", "disable", "--now", NAME]);
+            remove_file(&unit)?;
+            run("systemctl", &["--user", "daemon-reload"])?;
+            Ok(format!("Removed systemd user unit {}", unit.display()))
+        }
+    }
+}
+
+/// What the service manager says about the service.
+pub fn status() -> Result<String> {
+    let (program, args): (&str, Vec<&str>) = match std::env::consts::OS {
+        "macos" => ("launchctl", vec!["list", LAUNCHD_LABEL]),
+        "windows" => ("schtasks", vec!["/Query", "/V", "/FO", "LIST", "/TN", NAME]),
+        _ => ("systemctl", vec!["--user", "status", "--no-pager", NAME]),
+    };
+    // Non-zero when the service is stopped or missing, which is still an
+    // answer worth showing.
+    let output = Command::new(program)
+        .args(&args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    let mut status = String::from_utf8_lossy(&output.stdout).into_owned();
+    status.push_str(&String::from_utf8_lossy(&output.stderr));
+    if status.trim().is_empty() {
+        status = format!("{} is not installed", NAME);
+    }
+    Ok(status)
+}
+
+fn systemd_unit_path() -> Result<PathBuf> {
+    let home = home::home_dir().context("Could not find home directory")?;
+    Ok(home
+        .join(".config")
+        .join("systemd")
+        .join("user")
+        .join(format!("{}.service", NAME)))
+}
+
+fn launchd_plist_path() -> Result<PathBuf> {
+    let home = home::home_dir().context("Could not find home directory")?;
+    Ok(home
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", LAUNCHD_LABEL)))
+}
+
+fn systemd_unit(spec: &ServiceSpec) -> String {
+    let command: Vec<String> = std::iter::once(spec.exe.to_string_lossy().into_owned())
+        .chain(spec.args.iter().cloned())
+        .map(|word| systemd_quote(&word))
+        .collect();
+    let mut unit = format!(
+        "[Unit]\n\
+         Description=Video Transcriber MCP server\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=5\n",
+        command.join(" ")
+    );
+    for (name, value) in &spec.env {
+        unit.push_str(&format!(
+            "Environment={}\n",
+            systemd_quote(&format!("{}={}", name, value))
+        ));
+    }
+    unit.push_str("\n[Install]\nWantedBy=default.target\n");
+    unit
+}
+
+/// `word` as one systemd command-line or assignment word.
+fn systemd_quote(word: &str) -> String {
+    if !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '%' | '$'))
+    {
+        return word.to_string();
+    }
+    let escaped = word
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{}\"", escaped)
+}
+
+fn launchd_plist(spec: &ServiceSpec) -> String {
+    let strings = |values: Vec<String>| -> String {
+        values
+            .iter()
+            .map(|v| format!("        <string>{}</string>\n", xml_escape(v)))
+            .collect()
+    };
+    let arguments = strings(
+        std::iter::once(spec.exe.to_string_lossy().into_owned())
+            .chain(spec.args.iter().cloned())
+            .collect(),
+    );
+    let environment: String = spec
+        .env
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "        <key>{}</key>\n        <string>{}</string>\n",
+                xml_escape(name),
+                xml_escape(value)
+            )
+        })
+        .collect();
+    let log = std::env::temp_dir().join(format!("{}.log", NAME));
+    let log = xml_escape(&log.to_string_lossy());
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n    \
+             <key>Label</key>\n    <string>{LAUNCHD_LABEL}</string>\n    \
+             <key>ProgramArguments</key>\n    <array>\n{arguments}    </array>\n    \
+             <key>EnvironmentVariables</key>\n    <dict>\n{environment}    </dict>\n    \
+             <key>RunAtLoad</key>\n    <true/>\n    \
+             <key>KeepAlive</key>\n    <true/>\n    \
+             <key>StandardErrorPath</key>\n    <string>{log}</string>\n\
+         </dict>\n\
+         </plist>\n"
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The command line a Windows scheduled task runs.
+fn task_command(spec: &ServiceSpec) -> String {
+    std::iter::once(spec.exe.to_string_lossy().into_owned())
+        .chain(spec.args.iter().cloned())
+        .map(|word| {
+            if word.contains(' ') {
+                format!("\"{}\"", word)
+            } else {
+                word
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Can't create {}", dir.display()))?;
+    }
+    std::fs::write(path, contents).with_context(|| format!("Can't write {}", path.display()))
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Can't remove {}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Run `program`, failing with its output when it fails.
+fn run(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ServiceSpec {
+        ServiceSpec {
+            exe: PathBuf::from("/opt/My Tools/video-transcriber-mcp"),
+            args: ["--transport", "http", "--host", "0.0.0.0", "--port", "8080"]
+                .map(String::from)
+                .to_vec(),
+            env: vec![
+                ("PATH".to_string(), "/usr/local/bin:/usr/bin".to_string()),
+                (
+                    "DOWNLOAD_PROXY".to_string(),
+                    "http://proxy:3128/?a=1&b=100%".to_string(),
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn renders_units_for_each_platform() {
+        let unit = systemd_unit(&spec());
+        assert!(unit.contains(
+            "ExecStart=\"/opt/My Tools/video-transcriber-mcp\" --transport http --host 0.0.0.0 --port 8080\n"
+        ));
+        assert!(unit.contains("Environment=PATH=/usr/local/bin:/usr/bin\n"));
+        assert!(unit.contains("Environment=\"DOWNLOAD_PROXY=http://proxy:3128/?a=1&b=100%%\"\n"));
+        assert!(unit.ends_with("WantedBy=default.target\n"));
+
+        let plist = launchd_plist(&spec());
+        assert!(plist.contains("<string>/opt/My Tools/video-transcriber-mcp</string>"));
+        assert!(plist.contains(
+            "<key>DOWNLOAD_PROXY</key>\n        <string>http://proxy:3128/?a=1&amp;b=100%</string>"
+        ));
+        assert!(plist.contains(&format!("<string>{}</string>", LAUNCHD_LABEL)));
+
+        assert_eq!(
+            task_command(&spec()),
+            "\"/opt/My Tools/video-transcriber-mcp\" --transport http --host 0.0.0.0 --port 8080"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Alternative readings of low-confidence words, so a reviewer sees the
+//! choices instead of a single wrong guess.
+//!
+//! Whisper reports a probability per token; a word is as sure as its least
+//! sure token. A segment with a word under [`LOW_CONFIDENCE`] is decoded
+//! again, once with beam search and then at rising temperatures, and each
+//! reading is lined up with the original word by word (longest common
+//! subsequence). Whatever a reading has in place of an unsure word is an
+//! alternative for it.
+
+use std::collections::HashMap;
+
+use super::types::UncertainWord;
+
+/// Words below this probability get alternatives.
+pub const LOW_CONFIDENCE: f32 = 0.5;
+
+/// Most alternatives a request may ask for per word.
+pub const MAX_ALTERNATIVES: u32 = 5;
+
+/// Tokens `(bytes, probability)` joined into words `(text, probability)`:
+/// a token starting with a space starts a new word.
+pub fn words(tokens: impl IntoIterator<Item = (Vec<u8>, f32)>) -> Vec<(String, f32)> {
+    let mut words: Vec<(Vec<u8>, f32)> = Vec::new();
+    for (bytes, probability) in tokens {
+        match words.last_mut() {
+            Some((word, p)) if !bytes.starts_with(b" ") => {
+                word.extend_from_slice(&bytes);
+                *p = p.min(probability);
+            }
+            _ => words.push((bytes, probability)),
+        }
+    }
+    words
+        .into_iter()
+        .map(|(bytes, p)| (String::from_utf8_lossy(&bytes).trim().to_string(), p))
+        .filter(|(word, _)| !word.is_empty())
+        .collect()
+}
+
+/// Whether any of `words` is unsure enough to decode its segment again.
+pub fn any_uncertain(words: &[(String, f32)]) -> bool {
+    words.iter().any(|(_, p)| *p < LOW_CONFIDENCE)
+}
+
+/// The unsure `words` with what `readings` of the same audio have in
+/// their place, at most `limit` each.
+pub fn uncertain_words(
+    words: &[(String, f32)],
+    readings: &[String],
+    limit: usize,
+) -> Vec<UncertainWord> {
+    let original: Vec<String> = words.iter().map(|(w, _)| normalize(w)).collect();
+    // Per reading, what replaces each original word.
+    let replaced: Vec<Vec<Option<String>>> = readings
+        .iter()
+        .map(|reading| {
+            let reading: Vec<&str> = reading.split_whitespace().collect();
+            substitutions(&original, &reading)
+        })
+        .collect();
+    words
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, p))| *p < LOW_CONFIDENCE)
+        .map(|(i, (word, probability))| {
+            let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+            for (order, alternative) in replaced.iter().filter_map(|r| r[i].clone()).enumerate() {
+                counts.entry(alternative).or_insert((0, order)).0 += 1;
+            }
+            let mut alternatives: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+            alternatives.sort_by_key(|(_, (count, order))| (std::cmp::Reverse(*count), *order));
+            UncertainWord {
+                word: word.clone(),
+                probability: *probability,
+                alternatives: alternatives
+                    .into_iter()
+                    .take(limit)
+                    .map(|(alternative, _)| alternative)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// For each of `original`'s (normalized) words, the words of `reading`
+/// that stand in its place, if they differ: `original` and `reading` are
+/// lined up on their longest common subsequence, and every run of
+/// original words between matches is replaced by the reading's run.
+fn substitutions(original: &[String], reading: &[&str]) -> Vec<Option<String>> {
+    let normalized: Vec<String> = reading.iter().map(|w| normalize(w)).collect();
+    let (n, m) = (original.len(), reading.len());
+    // common[i][j]: longest common subsequence of original[i..], reading[j..].
+    let mut common = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            common[i][j] = if original[i] == normalized[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = vec![None; n];
+    let (mut i, mut j) = (0, 0);
+    let (mut run_i, mut run_j) = (0, 0);
+    loop {
+        let matched = i < n && j < m && original[i] == normalized[j];
+        if matched || (i == n && j == m) {
+            if run_i < i && run_j < j {
+                let replacement = reading[run_j..j].join(" ");
+                for slot in &mut result[run_i..i] {
+                    *slot = Some(replacement.clone());
+                }
+            }
+            if !matched {
+                break;
+            }
+            i += 1;
+            j += 1;
+            (run_i, run_j) = (i, j);
+        } else if j == m || (i < n && common[i + 1][j] >= common[i][j + 1]) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Lowercase without surrounding punctuation, for comparing words.
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_tokens_into_words() {
+        let tokens = [
+            (b" Deploy".to_vec(), 0.98),
+            (b" it".to_vec(), 0.95),
+            (b" with".to_vec(), 0.97),
+            (b" cube".to_vec(), 0.41),
+            (b"ern".to_vec(), 0.22),
+            (b"etes".to_vec(), 0.60),
+            (b".".to_vec(), 0.90),
+        ];
+        let words = words(tokens);
+        assert_eq!(words.len(), 4);
+        assert_eq!(words[3], ("cubeernetes.".to_string(), 0.22));
+        assert!(any_uncertain(&words));
+        assert!(!any_uncertain(&words[..3]));
+    }
+
+    #[test]
+    fn lines_up_readings_with_the_original() {
+        let words: Vec<(String, f32)> = [
+            ("We", 0.9),
+            ("use", 0.9),
+            ("cube", 0.3),
+            ("ernetes", 0.4),
+            ("in", 0.9),
+            ("prod.", 0.45),
+        ]
+        .iter()
+        .map(|(w, p)| (w.to_string(), *p))
+        .collect();
+        let readings = [
+            "We use Kubernetes in prod.".to_string(),
+            "We use Kubernetes in production.".to_string(),
+            "We used cube ernetes in prod".to_string(),
+            "We use Kubernetes in prod.".to_string(),
+        ];
+        let uncertain = uncertain_words(&words, &readings, 2);
+        assert_eq!(uncertain.len(), 3);
+        assert_eq!(uncertain[0].word, "cube");
+        assert_eq!(uncertain[0].alternatives, ["Kubernetes"]);
+        assert_eq!(uncertain[1].alternatives, ["Kubernetes"]);
+        assert_eq!(uncertain[2].word, "prod.");
+        assert_eq!(uncertain[2].probability, 0.45);
+        assert_eq!(uncertain[2].alternatives, ["production."]);
+
+        // Nothing in the reading stands in for a dropped word.
+        assert_eq!(
+            substitutions(&["a".into(), "b".into(), "c".into()], &["a", "c"]),
+            [None, None, None]
+        );
+    }
+}
@@ -1,9 +1,126 @@
 use anyhow::{Context, Result};
-use async_process::Command;
 use std::path::{Path, PathBuf};
 use tempfile::TempDir;
 use tracing::info;
 
+use super::types::{AudioFormat, AudioTrack, ClipRange};
+use crate::utils::process::{self, Access};
+
+/// Extensions of audio-only formats whisper's ffmpeg decode reads as is.
+pub const AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "wav", "m4a", "flac", "ogg", "opus", "aac", "mka", "weba",
+];
+
+/// Named ffmpeg filter chains accepted in `audio_filters`.
+pub const FILTER_PRESETS: &[(&str, &str)] = &[
+    // Narrowband telephone audio: keep the voice band, lift quiet callers.
+    (
+        "phone-call",
+        "highpass=f=200,lowpass=f=3400,afftdn=nr=12,dynaudnorm",
+    ),
+    // A distant mic in a large room: cut rumble and HVAC noise, bring the
+    // speaker forward.
+    (
+        "lecture-hall",
+        "highpass=f=100,afftdn=nr=20,equalizer=f=2500:t=q:w=1:g=3,dynaudnorm",
+    ),
+    // Steady background hiss only.
+    ("denoise", "afftdn=nr=15"),
+    ("loudnorm", "loudnorm=I=-16:TP=-1.5:LRA=11"),
+];
+
+/// Preset for ffmpeg's `arnndn` (RNNoise) denoiser, which needs a model
+/// file from `RNNOISE_MODEL`. Better than `denoise` on babble and keyboard
+/// noise, at some cost to quiet speech.
+pub const RNNOISE_PRESET: &str = "rnnoise";
+
+/// Filters that read or write files, or load plugins; a request must not
+/// reach the disk through them. `arnndn` is available as [`RNNOISE_PRESET`].
+const FILE_FILTERS: &[&str] = &[
+    "amovie", "movie", "asendcmd", "sendcmd", "azmq", "zmq", "arnndn", "ladspa", "lv2",
+];
+
+/// Resolve an `audio_filters` value into an ffmpeg `-af` chain. Each
+/// comma-separated entry is either a preset name from [`FILTER_PRESETS`]
+/// (or [`RNNOISE_PRESET`]) or a raw ffmpeg filter, so presets and raw
+/// filters can be mixed, e.g. `"phone-call,volume=2dB"`. `None` if `spec`
+/// is blank.
+pub fn filter_chain(spec: &str) -> Result<Option<String>> {
+    let mut filters = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        if let Some((_, chain)) = FILTER_PRESETS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(entry))
+        {
+            filters.push(chain.to_string());
+            continue;
+        }
+        if entry.eq_ignore_ascii_case(RNNOISE_PRESET) {
+            filters.push(rnnoise_filter(
+                std::env::var_os("RNNOISE_MODEL").as_deref(),
+            )?);
+            continue;
+        }
+        // A single linear chain only: no labels or parallel chains that
+        // could hide a source filter.
+        if entry.contains([';', '[', ']']) {
+            anyhow::bail!("Audio filter '{}' must be a plain filter chain", entry);
+        }
+        let (name, options) = entry.split_once('=').unwrap_or((entry, ""));
+        if FILE_FILTERS.contains(&name.trim()) {
+            anyhow::bail!("Audio filter '{}' is not allowed", name.trim());
+        }
+        if options
+            .split(':')
+            .any(|option| matches!(option.split('=').next(), Some("file" | "filename")))
+        {
+            anyhow::bail!("Audio filter '{}' may not name a file", entry);
+        }
+        filters.push(entry.to_string());
+    }
+    Ok((!filters.is_empty()).then(|| filters.join(",")))
+}
+
+/// `arnndn` with the model at `model`, quoted for the filter graph.
+fn rnnoise_filter(model: Option<&std::ffi::OsStr>) -> Result<String> {
+    let model = model
+        .filter(|m| !m.is_empty())
+        .context("The rnnoise preset needs RNNOISE_MODEL set to an RNNoise model file (.rnnn)")?;
+    // ffmpeg runs elsewhere (see `utils::process`).
+    let model = std::path::absolute(model)?;
+    let model = model
+        .to_str()
+        .filter(|m| !m.contains(['\'', '\\']))
+        .with_context(|| format!("Unusable RNNOISE_MODEL path: {}", model.display()))?;
+    if !Path::new(model).is_file() {
+        anyhow::bail!("RNNOISE_MODEL {} is not a file", model);
+    }
+    Ok(format!("arnndn=m='{}'", model))
+}
+
+/// What ffprobe reports about a local media file.
+#[derive(Debug, Clone, Default)]
+pub struct MediaInfo {
+    /// Whole seconds; 0 when the container doesn't say.
+    pub duration: u64,
+    /// ffprobe's format name, e.g. `mov,mp4,m4a,3gp,3g2,mj2`.
+    pub container: String,
+    pub audio_tracks: Vec<AudioTrack>,
+    /// Video streams, not counting embedded cover art.
+    pub video_streams: usize,
+}
+
+/// Whether a local file is audio only, so extracting its audio would just
+/// re-encode it. Known audio extensions are trusted; otherwise the probe
+/// has to have found no video stream.
+pub fn is_audio_only(path: &Path, info: Option<&MediaInfo>) -> bool {
+    let by_extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| AUDIO_EXTENSIONS.iter().any(|a| a.eq_ignore_ascii_case(e)));
+    by_extension || info.is_some_and(|info| info.video_streams == 0)
+}
+
 pub struct AudioProcessor {
     temp_dir: TempDir,
 }
@@ -16,38 +133,138 @@ impl Default for AudioProcessor {
 
 impl AudioProcessor {
     pub fn new() -> Self {
-        let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        Self { temp_dir }
+        Self::scoped().expect("Failed to create temp directory")
+    }
+
+    /// A processor with a temp directory of its own, removed when it is
+    /// dropped. One per job keeps concurrent extractions apart.
+    pub fn scoped() -> Result<Self> {
+        let temp_dir = TempDir::new().context("Failed to create temp directory")?;
+        Ok(Self { temp_dir })
     }
 
-    pub async fn extract_audio(&self, video_path: &Path) -> Result<PathBuf> {
-        info!("🎵 Extracting audio from video...");
+    /// Container, duration and streams of a local file via ffprobe.
+    pub async fn probe(&self, path: &Path) -> Result<MediaInfo> {
+        let output = process::async_command_reading(
+            "ffprobe",
+            self.temp_dir.path(),
+            Access::Local,
+            &[path],
+        )?
+        .args(process::LOCAL_ONLY)
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration,format_name:stream=index,codec_type,codec_name,sample_rate,channels\
+                 :stream_tags=title,language:stream_disposition=attached_pic",
+            "-of",
+            "json",
+        ])
+        .arg(path)
+        .output()
+        .await
+        .context("Failed to run ffprobe. Is ffmpeg installed?")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "ffprobe failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        parse_media_info(&String::from_utf8_lossy(&output.stdout))
+    }
 
+    /// Duration of a local media file in whole seconds, via ffprobe.
+    pub async fn probe_duration(&self, path: &Path) -> Result<u64> {
+        Ok(self.probe(path).await?.duration)
+    }
+
+    /// Extract audio to `format`. `track` picks one audio stream (1-based);
+    /// `None` lets ffmpeg choose its default stream. Only the `clip` part is
+    /// kept when it is set.
+    pub async fn extract_audio(
+        &self,
+        video_path: &Path,
+        track: Option<usize>,
+        clip: &ClipRange,
+        format: AudioFormat,
+    ) -> Result<PathBuf> {
+        match track {
+            Some(n) => info!("🎵 Extracting audio track {} from video...", n),
+            None => info!("🎵 Extracting audio from video..."),
+        }
+        self.extract(video_path, track, None, clip, format).await
+    }
+
+    /// Extract one channel (1-based: 1 is left, 2 right) of the default
+    /// audio stream as mono audio in `format`, for call recordings with
+    /// one speaker per channel.
+    pub async fn extract_channel(
+        &self,
+        video_path: &Path,
+        channel: usize,
+        clip: &ClipRange,
+        format: AudioFormat,
+    ) -> Result<PathBuf> {
+        info!("🎵 Extracting audio channel {}...", channel);
+        let pan = format!("pan=mono|c0=c{}", channel - 1);
+        self.extract(video_path, None, Some(&pan), clip, format)
+            .await
+    }
+
+    async fn extract(
+        &self,
+        video_path: &Path,
+        track: Option<usize>,
+        filter: Option<&str>,
+        clip: &ClipRange,
+        format: AudioFormat,
+    ) -> Result<PathBuf> {
         // Generate unique filename to avoid conflicts when processing multiple videos
         let unique_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_nanos();
-        let output_path = self
-            .temp_dir
-            .path()
-            .join(format!("audio_{}.mp3", unique_id));
-
-        let output = Command::new("ffmpeg")
-            .args([
-                "-i",
-                video_path.to_str().unwrap(),
-                "-vn", // No video
-                "-acodec",
-                "libmp3lame", // MP3 codec
-                "-q:a",
-                "2",  // Quality (2 is high quality)
-                "-y", // Overwrite output file
-                output_path.to_str().unwrap(),
-            ])
-            .output()
-            .await
-            .context("Failed to run ffmpeg. Is it installed?")?;
+        // A filtered stream has to be re-encoded; WAV loses nothing more.
+        let format = match (format, filter) {
+            (AudioFormat::Original, Some(_)) => AudioFormat::Wav,
+            _ => format,
+        };
+        let file_name = format!("audio_{}.{}", unique_id, format.extension());
+        let output_path = self.temp_dir.path().join(file_name);
+
+        let mut args: Vec<String> = process::LOCAL_ONLY.map(String::from).to_vec();
+        args.extend(clip.ffmpeg_input_args());
+        args.push("-i".to_string());
+        args.push(video_path.to_str().unwrap().to_string());
+        if let Some(n) = track {
+            args.push("-map".to_string());
+            args.push(format!("0:a:{}", n - 1));
+        }
+        if let Some(filter) = filter {
+            args.push("-af".to_string());
+            args.push(filter.to_string());
+        }
+        args.extend(clip.ffmpeg_output_args());
+
+        let output = process::async_command_reading(
+            "ffmpeg",
+            self.temp_dir.path(),
+            Access::Local,
+            &[video_path],
+        )?
+        .args(args)
+        .arg("-vn") // No video
+        .args(format.ffmpeg_args())
+        .args([
+            "-y", // Overwrite output file
+            output_path.to_str().unwrap(),
+        ])
+        .output()
+        .await
+        .context("Failed to run ffmpeg. Is it installed?")?;
 
         if !output.status.success() {
             anyhow::bail!(
@@ -68,3 +285,129 @@ impl AudioProcessor {
         Ok(output_path)
     }
 }
+
+/// Parse the `ffprobe -of json` output of [`AudioProcessor::probe`].
+/// Audio streams are numbered in order, matching ffmpeg's `0:a:N` (plus
+/// one).
+fn parse_media_info(json: &str) -> Result<MediaInfo> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).context("Failed to parse ffprobe output")?;
+    let streams = value["streams"].as_array().cloned().unwrap_or_default();
+    let of_type = |kind: &'static str| {
+        streams
+            .iter()
+            .filter(move |s| s["codec_type"].as_str() == Some(kind))
+    };
+    let text = |value: &serde_json::Value| {
+        value
+            .as_str()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty() && s != "und")
+    };
+    let audio_tracks = of_type("audio")
+        .enumerate()
+        .map(|(i, stream)| AudioTrack {
+            number: i + 1,
+            title: text(&stream["tags"]["title"]),
+            language: text(&stream["tags"]["language"]),
+            channels: stream["channels"].as_u64().map(|n| n as u32),
+            codec: text(&stream["codec_name"]),
+            sample_rate: stream["sample_rate"]
+                .as_str()
+                .and_then(|rate| rate.parse().ok()),
+        })
+        .collect();
+    Ok(MediaInfo {
+        duration: value["format"]["duration"]
+            .as_str()
+            .and_then(|d| d.parse::<f64>().ok())
+            .map_or(0, |d| d.round() as u64),
+        container: text(&value["format"]["format_name"]).unwrap_or_default(),
+        audio_tracks,
+        video_streams: of_type("video")
+            .filter(|s| s["disposition"]["attached_pic"].as_i64() != Some(1))
+            .count(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{filter_chain, parse_media_info, rnnoise_filter};
+
+    #[test]
+    fn parses_ffprobe_streams() {
+        let json = r#"{
+            "streams": [
+                {"index": 0, "codec_type": "video", "codec_name": "h264",
+                 "disposition": {"attached_pic": 0}},
+                {"index": 1, "codec_type": "audio", "codec_name": "aac", "sample_rate": "48000",
+                 "channels": 2, "tags": {"title": "Mic", "language": "eng"}},
+                {"index": 2, "codec_type": "audio", "tags": {"language": "und"}}
+            ],
+            "format": {"format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "3601.52"}
+        }"#;
+        let info = parse_media_info(json).unwrap();
+        assert_eq!(info.duration, 3602);
+        assert_eq!(info.container, "mov,mp4,m4a,3gp,3g2,mj2");
+        assert_eq!(info.video_streams, 1);
+        let tracks = &info.audio_tracks;
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].label(), "Track 1 (Mic)");
+        assert_eq!(tracks[0].channels, Some(2));
+        assert_eq!(tracks[0].details(), "aac, 48 kHz, 2 channels");
+        assert_eq!(tracks[1].number, 2);
+        assert_eq!(tracks[1].label(), "Track 2");
+        assert_eq!(tracks[1].channels, None);
+        assert_eq!(tracks[1].details(), "");
+        assert!(parse_media_info("not json").is_err());
+    }
+
+    #[test]
+    fn cover_art_is_not_video() {
+        let cover = r#"{"streams": [
+            {"index": 0, "codec_type": "audio", "codec_name": "mp3"},
+            {"index": 1, "codec_type": "video", "disposition": {"attached_pic": 1}}
+        ]}"#;
+        let info = parse_media_info(cover).unwrap();
+        assert_eq!(info.video_streams, 0);
+        assert_eq!(info.duration, 0);
+        assert!(super::is_audio_only("song.bin".as_ref(), Some(&info)));
+        assert!(super::is_audio_only("song.MP3".as_ref(), None));
+        assert!(!super::is_audio_only("talk.mp4".as_ref(), None));
+
+        let silent = parse_media_info(r#"{"streams": [{"index": 0, "codec_type": "video"}]}"#);
+        assert!(silent.unwrap().audio_tracks.is_empty());
+    }
+
+    #[test]
+    fn expands_presets_and_rejects_file_filters() {
+        assert_eq!(
+            filter_chain("Phone-Call, volume=2dB").unwrap().as_deref(),
+            Some("highpass=f=200,lowpass=f=3400,afftdn=nr=12,dynaudnorm,volume=2dB")
+        );
+        assert_eq!(
+            filter_chain("highpass=f=80").unwrap().as_deref(),
+            Some("highpass=f=80")
+        );
+        assert_eq!(filter_chain(" , ").unwrap(), None);
+        assert!(filter_chain("amovie=/etc/passwd").is_err());
+        assert!(filter_chain("anull;amovie=x").is_err());
+        assert!(filter_chain("ametadata=mode=print:file=/tmp/x").is_err());
+        assert!(filter_chain("arnndn=m=/etc/passwd").is_err());
+        assert!(filter_chain("ladspa=f=/tmp/evil.so").is_err());
+    }
+
+    #[test]
+    fn rnnoise_needs_a_model_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = dir.path().join("sh.rnnn");
+        std::fs::write(&model, "").unwrap();
+        assert_eq!(
+            rnnoise_filter(Some(model.as_os_str())).unwrap(),
+            format!("arnndn=m='{}'", model.display())
+        );
+        assert!(rnnoise_filter(None).is_err());
+        assert!(rnnoise_filter(Some(dir.path().join("missing.rnnn").as_os_str())).is_err());
+        assert!(rnnoise_filter(Some("it's.rnnn".as_ref())).is_err());
+    }
+}
@@ -0,0 +1,540 @@
+//! Multi-URL batch runs and their on-disk reports.
+//!
+//! A batch is a list of sources transcribed one after another with shared
+//! options. Its report is written to `<output_dir>/batches/<batch_id>.json`
+//! after every item, so a batch interrupted half-way still leaves an
+//! accurate record, and `retry_failed` can pick the same report back up —
+//! even from a later session — and re-run only what failed.
+//!
+//! A local directory can be run as a batch too: its media files become the
+//! items, minus those that already have a transcript.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use super::engine::TranscriberEngine;
+use super::types::{
+    IfExists, MdTimestamps, OutputFormat, SpeakerTurns, TranscriptionOptions, WhisperModel,
+};
+use super::usage::{ResourceUsage, UsageMeter};
+use crate::utils::encryption;
+
+/// Extensions picked up by a directory batch unless the caller gives its
+/// own list.
+pub const DEFAULT_MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "webm", "m4v", "flv", "wmv", "mp3", "wav", "m4a", "flac", "ogg",
+    "opus", "aac",
+];
+
+/// Subdirectory of the output dir holding batch reports. Kept out of the top
+/// level so the transcript listing / cleanup tools never see the reports.
+pub const BATCH_DIR: &str = "batches";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Pending,
+    Complete,
+    Failed,
+}
+
+/// Order in which a batch's items are run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchedulingPolicy {
+    /// As listed.
+    #[default]
+    Fifo,
+    /// Shortest sources first, so someone waiting on a mixed batch sees
+    /// results early.
+    ShortestFirst,
+    LongestFirst,
+}
+
+impl std::str::FromStr for SchedulingPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace('_', "-").as_str() {
+            "fifo" => Ok(Self::Fifo),
+            "shortest-first" => Ok(Self::ShortestFirst),
+            "longest-first" => Ok(Self::LongestFirst),
+            _ => anyhow::bail!(
+                "Invalid schedule: {} (expected fifo, shortest-first or longest-first)",
+                s
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub url: String,
+    pub status: BatchItemStatus,
+    /// Source duration in seconds, probed for scheduling or taken from a
+    /// finished transcription.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u64>,
+    /// Number of times this item has been attempted (first run + retries).
+    pub attempts: u32,
+    /// Model used on the most recent successful attempt — retries and
+    /// platform profiles may change it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Resources spent on this item, summed over all attempts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
+}
+
+/// The options a batch's items share, as saved in its report. Everything
+/// but the source and the output directory, and the per-run state
+/// (`progress`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchOptions {
+    pub model: Option<WhisperModel>,
+    pub language: Option<String>,
+    pub source_note: Option<String>,
+    pub cookies_file: Option<String>,
+    pub cookies_from_browser: Option<String>,
+    pub proxy: Option<String>,
+    pub formats: Vec<OutputFormat>,
+    pub if_exists: IfExists,
+    pub force: bool,
+    pub md_timestamps: MdTimestamps,
+    pub speaker_turns: SpeakerTurns,
+    pub audio_filter: Option<String>,
+    pub glossary: BTreeMap<String, String>,
+    pub hotwords: Vec<String>,
+    pub live_output: bool,
+    pub split_chapters: bool,
+    pub webhook_url: Option<String>,
+}
+
+impl BatchOptions {
+    pub fn from_options(options: &TranscriptionOptions) -> Self {
+        Self {
+            model: options.model,
+            language: options.language.clone(),
+            source_note: options.source_note.clone(),
+            cookies_file: options.cookies_file.clone(),
+            cookies_from_browser: options.cookies_from_browser.clone(),
+            proxy: options.proxy.clone(),
+            formats: options.formats.clone(),
+            if_exists: options.if_exists,
+            force: options.force,
+            md_timestamps: options.md_timestamps,
+            speaker_turns: options.speaker_turns,
+            audio_filter: options.audio_filter.clone(),
+            glossary: options.glossary.clone(),
+            hotwords: options.hotwords.clone(),
+            live_output: options.live_output,
+            split_chapters: options.split_chapters,
+            webhook_url: options.webhook_url.clone(),
+        }
+    }
+
+    /// Options to run the batch's items with, writing into `output_dir`.
+    pub fn to_options(&self, output_dir: String) -> TranscriptionOptions {
+        let saved = self.clone();
+        TranscriptionOptions {
+            url: String::new(),
+            output_dir,
+            model: saved.model,
+            language: saved.language,
+            source_note: saved.source_note,
+            cookies_file: saved.cookies_file,
+            cookies_from_browser: saved.cookies_from_browser,
+            proxy: saved.proxy,
+            formats: saved.formats,
+            if_exists: saved.if_exists,
+            force: saved.force,
+            md_timestamps: saved.md_timestamps,
+            speaker_turns: saved.speaker_turns,
+            audio_filter: saved.audio_filter,
+            glossary: saved.glossary,
+            hotwords: saved.hotwords,
+            live_output: saved.live_output,
+            split_chapters: saved.split_chapters,
+            webhook_url: saved.webhook_url,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub batch_id: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Options the batch was started with; retries default to these.
+    #[serde(flatten)]
+    pub options: BatchOptions,
+    #[serde(default)]
+    pub schedule: SchedulingPolicy,
+    pub items: Vec<BatchItem>,
+}
+
+impl BatchReport {
+    pub fn new(urls: Vec<String>, options: BatchOptions, schedule: SchedulingPolicy) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            batch_id: uuid::Uuid::new_v4().to_string(),
+            created_at: now,
+            updated_at: now,
+            options,
+            schedule,
+            items: urls
+                .into_iter()
+                .map(|url| BatchItem {
+                    url,
+                    status: BatchItemStatus::Pending,
+                    duration: None,
+                    attempts: 0,
+                    model: None,
+                    title: None,
+                    transcript_path: None,
+                    error: None,
+                    usage: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Indices of items that did not complete. Pending items count too: they
+    /// were never reached because the previous run was interrupted.
+    pub fn retryable_indices(&self) -> Vec<usize> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.status != BatchItemStatus::Complete)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// `indices` reordered by the report's scheduling policy. Items of
+    /// unknown duration run last under either duration-based policy.
+    pub fn ordered(&self, indices: &[usize]) -> Vec<usize> {
+        let mut order = indices.to_vec();
+        let key = |i: &usize| self.items[*i].duration;
+        match self.schedule {
+            SchedulingPolicy::Fifo => {}
+            SchedulingPolicy::ShortestFirst => {
+                order.sort_by_key(|i| (key(i).is_none(), key(i)));
+            }
+            SchedulingPolicy::LongestFirst => {
+                order.sort_by_key(|i| (key(i).is_none(), std::cmp::Reverse(key(i))));
+            }
+        }
+        order
+    }
+
+    /// Resources spent on the whole batch so far.
+    pub fn total_usage(&self) -> ResourceUsage {
+        let mut total = ResourceUsage::default();
+        for usage in self.items.iter().filter_map(|i| i.usage.as_ref()) {
+            total.add(usage);
+        }
+        total
+    }
+
+    pub fn count(&self, status: BatchItemStatus) -> usize {
+        self.items.iter().filter(|i| i.status == status).count()
+    }
+
+    pub fn load(output_dir: &Path, batch_id: &str) -> Result<Self> {
+        let path = report_path(output_dir, batch_id)?;
+        if !path.exists() {
+            anyhow::bail!("Batch report not found: {}", path.display());
+        }
+        let raw = encryption::read_to_string(&path)?;
+        serde_json::from_str(&raw).context("Failed to parse batch report")
+    }
+
+    pub fn save(&self, output_dir: &Path) -> Result<PathBuf> {
+        let path = report_path(output_dir, &self.batch_id)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create batch report directory")?;
+        }
+        encryption::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+/// Path of a batch's report. Rejects ids that aren't plain uuid-ish tokens,
+/// since the id comes straight from tool arguments and ends up in a path.
+pub fn report_path(output_dir: &Path, batch_id: &str) -> Result<PathBuf> {
+    if batch_id.is_empty()
+        || batch_id.len() > 64
+        || !batch_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        anyhow::bail!("Invalid batch id: {}", batch_id);
+    }
+    Ok(output_dir
+        .join(BATCH_DIR)
+        .join(format!("{}.json", batch_id)))
+}
+
+/// Transcribe the items at `indices` with `options` (its `url` is replaced
+/// per item), in the order the report's scheduling policy gives, updating
+/// and re-saving the report after each one. A failing item is recorded and
+/// the batch moves on.
+pub async fn run_items(
+    engine: &TranscriberEngine,
+    report: &mut BatchReport,
+    indices: &[usize],
+    options: &TranscriptionOptions,
+) -> Result<()> {
+    let output_dir = PathBuf::from(&options.output_dir);
+    if report.schedule != SchedulingPolicy::Fifo {
+        probe_durations(engine, report, indices).await;
+    }
+    let indices = report.ordered(indices);
+    for (n, &i) in indices.iter().enumerate() {
+        let url = report.items[i].url.clone();
+        info!(
+            "📚 Batch {} item {}/{}: {}",
+            report.batch_id,
+            n + 1,
+            indices.len(),
+            url
+        );
+
+        let item_options = TranscriptionOptions {
+            url,
+            ..options.clone()
+        };
+        let meter = UsageMeter::start();
+        let outcome = engine.transcribe(item_options).await;
+        let usage = meter.finish(outcome.as_ref().map_or(0, |r| r.usage.bytes_downloaded));
+
+        let item = &mut report.items[i];
+        item.attempts += 1;
+        item.usage.get_or_insert_default().add(&usage);
+        match outcome {
+            Ok(result) => {
+                if result.metadata.duration > 0 {
+                    item.duration = Some(result.metadata.duration);
+                }
+                item.model = Some(result.model_label());
+                item.status = BatchItemStatus::Complete;
+                item.title = Some(result.metadata.title);
+                item.transcript_path = Some(result.files.primary().to_string());
+                item.error = None;
+            }
+            Err(e) => {
+                warn!("Batch item failed ({}): {:#}", item.url, e);
+                item.status = BatchItemStatus::Failed;
+                item.error = Some(format!("{:#}", e));
+            }
+        }
+        report.updated_at = chrono::Utc::now().timestamp();
+        report.save(&output_dir)?;
+    }
+    Ok(())
+}
+
+/// Outcome of [`run_directory`].
+pub struct DirectoryRun {
+    pub report: BatchReport,
+    /// Files left out because they already have a transcript.
+    pub skipped: Vec<String>,
+}
+
+/// Media files in `dir` (and its subdirectories if `recursive`) whose
+/// extension is in `extensions`, case-insensitively, in path order.
+pub fn scan_directory(dir: &Path, extensions: &[String], recursive: bool) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        anyhow::bail!("Not a directory: {}", dir.display());
+    }
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(dir)
+        .max_depth(if recursive { usize::MAX } else { 1 })
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Transcribe every media file in `dir` that doesn't have a transcript in
+/// `options.output_dir` yet, as a batch with a saved report.
+pub async fn run_directory(
+    engine: &TranscriberEngine,
+    dir: &Path,
+    extensions: &[String],
+    recursive: bool,
+    options: &TranscriptionOptions,
+    schedule: SchedulingPolicy,
+) -> Result<DirectoryRun> {
+    let (skipped, pending): (Vec<String>, Vec<String>) =
+        scan_directory(dir, extensions, recursive)?
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .partition(|path| engine.is_transcribed(path, &options.output_dir));
+
+    let mut report = BatchReport::new(pending, BatchOptions::from_options(options), schedule);
+    info!(
+        "📂 Directory batch {}: {} to transcribe, {} already done",
+        report.batch_id,
+        report.items.len(),
+        skipped.len()
+    );
+    report.save(Path::new(&options.output_dir))?;
+    let indices: Vec<usize> = (0..report.items.len()).collect();
+    run_items(engine, &mut report, &indices, options).await?;
+    Ok(DirectoryRun { report, skipped })
+}
+
+/// Fill in missing durations. A probe failure just leaves the item
+/// unscheduled (it runs last) — the transcription itself will report the
+/// real error.
+async fn probe_durations(engine: &TranscriberEngine, report: &mut BatchReport, indices: &[usize]) {
+    for &i in indices {
+        let item = &mut report.items[i];
+        if item.duration.is_some() {
+            continue;
+        }
+        match engine.probe_duration(&item.url).await {
+            Ok(secs) if secs > 0 => item.duration = Some(secs),
+            Ok(_) => {}
+            Err(e) => warn!("Could not probe duration of {}: {:#}", item.url, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcriber::types::ModelSize;
+
+    #[test]
+    fn scans_directory_by_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("week2")).unwrap();
+        for name in ["b.MP4", "a.mkv", "notes.txt", "week2/c.mp3"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        let exts: Vec<String> = DEFAULT_MEDIA_EXTENSIONS
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| {
+                    f.strip_prefix(dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        let flat = scan_directory(dir.path(), &exts, false).unwrap();
+        assert_eq!(names(flat), ["a.mkv", "b.MP4"]);
+        let deep = scan_directory(dir.path(), &exts, true).unwrap();
+        assert_eq!(names(deep), ["a.mkv", "b.MP4", "week2/c.mp3"]);
+        assert!(scan_directory(&dir.path().join("missing"), &exts, false).is_err());
+    }
+
+    #[test]
+    fn retryable_skips_completed_items() {
+        let mut report = BatchReport::new(
+            vec!["a".into(), "b".into(), "c".into()],
+            BatchOptions::default(),
+            SchedulingPolicy::Fifo,
+        );
+        report.items[0].status = BatchItemStatus::Complete;
+        report.items[1].status = BatchItemStatus::Failed;
+        assert_eq!(report.retryable_indices(), vec![1, 2]);
+    }
+
+    #[test]
+    fn report_roundtrips_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = TranscriptionOptions {
+            model: Some(WhisperModel::from(ModelSize::Small)),
+            language: Some("en".into()),
+            proxy: Some("socks5://127.0.0.1:1080".into()),
+            formats: vec![OutputFormat::Srt],
+            if_exists: IfExists::Version,
+            speaker_turns: SpeakerTurns::Qa,
+            audio_filter: Some("loudnorm".into()),
+            glossary: BTreeMap::from([("cube ernetes".into(), "Kubernetes".into())]),
+            hotwords: vec!["PostgreSQL".into()],
+            webhook_url: Some("https://hooks.example.com/t".into()),
+            ..Default::default()
+        };
+        let report = BatchReport::new(
+            vec!["a".into()],
+            BatchOptions::from_options(&options),
+            SchedulingPolicy::ShortestFirst,
+        );
+        report.save(dir.path()).unwrap();
+        let loaded = BatchReport::load(dir.path(), &report.batch_id).unwrap();
+        assert_eq!(loaded.items.len(), 1);
+        assert_eq!(loaded.options, report.options);
+        assert_eq!(loaded.schedule, SchedulingPolicy::ShortestFirst);
+        let rerun = loaded.options.to_options("/out".into());
+        assert_eq!(rerun.output_dir, "/out");
+        assert_eq!(rerun.formats, options.formats);
+        assert_eq!(rerun.glossary, options.glossary);
+        assert_eq!(rerun.proxy, options.proxy);
+
+        // Reports from before the full option set was saved still load.
+        let old = r#"{"batch_id": "b", "created_at": 0, "updated_at": 0,
+            "model": null, "language": "de", "items": []}"#;
+        let old: BatchReport = serde_json::from_str(old).unwrap();
+        assert_eq!(old.options.language.as_deref(), Some("de"));
+        assert!(old.options.formats.is_empty());
+    }
+
+    #[test]
+    fn orders_by_duration_with_unknown_last() {
+        let urls = vec!["a".into(), "b".into(), "c".into(), "d".into()];
+        let mut report = BatchReport::new(
+            urls,
+            BatchOptions::default(),
+            SchedulingPolicy::ShortestFirst,
+        );
+        for (item, duration) in report
+            .items
+            .iter_mut()
+            .zip([Some(600), None, Some(60), Some(3600)])
+        {
+            item.duration = duration;
+        }
+        assert_eq!(report.ordered(&[0, 1, 2, 3]), vec![2, 0, 3, 1]);
+        report.schedule = SchedulingPolicy::LongestFirst;
+        assert_eq!(report.ordered(&[0, 1, 2, 3]), vec![3, 0, 2, 1]);
+        report.schedule = SchedulingPolicy::Fifo;
+        assert_eq!(report.ordered(&[2, 0]), vec![2, 0]);
+        assert!("shortest_first".parse::<SchedulingPolicy>().is_ok());
+    }
+
+    #[test]
+    fn rejects_path_like_batch_ids() {
+        let dir = Path::new("/tmp");
+        assert!(report_path(dir, "../etc/passwd").is_err());
+        assert!(report_path(dir, "").is_err());
+        assert!(report_path(dir, "0b5e-41aa").is_ok());
+    }
+}
@@ -0,0 +1,136 @@
+//! Platform captions as a transcript, skipping whisper.
+//!
+//! Most YouTube videos already have captions, uploaded or auto-generated.
+//! Fetching them with yt-dlp takes seconds, while downloading the audio and
+//! running whisper can take minutes. This module turns the WebVTT file
+//! yt-dlp writes into our segments.
+
+use super::types::Segment;
+
+/// Segments from a WebVTT file. Handles YouTube's auto-generated style,
+/// where each cue repeats the previous line before adding a new one and
+/// words carry inline `<00:00:01.234><c>` timing tags. Only files with
+/// those tags are deduplicated: elsewhere a line said twice in a row is
+/// said twice.
+pub fn parse_vtt(vtt: &str) -> Vec<Segment> {
+    let rolling = vtt.lines().any(has_timing_tag);
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut previous: Vec<String> = Vec::new();
+
+    for block in vtt.replace("\r\n", "\n").split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+        let Some((start_ms, end_ms)) = lines.next().and_then(parse_cue_timing) else {
+            continue;
+        };
+        let text_lines: Vec<String> = lines
+            .map(strip_tags)
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        // Keep only what this cue adds over the one before.
+        let new: Vec<&str> = text_lines
+            .iter()
+            .filter(|l| !rolling || !previous.contains(l))
+            .map(String::as_str)
+            .collect();
+        if !new.is_empty() {
+            segments.push(Segment {
+                start_ms,
+                end_ms,
+                text: new.join(" "),
+                ..Default::default()
+            });
+        } else if let Some(last) = segments.last_mut() {
+            // A pure repeat (YouTube's 10 ms "hold" cues) just extends the
+            // previous segment.
+            last.end_ms = last.end_ms.max(end_ms);
+        }
+        if !text_lines.is_empty() {
+            previous = text_lines;
+        }
+    }
+    segments
+}
+
+/// Whether `line` has an inline `<00:00:01.234>` word timing.
+fn has_timing_tag(line: &str) -> bool {
+    line.split('<')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('>'))
+        .any(|(tag, _)| parse_timestamp(tag).is_some())
+}
+
+/// `00:01:02.345 --> 00:01:04.000 align:start` → milliseconds.
+fn parse_cue_timing(line: &str) -> Option<(u64, u64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// `hh:mm:ss.mmm` or `mm:ss.mmm`.
+fn parse_timestamp(ts: &str) -> Option<u64> {
+    let (hms, millis) = ts.split_once('.')?;
+    let mut secs = 0u64;
+    for part in hms.split(':') {
+        secs = secs * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(secs * 1000 + millis.parse::<u64>().ok()?)
+}
+
+fn strip_tags(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_cues() {
+        let vtt = "WEBVTT\n\n1\n00:00:01.000 --> 00:00:03.500\nHello &amp; welcome\n\n\
+                   2\n00:00:03.500 --> 00:00:05.000\n<i>to the show</i>\n";
+        let segments = parse_vtt(vtt);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].start_ms, 1_000);
+        assert_eq!(segments[0].end_ms, 3_500);
+        assert_eq!(segments[0].text, "Hello & welcome");
+        assert_eq!(segments[1].text, "to the show");
+    }
+
+    #[test]
+    fn keeps_repeated_lines_in_plain_captions() {
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:02.000\nNo.\n\n\
+                   00:00:02.000 --> 00:00:03.000\nNo.\n\n\
+                   00:00:03.000 --> 00:00:04.000\n<i>Stop.</i>\n";
+        let texts: Vec<String> = parse_vtt(vtt).into_iter().map(|s| s.text).collect();
+        assert_eq!(texts, ["No.", "No.", "Stop."]);
+    }
+
+    #[test]
+    fn dedupes_youtube_rolling_captions() {
+        let vtt = "WEBVTT\nKind: captions\nLanguage: en\n\n\
+            00:00:00.000 --> 00:00:02.000 align:start position:0%\n \n\
+            hello<00:00:00.500><c> there</c>\n\n\
+            00:00:02.000 --> 00:00:02.010 align:start position:0%\n\
+            hello there\n \n\n\
+            00:00:02.010 --> 00:00:04.000 align:start position:0%\n\
+            hello there\n\
+            general<00:00:02.500><c> kenobi</c>\n";
+        let texts: Vec<String> = parse_vtt(vtt).into_iter().map(|s| s.text).collect();
+        assert_eq!(texts, ["hello there", "general kenobi"]);
+    }
+}
@@ -0,0 +1,171 @@
+//! Chapters, and the per-chapter transcripts `split_chapters` writes for
+//! course creators publishing notes lesson by lesson.
+//!
+//! Chapters come from the platform: yt-dlp lists the ones a video defines,
+//! including those written as timestamps in a YouTube description. Without
+//! any, [`generate`] cuts the transcript into parts of about
+//! [`GENERATED_CHAPTER_MS`] at the longest pause near each cut, and names
+//! each part after its main topic. A chapter's transcript has times from
+//! the chapter's start.
+
+use super::topics;
+use super::types::{Chapter, Segment};
+
+/// Length generated chapters aim for.
+pub const GENERATED_CHAPTER_MS: u64 = 10 * 60 * 1000;
+
+/// How far from the even split a generated chapter may start, to land on
+/// a pause.
+const BOUNDARY_WINDOW_MS: u64 = 2 * 60 * 1000;
+
+/// The chapters in yt-dlp's `chapters` field.
+pub fn from_yt_dlp(chapters: &serde_json::Value) -> Vec<Chapter> {
+    let ms = |value: &serde_json::Value| value.as_f64().map(|s| (s * 1000.0).round() as u64);
+    chapters
+        .as_array()
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(i, chapter)| {
+            let title = chapter["title"].as_str().unwrap_or_default().trim();
+            Some(Chapter {
+                title: if title.is_empty() {
+                    format!("Chapter {}", i + 1)
+                } else {
+                    title.to_string()
+                },
+                start_ms: ms(&chapter["start_time"])?,
+                end_ms: ms(&chapter["end_time"])?,
+            })
+        })
+        .filter(|chapter| chapter.end_ms > chapter.start_ms)
+        .collect()
+}
+
+/// Chapters for a transcript without any, or none when it is shorter than
+/// two of them. Titles are the parts' topics (`language` as for
+/// [`topics::extract`]), else `Part 1`, `Part 2`, ….
+pub fn generate(segments: &[Segment], language: Option<&str>) -> Vec<Chapter> {
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return Vec::new();
+    };
+    let length = last.end_ms.saturating_sub(first.start_ms);
+    let parts = (length as f64 / GENERATED_CHAPTER_MS as f64).round() as u64;
+    if parts < 2 {
+        return Vec::new();
+    }
+
+    // Index of each chapter's first segment.
+    let mut starts = vec![0];
+    for k in 1..parts {
+        let target = first.start_ms + k * length / parts;
+        let after = starts[starts.len() - 1];
+        let pause = |i: usize| segments[i].start_ms.saturating_sub(segments[i - 1].end_ms);
+        if let Some(i) = (after + 1..segments.len())
+            .filter(|&i| segments[i].start_ms.abs_diff(target) <= BOUNDARY_WINDOW_MS)
+            .max_by_key(|&i| pause(i))
+        {
+            starts.push(i);
+        }
+    }
+
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for (n, &i) in starts.iter().enumerate() {
+        let next = starts.get(n + 1).copied();
+        let part = &segments[i..next.unwrap_or(segments.len())];
+        let title = topics::extract(part, language)
+            .into_iter()
+            .map(|topic| capitalize(&topic.term))
+            .find(|title| !chapters.iter().any(|c| c.title.eq_ignore_ascii_case(title)))
+            .unwrap_or_else(|| format!("Part {}", n + 1));
+        chapters.push(Chapter {
+            title,
+            start_ms: segments[i].start_ms,
+            end_ms: next.map_or(last.end_ms, |next| segments[next].start_ms),
+        });
+    }
+    chapters
+}
+
+/// The segments starting within `chapter`, timed from its start.
+pub fn segments_in(segments: &[Segment], chapter: &Chapter) -> Vec<Segment> {
+    segments
+        .iter()
+        .filter(|s| s.start_ms >= chapter.start_ms && s.start_ms < chapter.end_ms)
+        .map(|s| Segment {
+            start_ms: s.start_ms - chapter.start_ms,
+            end_ms: s.end_ms.saturating_sub(chapter.start_ms),
+            ..s.clone()
+        })
+        .collect()
+}
+
+fn capitalize(term: &str) -> String {
+    let mut chars = term.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_s: u64, text: &str) -> Segment {
+        Segment {
+            start_ms: start_s * 1000,
+            end_ms: start_s * 1000 + 4_000,
+            text: format!(" {}", text),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reads_platform_chapters() {
+        let json = serde_json::json!([
+            { "start_time": 0.0, "end_time": 95.5, "title": "Intro" },
+            { "start_time": 95.5, "end_time": 600.0, "title": " " },
+        ]);
+        assert_eq!(
+            from_yt_dlp(&json),
+            [
+                Chapter {
+                    title: "Intro".to_string(),
+                    start_ms: 0,
+                    end_ms: 95_500,
+                },
+                Chapter {
+                    title: "Chapter 2".to_string(),
+                    start_ms: 95_500,
+                    end_ms: 600_000,
+                },
+            ]
+        );
+        assert!(from_yt_dlp(&serde_json::Value::Null).is_empty());
+
+        let local = segments_in(
+            &[segment(90, "Hi."), segment(100, "Lesson one.")],
+            &from_yt_dlp(&json)[1],
+        );
+        assert_eq!(local.len(), 1);
+        assert_eq!((local[0].start_ms, local[0].end_ms), (4_500, 8_500));
+    }
+
+    #[test]
+    fn generates_chapters_at_pauses() {
+        // 20 minutes: borrow checking, a 30 s pause at 9:30, then lifetimes.
+        let mut segments: Vec<Segment> = (0..114)
+            .map(|i| segment(i * 5, "Borrow checking."))
+            .collect();
+        segments.extend((0..126).map(|i| segment(600 + i * 5, "Lifetimes.")));
+        let chapters = generate(&segments, None);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].title, "Borrow checking");
+        assert_eq!((chapters[0].start_ms, chapters[0].end_ms), (0, 600_000));
+        assert_eq!(chapters[1].title, "Lifetimes");
+        assert_eq!(chapters[1].start_ms, 600_000);
+
+        assert!(generate(&segments[..100], None).is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+//! Progress of a chunked local decode, saved as it goes.
+//!
+//! With `chunk_minutes`, each finished chunk's segments are written to
+//! `<output name>.partial.json` next to where the outputs will go. If the
+//! decode fails or its caller gives up, a later run of the same source with
+//! the same model, language and chunk size picks the file up and only
+//! decodes the chunks it lacks. The file is removed once every chunk is
+//! done. Timestamps in it are relative to the decoded audio (a clip's start,
+//! not the video's).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::types::{DetectedLanguage, Segment};
+use crate::utils::encryption;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Label of the decoding model.
+    pub model: String,
+    /// Language the request asked for; `None` for auto-detection.
+    pub requested_language: Option<String>,
+    /// Reused on resume, so later chunks are decoded in the same language.
+    pub detected_language: Option<DetectedLanguage>,
+    pub chunk_minutes: u32,
+    /// Chunk boundaries as sample offsets. A resume only applies when the
+    /// audio splits the same way, which also means it is the same audio.
+    pub bounds: Vec<usize>,
+    /// Segments of each finished chunk, by chunk index.
+    pub chunks: BTreeMap<usize, Vec<Segment>>,
+    /// Audio up to here (ms) is transcribed: the end of the last chunk that
+    /// every earlier chunk also finished.
+    pub resume_offset_ms: u64,
+}
+
+/// Where the checkpoint for outputs at `base` (see `store::file`) goes,
+/// one per audio track of a multi-track source.
+pub fn path_for(base: &Path, track: Option<usize>) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    if let Some(n) = track {
+        path.push(format!(".track{}", n));
+    }
+    path.push(".partial.json");
+    path.into()
+}
+
+impl Checkpoint {
+    pub fn new(
+        model: &str,
+        requested_language: Option<&str>,
+        chunk_minutes: u32,
+        bounds: Vec<usize>,
+    ) -> Self {
+        Self {
+            model: model.to_string(),
+            requested_language: requested_language.map(str::to_string),
+            detected_language: None,
+            chunk_minutes,
+            bounds,
+            chunks: BTreeMap::new(),
+            resume_offset_ms: 0,
+        }
+    }
+
+    /// The checkpoint at `path` if it belongs to the same decode as `fresh`
+    /// (a checkpoint with no chunks done yet).
+    pub fn resume(path: &Path, fresh: &Checkpoint) -> Option<Self> {
+        let saved: Checkpoint =
+            serde_json::from_str(&encryption::read_to_string(path).ok()?).ok()?;
+        (saved.model == fresh.model
+            && saved.requested_language == fresh.requested_language
+            && saved.chunk_minutes == fresh.chunk_minutes
+            && saved.bounds == fresh.bounds)
+            .then_some(saved)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.bounds.len().saturating_sub(1)
+    }
+
+    /// Record chunk `i` as done with `segments`.
+    pub fn complete(&mut self, i: usize, segments: Vec<Segment>, sample_rate: usize) {
+        self.chunks.insert(i, segments);
+        let done = (0..self.chunk_count())
+            .take_while(|i| self.chunks.contains_key(i))
+            .count();
+        self.resume_offset_ms = (self.bounds[done] as u64 * 1000) / sample_rate as u64;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create output directory")?;
+        }
+        encryption::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumes_only_the_same_decode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_for(&dir.path().join("abc123-My Talk"), None);
+        assert!(path.ends_with("abc123-My Talk.partial.json"));
+        assert!(
+            path_for(&dir.path().join("abc123-My Talk"), Some(2))
+                .ends_with("abc123-My Talk.track2.partial.json")
+        );
+
+        let fresh = Checkpoint::new("base", None, 10, vec![0, 16_000, 32_000, 40_000]);
+        let mut saved = fresh.clone();
+        let segment = Segment {
+            start_ms: 1_200,
+            end_ms: 1_800,
+            text: "later".to_string(),
+            ..Default::default()
+        };
+        saved.complete(1, vec![segment], 16_000);
+        assert_eq!(saved.resume_offset_ms, 0);
+        saved.complete(0, Vec::new(), 16_000);
+        assert_eq!(saved.resume_offset_ms, 2_000);
+        saved.save(&path).unwrap();
+
+        assert_eq!(Checkpoint::resume(&path, &fresh), Some(saved));
+        let other_model = Checkpoint::new("small", None, 10, fresh.bounds.clone());
+        assert!(Checkpoint::resume(&path, &other_model).is_none());
+        let other_audio = Checkpoint::new("base", None, 10, vec![0, 16_000, 40_000]);
+        assert!(Checkpoint::resume(&path, &other_audio).is_none());
+    }
+}
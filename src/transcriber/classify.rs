@@ -0,0 +1,232 @@
+//! Speech / music / applause labelling of decoded audio.
+//!
+//! Whisper fed a concert or a gaming stream produces pages of "[Music]" and
+//! occasionally hallucinated lyrics. A cheap pass over the 16 kHz PCM finds
+//! the long non-speech stretches so they can be reported, and optionally
+//! silenced before decoding.
+//!
+//! The features are deliberately simple — no spectra, no model: speech
+//! alternates loud syllables with short gaps and voiced with unvoiced
+//! sounds, so its level and zero-crossing rate swing within a second; music
+//! holds a steady level, and applause is steady, loud noise.
+
+use serde::Serialize;
+
+use super::types::Segment;
+
+const SAMPLE_RATE: usize = 16_000;
+
+/// Classification window (1 s).
+const WINDOW: usize = SAMPLE_RATE;
+
+/// Sub-frames (20 ms) whose level / zero-crossing variation is measured.
+const SUB_FRAME: usize = SAMPLE_RATE / 50;
+
+/// Windows quieter than this RMS are silence.
+const SILENCE_RMS: f32 = 0.005;
+
+/// Non-speech runs shorter than this are left alone — a jingle or a burst
+/// of laughter between sentences isn't worth reporting or cutting.
+const MIN_REGION_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioClass {
+    Speech,
+    Music,
+    Applause,
+    Silence,
+}
+
+/// A stretch of audio with one label.
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioRegion {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub class: AudioClass,
+}
+
+/// Music and applause regions of at least [`MIN_REGION_MS`].
+pub fn non_speech_regions(samples: &[f32]) -> Vec<AudioRegion> {
+    let windows: Vec<AudioClass> = samples.chunks(WINDOW).map(classify_window).collect();
+    let labels = smooth(&windows);
+
+    let mut regions: Vec<AudioRegion> = Vec::new();
+    for (i, class) in labels.into_iter().enumerate() {
+        let start_ms = (i * WINDOW * 1000 / SAMPLE_RATE) as u64;
+        let end_ms = ((((i + 1) * WINDOW).min(samples.len())) * 1000 / SAMPLE_RATE) as u64;
+        match regions.last_mut() {
+            Some(last) if last.class == class => last.end_ms = end_ms,
+            _ => regions.push(AudioRegion {
+                start_ms,
+                end_ms,
+                class,
+            }),
+        }
+    }
+    regions.retain(|r| {
+        matches!(r.class, AudioClass::Music | AudioClass::Applause)
+            && r.end_ms - r.start_ms >= MIN_REGION_MS
+    });
+    regions
+}
+
+/// Zero the samples inside `regions` so whisper hears silence there while
+/// every later timestamp stays where it was.
+pub fn silence_regions(samples: &mut [f32], regions: &[AudioRegion]) {
+    for r in regions {
+        let start = (r.start_ms as usize * SAMPLE_RATE / 1000).min(samples.len());
+        let end = (r.end_ms as usize * SAMPLE_RATE / 1000).min(samples.len());
+        samples[start..end].fill(0.0);
+    }
+}
+
+/// Drop segments centred in a skipped region, and whisper's own
+/// non-speech annotations ("[Music]", "(applause)", "♪♪").
+pub fn drop_non_speech_segments(segments: Vec<Segment>, regions: &[AudioRegion]) -> Vec<Segment> {
+    segments
+        .into_iter()
+        .filter(|seg| {
+            let mid = (seg.start_ms + seg.end_ms) / 2;
+            !regions.iter().any(|r| r.start_ms <= mid && mid < r.end_ms)
+                && !is_annotation(&seg.text)
+        })
+        .collect()
+}
+
+fn is_annotation(text: &str) -> bool {
+    let t = text.trim();
+    !t.is_empty()
+        && ((t.starts_with('[') && t.ends_with(']'))
+            || (t.starts_with('(') && t.ends_with(')'))
+            || t.chars().all(|c| matches!(c, '♪' | '♫' | '♬' | ' ')))
+}
+
+fn classify_window(window: &[f32]) -> AudioClass {
+    let frames: Vec<(f32, f32)> = window
+        .chunks_exact(SUB_FRAME)
+        .map(|f| (rms(f), zero_crossing_rate(f)))
+        .collect();
+    if frames.is_empty() || rms(window) < SILENCE_RMS {
+        return AudioClass::Silence;
+    }
+
+    let levels: Vec<f32> = frames.iter().map(|(l, _)| *l).collect();
+    let zcrs: Vec<f32> = frames.iter().map(|(_, z)| *z).collect();
+    let (level_mean, level_std) = mean_std(&levels);
+    let (zcr_mean, zcr_std) = mean_std(&zcrs);
+    // Coefficient of variation: how much the level swings relative to
+    // itself, so loud and quiet recordings compare alike.
+    let level_cv = level_std / level_mean.max(1e-6);
+
+    if level_cv > 0.5 || zcr_std > 0.05 {
+        AudioClass::Speech
+    } else if zcr_mean > 0.25 {
+        AudioClass::Applause
+    } else {
+        AudioClass::Music
+    }
+}
+
+/// Majority vote over each window and its neighbours, so a single odd
+/// window doesn't split a region.
+fn smooth(labels: &[AudioClass]) -> Vec<AudioClass> {
+    (0..labels.len())
+        .map(|i| {
+            let prev = labels[i.saturating_sub(1)];
+            let next = labels[(i + 1).min(labels.len() - 1)];
+            if prev == next { prev } else { labels[i] }
+        })
+        .collect()
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32).sqrt()
+}
+
+fn zero_crossing_rate(frame: &[f32]) -> f32 {
+    let crossings = frame
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    crossings as f32 / frame.len().max(1) as f32
+}
+
+fn mean_std(values: &[f32]) -> (f32, f32) {
+    let n = values.len().max(1) as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    (mean, var.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(seconds: usize, amplitude: f32) -> Vec<f32> {
+        (0..seconds * SAMPLE_RATE)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                amplitude * (std::f32::consts::TAU * 440.0 * t).sin()
+            })
+            .collect()
+    }
+
+    /// Tone bursts gated at a syllable-like 4 Hz: loud for 150 ms, quiet
+    /// for 100 ms.
+    fn syllables(seconds: usize) -> Vec<f32> {
+        tone(seconds, 0.3)
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| if i % 4_000 < 2_400 { s } else { s * 0.02 })
+            .collect()
+    }
+
+    #[test]
+    fn finds_steady_music_between_speech() {
+        let mut audio = syllables(5);
+        audio.extend(tone(10, 0.3));
+        audio.extend(syllables(5));
+        let regions = non_speech_regions(&audio);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].class, AudioClass::Music);
+        assert!(regions[0].start_ms >= 4_000 && regions[0].start_ms <= 6_000);
+        assert!(regions[0].end_ms >= 14_000 && regions[0].end_ms <= 16_000);
+    }
+
+    #[test]
+    fn short_music_is_not_reported() {
+        let mut audio = syllables(5);
+        audio.extend(tone(2, 0.3));
+        audio.extend(syllables(5));
+        assert!(non_speech_regions(&audio).is_empty());
+    }
+
+    #[test]
+    fn drops_annotations_and_skipped_segments() {
+        let seg = |start_ms, end_ms, text: &str| Segment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            ..Default::default()
+        };
+        let regions = [AudioRegion {
+            start_ms: 10_000,
+            end_ms: 20_000,
+            class: AudioClass::Music,
+        }];
+        let kept = drop_non_speech_segments(
+            vec![
+                seg(0, 2_000, "Hello there"),
+                seg(2_000, 4_000, "[Music]"),
+                seg(4_000, 5_000, "♪ ♪"),
+                seg(12_000, 14_000, "la la la"),
+                seg(21_000, 23_000, "(applause)"),
+                seg(23_000, 25_000, "Welcome back"),
+            ],
+            &regions,
+        );
+        let texts: Vec<&str> = kept.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["Hello there", "Welcome back"]);
+    }
+}
@@ -1,41 +1,168 @@
 use anyhow::{Context, Result};
-use async_process::Command;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Stdio};
+use std::thread::JoinHandle;
 use tempfile::TempDir;
 use tracing::{info, warn};
 
-use super::types::VideoMetadata;
+use super::captions;
+use super::chapters;
+use super::types::{AudioFormat, ClipRange, Episode, Segment, VideoMetadata, parse_time};
+use crate::config::{Config, PlatformRule};
+use crate::utils::process::{self, Access};
+
+/// Prefix of the temp directories downloaded audio lands in, so the
+/// retention sweep can find audio left by any engine or earlier process.
+pub const AUDIO_DIR_PREFIX: &str = "video-transcriber-audio-";
+
+/// Name of the job's copy of the cookies file in its temp directory.
+const COOKIES_COPY: &str = "cookies.txt";
 
 pub struct VideoDownloader {
     temp_dir: TempDir,
 }
 
-/// Resolves the cookie source for yt-dlp from the environment, returning the
-/// flag pair to inject into yt-dlp commands.
+/// Resolves the cookie source for yt-dlp, returning the flag pair to inject
+/// into yt-dlp commands.
 ///
-/// Resolution order:
-/// 1. `YT_DLP_COOKIES` — path to a Netscape-format cookies file
+/// Resolution order, a cookies file winning over a browser at each level:
+/// 1. The request's own `cookies_file` (checked with
+///    [`request_cookies_file`]) / `cookies_from_browser`.
+/// 2. `cookies_file` / `cookies_from_browser` in the config file.
+/// 3. `YT_DLP_COOKIES` — path to a Netscape-format cookies file
 ///    (`--cookies <file>`). Use this on headless/Linux setups where the
 ///    browser cookie DB isn't accessible (e.g. cookies exported via a QR
 ///    login flow).
-/// 2. `YT_DLP_COOKIES_FROM_BROWSER` — browser name to read cookies from
+/// 4. `YT_DLP_COOKIES_FROM_BROWSER` — browser name to read cookies from
 ///    (`--cookies-from-browser <name>`), piggybacking on a logged-in session.
 ///
-/// Either source bypasses YouTube's "Sign in to confirm you're not a bot"
-/// wall and unlocks age-restricted / members-only videos.
-fn cookies_args() -> Option<[String; 2]> {
-    resolve_cookies_args(
-        std::env::var("YT_DLP_COOKIES").ok().as_deref(),
-        std::env::var("YT_DLP_COOKIES_FROM_BROWSER").ok().as_deref(),
-    )
+/// Any source bypasses YouTube's "Sign in to confirm you're not a bot"
+/// wall and unlocks age-restricted / members-only / private videos the
+/// account can see.
+pub fn cookies_args(cookies_file: Option<&str>, browser: Option<&str>) -> Option<[String; 2]> {
+    let config = Config::load();
+    resolve_cookies_args(cookies_file, browser)
+        .or_else(|| {
+            resolve_cookies_args(
+                config.cookies_file.as_deref(),
+                config.cookies_from_browser.as_deref(),
+            )
+        })
+        .or_else(|| {
+            resolve_cookies_args(
+                std::env::var("YT_DLP_COOKIES").ok().as_deref(),
+                std::env::var("YT_DLP_COOKIES_FROM_BROWSER").ok().as_deref(),
+            )
+        })
+}
+
+/// The cookies file a request named, which must be in the config file's
+/// `cookies_dir`. yt-dlp reads the file and writes the cookie jar back to
+/// it, so a request can't be allowed to name any file on the server.
+pub fn request_cookies_file(name: &str) -> Result<PathBuf> {
+    let dir = Config::load().cookies_dir.context(
+        "cookies_file needs cookies_dir in the config file; \
+         set cookies_file there or YT_DLP_COOKIES instead",
+    )?;
+    cookies_file_in(&dir, name)
+}
+
+/// `name` resolved to a file in `dir`, however it is spelled.
+fn cookies_file_in(dir: &Path, name: &str) -> Result<PathBuf> {
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("cookies_dir not found: {}", dir.display()))?;
+    let path = dir
+        .join(name.trim())
+        .canonicalize()
+        .with_context(|| format!("Cookies file not found: {}", name))?;
+    if !path.starts_with(&dir) || !path.is_file() {
+        anyhow::bail!("Not a cookies file in {}: {}", dir.display(), name);
+    }
+    Ok(path)
+}
+
+/// Proxy for yt-dlp and direct HTTP fetches: the request's own, else
+/// `proxy` in the config file, else `DOWNLOAD_PROXY`. Any scheme yt-dlp and
+/// reqwest both understand: `http://`, `https://`, `socks5://`, `socks5h://`.
+pub fn proxy_url(requested: Option<&str>) -> Option<String> {
+    let nonblank = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+    requested
+        .and_then(nonblank)
+        .or_else(|| Config::load().proxy.as_deref().and_then(nonblank))
+        .or_else(|| {
+            std::env::var("DOWNLOAD_PROXY")
+                .ok()
+                .as_deref()
+                .and_then(nonblank)
+        })
+}
+
+/// Per-request network settings for downloads.
+#[derive(Debug, Clone, Default)]
+pub struct Network {
+    /// Flag pair from [`cookies_args`].
+    pub cookies: Option<[String; 2]>,
+    /// From [`proxy_url`].
+    pub proxy: Option<String>,
+}
+
+impl Network {
+    /// Settings from the config file and environment alone.
+    pub fn configured() -> Self {
+        Self {
+            cookies: cookies_args(None, None),
+            proxy: proxy_url(None),
+        }
+    }
+
+    /// Flags to add to every yt-dlp invocation run in `workdir`. A cookies
+    /// file is copied there first and yt-dlp given the copy, so the cookie
+    /// jar it writes back on exit never replaces the original.
+    fn yt_dlp_args(&self, workdir: &Path) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        match &self.cookies {
+            Some([flag, file]) if flag == "--cookies" => {
+                let copy = workdir.join(COOKIES_COPY);
+                if !copy.exists() {
+                    std::fs::copy(file, &copy)
+                        .with_context(|| format!("Failed to read the cookies file {}", file))?;
+                }
+                args.push(flag.clone());
+                args.push(copy.to_string_lossy().into_owned());
+            }
+            Some(c) => args.extend(c.iter().cloned()),
+            None => {}
+        }
+        if let Some(proxy) = &self.proxy {
+            args.push("--proxy".to_string());
+            args.push(proxy.clone());
+        }
+        Ok(args)
+    }
+
+    /// Client for direct HTTP fetches, through the proxy if there is one.
+    pub fn http_client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy: {}", proxy))?,
+            );
+        }
+        Ok(builder.build()?)
+    }
 }
 
 /// Pure resolution of the cookie flag pair, factored out of `cookies_args` so
 /// the precedence rules can be unit-tested without touching process-global env.
 fn resolve_cookies_args(cookies_file: Option<&str>, browser: Option<&str>) -> Option<[String; 2]> {
     // Prefer an explicit cookies file — works on headless/Linux hosts.
+    // Absolute, as yt-dlp doesn't run in our working directory.
     if let Some(trimmed) = cookies_file.map(str::trim).filter(|s| !s.is_empty()) {
-        return Some(["--cookies".to_string(), trimmed.to_string()]);
+        let path = std::path::absolute(trimmed)
+            .map_or_else(|_| trimmed.to_string(), |p| p.to_string_lossy().to_string());
+        return Some(["--cookies".to_string(), path]);
     }
     // Fall back to reading cookies straight from a local browser.
     let trimmed = browser.map(str::trim).filter(|s| !s.is_empty())?;
@@ -50,32 +177,31 @@ impl Default for VideoDownloader {
 
 impl VideoDownloader {
     pub fn new() -> Self {
-        let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        Self { temp_dir }
+        Self::scoped().expect("Failed to create temp directory")
     }
 
-    pub async fn download(&self, url: &str) -> Result<(VideoMetadata, PathBuf)> {
-        info!("📥 Fetching video metadata...");
-        let metadata = self.fetch_metadata(url).await?;
-
-        info!("📺 Detected platform: {}", metadata.platform);
-        info!("🎬 Title: {}", metadata.title);
-
-        info!("⬇️  Downloading video (audio only)...");
-        let video_path = self.download_audio(url).await?;
-
-        Ok((metadata, video_path))
+    /// A downloader with a temp directory of its own, removed when it is
+    /// dropped. One per job keeps concurrent downloads apart.
+    pub fn scoped() -> Result<Self> {
+        let temp_dir = tempfile::Builder::new()
+            .prefix(AUDIO_DIR_PREFIX)
+            .tempdir()
+            .context("Failed to create temp directory")?;
+        Ok(Self { temp_dir })
     }
 
-    async fn fetch_metadata(&self, url: &str) -> Result<VideoMetadata> {
+    pub async fn fetch_metadata(&self, url: &str, net: &Network) -> Result<VideoMetadata> {
         let mut args: Vec<String> = vec!["--dump-json".to_string()];
-        if let Some(c) = cookies_args() {
+        if let Some(c) = &net.cookies {
             info!("Using {} {}", c[0], c[1]);
-            args.extend(c);
         }
+        if let Some(proxy) = &net.proxy {
+            info!("Using proxy {}", proxy);
+        }
+        args.extend(net.yt_dlp_args(self.temp_dir.path())?);
         args.push(url.to_string());
 
-        let output = Command::new("yt-dlp")
+        let output = process::async_command("yt-dlp", self.temp_dir.path(), Access::Network)?
             .args(&args)
             .output()
             .await
@@ -84,9 +210,9 @@ impl VideoDownloader {
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             // Surface a hint when bot-check fires and cookies aren't configured.
-            if stderr.contains("Sign in to confirm") && cookies_args().is_none() {
+            if stderr.contains("Sign in to confirm") && net.cookies.is_none() {
                 warn!(
-                    "YouTube triggered bot detection. Authenticate with cookies: set YT_DLP_COOKIES=/path/to/cookies.txt (a Netscape-format cookies file, e.g. exported via QR login on headless/Linux), or YT_DLP_COOKIES_FROM_BROWSER=chrome (or brave/firefox/edge) in .env."
+                    "YouTube triggered bot detection. Authenticate with cookies: pass cookies_file / cookies_from_browser, set them in the config file, or set YT_DLP_COOKIES=/path/to/cookies.txt (a Netscape-format cookies file, e.g. exported via QR login on headless/Linux) or YT_DLP_COOKIES_FROM_BROWSER=chrome (or brave/firefox/edge) in .env."
                 );
             }
             anyhow::bail!("yt-dlp failed to fetch metadata: {}", stderr);
@@ -105,12 +231,94 @@ impl VideoDownloader {
                 .to_string(),
             duration: json["duration"].as_u64().unwrap_or(0),
             upload_date: json["upload_date"].as_str().unwrap_or("").to_string(),
-            platform: detect_platform(url, &json),
+            platform: detect_platform(url, &json, &Config::load().platform_rules),
             url: url.to_string(),
+            language: json["language"].as_str().map(str::to_string),
+            source_note: None,
+            chapters: chapters::from_yt_dlp(&json["chapters"]),
+            live: json["live_status"] == "is_live" || json["is_live"] == true,
         })
     }
 
-    async fn download_audio(&self, url: &str) -> Result<PathBuf> {
+    /// Platform captions in `language` as segments, or `None` if the video
+    /// has none. Uploaded captions win over auto-generated ones.
+    pub async fn fetch_captions(
+        &self,
+        url: &str,
+        language: &str,
+        net: &Network,
+    ) -> Result<Option<Vec<Segment>>> {
+        let unique_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let stem = format!("captions_{}", unique_id);
+        let output_template = self.temp_dir.path().join(format!("{}.%(ext)s", stem));
+
+        let mut args: Vec<String> = vec![
+            "--skip-download".to_string(),
+            "--write-subs".to_string(),
+            "--write-auto-subs".to_string(),
+            // `en`, `en-US` and YouTube's `en-orig`, not every language
+            // the platform can auto-translate into.
+            "--sub-langs".to_string(),
+            format!("^{}(-[A-Za-z]+)?$", regex_escape(language)),
+            "--sub-format".to_string(),
+            "vtt/best".to_string(),
+            "--convert-subs".to_string(),
+            "vtt".to_string(),
+            "-o".to_string(),
+            output_template.to_string_lossy().to_string(),
+        ];
+        args.extend(net.yt_dlp_args(self.temp_dir.path())?);
+        args.push(url.to_string());
+
+        let output = process::async_command("yt-dlp", self.temp_dir.path(), Access::Network)?
+            .args(&args)
+            .output()
+            .await
+            .context("Failed to run yt-dlp")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "yt-dlp failed to fetch captions: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        // yt-dlp names them `<stem>.<lang>.vtt`; prefer the plain language
+        // code over regional or `-orig` variants.
+        let mut files: Vec<PathBuf> = std::fs::read_dir(self.temp_dir.path())?
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(&stem) && n.ends_with(".vtt"))
+            })
+            .collect();
+        files.sort_by_key(|p| !p.to_string_lossy().ends_with(&format!(".{}.vtt", language)));
+
+        let mut segments = None;
+        if let Some(path) = files.first() {
+            info!("💬 Using captions from {}", path.display());
+            let parsed = captions::parse_vtt(&std::fs::read_to_string(path)?);
+            segments = (!parsed.is_empty()).then_some(parsed);
+        }
+        for path in &files {
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(segments)
+    }
+
+    /// Download the audio of `url` as `format`, only the `clip` part if set.
+    /// Returns the file and the bytes fetched.
+    pub async fn download_audio(
+        &self,
+        url: &str,
+        clip: &ClipRange,
+        net: &Network,
+        format: AudioFormat,
+    ) -> Result<(PathBuf, u64)> {
         // Generate unique filename to avoid conflicts when downloading multiple videos
         let unique_id = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
@@ -120,24 +328,30 @@ impl VideoDownloader {
             .temp_dir
             .path()
             .join(format!("video_{}.%(ext)s", unique_id));
-        let expected_path = self
-            .temp_dir
-            .path()
-            .join(format!("video_{}.mp3", unique_id));
-
-        let mut args: Vec<String> = vec![
-            "-x".to_string(), // Extract audio
-            "--audio-format".to_string(),
-            "mp3".to_string(),
+        let mut args: Vec<String> = vec!["-x".to_string()]; // Extract audio
+        args.extend(format.yt_dlp_args().iter().map(|a| a.to_string()));
+        args.extend([
             "-o".to_string(),
             output_template.to_string_lossy().to_string(),
-        ];
-        if let Some(c) = cookies_args() {
-            args.extend(c);
+            // Report the size of the fetched stream; the file on disk may
+            // be re-encoded audio and say little about transfer size.
+            "--no-simulate".to_string(),
+            "--print".to_string(),
+            "after_move:%(filesize,filesize_approx)s".to_string(),
+            // And where the audio ended up: with `original` the extension
+            // is the source's.
+            "--print".to_string(),
+            "after_move:filepath".to_string(),
+        ]);
+        if clip.is_set() {
+            info!("✂️  Downloading only {}", clip);
+            args.push("--download-sections".to_string());
+            args.push(clip.download_section());
         }
+        args.extend(net.yt_dlp_args(self.temp_dir.path())?);
         args.push(url.to_string());
 
-        let output = Command::new("yt-dlp")
+        let output = process::async_command("yt-dlp", self.temp_dir.path(), Access::Network)?
             .args(&args)
             .output()
             .await
@@ -150,7 +364,16 @@ impl VideoDownloader {
             );
         }
 
-        // Find the downloaded file
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let expected_path = stdout
+            .lines()
+            .map(str::trim)
+            .find(|l| l.starts_with(&*self.temp_dir.path().to_string_lossy()))
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                let file_name = format!("video_{}.{}", unique_id, format.extension());
+                self.temp_dir.path().join(file_name)
+            });
         if !expected_path.exists() {
             anyhow::bail!(
                 "Downloaded audio file not found at {}",
@@ -160,37 +383,792 @@ impl VideoDownloader {
 
         info!("✅ Downloaded audio to {}", expected_path.display());
 
-        Ok(expected_path)
+        // The reported filesize is the whole stream's, so a clip falls back
+        // to the size of what landed on disk.
+        let reported = stdout
+            .lines()
+            .filter_map(|l| l.trim().parse::<f64>().ok())
+            .next_back()
+            .filter(|_| !clip.is_set());
+        let bytes = reported
+            .map(|b| b as u64)
+            .unwrap_or_else(|| std::fs::metadata(&expected_path).map_or(0, |m| m.len()));
+
+        Ok((expected_path, bytes))
+    }
+
+    /// Start fetching the audio of `url` (the `clip` part if set) decoded
+    /// to 16 kHz mono PCM through `audio_filter`, for whisper to read as it
+    /// arrives. Nothing is written to disk. A `live` stream is read until
+    /// it ends or its cap is reached.
+    pub fn stream_audio(
+        &self,
+        url: &str,
+        clip: &ClipRange,
+        net: &Network,
+        audio_filter: Option<&str>,
+        live: Option<&LiveCapture>,
+    ) -> Result<AudioStream> {
+        let workdir = self.temp_dir.path();
+        let mut ffmpeg = process::command("ffmpeg", workdir, Access::Local)?;
+        ffmpeg
+            .args(["-hide_banner", "-loglevel", "error"])
+            .args(process::LOCAL_ONLY)
+            .args(["-i", "pipe:0"])
+            // Seeking in a pipe means decoding up to the start.
+            .args(clip.ffmpeg_input_args())
+            .args(clip.ffmpeg_output_args())
+            .args(live.map(LiveCapture::ffmpeg_args).unwrap_or_default());
+        if let Some(filter) = audio_filter {
+            ffmpeg.args(["-af", filter]);
+        }
+        ffmpeg
+            .args(["-ar", "16000", "-ac", "1", "-f", "f32le", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut yt_dlp = process::command("yt-dlp", workdir, Access::Network)?
+            .args(["-f", "bestaudio/best", "--quiet", "--no-part", "-o", "-"])
+            .args(live.map(LiveCapture::yt_dlp_args).unwrap_or_default())
+            .args(net.yt_dlp_args(workdir)?)
+            .arg(url)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run yt-dlp")?;
+        let mut ffmpeg = match ffmpeg.spawn() {
+            Ok(ffmpeg) => ffmpeg,
+            Err(e) => {
+                let _ = yt_dlp.kill();
+                return Err(e).context("Failed to run ffmpeg");
+            }
+        };
+        // Relayed rather than handed over, to count what was fetched.
+        let (Some(mut from), Some(mut to), Some(pcm)) = (
+            yt_dlp.stdout.take(),
+            ffmpeg.stdin.take(),
+            ffmpeg.stdout.take(),
+        ) else {
+            anyhow::bail!("Failed to connect yt-dlp to ffmpeg");
+        };
+        let relay = std::thread::spawn(move || {
+            let mut relayed = Relayed::default();
+            let mut buf = vec![0; 64 * 1024];
+            while let Ok(n) = from.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                if to.write_all(&buf[..n]).is_err() {
+                    relayed.cut_short = true;
+                    break;
+                }
+                relayed.bytes += n as u64;
+            }
+            relayed
+        });
+        let stderr = [&mut yt_dlp, &mut ffmpeg].map(|child| {
+            let mut pipe = child.stderr.take();
+            std::thread::spawn(move || {
+                let mut text = String::new();
+                if let Some(pipe) = &mut pipe {
+                    let _ = pipe.read_to_string(&mut text);
+                }
+                text
+            })
+        });
+        Ok(AudioStream {
+            children: [yt_dlp, ffmpeg],
+            pcm,
+            relay: Some(relay),
+            stderr: stderr.map(Some),
+        })
+    }
+
+    /// Sniff whether `url` is a direct media link: a HEAD request's content
+    /// type, else the file extension. Sites with a known platform rule go
+    /// straight to yt-dlp.
+    pub async fn probe_direct_media(&self, url: &str, net: &Network) -> Option<DirectMedia> {
+        let parsed = reqwest::Url::parse(url).ok()?;
+        let host = parsed.host_str()?.to_lowercase();
+        let config = Config::load();
+        let known = config
+            .platform_rules
+            .iter()
+            .map(|r| r.domain.as_str())
+            .chain(BUILTIN_PLATFORM_RULES.iter().map(|(d, _)| *d))
+            .any(|domain| host_matches(&host, domain));
+        if known {
+            return None;
+        }
+
+        let filename = parsed
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .filter(|s| !s.is_empty())
+            .unwrap_or("media")
+            .to_string();
+        let content_type = net
+            .http_client()
+            .ok()?
+            .head(url)
+            .timeout(std::time::Duration::from_secs(15))
+            .send()
+            .await
+            .ok()
+            .filter(|r| r.status().is_success())
+            .and_then(|r| {
+                r.headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+            });
+        let stream = stream_type(content_type.as_deref(), &filename)?;
+        Some(DirectMedia {
+            content_type,
+            filename,
+            stream,
+        })
+    }
+
+    /// Whether the HLS playlist at `url` is live: its media playlist (the
+    /// first variant of a master playlist) doesn't say it has ended.
+    pub async fn hls_is_live(&self, url: &str, net: &Network) -> Result<bool> {
+        let client = net.http_client()?;
+        let fetch = |url: reqwest::Url| {
+            let request = client
+                .get(url.clone())
+                .timeout(std::time::Duration::from_secs(30));
+            async move {
+                request
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())?
+                    .text()
+                    .await
+                    .with_context(|| format!("Failed to read the playlist {}", url))
+            }
+        };
+        let url = reqwest::Url::parse(url)?;
+        let mut playlist = fetch(url.clone()).await?;
+        if let Some(variant) = first_variant(&playlist) {
+            playlist = fetch(url.join(variant)?).await?;
+        }
+        Ok(!playlist_ended(&playlist))
+    }
+
+    /// Fetch the audio of an HLS playlist (the `clip` part if set) with
+    /// ffmpeg, segment by segment, into `format`. A `live` playlist is read
+    /// until it ends or its cap is reached. Returns the file and its size.
+    pub async fn download_hls(
+        &self,
+        url: &str,
+        clip: &ClipRange,
+        net: &Network,
+        format: AudioFormat,
+        live: Option<&LiveCapture>,
+    ) -> Result<(PathBuf, u64)> {
+        let unique_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = self
+            .temp_dir
+            .path()
+            .join(format!("hls_{}.{}", unique_id, format.extension()));
+        let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error"]
+            .into_iter()
+            .chain(HLS_PROTOCOLS)
+            .map(String::from)
+            .collect();
+        if let Some(proxy) = &net.proxy {
+            // ffmpeg speaks HTTP proxies only.
+            if !proxy.starts_with("http://") {
+                anyhow::bail!("HLS streams can't be fetched through {}", proxy);
+            }
+            args.extend(["-http_proxy".to_string(), proxy.clone()]);
+        }
+        if let Some(live) = live {
+            args.extend(live.hls_args());
+        }
+        args.extend(clip.ffmpeg_input_args());
+        args.extend(["-i".to_string(), url.to_string()]);
+        args.extend(clip.ffmpeg_output_args());
+        args.extend(live.map(LiveCapture::ffmpeg_args).unwrap_or_default());
+
+        let output = process::async_command("ffmpeg", self.temp_dir.path(), Access::Network)?
+            .args(args)
+            .arg("-vn")
+            .args(format.ffmpeg_args())
+            .arg("-y")
+            .arg(&path)
+            .output()
+            .await
+            .context("Failed to run ffmpeg")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "ffmpeg failed to read the HLS stream: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let bytes = std::fs::metadata(&path).map_or(0, |m| m.len());
+        info!("✅ Downloaded {} ({} bytes)", path.display(), bytes);
+        Ok((path, bytes))
+    }
+
+    /// Download a direct media link over HTTP. Returns the file and its
+    /// size.
+    pub async fn download_direct(
+        &self,
+        url: &str,
+        media: &DirectMedia,
+        net: &Network,
+    ) -> Result<(PathBuf, u64)> {
+        use tokio::io::AsyncWriteExt;
+
+        let unique_id = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let ext = std::path::Path::new(&media.filename)
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| e.len() <= 5 && e.chars().all(|c| c.is_ascii_alphanumeric()))
+            .unwrap_or("media");
+        let path = self
+            .temp_dir
+            .path()
+            .join(format!("direct_{}.{}", unique_id, ext));
+
+        let mut response = net
+            .http_client()?
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to download {}", url))?;
+        let mut file = tokio::fs::File::create(&path).await?;
+        let mut bytes = 0u64;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .with_context(|| format!("Download of {} was interrupted", url))?
+        {
+            file.write_all(&chunk).await?;
+            bytes += chunk.len() as u64;
+        }
+        file.flush().await?;
+
+        info!("✅ Downloaded {} ({} bytes)", path.display(), bytes);
+        Ok((path, bytes))
+    }
+
+    /// Fetch and parse an RSS or Atom podcast feed.
+    pub async fn fetch_feed(&self, url: &str, net: &Network) -> Result<Feed> {
+        let xml = net
+            .http_client()?
+            .get(url)
+            .timeout(std::time::Duration::from_secs(30))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .with_context(|| format!("Failed to fetch feed {}", url))?
+            .text()
+            .await
+            .with_context(|| format!("Failed to read feed {}", url))?;
+        let feed = parse_feed(&xml);
+        if feed.episodes.is_empty() {
+            anyhow::bail!("No episodes with audio found in feed {}", url);
+        }
+        info!("🎙️  {}: {} episode(s)", feed.title, feed.episodes.len());
+        Ok(feed)
+    }
+}
+
+/// Cap on capturing a live stream without `max_live_minutes`, so a stream
+/// that runs around the clock doesn't hold a job forever.
+pub const DEFAULT_MAX_LIVE_MINUTES: u32 = 240;
+
+/// How much of a live stream to capture.
+pub struct LiveCapture {
+    /// From the stream's beginning, as far as the platform keeps it,
+    /// rather than from now.
+    pub from_start: bool,
+    pub max_minutes: u32,
+}
+
+impl LiveCapture {
+    fn yt_dlp_args(&self) -> Vec<String> {
+        if self.from_start {
+            vec!["--live-from-start".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// ffmpeg stops at the cap; yt-dlp then fails on the closed pipe, as
+    /// at the end of a clip.
+    fn ffmpeg_args(&self) -> Vec<String> {
+        vec!["-t".to_string(), (self.max_minutes as u64 * 60).to_string()]
+    }
+
+    /// ffmpeg's HLS reader starts three segments from the live edge unless
+    /// told to start at the first.
+    fn hls_args(&self) -> Vec<String> {
+        if self.from_start {
+            vec!["-live_start_index".to_string(), "0".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Audio being downloaded and decoded: yt-dlp piped into ffmpeg, whose
+/// 16 kHz mono f32 PCM is read from here. Dropping it stops both.
+pub struct AudioStream {
+    /// yt-dlp, ffmpeg.
+    children: [Child; 2],
+    pcm: ChildStdout,
+    /// Copies yt-dlp's output into ffmpeg.
+    relay: Option<JoinHandle<Relayed>>,
+    /// What each child wrote to stderr, once it exits.
+    stderr: [Option<JoinHandle<String>>; 2],
+}
+
+#[derive(Default)]
+struct Relayed {
+    bytes: u64,
+    /// ffmpeg stopped reading: it reached the end of the clip (or failed).
+    cut_short: bool,
+}
+
+impl Read for AudioStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.pcm.read(buf)
+    }
+}
+
+impl AudioStream {
+    /// Wait for yt-dlp and ffmpeg after the PCM has been read to the end.
+    /// Returns the bytes downloaded, or why either failed.
+    pub fn finish(mut self) -> Result<u64> {
+        let relayed = self
+            .relay
+            .take()
+            .and_then(|relay| relay.join().ok())
+            .unwrap_or_default();
+        for (i, program) in [(1, "ffmpeg"), (0, "yt-dlp")] {
+            let status = self.children[i]
+                .wait()
+                .with_context(|| format!("Failed to wait for {}", program))?;
+            let stderr = self.stderr[i]
+                .take()
+                .and_then(|h| h.join().ok())
+                .unwrap_or_default();
+            // When ffmpeg stops early, yt-dlp fails on the closed pipe.
+            let stopped = i == 0 && relayed.cut_short;
+            if !(status.success() || stopped) {
+                anyhow::bail!(
+                    "{} failed while streaming audio: {}",
+                    program,
+                    stderr.trim()
+                );
+            }
+        }
+        Ok(relayed.bytes)
+    }
+}
+
+impl Drop for AudioStream {
+    fn drop(&mut self) {
+        // Already exited after `finish`; otherwise stopped early.
+        for child in &mut self.children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// A URL serving a media file itself (a podcast enclosure, a file on a
+/// CDN) or an HLS playlist, rather than a page yt-dlp has an extractor
+/// for.
+#[derive(Debug, Clone)]
+pub struct DirectMedia {
+    pub content_type: Option<String>,
+    /// Last path segment of the URL, e.g. `episode-42.mp3`.
+    pub filename: String,
+    pub stream: StreamType,
+}
+
+/// How a direct media URL is fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamType {
+    /// A file, downloaded over HTTP.
+    File,
+    /// An HLS playlist (`.m3u8`), read by ffmpeg segment by segment.
+    Hls,
+}
+
+/// Content types of HLS playlists.
+const HLS_CONTENT_TYPES: &[&str] = &[
+    "application/vnd.apple.mpegurl",
+    "application/x-mpegurl",
+    "audio/mpegurl",
+    "audio/x-mpegurl",
+];
+
+/// ffmpeg input options for an HLS playlist: the network protocols its
+/// segments come over, and not local files, which a crafted playlist
+/// could otherwise name.
+const HLS_PROTOCOLS: [&str; 2] = ["-protocol_whitelist", "http,https,tcp,tls,crypto"];
+
+/// Extensions trusted as media when the server's content type doesn't say.
+const DIRECT_MEDIA_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "wav", "flac", "ogg", "opus", "aac", "mp4", "m4v", "mov", "webm", "mkv",
+];
+
+impl DirectMedia {
+    /// Metadata from the URL alone: the file name as id and title, the host
+    /// as channel.
+    pub fn metadata(&self, url: &str) -> VideoMetadata {
+        let stem = std::path::Path::new(&self.filename)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("media")
+            .to_string();
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| "Unknown".to_string());
+        VideoMetadata {
+            video_id: stem.clone(),
+            title: stem,
+            channel: host,
+            duration: 0,
+            upload_date: String::new(),
+            platform: detect_platform(
+                url,
+                &serde_json::json!({ "extractor": "Direct Link" }),
+                &Config::load().platform_rules,
+            ),
+            url: url.to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        }
+    }
+}
+
+/// A podcast feed: its title and the episodes that have an audio enclosure,
+/// newest first.
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    pub episodes: Vec<Episode>,
+}
+
+impl Feed {
+    /// Episodes named in `wanted` (by guid, audio URL or title, ignoring
+    /// case), or the newest `latest` when `wanted` is empty.
+    pub fn select(&self, wanted: &[String], latest: usize) -> Result<Vec<Episode>> {
+        if wanted.is_empty() {
+            return Ok(self.episodes.iter().take(latest).cloned().collect());
+        }
+        wanted
+            .iter()
+            .map(|w| {
+                let w = w.trim();
+                self.episodes
+                    .iter()
+                    .find(|e| e.guid == w || e.audio_url == w || e.title.eq_ignore_ascii_case(w))
+                    .cloned()
+                    .with_context(|| format!("No episode matching '{}' in {}", w, self.title))
+            })
+            .collect()
+    }
+}
+
+/// Parse an RSS 2.0 or Atom feed. Just enough XML for feeds: tags are
+/// matched by name, CDATA and entities are decoded, anything else is
+/// ignored. Entries without an audio enclosure are skipped.
+pub fn parse_feed(xml: &str) -> Feed {
+    let (entry_tag, atom) = if find_tag(xml, "item").is_some() {
+        ("item", false)
+    } else {
+        ("entry", true)
+    };
+    let head = &xml[..find_tag(xml, entry_tag).map_or(xml.len(), |(start, _)| start)];
+    let title = tag_text(head, "title").unwrap_or_else(|| "Unknown podcast".to_string());
+
+    let mut episodes: Vec<Episode> = tag_blocks(xml, entry_tag)
+        .into_iter()
+        .filter_map(|block| {
+            let audio_url = if atom {
+                tag_attrs(block, "link")
+                    .into_iter()
+                    .find(|attrs| attr(attrs, "rel").as_deref() == Some("enclosure"))
+                    .and_then(|attrs| attr(attrs, "href"))
+            } else {
+                tag_attrs(block, "enclosure")
+                    .first()
+                    .and_then(|attrs| attr(attrs, "url"))
+            }?;
+            let guid = tag_text(block, if atom { "id" } else { "guid" })
+                .unwrap_or_else(|| audio_url.clone());
+            Some(Episode {
+                show: title.clone(),
+                title: tag_text(block, "title").unwrap_or_else(|| guid.clone()),
+                guid,
+                published: ["pubDate", "published", "updated"]
+                    .iter()
+                    .find_map(|tag| tag_text(block, tag)),
+                audio_url,
+                duration: tag_text(block, "itunes:duration")
+                    .and_then(|d| parse_time(&d).ok())
+                    .map(|secs| secs as u64),
+            })
+        })
+        .collect();
+
+    // Feeds are usually newest first already, but not always.
+    let timestamp = |e: &Episode| {
+        e.published.as_deref().and_then(|d| {
+            chrono::DateTime::parse_from_rfc2822(d.trim())
+                .or_else(|_| chrono::DateTime::parse_from_rfc3339(d.trim()))
+                .ok()
+        })
+    };
+    episodes.sort_by_key(|e| std::cmp::Reverse(timestamp(e)));
+    Feed { title, episodes }
+}
+
+/// Byte range of the first `<tag ...>` (not `<tagsuffix>`) in `xml`: from
+/// its `<` to just past its `>`.
+fn find_tag(xml: &str, tag: &str) -> Option<(usize, usize)> {
+    let open = format!("<{}", tag);
+    let mut from = 0;
+    while let Some(i) = xml[from..].find(&open) {
+        let start = from + i;
+        let after = start + open.len();
+        if xml[after..].starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            let end = after + xml[after..].find('>')? + 1;
+            return Some((start, end));
+        }
+        from = after;
+    }
+    None
+}
+
+/// Contents of every `<tag>…</tag>` in `xml`.
+fn tag_blocks<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let close = format!("</{}>", tag);
+    let mut blocks = Vec::new();
+    let mut rest = xml;
+    while let Some((_, open_end)) = find_tag(rest, tag) {
+        if rest[..open_end].ends_with("/>") {
+            rest = &rest[open_end..];
+            continue;
+        }
+        let Some(len) = rest[open_end..].find(&close) else {
+            break;
+        };
+        blocks.push(&rest[open_end..open_end + len]);
+        rest = &rest[open_end + len + close.len()..];
+    }
+    blocks
+}
+
+/// Decoded text of the first `<tag>` in `xml`, if not empty.
+fn tag_text(xml: &str, tag: &str) -> Option<String> {
+    let inner = *tag_blocks(xml, tag).first()?;
+    let text = match inner.trim().strip_prefix("<![CDATA[") {
+        Some(cdata) => cdata.split("]]>").next().unwrap_or_default().to_string(),
+        None => decode_entities(inner),
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Attribute text (between the name and `>`) of every `<tag ...>` in `xml`.
+fn tag_attrs<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some((start, end)) = find_tag(rest, tag) {
+        found.push(rest[start + tag.len() + 1..end - 1].trim_end_matches('/'));
+        rest = &rest[end..];
+    }
+    found
+}
+
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(i) = rest.find(name) {
+        let starts_word = i == 0 || rest[..i].ends_with(char::is_whitespace);
+        rest = &rest[i + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !starts_word {
+            continue;
+        }
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|q| *q == '"' || *q == '\'')?;
+        let value = &value[1..];
+        return Some(decode_entities(&value[..value.find(quote)?]));
+    }
+    None
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                num => {
+                    let code = match num.strip_prefix("#x").or_else(|| num.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => num.strip_prefix('#').and_then(|d| d.parse().ok()),
+                    };
+                    char::from_u32(code?)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Media by content type; for generic or missing types, by extension.
+/// Pages (`text/html`) never count, whatever the URL looks like. `None`
+/// when the URL isn't media.
+fn stream_type(content_type: Option<&str>, filename: &str) -> Option<StreamType> {
+    let mime = content_type
+        .and_then(|c| c.split(';').next())
+        .map(|c| c.trim().to_lowercase());
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    let generic = matches!(
+        mime.as_deref(),
+        Some("application/octet-stream") | Some("binary/octet-stream") | None
+    );
+    match (mime.as_deref(), extension.as_deref()) {
+        (Some(m), _) if HLS_CONTENT_TYPES.contains(&m) => Some(StreamType::Hls),
+        (Some(m), _) if m.starts_with("audio/") || m.starts_with("video/") => {
+            Some(StreamType::File)
+        }
+        (Some("application/ogg"), _) => Some(StreamType::File),
+        // Playlists are often served as plain text.
+        (Some("text/plain"), Some("m3u8")) => Some(StreamType::Hls),
+        (_, Some("m3u8")) if generic => Some(StreamType::Hls),
+        (_, Some(e)) if generic && DIRECT_MEDIA_EXTENSIONS.contains(&e) => Some(StreamType::File),
+        _ => None,
     }
 }
 
-fn detect_platform(url: &str, json: &serde_json::Value) -> String {
-    // Try to detect from URL first
-    let url_lower = url.to_lowercase();
+/// The URI of the first variant of a master playlist, `None` for a media
+/// playlist.
+fn first_variant(playlist: &str) -> Option<&str> {
+    let mut lines = playlist.lines().map(str::trim);
+    lines.find(|line| line.starts_with("#EXT-X-STREAM-INF"))?;
+    lines.find(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Whether a media playlist is complete rather than live.
+fn playlist_ended(playlist: &str) -> bool {
+    playlist
+        .lines()
+        .map(str::trim)
+        .any(|line| line == "#EXT-X-ENDLIST" || line == "#EXT-X-PLAYLIST-TYPE:VOD")
+}
+
+fn regex_escape(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| {
+            let escape = !c.is_ascii_alphanumeric() && c != '-';
+            escape.then_some('\\').into_iter().chain(std::iter::once(c))
+        })
+        .collect()
+}
 
-    if url_lower.contains("youtube.com") || url_lower.contains("youtu.be") {
-        return "YouTube".to_string();
-    } else if url_lower.contains("vimeo.com") {
-        return "Vimeo".to_string();
-    } else if url_lower.contains("tiktok.com") {
-        return "TikTok".to_string();
-    } else if url_lower.contains("twitter.com") || url_lower.contains("x.com") {
-        return "Twitter/X".to_string();
-    } else if url_lower.contains("facebook.com") || url_lower.contains("fb.watch") {
-        return "Facebook".to_string();
-    } else if url_lower.contains("instagram.com") {
-        return "Instagram".to_string();
-    } else if url_lower.contains("twitch.tv") {
-        return "Twitch".to_string();
+/// Built-in domain → platform name table. User `platform_rules` from the
+/// config file are consulted first.
+const BUILTIN_PLATFORM_RULES: &[(&str, &str)] = &[
+    ("youtube.com", "YouTube"),
+    ("youtu.be", "YouTube"),
+    ("vimeo.com", "Vimeo"),
+    ("tiktok.com", "TikTok"),
+    ("twitter.com", "Twitter/X"),
+    ("x.com", "Twitter/X"),
+    ("facebook.com", "Facebook"),
+    ("fb.watch", "Facebook"),
+    ("instagram.com", "Instagram"),
+    ("twitch.tv", "Twitch"),
+];
+
+fn detect_platform(url: &str, json: &serde_json::Value, user_rules: &[PlatformRule]) -> String {
+    // Try to detect from the URL's host first
+    if let Some(host) = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_lowercase))
+    {
+        let user = user_rules
+            .iter()
+            .map(|r| (r.domain.as_str(), r.name.as_str()));
+        let builtin = BUILTIN_PLATFORM_RULES.iter().copied();
+        if let Some((_, name)) = user
+            .chain(builtin)
+            .find(|(domain, _)| host_matches(&host, domain))
+        {
+            return name.to_string();
+        }
     }
 
     // Fallback to extractor from metadata
     json["extractor"].as_str().unwrap_or("Unknown").to_string()
 }
 
+/// True if `host` is `domain` or one of its subdomains. Matching on the host
+/// (rather than a substring of the URL) keeps `x.com` from claiming
+/// `dropbox.com` links.
+fn host_matches(host: &str, domain: &str) -> bool {
+    let domain = domain.trim().trim_start_matches("*.").to_lowercase();
+    !domain.is_empty()
+        && (host == domain
+            || host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|rest| rest.ends_with('.')))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::resolve_cookies_args;
+    use super::{
+        COOKIES_COPY, DEFAULT_MAX_LIVE_MINUTES, LiveCapture, Network, StreamType, VideoDownloader,
+        cookies_file_in, detect_platform, first_variant, parse_feed, playlist_ended,
+        resolve_cookies_args, stream_type,
+    };
+    use crate::config::PlatformRule;
+    use serde_json::json;
 
     #[test]
     fn cookies_file_takes_priority_over_browser() {
@@ -216,4 +1194,219 @@ mod tests {
         assert!(resolve_cookies_args(None, None).is_none());
         assert!(resolve_cookies_args(Some(""), Some("  ")).is_none());
     }
+
+    #[test]
+    fn sniffs_direct_media_links() {
+        let file = Some(StreamType::File);
+        assert_eq!(stream_type(Some("audio/mpeg"), "episode"), file);
+        assert_eq!(stream_type(Some("video/mp4; charset=binary"), "x"), file);
+        assert_eq!(
+            stream_type(Some("application/octet-stream"), "episode-42.MP3"),
+            file
+        );
+        assert_eq!(stream_type(None, "talk.m4a"), file);
+        assert_eq!(stream_type(None, "watch"), None);
+        assert_eq!(
+            stream_type(Some("text/html; charset=utf-8"), "fake.mp3"),
+            None
+        );
+        assert_eq!(stream_type(Some("text/plain"), "notes.mp3"), None);
+
+        let hls = Some(StreamType::Hls);
+        assert_eq!(
+            stream_type(Some("application/vnd.apple.mpegurl"), "master"),
+            hls
+        );
+        assert_eq!(stream_type(Some("audio/x-mpegurl"), "index.m3u8"), hls);
+        assert_eq!(stream_type(Some("text/plain"), "index.M3U8"), hls);
+        assert_eq!(stream_type(None, "talk.m3u8"), hls);
+    }
+
+    #[test]
+    fn reads_hls_playlists() {
+        let master = "#EXTM3U\n\
+                      #EXT-X-STREAM-INF:BANDWIDTH=800000\n\
+                      \n\
+                      low/index.m3u8\n\
+                      #EXT-X-STREAM-INF:BANDWIDTH=2000000\n\
+                      high/index.m3u8\n";
+        assert_eq!(first_variant(master), Some("low/index.m3u8"));
+        let vod = "#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXTINF:6,\nseg0.ts\n#EXT-X-ENDLIST\n";
+        assert_eq!(first_variant(vod), None);
+        assert!(playlist_ended(vod));
+        assert!(playlist_ended("#EXTM3U\n#EXT-X-PLAYLIST-TYPE:VOD\n"));
+        assert!(!playlist_ended("#EXTM3U\n#EXTINF:6,\nseg41.ts\n"));
+    }
+
+    #[test]
+    fn builtin_rules_match_hosts_and_subdomains() {
+        let meta = json!({});
+        let p = |url| detect_platform(url, &meta, &[]);
+        assert_eq!(p("https://www.youtube.com/watch?v=x"), "YouTube");
+        assert_eq!(p("https://youtu.be/x"), "YouTube");
+        assert_eq!(p("https://x.com/user/status/1"), "Twitter/X");
+        // Substring lookalikes no longer match.
+        assert_eq!(p("https://www.dropbox.com/s/x.mp4"), "Unknown");
+    }
+
+    #[test]
+    fn user_rules_win_and_extractor_is_the_fallback() {
+        let rules = vec![
+            PlatformRule {
+                domain: "*.tube.example.org".to_string(),
+                name: "PeerTube (Example)".to_string(),
+            },
+            PlatformRule {
+                domain: "vimeo.com".to_string(),
+                name: "Vimeo Pro".to_string(),
+            },
+        ];
+        let meta = json!({"extractor": "kaltura", "extractor_key": "Kaltura"});
+        let p = |url| detect_platform(url, &meta, &rules);
+        assert_eq!(p("https://tube.example.org/w/abc"), "PeerTube (Example)");
+        assert_eq!(p("https://vimeo.com/123"), "Vimeo Pro");
+        assert_eq!(p("https://media.example.edu/v/1"), "kaltura");
+    }
+
+    #[test]
+    fn parses_rss_and_atom_feeds() {
+        let rss = r#"<?xml version="1.0"?>
+<rss xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"><channel>
+  <title>Rust &amp; Friends</title>
+  <image><title>ignored</title></image>
+  <item>
+    <title><![CDATA[Ep 1: <Intro>]]></title>
+    <guid isPermaLink="false">ep-1</guid>
+    <pubDate>Mon, 01 Jan 2024 10:00:00 +0000</pubDate>
+    <enclosure length="1" type="audio/mpeg" url="https://cdn.example.com/1.mp3?a=1&amp;b=2"/>
+    <itunes:duration>1:02:03</itunes:duration>
+  </item>
+  <item><title>Trailer, no audio</title></item>
+  <item>
+    <title>Ep 2</title>
+    <pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate>
+    <enclosure url='https://cdn.example.com/2.mp3' type="audio/mpeg" />
+  </item>
+</channel></rss>"#;
+        let feed = parse_feed(rss);
+        assert_eq!(feed.title, "Rust & Friends");
+        assert_eq!(feed.episodes.len(), 2);
+        // Newest first, whatever the feed order.
+        let ep2 = &feed.episodes[0];
+        assert_eq!(ep2.title, "Ep 2");
+        assert_eq!(ep2.guid, "https://cdn.example.com/2.mp3");
+        let ep1 = &feed.episodes[1];
+        assert_eq!(ep1.title, "Ep 1: <Intro>");
+        assert_eq!(ep1.show, "Rust & Friends");
+        assert_eq!(ep1.guid, "ep-1");
+        assert_eq!(ep1.audio_url, "https://cdn.example.com/1.mp3?a=1&b=2");
+        assert_eq!(ep1.duration, Some(3723));
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Atom Cast</title>
+  <entry>
+    <title>First</title>
+    <id>urn:uuid:1</id>
+    <published>2024-03-05T08:00:00Z</published>
+    <link rel="alternate" href="https://example.com/first"/>
+    <link rel="enclosure" type="audio/mpeg" href="https://example.com/first.mp3"/>
+  </entry>
+</feed>"#;
+        let feed = parse_feed(atom);
+        assert_eq!(feed.title, "Atom Cast");
+        assert_eq!(feed.episodes[0].audio_url, "https://example.com/first.mp3");
+        assert_eq!(feed.episodes[0].guid, "urn:uuid:1");
+
+        let rss_feed = parse_feed(rss);
+        let picked = rss_feed.select(&["ep 2".to_string()], 1).unwrap();
+        assert_eq!(picked[0].title, "Ep 2");
+        assert_eq!(rss_feed.select(&[], 5).unwrap().len(), 2);
+        assert!(rss_feed.select(&["missing".to_string()], 1).is_err());
+    }
+
+    #[test]
+    fn network_flags_and_proxy_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let cookies = dir.path().join("youtube.txt");
+        std::fs::write(&cookies, "# Netscape HTTP Cookie File\n").unwrap();
+        let workdir = dir.path().join("job");
+        std::fs::create_dir(&workdir).unwrap();
+        let net = Network {
+            cookies: resolve_cookies_args(cookies.to_str(), None),
+            proxy: Some("socks5://127.0.0.1:1080".to_string()),
+        };
+        // yt-dlp gets a copy in the job's directory, not the file itself.
+        let copy = workdir.join(COOKIES_COPY);
+        assert_eq!(
+            net.yt_dlp_args(&workdir).unwrap(),
+            [
+                "--cookies",
+                copy.to_str().unwrap(),
+                "--proxy",
+                "socks5://127.0.0.1:1080"
+            ]
+        );
+        assert_eq!(
+            std::fs::read(&copy).unwrap(),
+            std::fs::read(&cookies).unwrap()
+        );
+        assert!(net.http_client().is_ok());
+        assert!(Network::default().yt_dlp_args(&workdir).unwrap().is_empty());
+
+        let bad = Network {
+            proxy: Some("not a proxy".to_string()),
+            ..Default::default()
+        };
+        assert!(bad.http_client().is_err());
+    }
+
+    #[test]
+    fn request_cookies_files_stay_in_cookies_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let cookies_dir = dir.path().join("cookies");
+        std::fs::create_dir(&cookies_dir).unwrap();
+        std::fs::write(cookies_dir.join("youtube.txt"), "").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "").unwrap();
+
+        let path = cookies_file_in(&cookies_dir, "youtube.txt").unwrap();
+        assert_eq!(
+            path,
+            cookies_dir.canonicalize().unwrap().join("youtube.txt")
+        );
+        let absolute = cookies_dir.join("youtube.txt");
+        assert!(cookies_file_in(&cookies_dir, absolute.to_str().unwrap()).is_ok());
+        assert!(cookies_file_in(&cookies_dir, "../secret.txt").is_err());
+        let outside = dir.path().join("secret.txt");
+        assert!(cookies_file_in(&cookies_dir, outside.to_str().unwrap()).is_err());
+        assert!(cookies_file_in(&cookies_dir, "missing.txt").is_err());
+        assert!(cookies_file_in(&cookies_dir, ".").is_err());
+    }
+
+    #[test]
+    fn live_capture_args() {
+        let capture = LiveCapture {
+            from_start: true,
+            max_minutes: 90,
+        };
+        assert_eq!(capture.yt_dlp_args(), ["--live-from-start"]);
+        assert_eq!(capture.ffmpeg_args(), ["-t", "5400"]);
+        assert_eq!(capture.hls_args(), ["-live_start_index", "0"]);
+        let now = LiveCapture {
+            from_start: false,
+            max_minutes: DEFAULT_MAX_LIVE_MINUTES,
+        };
+        assert!(now.yt_dlp_args().is_empty());
+    }
+
+    #[test]
+    fn scoped_downloaders_do_not_share_a_temp_dir() {
+        let first = VideoDownloader::scoped().unwrap();
+        let second = VideoDownloader::scoped().unwrap();
+        let dir = first.temp_dir.path().to_path_buf();
+        assert_ne!(dir, second.temp_dir.path());
+        std::fs::write(dir.join("video_1.opus"), b"audio").unwrap();
+        drop(first);
+        assert!(!dir.exists());
+        assert!(second.temp_dir.path().is_dir());
+    }
 }
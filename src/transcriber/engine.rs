@@ -1,18 +1,46 @@
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
-use tracing::info;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
 
-use super::audio::AudioProcessor;
-use super::downloader::VideoDownloader;
+use super::audio::{self, AudioProcessor, MediaInfo};
+use super::chapters;
+use super::checkpoint;
+use super::downloader::{
+    DEFAULT_MAX_LIVE_MINUTES, Feed, LiveCapture, Network, StreamType, VideoDownloader, proxy_url,
+};
+use super::glossary::Glossary;
+use super::hotwords;
+use super::library::{self, Entry, Library};
+use super::live::LiveOutput;
+use super::notifier;
+use super::outputs::{self, Transcript};
+use super::progress::Stage;
+use super::refresh::{self, Refreshed};
+use super::scheduler::Scheduler;
+use super::sink::{self, Output, TranscriptSink};
+use super::stages::Stages;
+use super::store::{self, CacheKey};
+use super::topics;
+use super::turns;
 use super::types::{
-    OutputFiles, TranscriptionOptions, TranscriptionResult, VideoMetadata, WhisperModel,
+    AudioTrack, AudioTrackSelection, Chapter, ClipRange, IfExists, OutputFiles, OutputFormat,
+    STEREO_SPEAKERS, Segment, SpeakerTurns, TranscriptionOptions, TranscriptionResult,
+    VideoMetadata, WhisperModel, captions_label, captions_label_language, model_label,
 };
-use super::whisper::WhisperTranscriber;
+use super::usage::UsageMeter;
+use super::webhook::Webhook;
+use super::whisper::{self, WhisperOutput, WhisperTranscriber};
+use crate::config::Config;
+use crate::metrics;
+use crate::utils::{encryption, managed, process};
 
 pub struct TranscriberEngine {
     whisper: WhisperTranscriber,
-    downloader: VideoDownloader,
-    audio_processor: AudioProcessor,
+    scheduler: Scheduler,
+    /// Overrides the config file's `output_sink`.
+    sink: Option<Arc<dyn TranscriptSink>>,
 }
 
 impl Default for TranscriberEngine {
@@ -25,67 +53,602 @@ impl TranscriberEngine {
     pub fn new() -> Self {
         Self {
             whisper: WhisperTranscriber::new(),
-            downloader: VideoDownloader::new(),
-            audio_processor: AudioProcessor::new(),
+            scheduler: Scheduler::new(&Config::load().workers),
+            sink: None,
         }
     }
 
+    /// Send outputs to `sink` rather than where the config file says,
+    /// without the copies an S3 `output_sink` would get.
+    pub fn with_sink(mut self, sink: Arc<dyn TranscriptSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
     pub async fn transcribe(&self, options: TranscriptionOptions) -> Result<TranscriptionResult> {
+        let webhook = Webhook::resolve(options.webhook_url.as_deref());
+        let source = options.url.clone();
+        let result = self.transcribe_or_reuse(options).await;
+        if let Some(webhook) = webhook {
+            webhook.send(&source, &result);
+        }
+        notifier::notify(&source, &result);
+        result
+    }
+
+    async fn transcribe_or_reuse(
+        &self,
+        options: TranscriptionOptions,
+    ) -> Result<TranscriptionResult> {
+        if !options.force {
+            match self.find_existing(&options).await {
+                Ok(Some(result)) => return Ok(result),
+                Ok(None) => {}
+                Err(e) => warn!("Could not look for an existing transcript: {:#}", e),
+            }
+        }
+        let started = Instant::now();
+        metrics::global().transcription_started();
+        let result = self.run(options).await;
+        metrics::global().transcription_finished(&result, started.elapsed());
+        result
+    }
+
+    async fn run(&self, mut options: TranscriptionOptions) -> Result<TranscriptionResult> {
         info!("🎬 Starting transcription for: {}", options.url);
+        // Before profiles fill in the options: the index is keyed by what
+        // was asked for.
+        let cache_key = CacheKey::new(&options);
+        let meter = UsageMeter::start();
+        let config = Config::load();
+        let audio_format = config.audio_cache_format;
+        let (sink, copies) = match &self.sink {
+            Some(sink) => (sink.clone(), None),
+            None => (
+                sink::from_config(&config.output_sink)?,
+                sink::copies_from_config(&config.output_sink)?,
+            ),
+        };
+        // Temp directories of this job alone, so concurrent jobs never
+        // share a file, removed when it returns, whether it succeeded or not.
+        let downloader = VideoDownloader::scoped()?;
+        let audio_processor = AudioProcessor::scoped()?;
 
         // Create output directory
         std::fs::create_dir_all(&options.output_dir)
             .context("Failed to create output directory")?;
 
         // Determine if URL or local file
-        let is_local = !options.url.starts_with("http://") && !options.url.starts_with("https://");
+        let is_local = is_local_source(&options.url);
+        // Downloaded audio is kept here until the outputs are saved.
+        let stages = Stages::new(
+            Path::new(&options.output_dir),
+            &options.url,
+            &options.clip,
+            audio_format,
+        );
+        let stereo = is_local && options.audio_track == Some(AudioTrackSelection::Stereo);
 
-        let (metadata, audio_path) = if is_local {
+        // Each source is (track number, audio file). URLs and single-track
+        // files yield one untagged source.
+        let mut audio_tracks = Vec::new();
+        // What ffprobe found in a local file.
+        let mut media: Option<MediaInfo> = None;
+        let mut bytes_downloaded = 0;
+        // (language, segments) when platform captions replace whisper.
+        let mut captions: Option<(String, Vec<Segment>)> = None;
+        // Audio to pipe into whisper rather than download first.
+        let mut streamed = false;
+        let (mut metadata, sources) = if is_local {
             info!("📂 Processing local video file");
-            let audio_path = self.process_local_video(&options.url).await?;
-            let metadata = self.get_local_metadata(&options.url)?;
-            (metadata, audio_path)
+            // Absolute, as ffmpeg doesn't run in our working directory.
+            let video_path = std::path::absolute(&options.url)?;
+            if !video_path.exists() {
+                anyhow::bail!("Video file not found: {}", options.url);
+            }
+            media = match audio_processor.probe(&video_path).await {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    warn!(
+                        "Could not probe the file, using ffmpeg's default audio stream: {:#}",
+                        e
+                    );
+                    None
+                }
+            };
+            if let Some(info) = &media {
+                if info.audio_tracks.is_empty() {
+                    anyhow::bail!(
+                        "{} has no audio stream to transcribe (container: {})",
+                        options.url,
+                        info.container
+                    );
+                }
+                info!(
+                    "🎞️ {} ({}s): {}",
+                    info.container,
+                    info.duration,
+                    info.audio_tracks
+                        .iter()
+                        .map(|t| t.details())
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                );
+                audio_tracks = info.audio_tracks.clone();
+            }
+            let audio_only = audio::is_audio_only(&video_path, media.as_ref());
+            let mut sources = Vec::new();
+            if stereo {
+                // Channels stand in for tracks: 1 is left, 2 right.
+                match audio_tracks.first().and_then(|t| t.channels) {
+                    Some(1) => anyhow::bail!(
+                        "audio_track 'stereo' needs a two-channel recording, but the audio is mono"
+                    ),
+                    Some(n) if n > 2 => warn!(
+                        "Audio has {} channels; transcribing the first two as {} and {}",
+                        n, STEREO_SPEAKERS[0], STEREO_SPEAKERS[1]
+                    ),
+                    _ => {}
+                }
+                for channel in 1..=STEREO_SPEAKERS.len() {
+                    let audio_path = audio_processor
+                        .extract_channel(&video_path, channel, &options.clip, audio_format)
+                        .await?;
+                    sources.push((Some(channel), audio_path));
+                }
+            }
+            let tracks = if stereo {
+                Vec::new()
+            } else {
+                select_tracks(&audio_tracks, options.audio_track)?
+            };
+            for track in tracks {
+                // Whisper decodes any audio format itself, so a plain audio
+                // file only needs extracting to pick a track or cut a clip.
+                if audio_only && track.is_none() && !options.clip.is_set() {
+                    info!("🎵 Audio file, skipping extraction");
+                    sources.push((track, video_path.clone()));
+                    continue;
+                }
+                let audio_path = audio_processor
+                    .extract_audio(&video_path, track, &options.clip, audio_format)
+                    .await?;
+                sources.push((track, audio_path));
+            }
+            let mut metadata = self.get_local_metadata(&options.url)?;
+            metadata.duration = media.as_ref().map_or(0, |info| info.duration);
+            (metadata, sources)
+        } else if let Some((metadata, audio_path)) = stages.downloaded() {
+            info!("♻️  Resuming with the audio an earlier run downloaded");
+            (metadata, vec![(None, audio_path)])
+        } else if let Some(media) = downloader
+            .probe_direct_media(&options.url, &options.network())
+            .await
+        {
+            // A podcast .mp3, a conference's raw HLS or similar: yt-dlp's
+            // metadata step has nothing to extract, so fetch it directly.
+            let mut metadata = media.metadata(&options.url);
+            let net = options.network();
+            let download = self.scheduler.download().await;
+            let audio_path = match media.stream {
+                StreamType::File => {
+                    info!(
+                        "🔗 Direct media link ({}), downloading over HTTP",
+                        media.content_type.as_deref().unwrap_or("by extension")
+                    );
+                    let (path, bytes) = downloader
+                        .download_direct(&options.url, &media, &net)
+                        .await?;
+                    drop(download);
+                    bytes_downloaded = bytes;
+                    if options.clip.is_set() {
+                        audio_processor
+                            .extract_audio(&path, None, &options.clip, audio_format)
+                            .await?
+                    } else {
+                        path
+                    }
+                }
+                StreamType::Hls => {
+                    metadata.live = downloader
+                        .hls_is_live(&options.url, &net)
+                        .await
+                        .unwrap_or_else(|e| {
+                            warn!("Couldn't tell whether the HLS stream is live: {:#}", e);
+                            false
+                        });
+                    let live_capture = metadata.live.then(|| LiveCapture {
+                        from_start: options.live_from_start,
+                        max_minutes: options.max_live_minutes.unwrap_or(DEFAULT_MAX_LIVE_MINUTES),
+                    });
+                    info!(
+                        "📼 HLS stream{}, reading it with ffmpeg",
+                        if metadata.live { " (live)" } else { "" }
+                    );
+                    let (path, bytes) = downloader
+                        .download_hls(
+                            &options.url,
+                            &options.clip,
+                            &net,
+                            audio_format,
+                            live_capture.as_ref(),
+                        )
+                        .await?;
+                    drop(download);
+                    bytes_downloaded = bytes;
+                    path
+                }
+            };
+            let audio_path = keep_stage(&stages, &options.url, &metadata, audio_path);
+            (metadata, vec![(None, audio_path)])
         } else {
             info!("🌐 Downloading video from URL");
-            // yt-dlp already extracts audio to mp3 (-x --audio-format mp3),
-            // so the returned path IS the audio. No need to re-run ffmpeg here;
-            // whisper.rs converts to 16kHz mono PCM in one shot.
-            let (metadata, audio_path) = self.downloader.download(&options.url).await?;
-            (metadata, audio_path)
+            if options.audio_track.is_some() {
+                warn!("audio_track only applies to local files; ignoring it for a URL");
+            }
+            info!("📥 Fetching video metadata...");
+            let net = options.network();
+            let metadata = downloader.fetch_metadata(&options.url, &net).await?;
+            info!("📺 Detected platform: {}", metadata.platform);
+            info!("🎬 Title: {}", metadata.title);
+            if metadata.live {
+                if whisper::is_remote() {
+                    anyhow::bail!("Live streams need local Whisper, not a remote backend");
+                }
+                info!("🔴 Live stream: transcribing as it plays");
+                if options.clip.is_set() {
+                    warn!("start_time and end_time don't apply to a live stream; ignoring them");
+                    options.clip = ClipRange::default();
+                }
+                // The transcript is there to be read while the stream runs.
+                options.live_output = true;
+            }
+
+            if options.prefer_captions == Some(true) && !metadata.live {
+                captions = self.fetch_captions(&downloader, &options, &metadata).await;
+            }
+            if options.stream_audio && whisper::is_remote() {
+                warn!("stream_audio needs local Whisper; downloading the audio first");
+            }
+            if captions.is_some() {
+                (metadata, Vec::new())
+            } else if metadata.live || (options.stream_audio && !whisper::is_remote()) {
+                // Started once the profiles below have settled the options.
+                streamed = true;
+                (metadata, Vec::new())
+            } else {
+                // yt-dlp already extracts audio (-x, as is by default),
+                // so the returned path IS the audio. No need to re-run ffmpeg here;
+                // whisper.rs converts to 16kHz mono PCM in one shot.
+                let _download = self.scheduler.download().await;
+                info!("⬇️  Downloading video (audio only)...");
+                let (audio_path, bytes) = downloader
+                    .download_audio(&options.url, &options.clip, &net, audio_format)
+                    .await?;
+                bytes_downloaded = bytes;
+                let audio_path = keep_stage(&stages, &options.url, &metadata, audio_path);
+                (metadata, vec![(None, audio_path)])
+            }
+        };
+        let transcribed_tracks: Vec<usize> = if stereo {
+            Vec::new()
+        } else {
+            sources.iter().filter_map(|(t, _)| *t).collect()
+        };
+        if let Some(episode) = &options.episode {
+            episode.apply(&mut metadata);
+        }
+        metadata.source_note = options
+            .source_note
+            .as_deref()
+            .map(str::trim)
+            .filter(|note| !note.is_empty())
+            .map(str::to_string);
+
+        // Platform profiles can only be resolved now that we know where the
+        // media came from; they fill in whatever the request left unset.
+        let profile = config
+            .profile_for(&metadata.platform)
+            .map(|(name, profile)| {
+                info!("🧩 Applying '{}' profile", name);
+                profile.apply(&mut options);
+                name.to_string()
+            });
+        if options.audio_filter.is_none()
+            && let Some((name, audio_profile)) = config.audio_profile_for(&metadata.channel)
+        {
+            info!("🎛️  Applying '{}' audio profile", name);
+            options.audio_filter = audio_profile.filter_chain();
+        }
+        if options.language_fallback.is_none() {
+            options.language_fallback = config.language_detection.clone();
+        }
+        let hotwords = hotwords::clean(&options.hotwords);
+        options.decoding.prompt = hotwords::prompt(&hotwords);
+        let mut glossary_entries = hotwords::glossary(&hotwords);
+        glossary_entries.append(&mut config.glossary.clone());
+        glossary_entries.append(&mut options.glossary);
+        let glossary = Glossary::new(&glossary_entries)?;
+        options.decoding.speaker_turns |= options.speaker_turns != SpeakerTurns::Off;
+        // A stereo call is laid out as turns unless the request picked a
+        // style; the channels already tell the speakers apart.
+        if stereo && options.speaker_turns == SpeakerTurns::Off {
+            options.speaker_turns = SpeakerTurns::Speaker;
+        }
+        let speakers: Vec<String> = if stereo {
+            STEREO_SPEAKERS.map(String::from).to_vec()
+        } else {
+            Vec::new()
         };
+        let model = *options.model.get_or_insert_with(WhisperModel::default);
+        if model.size.is_english_only()
+            && let Some(lang) = options.language.as_deref()
+            && !matches!(lang, "en" | "auto")
+        {
+            warn!(
+                "{} is an English-only model; language '{}' will be transcribed as English",
+                model, lang
+            );
+        }
 
-        info!(
-            "🎤 Transcribing audio with Whisper ({:?} model)...",
-            options.model
+        let mut base = output_base(
+            &options.output_dir,
+            config.output_layout.as_deref(),
+            &metadata,
+            &options.clip,
         );
-        let (transcript, segments) = self
-            .whisper
-            .transcribe(&audio_path, options.model, options.language.as_deref())
-            .await?;
+        // Chunk progress stays with the unversioned name, so a rerun finds it.
+        let checkpoint_base = base.clone();
+        // Pick the name now rather than when saving: live output is written
+        // straight to the final files.
+        if options.if_exists != IfExists::Overwrite && sink.local_files() && store::exists(&base) {
+            base = store::next_version(&base);
+            info!(
+                "📑 Keeping the existing transcript; writing {}",
+                base.display()
+            );
+        }
+        let live = if !options.live_output || captions.is_some() {
+            None
+        } else if sources.len() > 1 {
+            warn!("live_output needs a single audio track; writing outputs at the end");
+            None
+        } else if !sink.local_files() {
+            warn!("live_output writes to the output directory; sending outputs at the end");
+            None
+        } else if encryption::EncryptionKey::from_env()?.is_some() {
+            warn!("live_output can't be used with encryption at rest; writing outputs at the end");
+            None
+        } else {
+            LiveOutput::create(&base, &options.formats, options.clip.start_ms())?
+        };
+        let captions_language = captions.as_ref().map(|(lang, _)| lang.clone());
+        let label = match &captions_language {
+            Some(lang) => captions_label(lang),
+            None => model_label(model, options.model_path.as_deref()),
+        };
+        let output = if let Some((_, mut segments)) = captions {
+            info!("💬 Using platform captions instead of Whisper");
+            // Captions cover the whole video; keep the clip's part.
+            segments.retain(|s| options.clip.contains(s));
+            WhisperOutput {
+                transcript: segments
+                    .iter()
+                    .map(|s| s.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                segments,
+                gpu_used: None,
+                audio_quality: None,
+                non_speech: Vec::new(),
+                detected_language: None,
+            }
+        } else {
+            let _transcription = self
+                .scheduler
+                .transcription(self.model_memory_mb(&options))
+                .await;
+            let device = if whisper::is_remote() {
+                None
+            } else {
+                self.scheduler.device(options.device, options.gpu).await
+            };
+            if let Some(slot) = &device {
+                info!("🖥️  Running on {}", slot.device());
+                options.device = Some(slot.device());
+            }
+            info!("🎤 Transcribing audio with Whisper ({} model)...", label);
+            if let Some(progress) = &options.progress {
+                progress.set_stage(Stage::Transcribing);
+            }
+            let mut outputs = Vec::with_capacity(sources.len());
+            if streamed {
+                let _download = self.scheduler.download().await;
+                info!("📡 Streaming the audio into Whisper...");
+                let live_capture = metadata.live.then(|| LiveCapture {
+                    from_start: options.live_from_start,
+                    max_minutes: options.max_live_minutes.unwrap_or(DEFAULT_MAX_LIVE_MINUTES),
+                });
+                if metadata.live && options.chunk_minutes.is_none() {
+                    options.chunk_minutes = Some(whisper::LIVE_CHUNK_MINUTES);
+                }
+                let stream = downloader.stream_audio(
+                    &options.url,
+                    &options.clip,
+                    &options.network(),
+                    options.audio_filter.as_deref(),
+                    live_capture.as_ref(),
+                )?;
+                let output = self
+                    .whisper
+                    .transcribe_stream(stream, &options, live.clone())
+                    .await;
+                let (output, bytes) = match (output, &live) {
+                    (Err(e), Some(live)) => {
+                        live.set_aside();
+                        return Err(e);
+                    }
+                    (output, _) => output?,
+                };
+                bytes_downloaded = bytes;
+                outputs.push((None, output));
+            }
+            for (track, audio_path) in &sources {
+                if let Some(n) = track {
+                    info!("🎚️  Audio track {}", n);
+                }
+                let checkpoint = checkpoint::path_for(&checkpoint_base, *track);
+                let output = self
+                    .whisper
+                    .transcribe(audio_path, &options, Some(checkpoint), live.clone())
+                    .await;
+                let output = match (output, &live) {
+                    (Err(e), Some(live)) => {
+                        // Half a transcript would pass for a finished one.
+                        live.set_aside();
+                        return Err(e);
+                    }
+                    (output, _) => output?,
+                };
+                outputs.push((*track, output));
+            }
+            let mut output = if stereo {
+                merge_track_outputs(&channel_tracks(), outputs)
+            } else {
+                merge_track_outputs(&audio_tracks, outputs)
+            };
+            // Clipped audio starts at 0; move it back to the video's time.
+            let offset = options.clip.start_ms();
+            for segment in &mut output.segments {
+                segment.start_ms += offset;
+                segment.end_ms += offset;
+            }
+            for region in &mut output.non_speech {
+                region.start_ms += offset;
+                region.end_ms += offset;
+            }
+            // Interleaved by time rather than one section per channel.
+            if stereo
+                && let Some(turns) = turns::render(
+                    SpeakerTurns::Speaker,
+                    &output.segments,
+                    &speakers,
+                    false,
+                    |_| None,
+                )
+            {
+                output.transcript = turns;
+            }
+            output
+        };
+        let (mut transcript, mut segments) = (output.transcript, output.segments);
+        if metadata.live {
+            metadata.duration = segments.last().map_or(0, |s| s.end_ms.div_ceil(1000));
+        }
+        let glossary_changes = match &glossary {
+            Some(glossary) => glossary.apply(&mut transcript, &mut segments),
+            None => Vec::new(),
+        };
+        if !glossary_changes.is_empty() {
+            info!("📖 Glossary made {} replacement(s)", glossary_changes.len());
+        }
+        let language = output
+            .detected_language
+            .as_ref()
+            .map(|d| d.code.as_str())
+            .or(options.language.as_deref().filter(|l| *l != "auto"))
+            .or(metadata.language.as_deref());
+        let topics = topics::extract(&segments, language);
+        if options.speaker_turns != SpeakerTurns::Off && turns::turns(&segments).is_none() {
+            warn!(
+                "No speaker information for speaker_turns; transcribe every track of a multi-track file (audio_track 'all') or use a tinydiarize model"
+            );
+        }
 
         // Save output files
-        let files =
-            self.save_outputs(&metadata, &transcript, &options.output_dir, options.model)?;
+        let rendered = Transcript {
+            metadata: &metadata,
+            text: &transcript,
+            segments: &segments,
+            clip: &options.clip,
+            model: &label,
+            detected_language: output.detected_language.as_ref(),
+            md_timestamps: options.md_timestamps,
+            speaker_turns: options.speaker_turns,
+            speakers: &speakers,
+            glossary_changes: &glossary_changes,
+            topics: &topics,
+            offset_ms: 0,
+        };
+        let output_dir = Path::new(&options.output_dir);
+        let files = save_outputs(
+            sink.as_ref(),
+            output_dir,
+            &base,
+            &options.formats,
+            &rendered,
+        )
+        .await?;
+        let chapter_files = if options.split_chapters {
+            let chapters = if metadata.chapters.is_empty() {
+                chapters::generate(&segments, language)
+            } else {
+                metadata.chapters.clone()
+            };
+            if chapters.is_empty() {
+                warn!(
+                    "No chapters to split by: the video lists none and it is too short to generate them"
+                );
+            }
+            let files = save_chapters(
+                sink.as_ref(),
+                output_dir,
+                &base,
+                &options.formats,
+                &rendered,
+                &chapters,
+            )
+            .await?;
+            info!("📑 Wrote {} chapter transcript(s)", files.len());
+            files
+        } else {
+            Vec::new()
+        };
+        let uploaded = match &copies {
+            Some(copies) => {
+                for chapter in &chapter_files {
+                    sink::upload_copies(copies.as_ref(), output_dir, chapter).await?;
+                }
+                let uploaded = sink::upload_copies(copies.as_ref(), output_dir, &files).await?;
+                info!("☁️  Uploaded copies of {} output(s)", uploaded.0.len());
+                uploaded
+            }
+            None => OutputFiles::default(),
+        };
+        if sink.local_files()
+            && let Err(e) = library::record(output_dir, &base, &rendered, &files)
+        {
+            warn!("Could not add the transcript to the library: {:#}", e);
+        }
+        if sink.local_files()
+            && files.get(OutputFormat::Json).is_some()
+            && let Err(e) = store::remember(
+                Path::new(&options.output_dir),
+                cache_key,
+                &base,
+                &metadata.video_id,
+                &label,
+            )
+        {
+            warn!("Could not update the transcript cache index: {:#}", e);
+        }
+        stages.clear();
 
         // Calculate stats
         let word_count = transcript.split_whitespace().count();
-        let transcript_preview = if transcript.len() > 500 {
-            // Walk back from byte 500 to the nearest char boundary. Languages
-            // with multi-byte UTF-8 sequences (Vietnamese, Chinese, Arabic…)
-            // will land mid-character at a raw byte 500 and panic the slice.
-            let mut end = 500;
-            while !transcript.is_char_boundary(end) {
-                end -= 1;
-            }
-            format!("{}...", &transcript[..end])
-        } else {
-            transcript.clone()
-        };
+        let transcript_preview = preview(&transcript);
 
-        info!(
-            "✅ Transcription complete! ({} segments)",
-            segments.len()
-        );
+        info!("✅ Transcription complete! ({} segments)", segments.len());
 
         Ok(TranscriptionResult {
             success: true,
@@ -95,17 +658,227 @@ impl TranscriberEngine {
             segments,
             transcript_preview,
             word_count,
-            model_used: options.model,
+            model_used: model,
+            model_path: options.model_path.clone(),
+            profile,
+            gpu_used: output.gpu_used,
+            audio_tracks,
+            transcribed_tracks,
+            audio_quality: output.audio_quality,
+            non_speech: output.non_speech,
+            detected_language: output.detected_language,
+            captions_language,
+            usage: meter.finish(bytes_downloaded),
+            cached: false,
+            chapter_files,
+            uploaded,
+            container: media.map(|info| info.container),
         })
     }
 
-    async fn process_local_video(&self, path: &str) -> Result<PathBuf> {
-        let video_path = PathBuf::from(path);
-        if !video_path.exists() {
-            anyhow::bail!("Video file not found: {}", path);
+    /// The transcript an earlier call with these options left. The cache
+    /// index answers a repeat of the same request without network access.
+    /// With `IfExists::Skip`, any transcript of the source by the same
+    /// model also counts (any platform captions, for `prefer_captions`);
+    /// finding it needs the source's metadata, so a URL costs a metadata
+    /// fetch but no download.
+    pub async fn find_existing(
+        &self,
+        options: &TranscriptionOptions,
+    ) -> Result<Option<TranscriptionResult>> {
+        let output_dir = Path::new(&options.output_dir);
+        let key = CacheKey::new(options);
+        if let Some((base, stored)) = store::recall(output_dir, &key) {
+            info!("♻️  Cached transcript {}", base.display());
+            let model = stored
+                .model
+                .parse()
+                .unwrap_or_else(|_| options.model.unwrap_or_default());
+            return Ok(Some(stored.into_result(
+                &base,
+                model,
+                options.model_path.clone(),
+            )));
+        }
+        if options.if_exists != IfExists::Skip {
+            return Ok(None);
+        }
+
+        let net = options.network();
+        let downloader = VideoDownloader::scoped()?;
+        let mut metadata = if is_local_source(&options.url) {
+            self.get_local_metadata(&options.url)?
+        } else if let Some(media) = downloader.probe_direct_media(&options.url, &net).await {
+            media.metadata(&options.url)
+        } else {
+            downloader.fetch_metadata(&options.url, &net).await?
+        };
+        if let Some(episode) = &options.episode {
+            episode.apply(&mut metadata);
+        }
+
+        let config = Config::load();
+        let mut options = options.clone();
+        if let Some((_, profile)) = config.profile_for(&metadata.platform) {
+            profile.apply(&mut options);
+        }
+        let model = options.model.unwrap_or_default();
+        let label = model_label(model, options.model_path.as_deref());
+        let captions_ok = options.prefer_captions == Some(true);
+        let base = output_base(
+            &options.output_dir,
+            config.output_layout.as_deref(),
+            &metadata,
+            &options.clip,
+        );
+        let Some((base, stored)) = store::find(&base, |m| {
+            m == label || (captions_ok && captions_label_language(m).is_some())
+        }) else {
+            return Ok(None);
+        };
+        info!("♻️  Reusing existing transcript {}", base.display());
+        if let Err(e) = store::remember(output_dir, key, &base, &metadata.video_id, &stored.model) {
+            warn!("Could not update the transcript cache index: {:#}", e);
+        }
+        Ok(Some(stored.into_result(&base, model, options.model_path)))
+    }
+
+    /// Platform captions for `options.language`, or the language the
+    /// platform reports. Any failure falls back to whisper, so it only logs.
+    async fn fetch_captions(
+        &self,
+        downloader: &VideoDownloader,
+        options: &TranscriptionOptions,
+        metadata: &VideoMetadata,
+    ) -> Option<(String, Vec<Segment>)> {
+        let Some(language) = options
+            .language
+            .as_deref()
+            .filter(|l| *l != "auto")
+            .or(metadata.language.as_deref())
+        else {
+            info!("Video language unknown; skipping captions and using Whisper");
+            return None;
+        };
+        info!("💬 Looking for '{}' captions...", language);
+        match downloader
+            .fetch_captions(&options.url, language, &options.network())
+            .await
+        {
+            Ok(Some(segments)) => Some((language.to_string(), segments)),
+            Ok(None) => {
+                info!("No '{}' captions available; using Whisper", language);
+                None
+            }
+            Err(e) => {
+                warn!("Could not fetch captions, using Whisper: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Source duration in seconds without downloading it: ffprobe for local
+    /// files, yt-dlp metadata for URLs. Used to schedule batches.
+    pub async fn probe_duration(&self, url: &str) -> Result<u64> {
+        if is_local_source(url) {
+            AudioProcessor::scoped()?
+                .probe_duration(Path::new(url))
+                .await
+        } else {
+            Ok(VideoDownloader::scoped()?
+                .fetch_metadata(url, &Network::configured())
+                .await?
+                .duration)
         }
+    }
+
+    /// Fetch the current metadata for up to `limit` saved transcripts that
+    /// `pick` selects, and write what changed into their outputs and the
+    /// library (see [`refresh`]). Only transcripts of video pages are
+    /// refreshed: local files and direct media links have nothing to fetch.
+    pub async fn refresh_metadata(
+        &self,
+        output_dir: &Path,
+        pick: impl Fn(&Entry) -> bool,
+        limit: usize,
+    ) -> Result<Vec<Refreshed>> {
+        let entries: Vec<Entry> = Library::open(output_dir)?
+            .list(None)?
+            .into_iter()
+            .filter(|entry| !is_local_source(&entry.url) && pick(entry))
+            .take(limit)
+            .collect();
+        let downloader = VideoDownloader::scoped()?;
+        let net = Network::configured();
+        let mut refreshed = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let base = output_dir.join(&entry.base);
+            let result = async {
+                if downloader
+                    .probe_direct_media(&entry.url, &net)
+                    .await
+                    .is_some()
+                {
+                    anyhow::bail!("a direct media link has no metadata to refresh");
+                }
+                let saved = store::load(&base)?.metadata;
+                let fresh = downloader.fetch_metadata(&entry.url, &net).await?;
+                let changes = refresh::changes(&saved, &fresh);
+                let metadata = refresh::updated(&saved, &changes);
+                if !changes.is_empty() {
+                    refresh::rewrite(&base, &metadata)?;
+                    library::update_metadata(output_dir, &base, &metadata)?;
+                    info!(
+                        "🔄 Refreshed {} ({} field(s))",
+                        entry.video_id,
+                        changes.len()
+                    );
+                }
+                Ok((metadata.title, changes))
+            }
+            .await;
+            refreshed.push(match result {
+                Ok((title, changes)) => Refreshed {
+                    base: entry.base,
+                    video_id: entry.video_id,
+                    title,
+                    changes,
+                    error: None,
+                },
+                Err(e) => {
+                    warn!("Could not refresh {}: {:#}", entry.video_id, e);
+                    Refreshed {
+                        base: entry.base,
+                        video_id: entry.video_id,
+                        title: entry.title,
+                        changes: Vec::new(),
+                        error: Some(format!("{:#}", e)),
+                    }
+                }
+            });
+        }
+        Ok(refreshed)
+    }
 
-        self.audio_processor.extract_audio(&video_path).await
+    /// Fetch a podcast feed's episode list, through `proxy` if given (else
+    /// the configured one).
+    pub async fn fetch_feed(&self, url: &str, proxy: Option<&str>) -> Result<Feed> {
+        let net = Network {
+            proxy: proxy_url(proxy),
+            ..Network::configured()
+        };
+        VideoDownloader::scoped()?.fetch_feed(url, &net).await
+    }
+
+    /// Memory the local model for `options` takes, for the scheduler;
+    /// `None` with a remote worker.
+    fn model_memory_mb(&self, options: &TranscriptionOptions) -> Option<u32> {
+        if whisper::is_remote() {
+            return None;
+        }
+        let bytes = self.whisper.model_file_size(options)?;
+        let model = options.model.unwrap_or_default().to_string();
+        Some(self.scheduler.memory_mb(&model, bytes))
     }
 
     fn get_local_metadata(&self, path: &str) -> Result<VideoMetadata> {
@@ -120,75 +893,37 @@ impl TranscriberEngine {
             video_id: filename.clone(),
             title: filename,
             channel: "Local File".to_string(),
-            duration: 0, // We could get this from ffprobe
+            duration: 0, // Filled in from ffprobe when transcribing
             upload_date: String::new(),
             platform: "Local File".to_string(),
             url: path.to_string_lossy().to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
         })
     }
 
-    fn save_outputs(
-        &self,
-        metadata: &VideoMetadata,
-        transcript: &str,
-        output_dir: &str,
-        model: WhisperModel,
-    ) -> Result<OutputFiles> {
-        let safe_filename = sanitize_filename(&format!("{}-{}", metadata.video_id, metadata.title));
-
-        let txt_path = Path::new(output_dir).join(format!("{}.txt", safe_filename));
-        let json_path = Path::new(output_dir).join(format!("{}.json", safe_filename));
-        let md_path = Path::new(output_dir).join(format!("{}.md", safe_filename));
-
-        // Save TXT
-        std::fs::write(&txt_path, transcript)?;
-
-        // Save JSON
-        let json_output = serde_json::json!({
-            "metadata": metadata,
-            "transcript": transcript,
-            "model": model.as_str(),
-        });
-        std::fs::write(&json_path, serde_json::to_string_pretty(&json_output)?)?;
-
-        // Save Markdown
-        let md_content = format!(
-            "# {}\n\n\
-            **Video:** {}\n\
-            **Platform:** {}\n\
-            **Channel:** {}\n\
-            **Video ID:** {}\n\
-            **Duration:** {}s\n\
-            **Published:** {}\n\n\
-            ---\n\n\
-            ## Transcript\n\n\
-            {}\n\n\
-            ---\n\n\
-            *Transcribed using whisper.cpp (Rust) - Model: {}*\n",
-            metadata.title,
-            metadata.url,
-            metadata.platform,
-            metadata.channel,
-            metadata.video_id,
-            metadata.duration,
-            metadata.upload_date,
-            transcript,
-            model.as_str()
-        );
-        std::fs::write(&md_path, md_content)?;
-
-        Ok(OutputFiles {
-            txt: txt_path.to_string_lossy().to_string(),
-            json: json_path.to_string_lossy().to_string(),
-            md: md_path.to_string_lossy().to_string(),
-        })
+    /// Whether a local file already has a transcript in `output_dir`.
+    pub fn is_transcribed(&self, path: &str, output_dir: &str) -> bool {
+        let layout = Config::load().output_layout;
+        self.get_local_metadata(path)
+            .map(|metadata| {
+                store::exists(&output_base(
+                    output_dir,
+                    layout.as_deref(),
+                    &metadata,
+                    &ClipRange::default(),
+                ))
+            })
+            .unwrap_or(false)
     }
 
     pub fn check_dependencies(&self) -> Result<String> {
         let mut status = String::new();
 
         // Check yt-dlp
-        match std::process::Command::new("yt-dlp")
+        match std::process::Command::new(managed::program("yt-dlp"))
             .arg("--version")
             .output()
         {
@@ -205,6 +940,13 @@ impl TranscriberEngine {
             Err(_) => status.push_str("❌ ffmpeg: NOT installed\n"),
         }
 
+        // Check the subprocess sandbox, if configured
+        match process::sandbox_status() {
+            Some(Ok(program)) => status.push_str(&format!("✅ sandbox: {}\n", program)),
+            Some(Err(e)) => status.push_str(&format!("❌ sandbox: {:#}\n", e)),
+            None => {}
+        }
+
         // Check whisper models
         status.push_str(&self.whisper.check_models_status());
 
@@ -212,6 +954,281 @@ impl TranscriberEngine {
     }
 }
 
+/// Send the requested formats (the default set if none were asked for)
+/// to `sink`, named after `base` in `output_dir`.
+async fn save_outputs(
+    sink: &dyn TranscriptSink,
+    output_dir: &Path,
+    base: &Path,
+    formats: &[OutputFormat],
+    rendered: &Transcript<'_>,
+) -> Result<OutputFiles> {
+    let formats = if formats.is_empty() {
+        &OutputFormat::DEFAULT[..]
+    } else {
+        formats
+    };
+    let mut files = OutputFiles::default();
+    for &format in formats {
+        let path = store::file(base, format);
+        let key = sink::key(output_dir, &path);
+        let content = outputs::render(format, rendered)?;
+        let location = sink
+            .put(Output {
+                path: &path,
+                key: &key,
+                format,
+                content: content.as_bytes(),
+            })
+            .await
+            .with_context(|| format!("Failed to save the {} output", format))?;
+        files.0.insert(format, location);
+    }
+    Ok(files)
+}
+
+/// One set of outputs per chapter, in `<base>-chapters/` and named
+/// `01-<chapter title>`, …, timed from the chapter's start. Chapters no
+/// segment starts in are skipped.
+async fn save_chapters(
+    sink: &dyn TranscriptSink,
+    output_dir: &Path,
+    base: &Path,
+    formats: &[OutputFormat],
+    rendered: &Transcript<'_>,
+    chapters: &[Chapter],
+) -> Result<Vec<OutputFiles>> {
+    let mut dir = base.as_os_str().to_owned();
+    dir.push("-chapters");
+    let dir = PathBuf::from(dir);
+    let mut files = Vec::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let segments = chapters::segments_in(rendered.segments, chapter);
+        if segments.is_empty() {
+            continue;
+        }
+        let text: Vec<&str> = segments
+            .iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let text = text.join(" ");
+        let metadata = VideoMetadata {
+            title: format!("{} – {}", rendered.metadata.title, chapter.title),
+            duration: (chapter.end_ms - chapter.start_ms) / 1000,
+            chapters: Vec::new(),
+            live: false,
+            ..rendered.metadata.clone()
+        };
+        // Says where in the source the chapter is.
+        let clip = ClipRange::new(
+            Some(chapter.start_ms as f64 / 1000.0),
+            Some(chapter.end_ms as f64 / 1000.0),
+        )
+        .unwrap_or_default();
+        let name = format!("{:02}-{}", i + 1, chapter.title);
+        let chapter_base = dir.join(sanitize_filename(&name));
+        let transcript = Transcript {
+            metadata: &metadata,
+            text: &text,
+            segments: &segments,
+            clip: &clip,
+            glossary_changes: &[],
+            topics: &[],
+            offset_ms: chapter.start_ms,
+            ..*rendered
+        };
+        files.push(save_outputs(sink, output_dir, &chapter_base, formats, &transcript).await?);
+    }
+    Ok(files)
+}
+
+/// `audio` moved into `stages` for a later run to resume from, or left
+/// where it is when that fails.
+fn keep_stage(stages: &Stages, url: &str, metadata: &VideoMetadata, audio: PathBuf) -> PathBuf {
+    stages.keep(url, metadata, &audio).unwrap_or_else(|e| {
+        warn!("Could not keep the downloaded audio for a resume: {:#}", e);
+        audio
+    })
+}
+
+fn is_local_source(url: &str) -> bool {
+    !url.starts_with("http://") && !url.starts_with("https://")
+}
+
+/// Resolve the requested track selection against the tracks ffprobe found.
+/// Returns the track numbers to extract; `None` means "let ffmpeg pick",
+/// used when the file has a single track or couldn't be probed.
+fn select_tracks(
+    tracks: &[AudioTrack],
+    selection: Option<AudioTrackSelection>,
+) -> Result<Vec<Option<usize>>> {
+    match selection {
+        Some(AudioTrackSelection::Track(n)) => {
+            if !tracks.is_empty() && n > tracks.len() {
+                anyhow::bail!(
+                    "Audio track {} not found: the file has {} audio track(s)",
+                    n,
+                    tracks.len()
+                );
+            }
+            Ok(vec![Some(n)])
+        }
+        Some(AudioTrackSelection::All) if tracks.len() > 1 => {
+            Ok(tracks.iter().map(|t| Some(t.number)).collect())
+        }
+        None if tracks.len() > 1 => {
+            warn!(
+                "File has {} audio tracks; transcribing track 1 only. Pass audio_track to pick another, or 'all'",
+                tracks.len()
+            );
+            Ok(vec![Some(1)])
+        }
+        _ => Ok(vec![None]),
+    }
+}
+
+/// The channels of a stereo call as tracks, titled with their speakers.
+fn channel_tracks() -> Vec<AudioTrack> {
+    STEREO_SPEAKERS
+        .iter()
+        .enumerate()
+        .map(|(i, name)| AudioTrack {
+            number: i + 1,
+            title: Some(name.to_string()),
+            language: None,
+            channels: Some(1),
+            codec: None,
+            sample_rate: None,
+        })
+        .collect()
+}
+
+/// Combine per-track whisper runs into one output. A single run passes
+/// through untouched (its segments tagged with the track when the file had
+/// several); multiple runs get a labelled section per track in the
+/// transcript and their segments interleaved by start time.
+fn merge_track_outputs(
+    tracks: &[AudioTrack],
+    outputs: Vec<(Option<usize>, WhisperOutput)>,
+) -> WhisperOutput {
+    let multi_track = tracks.len() > 1;
+    let label = |n: usize| {
+        tracks
+            .iter()
+            .find(|t| t.number == n)
+            .map(AudioTrack::label)
+            .unwrap_or_else(|| format!("Track {}", n))
+    };
+
+    let gpu_used = outputs.last().and_then(|(_, o)| o.gpu_used);
+    let audio_quality = outputs
+        .iter()
+        .filter_map(|(_, o)| o.audio_quality.clone())
+        .min_by_key(|q| q.score);
+    let mut non_speech = Vec::new();
+    let detected_language = outputs
+        .iter()
+        .find_map(|(_, o)| o.detected_language.clone());
+    let labelled = outputs.len() > 1;
+    let mut sections = Vec::with_capacity(outputs.len());
+    let mut segments = Vec::new();
+    for (track, output) in outputs {
+        non_speech.extend(output.non_speech);
+        match track {
+            Some(n) if labelled => sections.push(format!("[{}]\n{}", label(n), output.transcript)),
+            _ => sections.push(output.transcript),
+        }
+        segments.extend(output.segments.into_iter().map(|mut seg| {
+            if multi_track {
+                seg.track = track;
+            }
+            seg
+        }));
+    }
+    if labelled {
+        segments.sort_by_key(|seg| seg.start_ms);
+    }
+
+    WhisperOutput {
+        transcript: sections.join("\n\n"),
+        segments,
+        gpu_used,
+        audio_quality,
+        non_speech,
+        detected_language,
+    }
+}
+
+/// `<output_dir>/<video_id>-<title>`, the output files' path without an
+/// extension (see [`store::file`]), with a `.clip-<start>-<end>` suffix for
+/// clips so they don't overwrite the full transcript. With an
+/// `output_layout`, the files go in the subdirectory it names.
+fn output_base(
+    output_dir: &str,
+    layout: Option<&str>,
+    metadata: &VideoMetadata,
+    clip: &ClipRange,
+) -> PathBuf {
+    let safe_filename = sanitize_filename(&format!("{}-{}", metadata.video_id, metadata.title));
+    let clip_suffix = if clip.is_set() {
+        let end = clip
+            .end_time
+            .map(|e| format!("{}", e.round() as u64))
+            .unwrap_or_else(|| "end".to_string());
+        format!(".clip-{}-{}", clip.start().round() as u64, end)
+    } else {
+        String::new()
+    };
+    let dir = match layout {
+        Some(layout) => Path::new(output_dir).join(layout_dir(layout, metadata)),
+        None => PathBuf::from(output_dir),
+    };
+    dir.join(format!("{}{}", safe_filename, clip_suffix))
+}
+
+/// The first 500 bytes or so of a transcript, for tool responses.
+pub(super) fn preview(transcript: &str) -> String {
+    if transcript.len() > 500 {
+        // Walk back from byte 500 to the nearest char boundary. Languages
+        // with multi-byte UTF-8 sequences (Vietnamese, Chinese, Arabic…)
+        // will land mid-character at a raw byte 500 and panic the slice.
+        let mut end = 500;
+        while !transcript.is_char_boundary(end) {
+            end -= 1;
+        }
+        format!("{}...", &transcript[..end])
+    } else {
+        transcript.to_string()
+    }
+}
+
+/// Fill in an `output_layout` template. Values are sanitized so they can't
+/// add path components, and `.`/`..` components are dropped so the result
+/// stays inside the output directory.
+fn layout_dir(layout: &str, metadata: &VideoMetadata) -> PathBuf {
+    let date = metadata.upload_date.as_str();
+    let dated = date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit());
+    let value = |v: &str| {
+        let v = sanitize_filename(v.trim());
+        if v.is_empty() {
+            "unknown".to_string()
+        } else {
+            v
+        }
+    };
+    let filled = layout
+        .replace("{platform}", &value(&metadata.platform))
+        .replace("{channel}", &value(&metadata.channel))
+        .replace("{video_id}", &value(&metadata.video_id))
+        .replace("{year}", if dated { &date[..4] } else { "undated" })
+        .replace("{month}", if dated { &date[4..6] } else { "undated" });
+    filled
+        .split(['/', '\\'])
+        .filter(|c| !matches!(c.trim(), "" | "." | ".."))
+        .collect()
+}
+
 fn sanitize_filename(name: &str) -> String {
     name.chars()
         .map(|c| match c {
@@ -223,3 +1240,113 @@ fn sanitize_filename(name: &str) -> String {
         .take(150)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcriber::types::Segment;
+
+    fn tracks(n: usize) -> Vec<AudioTrack> {
+        (1..=n)
+            .map(|number| AudioTrack {
+                number,
+                title: None,
+                language: None,
+                channels: None,
+                codec: None,
+                sample_rate: None,
+            })
+            .collect()
+    }
+
+    fn output(text: &str, start_ms: u64) -> WhisperOutput {
+        WhisperOutput {
+            transcript: text.to_string(),
+            segments: vec![Segment {
+                start_ms,
+                end_ms: start_ms + 500,
+                text: text.to_string(),
+                ..Default::default()
+            }],
+            gpu_used: Some(false),
+            audio_quality: None,
+            non_speech: Vec::new(),
+            detected_language: None,
+        }
+    }
+
+    #[test]
+    fn selects_first_track_of_multi_track_file_by_default() {
+        assert_eq!(select_tracks(&tracks(1), None).unwrap(), vec![None]);
+        assert_eq!(select_tracks(&tracks(2), None).unwrap(), vec![Some(1)]);
+        assert_eq!(
+            select_tracks(&tracks(2), Some(AudioTrackSelection::All)).unwrap(),
+            vec![Some(1), Some(2)]
+        );
+        assert!(select_tracks(&tracks(2), Some(AudioTrackSelection::Track(3))).is_err());
+    }
+
+    #[test]
+    fn merges_tracks_with_labels() {
+        let merged = merge_track_outputs(
+            &tracks(2),
+            vec![
+                (Some(1), output("mic", 1_000)),
+                (Some(2), output("desktop", 0)),
+            ],
+        );
+        assert_eq!(merged.transcript, "[Track 1]\nmic\n\n[Track 2]\ndesktop");
+        assert_eq!(merged.segments[0].track, Some(2));
+        assert_eq!(merged.segments[1].track, Some(1));
+
+        // One stereo file: the channels are merged like tracks.
+        let call = merge_track_outputs(
+            &channel_tracks(),
+            vec![
+                (Some(1), output("hello", 0)),
+                (Some(2), output("how can I help", 600)),
+            ],
+        );
+        assert_eq!(
+            call.transcript,
+            "[Track 1 (Caller)]\nhello\n\n[Track 2 (Agent)]\nhow can I help"
+        );
+        assert_eq!(call.segments[1].track, Some(2));
+    }
+    #[test]
+    fn output_layout_places_files_in_subdirectories() {
+        let mut metadata = VideoMetadata {
+            video_id: "abc123".to_string(),
+            title: "My Talk".to_string(),
+            channel: "AC/DC".to_string(),
+            duration: 90,
+            upload_date: "20240501".to_string(),
+            platform: "YouTube".to_string(),
+            url: "https://youtu.be/abc123".to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        };
+        let clip = ClipRange::default();
+        assert_eq!(
+            output_base("/out", None, &metadata, &clip),
+            Path::new("/out/abc123-My Talk")
+        );
+        assert_eq!(
+            output_base(
+                "/out",
+                Some("{platform}/{year}/{video_id}"),
+                &metadata,
+                &clip
+            ),
+            Path::new("/out/YouTube/2024/abc123/abc123-My Talk")
+        );
+        assert_eq!(
+            layout_dir("../{channel}/{year}-{month}/", &metadata),
+            Path::new("AC-DC/2024-05")
+        );
+        metadata.upload_date = String::new();
+        assert_eq!(layout_dir("{year}", &metadata), Path::new("undated"));
+    }
+}
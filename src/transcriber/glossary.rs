@@ -0,0 +1,173 @@
+//! Glossary substitution: fixing names and terms Whisper keeps getting
+//! wrong ("cube ernetes" → "Kubernetes") in every output.
+//!
+//! Entries come from `glossary` in the config file and per request (which
+//! wins). Matching ignores case, only takes whole words, and lets any run
+//! of whitespace stand for a space. Each replacement is logged with the
+//! time of its segment, and the log is saved in the JSON output.
+
+use anyhow::{Context, Result};
+use regex_automata::meta::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::types::Segment;
+
+/// One replacement made in a segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    /// The text as Whisper wrote it.
+    pub from: String,
+    pub to: String,
+    pub start_ms: u64,
+}
+
+pub struct Glossary {
+    regex: Regex,
+    /// Replacement for each pattern, by pattern id.
+    replacements: Vec<String>,
+}
+
+impl Glossary {
+    /// Build from misrecognition → canonical spelling pairs. `None` when
+    /// there is nothing to replace.
+    pub fn new(entries: &BTreeMap<String, String>) -> Result<Option<Self>> {
+        let mut entries: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|(from, to)| (from.trim(), to.as_str()))
+            .filter(|(from, _)| !from.is_empty())
+            .collect();
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        // Leftmost-first matching: try "new york city" before "new york".
+        entries.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+        let patterns: Vec<String> = entries
+            .iter()
+            .map(|(from, _)| {
+                let words: Vec<String> = from.split_whitespace().map(escape).collect();
+                // Word boundaries only where the entry starts or ends with a
+                // word character, so "C++" still matches before a space.
+                let edge = |c: Option<char>| match c {
+                    Some(c) if c.is_alphanumeric() => r"\b",
+                    _ => "",
+                };
+                format!(
+                    "(?i){}{}{}",
+                    edge(from.chars().next()),
+                    words.join(r"\s+"),
+                    edge(from.chars().last())
+                )
+            })
+            .collect();
+        let regex = Regex::new_many(&patterns).context("Invalid glossary entry")?;
+        Ok(Some(Self {
+            regex,
+            replacements: entries.iter().map(|(_, to)| to.to_string()).collect(),
+        }))
+    }
+
+    /// Replace glossary terms in `segments` and `transcript`. Returns what
+    /// was changed in the segments, in order.
+    pub fn apply(&self, transcript: &mut String, segments: &mut [Segment]) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for segment in segments.iter_mut() {
+            let start_ms = segment.start_ms;
+            segment.text = self.replace(&segment.text, |from, to| {
+                changes.push(Change {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    start_ms,
+                });
+            });
+        }
+        *transcript = self.replace(transcript, |_, _| {});
+        changes
+    }
+
+    fn replace(&self, text: &str, mut on_change: impl FnMut(&str, &str)) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for m in self.regex.find_iter(text) {
+            let from = &text[m.range()];
+            let to = &self.replacements[m.pattern().as_usize()];
+            out.push_str(&text[last..m.start()]);
+            out.push_str(to);
+            if from != to {
+                on_change(from, to);
+            }
+            last = m.end();
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+}
+
+/// `s` with regex metacharacters escaped.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if r"\.+*?()|[]{}^$#&-~".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms: start_ms + 2_000,
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn replaces_whole_words_and_logs_changes() {
+        let entries = BTreeMap::from([
+            ("cube ernetes".to_string(), "Kubernetes".to_string()),
+            ("post gress".to_string(), "Postgres".to_string()),
+            ("c plus plus".to_string(), "C++".to_string()),
+            ("k-8-s".to_string(), "K8s".to_string()),
+            ("rust".to_string(), "Rust".to_string()),
+        ]);
+        let glossary = Glossary::new(&entries).unwrap().unwrap();
+        let mut segments = [
+            segment(0, "We run Cube  Ernetes on post gress."),
+            segment(2_000, "Trusty rust, and c plus plus on k-8-s."),
+        ];
+        let mut transcript =
+            "We run Cube  Ernetes on post gress. Trusty rust, and c plus plus on k-8-s."
+                .to_string();
+
+        let changes = glossary.apply(&mut transcript, &mut segments);
+        assert_eq!(segments[0].text, "We run Kubernetes on Postgres.");
+        assert_eq!(segments[1].text, "Trusty Rust, and C++ on K8s.");
+        assert_eq!(
+            transcript,
+            "We run Kubernetes on Postgres. Trusty Rust, and C++ on K8s."
+        );
+        let logged: Vec<_> = changes
+            .iter()
+            .map(|c| (c.from.as_str(), c.start_ms))
+            .collect();
+        assert_eq!(
+            logged,
+            [
+                ("Cube  Ernetes", 0),
+                ("post gress", 0),
+                ("rust", 2_000),
+                ("c plus plus", 2_000),
+                ("k-8-s", 2_000)
+            ]
+        );
+
+        assert!(Glossary::new(&BTreeMap::new()).unwrap().is_none());
+    }
+}
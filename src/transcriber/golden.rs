@@ -0,0 +1,122 @@
+//! Golden-file regression check for the output formats.
+//!
+//! `tests/golden/fixture.json` holds one canonical transcription (in the
+//! shape of the JSON output), chosen to reach the emitters' edge cases:
+//! hour-long timestamps, a blank segment, a multi-line one, a second audio
+//! track, non-ASCII text, a clip and a detected language. [`verify`]
+//! renders every [`OutputFormat`] from it and compares each with the
+//! `expected.<ext>` file beside it, so a formatting change has to show up
+//! as a diff to the golden files. Run it with `--verify-formats`; add
+//! `--bless` after an intended change to rewrite the golden files.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::outputs::{self, Transcript};
+use super::store::StoredTranscript;
+use super::types::{MdTimestamps, OutputFormat, SpeakerTurns};
+
+pub const FIXTURE: &str = "fixture.json";
+
+/// A format whose output no longer matches its golden file.
+#[derive(Debug)]
+pub struct Mismatch {
+    pub format: OutputFormat,
+    /// 1-based line of the first difference.
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: line {} differs\n  expected: {:?}\n  actual:   {:?}",
+            self.format, self.line, self.expected, self.actual
+        )
+    }
+}
+
+/// Render every format from the fixture in `dir` and compare it with the
+/// golden files there. With `bless`, rewrite the golden files instead and
+/// report nothing.
+pub fn verify(dir: &Path, bless: bool) -> Result<Vec<Mismatch>> {
+    let fixture_path = dir.join(FIXTURE);
+    let fixture: StoredTranscript = serde_json::from_str(
+        &std::fs::read_to_string(&fixture_path)
+            .with_context(|| format!("Can't read {}", fixture_path.display()))?,
+    )
+    .with_context(|| format!("{} isn't a valid fixture", fixture_path.display()))?;
+    let transcript = Transcript {
+        metadata: &fixture.metadata,
+        text: &fixture.transcript,
+        segments: &fixture.segments,
+        clip: &fixture.clip,
+        model: &fixture.model,
+        detected_language: fixture.detected_language.as_ref(),
+        md_timestamps: MdTimestamps::Off,
+        speaker_turns: SpeakerTurns::Off,
+        speakers: &[],
+        glossary_changes: &[],
+        topics: &[],
+        offset_ms: 0,
+    };
+
+    let mut mismatches = Vec::new();
+    for format in OutputFormat::ALL {
+        let actual = outputs::render(format, &transcript)?;
+        let golden = dir.join(format!("expected.{}", format));
+        if bless {
+            std::fs::write(&golden, &actual)?;
+            continue;
+        }
+        let expected = std::fs::read_to_string(&golden).with_context(|| {
+            format!(
+                "Can't read {} (run with --bless to create it)",
+                golden.display()
+            )
+        })?;
+        if let Some(mismatch) = first_difference(format, &expected, &actual) {
+            mismatches.push(mismatch);
+        }
+    }
+    Ok(mismatches)
+}
+
+fn first_difference(format: OutputFormat, expected: &str, actual: &str) -> Option<Mismatch> {
+    if expected == actual {
+        return None;
+    }
+    let (mut expected_lines, mut actual_lines) = (expected.split('\n'), actual.split('\n'));
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (e, a) => {
+                return Some(Mismatch {
+                    format,
+                    line,
+                    expected: e.unwrap_or("<end of file>").to_string(),
+                    actual: a.unwrap_or("<end of file>").to_string(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_match_golden_files() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+        let mismatches = verify(&dir, false).unwrap();
+        let report: Vec<String> = mismatches.iter().map(|m| m.to_string()).collect();
+        assert!(report.is_empty(), "{}", report.join("\n"));
+
+        let mismatch = first_difference(OutputFormat::Srt, "1\n2\n3", "1\n2\n").unwrap();
+        assert_eq!((mismatch.line, mismatch.actual.as_str()), (3, ""));
+    }
+}
@@ -0,0 +1,233 @@
+//! Constrained decoding: a regular expression every segment's text must
+//! follow, for audio with a known shape such as spelled-out codes, numeric
+//! readouts or a fixed command vocabulary.
+//!
+//! The pattern is compiled to a DFA and enforced token by token through
+//! whisper.cpp's logits filter: any text token that would take the segment
+//! somewhere the pattern can't match is ruled out before sampling.
+//! Timestamp and end tokens stay open, so a segment can always end.
+//! (whisper-rs's GBNF hook hands whisper.cpp a flat element list where it
+//! expects one pointer per rule, so whisper.cpp's own grammar support
+//! isn't usable from here.)
+
+use anyhow::{Context, Result};
+use regex_automata::{
+    Anchored,
+    dfa::{Automaton, StartKind, dense},
+    util::{primitives::StateID, start},
+};
+use std::ffi::{c_int, c_void};
+use whisper_rs::{
+    FullParams, WhisperContext, WhisperSysContext, WhisperSysState, WhisperTokenData,
+};
+
+/// Compiled DFAs larger than this are refused; patterns that big are
+/// almost always a mistake (e.g. a huge bounded repetition).
+const DFA_SIZE_LIMIT: usize = 16 << 20;
+
+pub struct Grammar {
+    dfa: dense::DFA<Vec<u32>>,
+    start: StateID,
+}
+
+impl Grammar {
+    /// Compile `pattern`. It is matched against each segment's trimmed
+    /// text from the start, e.g. `(?i)(alpha|bravo|charlie)( (alpha|bravo|charlie))*`.
+    pub fn new(pattern: &str) -> Result<Self> {
+        let dfa = dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .start_kind(StartKind::Anchored)
+                    .dfa_size_limit(Some(DFA_SIZE_LIMIT))
+                    .determinize_size_limit(Some(DFA_SIZE_LIMIT)),
+            )
+            .build(pattern)
+            .with_context(|| format!("Invalid grammar pattern: {}", pattern))?;
+        let start = dfa
+            .start_state(&start::Config::new().anchored(Anchored::Yes))
+            .context("Grammar pattern has no anchored start")?;
+        Ok(Self { dfa, start })
+    }
+
+    /// Feed `bytes` to a segment at `at`. Leading whitespace is skipped
+    /// until the segment has text. `None` once the pattern can't match.
+    fn feed(&self, at: Position, bytes: &[u8]) -> Option<Position> {
+        let mut bytes = bytes;
+        if !at.started {
+            bytes = bytes.trim_ascii_start();
+            if bytes.is_empty() {
+                return Some(at);
+            }
+        }
+        let mut state = at.state;
+        for &b in bytes {
+            state = self.dfa.next_state(state, b);
+            if self.dfa.is_dead_state(state) || self.dfa.is_quit_state(state) {
+                return None;
+            }
+        }
+        Some(Position {
+            state,
+            started: true,
+        })
+    }
+
+    fn segment_start(&self) -> Position {
+        Position {
+            state: self.start,
+            started: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Position {
+    state: StateID,
+    started: bool,
+}
+
+/// A grammar bound to one model's vocabulary, ready to install as a
+/// whisper.cpp logits filter.
+pub struct TokenFilter<'a> {
+    grammar: &'a Grammar,
+    /// Bytes of every text token, indexed by token id.
+    tokens: Vec<Vec<u8>>,
+    /// First timestamp token; ids from here on start a new segment.
+    timestamp_begin: i32,
+    n_vocab: usize,
+}
+
+impl<'a> TokenFilter<'a> {
+    pub fn new(grammar: &'a Grammar, ctx: &WhisperContext) -> Self {
+        let eot = ctx.token_eot();
+        let tokens = (0..eot)
+            .map(|id| {
+                ctx.token_to_bytes(id)
+                    .map(<[u8]>::to_vec)
+                    .unwrap_or_default()
+            })
+            .collect();
+        Self {
+            grammar,
+            tokens,
+            timestamp_begin: ctx.token_beg(),
+            n_vocab: ctx.n_vocab().max(0) as usize,
+        }
+    }
+
+    /// Route `params`' logits through this filter.
+    ///
+    /// # Safety
+    /// `self` must outlive every use of `params`.
+    pub unsafe fn install(&self, params: &mut FullParams) {
+        unsafe {
+            params.set_filter_logits_callback(Some(filter_logits));
+            params.set_filter_logits_callback_user_data(self as *const Self as *mut c_void);
+        }
+    }
+
+    /// Where the current segment is after the tokens generated so far.
+    /// `None` if it already broke the pattern.
+    fn position(&self, generated: impl Iterator<Item = i32>) -> Option<Position> {
+        let mut at = self.grammar.segment_start();
+        for id in generated {
+            if id >= self.timestamp_begin {
+                at = self.grammar.segment_start();
+            } else if let Some(bytes) = self.tokens.get(id as usize) {
+                at = self.grammar.feed(at, bytes)?;
+            }
+        }
+        Some(at)
+    }
+
+    /// Rule out the text tokens the pattern can't take next.
+    fn apply(&self, generated: impl Iterator<Item = i32>, logits: &mut [f32]) {
+        let Some(at) = self.position(generated) else {
+            return;
+        };
+        for (id, bytes) in self.tokens.iter().enumerate() {
+            if self.grammar.feed(at, bytes).is_none()
+                && let Some(logit) = logits.get_mut(id)
+            {
+                *logit = f32::NEG_INFINITY;
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn filter_logits(
+    _ctx: *mut WhisperSysContext,
+    _state: *mut WhisperSysState,
+    tokens: *const WhisperTokenData,
+    n_tokens: c_int,
+    logits: *mut f32,
+    user_data: *mut c_void,
+) {
+    if user_data.is_null() || logits.is_null() {
+        return;
+    }
+    // SAFETY: `install` points user_data at a TokenFilter that outlives the
+    // decode, and whisper.cpp passes n_vocab logits and n_tokens tokens.
+    unsafe {
+        let filter = &*(user_data as *const TokenFilter);
+        let generated: &[WhisperTokenData] = if tokens.is_null() || n_tokens <= 0 {
+            &[]
+        } else {
+            std::slice::from_raw_parts(tokens, n_tokens as usize)
+        };
+        let logits = std::slice::from_raw_parts_mut(logits, filter.n_vocab);
+        filter.apply(generated.iter().map(|t| t.id), logits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(grammar: &Grammar, parts: &[&str]) -> Option<Position> {
+        parts.iter().try_fold(grammar.segment_start(), |at, part| {
+            grammar.feed(at, part.as_bytes())
+        })
+    }
+
+    #[test]
+    fn keeps_only_prefixes_of_the_pattern() {
+        let grammar = Grammar::new("(?i)(alpha|bravo)( (alpha|bravo))*").unwrap();
+        assert!(feed_all(&grammar, &[" Alpha", " bra", "vo"]).is_some());
+        assert!(feed_all(&grammar, &[" ", " alpha"]).is_some());
+        assert!(feed_all(&grammar, &[" Alpha", " delta"]).is_none());
+        assert!(feed_all(&grammar, &["Hello"]).is_none());
+
+        let digits = Grammar::new("[0-9]+( [0-9]+)*").unwrap();
+        assert!(feed_all(&digits, &[" 4", "2 7"]).is_some());
+        assert!(feed_all(&digits, &[" four"]).is_none());
+        assert!(Grammar::new("(unclosed").is_err());
+    }
+
+    #[test]
+    fn masks_tokens_and_resets_at_timestamps() {
+        let grammar = Grammar::new("(yes|no)").unwrap();
+        let filter = TokenFilter {
+            grammar: &grammar,
+            tokens: [" yes", " no", " maybe", "s"]
+                .iter()
+                .map(|t| t.as_bytes().to_vec())
+                .collect(),
+            timestamp_begin: 10,
+            n_vocab: 12,
+        };
+        let mut logits = vec![0.0; 12];
+        filter.apply([10].into_iter(), &mut logits);
+        assert_eq!(
+            logits[..4],
+            [0.0, 0.0, f32::NEG_INFINITY, f32::NEG_INFINITY]
+        );
+        assert_eq!(logits[10], 0.0);
+
+        // " yes" then a timestamp: a new segment may start with " no".
+        let mut logits = vec![0.0; 12];
+        filter.apply([0, 11].into_iter(), &mut logits);
+        assert_eq!(logits[1], 0.0);
+        assert_eq!(logits[3], f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,116 @@
+//! Hotwords: names and terms a request expects to hear, such as
+//! "Kubernetes" or "PostgreSQL", listed apart from any glossary.
+//!
+//! They reach local Whisper as its initial prompt, which makes the decoder
+//! more likely to pick those spellings. The glossary then fixes what got
+//! through anyway: a hotword in any case, or split where its capitals are
+//! ("Postgre SQL"), is rewritten to the listed spelling. Entries in the
+//! glossary proper win over these.
+
+use std::collections::BTreeMap;
+
+/// Characters of hotwords put in the prompt. Whisper only keeps the last
+/// 224 prompt tokens; this stays well under them. Hotwords past it still
+/// go to the glossary.
+pub const MAX_PROMPT_CHARS: usize = 600;
+
+/// `hotwords` trimmed, without blanks or repeats (ignoring case; the first
+/// spelling wins).
+pub fn clean(hotwords: &[String]) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for hotword in hotwords {
+        let hotword = hotword.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !hotword.is_empty() && !cleaned.iter().any(|h| h.eq_ignore_ascii_case(&hotword)) {
+            cleaned.push(hotword);
+        }
+    }
+    cleaned
+}
+
+/// Whisper's initial prompt for `hotwords`, or `None` without any.
+pub fn prompt(hotwords: &[String]) -> Option<String> {
+    let mut prompt = String::new();
+    for hotword in hotwords {
+        if prompt.len() + hotword.len() + 2 > MAX_PROMPT_CHARS {
+            break;
+        }
+        if !prompt.is_empty() {
+            prompt.push_str(", ");
+        }
+        prompt.push_str(hotword);
+    }
+    (!prompt.is_empty()).then(|| format!("{}.", prompt))
+}
+
+/// Glossary entries restoring each hotword's spelling.
+pub fn glossary(hotwords: &[String]) -> BTreeMap<String, String> {
+    let mut entries = BTreeMap::new();
+    for hotword in hotwords {
+        let split = hotword
+            .split(' ')
+            .map(split_at_capitals)
+            .collect::<Vec<_>>()
+            .join(" ");
+        if split != *hotword {
+            entries.insert(split, hotword.clone());
+        }
+        entries.insert(hotword.clone(), hotword.clone());
+    }
+    entries
+}
+
+/// `word` with a space before each capital that follows a lowercase
+/// letter: "PostgreSQL" → "Postgre SQL".
+fn split_at_capitals(word: &str) -> String {
+    let mut split = String::with_capacity(word.len() + 2);
+    let mut previous: Option<char> = None;
+    for c in word.chars() {
+        if c.is_uppercase() && previous.is_some_and(char::is_lowercase) {
+            split.push(' ');
+        }
+        split.push(c);
+        previous = Some(c);
+    }
+    split
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcriber::glossary::Glossary;
+    use crate::transcriber::types::Segment;
+
+    #[test]
+    fn prompts_and_restores_hotwords() {
+        let hotwords = clean(&[
+            " Kubernetes".to_string(),
+            "PostgreSQL".to_string(),
+            "".to_string(),
+            "kubernetes".to_string(),
+            "GitHub  Actions".to_string(),
+        ]);
+        assert_eq!(hotwords, ["Kubernetes", "PostgreSQL", "GitHub Actions"]);
+        assert_eq!(
+            prompt(&hotwords).as_deref(),
+            Some("Kubernetes, PostgreSQL, GitHub Actions.")
+        );
+        assert_eq!(prompt(&[]), None);
+        let many = vec!["x".repeat(100); 10];
+        assert!(prompt(&many).unwrap().len() <= MAX_PROMPT_CHARS);
+
+        let glossary = Glossary::new(&glossary(&hotwords)).unwrap().unwrap();
+        let mut segments = [Segment {
+            start_ms: 0,
+            end_ms: 2_000,
+            text: "Deploy kubernetes with github actions and Postgre SQL.".to_string(),
+            ..Default::default()
+        }];
+        let mut transcript = segments[0].text.clone();
+        let changes = glossary.apply(&mut transcript, &mut segments);
+        assert_eq!(
+            transcript,
+            "Deploy Kubernetes with GitHub Actions and PostgreSQL."
+        );
+        assert_eq!(changes.len(), 3);
+    }
+}
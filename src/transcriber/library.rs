@@ -0,0 +1,599 @@
+//! SQLite index of the transcripts in an output directory.
+//!
+//! `<output_dir>/.library.sqlite` keeps one row per transcript (output
+//! base, see [`store::file`]) with its video's metadata, model, language,
+//! word count, output files and save time, so listing transcripts reads
+//! them instead of guessing titles from file names. The engine adds a row
+//! for each transcript it saves. [`Library::open`] also brings the index in
+//! line with the directory: rows whose files are all gone are dropped and
+//! transcripts it doesn't know (written before it existed, or copied in)
+//! are added from their JSON output, so deleting or moving transcripts
+//! needs no index update. Transcripts without a JSON output can only be
+//! added when they are saved.
+//!
+//! Passages embedded for semantic search ([`super::semantic`]) are kept
+//! here too, and dropped with their transcript.
+//!
+//! With encryption at rest the index would leak titles and channels, so it
+//! isn't written; each [`Library::open`] rebuilds it in memory from the
+//! JSON outputs instead.
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags, params};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::outputs::Transcript;
+use super::semantic::Passage;
+use super::store;
+use super::topics::Topic;
+use super::types::{MdTimestamps, OutputFiles, OutputFormat, SpeakerTurns, VideoMetadata};
+use crate::utils::encryption::EncryptionKey;
+use crate::utils::paths::transcript_files;
+
+const LIBRARY_FILE: &str = ".library.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transcripts (
+    base TEXT PRIMARY KEY,
+    video_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    platform TEXT NOT NULL,
+    url TEXT NOT NULL,
+    upload_date TEXT NOT NULL,
+    duration INTEGER NOT NULL,
+    model TEXT NOT NULL,
+    language TEXT,
+    word_count INTEGER NOT NULL,
+    formats TEXT NOT NULL,
+    saved_at INTEGER NOT NULL,
+    topics TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS transcripts_saved_at ON transcripts (saved_at);
+CREATE TABLE IF NOT EXISTS passages (
+    base TEXT NOT NULL,
+    model TEXT NOT NULL,
+    start_ms INTEGER NOT NULL,
+    end_ms INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    vector BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS passages_base ON passages (base, model);
+";
+
+/// One transcript in the library.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Output base path, relative to the output directory.
+    pub base: PathBuf,
+    pub video_id: String,
+    pub title: String,
+    pub channel: String,
+    pub platform: String,
+    pub url: String,
+    pub upload_date: String,
+    /// Seconds, as reported by the platform.
+    pub duration: u64,
+    /// Model label, e.g. `base` or `platform captions (en)`.
+    pub model: String,
+    /// Detected language, else the one the platform reported.
+    pub language: Option<String>,
+    pub word_count: usize,
+    /// Formats written, in [`OutputFormat::ALL`] order.
+    pub formats: Vec<OutputFormat>,
+    /// Unix seconds.
+    pub saved_at: u64,
+    /// Main topics, best first.
+    pub topics: Vec<String>,
+}
+
+impl Entry {
+    fn new(base: PathBuf, t: &Transcript, formats: Vec<OutputFormat>, saved_at: u64) -> Self {
+        Self {
+            base,
+            video_id: t.metadata.video_id.clone(),
+            title: t.metadata.title.clone(),
+            channel: t.metadata.channel.clone(),
+            platform: t.metadata.platform.clone(),
+            url: t.metadata.url.clone(),
+            upload_date: t.metadata.upload_date.clone(),
+            duration: t.metadata.duration,
+            model: t.model.to_string(),
+            language: t
+                .detected_language
+                .map(|d| d.code.clone())
+                .or_else(|| t.metadata.language.clone()),
+            word_count: t.text.split_whitespace().count(),
+            formats,
+            saved_at,
+            topics: terms(t.topics),
+        }
+    }
+
+    /// The video's details as far as the library keeps them, e.g. to link
+    /// to a moment with [`super::links::at`].
+    pub fn metadata(&self) -> VideoMetadata {
+        VideoMetadata {
+            video_id: self.video_id.clone(),
+            title: self.title.clone(),
+            channel: self.channel.clone(),
+            duration: self.duration,
+            upload_date: self.upload_date.clone(),
+            platform: self.platform.clone(),
+            url: self.url.clone(),
+            language: self.language.clone(),
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        }
+    }
+
+    /// Paths of the entry's files in `output_dir`.
+    pub fn files(&self, output_dir: &Path) -> OutputFiles {
+        let base = output_dir.join(&self.base);
+        OutputFiles(
+            self.formats
+                .iter()
+                .map(|&f| (f, store::file(&base, f).to_string_lossy().into_owned()))
+                .collect(),
+        )
+    }
+}
+
+pub struct Library {
+    conn: Connection,
+    output_dir: PathBuf,
+}
+
+impl Library {
+    /// The library of `output_dir`, brought in line with the files there.
+    pub fn open(output_dir: &Path) -> Result<Self> {
+        let library = Self::connect(output_dir)?;
+        library.sync()?;
+        Ok(library)
+    }
+
+    /// The library of `output_dir` as last saved, for reading only. Unlike
+    /// [`Library::open`] this creates nothing and doesn't walk the
+    /// directory: without an index there is no library (`None`), and rows
+    /// may name files deleted since. With encryption at rest the index is
+    /// never on disk, so it is built in memory as `open` does.
+    pub fn open_read_only(output_dir: &Path) -> Result<Option<Self>> {
+        if EncryptionKey::from_env()?.is_some() {
+            return Self::open(output_dir).map(Some);
+        }
+        let path = output_dir.join(LIBRARY_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .context("Failed to open the transcript library")?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        Ok(Some(Self {
+            conn,
+            output_dir: output_dir.to_path_buf(),
+        }))
+    }
+
+    fn connect(output_dir: &Path) -> Result<Self> {
+        let conn = if EncryptionKey::from_env()?.is_some() {
+            Connection::open_in_memory()
+        } else {
+            std::fs::create_dir_all(output_dir).context("Failed to create output directory")?;
+            Connection::open(output_dir.join(LIBRARY_FILE))
+        }
+        .context("Failed to open the transcript library")?;
+        // Another process may be listing or saving at the same time.
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.execute_batch(SCHEMA)
+            .context("Failed to set up the transcript library")?;
+        Ok(Self {
+            conn,
+            output_dir: output_dir.to_path_buf(),
+        })
+    }
+
+    /// Transcripts, newest first.
+    pub fn list(&self, limit: Option<usize>) -> Result<Vec<Entry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT base, video_id, title, channel, platform, url, upload_date, duration,
+                    model, language, word_count, formats, saved_at, topics
+             FROM transcripts ORDER BY saved_at DESC, base LIMIT ?1",
+        )?;
+        let limit = limit.map_or(-1, |n| n as i64);
+        let entries = statement
+            .query_map(params![limit], |row| {
+                Ok(Entry {
+                    base: PathBuf::from(row.get::<_, String>(0)?),
+                    video_id: row.get(1)?,
+                    title: row.get(2)?,
+                    channel: row.get(3)?,
+                    platform: row.get(4)?,
+                    url: row.get(5)?,
+                    upload_date: row.get(6)?,
+                    duration: row.get::<_, i64>(7)? as u64,
+                    model: row.get(8)?,
+                    language: row.get(9)?,
+                    word_count: row.get::<_, i64>(10)? as usize,
+                    formats: parse_formats(&row.get::<_, String>(11)?),
+                    saved_at: row.get::<_, i64>(12)? as u64,
+                    topics: parse_topics(&row.get::<_, String>(13)?),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    fn insert(&self, entry: &Entry) -> Result<()> {
+        // A transcript saved again needs embedding again.
+        self.conn.execute(
+            "DELETE FROM passages WHERE base = ?1",
+            params![entry.base.to_string_lossy()],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO transcripts
+                (base, video_id, title, channel, platform, url, upload_date, duration,
+                 model, language, word_count, formats, saved_at, topics)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                entry.base.to_string_lossy(),
+                entry.video_id,
+                entry.title,
+                entry.channel,
+                entry.platform,
+                entry.url,
+                entry.upload_date,
+                entry.duration as i64,
+                entry.model,
+                entry.language,
+                entry.word_count as i64,
+                format_list(&entry.formats),
+                entry.saved_at as i64,
+                topic_list(&entry.topics),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Entries with a JSON output and no passages embedded with `model`.
+    pub fn unembedded(&self, model: &str) -> Result<Vec<Entry>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT DISTINCT base FROM passages WHERE model = ?1")?;
+        let embedded: HashSet<PathBuf> = statement
+            .query_map(params![model], |row| {
+                Ok(PathBuf::from(row.get::<_, String>(0)?))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(self
+            .list(None)?
+            .into_iter()
+            .filter(|e| e.formats.contains(&OutputFormat::Json) && !embedded.contains(&e.base))
+            .collect())
+    }
+
+    /// Keep `passages` of the transcript at `base` with their `model`
+    /// vectors, replacing any it had for `model`.
+    pub fn add_passages(
+        &mut self,
+        base: &Path,
+        model: &str,
+        passages: &[(Passage, Vec<f32>)],
+    ) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "DELETE FROM passages WHERE base = ?1 AND model = ?2",
+            params![base.to_string_lossy(), model],
+        )?;
+        for (passage, vector) in passages {
+            let bytes: Vec<u8> = vector.iter().flat_map(|x| x.to_le_bytes()).collect();
+            tx.execute(
+                "INSERT INTO passages (base, model, start_ms, end_ms, text, vector)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    base.to_string_lossy(),
+                    model,
+                    passage.start_ms as i64,
+                    passage.end_ms as i64,
+                    passage.text,
+                    bytes,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Every passage embedded with `model`, with its transcript's base.
+    pub fn passages(&self, model: &str) -> Result<Vec<(PathBuf, Passage, Vec<f32>)>> {
+        let mut statement = self.conn.prepare(
+            "SELECT base, start_ms, end_ms, text, vector FROM passages WHERE model = ?1",
+        )?;
+        let passages = statement
+            .query_map(params![model], |row| {
+                let bytes: Vec<u8> = row.get(4)?;
+                Ok((
+                    PathBuf::from(row.get::<_, String>(0)?),
+                    Passage {
+                        start_ms: row.get::<_, i64>(1)? as u64,
+                        end_ms: row.get::<_, i64>(2)? as u64,
+                        text: row.get(3)?,
+                    },
+                    bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(passages)
+    }
+
+    /// Drop rows whose files are gone, refresh which formats remain, and
+    /// add transcripts that have a JSON output but no row.
+    fn sync(&self) -> Result<()> {
+        let mut known = HashSet::new();
+        for entry in self.list(None)? {
+            let formats: Vec<OutputFormat> = store::files(&self.output_dir.join(&entry.base))
+                .0
+                .into_keys()
+                .collect();
+            if formats.is_empty() {
+                for table in ["transcripts", "passages"] {
+                    self.conn.execute(
+                        &format!("DELETE FROM {} WHERE base = ?1", table),
+                        params![entry.base.to_string_lossy()],
+                    )?;
+                }
+                continue;
+            }
+            if formats != entry.formats {
+                self.conn.execute(
+                    "UPDATE transcripts SET formats = ?1 WHERE base = ?2",
+                    params![format_list(&formats), entry.base.to_string_lossy()],
+                )?;
+            }
+            known.insert(entry.base);
+        }
+
+        for path in transcript_files(&self.output_dir) {
+            if path.extension().and_then(|e| e.to_str()) != Some(OutputFormat::Json.as_str()) {
+                continue;
+            }
+            let base = path.with_extension("");
+            let relative = base.strip_prefix(&self.output_dir).unwrap_or(&base);
+            if known.contains(relative) {
+                continue;
+            }
+            // Other JSON files (such as chunk checkpoints) aren't transcripts.
+            let Ok(stored) = store::load(&base) else {
+                continue;
+            };
+            let topics = stored.topics_or_extract();
+            let transcript = Transcript {
+                metadata: &stored.metadata,
+                text: &stored.transcript,
+                segments: &stored.segments,
+                clip: &stored.clip,
+                model: &stored.model,
+                detected_language: stored.detected_language.as_ref(),
+                md_timestamps: MdTimestamps::Off,
+                speaker_turns: SpeakerTurns::Off,
+                speakers: &stored.speakers,
+                glossary_changes: &stored.glossary_changes,
+                topics: &topics,
+                offset_ms: 0,
+            };
+            let formats = store::files(&base).0.into_keys().collect();
+            self.insert(&Entry::new(
+                relative.to_path_buf(),
+                &transcript,
+                formats,
+                modified_secs(&path),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+/// Add the transcript just saved at `base` in `output_dir` as `files`,
+/// replacing what the library had there.
+pub fn record(output_dir: &Path, base: &Path, t: &Transcript, files: &OutputFiles) -> Result<()> {
+    if EncryptionKey::from_env()?.is_some() {
+        return Ok(());
+    }
+    let relative = base.strip_prefix(output_dir).unwrap_or(base).to_path_buf();
+    let saved_at = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let formats = files.0.keys().copied().collect();
+    Library::connect(output_dir)?.insert(&Entry::new(relative, t, formats, saved_at))
+}
+
+/// Replace the video details of the transcript at `base` in `output_dir`
+/// with `metadata`'s.
+pub fn update_metadata(output_dir: &Path, base: &Path, metadata: &VideoMetadata) -> Result<()> {
+    if EncryptionKey::from_env()?.is_some() {
+        return Ok(());
+    }
+    let relative = base.strip_prefix(output_dir).unwrap_or(base);
+    Library::connect(output_dir)?.conn.execute(
+        "UPDATE transcripts SET title = ?1, channel = ?2, upload_date = ?3, duration = ?4
+         WHERE base = ?5",
+        params![
+            metadata.title,
+            metadata.channel,
+            metadata.upload_date,
+            metadata.duration as i64,
+            relative.to_string_lossy(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn format_list(formats: &[OutputFormat]) -> String {
+    formats
+        .iter()
+        .map(|f| f.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_formats(list: &str) -> Vec<OutputFormat> {
+    list.split(',').filter_map(|f| f.parse().ok()).collect()
+}
+
+fn terms(topics: &[Topic]) -> Vec<String> {
+    topics.iter().map(|t| t.term.clone()).collect()
+}
+
+/// Topic terms never contain punctuation, so a comma separates them.
+fn topic_list(topics: &[String]) -> String {
+    topics.join(",")
+}
+
+fn parse_topics(list: &str) -> Vec<String> {
+    list.split(',')
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn modified_secs(path: &Path) -> u64 {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcriber::types::ClipRange;
+
+    fn metadata(video_id: &str, title: &str) -> VideoMetadata {
+        VideoMetadata {
+            video_id: video_id.to_string(),
+            title: title.to_string(),
+            channel: "Some Channel".to_string(),
+            duration: 60,
+            upload_date: "20260101".to_string(),
+            platform: "YouTube".to_string(),
+            url: format!("https://youtu.be/{}", video_id),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        }
+    }
+
+    #[test]
+    fn lists_saved_and_imported_transcripts_and_forgets_deleted_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path();
+
+        // Written before the library existed: only its files are there.
+        let old = metadata("abc-123", "Old - Talk");
+        let old_base = output_dir.join("abc-123-Old - Talk");
+        let json = serde_json::json!({
+            "metadata": old,
+            "transcript": "one two three",
+            "segments": [],
+            "model": "base",
+        });
+        std::fs::write(store::file(&old_base, OutputFormat::Json), json.to_string()).unwrap();
+        std::fs::write(store::file(&old_base, OutputFormat::Txt), "one two three").unwrap();
+        let an_hour_ago = SystemTime::now() - std::time::Duration::from_secs(3600);
+        std::fs::File::options()
+            .write(true)
+            .open(store::file(&old_base, OutputFormat::Json))
+            .unwrap()
+            .set_modified(an_hour_ago)
+            .unwrap();
+
+        // Saved now, without a JSON output.
+        let new = metadata("xyz", "New");
+        let new_base = output_dir.join("YouTube").join("xyz-New");
+        std::fs::create_dir_all(new_base.parent().unwrap()).unwrap();
+        let path = store::file(&new_base, OutputFormat::Srt);
+        std::fs::write(&path, "").unwrap();
+        let files = OutputFiles([(OutputFormat::Srt, path.to_string_lossy().into_owned())].into());
+        let transcript = Transcript {
+            metadata: &new,
+            text: "four five",
+            segments: &[],
+            clip: &ClipRange::default(),
+            model: "small",
+            detected_language: None,
+            md_timestamps: MdTimestamps::Off,
+            speaker_turns: SpeakerTurns::Off,
+            speakers: &[],
+            glossary_changes: &[],
+            topics: &[],
+            offset_ms: 0,
+        };
+        record(output_dir, &new_base, &transcript, &files).unwrap();
+
+        let entries = Library::open(output_dir).unwrap().list(None).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].video_id, "xyz");
+        assert_eq!(entries[0].base, Path::new("YouTube/xyz-New"));
+        assert_eq!(entries[0].files(output_dir).primary(), path.to_string_lossy());
+        assert_eq!(entries[1].video_id, "abc-123");
+        assert_eq!(entries[1].title, "Old - Talk");
+        assert_eq!(entries[1].model, "base");
+        assert_eq!(entries[1].word_count, 3);
+        assert_eq!(entries[1].formats, [OutputFormat::Txt, OutputFormat::Json]);
+
+        std::fs::remove_file(store::file(&old_base, OutputFormat::Txt)).unwrap();
+        std::fs::remove_file(path).unwrap();
+        let entries = Library::open(output_dir).unwrap().list(Some(5)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].formats, [OutputFormat::Json]);
+    }
+
+    #[test]
+    fn indexes_topics_and_keeps_passages() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path();
+        let base = output_dir.join("abc-Talk");
+        let segment = |start_ms: u64, text: &str| {
+            serde_json::json!({
+                "start_ms": start_ms,
+                "end_ms": start_ms + 1_000,
+                "text": text,
+            })
+        };
+        let json = serde_json::json!({
+            "metadata": metadata("abc", "Talk"),
+            "transcript": "",
+            "segments": [segment(0, "The borrow checker."), segment(1_000, "A borrow checker!")],
+            "model": "base",
+        });
+        std::fs::write(store::file(&base, OutputFormat::Json), json.to_string()).unwrap();
+
+        let entries = Library::open(output_dir).unwrap().list(None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].topics, ["borrow checker"]);
+
+        let mut library = Library::open(output_dir).unwrap();
+        assert_eq!(library.unembedded("m").unwrap().len(), 1);
+        let passage = Passage {
+            start_ms: 0,
+            end_ms: 2_000,
+            text: "The borrow checker.".to_string(),
+        };
+        library
+            .add_passages(&entries[0].base, "m", &[(passage.clone(), vec![0.5, -1.0])])
+            .unwrap();
+        assert!(library.unembedded("m").unwrap().is_empty());
+        assert_eq!(library.unembedded("other").unwrap().len(), 1);
+        assert_eq!(
+            library.passages("m").unwrap(),
+            [(PathBuf::from("abc-Talk"), passage, vec![0.5, -1.0])]
+        );
+    }
+}
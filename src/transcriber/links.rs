@@ -0,0 +1,77 @@
+//! Links to a moment in the source video, for platforms whose players
+//! take a start time in the URL: YouTube (`&t=83s`), Vimeo (`#t=83s`) and
+//! Twitch VODs (`?t=1h2m3s`). Other sources get no links.
+
+use super::types::VideoMetadata;
+
+/// URL opening `metadata`'s video at `ms` (rounded down to the second).
+pub fn at(metadata: &VideoMetadata, ms: u64) -> Option<String> {
+    let secs = ms / 1000;
+    let url = metadata.url.split('#').next().unwrap_or_default();
+    match metadata.platform.as_str() {
+        // Rebuilt from the ID so youtu.be, Shorts and embed URLs work too.
+        "YouTube" if !metadata.video_id.is_empty() => Some(format!(
+            "https://www.youtube.com/watch?v={}&t={}s",
+            metadata.video_id, secs
+        )),
+        "Vimeo" if url.starts_with("http") => Some(format!("{}#t={}s", url, secs)),
+        // Only VODs can seek; clips and live channels can't.
+        "Twitch" if url.contains("/videos/") => {
+            let url = url.split('?').next().unwrap_or_default();
+            Some(format!(
+                "{}?t={}h{}m{}s",
+                url,
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(platform: &str, url: &str) -> VideoMetadata {
+        VideoMetadata {
+            video_id: "abc123".to_string(),
+            title: "Talk".to_string(),
+            channel: "Chan".to_string(),
+            duration: 4_000,
+            upload_date: "20240501".to_string(),
+            platform: platform.to_string(),
+            url: url.to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        }
+    }
+
+    #[test]
+    fn links_to_the_second_on_supported_platforms() {
+        assert_eq!(
+            at(&metadata("YouTube", "https://youtu.be/abc123"), 83_900).as_deref(),
+            Some("https://www.youtube.com/watch?v=abc123&t=83s")
+        );
+        assert_eq!(
+            at(&metadata("Vimeo", "https://vimeo.com/123#t=5s"), 83_000).as_deref(),
+            Some("https://vimeo.com/123#t=83s")
+        );
+        assert_eq!(
+            at(
+                &metadata("Twitch", "https://www.twitch.tv/videos/42?filter=all"),
+                3_723_000
+            )
+            .as_deref(),
+            Some("https://www.twitch.tv/videos/42?t=1h2m3s")
+        );
+        assert_eq!(
+            at(&metadata("Twitch", "https://www.twitch.tv/chan"), 0),
+            None
+        );
+        assert_eq!(at(&metadata("Local File", "/tmp/talk.mp4"), 0), None);
+    }
+}
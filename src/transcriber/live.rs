@@ -0,0 +1,225 @@
+//! Live output: the txt and srt files of a long local transcription
+//! filling in while it runs.
+//!
+//! With `live_output`, each segment whisper finishes is appended to the
+//! final `.txt` / `.srt` paths, so a transcript can be opened and read
+//! (or followed with `tail -f`) long before the job completes. A chunked
+//! decode appends each chunk once every earlier chunk is in, so the files
+//! stay in order. On completion the finished outputs, with the glossary
+//! and clip offsets applied, replace the live files in one rename; edits
+//! made to a live file are lost then. If the job fails, what was decoded
+//! so far is set aside as `<file>.partial` (say `abc123-Talk.txt.partial`)
+//! rather than lost or left to pass for a finished transcript; the next
+//! live run of the same source removes it. Encrypted outputs can't be
+//! appended to, so there is no live output with encryption at rest.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+use super::store;
+use super::subtitles;
+use super::types::{OutputFormat, Segment};
+use super::vad::Timeline;
+
+/// Handle on the live files of one transcription, shared with the decoder.
+#[derive(Clone)]
+pub struct LiveOutput {
+    files: Arc<Mutex<Files>>,
+}
+
+struct Files {
+    paths: Vec<PathBuf>,
+    txt: Option<File>,
+    srt: Option<File>,
+    /// Non-empty segments appended so far.
+    written: usize,
+    /// Added to segment times: the clip's start, as decoded audio starts
+    /// at 0.
+    offset_ms: u64,
+    /// Where the decoded audio had silences cut (see [`super::vad`]).
+    timeline: Option<Timeline>,
+    /// Set after a failed write; the final outputs are still written.
+    failed: bool,
+}
+
+impl LiveOutput {
+    /// Start the live files under `base` for the txt and srt outputs in
+    /// `formats`. `None` when neither is requested.
+    pub fn create(base: &Path, formats: &[OutputFormat], offset_ms: u64) -> Result<Option<Self>> {
+        let formats = if formats.is_empty() {
+            &OutputFormat::DEFAULT[..]
+        } else {
+            formats
+        };
+        if let Some(dir) = base.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create output directory")?;
+        }
+        let mut paths = Vec::new();
+        let mut open = |format: OutputFormat| -> Result<Option<File>> {
+            if !formats.contains(&format) {
+                return Ok(None);
+            }
+            let path = store::file(base, format);
+            let _ = std::fs::remove_file(partial_path(&path));
+            let file = File::create(&path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            paths.push(path);
+            Ok(Some(file))
+        };
+        let (txt, srt) = (open(OutputFormat::Txt)?, open(OutputFormat::Srt)?);
+        if paths.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            files: Arc::new(Mutex::new(Files {
+                paths,
+                txt,
+                srt,
+                written: 0,
+                offset_ms,
+                timeline: None,
+                failed: false,
+            })),
+        }))
+    }
+
+    /// Append finished segments, in order.
+    pub fn append(&self, segments: &[Segment]) {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        if files.failed {
+            return;
+        }
+        if let Err(e) = files.append(segments) {
+            warn!("Live output stopped: {:#}", e);
+            files.failed = true;
+        }
+    }
+
+    /// Map segment times through `timeline` from now on.
+    pub fn set_timeline(&self, timeline: Timeline) {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files.timeline = Some(timeline);
+    }
+
+    /// Move the live files of a transcription that failed to their
+    /// `.partial` names, or remove them if nothing was written yet.
+    pub fn set_aside(&self) {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files.failed = true;
+        (files.txt, files.srt) = (None, None);
+        for path in &files.paths {
+            if files.written == 0 || std::fs::rename(path, partial_path(path)).is_err() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Where the live file at `path` goes when its transcription fails.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    partial.into()
+}
+
+impl Files {
+    fn append(&mut self, segments: &[Segment]) -> Result<()> {
+        for segment in segments.iter().filter(|s| !s.text.trim().is_empty()) {
+            let separator = if self.written == 0 { "" } else { " " };
+            if let Some(txt) = &mut self.txt {
+                write!(txt, "{}{}", separator, segment.text.trim())?;
+                txt.flush()?;
+            }
+            if let Some(srt) = &mut self.srt {
+                let mut segment = segment.clone();
+                if let Some(timeline) = &self.timeline {
+                    timeline.remap(std::slice::from_mut(&mut segment));
+                }
+                segment.start_ms += self.offset_ms;
+                segment.end_ms += self.offset_ms;
+                let separator = if self.written == 0 { "" } else { "\n" };
+                write!(
+                    srt,
+                    "{}{}",
+                    separator,
+                    subtitles::srt_cue(self.written + 1, &segment)
+                )?;
+                srt.flush()?;
+            }
+            self.written += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms: start_ms + 1_500,
+            text: format!(" {}", text),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn appends_like_the_final_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("abc123-Talk");
+        let live = LiveOutput::create(&base, &[OutputFormat::Txt, OutputFormat::Srt], 60_000)
+            .unwrap()
+            .unwrap();
+        let segments = [
+            segment(0, "Hello."),
+            segment(1_500, ""),
+            segment(3_000, "Bye."),
+        ];
+        live.append(&segments[..1]);
+        assert_eq!(
+            std::fs::read_to_string(store::file(&base, OutputFormat::Txt)).unwrap(),
+            "Hello."
+        );
+        live.append(&segments[1..]);
+
+        let shifted: Vec<Segment> = segments
+            .iter()
+            .map(|s| Segment {
+                start_ms: s.start_ms + 60_000,
+                end_ms: s.end_ms + 60_000,
+                ..s.clone()
+            })
+            .collect();
+        assert_eq!(
+            std::fs::read_to_string(store::file(&base, OutputFormat::Txt)).unwrap(),
+            "Hello. Bye."
+        );
+        assert_eq!(
+            std::fs::read_to_string(store::file(&base, OutputFormat::Srt)).unwrap(),
+            subtitles::srt(&shifted)
+        );
+
+        live.set_aside();
+        live.append(&segments);
+        assert!(!store::exists(&base));
+        let txt = store::file(&base, OutputFormat::Txt);
+        assert_eq!(
+            std::fs::read_to_string(partial_path(&txt)).unwrap(),
+            "Hello. Bye."
+        );
+        // The next run starts over.
+        LiveOutput::create(&base, &[OutputFormat::Txt], 0).unwrap();
+        assert!(!partial_path(&txt).exists());
+        assert!(
+            LiveOutput::create(&base, &[OutputFormat::Json], 0)
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -1,8 +1,41 @@
+pub mod alternatives;
 pub mod audio;
+pub mod batch;
+pub mod captions;
+pub mod chapters;
+pub mod checkpoint;
+pub mod classify;
 pub mod downloader;
 pub mod engine;
+pub mod glossary;
+pub mod golden;
+pub mod grammar;
+pub mod hotwords;
+pub mod library;
+pub mod links;
+pub mod live;
+pub mod notifier;
+pub mod outputs;
+pub mod progress;
+pub mod quality;
+pub mod query;
+pub mod redact;
+pub mod refresh;
+pub mod scheduler;
+pub mod search;
+pub mod semantic;
+pub mod sink;
+pub mod stages;
+pub mod store;
+pub mod subtitles;
+pub mod topics;
+pub mod turns;
 pub mod types;
+pub mod usage;
+pub mod vad;
+pub mod webhook;
 pub mod whisper;
+pub mod whisper_api;
 
 pub use engine::TranscriberEngine;
 pub use types::{TranscriptionOptions, WhisperModel};
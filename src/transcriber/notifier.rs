@@ -0,0 +1,110 @@
+//! Slack and Discord messages when transcriptions finish.
+//!
+//! Each of the config file's `notifiers` is an incoming webhook of a Slack
+//! or Discord channel. Once a transcription is done, each gets
+//! "Transcription finished: <title> (1234 words)" linking to the video,
+//! and to the transcript when the output sink gave it a URL. Failures are
+//! posted too unless `failures` is off. Cached transcripts returned
+//! without transcribing aren't announced. Messages go out in the
+//! background and a failed post is only logged.
+
+use anyhow::Result;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tracing::warn;
+
+use super::types::TranscriptionResult;
+use crate::config::{Config, Notifier, NotifierKind};
+
+/// Post the outcome of transcribing `source` to every configured channel.
+pub fn notify(source: &str, result: &Result<TranscriptionResult>) {
+    if matches!(result, Ok(r) if r.cached) {
+        return;
+    }
+    for notifier in Config::load().notifiers {
+        if result.is_err() && !notifier.failures {
+            continue;
+        }
+        let body = message(notifier.kind, source, result);
+        tokio::spawn(post(notifier, body));
+    }
+}
+
+async fn post(notifier: Notifier, body: Value) {
+    let sent = async {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?
+            .post(&notifier.url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        anyhow::Ok(())
+    };
+    if let Err(e) = sent.await {
+        warn!("{:?} notification failed: {:#}", notifier.kind, e);
+    }
+}
+
+/// The channel message for `result`, in `kind`'s webhook format.
+fn message(kind: NotifierKind, source: &str, result: &Result<TranscriptionResult>) -> Value {
+    let link = |text: &str, url: &str| match kind {
+        NotifierKind::Slack => format!("<{}|{}>", url, slack_escape(text)),
+        NotifierKind::Discord => format!("[{}](<{}>)", text.replace(['[', ']'], ""), url),
+    };
+    let text = match result {
+        Ok(result) => {
+            let metadata = &result.metadata;
+            // Local files have a path, not a link.
+            let title = if metadata.url.starts_with("http") {
+                link(&metadata.title, &metadata.url)
+            } else {
+                metadata.title.clone()
+            };
+            let mut text = format!(
+                "Transcription finished: {} ({} words)",
+                title, result.word_count
+            );
+            let transcript = match result.uploaded.0.is_empty() {
+                true => result.files.primary(),
+                false => result.uploaded.primary(),
+            };
+            if transcript.starts_with("https://") || transcript.starts_with("http://") {
+                text.push_str(&format!(" · {}", link("transcript", transcript)));
+            }
+            text
+        }
+        Err(e) => format!("Transcription failed: {}: {:#}", source, e),
+    };
+    match kind {
+        NotifierKind::Slack => json!({ "text": text }),
+        NotifierKind::Discord => json!({ "content": text }),
+    }
+}
+
+/// `text` with the characters Slack's mrkdwn reserves escaped.
+fn slack_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_for_each_service() {
+        let failed: Result<TranscriptionResult> = Err(anyhow::anyhow!("yt-dlp failed"));
+        assert_eq!(
+            message(NotifierKind::Slack, "https://example.com/talk", &failed),
+            json!({ "text": "Transcription failed: https://example.com/talk: yt-dlp failed" })
+        );
+        assert_eq!(
+            message(NotifierKind::Discord, "https://example.com/talk", &failed)["content"],
+            "Transcription failed: https://example.com/talk: yt-dlp failed"
+        );
+        assert_eq!(slack_escape("Q&A <live>"), "Q&amp;A &lt;live&gt;");
+    }
+}
@@ -0,0 +1,339 @@
+//! Rendering a finished transcription into each [`OutputFormat`].
+//!
+//! [`render`] is the one place a format maps to its content; adding a
+//! format means a variant in `OutputFormat` and an arm here.
+
+use anyhow::Result;
+
+use super::glossary::Change;
+use super::links;
+use super::subtitles;
+use super::topics::Topic;
+use super::turns;
+use super::types::{
+    ClipRange, DetectedLanguage, MdTimestamps, OutputFormat, Segment, SpeakerTurns, VideoMetadata,
+};
+
+/// Everything a format can draw on.
+pub struct Transcript<'a> {
+    pub metadata: &'a VideoMetadata,
+    pub text: &'a str,
+    pub segments: &'a [Segment],
+    pub clip: &'a ClipRange,
+    /// Model label, e.g. `base` or `captions (en)`.
+    pub model: &'a str,
+    pub detected_language: Option<&'a DetectedLanguage>,
+    pub md_timestamps: MdTimestamps,
+    /// Speaker-turn layout of the txt and Markdown outputs.
+    pub speaker_turns: SpeakerTurns,
+    /// Names of the speakers by track number, e.g. Caller and Agent for a
+    /// stereo call. Empty numbers them instead.
+    pub speakers: &'a [String],
+    /// Replacements the glossary made in the segments.
+    pub glossary_changes: &'a [Change],
+    /// Main topics, best first.
+    pub topics: &'a [Topic],
+    /// Where the segments' zero is in the source, for links: a chapter's
+    /// start in its own transcript.
+    pub offset_ms: u64,
+}
+
+pub fn render(format: OutputFormat, t: &Transcript) -> Result<String> {
+    Ok(match format {
+        OutputFormat::Txt => {
+            turns::render(t.speaker_turns, t.segments, t.speakers, false, |_| None)
+                .unwrap_or_else(|| t.text.to_string())
+        }
+        OutputFormat::Json => json(t)?,
+        OutputFormat::Md => markdown(t),
+        OutputFormat::Srt => subtitles::srt(t.segments),
+        OutputFormat::Vtt => subtitles::vtt(t.segments),
+    })
+}
+
+fn json(t: &Transcript) -> Result<String> {
+    let mut segments = serde_json::to_value(t.segments)?;
+    if let Some(segments) = segments.as_array_mut() {
+        for (value, segment) in segments.iter_mut().zip(t.segments) {
+            if let Some(link) = links::at(t.metadata, t.offset_ms + segment.start_ms) {
+                value["link"] = link.into();
+            }
+        }
+    }
+    let mut json_output = serde_json::json!({
+        "metadata": t.metadata,
+        "transcript": t.text,
+        "segments": segments,
+        "model": t.model,
+    });
+    if let Some(lang) = t.detected_language {
+        json_output["detected_language"] = serde_json::to_value(lang)?;
+    }
+    if t.clip.is_set() {
+        json_output["clip"] = serde_json::to_value(t.clip)?;
+    }
+    if !t.glossary_changes.is_empty() {
+        json_output["glossary_changes"] = serde_json::to_value(t.glossary_changes)?;
+    }
+    if !t.topics.is_empty() {
+        json_output["topics"] = serde_json::to_value(t.topics)?;
+    }
+    if !t.speakers.is_empty() {
+        json_output["speakers"] = serde_json::to_value(t.speakers)?;
+    }
+    Ok(serde_json::to_string_pretty(&json_output)?)
+}
+
+fn markdown(t: &Transcript) -> String {
+    let metadata = t.metadata;
+    format!(
+        "# {}\n\n\
+        **Video:** {}\n\
+        **Platform:** {}\n\
+        **Channel:** {}\n\
+        **Video ID:** {}\n\
+        **Duration:** {}s\n\
+        **Published:** {}\n{}{}{}\n\
+        ---\n\n\
+        ## Transcript\n\n\
+        {}\n\n\
+        ---\n\n\
+        *Transcribed using whisper.cpp (Rust) - Model: {}*\n",
+        metadata.title,
+        metadata.url,
+        metadata.platform,
+        metadata.channel,
+        metadata.video_id,
+        metadata.duration,
+        metadata.upload_date,
+        t.detected_language
+            .map(|lang| if lang.is_detected() {
+                format!("**Language:** {} (auto-detected)\n", lang)
+            } else {
+                format!("**Language:** {}\n", lang)
+            })
+            .unwrap_or_default(),
+        metadata
+            .source_note
+            .as_deref()
+            .map(|note| format!("**Source note:** {}\n", note))
+            .unwrap_or_default(),
+        if t.clip.is_set() {
+            format!("**Clip:** {}\n", t.clip)
+        } else {
+            String::new()
+        },
+        markdown_body(t),
+        t.model
+    )
+}
+
+/// A new paragraph starts after a pause this long…
+const PARAGRAPH_PAUSE_MS: u64 = 2_000;
+/// …or once the current one has run this long.
+const PARAGRAPH_MAX_MS: u64 = 60_000;
+
+fn markdown_body(t: &Transcript) -> String {
+    if let Some(turns) = turns::render(t.speaker_turns, t.segments, t.speakers, true, |ms| {
+        links::at(t.metadata, t.offset_ms + ms)
+    }) {
+        return turns;
+    }
+    let segments: Vec<&Segment> = t
+        .segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .collect();
+    // The marker links to the moment where the platform allows it.
+    let line = |s: &Segment, text: &str| {
+        let marker = match links::at(t.metadata, t.offset_ms + s.start_ms) {
+            Some(link) => format!("**[{}]({})**", marker(s.start_ms), link),
+            None => format!("**[{}]**", marker(s.start_ms)),
+        };
+        match s.track {
+            Some(n) => format!("{} Track {}: {}", marker, n, text),
+            None => format!("{} {}", marker, text),
+        }
+    };
+    match t.md_timestamps {
+        // Transcripts saved without segments can only be shown plain.
+        _ if segments.is_empty() => t.text.to_string(),
+        MdTimestamps::Off => t.text.to_string(),
+        MdTimestamps::Segment => segments
+            .iter()
+            .map(|s| line(s, s.text.trim()))
+            .collect::<Vec<_>>()
+            .join("  \n"),
+        MdTimestamps::Paragraph => {
+            let mut paragraphs: Vec<Vec<&Segment>> = Vec::new();
+            for s in segments {
+                match paragraphs.last_mut() {
+                    Some(p)
+                        if s.track == p[0].track
+                            && s.start_ms < p[p.len() - 1].end_ms + PARAGRAPH_PAUSE_MS
+                            && s.start_ms < p[0].start_ms + PARAGRAPH_MAX_MS =>
+                    {
+                        p.push(s)
+                    }
+                    _ => paragraphs.push(vec![s]),
+                }
+            }
+            paragraphs
+                .iter()
+                .map(|p| {
+                    let text: Vec<&str> = p.iter().map(|s| s.text.trim()).collect();
+                    line(p[0], &text.join(" "))
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        }
+    }
+}
+
+/// `MM:SS`, or `H:MM:SS` from an hour in.
+pub fn marker(ms: u64) -> String {
+    let secs = ms / 1000;
+    match secs / 3600 {
+        0 => format!("{:02}:{:02}", secs / 60, secs % 60),
+        h => format!("{}:{:02}:{:02}", h, secs / 60 % 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_each_format() {
+        let metadata = VideoMetadata {
+            video_id: "abc123".to_string(),
+            title: "My Talk".to_string(),
+            channel: "Chan".to_string(),
+            duration: 90,
+            upload_date: "20240501".to_string(),
+            platform: "YouTube".to_string(),
+            url: "https://youtu.be/abc123".to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        };
+        let segments = [Segment {
+            start_ms: 0,
+            end_ms: 1_500,
+            text: " Hello.".to_string(),
+            ..Default::default()
+        }];
+        let t = Transcript {
+            metadata: &metadata,
+            text: "Hello.",
+            segments: &segments,
+            clip: &ClipRange::default(),
+            model: "base",
+            detected_language: None,
+            md_timestamps: MdTimestamps::Off,
+            speaker_turns: SpeakerTurns::Off,
+            speakers: &[],
+            glossary_changes: &[],
+            topics: &[],
+            offset_ms: 0,
+        };
+
+        assert_eq!(render(OutputFormat::Txt, &t).unwrap(), "Hello.");
+        let json: serde_json::Value =
+            serde_json::from_str(&render(OutputFormat::Json, &t).unwrap()).unwrap();
+        assert_eq!(json["segments"][0]["end_ms"], 1_500);
+        assert_eq!(
+            json["segments"][0]["link"],
+            "https://www.youtube.com/watch?v=abc123&t=0s"
+        );
+        assert!(json.get("clip").is_none());
+        assert!(
+            render(OutputFormat::Md, &t)
+                .unwrap()
+                .starts_with("# My Talk\n")
+        );
+        assert!(
+            render(OutputFormat::Srt, &t)
+                .unwrap()
+                .starts_with("1\n00:00:00,000")
+        );
+        assert!(
+            render(OutputFormat::Vtt, &t)
+                .unwrap()
+                .starts_with("WEBVTT\n")
+        );
+    }
+
+    #[test]
+    fn markdown_timestamps_mark_paragraphs_or_segments() {
+        let metadata = VideoMetadata {
+            video_id: "abc123".to_string(),
+            title: "My Talk".to_string(),
+            channel: "Chan".to_string(),
+            duration: 4_000,
+            upload_date: "20240501".to_string(),
+            platform: "Local File".to_string(),
+            url: "/talks/my-talk.mp4".to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        };
+        let segment = |start_ms, end_ms, text: &str| Segment {
+            start_ms,
+            end_ms,
+            text: format!(" {}", text),
+            ..Default::default()
+        };
+        let segments = [
+            segment(0, 4_000, "Welcome."),
+            segment(4_500, 9_000, "Let's start."),
+            segment(65_000, 70_000, "After the break."),
+            segment(3_725_000, 3_730_000, "Thanks."),
+        ];
+        let mut t = Transcript {
+            metadata: &metadata,
+            text: "Welcome. Let's start. After the break. Thanks.",
+            segments: &segments,
+            clip: &ClipRange::default(),
+            model: "base",
+            detected_language: None,
+            md_timestamps: MdTimestamps::Paragraph,
+            speaker_turns: SpeakerTurns::Off,
+            speakers: &[],
+            glossary_changes: &[],
+            topics: &[],
+            offset_ms: 0,
+        };
+
+        let md = render(OutputFormat::Md, &t).unwrap();
+        assert!(md.contains(
+            "## Transcript\n\n\
+            **[00:00]** Welcome. Let's start.\n\n\
+            **[01:05]** After the break.\n\n\
+            **[1:02:05]** Thanks.\n\n---"
+        ));
+
+        t.md_timestamps = MdTimestamps::Segment;
+        let md = render(OutputFormat::Md, &t).unwrap();
+        assert!(md.contains("**[00:00]** Welcome.  \n**[00:04]** Let's start.  \n"));
+
+        let youtube = VideoMetadata {
+            platform: "YouTube".to_string(),
+            url: "https://youtu.be/abc123".to_string(),
+            ..metadata.clone()
+        };
+        t.metadata = &youtube;
+        let md = render(OutputFormat::Md, &t).unwrap();
+        assert!(md.contains(
+            "**[01:05](https://www.youtube.com/watch?v=abc123&t=65s)** After the break."
+        ));
+
+        assert_eq!(
+            "segment".parse::<MdTimestamps>().unwrap(),
+            MdTimestamps::Segment
+        );
+        assert!("minutes".parse::<MdTimestamps>().is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Progress of a running job, for watchers such as the REST API's event
+//! stream.
+//!
+//! A [`Progress`] goes to the engine in `TranscriptionOptions::progress`.
+//! The engine reports when Whisper starts and then, as segments come in,
+//! how far through the audio it is and the latest segment; the REST API
+//! reports the stages around that. It is a `tokio::sync::watch` channel, so
+//! watchers see the newest [`Snapshot`] each time it changes and a slow
+//! one skips ahead instead of falling behind.
+
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+use super::types::Segment;
+
+/// Where a job is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Queued,
+    Downloading,
+    Transcribing,
+    Summarizing,
+    Complete,
+    Failed,
+    Cancelled,
+}
+
+impl Stage {
+    /// Nothing more will happen.
+    pub fn is_finished(self) -> bool {
+        matches!(self, Stage::Complete | Stage::Failed | Stage::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Snapshot {
+    pub stage: Stage,
+    /// How much of the audio Whisper has been through. `None` before it
+    /// starts and when the length isn't known, as for streamed audio.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latest_segment: Option<Segment>,
+}
+
+/// Sending side of a job's progress; clones report to the same watchers.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    tx: Arc<watch::Sender<Snapshot>>,
+}
+
+impl Progress {
+    pub fn new(stage: Stage) -> Self {
+        let (tx, _) = watch::channel(Snapshot {
+            stage,
+            percent: None,
+            latest_segment: None,
+        });
+        Self { tx: Arc::new(tx) }
+    }
+
+    pub fn stage(&self) -> Stage {
+        self.tx.borrow().stage
+    }
+
+    pub fn set_stage(&self, stage: Stage) {
+        self.tx.send_if_modified(|snapshot| {
+            let changed = snapshot.stage != stage;
+            snapshot.stage = stage;
+            changed
+        });
+    }
+
+    /// Whisper is `percent` through the audio, with `latest` the last
+    /// segment it has.
+    pub fn transcribed(&self, percent: Option<u8>, latest: Option<&Segment>) {
+        self.tx.send_modify(|snapshot| {
+            snapshot.stage = Stage::Transcribing;
+            if percent.is_some() {
+                snapshot.percent = percent.map(|p| p.min(100));
+            }
+            if let Some(segment) = latest {
+                snapshot.latest_segment = Some(segment.clone());
+            }
+        });
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Snapshot> {
+        self.tx.subscribe()
+    }
+}
+
+/// `done` of `total`, as a whole percentage.
+pub fn percent(done: u64, total: u64) -> Option<u8> {
+    (total > 0).then(|| (done.min(total) * 100 / total) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchers_see_the_latest_snapshot() {
+        let progress = Progress::new(Stage::Queued);
+        let mut rx = progress.subscribe();
+        progress.set_stage(Stage::Downloading);
+        assert!(rx.has_changed().unwrap());
+        rx.mark_unchanged();
+        progress.set_stage(Stage::Downloading);
+        assert!(!rx.has_changed().unwrap());
+
+        let segment = Segment {
+            start_ms: 0,
+            end_ms: 2500,
+            text: "Hello there.".to_string(),
+            ..Default::default()
+        };
+        progress.transcribed(percent(30_000, 120_000), Some(&segment));
+        progress.transcribed(None, None);
+        let snapshot = rx.borrow_and_update().clone();
+        assert_eq!(snapshot.stage, Stage::Transcribing);
+        assert_eq!(snapshot.percent, Some(25));
+        assert_eq!(snapshot.latest_segment.unwrap().text, "Hello there.");
+        assert_eq!(percent(5, 0), None);
+        assert!(!progress.stage().is_finished());
+    }
+}
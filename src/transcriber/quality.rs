@@ -0,0 +1,224 @@
+//! Quick signal checks on the decoded audio before whisper runs.
+//!
+//! Bad input — clipped levels, heavy background noise, telephone-band
+//! audio — is the usual reason for a poor transcript, and it's cheap to spot
+//! from the 16 kHz PCM we already hold. The numbers are rough estimates,
+//! meant to explain a bad result rather than to grade recordings.
+
+use serde::Serialize;
+
+const SAMPLE_RATE: f32 = 16_000.0;
+
+/// 25 ms frames for the level statistics.
+const LEVEL_FRAME: usize = 400;
+
+/// Samples at or above this magnitude count as clipped.
+const CLIP_LEVEL: f32 = 0.999;
+
+/// DFT size for the bandwidth estimate (32 ms, 31.25 Hz bins).
+const SPECTRUM_FRAME: usize = 512;
+
+/// Only the loudest frames are analysed; silence says nothing about
+/// bandwidth and a few dozen frames of speech are plenty.
+const SPECTRUM_FRAMES: usize = 48;
+
+/// Bins averaged into one band (250 Hz) to smooth the spectrum.
+const SPECTRUM_BAND_BINS: usize = 8;
+
+/// Bandwidth ends at the highest band within this many dB of the peak.
+const BANDWIDTH_FLOOR_DB: f32 = 60.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioQuality {
+    /// 0–100; see [`score`].
+    pub score: u8,
+    /// Estimated signal-to-noise ratio: loud frames vs. quiet frames.
+    pub snr_db: f32,
+    /// Share of samples at full scale.
+    pub clipping_percent: f32,
+    /// Highest frequency carrying meaningful energy.
+    pub bandwidth_hz: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl AudioQuality {
+    /// One-line summary for tool output and logs.
+    pub fn summary(&self) -> String {
+        format!(
+            "{}/100 (SNR {:.0} dB, clipping {:.2}%, bandwidth {:.1} kHz)",
+            self.score,
+            self.snr_db,
+            self.clipping_percent,
+            self.bandwidth_hz as f32 / 1000.0
+        )
+    }
+}
+
+/// Assess 16 kHz mono PCM. Returns `None` for clips under a second, which
+/// are too short to say anything useful about.
+pub fn assess(samples: &[f32]) -> Option<AudioQuality> {
+    if samples.len() < SAMPLE_RATE as usize {
+        return None;
+    }
+
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+    let clipping_percent = clipped as f32 * 100.0 / samples.len() as f32;
+    let snr_db = estimate_snr_db(samples);
+    let bandwidth_hz = estimate_bandwidth_hz(samples);
+
+    let mut warnings = Vec::new();
+    if clipping_percent >= 0.5 {
+        warnings.push("Heavily clipped audio, accuracy will suffer".to_string());
+    } else if clipping_percent >= 0.05 {
+        warnings.push("Some clipping detected".to_string());
+    }
+    if snr_db < 10.0 {
+        warnings.push("Very noisy audio, expect misheard words".to_string());
+    } else if snr_db < 20.0 {
+        warnings.push("Noticeable background noise".to_string());
+    }
+    if bandwidth_hz < 4_000 {
+        warnings.push(
+            "Narrowband (telephone-quality) audio, consonants may be misrecognised".to_string(),
+        );
+    }
+
+    Some(AudioQuality {
+        score: score(snr_db, clipping_percent, bandwidth_hz),
+        snr_db,
+        clipping_percent,
+        bandwidth_hz,
+        warnings,
+    })
+}
+
+/// Combine the measurements into a 0–100 score: 100 for clean wideband
+/// audio, losing up to 40 points each for noise and clipping and up to 20
+/// for a narrow band.
+fn score(snr_db: f32, clipping_percent: f32, bandwidth_hz: u32) -> u8 {
+    let noise_penalty = ((30.0 - snr_db).max(0.0) * 2.0).min(40.0);
+    let clipping_penalty = (clipping_percent * 40.0).min(40.0);
+    let band_penalty = match bandwidth_hz {
+        0..4_000 => 20.0,
+        4_000..6_000 => 10.0,
+        _ => 0.0,
+    };
+    (100.0 - noise_penalty - clipping_penalty - band_penalty).clamp(0.0, 100.0) as u8
+}
+
+/// Ratio of loud-frame to quiet-frame RMS (95th vs. 10th percentile). Speech
+/// has pauses, so the quiet frames approximate the noise floor. Capped at
+/// 60 dB so digital silence doesn't produce absurd numbers.
+fn estimate_snr_db(samples: &[f32]) -> f32 {
+    let mut levels: Vec<f32> = samples.chunks(LEVEL_FRAME).map(rms).collect();
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let noise = levels[levels.len() / 10].max(1e-6);
+    let signal = levels[levels.len() * 95 / 100].max(1e-6);
+    (20.0 * (signal / noise).log10()).clamp(0.0, 60.0)
+}
+
+fn estimate_bandwidth_hz(samples: &[f32]) -> u32 {
+    let mut frames: Vec<(f32, &[f32])> = samples
+        .chunks_exact(SPECTRUM_FRAME)
+        .map(|frame| (rms(frame), frame))
+        .collect();
+    frames.sort_by(|a, b| b.0.total_cmp(&a.0));
+    frames.truncate(SPECTRUM_FRAMES);
+
+    // Hann window and a twiddle table, then a plain DFT — 48 frames × 256
+    // bins is far too little work to justify an FFT dependency.
+    let n = SPECTRUM_FRAME;
+    let window: Vec<f32> = (0..n)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / n as f32).cos())
+        .collect();
+    let (cos, sin): (Vec<f32>, Vec<f32>) = (0..n)
+        .map(|i| {
+            let phase = std::f32::consts::TAU * i as f32 / n as f32;
+            (phase.cos(), phase.sin())
+        })
+        .unzip();
+
+    let bins = n / 2;
+    let mut power = vec![0.0f64; bins];
+    for (_, frame) in &frames {
+        for (k, p) in power.iter_mut().enumerate() {
+            let (mut re, mut im) = (0.0f32, 0.0f32);
+            for (i, s) in frame.iter().enumerate() {
+                let x = s * window[i];
+                let t = (k * i) % n;
+                re += x * cos[t];
+                im -= x * sin[t];
+            }
+            *p += (re * re + im * im) as f64;
+        }
+    }
+
+    let bands: Vec<f64> = power
+        .chunks(SPECTRUM_BAND_BINS)
+        .map(|b| b.iter().sum::<f64>() / b.len() as f64)
+        .collect();
+    let peak = bands.iter().cloned().fold(0.0, f64::max);
+    if peak <= 0.0 {
+        return 0;
+    }
+    let floor = peak * 10f64.powf(-(BANDWIDTH_FLOOR_DB as f64) / 10.0);
+    let last = bands.iter().rposition(|&b| b >= floor).unwrap_or(0);
+    let band_hz = SAMPLE_RATE / n as f32 * SPECTRUM_BAND_BINS as f32;
+    ((last + 1) as f32 * band_hz) as u32
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white-ish noise in [-1, 1).
+    fn noise(len: usize, seed: u32) -> Vec<f32> {
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x = x.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (x >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+            })
+            .collect()
+    }
+
+    /// Two seconds of a tone burst followed by near-silence.
+    fn burst(amplitude: f32) -> Vec<f32> {
+        (0..32_000)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE;
+                let level = if i < 24_000 { amplitude } else { 0.001 };
+                (level * (std::f32::consts::TAU * 300.0 * t).sin()).clamp(-1.0, 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flags_narrowband_tone() {
+        let q = assess(&burst(0.5)).unwrap();
+        assert!(q.bandwidth_hz < 4_000, "{}", q.summary());
+        assert!(q.snr_db > 40.0, "{}", q.summary());
+        assert_eq!(q.clipping_percent, 0.0);
+        assert!(q.warnings.iter().any(|w| w.contains("Narrowband")));
+    }
+
+    #[test]
+    fn flags_clipping() {
+        let q = assess(&burst(3.0)).unwrap();
+        assert!(q.clipping_percent > 0.5, "{}", q.summary());
+        assert!(q.warnings.iter().any(|w| w.contains("clipped")));
+    }
+
+    #[test]
+    fn white_noise_is_wideband_but_noisy() {
+        let q = assess(&noise(32_000, 7).iter().map(|s| s * 0.3).collect::<Vec<_>>()).unwrap();
+        assert!(q.bandwidth_hz >= 7_500, "{}", q.summary());
+        assert!(q.snr_db < 10.0, "{}", q.summary());
+        assert!(assess(&[0.0; 100]).is_none());
+    }
+}
@@ -0,0 +1,543 @@
+//! `query_transcripts`: a small SQL-like filter language over the library
+//! index, so one call can answer "which talks from 2024 mention Rust
+//! macros".
+//!
+//! ```text
+//! platform = YouTube AND year = 2024 AND mentions "rust macros"
+//! channel ~ conf AND duration > 30m ORDER BY words DESC LIMIT 5
+//! ```
+//!
+//! Conditions are joined with `AND` (a leading `WHERE` is allowed) and
+//! compare a [`Field`] with `=`, `!=`, `<`, `<=`, `>`, `>=` or `~`
+//! (contains; also spelled `CONTAINS`). Text compares ignore case.
+//! `mentions "phrase"` looks for the phrase in the transcript's segments,
+//! like `search_transcripts`, and is only checked for transcripts the
+//! other conditions leave. Transcripts without an upload date (local files)
+//! fail every `date` and `year` condition.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::library::{Entry, Library};
+use super::search::{self, parse_date};
+use super::store;
+use super::types::OutputFormat;
+
+/// Rows returned when the query has no `LIMIT`.
+pub const DEFAULT_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Platform,
+    Channel,
+    Title,
+    Language,
+    Model,
+    VideoId,
+    /// Any of the transcript's topics.
+    Topic,
+    /// Upload date, `YYYY-MM-DD`.
+    Date,
+    /// Upload year.
+    Year,
+    /// Seconds, or `90s`, `45m`, `1h`, `1:30:00`.
+    Duration,
+    /// Word count.
+    Words,
+}
+
+impl Field {
+    pub const NAMES: [&'static str; 11] = [
+        "platform", "channel", "title", "language", "model", "video_id", "topic", "date", "year",
+        "duration", "words",
+    ];
+
+    fn numeric(self) -> bool {
+        matches!(self, Field::Year | Field::Duration | Field::Words)
+    }
+}
+
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "platform" => Field::Platform,
+            "channel" => Field::Channel,
+            "title" => Field::Title,
+            "language" => Field::Language,
+            "model" => Field::Model,
+            "video_id" | "id" => Field::VideoId,
+            "topic" | "topics" => Field::Topic,
+            "date" | "upload_date" => Field::Date,
+            "year" => Field::Year,
+            "duration" => Field::Duration,
+            "words" | "word_count" => Field::Words,
+            other => anyhow::bail!(
+                "Unknown field '{}'; use one of {}",
+                other,
+                Field::NAMES.join(", ")
+            ),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(u64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Compare { field: Field, op: Op, value: Value },
+    Mentions(String),
+}
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    conditions: Vec<Condition>,
+    /// Field and whether descending; `None` is newest saved first.
+    order: Option<(Field, bool)>,
+    pub limit: Option<usize>,
+}
+
+/// A transcript the query matched.
+#[derive(Debug, Clone, Serialize)]
+pub struct Row {
+    pub video_id: String,
+    pub title: String,
+    pub channel: String,
+    pub platform: String,
+    pub url: String,
+    /// `YYYYMMDD`, empty when unknown.
+    pub upload_date: String,
+    /// Seconds.
+    pub duration: u64,
+    pub language: Option<String>,
+    pub model: String,
+    pub word_count: usize,
+    pub topics: Vec<String>,
+    /// Path of the transcript, relative to the output directory.
+    pub transcript: String,
+    /// Segments mentioning each `mentions` phrase, when there were any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mentions: Option<usize>,
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = tokenize(s)?;
+        let mut tokens = tokens.iter().peekable();
+        if keyword(tokens.peek(), "where") {
+            tokens.next();
+        }
+
+        let mut query = Query {
+            conditions: Vec::new(),
+            order: None,
+            limit: None,
+        };
+        while let Some(token) = tokens.next() {
+            if keyword(Some(&token), "and") && !query.conditions.is_empty() {
+                continue;
+            }
+            if keyword(Some(&token), "order") {
+                if !keyword(tokens.next().as_ref(), "by") {
+                    anyhow::bail!("Expected BY after ORDER");
+                }
+                let field: Field = match tokens.next() {
+                    Some(Token::Word(w)) => w.parse()?,
+                    _ => anyhow::bail!("Expected a field after ORDER BY"),
+                };
+                let descending = if keyword(tokens.peek(), "desc") {
+                    tokens.next();
+                    true
+                } else {
+                    if keyword(tokens.peek(), "asc") {
+                        tokens.next();
+                    }
+                    false
+                };
+                query.order = Some((field, descending));
+                continue;
+            }
+            if keyword(Some(&token), "limit") {
+                let limit = match tokens.next() {
+                    Some(Token::Word(w)) => w.parse().ok(),
+                    _ => None,
+                };
+                query.limit = Some(limit.context("Expected a number after LIMIT")?);
+                continue;
+            }
+            if keyword(Some(&token), "mentions") {
+                let phrase = match tokens.next() {
+                    Some(Token::Word(w) | Token::Quoted(w)) if !w.trim().is_empty() => w,
+                    _ => anyhow::bail!("Expected a phrase after MENTIONS"),
+                };
+                query.conditions.push(Condition::Mentions(phrase.clone()));
+                continue;
+            }
+
+            let field: Field = match token {
+                Token::Word(w) => w.parse()?,
+                other => anyhow::bail!("Expected a field, found {}", other),
+            };
+            let op = match tokens.next() {
+                Some(Token::Op(op)) => parse_op(op)?,
+                Some(Token::Word(w)) if w.eq_ignore_ascii_case("contains") => Op::Contains,
+                _ => anyhow::bail!("Expected an operator after {:?}", field),
+            };
+            let raw = match tokens.next() {
+                Some(Token::Word(w) | Token::Quoted(w)) => w,
+                _ => anyhow::bail!("Expected a value after {:?}", field),
+            };
+            if field.numeric() && op == Op::Contains {
+                anyhow::bail!("~ only works on text fields");
+            }
+            let value = match field {
+                Field::Date => Value::Text(parse_date(raw)?),
+                Field::Year => Value::Number(
+                    raw.parse()
+                        .with_context(|| format!("Invalid year {:?}", raw))?,
+                ),
+                Field::Duration => Value::Number(parse_duration(raw)?),
+                Field::Words => Value::Number(
+                    raw.parse()
+                        .with_context(|| format!("Invalid word count {:?}", raw))?,
+                ),
+                _ => Value::Text(raw.to_lowercase()),
+            };
+            query
+                .conditions
+                .push(Condition::Compare { field, op, value });
+        }
+        Ok(query)
+    }
+}
+
+impl Query {
+    /// Transcripts in `output_dir` matching the query, ordered and limited
+    /// as it says (at most [`DEFAULT_LIMIT`] without a `LIMIT`).
+    pub fn run(&self, output_dir: &Path) -> Result<Vec<Row>> {
+        let mut entries: Vec<Entry> = Library::open(output_dir)?
+            .list(None)?
+            .into_iter()
+            .filter(|entry| {
+                self.conditions.iter().all(|c| match c {
+                    Condition::Compare { field, op, value } => compare(entry, *field, *op, value),
+                    Condition::Mentions(_) => true,
+                })
+            })
+            .collect();
+        if let Some((field, descending)) = self.order {
+            entries.sort_by(|a, b| {
+                let ordering = sort_key(a, field).cmp(&sort_key(b, field));
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+
+        let phrases: Vec<&str> = self
+            .conditions
+            .iter()
+            .filter_map(|c| match c {
+                Condition::Mentions(phrase) => Some(phrase.as_str()),
+                _ => None,
+            })
+            .collect();
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT);
+        let mut rows = Vec::new();
+        for entry in entries {
+            if rows.len() == limit {
+                break;
+            }
+            let mentions = if phrases.is_empty() {
+                None
+            } else {
+                if !entry.formats.contains(&OutputFormat::Json) {
+                    continue;
+                }
+                let Ok(stored) = store::load(&output_dir.join(&entry.base)) else {
+                    continue;
+                };
+                let counts: Vec<usize> = phrases
+                    .iter()
+                    .map(|phrase| search::search(&stored.segments, phrase).len())
+                    .collect();
+                if counts.contains(&0) {
+                    continue;
+                }
+                Some(counts.iter().sum())
+            };
+            rows.push(Row {
+                transcript: entry.base.to_string_lossy().to_string(),
+                video_id: entry.video_id,
+                title: entry.title,
+                channel: entry.channel,
+                platform: entry.platform,
+                url: entry.url,
+                upload_date: entry.upload_date,
+                duration: entry.duration,
+                language: entry.language,
+                model: entry.model,
+                word_count: entry.word_count,
+                topics: entry.topics,
+                mentions,
+            });
+        }
+        Ok(rows)
+    }
+}
+
+fn compare(entry: &Entry, field: Field, op: Op, value: &Value) -> bool {
+    let dated = !entry.upload_date.is_empty();
+    match (field, value) {
+        (Field::Year, Value::Number(n)) => entry
+            .upload_date
+            .get(..4)
+            .and_then(|year| year.parse().ok())
+            .is_some_and(|year: u64| compare_ord(year, op, *n)),
+        (Field::Duration, Value::Number(n)) => compare_ord(entry.duration, op, *n),
+        (Field::Words, Value::Number(n)) => compare_ord(entry.word_count as u64, op, *n),
+        (Field::Date, Value::Text(date)) => {
+            dated && compare_ord(entry.upload_date.as_str(), op, date.as_str())
+        }
+        (Field::Topic, Value::Text(wanted)) => {
+            let any = |f: &dyn Fn(&str) -> bool| entry.topics.iter().any(|t| f(&t.to_lowercase()));
+            match op {
+                Op::Contains => any(&|t| t.contains(wanted.as_str())),
+                Op::Ne => !any(&|t| t == wanted),
+                _ => any(&|t| compare_ord(t, op, wanted.as_str())),
+            }
+        }
+        (_, Value::Text(wanted)) => {
+            let text = match field {
+                Field::Platform => &entry.platform,
+                Field::Channel => &entry.channel,
+                Field::Title => &entry.title,
+                Field::Language => entry.language.as_deref().unwrap_or_default(),
+                Field::Model => &entry.model,
+                _ => &entry.video_id,
+            }
+            .to_lowercase();
+            match op {
+                Op::Contains => text.contains(wanted.as_str()),
+                _ => compare_ord(text.as_str(), op, wanted.as_str()),
+            }
+        }
+        _ => false,
+    }
+}
+
+fn compare_ord<T: PartialOrd>(a: T, op: Op, b: T) -> bool {
+    match op {
+        Op::Eq => a == b,
+        Op::Ne => a != b,
+        Op::Lt => a < b,
+        Op::Le => a <= b,
+        Op::Gt => a > b,
+        Op::Ge => a >= b,
+        Op::Contains => false,
+    }
+}
+
+/// What `ORDER BY field` sorts on: numbers first, then lowercased text.
+fn sort_key(entry: &Entry, field: Field) -> (u64, String) {
+    match field {
+        Field::Duration => (entry.duration, String::new()),
+        Field::Words => (entry.word_count as u64, String::new()),
+        Field::Date | Field::Year => (0, entry.upload_date.clone()),
+        Field::Platform => (0, entry.platform.to_lowercase()),
+        Field::Channel => (0, entry.channel.to_lowercase()),
+        Field::Title => (0, entry.title.to_lowercase()),
+        Field::Language => (0, entry.language.clone().unwrap_or_default()),
+        Field::Model => (0, entry.model.clone()),
+        Field::VideoId => (0, entry.video_id.clone()),
+        Field::Topic => (0, entry.topics.first().cloned().unwrap_or_default()),
+    }
+}
+
+fn keyword(token: Option<&&Token>, word: &str) -> bool {
+    matches!(token, Some(Token::Word(w)) if w.eq_ignore_ascii_case(word))
+}
+
+fn parse_op(op: &str) -> Result<Op> {
+    Ok(match op {
+        "=" | "==" => Op::Eq,
+        "!=" | "<>" => Op::Ne,
+        "<" => Op::Lt,
+        "<=" => Op::Le,
+        ">" => Op::Gt,
+        ">=" => Op::Ge,
+        "~" => Op::Contains,
+        other => anyhow::bail!("Unknown operator '{}'", other),
+    })
+}
+
+/// Seconds from `90`, `90s`, `45m`, `1.5h` or `1:30:00`.
+fn parse_duration(s: &str) -> Result<u64> {
+    let invalid = || format!("Invalid duration {:?}; use seconds, 45m, 1h or 1:30:00", s);
+    if s.contains(':') {
+        let mut seconds = 0;
+        for part in s.split(':') {
+            seconds = seconds * 60 + part.parse::<u64>().with_context(invalid)?;
+        }
+        return Ok(seconds);
+    }
+    let (number, unit) = match s.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((i, _)) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let scale = match unit.to_lowercase().as_str() {
+        "s" | "sec" => 1.0,
+        "m" | "min" => 60.0,
+        "h" | "hr" => 3600.0,
+        _ => anyhow::bail!(invalid()),
+    };
+    let number: f64 = number.parse().with_context(invalid)?;
+    Ok((number * scale).round() as u64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) | Token::Op(w) => write!(f, "'{}'", w),
+            Token::Quoted(w) => write!(f, "\"{}\"", w),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    const OPERATOR: &str = "=!<>~";
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some(q) if q == c => break,
+                    Some(ch) => text.push(ch),
+                    None => anyhow::bail!("Unclosed quote in query"),
+                }
+            }
+            tokens.push(Token::Quoted(text));
+        } else if OPERATOR.contains(c) {
+            let mut op = String::new();
+            while let Some(&ch) = chars.peek().filter(|ch| OPERATOR.contains(**ch)) {
+                op.push(ch);
+                chars.next();
+            }
+            tokens.push(Token::Op(op));
+        } else {
+            let mut word = String::new();
+            while let Some(&ch) = chars
+                .peek()
+                .filter(|ch| !ch.is_whitespace() && !OPERATOR.contains(**ch) && **ch != '"')
+            {
+                word.push(ch);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(video_id: &str, upload_date: &str, duration: u64, words: usize) -> Entry {
+        Entry {
+            base: video_id.into(),
+            video_id: video_id.to_string(),
+            title: format!("Talk {}", video_id),
+            channel: "RustConf".to_string(),
+            platform: "YouTube".to_string(),
+            url: String::new(),
+            upload_date: upload_date.to_string(),
+            duration,
+            model: "base".to_string(),
+            language: Some("en".to_string()),
+            word_count: words,
+            formats: vec![OutputFormat::Json],
+            saved_at: 0,
+            topics: vec!["macros".to_string(), "traits".to_string()],
+        }
+    }
+
+    fn matches(query: &str, entry: &Entry) -> bool {
+        let query: Query = query.parse().unwrap();
+        query.conditions.iter().all(|c| match c {
+            Condition::Compare { field, op, value } => compare(entry, *field, *op, value),
+            Condition::Mentions(_) => true,
+        })
+    }
+
+    #[test]
+    fn parses_and_filters() {
+        let query: Query = "WHERE year=2024 and mentions 'rust macros' ORDER BY words desc LIMIT 5"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.conditions,
+            [
+                Condition::Compare {
+                    field: Field::Year,
+                    op: Op::Eq,
+                    value: Value::Number(2024)
+                },
+                Condition::Mentions("rust macros".to_string()),
+            ]
+        );
+        assert_eq!(query.order, Some((Field::Words, true)));
+        assert_eq!(query.limit, Some(5));
+
+        let talk = entry("a", "20240315", 2_700, 6_000);
+        assert!(matches("platform = youtube AND channel ~ conf", &talk));
+        assert!(matches("date >= 2024-01-01 AND date < 2024-06-01", &talk));
+        assert!(matches("duration > 30m AND duration <= 0:45:00", &talk));
+        assert!(matches("words >= 5000 AND topic = Macros", &talk));
+        assert!(!matches("year = 2023", &talk));
+        assert!(!matches("title CONTAINS \"keynote\"", &talk));
+        assert!(!matches("topic != macros", &talk));
+        // Local files have no upload date.
+        assert!(!matches("year < 3000", &entry("b", "", 60, 100)));
+
+        assert!("duration ~ 5".parse::<Query>().is_err());
+        assert!("speaker = me".parse::<Query>().is_err());
+        assert!("year = last".parse::<Query>().is_err());
+        assert!("title = \"open".parse::<Query>().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Redacting a time range out of stored transcripts.
+//!
+//! The JSON output keeps every segment's timing, so a time range maps to
+//! the segments it overlaps. Their text is replaced with [`REDACTED`] in the
+//! JSON segments and in the transcript of the txt and md files, and any
+//! srt / vtt files are rendered again from the redacted segments.
+//! Transcripts saved before segments were stored in the JSON can't be
+//! redacted by time.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use super::subtitles;
+use super::types::{ClipRange, Segment};
+use crate::utils::encryption;
+use crate::utils::process::{self, Access};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug)]
+pub struct Redaction {
+    /// Segments newly redacted; already redacted ones aren't counted.
+    pub segments: usize,
+    pub files: Vec<PathBuf>,
+}
+
+/// Redact `range` from the transcript `path` (any of its txt / json / md
+/// files) and its sibling formats.
+pub fn redact_range(path: &Path, range: &ClipRange) -> Result<Redaction> {
+    let json_path = path.with_extension("json");
+    let mut json: serde_json::Value = serde_json::from_str(
+        &encryption::read_to_string(&json_path)
+            .with_context(|| format!("No JSON output next to {}", path.display()))?,
+    )?;
+    let Some(segments) = json["segments"].as_array_mut() else {
+        anyhow::bail!(
+            "{} has no segment timings (it predates them); re-transcribe to redact by time",
+            json_path.display()
+        );
+    };
+
+    let mut removed = Vec::new();
+    for value in segments.iter_mut() {
+        let segment: Segment = serde_json::from_value(value.clone())?;
+        if range.contains(&segment) && segment.text != REDACTED {
+            removed.push(segment.text);
+            value["text"] = REDACTED.into();
+        }
+    }
+    if removed.is_empty() {
+        return Ok(Redaction {
+            segments: 0,
+            files: Vec::new(),
+        });
+    }
+
+    let transcript = json["transcript"].as_str().unwrap_or_default();
+    json["transcript"] = redact_text(transcript, &removed).into();
+    encryption::write(&json_path, serde_json::to_string_pretty(&json)?)?;
+    let mut files = vec![json_path];
+
+    let txt_path = path.with_extension("txt");
+    if txt_path.exists() {
+        let txt = encryption::read_to_string(&txt_path)?;
+        encryption::write(&txt_path, redact_text(&txt, &removed))?;
+        files.push(txt_path);
+    }
+
+    // Only touch the transcript section, not the title or metadata lines.
+    let md_path = path.with_extension("md");
+    if md_path.exists() {
+        let md = encryption::read_to_string(&md_path)?;
+        let body = md.find("## Transcript").unwrap_or(0);
+        let redacted = format!("{}{}", &md[..body], redact_text(&md[body..], &removed));
+        encryption::write(&md_path, redacted)?;
+        files.push(md_path);
+    }
+
+    let segments: Vec<Segment> = serde_json::from_value(json["segments"].clone())?;
+    for ext in ["srt", "vtt"] {
+        let subtitle_path = path.with_extension(ext);
+        if subtitle_path.exists() {
+            let body = if ext == "srt" {
+                subtitles::srt(&segments)
+            } else {
+                subtitles::vtt(&segments)
+            };
+            encryption::write(&subtitle_path, body)?;
+            files.push(subtitle_path);
+        }
+    }
+
+    info!("🔒 Redacted {} segment(s) ({})", removed.len(), range);
+    Ok(Redaction {
+        segments: removed.len(),
+        files,
+    })
+}
+
+/// Replace each of `pieces` (segment texts in time order) in `text`. Each
+/// is looked for after the previous one so repeated phrases elsewhere stay;
+/// pieces not found in order (multi-track transcripts list tracks one after
+/// another) fall back to their first occurrence.
+fn redact_text(text: &str, pieces: &[String]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut missed = Vec::new();
+    for piece in pieces.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        match rest.find(piece) {
+            Some(i) => {
+                out.push_str(&rest[..i]);
+                out.push_str(REDACTED);
+                rest = &rest[i + piece.len()..];
+            }
+            None => missed.push(piece),
+        }
+    }
+    out.push_str(rest);
+    for piece in missed {
+        out = out.replacen(piece, REDACTED, 1);
+    }
+    out
+}
+
+/// Silence `range` in an audio file in place, keeping its format.
+pub async fn mute_audio(path: &Path, range: &ClipRange) -> Result<()> {
+    let window = match range.end_time {
+        Some(end) => format!("between(t,{:.3},{:.3})", range.start(), end),
+        None => format!("gte(t,{:.3})", range.start()),
+    };
+    let path = &std::path::absolute(path)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("mp3");
+    let muted = path.with_extension(format!("muted.{}", ext));
+
+    let output = process::async_command(
+        "ffmpeg",
+        path.parent().unwrap_or(Path::new("/")),
+        Access::Local,
+    )?
+    .args(process::LOCAL_ONLY)
+    .arg("-i")
+    .arg(path)
+    .args(["-af", &format!("volume=enable='{}':volume=0", window), "-y"])
+    .arg(&muted)
+    .output()
+    .await
+    .context("Failed to run ffmpeg. Is it installed?")?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&muted);
+        anyhow::bail!(
+            "ffmpeg failed to mute audio: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    std::fs::rename(&muted, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    info!("🔇 Muted {} in {}", range, path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_overlapping_segments_in_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("vid-Title");
+        let segments = [
+            (0, 2_000, "Hello everyone."),
+            (2_000, 4_000, "My card number is 1234."),
+            (4_000, 6_000, "Hello everyone."),
+        ];
+        let json = serde_json::json!({
+            "transcript": "Hello everyone. My card number is 1234. Hello everyone.",
+            "segments": segments
+                .iter()
+                .map(|(s, e, t)| serde_json::json!({"start_ms": s, "end_ms": e, "text": t}))
+                .collect::<Vec<_>>(),
+        });
+        std::fs::write(base.with_extension("json"), json.to_string()).unwrap();
+        std::fs::write(
+            base.with_extension("txt"),
+            "Hello everyone. My card number is 1234. Hello everyone.",
+        )
+        .unwrap();
+        std::fs::write(
+            base.with_extension("md"),
+            "# Hello everyone.\n\n## Transcript\n\nHello everyone. My card number is 1234. Hello everyone.\n",
+        )
+        .unwrap();
+        std::fs::write(base.with_extension("srt"), "stale").unwrap();
+
+        let range = ClipRange::new(Some(2.5), Some(5.0)).unwrap();
+        let redaction = redact_range(&base.with_extension("txt"), &range).unwrap();
+        assert_eq!(redaction.segments, 2);
+        assert_eq!(redaction.files.len(), 4);
+
+        let txt = std::fs::read_to_string(base.with_extension("txt")).unwrap();
+        assert_eq!(txt, "Hello everyone. [REDACTED] [REDACTED]");
+        let md = std::fs::read_to_string(base.with_extension("md")).unwrap();
+        assert!(md.starts_with("# Hello everyone.\n"));
+        assert!(md.contains("Hello everyone. [REDACTED] [REDACTED]"));
+        let srt = std::fs::read_to_string(base.with_extension("srt")).unwrap();
+        assert!(srt.contains("00:00:02,000 --> 00:00:04,000\n[REDACTED]\n"));
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(base.with_extension("json")).unwrap())
+                .unwrap();
+        assert_eq!(json["segments"][1]["text"], REDACTED);
+        assert_eq!(json["segments"][0]["text"], "Hello everyone.");
+
+        // Running it again finds nothing new.
+        assert_eq!(redact_range(&base, &range).unwrap().segments, 0);
+    }
+}
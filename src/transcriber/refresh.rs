@@ -0,0 +1,197 @@
+//! Refreshing the video details of transcripts already saved, without
+//! transcribing again: titles change, channels get renamed, and
+//! transcripts saved before the platform reported an upload date can get
+//! one.
+//!
+//! Fresh metadata is compared field by field with what the JSON output
+//! recorded. Changed fields are written to the JSON `metadata`, the header
+//! of the Markdown output (the transcript below it is left alone) and the
+//! library index. File names keep the title they were saved under. Values
+//! yt-dlp didn't report (an empty date, `Unknown`, a zero duration) never
+//! replace known ones.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use super::store;
+use super::types::{OutputFormat, VideoMetadata};
+use crate::utils::encryption;
+
+/// Transcripts refreshed per call unless asked for more, as each costs a
+/// yt-dlp run.
+pub const DEFAULT_LIMIT: usize = 20;
+
+/// One field whose value changed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+/// What refreshing one transcript did.
+#[derive(Debug, Clone, Serialize)]
+pub struct Refreshed {
+    /// Output base path, relative to the output directory.
+    pub base: PathBuf,
+    pub video_id: String,
+    /// The title after the refresh.
+    pub title: String,
+    pub changes: Vec<FieldChange>,
+    /// Why the transcript couldn't be refreshed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The fields of `fresh` that differ from `saved` and are worth keeping.
+pub fn changes(saved: &VideoMetadata, fresh: &VideoMetadata) -> Vec<FieldChange> {
+    let known = |value: &str| !value.trim().is_empty() && value != "Unknown";
+    let fields = [
+        ("title", saved.title.clone(), fresh.title.clone()),
+        ("channel", saved.channel.clone(), fresh.channel.clone()),
+        (
+            "upload_date",
+            saved.upload_date.clone(),
+            fresh.upload_date.clone(),
+        ),
+        (
+            "duration",
+            saved.duration.to_string(),
+            fresh.duration.to_string(),
+        ),
+    ];
+    fields
+        .into_iter()
+        .filter(|(field, from, to)| from != to && known(to) && !(*field == "duration" && to == "0"))
+        .map(|(field, from, to)| FieldChange { field, from, to })
+        .collect()
+}
+
+/// `metadata` with `changes` applied.
+pub fn updated(metadata: &VideoMetadata, changes: &[FieldChange]) -> VideoMetadata {
+    let mut metadata = metadata.clone();
+    for change in changes {
+        match change.field {
+            "title" => metadata.title = change.to.clone(),
+            "channel" => metadata.channel = change.to.clone(),
+            "upload_date" => metadata.upload_date = change.to.clone(),
+            "duration" => metadata.duration = change.to.parse().unwrap_or(metadata.duration),
+            _ => {}
+        }
+    }
+    metadata
+}
+
+/// Write `metadata` into the JSON and Markdown outputs under `base`.
+pub fn rewrite(base: &Path, metadata: &VideoMetadata) -> Result<()> {
+    let json_path = store::file(base, OutputFormat::Json);
+    let mut json: serde_json::Value = serde_json::from_str(
+        &encryption::read_to_string(&json_path)
+            .with_context(|| format!("Can't read {}", json_path.display()))?,
+    )
+    .with_context(|| format!("{} isn't a transcript", json_path.display()))?;
+    json["metadata"]["title"] = metadata.title.clone().into();
+    json["metadata"]["channel"] = metadata.channel.clone().into();
+    json["metadata"]["upload_date"] = metadata.upload_date.clone().into();
+    json["metadata"]["duration"] = metadata.duration.into();
+    encryption::write(&json_path, serde_json::to_string_pretty(&json)?)?;
+
+    let md_path = store::file(base, OutputFormat::Md);
+    if md_path.exists() {
+        let md = encryption::read_to_string(&md_path)?;
+        encryption::write(&md_path, markdown_header(&md, metadata))?;
+    }
+    Ok(())
+}
+
+/// `md` with the title and detail lines above `## Transcript` showing
+/// `metadata`.
+fn markdown_header(md: &str, metadata: &VideoMetadata) -> String {
+    let body = md.find("## Transcript").unwrap_or(md.len());
+    let header: Vec<String> = md[..body]
+        .split('\n')
+        .map(|line| {
+            if line.starts_with("# ") {
+                format!("# {}", metadata.title)
+            } else if line.starts_with("**Channel:** ") {
+                format!("**Channel:** {}", metadata.channel)
+            } else if line.starts_with("**Duration:** ") {
+                format!("**Duration:** {}s", metadata.duration)
+            } else if line.starts_with("**Published:** ") {
+                format!("**Published:** {}", metadata.upload_date)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    format!("{}{}", header.join("\n"), &md[body..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(title: &str, channel: &str, upload_date: &str, duration: u64) -> VideoMetadata {
+        VideoMetadata {
+            video_id: "abc".to_string(),
+            title: title.to_string(),
+            channel: channel.to_string(),
+            duration,
+            upload_date: upload_date.to_string(),
+            platform: "YouTube".to_string(),
+            url: "https://www.youtube.com/watch?v=abc".to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        }
+    }
+
+    #[test]
+    fn keeps_only_reported_changes() {
+        let saved = metadata("Old title", "Old channel", "", 600);
+        let fresh = metadata("New title", "Unknown", "20240105", 0);
+        let changes = changes(&saved, &fresh);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["title", "upload_date"]);
+        let updated = updated(&saved, &changes);
+        assert_eq!(updated.title, "New title");
+        assert_eq!(updated.channel, "Old channel");
+        assert_eq!(updated.upload_date, "20240105");
+        assert_eq!(updated.duration, 600);
+        assert!(super::changes(&updated, &fresh).is_empty());
+    }
+
+    #[test]
+    fn rewrites_the_json_metadata_and_markdown_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("abc-Old title");
+        let json = serde_json::json!({
+            "metadata": metadata("Old title", "Old channel", "", 600),
+            "transcript": "# Not a heading",
+            "segments": [],
+            "model": "base",
+        });
+        std::fs::write(store::file(&base, OutputFormat::Json), json.to_string()).unwrap();
+        std::fs::write(
+            store::file(&base, OutputFormat::Md),
+            "# Old title\n\n**Channel:** Old channel\n**Duration:** 600s\n**Published:** \n\n\
+             ---\n\n## Transcript\n\n# Not a heading\n",
+        )
+        .unwrap();
+
+        let fresh = metadata("New title", "New channel", "20240105", 600);
+        rewrite(&base, &fresh).unwrap();
+        let stored = store::load(&base).unwrap();
+        assert_eq!(stored.metadata.title, "New title");
+        assert_eq!(stored.metadata.upload_date, "20240105");
+        assert_eq!(stored.transcript, "# Not a heading");
+        let md = std::fs::read_to_string(store::file(&base, OutputFormat::Md)).unwrap();
+        assert_eq!(
+            md,
+            "# New title\n\n**Channel:** New channel\n**Duration:** 600s\n**Published:** 20240105\n\n\
+             ---\n\n## Transcript\n\n# Not a heading\n"
+        );
+    }
+}
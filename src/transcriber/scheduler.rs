@@ -0,0 +1,268 @@
+//! Limits on the work running jobs do at once, from the config file's
+//! `workers` section, so a burst of requests can't exhaust the machine.
+//! Read when the server starts.
+//!
+//! `admission.max_concurrent_jobs` (see `mcp::queue`) bounds jobs from
+//! start to finish. Within those, downloads and Whisper runs have limits
+//! of their own: with four jobs, two downloads and one transcription, the
+//! network and the CPU both stay busy without four decoders contending.
+//!
+//! The model memory budget bounds what decoding jobs take together. Each
+//! reserves its model's share (`model_memory_mb`, else [`estimate_mb`] of
+//! the model file) and waits while the rest is taken, so five requests for
+//! large models don't decode side by side on a box with room for two.
+//! A model bigger than the whole budget runs alone. A remote worker uses
+//! none of this machine's memory and reserves nothing. Waiting is first
+//! come, first served, so a large model isn't starved by small ones.
+//!
+//! With `devices`, each local Whisper run also takes a slot on a device:
+//! the one the request's `device` names, else the first listed device
+//! with a free slot (a GPU for `gpu: true`, the CPU for `gpu: false`).
+//! Two GPUs and the CPU then decode three jobs side by side rather than
+//! queueing them all for the first GPU.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+use tracing::info;
+
+use super::types::Device;
+use crate::config::WorkerLimits;
+
+/// Memory whisper.cpp needs beyond a model's weights, for its decoding
+/// buffers.
+const DECODING_OVERHEAD_MB: u64 = 200;
+
+pub struct Scheduler {
+    downloads: Option<Semaphore>,
+    transcriptions: Option<Semaphore>,
+    /// One permit per MB of the budget, and the budget.
+    memory: Option<(Semaphore, u32)>,
+    model_memory_mb: HashMap<String, u32>,
+    devices: Option<Devices>,
+}
+
+/// The configured devices and their free slots.
+struct Devices {
+    devices: Vec<Device>,
+    free: Mutex<Vec<usize>>,
+    released: Notify,
+}
+
+/// A slot on a device, held until the Whisper run is done.
+pub struct DeviceSlot<'a> {
+    devices: &'a Devices,
+    index: usize,
+}
+
+impl DeviceSlot<'_> {
+    pub fn device(&self) -> Device {
+        self.devices.devices[self.index]
+    }
+}
+
+impl Drop for DeviceSlot<'_> {
+    fn drop(&mut self) {
+        self.devices.free.lock().unwrap_or_else(|e| e.into_inner())[self.index] += 1;
+        self.devices.released.notify_waiters();
+    }
+}
+
+/// What a Whisper run holds until it is done.
+pub struct Transcription<'a> {
+    _slot: Option<SemaphorePermit<'a>>,
+    _memory: Option<SemaphorePermit<'a>>,
+}
+
+impl Scheduler {
+    pub fn new(limits: &WorkerLimits) -> Self {
+        let slots = |limit: Option<usize>| limit.map(|n| Semaphore::new(n.max(1)));
+        Self {
+            downloads: slots(limits.max_concurrent_downloads),
+            transcriptions: slots(limits.max_concurrent_transcriptions),
+            memory: limits
+                .model_memory_budget_mb
+                .map(|mb| (Semaphore::new(mb as usize), mb)),
+            model_memory_mb: limits.model_memory_mb.clone(),
+            devices: (!limits.devices.is_empty()).then(|| Devices {
+                devices: limits.devices.iter().map(|d| d.device).collect(),
+                free: Mutex::new(limits.devices.iter().map(|d| d.jobs.max(1)).collect()),
+                released: Notify::new(),
+            }),
+        }
+    }
+
+    /// A slot on the `device` a request names, else on the first device
+    /// with one free, among the GPUs for `gpu: true` and the CPU for
+    /// `gpu: false`. `None` without `devices`, or when none of them fits
+    /// the request.
+    pub async fn device(
+        &self,
+        device: Option<Device>,
+        gpu: Option<bool>,
+    ) -> Option<DeviceSlot<'_>> {
+        let devices = self.devices.as_ref()?;
+        let fits = |candidate: &Device| match (device, gpu) {
+            (Some(device), _) => *candidate == device,
+            (None, Some(gpu)) => matches!(candidate, Device::Gpu(_)) == gpu,
+            (None, None) => true,
+        };
+        let candidates: Vec<usize> = (0..devices.devices.len())
+            .filter(|&i| fits(&devices.devices[i]))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        let mut waiting = false;
+        loop {
+            // Registered before looking, so a slot freed in between isn't
+            // missed.
+            let released = devices.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            {
+                let mut free = devices.free.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(&index) = candidates.iter().find(|&&i| free[i] > 0) {
+                    free[index] -= 1;
+                    return Some(DeviceSlot { devices, index });
+                }
+            }
+            if !waiting {
+                info!("⏳ Waiting for a free device...");
+                waiting = true;
+            }
+            released.await;
+        }
+    }
+
+    /// A download slot, held until dropped. `None` without a limit.
+    pub async fn download(&self) -> Option<SemaphorePermit<'_>> {
+        acquire(self.downloads.as_ref()?, 1, "a download slot").await
+    }
+
+    /// A transcription slot and, with a budget, `memory_mb` of it. Without
+    /// `memory_mb` (a remote worker, or a model file not there to measure)
+    /// only the slot is taken.
+    pub async fn transcription(&self, memory_mb: Option<u32>) -> Transcription<'_> {
+        let slot = match &self.transcriptions {
+            Some(slots) => acquire(slots, 1, "a transcription slot").await,
+            None => None,
+        };
+        let memory = match (&self.memory, memory_mb) {
+            (Some((budget, total)), Some(mb)) => {
+                acquire(budget, mb.clamp(1, *total), "model memory").await
+            }
+            _ => None,
+        };
+        Transcription {
+            _slot: slot,
+            _memory: memory,
+        }
+    }
+
+    /// Memory `model` takes: its `model_memory_mb` entry, else an estimate
+    /// from its file of `file_bytes`.
+    pub fn memory_mb(&self, model: &str, file_bytes: u64) -> u32 {
+        self.model_memory_mb
+            .get(model)
+            .copied()
+            .unwrap_or_else(|| estimate_mb(file_bytes))
+    }
+}
+
+/// Memory a model of `file_bytes` takes while decoding: its weights, about
+/// a third more for the context, and the decoding buffers. Close to the
+/// figures whisper.cpp publishes (about 3.9 GB for large, 390 MB for base).
+pub fn estimate_mb(file_bytes: u64) -> u32 {
+    let weights_mb = file_bytes / 1_000_000;
+    (weights_mb * 13 / 10 + DECODING_OVERHEAD_MB).min(u32::MAX as u64) as u32
+}
+
+/// `permits` of `semaphore`, saying so when they have to be waited for.
+async fn acquire<'a>(
+    semaphore: &'a Semaphore,
+    permits: u32,
+    what: &str,
+) -> Option<SemaphorePermit<'a>> {
+    if let Ok(permit) = semaphore.try_acquire_many(permits) {
+        return Some(permit);
+    }
+    info!("⏳ Waiting for {}...", what);
+    // The semaphore is never closed.
+    semaphore.acquire_many(permits).await.ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[test]
+    fn estimates_model_memory_from_the_file() {
+        // ggml-base.bin and ggml-large-v3.bin.
+        assert_eq!(estimate_mb(147_951_465), 391);
+        assert_eq!(estimate_mb(3_095_033_483), 4_223);
+        let scheduler = Scheduler::new(&WorkerLimits {
+            model_memory_mb: HashMap::from([("large-v3".to_string(), 3_500)]),
+            ..Default::default()
+        });
+        assert_eq!(scheduler.memory_mb("large-v3", 3_095_033_483), 3_500);
+        assert_eq!(scheduler.memory_mb("base", 147_951_465), 391);
+    }
+
+    #[tokio::test]
+    async fn decoding_waits_for_model_memory() {
+        let scheduler = Scheduler::new(&WorkerLimits {
+            model_memory_budget_mb: Some(5_000),
+            ..Default::default()
+        });
+        let large = scheduler.transcription(Some(4_000)).await;
+        let (budget, _) = scheduler.memory.as_ref().unwrap();
+        assert_eq!(budget.available_permits(), 1_000);
+        // Another large model doesn't fit beside it; a small one does.
+        let second = scheduler.transcription(Some(4_000));
+        assert!(timeout(Duration::from_millis(50), second).await.is_err());
+        let small = scheduler.transcription(Some(500)).await;
+        assert_eq!(budget.available_permits(), 500);
+        drop(large);
+        drop(small);
+        // Bigger than the whole budget: runs alone.
+        let huge = scheduler.transcription(Some(9_000)).await;
+        assert_eq!(budget.available_permits(), 0);
+        drop(huge);
+        // A remote worker takes none.
+        let _remote = scheduler.transcription(None).await;
+        assert_eq!(budget.available_permits(), 5_000);
+        assert!(scheduler.download().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn spreads_jobs_over_devices() {
+        let devices = ["gpu:0", "gpu:1", "cpu"].map(|device| crate::config::DeviceSlots {
+            device: device.parse().unwrap(),
+            jobs: 1,
+        });
+        let scheduler = Scheduler::new(&WorkerLimits {
+            devices: devices.to_vec(),
+            ..Default::default()
+        });
+        let first = scheduler.device(None, None).await.unwrap();
+        let second = scheduler.device(None, Some(true)).await.unwrap();
+        assert_eq!(
+            (first.device(), second.device()),
+            (Device::Gpu(0), Device::Gpu(1))
+        );
+        // Both GPUs are busy: a GPU job waits, a CPU job doesn't.
+        let gpu = scheduler.device(None, Some(true));
+        assert!(timeout(Duration::from_millis(50), gpu).await.is_err());
+        let cpu = scheduler.device(None, None).await.unwrap();
+        assert_eq!(cpu.device(), Device::Cpu);
+        drop(second);
+        let pinned = scheduler.device(Some(Device::Gpu(1)), None).await.unwrap();
+        assert_eq!(pinned.device(), Device::Gpu(1));
+        // A device that isn't listed runs outside the slots.
+        assert!(scheduler.device(Some(Device::Gpu(7)), None).await.is_none());
+        drop(first);
+    }
+}
@@ -0,0 +1,396 @@
+//! Finding a word or phrase in stored transcripts: in one, or across the
+//! library with facets on platform, channel and upload date.
+//!
+//! Matching is per segment and ignores case, so each hit carries the time
+//! its segment starts. A phrase split across two segments isn't found.
+//! Library-wide hits can be exported as a CSV or Markdown report with a
+//! link to each moment, for media monitoring.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::library::{Entry, Library};
+use super::links;
+use super::outputs::marker;
+use super::store;
+use super::types::{OutputFormat, Segment};
+use crate::utils::encryption;
+
+/// Subdirectory of the output dir holding exported search reports, kept
+/// out of the transcript listing like batch reports.
+pub const REPORTS_DIR: &str = "reports";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    /// Position of the segment in the transcript.
+    pub index: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// The segment's text, trimmed.
+    pub text: String,
+    pub track: Option<usize>,
+}
+
+/// Segments of `segments` containing `query`, in order.
+pub fn search(segments: &[Segment], query: &str) -> Vec<Hit> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    segments
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.text.to_lowercase().contains(&query))
+        .map(|(index, s)| Hit {
+            index,
+            start_ms: s.start_ms,
+            end_ms: s.end_ms,
+            text: s.text.trim().to_string(),
+            track: s.track,
+        })
+        .collect()
+}
+
+/// Which transcripts a library-wide search looks at. Unset facets match
+/// every transcript.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Facets {
+    /// Platform name, e.g. `YouTube`; ignores case.
+    pub platform: Option<String>,
+    /// Part of the channel name; ignores case.
+    pub channel: Option<String>,
+    /// Earliest upload date, `YYYYMMDD`, inclusive.
+    pub since: Option<String>,
+    /// Latest upload date, `YYYYMMDD`, inclusive.
+    pub until: Option<String>,
+}
+
+impl Facets {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let platform = self
+            .platform
+            .as_ref()
+            .is_none_or(|p| entry.platform.eq_ignore_ascii_case(p.trim()));
+        let channel = self.channel.as_ref().is_none_or(|c| {
+            entry
+                .channel
+                .to_lowercase()
+                .contains(&c.trim().to_lowercase())
+        });
+        // Transcripts without an upload date (local files) fall outside
+        // any date range.
+        let dated = self.since.is_none() && self.until.is_none() || !entry.upload_date.is_empty();
+        let since = self
+            .since
+            .as_ref()
+            .is_none_or(|d| entry.upload_date.as_str() >= d.as_str());
+        let until = self
+            .until
+            .as_ref()
+            .is_none_or(|d| entry.upload_date.as_str() <= d.as_str());
+        platform && channel && dated && since && until
+    }
+
+    /// The facets set, as `platform YouTube, since 20240101`.
+    pub fn describe(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            ("platform", &self.platform),
+            ("channel", &self.channel),
+            ("since", &self.since),
+            ("until", &self.until),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_ref().map(|v| format!("{} {}", name, v)))
+        .collect();
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+}
+
+/// A date facet, `YYYY-MM-DD` or `YYYYMMDD`, as `YYYYMMDD` like yt-dlp's
+/// upload dates.
+pub fn parse_date(s: &str) -> Result<String> {
+    let s = s.trim();
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y%m%d"))
+        .map(|date| date.format("%Y%m%d").to_string())
+        .with_context(|| format!("Invalid date {:?}; use YYYY-MM-DD", s))
+}
+
+/// A hit in one transcript of the library.
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub entry: Entry,
+    pub hit: Hit,
+}
+
+/// Hits for `query` in every transcript in `output_dir` that `facets`
+/// match: newest transcript first, in order within each. Transcripts
+/// without a JSON output have no segments to search and are skipped.
+pub fn search_library(output_dir: &Path, query: &str, facets: &Facets) -> Result<Vec<Match>> {
+    let entries = Library::open(output_dir)?.list(None)?;
+    let mut matches = Vec::new();
+    for entry in entries
+        .into_iter()
+        .filter(|e| e.formats.contains(&OutputFormat::Json) && facets.matches(e))
+    {
+        let Ok(stored) = store::load(&output_dir.join(&entry.base)) else {
+            continue;
+        };
+        for hit in search(&stored.segments, query) {
+            matches.push(Match {
+                entry: entry.clone(),
+                hit,
+            });
+        }
+    }
+    Ok(matches)
+}
+
+/// Format of an exported search report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Md,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Csv => "csv",
+            ReportFormat::Md => "md",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "md" | "markdown" => Ok(ReportFormat::Md),
+            other => anyhow::bail!("Unknown report format '{}'; use 'csv' or 'md'", other),
+        }
+    }
+}
+
+/// Write `matches` as a report under `<output_dir>/reports/`, named after
+/// the query and the time. Returns its path.
+pub fn export(
+    output_dir: &Path,
+    query: &str,
+    facets: &Facets,
+    matches: &[Match],
+    format: ReportFormat,
+) -> Result<PathBuf> {
+    let now = chrono::Local::now();
+    let slug: String = query
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .take(40)
+        .collect();
+    let path = output_dir.join(REPORTS_DIR).join(format!(
+        "search-{}-{}.{}",
+        slug.trim_matches('-'),
+        now.format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ));
+    let report = match format {
+        ReportFormat::Csv => render_csv(matches),
+        ReportFormat::Md => render_markdown(
+            query,
+            facets,
+            matches,
+            &now.format("%Y-%m-%d %H:%M").to_string(),
+        ),
+    };
+    std::fs::create_dir_all(output_dir.join(REPORTS_DIR))
+        .context("Failed to create the reports directory")?;
+    encryption::write(&path, report)?;
+    Ok(path)
+}
+
+/// Link to the hit's moment, else to the video.
+fn link(m: &Match) -> String {
+    links::at(&m.entry.metadata(), m.hit.start_ms).unwrap_or_else(|| m.entry.url.clone())
+}
+
+fn render_csv(matches: &[Match]) -> String {
+    let mut out =
+        String::from("video_id,title,channel,platform,upload_date,time,seconds,text,link\n");
+    for m in matches {
+        let fields = [
+            m.entry.video_id.clone(),
+            m.entry.title.clone(),
+            m.entry.channel.clone(),
+            m.entry.platform.clone(),
+            m.entry.upload_date.clone(),
+            marker(m.hit.start_ms),
+            (m.hit.start_ms / 1000).to_string(),
+            m.hit.text.clone(),
+            link(m),
+        ];
+        let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// `field` quoted when it holds a comma, quote or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn render_markdown(query: &str, facets: &Facets, matches: &[Match], exported_at: &str) -> String {
+    let transcripts = matches
+        .iter()
+        .enumerate()
+        .filter(|(i, m)| *i == 0 || matches[i - 1].entry.base != m.entry.base)
+        .count();
+    let mut out = format!("# Search: \"{}\"\n\n", query.trim());
+    let _ = writeln!(
+        out,
+        "- Filters: {}\n- Hits: {} in {} transcript(s)\n- Exported: {}",
+        facets.describe().unwrap_or_else(|| "none".to_string()),
+        matches.len(),
+        transcripts,
+        exported_at
+    );
+    let mut current = None;
+    for m in matches {
+        if current != Some(&m.entry.base) {
+            current = Some(&m.entry.base);
+            let _ = write!(
+                out,
+                "\n## {}\n\n{} ({}), uploaded {} · {}\n\n",
+                m.entry.title,
+                m.entry.channel,
+                m.entry.platform,
+                if m.entry.upload_date.is_empty() {
+                    "unknown"
+                } else {
+                    &m.entry.upload_date
+                },
+                m.entry.video_id
+            );
+        }
+        let time = marker(m.hit.start_ms);
+        let time = match links::at(&m.entry.metadata(), m.hit.start_ms) {
+            Some(url) => format!("[{}]({})", time, url),
+            None => time,
+        };
+        let _ = writeln!(out, "- {} {}", time, m.hit.text);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_segments_ignoring_case() {
+        let segment = |start_ms, text: &str| Segment {
+            start_ms,
+            end_ms: start_ms + 2_000,
+            text: format!(" {}", text),
+            ..Default::default()
+        };
+        let segments = [
+            segment(0, "Rust is fast."),
+            segment(2_000, "Memory safety matters."),
+            segment(4_000, "Did I mention RUST?"),
+        ];
+
+        let hits = search(&segments, "rust");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].start_ms, 0);
+        assert_eq!(hits[1].index, 2);
+        assert_eq!(hits[1].text, "Did I mention RUST?");
+        assert!(search(&segments, "  ").is_empty());
+        assert!(search(&segments, "safety matters. did").is_empty());
+    }
+
+    fn entry(video_id: &str, channel: &str, platform: &str, upload_date: &str) -> Entry {
+        Entry {
+            base: PathBuf::from(format!("{}-Talk", video_id)),
+            video_id: video_id.to_string(),
+            title: "Talk, part 1".to_string(),
+            channel: channel.to_string(),
+            platform: platform.to_string(),
+            url: format!("https://youtu.be/{}", video_id),
+            upload_date: upload_date.to_string(),
+            duration: 600,
+            model: "base".to_string(),
+            language: None,
+            word_count: 100,
+            formats: vec![OutputFormat::Json],
+            saved_at: 0,
+            topics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn filters_by_facets_and_renders_reports() {
+        let facets = Facets {
+            platform: Some("youtube".to_string()),
+            channel: Some("rust".to_string()),
+            since: Some(parse_date("2024-01-01").unwrap()),
+            until: Some(parse_date("20241231").unwrap()),
+        };
+        assert!(facets.matches(&entry("a", "The Rust Channel", "YouTube", "20240501")));
+        assert!(!facets.matches(&entry("b", "The Rust Channel", "Vimeo", "20240501")));
+        assert!(!facets.matches(&entry("c", "Go Talks", "YouTube", "20240501")));
+        assert!(!facets.matches(&entry("d", "The Rust Channel", "YouTube", "20250101")));
+        assert!(!facets.matches(&entry("e", "The Rust Channel", "YouTube", "")));
+        assert!(Facets::default().matches(&entry("e", "", "Local File", "")));
+        assert!(parse_date("2024-13-01").is_err());
+        assert_eq!(
+            facets.describe().unwrap(),
+            "platform youtube, channel rust, since 20240101, until 20241231"
+        );
+
+        let hit = |start_ms, text: &str| Hit {
+            index: 0,
+            start_ms,
+            end_ms: start_ms + 2_000,
+            text: text.to_string(),
+            track: None,
+        };
+        let a = entry("abc123", "The Rust Channel", "YouTube", "20240501");
+        let matches = [
+            Match {
+                entry: a.clone(),
+                hit: hit(65_000, "Rust, \"fast\" and safe"),
+            },
+            Match {
+                entry: a,
+                hit: hit(70_000, "More Rust."),
+            },
+        ];
+        assert_eq!(
+            render_csv(&matches).lines().nth(1).unwrap(),
+            "abc123,\"Talk, part 1\",The Rust Channel,YouTube,20240501,01:05,65,\
+             \"Rust, \"\"fast\"\" and safe\",https://www.youtube.com/watch?v=abc123&t=65s"
+        );
+        assert_eq!(
+            render_markdown("rust", &Facets::default(), &matches, "2026-01-01 12:00"),
+            "# Search: \"rust\"\n\n\
+             - Filters: none\n- Hits: 2 in 1 transcript(s)\n- Exported: 2026-01-01 12:00\n\n\
+             ## Talk, part 1\n\n\
+             The Rust Channel (YouTube), uploaded 20240501 · abc123\n\n\
+             - [01:05](https://www.youtube.com/watch?v=abc123&t=65s) Rust, \"fast\" and safe\n\
+             - [01:10](https://www.youtube.com/watch?v=abc123&t=70s) More Rust.\n"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Semantic search: finding passages by meaning rather than wording.
+//!
+//! Transcripts are cut into passages of about [`PASSAGE_MS`] and embedded
+//! through the OpenAI-compatible `/v1/embeddings` endpoint configured as
+//! `embeddings` (OpenAI, or a local Ollama, llama.cpp or LM Studio server).
+//! Vectors are kept in the library index per embedding model. Transcripts
+//! are embedded on the first search after they are saved, so only new
+//! transcripts cost a request. Queries are ranked by cosine similarity.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+use super::library::{Entry, Library};
+use super::store;
+use super::types::Segment;
+use crate::config::EmbeddingsConfig;
+
+/// Passages cover about this much of the video…
+const PASSAGE_MS: u64 = 45_000;
+/// …and at most this many characters, to stay well inside the context of
+/// small embedding models.
+const PASSAGE_MAX_CHARS: usize = 1_500;
+
+/// Texts sent per embeddings request.
+const BATCH_SIZE: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passage {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// A passage matching a query.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub entry: Entry,
+    pub passage: Passage,
+    /// Cosine similarity to the query, -1 to 1.
+    pub score: f32,
+}
+
+/// `segments` merged into passages, each ending at a segment boundary.
+pub fn passages(segments: &[Segment]) -> Vec<Passage> {
+    let mut passages: Vec<Passage> = Vec::new();
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match passages.last_mut() {
+            Some(p)
+                if segment.start_ms < p.start_ms + PASSAGE_MS
+                    && p.text.len() + text.len() < PASSAGE_MAX_CHARS =>
+            {
+                p.text.push(' ');
+                p.text.push_str(text);
+                p.end_ms = segment.end_ms;
+            }
+            _ => passages.push(Passage {
+                start_ms: segment.start_ms,
+                end_ms: segment.end_ms,
+                text: text.to_string(),
+            }),
+        }
+    }
+    passages
+}
+
+pub struct Embedder {
+    client: reqwest::Client,
+    config: EmbeddingsConfig,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<Embedding>,
+}
+
+#[derive(Deserialize)]
+struct Embedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl Embedder {
+    pub fn new(config: EmbeddingsConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()?;
+        let api_key = config
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("EMBEDDINGS_API_KEY").ok())
+            .filter(|key| !key.is_empty());
+        Ok(Self {
+            client,
+            config,
+            api_key,
+        })
+    }
+
+    pub fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// One vector per text, in order.
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH_SIZE) {
+            let mut request = self.client.post(&self.config.url).json(&serde_json::json!({
+                "model": self.config.model,
+                "input": batch,
+            }));
+            if let Some(key) = &self.api_key {
+                request = request.bearer_auth(key);
+            }
+            let resp = request
+                .send()
+                .await
+                .with_context(|| format!("Embeddings request to {} failed", self.config.url))?;
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().await.unwrap_or_default();
+                anyhow::bail!("Embeddings endpoint returned {}: {}", status, body);
+            }
+            let mut data = resp
+                .json::<EmbeddingsResponse>()
+                .await
+                .context("Failed to parse the embeddings response")?
+                .data;
+            if data.len() != batch.len() {
+                anyhow::bail!(
+                    "Embeddings endpoint returned {} vectors for {} texts",
+                    data.len(),
+                    batch.len()
+                );
+            }
+            data.sort_by_key(|e| e.index);
+            vectors.extend(data.into_iter().map(|e| e.embedding));
+        }
+        Ok(vectors)
+    }
+}
+
+/// The `limit` passages in `output_dir` closest in meaning to `query`,
+/// best first, optionally only from transcripts of `video_id`. Embeds
+/// transcripts that have no passages for the model yet.
+pub async fn search(
+    output_dir: &Path,
+    embedder: &Embedder,
+    query: &str,
+    video_id: Option<&str>,
+    limit: usize,
+) -> Result<Vec<Hit>> {
+    let model = embedder.model();
+    // One connection throughout: with encryption at rest the library only
+    // lives in memory.
+    let mut library = Library::open(output_dir)?;
+    let todo: Vec<(PathBuf, Vec<Passage>)> = library
+        .unembedded(model)?
+        .into_iter()
+        .filter_map(|entry| {
+            let stored = store::load(&output_dir.join(&entry.base)).ok()?;
+            Some((entry.base, passages(&stored.segments)))
+        })
+        .filter(|(_, passages)| !passages.is_empty())
+        .collect();
+    for (base, passages) in todo {
+        info!(
+            "🧭 Embedding {} passage(s) of {}",
+            passages.len(),
+            base.display()
+        );
+        let texts: Vec<String> = passages.iter().map(|p| p.text.clone()).collect();
+        let vectors = embedder.embed(&texts).await?;
+        let embedded: Vec<(Passage, Vec<f32>)> = passages.into_iter().zip(vectors).collect();
+        library.add_passages(&base, model, &embedded)?;
+    }
+
+    let query = embedder
+        .embed(&[query.to_string()])
+        .await?
+        .pop()
+        .context("No vector for the query")?;
+    let entries: Vec<Entry> = library
+        .list(None)?
+        .into_iter()
+        .filter(|e| video_id.is_none_or(|id| e.video_id == id))
+        .collect();
+    let mut hits: Vec<Hit> = library
+        .passages(model)?
+        .into_iter()
+        .filter_map(|(base, passage, vector)| {
+            let entry = entries.iter().find(|e| e.base == base)?;
+            Some(Hit {
+                entry: entry.clone(),
+                passage,
+                score: cosine(&query, &vector),
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Cosine similarity; 0 for vectors of different lengths (embedded with
+/// another model) or no length.
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms: start_ms + 10_000,
+            text: format!(" {}", text),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn cuts_passages_at_segment_boundaries() {
+        let segments = [
+            segment(0, "Intro."),
+            segment(10_000, "More intro."),
+            segment(20_000, ""),
+            segment(50_000, "Next topic."),
+        ];
+        assert_eq!(
+            passages(&segments),
+            [
+                Passage {
+                    start_ms: 0,
+                    end_ms: 20_000,
+                    text: "Intro. More intro.".to_string(),
+                },
+                Passage {
+                    start_ms: 50_000,
+                    end_ms: 60_000,
+                    text: "Next topic.".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn scores_by_angle() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-6);
+        assert_eq!(cosine(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -0,0 +1,168 @@
+//! The audio a URL job downloaded, kept until its transcript is written.
+//!
+//! Once downloaded, the audio is moved from the job's temp directory to
+//! `.stages/<key>/` in the output directory, beside a `stage.json` holding
+//! the video's metadata. If the job then fails, is cancelled or the server
+//! goes down, running the same URL again (same clip and audio format)
+//! skips yt-dlp and starts from that audio; a chunked decode also picks up
+//! its finished chunks (see [`super::checkpoint`]). The directory is
+//! removed once the outputs are saved.
+//!
+//! Audio isn't staged while encryption at rest is on, since it would sit in
+//! the output directory in plaintext; such jobs download again on a retry.
+//!
+//! The PCM Whisper decodes from the audio isn't kept: decoding takes a
+//! small fraction of the download's time, and the PCM of an hour of audio
+//! is about 230 MB.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use super::types::{AudioFormat, ClipRange, VideoMetadata};
+use crate::utils::encryption;
+
+/// Directory of the output directory holding stage directories. Hidden, so
+/// listings and searches pass it by.
+pub const STAGES_DIR: &str = ".stages";
+
+const STAGE_FILE: &str = "stage.json";
+
+/// What `stage.json` records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Stage {
+    url: String,
+    metadata: VideoMetadata,
+    /// File name of the audio in the stage directory.
+    audio: String,
+    /// Size of the audio, so a file cut short isn't taken for it.
+    audio_bytes: u64,
+}
+
+/// The stage directory of one URL, clip and audio format.
+pub struct Stages {
+    dir: PathBuf,
+}
+
+impl Stages {
+    pub fn new(output_dir: &Path, url: &str, clip: &ClipRange, audio_format: AudioFormat) -> Self {
+        let key = serde_json::json!([url, clip, audio_format]).to_string();
+        let hash = hex::encode(Sha256::digest(key.as_bytes()));
+        Self {
+            dir: output_dir.join(STAGES_DIR).join(&hash[..16]),
+        }
+    }
+
+    /// The metadata and audio an earlier run left, if the audio is all
+    /// there.
+    pub fn downloaded(&self) -> Option<(VideoMetadata, PathBuf)> {
+        let json = encryption::read_to_string(&self.dir.join(STAGE_FILE)).ok()?;
+        let stage: Stage = serde_json::from_str(&json).ok()?;
+        let audio = self.dir.join(&stage.audio);
+        let bytes = std::fs::metadata(&audio).ok()?.len();
+        (bytes == stage.audio_bytes).then_some((stage.metadata, audio))
+    }
+
+    /// Move the downloaded `audio` of `url` into the stage directory and
+    /// record it with `metadata`. Returns where the audio is now, which is
+    /// where it was when encryption at rest is on.
+    pub fn keep(&self, url: &str, metadata: &VideoMetadata, audio: &Path) -> Result<PathBuf> {
+        if encryption::EncryptionKey::from_env()?.is_some() {
+            return Ok(audio.to_path_buf());
+        }
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Can't create {}", self.dir.display()))?;
+        let name = audio
+            .file_name()
+            .context("Downloaded audio has no file name")?
+            .to_string_lossy()
+            .into_owned();
+        let kept = self.dir.join(&name);
+        // The temp directory may be on another file system; the copy left
+        // behind goes with it.
+        if std::fs::rename(audio, &kept).is_err() {
+            std::fs::copy(audio, &kept)
+                .with_context(|| format!("Can't copy the audio to {}", kept.display()))?;
+        }
+        // Written last, so a crash while copying leaves no record.
+        let stage = Stage {
+            url: url.to_string(),
+            metadata: metadata.clone(),
+            audio: name,
+            audio_bytes: std::fs::metadata(&kept)?.len(),
+        };
+        encryption::write(
+            &self.dir.join(STAGE_FILE),
+            serde_json::to_string_pretty(&stage)?,
+        )?;
+        Ok(kept)
+    }
+
+    /// Remove the stage directory, once the outputs are saved.
+    pub fn clear(&self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+        // Only removed when no other job has a stage.
+        if let Some(parent) = self.dir.parent() {
+            let _ = std::fs::remove_dir(parent);
+        }
+    }
+}
+
+/// Every stage directory under `output_dir`, for retention.
+pub fn dirs(output_dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(output_dir.join(STAGES_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumes_from_kept_audio() {
+        let output = tempfile::tempdir().unwrap();
+        let temp = tempfile::tempdir().unwrap();
+        let url = "https://www.youtube.com/watch?v=abc";
+        let clip = ClipRange::default();
+        let stages = Stages::new(output.path(), url, &clip, AudioFormat::Original);
+        assert!(stages.downloaded().is_none());
+
+        let audio = temp.path().join("abc.webm");
+        std::fs::write(&audio, "audio").unwrap();
+        let metadata: VideoMetadata = serde_json::from_value(serde_json::json!({
+            "video_id": "abc",
+            "title": "Talk",
+            "channel": "Conf",
+            "duration": 10_800,
+            "upload_date": "20240105",
+            "platform": "YouTube",
+            "url": url,
+        }))
+        .unwrap();
+        let kept = stages.keep(url, &metadata, &audio).unwrap();
+        assert!(!audio.exists());
+        assert_eq!(dirs(output.path()), [kept.parent().unwrap()]);
+
+        let again = Stages::new(output.path(), url, &clip, AudioFormat::Original);
+        let (resumed, path) = again.downloaded().unwrap();
+        assert_eq!((resumed.title.as_str(), path), ("Talk", kept.clone()));
+        // Another format is another download.
+        assert!(
+            Stages::new(output.path(), url, &clip, AudioFormat::Opus)
+                .downloaded()
+                .is_none()
+        );
+        // Nor is a file cut short taken for the audio.
+        std::fs::write(&kept, "aud").unwrap();
+        assert!(again.downloaded().is_none());
+
+        again.clear();
+        assert!(!output.path().join(STAGES_DIR).exists());
+    }
+}
@@ -0,0 +1,421 @@
+//! Transcripts already in the output directory.
+//!
+//! The files of one transcription share a base path,
+//! `<output_dir>/[<layout>/]<video_id>-<title>[.clip-<start>-<end>]`, and
+//! differ only in extension. Versions kept by `IfExists::Version` add `-2`,
+//! `-3`, … to the base. The JSON output records the model, so an existing
+//! transcript can only be matched when its JSON was written.
+//!
+//! Each output directory also keeps a cache index, `.index.json`, mapping
+//! a request (source URL or path, requested model and language, clip) to
+//! the transcript it produced. A repeat request is answered from it
+//! without any network access; `force` skips it. Entries are checked
+//! against the files on each hit, so deleting transcripts needs no index
+//! update.
+//!
+//! [`TranscriptStore`] is the lookup for applications embedding the
+//! engine: check it before calling `TranscriberEngine::transcribe`, as the
+//! MCP tools do with `if_exists: skip`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::engine::preview;
+use super::glossary::Change;
+use super::topics::{self, Topic};
+use super::types::{
+    ClipRange, DetectedLanguage, OutputFiles, OutputFormat, Segment, Suggestion,
+    TranscriptionOptions, TranscriptionResult, VideoMetadata, WhisperModel,
+    captions_label_language, model_label,
+};
+use super::usage::ResourceUsage;
+use crate::utils::encryption;
+use crate::utils::paths::transcript_files;
+
+/// Finished transcripts in one output directory, looked up by source and
+/// model.
+///
+/// ```no_run
+/// use video_transcriber_mcp::{TranscriptStore, WhisperModel};
+///
+/// let store = TranscriptStore::new("/data/transcripts");
+/// if let Some(result) = store.find("dQw4w9WgXcQ", WhisperModel::default()) {
+///     println!("{}", result.files.primary());
+/// }
+/// ```
+// For library users; the server binary doesn't construct one.
+#[allow(dead_code)]
+pub struct TranscriptStore {
+    output_dir: PathBuf,
+}
+
+#[allow(dead_code)]
+impl TranscriptStore {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+        }
+    }
+
+    /// The newest full (not clipped) transcript of `video_id` made by
+    /// `model`, including those in `output_layout` subdirectories, as a
+    /// result with `cached` set. Transcripts from a custom model file or
+    /// platform captions don't match a built-in model.
+    pub fn find(&self, video_id: &str, model: WhisperModel) -> Option<TranscriptionResult> {
+        let label = model_label(model, None);
+        let (base, stored) = transcript_files(&self.output_dir)
+            .into_iter()
+            .filter(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                name.starts_with(&format!("{}-", video_id))
+                    && name.ends_with(".json")
+                    && !name.contains(".clip-")
+            })
+            .filter_map(|path| {
+                let modified = path.metadata().and_then(|m| m.modified()).ok();
+                let base = path.with_extension("");
+                let stored = load(&base).ok()?;
+                (stored.metadata.video_id == video_id && stored.model == label)
+                    .then_some((modified, base, stored))
+            })
+            .max_by_key(|(modified, _, _)| *modified)
+            .map(|(_, base, stored)| (base, stored))?;
+        Some(stored.into_result(&base, model, None))
+    }
+}
+
+/// The JSON output of a transcription, read back.
+#[derive(Debug, Deserialize)]
+pub struct StoredTranscript {
+    pub metadata: VideoMetadata,
+    pub transcript: String,
+    pub segments: Vec<Segment>,
+    /// Model label, e.g. `base` or `platform captions (en)`.
+    pub model: String,
+    #[serde(default)]
+    pub detected_language: Option<DetectedLanguage>,
+    #[serde(default)]
+    pub clip: ClipRange,
+    #[serde(default)]
+    pub glossary_changes: Vec<Change>,
+    #[serde(default)]
+    pub topics: Vec<Topic>,
+    #[serde(default)]
+    pub speakers: Vec<String>,
+    #[serde(default)]
+    pub suggested: Option<Suggestion>,
+}
+
+impl StoredTranscript {
+    /// The saved topics, or for a transcript saved before topics were,
+    /// topics worked out from its segments.
+    pub fn topics_or_extract(&self) -> Vec<Topic> {
+        if !self.topics.is_empty() {
+            return self.topics.clone();
+        }
+        let language = self
+            .detected_language
+            .as_ref()
+            .map(|d| d.code.as_str())
+            .or(self.metadata.language.as_deref());
+        topics::extract(&self.segments, language)
+    }
+
+    /// A result for this transcript as found under `base`. Only what the
+    /// outputs record is filled in; nothing was measured.
+    pub fn into_result(
+        self,
+        base: &Path,
+        model: WhisperModel,
+        model_path: Option<String>,
+    ) -> TranscriptionResult {
+        TranscriptionResult {
+            success: true,
+            files: files(base),
+            word_count: self.transcript.split_whitespace().count(),
+            transcript_preview: preview(&self.transcript),
+            captions_language: captions_label_language(&self.model).map(str::to_string),
+            metadata: self.metadata,
+            transcript: self.transcript,
+            segments: self.segments,
+            model_used: model,
+            model_path,
+            profile: None,
+            gpu_used: None,
+            audio_tracks: Vec::new(),
+            transcribed_tracks: Vec::new(),
+            audio_quality: None,
+            non_speech: Vec::new(),
+            detected_language: self.detected_language,
+            usage: ResourceUsage::default(),
+            cached: true,
+            chapter_files: Vec::new(),
+            uploaded: OutputFiles::default(),
+            container: None,
+        }
+    }
+}
+
+/// The file for `format` under `base`. Not `with_extension`, which would
+/// cut a title or clip suffix at its last dot.
+pub fn file(base: &Path, format: OutputFormat) -> PathBuf {
+    let mut path = base.as_os_str().to_owned();
+    path.push(".");
+    path.push(format.as_str());
+    path.into()
+}
+
+/// Whether any output file exists under `base`.
+pub fn exists(base: &Path) -> bool {
+    OutputFormat::ALL.iter().any(|&f| file(base, f).exists())
+}
+
+fn version(base: &Path, n: u32) -> PathBuf {
+    if n <= 1 {
+        return base.to_path_buf();
+    }
+    let mut path = base.as_os_str().to_owned();
+    path.push(format!("-{}", n));
+    path.into()
+}
+
+/// Existing versions of `base`, oldest first.
+fn versions(base: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    (1..)
+        .map(|n| version(base, n))
+        .take_while(|path| exists(path))
+}
+
+/// The first version of `base` with no files yet.
+pub fn next_version(base: &Path) -> PathBuf {
+    (1..)
+        .map(|n| version(base, n))
+        .find(|path| !exists(path))
+        .expect("unbounded range")
+}
+
+/// The output files that exist under `base`.
+pub fn files(base: &Path) -> OutputFiles {
+    OutputFiles(
+        OutputFormat::ALL
+            .into_iter()
+            .map(|format| (format, file(base, format)))
+            .filter(|(_, path)| path.exists())
+            .map(|(format, path)| (format, path.to_string_lossy().into_owned()))
+            .collect(),
+    )
+}
+
+pub fn load(base: &Path) -> Result<StoredTranscript> {
+    let path = file(base, OutputFormat::Json);
+    let json = encryption::read_to_string(&path)
+        .with_context(|| format!("Can't read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("{} isn't a transcript", path.display()))
+}
+
+/// Save `suggestion` in the JSON output under `base`, replacing any
+/// earlier one.
+pub fn save_suggestion(base: &Path, suggestion: &Suggestion) -> Result<()> {
+    let path = file(base, OutputFormat::Json);
+    let mut json: serde_json::Value = serde_json::from_str(
+        &encryption::read_to_string(&path)
+            .with_context(|| format!("Can't read {}", path.display()))?,
+    )
+    .with_context(|| format!("{} isn't a transcript", path.display()))?;
+    json["suggested"] = serde_json::to_value(suggestion)?;
+    encryption::write(&path, serde_json::to_string_pretty(&json)?)
+}
+
+/// The newest version of `base` whose model label satisfies `model`, with
+/// the base path it was found under.
+pub fn find(base: &Path, model: impl Fn(&str) -> bool) -> Option<(PathBuf, StoredTranscript)> {
+    let found: Vec<PathBuf> = versions(base).collect();
+    found
+        .into_iter()
+        .rev()
+        .filter_map(|base| load(&base).ok().map(|stored| (base, stored)))
+        .find(|(_, stored)| model(&stored.model))
+}
+
+const INDEX_FILE: &str = ".index.json";
+
+/// Serializes read-modify-write of index files within the process.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// What makes two requests ask for the same transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheKey {
+    pub url: String,
+    /// Model as requested (a model file's name for `model_path`). `None`
+    /// when the request left it to the platform profile or the default.
+    pub model: Option<String>,
+    pub language: Option<String>,
+    #[serde(default)]
+    pub clip: ClipRange,
+}
+
+impl CacheKey {
+    pub fn new(options: &TranscriptionOptions) -> Self {
+        Self {
+            url: options.url.clone(),
+            model: match (&options.model_path, options.model) {
+                (Some(_), _) | (None, Some(_)) => Some(model_label(
+                    options.model.unwrap_or_default(),
+                    options.model_path.as_deref(),
+                )),
+                (None, None) => None,
+            },
+            language: options.language.clone(),
+            clip: options.clip,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    #[serde(flatten)]
+    key: CacheKey,
+    video_id: String,
+    /// Label of the model that made the transcript.
+    model_used: String,
+    /// Output base path, relative to the output directory.
+    base: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    entries: Vec<IndexEntry>,
+}
+
+impl Index {
+    fn load(output_dir: &Path) -> Index {
+        encryption::read_to_string(&output_dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Note in `output_dir`'s index that `key` produced the transcript of
+/// `video_id` at `base` with `model_used`, replacing what it produced
+/// before.
+pub fn remember(
+    output_dir: &Path,
+    key: CacheKey,
+    base: &Path,
+    video_id: &str,
+    model_used: &str,
+) -> Result<()> {
+    let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut index = Index::load(output_dir);
+    index.entries.retain(|entry| entry.key != key);
+    index.entries.push(IndexEntry {
+        key,
+        video_id: video_id.to_string(),
+        model_used: model_used.to_string(),
+        base: base.strip_prefix(output_dir).unwrap_or(base).to_path_buf(),
+    });
+    encryption::write(
+        &output_dir.join(INDEX_FILE),
+        serde_json::to_string_pretty(&index)?,
+    )
+}
+
+/// The transcript `key` produced in `output_dir`, if its files are still
+/// there unchanged in model and, for a local source, newer than the source.
+pub fn recall(output_dir: &Path, key: &CacheKey) -> Option<(PathBuf, StoredTranscript)> {
+    let entry = {
+        let _guard = INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        Index::load(output_dir)
+            .entries
+            .into_iter()
+            .find(|entry| entry.key == *key)?
+    };
+    let base = output_dir.join(&entry.base);
+    let stored = load(&base).ok()?;
+    if stored.model != entry.model_used || stored.metadata.video_id != entry.video_id {
+        return None;
+    }
+    let modified = |path: &Path| path.metadata().and_then(|m| m.modified()).ok();
+    if let (Some(source), Some(transcript)) = (
+        modified(Path::new(&key.url)),
+        modified(&file(&base, OutputFormat::Json)),
+    ) && source > transcript
+    {
+        return None;
+    }
+    Some((base, stored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_versions_by_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("abc123-My Talk v1.2");
+        let write = |base: &Path, model: &str| {
+            let json = serde_json::json!({
+                "metadata": {
+                    "video_id": "abc123", "title": "My Talk v1.2", "channel": "Chan",
+                    "duration": 90, "upload_date": "20240501", "platform": "YouTube",
+                    "url": "https://youtu.be/abc123"
+                },
+                "transcript": "Hello.",
+                "segments": [],
+                "model": model,
+            });
+            std::fs::write(file(base, OutputFormat::Json), json.to_string()).unwrap();
+            std::fs::write(file(base, OutputFormat::Txt), "Hello.").unwrap();
+        };
+
+        assert_eq!(next_version(&base), base);
+        write(&base, "base");
+        let second = next_version(&base);
+        assert_eq!(second, dir.path().join("abc123-My Talk v1.2-2"));
+        write(&second, "small");
+
+        let (found, stored) = find(&base, |m| m == "base").unwrap();
+        assert_eq!(
+            (found.as_path(), stored.model.as_str()),
+            (base.as_path(), "base")
+        );
+        assert_eq!(find(&base, |m| m == "small").unwrap().0, second);
+        assert!(find(&base, |m| m == "tiny").is_none());
+
+        let store = TranscriptStore::new(dir.path());
+        let small = "small".parse::<WhisperModel>().unwrap();
+        let result = store.find("abc123", small).unwrap();
+        assert!(result.cached);
+        assert_eq!(result.files.primary(), files(&second).primary());
+        assert!(store.find("abc12", small).is_none());
+
+        let key = CacheKey::new(&TranscriptionOptions {
+            url: "https://youtu.be/abc123".to_string(),
+            model: Some(small),
+            ..Default::default()
+        });
+        assert!(recall(dir.path(), &key).is_none());
+        remember(dir.path(), key.clone(), &second, "abc123", "small").unwrap();
+        assert_eq!(recall(dir.path(), &key).unwrap().0, second);
+        let other_language = CacheKey {
+            language: Some("de".to_string()),
+            ..key.clone()
+        };
+        assert!(recall(dir.path(), &other_language).is_none());
+        std::fs::remove_file(file(&second, OutputFormat::Json)).unwrap();
+        assert!(recall(dir.path(), &key).is_none());
+        assert_eq!(
+            files(&second).get(OutputFormat::Txt),
+            Some(
+                dir.path()
+                    .join("abc123-My Talk v1.2-2.txt")
+                    .to_str()
+                    .unwrap()
+            )
+        );
+    }
+}
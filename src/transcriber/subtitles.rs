@@ -0,0 +1,79 @@
+//! SRT and WebVTT rendering of transcript segments.
+
+use super::types::Segment;
+
+pub fn srt(segments: &[Segment]) -> String {
+    segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .enumerate()
+        .map(|(i, s)| srt_cue(i + 1, s))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cue number `index` (from 1) of an SRT file.
+pub fn srt_cue(index: usize, s: &Segment) -> String {
+    format!(
+        "{}\n{} --> {}\n{}\n",
+        index,
+        timestamp(s.start_ms, ','),
+        timestamp(s.end_ms, ','),
+        s.text.trim()
+    )
+}
+
+pub fn vtt(segments: &[Segment]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for s in segments.iter().filter(|s| !s.text.trim().is_empty()) {
+        out.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            timestamp(s.start_ms, '.'),
+            timestamp(s.end_ms, '.'),
+            s.text.trim()
+        ));
+    }
+    out
+}
+
+/// `hh:mm:ss,mmm` (SRT) or `hh:mm:ss.mmm` (WebVTT).
+fn timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_srt_and_vtt() {
+        let segment = |start_ms, end_ms, text: &str| Segment {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+            ..Default::default()
+        };
+        let segments = [
+            segment(0, 1_500, " Hello."),
+            segment(1_500, 2_000, " "),
+            segment(3_723_004, 3_725_000, "Bye."),
+        ];
+        assert_eq!(
+            srt(&segments),
+            "1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n\
+             2\n01:02:03,004 --> 01:02:05,000\nBye.\n"
+        );
+        assert_eq!(
+            vtt(&segments),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello.\n\n\
+             01:02:03.004 --> 01:02:05.000\nBye.\n"
+        );
+    }
+}
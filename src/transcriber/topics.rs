@@ -0,0 +1,266 @@
+//! Keywords and topics of a transcript, for browsing an archive by subject.
+//!
+//! Uses RAKE (Rapid Automatic Keyword Extraction): stopwords, filler words
+//! and punctuation split each segment into runs of words, and phrases of up
+//! to [`MAX_PHRASE_WORDS`] words within them are the candidates. A word
+//! scores its degree (the length of the runs it appears in) over its
+//! frequency, a phrase the sum of its words, weighted by how often the
+//! phrase comes up. Needs no corpus, so a transcript's topics don't change
+//! as the archive grows. The stopword list is English; other languages get
+//! no topics.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::types::Segment;
+
+/// Topics kept per transcript.
+pub const MAX_TOPICS: usize = 10;
+
+/// Longest phrase taken as a topic. Longer runs without a stopword are
+/// usually a missing full stop rather than a term.
+const MAX_PHRASE_WORDS: usize = 3;
+
+/// Separated by spaces.
+const STOPWORDS: &str = "a about above actually after again against ah all almost also always am \
+    an and another any anything are around as at back basically be because \
+    been before being below between both but by can can't come could did \
+    didn't do does doesn't doing don't done down during each either even \
+    ever every few first for from further get gets getting go goes going \
+    gonna good got gotta had has have having he he's her here here's hers \
+    him his how i i'd i'll i'm i've if in into is isn't it it's its just \
+    kind know let let's like little lot lots make makes many maybe me mean \
+    might mm more most much must my need never new next no nor not now of \
+    off oh ok okay on once one only or other our ours out over own pretty \
+    probably put quite rather really right said same say says see she she's \
+    should so some something sort still such sure take than that that's the \
+    their theirs them then there there's these they they'll they're they've \
+    thing things think this those though through to too two uh um under \
+    until up us use used very want wanna was wasn't way we we'll we're we've \
+    well were what what's when where which while who whole why will with \
+    won't would yeah yes yet you you'll you're you've your yours";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Topic {
+    /// The phrase as most often written in the transcript.
+    pub term: String,
+    /// RAKE score; higher is more central to the transcript.
+    pub score: f32,
+    pub mentions: usize,
+    /// Start of the first segment that mentions it.
+    pub first_ms: u64,
+}
+
+/// The transcript's main topics, best first. Empty for transcripts in a
+/// language other than English (`language` is a Whisper code; `None` is
+/// taken as English).
+pub fn extract(segments: &[Segment], language: Option<&str>) -> Vec<Topic> {
+    if language.is_some_and(|l| l != "en") {
+        return Vec::new();
+    }
+
+    #[derive(Default)]
+    struct Candidate {
+        words: usize,
+        mentions: usize,
+        first_ms: u64,
+        spellings: HashMap<String, usize>,
+    }
+    let mut candidates: HashMap<String, Candidate> = HashMap::new();
+    let mut words: HashMap<String, (usize, usize)> = HashMap::new();
+    for segment in segments {
+        for run in runs(&segment.text) {
+            let lower: Vec<String> = run.iter().map(|w| w.to_lowercase()).collect();
+            for word in &lower {
+                let (frequency, degree) = words.entry(word.clone()).or_default();
+                *frequency += 1;
+                *degree += run.len().min(MAX_PHRASE_WORDS);
+            }
+            for n in 1..=run.len().min(MAX_PHRASE_WORDS) {
+                for start in 0..=run.len() - n {
+                    if n == 1 && run[start].chars().count() < 3 {
+                        continue;
+                    }
+                    let candidate = candidates
+                        .entry(lower[start..start + n].join(" "))
+                        .or_insert_with(|| Candidate {
+                            words: n,
+                            first_ms: segment.start_ms,
+                            ..Default::default()
+                        });
+                    candidate.mentions += 1;
+                    *candidate
+                        .spellings
+                        .entry(run[start..start + n].join(" "))
+                        .or_default() += 1;
+                }
+            }
+        }
+    }
+
+    // One-off phrases are noise as long as anything comes up twice.
+    let min_mentions = if candidates.values().any(|c| c.mentions > 1) {
+        2
+    } else {
+        1
+    };
+    candidates.retain(|_, c| c.mentions >= min_mentions);
+    // "memory" is no topic of its own if it only ever comes up as part of
+    // "memory safety".
+    let mut within: HashMap<String, usize> = HashMap::new();
+    for (key, c) in &candidates {
+        let parts: Vec<&str> = key.split(' ').collect();
+        for n in 1..c.words {
+            for part in parts.windows(n) {
+                let most = within.entry(part.join(" ")).or_default();
+                *most = (*most).max(c.mentions);
+            }
+        }
+    }
+
+    let mut topics: Vec<Topic> = candidates
+        .into_iter()
+        .filter(|(key, c)| within.get(key).is_none_or(|&most| most < c.mentions))
+        .map(|(key, c)| {
+            let rake: f32 = key
+                .split(' ')
+                .map(|w| {
+                    let (frequency, degree) = words[w];
+                    degree as f32 / frequency as f32
+                })
+                .sum();
+            let score = rake * (1.0 + (c.mentions as f32).ln());
+            let term = c
+                .spellings
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .map(|(spelling, _)| spelling)
+                .unwrap_or(key);
+            Topic {
+                term,
+                score: (score * 100.0).round() / 100.0,
+                mentions: c.mentions,
+                first_ms: c.first_ms,
+            }
+        })
+        .collect();
+    topics.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.first_ms.cmp(&b.first_ms))
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    topics.truncate(MAX_TOPICS);
+    topics
+}
+
+/// Runs of words in `text` between stopwords and punctuation. Possessive
+/// `'s` is dropped.
+fn runs(text: &str) -> Vec<Vec<String>> {
+    let mut runs = Vec::new();
+    let mut run: Vec<String> = Vec::new();
+    let text = text.replace('’', "'");
+    for token in text.split_inclusive(|c: char| !is_word_char(c)) {
+        let word = token
+            .trim_end_matches(|c: char| !is_word_char(c))
+            .trim_matches(|c| c == '\'' || c == '-');
+        if !word.is_empty() {
+            let lower = word.to_lowercase();
+            if STOPWORDS.split(' ').any(|w| w == lower) || !word.chars().any(char::is_alphabetic) {
+                runs.extend((!run.is_empty()).then(|| std::mem::take(&mut run)));
+            } else {
+                let word = match lower.strip_suffix("'s") {
+                    Some(_) => &word[..word.len() - 2],
+                    None => word,
+                };
+                run.push(word.to_string());
+            }
+        }
+        if token.ends_with(|c: char| !is_word_char(c) && !c.is_whitespace()) {
+            runs.extend((!run.is_empty()).then(|| std::mem::take(&mut run)));
+        }
+    }
+    runs.extend((!run.is_empty()).then_some(run));
+    runs
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '\'' || c == '-'
+}
+
+/// How many transcripts each topic appears in, most common first.
+/// Topics are compared ignoring case.
+pub fn tally<'a>(lists: impl IntoIterator<Item = &'a [String]>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+    for list in lists {
+        for term in list {
+            counts
+                .entry(term.to_lowercase())
+                .or_insert_with(|| (term.clone(), 0))
+                .1 += 1;
+        }
+    }
+    let mut tally: Vec<(String, usize)> = counts.into_values().collect();
+    tally.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tally
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, text: &str) -> Segment {
+        Segment {
+            start_ms,
+            end_ms: start_ms + 5_000,
+            text: format!(" {}", text),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ranks_repeated_phrases() {
+        let segments = [
+            segment(0, "Today we talk about memory safety in Rust."),
+            segment(5_000, "Um, so the borrow checker, right?"),
+            segment(10_000, "The borrow checker enforces memory safety."),
+            segment(
+                15_000,
+                "Rust's borrow checker is strict, and memory safety is free.",
+            ),
+            segment(20_000, "I like Rust. Pizza is nice."),
+        ];
+        let topics = extract(&segments, Some("en"));
+        let terms: Vec<&str> = topics.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(terms, ["borrow checker", "memory safety", "Rust"]);
+        assert_eq!(topics[0].mentions, 3);
+        assert_eq!(topics[0].first_ms, 5_000);
+        assert_eq!(topics[1].first_ms, 0);
+
+        assert!(extract(&segments, Some("de")).is_empty());
+        assert!(extract(&[], None).is_empty());
+    }
+
+    #[test]
+    fn splits_at_stopwords_and_punctuation() {
+        assert_eq!(
+            runs("Yeah, it’s the Rust compiler — open-source; Cargo's build cache of many words"),
+            [
+                vec!["Rust", "compiler"],
+                vec!["open-source"],
+                vec!["Cargo", "build", "cache"],
+                vec!["words"]
+            ]
+        );
+    }
+
+    #[test]
+    fn tallies_topics_across_transcripts() {
+        let a = vec!["Rust".to_string(), "memory safety".to_string()];
+        let b = vec!["rust".to_string()];
+        assert_eq!(
+            tally([a.as_slice(), b.as_slice()]),
+            [("Rust".to_string(), 2), ("memory safety".to_string(), 1)]
+        );
+    }
+}
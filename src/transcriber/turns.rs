@@ -0,0 +1,168 @@
+//! Speaker turns: the transcript laid out as who said what, for interviews
+//! and conversations ([`SpeakerTurns`]).
+//!
+//! A multi-track source transcribed track by track has one speaker per
+//! track, numbered like the tracks; a stereo call recording split by
+//! channel has named speakers (Caller and Agent). Otherwise a tinydiarize
+//! model marks where the speaker changes but not who speaks, so turns
+//! alternate between Speaker 1 and Speaker 2, which fits a two-person
+//! interview.
+
+use super::outputs::marker;
+use super::types::{Segment, SpeakerTurns};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub speaker: usize,
+    pub start_ms: u64,
+    pub text: String,
+}
+
+/// `segments` merged into turns, or `None` when they carry no speaker
+/// information.
+pub fn turns(segments: &[Segment]) -> Option<Vec<Turn>> {
+    let by_track = segments.iter().any(|s| s.track.is_some());
+    if !by_track && !segments.iter().any(|s| s.speaker_turn) {
+        return None;
+    }
+    let mut turns: Vec<Turn> = Vec::new();
+    let mut alternating = 1;
+    for segment in segments {
+        let speaker = if by_track {
+            segment.track.unwrap_or(1)
+        } else {
+            alternating
+        };
+        if segment.speaker_turn {
+            alternating = 3 - alternating;
+        }
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match turns.last_mut() {
+            Some(turn) if turn.speaker == speaker => {
+                turn.text.push(' ');
+                turn.text.push_str(text);
+            }
+            _ => turns.push(Turn {
+                speaker,
+                start_ms: segment.start_ms,
+                text: text.to_string(),
+            }),
+        }
+    }
+    Some(turns)
+}
+
+/// The turns in `style`, one paragraph each, or `None` when `style` is off
+/// or the segments carry no speakers. Speaker n is called `names[n - 1]`
+/// where there is one. In Markdown, labels are bold and times link to the
+/// moment where `link` gives a URL.
+pub fn render(
+    style: SpeakerTurns,
+    segments: &[Segment],
+    names: &[String],
+    markdown: bool,
+    link: impl Fn(u64) -> Option<String>,
+) -> Option<String> {
+    if style == SpeakerTurns::Off {
+        return None;
+    }
+    let turns = turns(segments)?;
+    let interviewer = turns.first().map(|t| t.speaker);
+    let paragraphs: Vec<String> = turns
+        .iter()
+        .map(|turn| {
+            let label = match style {
+                SpeakerTurns::Qa if Some(turn.speaker) == interviewer => "Q".to_string(),
+                SpeakerTurns::Qa => "A".to_string(),
+                _ => {
+                    let time = marker(turn.start_ms);
+                    let time = match link(turn.start_ms) {
+                        Some(url) if markdown => format!("[{}]({})", time, url),
+                        _ => time,
+                    };
+                    match names.get(turn.speaker.wrapping_sub(1)) {
+                        Some(name) => format!("{} ({})", name, time),
+                        None => format!("Speaker {} ({})", turn.speaker, time),
+                    }
+                }
+            };
+            if markdown {
+                format!("**{}:** {}", label, turn.text)
+            } else {
+                format!("{}: {}", label, turn.text)
+            }
+        })
+        .collect();
+    Some(paragraphs.join("\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_ms: u64, text: &str, track: Option<usize>, speaker_turn: bool) -> Segment {
+        Segment {
+            start_ms,
+            end_ms: start_ms + 2_000,
+            text: format!(" {}", text),
+            track,
+            speaker_turn,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn alternates_at_marked_turns() {
+        let segments = [
+            segment(0, "So, how did it start?", None, true),
+            segment(2_000, "By accident.", None, false),
+            segment(4_000, "Really.", None, true),
+            segment(6_000, "Why?", None, false),
+        ];
+        assert_eq!(
+            render(SpeakerTurns::Qa, &segments, &[], false, |_| None).unwrap(),
+            "Q: So, how did it start?\n\nA: By accident. Really.\n\nQ: Why?"
+        );
+        assert_eq!(
+            render(SpeakerTurns::Speaker, &segments, &[], true, |ms| Some(
+                format!("https://youtu.be/x?t={}", ms / 1000)
+            ))
+            .unwrap(),
+            "**Speaker 1 ([00:00](https://youtu.be/x?t=0)):** So, how did it start?\n\n\
+             **Speaker 2 ([00:02](https://youtu.be/x?t=2)):** By accident. Really.\n\n\
+             **Speaker 1 ([00:06](https://youtu.be/x?t=6)):** Why?"
+        );
+        assert_eq!(
+            render(SpeakerTurns::Off, &segments, &[], false, |_| None),
+            None
+        );
+    }
+
+    #[test]
+    fn takes_speakers_from_tracks() {
+        let segments = [
+            segment(0, "Welcome.", Some(2), false),
+            segment(1_000, "Thanks.", Some(1), false),
+            segment(3_000, "Glad to be here.", Some(1), false),
+        ];
+        assert_eq!(
+            render(SpeakerTurns::Speaker, &segments, &[], false, |_| None).unwrap(),
+            "Speaker 2 (00:00): Welcome.\n\nSpeaker 1 (00:01): Thanks. Glad to be here."
+        );
+        let names = ["Caller".to_string(), "Agent".to_string()];
+        assert_eq!(
+            render(SpeakerTurns::Speaker, &segments, &names, false, |_| None).unwrap(),
+            "Agent (00:00): Welcome.\n\nCaller (00:01): Thanks. Glad to be here."
+        );
+        assert_eq!(
+            render(SpeakerTurns::Qa, &segments, &[], false, |_| None).unwrap(),
+            "Q: Welcome.\n\nA: Thanks. Glad to be here."
+        );
+
+        let plain = [segment(0, "Hello.", None, false)];
+        assert_eq!(render(SpeakerTurns::Qa, &plain, &[], false, |_| None), None);
+    }
+}
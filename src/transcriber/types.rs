@@ -1,52 +1,527 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum WhisperModel {
+use super::classify::AudioRegion;
+use super::downloader::{Network, cookies_args, proxy_url};
+use super::progress::Progress;
+use super::quality::AudioQuality;
+use super::usage::ResourceUsage;
+
+/// Model size / family. Each maps to a `ggml-<name>.bin` file from the
+/// whisper.cpp model repository.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ModelSize {
     Tiny,
+    #[default]
     Base,
     Small,
     Medium,
     Large,
+    LargeV2,
+    LargeV3,
+    LargeV3Turbo,
+    TinyEn,
+    BaseEn,
+    SmallEn,
+    MediumEn,
+}
+
+impl ModelSize {
+    pub const ALL: [ModelSize; 12] = [
+        ModelSize::Tiny,
+        ModelSize::Base,
+        ModelSize::Small,
+        ModelSize::Medium,
+        ModelSize::Large,
+        ModelSize::LargeV2,
+        ModelSize::LargeV3,
+        ModelSize::LargeV3Turbo,
+        ModelSize::TinyEn,
+        ModelSize::BaseEn,
+        ModelSize::SmallEn,
+        ModelSize::MediumEn,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelSize::Tiny => "tiny",
+            ModelSize::Base => "base",
+            ModelSize::Small => "small",
+            ModelSize::Medium => "medium",
+            // `ggml-large.bin` is upstream's original large (v1) checkpoint.
+            ModelSize::Large => "large",
+            ModelSize::LargeV2 => "large-v2",
+            ModelSize::LargeV3 => "large-v3",
+            ModelSize::LargeV3Turbo => "large-v3-turbo",
+            ModelSize::TinyEn => "tiny.en",
+            ModelSize::BaseEn => "base.en",
+            ModelSize::SmallEn => "small.en",
+            ModelSize::MediumEn => "medium.en",
+        }
+    }
+
+    /// The large family, several times slower than `medium` per hour of
+    /// audio.
+    pub fn is_large(&self) -> bool {
+        matches!(
+            self,
+            ModelSize::Large | ModelSize::LargeV2 | ModelSize::LargeV3 | ModelSize::LargeV3Turbo
+        )
+    }
+
+    /// English-only (`.en`) checkpoints: smaller and more accurate on
+    /// English speech, but they can't transcribe other languages.
+    pub fn is_english_only(&self) -> bool {
+        matches!(
+            self,
+            ModelSize::TinyEn | ModelSize::BaseEn | ModelSize::SmallEn | ModelSize::MediumEn
+        )
+    }
+}
+
+/// Quantized ggml weights (`ggml-medium-q5_0.bin`, …). Much smaller in RAM
+/// and noticeably faster on CPU-only machines, for a small accuracy cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quantization {
+    Q5_0,
+    Q5_1,
+    Q8_0,
+}
+
+impl Quantization {
+    pub const ALL: [Quantization; 3] = [Quantization::Q5_0, Quantization::Q5_1, Quantization::Q8_0];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Quantization::Q5_0 => "q5_0",
+            Quantization::Q5_1 => "q5_1",
+            Quantization::Q8_0 => "q8_0",
+        }
+    }
+}
+
+/// A Whisper model: a size plus optional quantization. Parsed from and
+/// displayed as the ggml file stem, e.g. `base` or `medium-q5_0`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WhisperModel {
+    pub size: ModelSize,
+    pub quantization: Option<Quantization>,
+}
+
+impl From<ModelSize> for WhisperModel {
+    fn from(size: ModelSize) -> Self {
+        Self {
+            size,
+            quantization: None,
+        }
+    }
 }
 
 impl FromStr for WhisperModel {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "tiny" => Ok(WhisperModel::Tiny),
-            "base" => Ok(WhisperModel::Base),
-            "small" => Ok(WhisperModel::Small),
-            "medium" => Ok(WhisperModel::Medium),
-            "large" => Ok(WhisperModel::Large),
-            _ => Err(anyhow::anyhow!("Invalid whisper model: {}", s)),
+        let lower = s.trim().to_lowercase();
+        // A trailing `-q5_0` etc. selects the quantized file; anything else
+        // after a dash is part of the size name.
+        let (size, quantization) = match lower.rsplit_once('-') {
+            Some((size, q)) => match Quantization::ALL.iter().find(|x| x.as_str() == q) {
+                Some(q) => (size, Some(*q)),
+                None => (lower.as_str(), None),
+            },
+            None => (lower.as_str(), None),
+        };
+        let size = ModelSize::ALL
+            .iter()
+            .find(|m| m.as_str() == size)
+            .ok_or_else(|| anyhow::anyhow!("Invalid whisper model: {}", s))?;
+        Ok(Self {
+            size: *size,
+            quantization,
+        })
+    }
+}
+
+impl TryFrom<String> for WhisperModel {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<WhisperModel> for String {
+    fn from(model: WhisperModel) -> Self {
+        model.to_string()
+    }
+}
+
+impl std::fmt::Display for WhisperModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.size.as_str())?;
+        if let Some(q) = self.quantization {
+            write!(f, "-{}", q.as_str())?;
         }
+        Ok(())
     }
 }
 
 impl WhisperModel {
-    pub fn as_str(&self) -> &str {
+    /// Every selectable model name: each size, unquantized and quantized.
+    pub fn all() -> Vec<WhisperModel> {
+        ModelSize::ALL
+            .iter()
+            .flat_map(|&size| {
+                std::iter::once(None)
+                    .chain(Quantization::ALL.iter().copied().map(Some))
+                    .map(move |quantization| WhisperModel { size, quantization })
+            })
+            .collect()
+    }
+
+    pub fn model_filename(&self) -> String {
+        format!("ggml-{}.bin", self)
+    }
+}
+
+/// Where local whisper runs: `cpu`, or a GPU of the build's backend by
+/// index. Parsed from `cpu`, `gpu:1` or the backend's name (`cuda:0`,
+/// `vulkan:1`, `metal`), where no index means the first GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Device {
+    Cpu,
+    Gpu(u32),
+}
+
+impl FromStr for Device {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        let (name, index) = match lower.split_once(':') {
+            Some((name, index)) => (name, Some(index)),
+            None => (lower.as_str(), None),
+        };
+        if name == "cpu" && index.is_none() {
+            return Ok(Device::Cpu);
+        }
+        if !matches!(name, "gpu" | "cuda" | "metal" | "vulkan") {
+            anyhow::bail!("Invalid device: {} (expected cpu, gpu:N or cuda:N)", s);
+        }
+        if name != "gpu"
+            && let Some(backend) = super::whisper::gpu_backend()
+            && !backend.eq_ignore_ascii_case(name)
+        {
+            anyhow::bail!("Device {} isn't available: this build uses {}", s, backend);
+        }
+        let index = match index {
+            Some(index) => index
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid device: {} (bad GPU index)", s))?,
+            None => 0,
+        };
+        Ok(Device::Gpu(index))
+    }
+}
+
+impl TryFrom<String> for Device {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Device> for String {
+    fn from(device: Device) -> Self {
+        device.to_string()
+    }
+}
+
+impl std::fmt::Display for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            WhisperModel::Tiny => "tiny",
-            WhisperModel::Base => "base",
-            WhisperModel::Small => "small",
-            WhisperModel::Medium => "medium",
-            WhisperModel::Large => "large",
+            Device::Cpu => f.write_str("cpu"),
+            Device::Gpu(index) => write!(f, "gpu:{}", index),
         }
     }
+}
 
-    pub fn model_filename(&self) -> String {
-        format!("ggml-{}.bin", self.as_str())
+/// Which audio track(s) of a multi-track local file to transcribe, e.g. an
+/// OBS recording with the mic and desktop audio on separate tracks.
+/// Track numbers are 1-based, counting audio streams only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioTrackSelection {
+    Track(usize),
+    /// Transcribe every track separately and label each in the output.
+    All,
+    /// Transcribe the left and right channels of a two-channel call
+    /// recording separately, as the [`STEREO_SPEAKERS`].
+    Stereo,
+}
+
+/// Speakers on the left and right channels with
+/// [`AudioTrackSelection::Stereo`], as most call recorders wire them.
+pub const STEREO_SPEAKERS: [&str; 2] = ["Caller", "Agent"];
+
+impl FromStr for AudioTrackSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        if s.eq_ignore_ascii_case("stereo") {
+            return Ok(Self::Stereo);
+        }
+        match s.parse::<usize>() {
+            Ok(n) if n >= 1 => Ok(Self::Track(n)),
+            _ => anyhow::bail!(
+                "Invalid audio track: {} (expected a track number from 1, 'all' or 'stereo')",
+                s
+            ),
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+/// One audio stream of a local media file, as reported by ffprobe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioTrack {
+    /// 1-based position among the file's audio streams.
+    pub number: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Audio channels in the stream, when ffprobe reports them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u32>,
+    /// ffprobe's codec name, e.g. `aac` or `opus`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    /// Hz.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+}
+
+impl AudioTrack {
+    /// Human-readable label, e.g. `Track 2 (Desktop Audio)`.
+    pub fn label(&self) -> String {
+        match self.title.as_deref().or(self.language.as_deref()) {
+            Some(name) => format!("Track {} ({})", self.number, name),
+            None => format!("Track {}", self.number),
+        }
+    }
+
+    /// What ffprobe said about the stream, e.g. `aac, 44.1 kHz, 2 channels`;
+    /// empty when it said nothing.
+    pub fn details(&self) -> String {
+        let mut details: Vec<String> = self.codec.iter().cloned().collect();
+        if let Some(rate) = self.sample_rate {
+            details.push(format!("{} kHz", rate as f64 / 1000.0));
+        }
+        match self.channels {
+            Some(1) => details.push("1 channel".to_string()),
+            Some(n) => details.push(format!("{} channels", n)),
+            None => {}
+        }
+        details.join(", ")
+    }
+}
+
+/// whisper.cpp decoder tuning. Every field left `None` keeps the default:
+/// greedy decoding with a single candidate, which is fast but can be shaky
+/// on noisy audio.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct DecodingOptions {
+    /// Beam search width. Above 1 switches from greedy to beam search —
+    /// more accurate, roughly proportionally slower.
+    #[serde(default)]
+    pub beam_size: Option<u32>,
+    /// Candidates sampled per temperature fallback step in greedy mode.
+    #[serde(default)]
+    pub best_of: Option<u32>,
+    /// Initial sampling temperature (0 = deterministic).
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Decoder output entropy above which a segment is re-decoded at a
+    /// higher temperature (whisper.cpp default 2.4). Lower values retry
+    /// repetitive output more eagerly.
+    #[serde(default)]
+    pub entropy_threshold: Option<f32>,
+    /// Probability above which a window is treated as silence (default
+    /// 0.6).
+    #[serde(default)]
+    pub no_speech_threshold: Option<f32>,
+    /// Regular expression each segment's text must follow (see
+    /// [`super::grammar`]), for audio with a known shape: codes, numbers,
+    /// a command vocabulary.
+    #[serde(default)]
+    pub grammar: Option<String>,
+    /// Mark where the speaker changes. Only tinydiarize models (such as
+    /// `ggml-small.en-tdrz.bin`) emit the marks.
+    #[serde(default)]
+    pub speaker_turns: bool,
+    /// Up to this many alternative readings for each low-confidence word,
+    /// from decoding its segment again with beam search and at rising
+    /// temperatures (see [`super::whisper`]). Slower on unclear audio.
+    #[serde(default)]
+    pub alternatives: Option<u32>,
+    /// Initial prompt biasing the decoder towards the request's hotwords
+    /// (see [`super::hotwords`]). Set by the engine.
+    #[serde(skip)]
+    pub prompt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct TranscriptionOptions {
     pub url: String,
     pub output_dir: String,
-    pub model: WhisperModel,
+    /// `None` takes the model from the platform profile, falling back to
+    /// `WhisperModel::default()` (base).
+    pub model: Option<WhisperModel>,
+    /// A ggml model file outside the built-in set (fine-tuned, distil-whisper,
+    /// …): absolute, or relative to the models directory. Takes precedence
+    /// over `model`. Local whisper only.
+    pub model_path: Option<String>,
     pub language: Option<String>,
+    /// Split local audio longer than this many minutes into chunks that are
+    /// transcribed in parallel. `None` decodes in a single pass.
+    pub chunk_minutes: Option<u32>,
+    /// Run whisper on the GPU when the build has a GPU backend. `None`
+    /// follows the server default (`WHISPER_USE_GPU`, on by default).
+    pub gpu: Option<bool>,
+    /// Device to run local whisper on, overriding `gpu`. `None` takes a
+    /// free one of the config file's `workers.devices`, else follows `gpu`.
+    pub device: Option<Device>,
+    /// Audio track(s) to transcribe from a multi-track local file. `None`
+    /// takes the first track. Ignored for URLs, which yt-dlp reduces to a
+    /// single audio stream.
+    pub audio_track: Option<AudioTrackSelection>,
+    /// ffmpeg `-af` filter chain applied while decoding audio for local
+    /// whisper, from the request's `audio_filters` (presets already
+    /// expanded) or else a channel's audio profile. Not applied by the
+    /// remote worker.
+    pub audio_filter: Option<String>,
+    /// Abort before transcribing when the audio quality score (0–100) is
+    /// below this. `None` only warns.
+    pub min_audio_quality: Option<u8>,
+    /// Silence detected music / applause before decoding and drop
+    /// "[Music]"-style segments. `None` keeps everything.
+    pub skip_non_speech: Option<bool>,
+    /// Cut long silences out of the audio before decoding, with timestamps
+    /// mapped back to the source (see [`super::vad`]). `None` keeps them.
+    pub skip_silence: Option<bool>,
+    /// Decoder tuning for local whisper; the remote worker ignores it.
+    pub decoding: DecodingOptions,
+    /// For URLs, use the platform's captions when they exist and skip the
+    /// audio download and whisper entirely.
+    pub prefer_captions: Option<bool>,
+    /// Free-form note on the basis for recording and transcribing (consent
+    /// obtained, source, purpose). Saved in the metadata and outputs.
+    pub source_note: Option<String>,
+    /// Transcribe only this part of the media. Segment timestamps stay on
+    /// the original timeline.
+    pub clip: ClipRange,
+    /// Podcast feed details for an episode's audio URL, used in place of
+    /// the metadata the URL alone gives.
+    pub episode: Option<Episode>,
+    /// Netscape-format cookies file handed to yt-dlp (`--cookies`), for
+    /// age-restricted, members-only or private videos. Wins over
+    /// `cookies_from_browser`, the config file and the environment.
+    pub cookies_file: Option<String>,
+    /// Browser to read cookies from (`--cookies-from-browser`), e.g.
+    /// `firefox` or `chrome:Profile 1`.
+    pub cookies_from_browser: Option<String>,
+    /// HTTP or SOCKS proxy for downloads (yt-dlp and direct links). Wins
+    /// over the config file and `DOWNLOAD_PROXY`.
+    pub proxy: Option<String>,
+    /// Files to write. Empty means [`OutputFormat::DEFAULT`].
+    pub formats: Vec<OutputFormat>,
+    /// Policy for outputs left by an earlier transcription of the same
+    /// source.
+    pub if_exists: IfExists,
+    /// Transcribe even when the cache index has a result for this request.
+    pub force: bool,
+    /// Timestamp markers in the Markdown output.
+    pub md_timestamps: MdTimestamps,
+    /// Speaker-turn layout of the txt and Markdown outputs.
+    pub speaker_turns: SpeakerTurns,
+    /// Misrecognition → canonical spelling replacements for every output,
+    /// on top of the config file's `glossary` (same keys are overridden).
+    pub glossary: BTreeMap<String, String>,
+    /// Names and terms to expect, such as "Kubernetes": they bias local
+    /// whisper and are restored in the outputs (see [`super::hotwords`]).
+    /// Empty takes the platform profile's.
+    pub hotwords: Vec<String>,
+    /// What to do when auto-detection is unsure of the language, from the
+    /// config file's `language_detection`. `None` takes the best guess.
+    pub language_fallback: Option<LanguageFallback>,
+    /// Append segments to the txt and srt outputs as they are decoded
+    /// (local whisper only; see [`super::live`]).
+    pub live_output: bool,
+    /// Where to report how far the transcription has got (see
+    /// [`super::progress`]).
+    pub progress: Option<Progress>,
+    /// For URLs, pipe the download through ffmpeg straight into whisper
+    /// instead of saving the audio first (local whisper only; see
+    /// [`super::whisper::WhisperTranscriber::transcribe_stream`]).
+    pub stream_audio: bool,
+    /// For a live stream, start from its beginning (yt-dlp
+    /// `--live-from-start`) rather than from now.
+    pub live_from_start: bool,
+    /// Stop capturing a live stream after this long, else
+    /// [`super::downloader::DEFAULT_MAX_LIVE_MINUTES`].
+    pub max_live_minutes: Option<u32>,
+    /// Also write one transcript per chapter (see [`super::chapters`]).
+    pub split_chapters: bool,
+    /// Where to POST the outcome, else the config file's `webhook.url`
+    /// (see [`super::webhook`]).
+    pub webhook_url: Option<String>,
+}
+
+/// Handling of low-confidence language detection (local whisper only).
+#[derive(Debug, Clone, Deserialize)]
+pub struct LanguageFallback {
+    /// Detections below this confidence (0–1) count as unsure.
+    pub min_confidence: f32,
+    /// When unsure, detect again on audio from the middle of the file and
+    /// keep the more confident result. Default: on.
+    #[serde(default = "default_retry_detection")]
+    pub retry: bool,
+    /// Language to transcribe in when still unsure. `None` keeps the guess.
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+fn default_retry_detection() -> bool {
+    true
+}
+
+impl LanguageFallback {
+    pub fn is_unsure(&self, detected: &DetectedLanguage) -> bool {
+        detected
+            .probability
+            .is_some_and(|p| p < self.min_confidence)
+    }
+}
+
+impl TranscriptionOptions {
+    /// Cookies and proxy for this request, falling back to the config file
+    /// and environment.
+    pub fn network(&self) -> Network {
+        Network {
+            cookies: cookies_args(
+                self.cookies_file.as_deref(),
+                self.cookies_from_browser.as_deref(),
+            ),
+            proxy: proxy_url(self.proxy.as_deref()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,20 +533,334 @@ pub struct VideoMetadata {
     pub upload_date: String,
     pub platform: String,
     pub url: String,
+    /// Spoken language as reported by the platform, when it says.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// The request's `source_note`, e.g. how consent was obtained.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_note: Option<String>,
+    /// Chapters as the platform lists them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<Chapter>,
+    /// Live when transcribed: the transcript covers what was captured, and
+    /// `duration` is its length.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub live: bool,
 }
 
-#[derive(Debug, Clone)]
-pub struct OutputFiles {
-    pub txt: String,
-    pub json: String,
-    pub md: String,
+/// A titled part of a video.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Publishing metadata suggested by an LLM from the transcript, for
+/// reposting the content: kept in the JSON output as `suggested`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub title: String,
+    /// One paragraph.
+    pub description: String,
+    pub tags: Vec<String>,
+    /// The model that wrote it, e.g. `gpt-4o via the client`.
+    pub model: String,
+}
+
+/// A transcript file a request can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Txt,
+    Json,
+    Md,
+    Srt,
+    Vtt,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 5] = [
+        OutputFormat::Txt,
+        OutputFormat::Json,
+        OutputFormat::Md,
+        OutputFormat::Srt,
+        OutputFormat::Vtt,
+    ];
+
+    /// What a request that doesn't choose gets.
+    pub const DEFAULT: [OutputFormat; 3] =
+        [OutputFormat::Txt, OutputFormat::Json, OutputFormat::Md];
+
+    /// Also the file extension.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Txt => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Md => "md",
+            OutputFormat::Srt => "srt",
+            OutputFormat::Vtt => "vtt",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|f| f.as_str() == lower)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid output format: {} (expected txt, json, md, srt or vtt)",
+                    s
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Paths of the files a transcription wrote, by format (or where the
+/// output sink put them, e.g. URLs).
+#[derive(Debug, Clone, Default)]
+pub struct OutputFiles(pub BTreeMap<OutputFormat, String>);
+
+impl OutputFiles {
+    pub fn get(&self, format: OutputFormat) -> Option<&str> {
+        self.0.get(&format).map(|s| s.as_str())
+    }
+
+    /// The file to point a reader at: the plain-text transcript if it was
+    /// written, else the first format that was.
+    pub fn primary(&self) -> &str {
+        self.get(OutputFormat::Txt)
+            .or_else(|| self.0.values().next().map(|s| s.as_str()))
+            .unwrap_or_default()
+    }
+}
+
+/// What to do when the output files a transcription would write already
+/// exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IfExists {
+    /// Replace them.
+    #[default]
+    Overwrite,
+    /// Return the existing transcript if the same model made it, without
+    /// transcribing again; otherwise write a new version.
+    Skip,
+    /// Keep them and write `<name>-2`, `<name>-3`, ….
+    Version,
+}
+
+impl FromStr for IfExists {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "overwrite" => Ok(Self::Overwrite),
+            "skip" => Ok(Self::Skip),
+            "version" => Ok(Self::Version),
+            _ => anyhow::bail!(
+                "Invalid if_exists: {} (expected overwrite, skip or version)",
+                s
+            ),
+        }
+    }
+}
+
+/// `[MM:SS]` markers in the Markdown transcript, pointing readers at the
+/// moment in the source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MdTimestamps {
+    /// The transcript as one block of text.
+    #[default]
+    Off,
+    /// A marker at the start of each paragraph. Paragraphs break at pauses
+    /// of two seconds or more, and after a minute.
+    Paragraph,
+    /// Every segment on its own line, with its marker.
+    Segment,
+}
+
+impl FromStr for MdTimestamps {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "paragraph" => Ok(Self::Paragraph),
+            "segment" => Ok(Self::Segment),
+            _ => anyhow::bail!(
+                "Invalid md_timestamps: {} (expected off, paragraph or segment)",
+                s
+            ),
+        }
+    }
+}
+
+/// Speaker-turn layout of the txt and Markdown transcripts, for interviews
+/// and conversations. Speakers come from the audio tracks of a multi-track
+/// source transcribed with `audio_track: all`, else from the turns a
+/// tinydiarize model marks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeakerTurns {
+    /// Running text.
+    #[default]
+    Off,
+    /// Each turn as `Speaker 1 (MM:SS): …`.
+    Speaker,
+    /// The first speaker's turns as `Q: …`, everyone else's as `A: …`.
+    Qa,
+}
+
+impl FromStr for SpeakerTurns {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "speaker" => Ok(Self::Speaker),
+            "qa" => Ok(Self::Qa),
+            _ => anyhow::bail!("Invalid speaker_turns: {} (expected off, speaker or qa)", s),
+        }
+    }
+}
+
+/// Codec downloaded and extracted audio is stored in. Whisper, clip
+/// extraction and redaction decode all of them, so a cached file can be
+/// reused whatever it was saved as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// The source's own audio stream, not re-encoded: whisper's ffmpeg
+    /// decode is then the only conversion. Downloads keep yt-dlp's best
+    /// audio in its container; extracted streams are copied into Matroska
+    /// audio (`.mka`), or written as WAV where a filter has to re-encode.
+    #[default]
+    Original,
+    Mp3,
+    /// Speech-grade Opus at 24 kbit/s, roughly a tenth of the MP3's size.
+    Opus,
+    /// Uncompressed 16-bit PCM, for archives that must stay lossless from
+    /// here on.
+    Wav,
+}
+
+impl AudioFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Original => "mka",
+            Self::Mp3 => "mp3",
+            Self::Opus => "opus",
+            Self::Wav => "wav",
+        }
+    }
+
+    /// ffmpeg output options encoding to this format.
+    pub fn ffmpeg_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Original => &["-acodec", "copy"],
+            Self::Mp3 => &["-acodec", "libmp3lame", "-q:a", "2"],
+            Self::Opus => &["-acodec", "libopus", "-b:a", "24k", "-application", "voip"],
+            Self::Wav => &["-acodec", "pcm_s16le"],
+        }
+    }
+
+    /// yt-dlp options picking the audio to extract (`-x`) and its
+    /// `--audio-format` / `--audio-quality`.
+    pub fn yt_dlp_args(self) -> &'static [&'static str] {
+        match self {
+            // Left to `-x`, the best audio stream is kept as it is.
+            Self::Original => &["-f", "bestaudio/best"],
+            // yt-dlp's default VBR quality.
+            Self::Mp3 => &["--audio-format", "mp3", "--audio-quality", "5"],
+            Self::Opus => &["--audio-format", "opus", "--audio-quality", "24K"],
+            Self::Wav => &["--audio-format", "wav"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Segment {
     pub start_ms: u64,
     pub end_ms: u64,
     pub text: String,
+    /// Audio track number the segment came from, set only when the source
+    /// has more than one audio track.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<usize>,
+    /// The next segment is by another speaker, as marked by a tinydiarize
+    /// model.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub speaker_turn: bool,
+    /// Words whisper was unsure of, when alternatives were asked for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uncertain_words: Vec<UncertainWord>,
+}
+
+/// A low-confidence word and what other decodes of its segment heard
+/// instead (`alternatives` in [`DecodingOptions`]).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UncertainWord {
+    pub word: String,
+    /// Lowest probability among the word's tokens, 0–1.
+    pub probability: f32,
+    /// Other readings, most often heard first. Empty when every decode
+    /// agreed.
+    pub alternatives: Vec<String>,
+}
+
+/// Language whisper identified when the request left it on auto.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code, e.g. `es`.
+    pub code: String,
+    /// Detection confidence, 0–1. `None` when the model can't detect
+    /// (English-only models) or the remote worker didn't say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probability: Option<f32>,
+    /// Set when detection stayed below the configured confidence and the
+    /// fallback language was used instead: the language detection guessed,
+    /// at `probability`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_from: Option<String>,
+}
+
+impl DetectedLanguage {
+    /// `code` was detected rather than chosen by the fallback.
+    pub fn is_detected(&self) -> bool {
+        self.fallback_from.is_none()
+    }
+}
+
+impl std::fmt::Display for DetectedLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.fallback_from, self.probability) {
+            (Some(guess), Some(p)) => write!(
+                f,
+                "{} (fallback; detection guessed {} at {:.0}%)",
+                self.code,
+                guess,
+                p * 100.0
+            ),
+            (Some(guess), None) => {
+                write!(f, "{} (fallback; detection guessed {})", self.code, guess)
+            }
+            (None, Some(p)) => write!(f, "{} ({:.0}%)", self.code, p * 100.0),
+            (None, None) => f.write_str(&self.code),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -86,4 +875,372 @@ pub struct TranscriptionResult {
     pub transcript_preview: String,
     pub word_count: usize,
     pub model_used: WhisperModel,
+    /// Custom model file used instead of `model_used`, if any.
+    pub model_path: Option<String>,
+    /// Name of the platform profile that supplied defaults, if any.
+    pub profile: Option<String>,
+    /// Whether whisper ran on a GPU. `None` when a remote worker did the
+    /// transcription.
+    pub gpu_used: Option<bool>,
+    /// Audio tracks found in a local source file (empty for URLs).
+    pub audio_tracks: Vec<AudioTrack>,
+    /// Numbers of the tracks that were transcribed, when the source has
+    /// more than one.
+    pub transcribed_tracks: Vec<usize>,
+    /// Signal checks on the decoded audio (worst track when several were
+    /// transcribed). `None` when a remote worker transcribed.
+    pub audio_quality: Option<AudioQuality>,
+    /// Music / applause stretches found in the audio, whether or not they
+    /// were skipped.
+    pub non_speech: Vec<AudioRegion>,
+    /// Set when the language was auto-detected rather than requested.
+    pub detected_language: Option<DetectedLanguage>,
+    /// Language of the platform captions used instead of whisper, if any.
+    pub captions_language: Option<String>,
+    pub usage: ResourceUsage,
+    /// An existing transcript returned instead of transcribing again,
+    /// from the cache index or `IfExists::Skip`. Only the fields the stored
+    /// outputs hold are set.
+    pub cached: bool,
+    /// The per-chapter transcripts `split_chapters` wrote, in order.
+    pub chapter_files: Vec<OutputFiles>,
+    /// Where copies of `files` were uploaded, e.g. S3 object URLs; empty
+    /// unless the output sink keeps local files and uploads copies.
+    pub uploaded: OutputFiles,
+    /// Container format of a local source file, as ffprobe names it.
+    pub container: Option<String>,
+}
+
+impl TranscriptionResult {
+    /// Name of the model that produced the transcript, for display.
+    pub fn model_label(&self) -> String {
+        match &self.captions_language {
+            Some(lang) => captions_label(lang),
+            None => model_label(self.model_used, self.model_path.as_deref()),
+        }
+    }
+}
+
+/// Stands in for the model name when platform captions were used.
+pub fn captions_label(language: &str) -> String {
+    format!("platform captions ({})", language)
+}
+
+/// The language of a [`captions_label`], or `None` for a model name.
+pub fn captions_label_language(label: &str) -> Option<&str> {
+    label
+        .strip_prefix("platform captions (")
+        .and_then(|rest| rest.strip_suffix(')'))
+}
+
+/// Part of the media to transcribe, in seconds from the start. Either end
+/// may be open. Accepts seconds (`90`, `90.5`) or `[hh:]mm:ss[.ms]` strings
+/// when deserialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClipRange {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_time",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub start_time: Option<f64>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_time",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub end_time: Option<f64>,
+}
+
+impl ClipRange {
+    pub fn new(start_time: Option<f64>, end_time: Option<f64>) -> anyhow::Result<Self> {
+        let clip = Self {
+            start_time: start_time.filter(|&s| s > 0.0),
+            end_time,
+        };
+        if let Some(start) = start_time
+            && start < 0.0
+        {
+            anyhow::bail!("start_time must not be negative");
+        }
+        if let Some(end) = end_time
+            && end <= clip.start()
+        {
+            anyhow::bail!("end_time must be after start_time");
+        }
+        Ok(clip)
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.start_time.is_some() || self.end_time.is_some()
+    }
+
+    pub fn start(&self) -> f64 {
+        self.start_time.unwrap_or(0.0)
+    }
+
+    /// Offset of the clip in the original media, for shifting the segments
+    /// of clipped audio back onto the original timeline.
+    pub fn start_ms(&self) -> u64 {
+        (self.start() * 1000.0).round() as u64
+    }
+
+    /// ffmpeg input options seeking to the start, placed before `-i`.
+    pub fn ffmpeg_input_args(&self) -> Vec<String> {
+        match self.start_time {
+            Some(start) => vec!["-ss".to_string(), format!("{:.3}", start)],
+            None => Vec::new(),
+        }
+    }
+
+    /// ffmpeg output options limiting the length. After an input seek the
+    /// output starts at 0, so this is a duration rather than `-to`.
+    pub fn ffmpeg_output_args(&self) -> Vec<String> {
+        match self.end_time {
+            Some(end) => vec!["-t".to_string(), format!("{:.3}", end - self.start())],
+            None => Vec::new(),
+        }
+    }
+
+    /// yt-dlp `--download-sections` value, e.g. `*600-900` or `*600-inf`.
+    pub fn download_section(&self) -> String {
+        match self.end_time {
+            Some(end) => format!("*{}-{}", self.start(), end),
+            None => format!("*{}-inf", self.start()),
+        }
+    }
+
+    pub fn contains(&self, segment: &Segment) -> bool {
+        let start_ms = self.start_ms();
+        let end_ms = self.end_time.map(|e| (e * 1000.0).round() as u64);
+        segment.end_ms > start_ms && end_ms.is_none_or(|end| segment.start_ms < end)
+    }
+}
+
+impl std::fmt::Display for ClipRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let end = self
+            .end_time
+            .map(format_clock)
+            .unwrap_or_else(|| "end".to_string());
+        write!(f, "{} – {}", format_clock(self.start()), end)
+    }
+}
+
+fn format_clock(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// `90`, `90.5`, `01:30` or `1:01:30.5` → seconds.
+pub fn parse_time(s: &str) -> anyhow::Result<f64> {
+    let s = s.trim();
+    let mut secs = 0.0;
+    for (i, part) in s.split(':').enumerate() {
+        if i > 2 {
+            anyhow::bail!("Invalid time: {} (expected seconds or [hh:]mm:ss)", s);
+        }
+        let value: f64 = part
+            .parse()
+            .ok()
+            .filter(|v: &f64| v.is_finite() && *v >= 0.0)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Invalid time: {} (expected seconds or [hh:]mm:ss)", s)
+            })?;
+        secs = secs * 60.0 + value;
+    }
+    Ok(secs)
+}
+
+fn deserialize_time<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<serde_json::Value>::deserialize(deserializer)? {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Number(n)) => Ok(n.as_f64()),
+        Some(serde_json::Value::String(s)) => {
+            parse_time(&s).map(Some).map_err(serde::de::Error::custom)
+        }
+        Some(other) => Err(serde::de::Error::custom(format!(
+            "Invalid time: {} (expected seconds or [hh:]mm:ss)",
+            other
+        ))),
+    }
+}
+
+/// An episode from a podcast feed (see `downloader::parse_feed`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Episode {
+    /// The feed's title.
+    pub show: String,
+    pub title: String,
+    /// `<guid>` (RSS) or `<id>` (Atom), else the audio URL.
+    pub guid: String,
+    /// `pubDate` / `published` as the feed wrote it.
+    pub published: Option<String>,
+    pub audio_url: String,
+    /// `itunes:duration`, in seconds.
+    pub duration: Option<u64>,
+}
+
+impl Episode {
+    /// Fill in what the audio URL alone can't tell. The id is derived from
+    /// the guid, since enclosure file names are often generic.
+    pub fn apply(&self, metadata: &mut VideoMetadata) {
+        use sha2::{Digest, Sha256};
+        let hash = hex::encode(Sha256::digest(self.guid.as_bytes()));
+        metadata.video_id = format!("ep-{}", &hash[..12]);
+        metadata.title = self.title.clone();
+        metadata.channel = self.show.clone();
+        if let Some(date) = self.published.as_deref().and_then(parse_feed_date) {
+            metadata.upload_date = date;
+        }
+        if metadata.duration == 0 {
+            metadata.duration = self.duration.unwrap_or(0);
+        }
+    }
+}
+
+/// RFC 2822 (RSS) or RFC 3339 (Atom) date as `YYYYMMDD`, the form yt-dlp
+/// reports upload dates in.
+fn parse_feed_date(date: &str) -> Option<String> {
+    let date = date.trim();
+    chrono::DateTime::parse_from_rfc2822(date)
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(date))
+        .ok()
+        .map(|d| d.format("%Y%m%d").to_string())
+}
+
+/// `medium-q5_0`, or the file name of a custom model.
+pub fn model_label(model: WhisperModel, model_path: Option<&str>) -> String {
+    match model_path {
+        Some(path) => std::path::Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.to_string()),
+        None => model.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quantized_model_names() {
+        let model: WhisperModel = "Medium-Q5_0".parse().unwrap();
+        assert_eq!(model.size, ModelSize::Medium);
+        assert_eq!(model.quantization, Some(Quantization::Q5_0));
+        assert_eq!(model.model_filename(), "ggml-medium-q5_0.bin");
+        assert_eq!(
+            "base".parse::<WhisperModel>().unwrap(),
+            ModelSize::Base.into()
+        );
+        assert!("base-q4_0".parse::<WhisperModel>().is_err());
+        let turbo: WhisperModel = "large-v3-turbo-q8_0".parse().unwrap();
+        assert_eq!(turbo.size, ModelSize::LargeV3Turbo);
+        assert_eq!(turbo.quantization, Some(Quantization::Q8_0));
+        assert_eq!(
+            WhisperModel::from(ModelSize::LargeV3).model_filename(),
+            "ggml-large-v3.bin"
+        );
+        let en: WhisperModel = "small.en-q5_1".parse().unwrap();
+        assert_eq!(en.size, ModelSize::SmallEn);
+        assert_eq!(en.model_filename(), "ggml-small.en-q5_1.bin");
+        assert!("large.en".parse::<WhisperModel>().is_err());
+        assert!("huge".parse::<WhisperModel>().is_err());
+    }
+
+    #[test]
+    fn parses_devices() {
+        assert_eq!("CPU".parse::<Device>().unwrap(), Device::Cpu);
+        assert_eq!("gpu:1".parse::<Device>().unwrap(), Device::Gpu(1));
+        assert_eq!("gpu".parse::<Device>().unwrap().to_string(), "gpu:0");
+        assert!("cpu:0".parse::<Device>().is_err());
+        assert!("gpu:x".parse::<Device>().is_err());
+        assert!("tpu:0".parse::<Device>().is_err());
+        // A backend's own name works only for that backend.
+        let cuda = "cuda:1".parse::<Device>();
+        match super::super::whisper::gpu_backend() {
+            Some("CUDA") | None => assert_eq!(cuda.unwrap(), Device::Gpu(1)),
+            Some(_) => assert!(cuda.is_err()),
+        }
+    }
+
+    #[test]
+    fn parses_audio_track_selection() {
+        assert_eq!(
+            "2".parse::<AudioTrackSelection>().unwrap(),
+            AudioTrackSelection::Track(2)
+        );
+        assert_eq!(
+            "ALL".parse::<AudioTrackSelection>().unwrap(),
+            AudioTrackSelection::All
+        );
+        assert_eq!(
+            "Stereo".parse::<AudioTrackSelection>().unwrap(),
+            AudioTrackSelection::Stereo
+        );
+        assert!("0".parse::<AudioTrackSelection>().is_err());
+        assert!("mic".parse::<AudioTrackSelection>().is_err());
+    }
+
+    #[test]
+    fn parses_clip_times() {
+        assert_eq!(parse_time("90").unwrap(), 90.0);
+        assert_eq!(parse_time("01:30").unwrap(), 90.0);
+        assert_eq!(parse_time("1:01:30.5").unwrap(), 3690.5);
+        assert!(parse_time("1:2:3:4").is_err());
+        assert!(parse_time("-5").is_err());
+
+        let clip: ClipRange =
+            serde_json::from_str(r#"{"start_time": "1:00:00", "end_time": 3900}"#).unwrap();
+        assert_eq!(clip.ffmpeg_input_args(), ["-ss", "3600.000"]);
+        assert_eq!(clip.ffmpeg_output_args(), ["-t", "300.000"]);
+        assert_eq!(clip.download_section(), "*3600-3900");
+        assert_eq!(clip.to_string(), "01:00:00 – 01:05:00");
+        assert!(ClipRange::new(Some(60.0), Some(30.0)).is_err());
+        assert_eq!(
+            ClipRange::new(None, Some(30.0)).unwrap().download_section(),
+            "*0-30"
+        );
+    }
+
+    #[test]
+    fn serializes_as_model_name() {
+        let model: WhisperModel = serde_json::from_str(r#""small-q8_0""#).unwrap();
+        assert_eq!(serde_json::to_string(&model).unwrap(), r#""small-q8_0""#);
+    }
+
+    #[test]
+    fn episode_overrides_direct_link_metadata() {
+        let mut metadata = VideoMetadata {
+            video_id: "default".to_string(),
+            title: "default".to_string(),
+            channel: "cdn.example.com".to_string(),
+            duration: 0,
+            upload_date: String::new(),
+            platform: "Direct Link".to_string(),
+            url: "https://cdn.example.com/default.mp3".to_string(),
+            language: None,
+            source_note: None,
+            chapters: Vec::new(),
+            live: false,
+        };
+        let episode = Episode {
+            show: "Rust & Friends".to_string(),
+            title: "Ep 1".to_string(),
+            guid: "ep-1".to_string(),
+            published: Some("Mon, 01 Jan 2024 10:00:00 +0000".to_string()),
+            audio_url: metadata.url.clone(),
+            duration: Some(60),
+        };
+        episode.apply(&mut metadata);
+        assert_eq!(metadata.title, "Ep 1");
+        assert_eq!(metadata.channel, "Rust & Friends");
+        assert_eq!(metadata.upload_date, "20240101");
+        assert_eq!(metadata.duration, 60);
+        assert!(metadata.video_id.starts_with("ep-") && metadata.video_id.len() == 15);
+    }
 }
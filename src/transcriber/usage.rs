@@ -0,0 +1,179 @@
+//! Per-job resource accounting.
+//!
+//! Operators of a shared server want to know what each job cost. Wall time
+//! and bytes downloaded are exact. CPU time and peak memory come from
+//! `getrusage`, which only reports whole-process numbers. CPU is the
+//! difference between the start and end of the job, and it includes yt-dlp
+//! and ffmpeg once they have exited. Jobs running at the same time see each
+//! other's CPU time, so treat that figure as an upper bound.
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub wall_secs: f64,
+    /// User + system CPU of this process and its finished children.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_secs: Option<f64>,
+    /// Peak resident memory of the whole server process since it started
+    /// (or of its largest ffmpeg / yt-dlp child, if bigger), read at the
+    /// end of the job. Not the job's own peak: an earlier or concurrent
+    /// job that used more shows up here too.
+    #[serde(
+        default,
+        alias = "peak_rss_bytes",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub process_peak_rss_bytes: Option<u64>,
+    #[serde(default)]
+    pub bytes_downloaded: u64,
+}
+
+impl ResourceUsage {
+    /// Fold `other` in: times and bytes add up, peak memory is the larger.
+    pub fn add(&mut self, other: &ResourceUsage) {
+        self.wall_secs += other.wall_secs;
+        self.cpu_secs = match (self.cpu_secs, other.cpu_secs) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.process_peak_rss_bytes = self
+            .process_peak_rss_bytes
+            .max(other.process_peak_rss_bytes);
+        self.bytes_downloaded += other.bytes_downloaded;
+    }
+
+    /// One-line summary for tool output.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("{:.1}s wall", self.wall_secs)];
+        if let Some(cpu) = self.cpu_secs {
+            parts.push(format!("{:.1}s CPU", cpu));
+        }
+        if let Some(rss) = self.process_peak_rss_bytes {
+            parts.push(format!("process peak RSS {} MB", rss / (1024 * 1024)));
+        }
+        if self.bytes_downloaded > 0 {
+            parts.push(format!(
+                "{:.1} MB downloaded",
+                self.bytes_downloaded as f64 / (1024.0 * 1024.0)
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Started when a job begins; [`UsageMeter::finish`] turns it into a
+/// [`ResourceUsage`].
+pub struct UsageMeter {
+    started: Instant,
+    cpu_at_start: Option<f64>,
+}
+
+impl UsageMeter {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            cpu_at_start: rusage().map(|r| r.cpu_secs),
+        }
+    }
+
+    pub fn finish(&self, bytes_downloaded: u64) -> ResourceUsage {
+        let now = rusage();
+        ResourceUsage {
+            wall_secs: self.started.elapsed().as_secs_f64(),
+            cpu_secs: match (self.cpu_at_start, &now) {
+                (Some(start), Some(now)) => Some((now.cpu_secs - start).max(0.0)),
+                _ => None,
+            },
+            process_peak_rss_bytes: now.map(|r| r.peak_rss_bytes),
+            bytes_downloaded,
+        }
+    }
+}
+
+struct Rusage {
+    cpu_secs: f64,
+    peak_rss_bytes: u64,
+}
+
+#[cfg(unix)]
+fn rusage() -> Option<Rusage> {
+    fn get(who: libc::c_int) -> Option<libc::rusage> {
+        let mut usage = std::mem::MaybeUninit::<libc::rusage>::zeroed();
+        // SAFETY: getrusage only writes into the struct we hand it.
+        let rc = unsafe { libc::getrusage(who, usage.as_mut_ptr()) };
+        // SAFETY: zero-initialised, and fully written on success.
+        (rc == 0).then(|| unsafe { usage.assume_init() })
+    }
+    let secs = |t: libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1e6;
+    // ru_maxrss is in kilobytes on Linux but bytes on macOS.
+    let rss_unit: u64 = if cfg!(target_os = "macos") { 1 } else { 1024 };
+
+    let own = get(libc::RUSAGE_SELF)?;
+    let children = get(libc::RUSAGE_CHILDREN)?;
+    Some(Rusage {
+        cpu_secs: secs(own.ru_utime)
+            + secs(own.ru_stime)
+            + secs(children.ru_utime)
+            + secs(children.ru_stime),
+        peak_rss_bytes: own.ru_maxrss.max(children.ru_maxrss).max(0) as u64 * rss_unit,
+    })
+}
+
+#[cfg(not(unix))]
+fn rusage() -> Option<Rusage> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meters_wall_and_cpu_time() {
+        let meter = UsageMeter::start();
+        let mut x = 0u64;
+        for i in 0..2_000_000u64 {
+            x = x.wrapping_mul(31).wrapping_add(std::hint::black_box(i));
+        }
+        std::hint::black_box(x);
+        let usage = meter.finish(2 * 1024 * 1024);
+        assert!(usage.wall_secs > 0.0);
+        if cfg!(unix) {
+            assert!(usage.cpu_secs.is_some());
+            assert!(usage.process_peak_rss_bytes.unwrap() > 0);
+        }
+        assert!(usage.summary().contains("2.0 MB downloaded"));
+    }
+
+    #[test]
+    fn adds_usage_across_attempts() {
+        let mut total = ResourceUsage::default();
+        total.add(&ResourceUsage {
+            wall_secs: 2.0,
+            cpu_secs: Some(3.0),
+            process_peak_rss_bytes: Some(100),
+            bytes_downloaded: 10,
+        });
+        total.add(&ResourceUsage {
+            wall_secs: 1.0,
+            cpu_secs: None,
+            process_peak_rss_bytes: Some(50),
+            bytes_downloaded: 5,
+        });
+        assert_eq!(
+            total,
+            ResourceUsage {
+                wall_secs: 3.0,
+                cpu_secs: Some(3.0),
+                process_peak_rss_bytes: Some(100),
+                bytes_downloaded: 15,
+            }
+        );
+        // Batch reports written before the rename still load.
+        let old: ResourceUsage =
+            serde_json::from_str(r#"{"wall_secs": 1.0, "peak_rss_bytes": 100}"#).unwrap();
+        assert_eq!(old.process_peak_rss_bytes, Some(100));
+    }
+}
@@ -0,0 +1,205 @@
+//! Voice activity detection: cutting long silences out of the audio before
+//! whisper, for lectures and meetings with long pauses.
+//!
+//! Energy-based, like [`super::classify`]: 20 ms frames louder than the
+//! recording's noise floor are voice. Silent runs of [`MIN_SILENCE_MS`] or
+//! more are cut down to [`PAD_MS`] either side, so whisper still hears a
+//! pause between sentences. A [`Timeline`] maps times in the shortened
+//! audio back to the source, so timestamps come out as if nothing had been
+//! cut. Music and applause silenced by `skip_non_speech` are cut too.
+
+use super::types::Segment;
+
+const SAMPLE_RATE: usize = 16_000;
+
+/// Frame whose level decides voice or silence (20 ms).
+const FRAME: usize = SAMPLE_RATE / 50;
+
+/// Silences shorter than this are left alone.
+const MIN_SILENCE_MS: usize = 2_000;
+
+/// Silence kept before and after each voiced stretch.
+const PAD_MS: usize = 250;
+
+/// Frames quieter than this RMS are always silence…
+const MIN_THRESHOLD: f32 = 0.003;
+/// …and frames louder than this always voice, however noisy the recording.
+const MAX_THRESHOLD: f32 = 0.02;
+/// Frames this many times the noise floor (the 10th percentile of frame
+/// levels) are voice.
+const FLOOR_RATIO: f32 = 3.0;
+
+/// A stretch of the shortened audio and where it came from (in samples).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    source: usize,
+    kept: usize,
+}
+
+/// Where the kept audio sits in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline {
+    spans: Vec<Span>,
+}
+
+/// Shortened audio with its timeline.
+pub struct Trimmed {
+    pub samples: Vec<f32>,
+    pub timeline: Timeline,
+    /// How many silences were cut, and how much.
+    pub cuts: usize,
+    pub cut_ms: u64,
+}
+
+/// `samples` without their long silences, or `None` when there are none.
+pub fn trim_silence(samples: &[f32]) -> Option<Trimmed> {
+    let levels: Vec<f32> = samples.chunks(FRAME).map(rms).collect();
+    let threshold = noise_floor(&levels) * FLOOR_RATIO;
+    let threshold = threshold.clamp(MIN_THRESHOLD, MAX_THRESHOLD);
+    let min_frames = MIN_SILENCE_MS * SAMPLE_RATE / 1000 / FRAME;
+    let pad = PAD_MS * SAMPLE_RATE / 1000;
+
+    // Sample ranges to cut: silent runs less the padding, which the very
+    // start and end of the audio don't need on their outer side.
+    let mut cuts: Vec<(usize, usize)> = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for (i, &level) in levels.iter().chain([&f32::INFINITY]).enumerate() {
+        if level < threshold {
+            run_start.get_or_insert(i);
+            continue;
+        }
+        let Some(start) = run_start.take() else {
+            continue;
+        };
+        if i - start < min_frames {
+            continue;
+        }
+        let from = if start == 0 { 0 } else { start * FRAME + pad };
+        let to = if i == levels.len() {
+            samples.len()
+        } else {
+            (i * FRAME).saturating_sub(pad)
+        };
+        if from < to {
+            cuts.push((from, to));
+        }
+    }
+    if cuts.is_empty() || cuts == [(0, samples.len())] {
+        return None;
+    }
+
+    let mut spans = Vec::new();
+    let mut kept = Vec::with_capacity(samples.len());
+    let mut at = 0;
+    for &(from, to) in cuts.iter().chain(&[(samples.len(), samples.len())]) {
+        if at < from {
+            spans.push(Span {
+                source: at,
+                kept: kept.len(),
+            });
+            kept.extend_from_slice(&samples[at..from]);
+        }
+        at = to;
+    }
+    let cut_samples = samples.len() - kept.len();
+    Some(Trimmed {
+        samples: kept,
+        timeline: Timeline { spans },
+        cuts: cuts.len(),
+        cut_ms: ms(cut_samples),
+    })
+}
+
+impl Timeline {
+    /// The source time of `kept_ms` in the shortened audio. An `end` time
+    /// right where a cut was made stays before the cut.
+    pub fn source_ms(&self, kept_ms: u64, end: bool) -> u64 {
+        let at = (kept_ms as usize * SAMPLE_RATE) / 1000;
+        let span = self
+            .spans
+            .iter()
+            .rev()
+            .find(|s| if end { s.kept < at } else { s.kept <= at })
+            .or(self.spans.first());
+        match span {
+            Some(s) => ms(s.source) + kept_ms.saturating_sub(ms(s.kept)),
+            None => kept_ms,
+        }
+    }
+
+    /// Move `segments` from the shortened audio to the source timeline.
+    pub fn remap(&self, segments: &mut [Segment]) {
+        for segment in segments {
+            let start_ms = self.source_ms(segment.start_ms, false);
+            segment.end_ms = self.source_ms(segment.end_ms, true).max(start_ms);
+            segment.start_ms = start_ms;
+        }
+    }
+}
+
+fn noise_floor(levels: &[f32]) -> f32 {
+    let mut sorted = levels.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    sorted.get(sorted.len() / 10).copied().unwrap_or(0.0)
+}
+
+fn rms(frame: &[f32]) -> f32 {
+    (frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32).sqrt()
+}
+
+fn ms(samples: usize) -> u64 {
+    (samples as u64 * 1000) / SAMPLE_RATE as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(seconds: usize) -> Vec<f32> {
+        (0..seconds * SAMPLE_RATE)
+            .map(|i| 0.3 * (std::f32::consts::TAU * 440.0 * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn silence(seconds: usize) -> Vec<f32> {
+        vec![0.0; seconds * SAMPLE_RATE]
+    }
+
+    #[test]
+    fn cuts_long_silences_and_maps_times_back() {
+        // 3 s silence, 2 s voice, 10 s silence, 2 s voice, 1 s pause,
+        // 2 s voice.
+        let audio = [
+            silence(3),
+            tone(2),
+            silence(10),
+            tone(2),
+            silence(1),
+            tone(2),
+        ]
+        .concat();
+        let trimmed = trim_silence(&audio).unwrap();
+        assert_eq!(trimmed.cuts, 2);
+        assert_eq!(trimmed.cut_ms, 12_250);
+        assert_eq!(trimmed.samples.len(), audio.len() - 12_250 * 16);
+
+        // Kept: 2.75–5.25 s and 14.75–21 s of the source.
+        let timeline = &trimmed.timeline;
+        assert_eq!(timeline.source_ms(0, false), 2_750);
+        assert_eq!(timeline.source_ms(2_500, true), 5_250);
+        assert_eq!(timeline.source_ms(2_500, false), 14_750);
+        assert_eq!(timeline.source_ms(3_000, false), 15_250);
+
+        let mut segments = [Segment {
+            start_ms: 500,
+            end_ms: 2_500,
+            text: "Hello.".to_string(),
+            ..Default::default()
+        }];
+        timeline.remap(&mut segments);
+        assert_eq!((segments[0].start_ms, segments[0].end_ms), (3_250, 5_250));
+
+        assert!(trim_silence(&[tone(3), silence(1), tone(3)].concat()).is_none());
+        assert!(trim_silence(&silence(5)).is_none());
+    }
+}
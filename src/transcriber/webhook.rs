@@ -0,0 +1,266 @@
+//! Webhook POSTed when a transcription finishes or fails.
+//!
+//! A request's `webhook_url`, else the config file's `webhook.url`, gets a
+//! JSON body once the engine is done with the request, including when it
+//! returned a cached transcript:
+//!
+//! ```json
+//! { "event": "transcription.completed", "source": "https://…", "timestamp": 1760000000,
+//!   "metadata": { "title": "…", … }, "model": "base", "word_count": 1234,
+//!   "cached": false, "files": { "txt": "/…/talk.txt", "json": "/…/talk.json" },
+//!   "uploaded": {} }
+//! ```
+//!
+//! `uploaded` has the URLs of copies the output sink uploaded, if any.
+//!
+//! A failure sends `transcription.failed` with `error` instead. With a
+//! secret (`webhook.secret`, else `WEBHOOK_SECRET`), POSTs to the config
+//! file's URL are signed like GitHub's: `X-Webhook-Signature: sha256=<hex
+//! HMAC-SHA256 of the body>`. A request's own URL is never signed with
+//! that secret, so a caller can't collect signed bodies to replay at the
+//! real receiver. It must be http(s) to a public host: loopback, private
+//! and link-local addresses are refused, after DNS too, and redirects
+//! aren't followed. The POST runs in the background and is tried three
+//! times; the transcription's result doesn't wait for it or depend on it.
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde_json::{Value, json};
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use super::types::{OutputFiles, TranscriptionResult};
+use crate::config::Config;
+
+const ATTEMPTS: u32 = 3;
+
+/// Where one request's webhook goes.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    secret: Option<String>,
+    /// Named by the request rather than the config file: only public
+    /// hosts are POSTed to.
+    public_only: bool,
+}
+
+impl Webhook {
+    /// The webhook for a request naming `url`, else the config file's
+    /// default; `None` when neither is set. Only the default is signed.
+    pub fn resolve(url: Option<&str>) -> Option<Self> {
+        if let Some(url) = url.filter(|u| !u.trim().is_empty()) {
+            return Some(Self {
+                url: url.to_string(),
+                secret: None,
+                public_only: true,
+            });
+        }
+        let config = Config::load().webhook.unwrap_or_default();
+        let url = config.url.filter(|u| !u.trim().is_empty())?;
+        let secret = config
+            .secret
+            .or_else(|| std::env::var("WEBHOOK_SECRET").ok())
+            .filter(|s| !s.is_empty());
+        Some(Self {
+            url,
+            secret,
+            public_only: false,
+        })
+    }
+
+    /// POST the outcome of transcribing `source` in the background.
+    pub fn send(self, source: &str, result: &Result<TranscriptionResult>) {
+        let body = payload(source, result, now_unix()).to_string();
+        tokio::spawn(async move {
+            if let Err(e) = self.post(body).await {
+                warn!("Webhook to {} failed: {:#}", self.url, e);
+            }
+        });
+    }
+
+    async fn post(&self, body: String) -> Result<()> {
+        let mut client = reqwest::Client::builder().timeout(Duration::from_secs(10));
+        if self.public_only {
+            // Connect to the addresses checked here, so a second lookup
+            // can't answer with a private one.
+            let url = check_url(&self.url)?;
+            let host = url.host_str().unwrap_or_default();
+            let port = url.port_or_known_default().unwrap_or(80);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+            if addrs.is_empty() || !addrs.iter().all(|a| is_public(a.ip())) {
+                anyhow::bail!("{} doesn't resolve to a public address", host);
+            }
+            client = client
+                .resolve_to_addrs(host, &addrs)
+                .redirect(reqwest::redirect::Policy::none());
+        }
+        let client = client.build()?;
+        let mut attempt = 1;
+        loop {
+            let mut request = client
+                .post(&self.url)
+                .header("content-type", "application/json")
+                .body(body.clone());
+            if let Some(secret) = &self.secret {
+                request = request.header("x-webhook-signature", sign(secret, body.as_bytes()));
+            }
+            let outcome = match request.send().await {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) => anyhow::anyhow!("receiver answered {}", resp.status()),
+                Err(e) => e.into(),
+            };
+            if attempt == ATTEMPTS {
+                return Err(outcome);
+            }
+            info!(
+                "Webhook attempt {} of {} failed ({:#}); retrying",
+                attempt, ATTEMPTS, outcome
+            );
+            tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// `url` if a request may name it as its webhook: http(s), to a host that
+/// isn't loopback, private or link-local as far as the URL shows.
+pub fn check_url(url: &str) -> Result<reqwest::Url> {
+    let parsed = reqwest::Url::parse(url)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https"))
+        .ok_or_else(|| anyhow::anyhow!("webhook_url must be an http(s) URL: {}", url))?;
+    let host = parsed.host_str().unwrap_or_default();
+    let public = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => is_public(ip),
+        Err(_) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            !host.is_empty() && host != "localhost" && !host.ends_with(".localhost")
+        }
+    };
+    if !public {
+        anyhow::bail!("webhook_url must point at a public host: {}", url);
+    }
+    Ok(parsed)
+}
+
+/// Whether `ip` is reachable from the internet at large, rather than the
+/// server itself or its private network.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                // Carrier-grade NAT, 100.64.0.0/10.
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(v4.into()),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7, and link-local, fe80::/10.
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Body announcing how transcribing `source` went.
+fn payload(source: &str, result: &Result<TranscriptionResult>, timestamp: u64) -> Value {
+    match result {
+        Ok(result) => {
+            let by_format = |files: &OutputFiles| -> serde_json::Map<_, _> {
+                files
+                    .0
+                    .iter()
+                    .map(|(format, path)| (format.as_str().to_string(), json!(path)))
+                    .collect()
+            };
+            json!({
+                "event": "transcription.completed",
+                "source": source,
+                "timestamp": timestamp,
+                "metadata": result.metadata,
+                "model": result.model_label(),
+                "word_count": result.word_count,
+                "cached": result.cached,
+                "files": by_format(&result.files),
+                "uploaded": by_format(&result.uploaded),
+            })
+        }
+        Err(e) => json!({
+            "event": "transcription.failed",
+            "source": source,
+            "timestamp": timestamp,
+            "error": format!("{:#}", e),
+        }),
+    }
+}
+
+/// `X-Webhook-Signature` value for `body`.
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes any key size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_failure_payloads() {
+        let failed = payload(
+            "https://example.com/talk",
+            &Err(anyhow::anyhow!("yt-dlp failed")),
+            1_760_000_000,
+        );
+        assert_eq!(failed["event"], "transcription.failed");
+        assert_eq!(failed["error"], "yt-dlp failed");
+        assert_eq!(failed["timestamp"], 1_760_000_000);
+
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn refuses_private_request_urls() {
+        assert!(check_url("https://hooks.example.com/transcripts").is_ok());
+        assert!(check_url("http://203.0.113.7:8080/hook").is_ok());
+        for url in [
+            "file:///etc/passwd",
+            "ftp://example.com/hook",
+            "http://localhost:3000/hook",
+            "http://api.localhost/hook",
+            "http://127.0.0.1/hook",
+            "http://10.0.0.5/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://0.0.0.0/hook",
+        ] {
+            assert!(check_url(url).is_err(), "{}", url);
+        }
+    }
+}
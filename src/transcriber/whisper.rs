@@ -1,17 +1,107 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tracing::info;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use tracing::{info, warn};
+use whisper_rs::{
+    FullParams, SamplingStrategy, SegmentCallbackData, WhisperContext, WhisperContextParameters,
+};
 
-use super::types::{Segment, WhisperModel};
+use super::alternatives;
+use super::checkpoint::Checkpoint;
+use super::classify::{self, AudioRegion};
+use super::downloader::AudioStream;
+use super::grammar::{Grammar, TokenFilter};
+use super::live::LiveOutput;
+use super::progress::{self, Progress};
+use super::quality::{self, AudioQuality};
+use super::types::{
+    DecodingOptions, DetectedLanguage, Device, LanguageFallback, Segment, TranscriptionOptions,
+    WhisperModel, model_label,
+};
+use super::vad;
+use super::whisper_api;
+use crate::config::Config;
 use crate::utils::paths::get_models_dir;
+use crate::utils::process::{self, Access};
+
+/// GPU backend whisper-rs was compiled with, if any. Metal is always on for
+/// macOS builds (target-specific dependency in Cargo.toml); elsewhere a
+/// backend is opted into with `--features cuda|vulkan|metal`.
+pub(crate) fn gpu_backend() -> Option<&'static str> {
+    if cfg!(feature = "cuda") {
+        Some("CUDA")
+    } else if cfg!(feature = "vulkan") {
+        Some("Vulkan")
+    } else if cfg!(any(feature = "metal", target_os = "macos")) {
+        Some("Metal")
+    } else {
+        None
+    }
+}
+
+/// The GPU `options` run whisper on, `None` for the CPU: the `device` if
+/// set, else the first GPU unless `gpu` (or the server default) is off.
+fn gpu_placement(options: &TranscriptionOptions) -> Option<u32> {
+    if gpu_backend().is_none() {
+        if let Some(device @ Device::Gpu(_)) = options.device {
+            warn!(
+                "{} requested, but no GPU support is compiled in; using the CPU",
+                device
+            );
+        }
+        return None;
+    }
+    match options.device {
+        Some(Device::Cpu) => None,
+        Some(Device::Gpu(index)) => Some(index),
+        None => options.gpu.unwrap_or_else(default_use_gpu).then_some(0),
+    }
+}
+
+/// Server-wide GPU default from `WHISPER_USE_GPU` (on unless set to
+/// `0`/`false`/`off`). Per-request `gpu` options take precedence.
+fn default_use_gpu() -> bool {
+    std::env::var("WHISPER_USE_GPU")
+        .map(|v| !matches!(v.trim().to_lowercase().as_str(), "0" | "false" | "off"))
+        .unwrap_or(true)
+}
+
+/// What a whisper run produced, plus how it was run.
+pub struct WhisperOutput {
+    pub transcript: String,
+    pub segments: Vec<Segment>,
+    /// Whether the model ran on a GPU backend. `None` for the remote
+    /// worker, whose hardware we can't see from here.
+    pub gpu_used: Option<bool>,
+    /// Signal checks on the decoded audio; `None` for the remote worker
+    /// and for clips too short to assess.
+    pub audio_quality: Option<AudioQuality>,
+    /// Music / applause stretches found in the audio (local only).
+    pub non_speech: Vec<AudioRegion>,
+    /// Language whisper settled on when none was requested.
+    pub detected_language: Option<DetectedLanguage>,
+}
+
+/// How many loaded models to keep resident when `WHISPER_MODEL_CACHE_SIZE`
+/// isn't set. Two covers the common "default model plus the occasional
+/// bigger one" pattern without holding several GB of large-model weights.
+const DEFAULT_MODEL_CACHE_SIZE: usize = 2;
 
 pub struct WhisperTranscriber {
     models_dir: PathBuf,
+    /// Loaded `WhisperContext`s, reused across calls. Loading the ggml file
+    /// costs 10–30s for medium/large, while a fresh `WhisperState` per
+    /// request is cheap — so we keep the context and throw the state away.
+    contexts: Arc<Mutex<ContextCache>>,
 }
 
+/// Loaded contexts keyed by model file and the GPU they were loaded on, if
+/// any.
+type ContextCache = ModelCache<(PathBuf, Option<u32>), Arc<WhisperContext>>;
+
 impl Default for WhisperTranscriber {
     fn default() -> Self {
         Self::new()
@@ -23,66 +113,165 @@ impl WhisperTranscriber {
         let models_dir = get_models_dir();
         std::fs::create_dir_all(&models_dir).ok();
 
-        Self { models_dir }
+        Self {
+            models_dir,
+            contexts: Arc::new(Mutex::new(ModelCache::new(model_cache_size()))),
+        }
     }
 
     /// Transcribe an audio file. Routes to a remote whisper worker if
-    /// `REMOTE_WHISPER_URL` is set; otherwise falls back to local
-    /// whisper-rs (blocking, run on a tokio worker thread).
+    /// `REMOTE_WHISPER_URL` is set, else to the config file's `whisper_api`
+    /// if there is one; otherwise falls back to local whisper-rs
+    /// (blocking, run on a tokio worker thread).
+    ///
+    /// `chunk_minutes` splits long local audio into chunks decoded in
+    /// parallel, and `gpu` toggles GPU offload; the remote worker does its
+    /// own batching and hardware selection and ignores both. Chunked
+    /// progress is kept at `checkpoint` when given (see
+    /// [`super::checkpoint`]), and decoded segments are appended to `live`
+    /// as they come (see [`super::live`]); the remote worker answers all at
+    /// once, so there is nothing to append before it is done.
     pub async fn transcribe(
         &self,
         audio_path: &Path,
-        model: WhisperModel,
-        language: Option<&str>,
-    ) -> Result<(String, Vec<Segment>)> {
-        if let Some(url) = remote_whisper_url()
-            && !url.trim().is_empty()
+        options: &TranscriptionOptions,
+        checkpoint: Option<PathBuf>,
+        live: Option<LiveOutput>,
+    ) -> Result<WhisperOutput> {
+        let model = options.model.unwrap_or_default();
+        if is_remote()
+            && let Some(url) = remote_whisper_url()
         {
-            return transcribe_remote(&url, audio_path, model, language).await;
+            if options.model_path.is_some() {
+                anyhow::bail!("model_path can't be used with REMOTE_WHISPER_URL");
+            }
+            return transcribe_remote(&url, audio_path, model, options.language.as_deref()).await;
+        }
+        if let Some(api) = Config::load().whisper_api {
+            if options.model_path.is_some() {
+                anyhow::bail!("model_path can't be used with whisper_api");
+            }
+            return whisper_api::transcribe(&api, audio_path, options.language.as_deref()).await;
+        }
+
+        // The auto-download build fetches a missing model on first use.
+        #[cfg(feature = "auto-download")]
+        if options.model_path.is_none() {
+            crate::utils::managed::fetch_model(&self.models_dir, model).await?;
         }
 
         // Local fallback — the underlying whisper-rs API is blocking, so we
         // run it on a worker thread to avoid stalling the tokio scheduler.
         let audio_path = audio_path.to_path_buf();
         let models_dir = self.models_dir.clone();
-        let language = language.map(|s| s.to_string());
+        let contexts = self.contexts.clone();
+        let options = options.clone();
+        let gpu = gpu_placement(&options);
+        tokio::task::spawn_blocking(move || {
+            let model_path = get_model_path(&models_dir, model, options.model_path.as_deref())?;
+            let ctx = load_context(&contexts, &model_path, gpu)?;
+            let mut output = transcribe_local(
+                &ctx,
+                &audio_path,
+                &options,
+                checkpoint.as_deref(),
+                live.as_ref(),
+            )?;
+            output.gpu_used = Some(gpu.is_some());
+            Ok(output)
+        })
+        .await
+        .context("transcribe task panicked")?
+    }
+
+    /// Transcribe audio as it streams in (see
+    /// [`super::downloader::VideoDownloader::stream_audio`]), a chunk at a
+    /// time, starting before the download is done. Local whisper only.
+    /// Returns the output and the bytes downloaded.
+    ///
+    /// Chunks are `chunk_minutes` long (else [`STREAM_CHUNK_MINUTES`]) and
+    /// decoded one after another; the language is detected on the first.
+    /// Audio quality, `skip_non_speech` and `skip_silence` need the whole
+    /// audio and don't apply.
+    pub async fn transcribe_stream(
+        &self,
+        stream: AudioStream,
+        options: &TranscriptionOptions,
+        live: Option<LiveOutput>,
+    ) -> Result<(WhisperOutput, u64)> {
+        if is_remote() {
+            anyhow::bail!("Streaming audio needs local Whisper, not a remote backend");
+        }
+        let model = options.model.unwrap_or_default();
+        #[cfg(feature = "auto-download")]
+        if options.model_path.is_none() {
+            crate::utils::managed::fetch_model(&self.models_dir, model).await?;
+        }
+        let models_dir = self.models_dir.clone();
+        let contexts = self.contexts.clone();
+        let options = options.clone();
+        let gpu = gpu_placement(&options);
         tokio::task::spawn_blocking(move || {
-            transcribe_local(&models_dir, &audio_path, model, language.as_deref())
+            let model_path = get_model_path(&models_dir, model, options.model_path.as_deref())?;
+            let ctx = load_context(&contexts, &model_path, gpu)?;
+            let mut stream = stream;
+            let mut output = transcribe_streamed(&ctx, &mut stream, &options, live.as_ref())?;
+            output.gpu_used = Some(gpu.is_some());
+            let bytes = stream.finish()?;
+            Ok((output, bytes))
         })
         .await
         .context("transcribe task panicked")?
     }
 
+    /// Size of the model file `options` pick, if it is there.
+    pub fn model_file_size(&self, options: &TranscriptionOptions) -> Option<u64> {
+        let model = options.model.unwrap_or_default();
+        let path = get_model_path(&self.models_dir, model, options.model_path.as_deref()).ok()?;
+        std::fs::metadata(path).ok().map(|m| m.len())
+    }
+
     pub fn check_models_status(&self) -> String {
         let mut status = String::new();
+        match gpu_backend() {
+            Some(backend) if default_use_gpu() => {
+                status.push_str(&format!("⚡ GPU acceleration: {} (enabled)\n", backend))
+            }
+            Some(backend) => status.push_str(&format!(
+                "⚡ GPU acceleration: {} (disabled via WHISPER_USE_GPU)\n",
+                backend
+            )),
+            None => status.push_str("⚡ GPU acceleration: not compiled in (CPU only)\n"),
+        }
         status.push_str("📦 Whisper Models:\n");
 
         if remote_whisper_url().is_some() {
             status.push_str(
                 "  (remote: REMOTE_WHISPER_URL is set — local models unused)\n",
             );
+        } else if let Some(api) = Config::load().whisper_api {
+            status.push_str(&format!(
+                "  (remote: whisper_api {} with {} — local models unused)\n",
+                api.base_url, api.model
+            ));
         }
 
-        for model in [
-            WhisperModel::Tiny,
-            WhisperModel::Base,
-            WhisperModel::Small,
-            WhisperModel::Medium,
-            WhisperModel::Large,
-        ] {
+        // Quantized variants are only listed when present, so the default
+        // output stays one line per model size.
+        for model in WhisperModel::all() {
             let model_path = self.models_dir.join(model.model_filename());
             if model_path.exists() {
                 let size = std::fs::metadata(&model_path)
                     .map(|m| format!("{:.1} MB", m.len() as f64 / 1_000_000.0))
                     .unwrap_or_else(|_| "unknown".to_string());
                 status.push_str(&format!(
-                    "  ✅ {:?}: {} ({})\n",
+                    "  ✅ {}: {} ({})\n",
                     model,
                     model_path.display(),
                     size
                 ));
-            } else {
-                status.push_str(&format!("  ❌ {:?}: not installed\n", model));
+            } else if model.quantization.is_none() {
+                status.push_str(&format!("  ❌ {}: not installed\n", model));
             }
         }
 
@@ -94,12 +283,70 @@ fn remote_whisper_url() -> Option<String> {
     std::env::var("REMOTE_WHISPER_URL").ok()
 }
 
+/// Whether transcription goes to a remote worker or a Whisper API (see
+/// [`super::whisper_api`]) rather than local whisper.cpp.
+pub fn is_remote() -> bool {
+    remote_whisper_url().is_some_and(|url| !url.trim().is_empty())
+        || Config::load().whisper_api.is_some()
+}
+
+/// Resident-model limit from `WHISPER_MODEL_CACHE_SIZE`. `0` disables the
+/// cache (reload on every call, the pre-cache behaviour) for memory-tight
+/// hosts.
+fn model_cache_size() -> usize {
+    std::env::var("WHISPER_MODEL_CACHE_SIZE")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(DEFAULT_MODEL_CACHE_SIZE)
+}
+
+/// Small least-recently-used map from model to its loaded context. Entries
+/// are kept in use order (most recent last); the front is evicted when full.
+/// A handful of models at most, so a `Vec` beats pulling in an LRU crate.
+struct ModelCache<K, T> {
+    capacity: usize,
+    entries: Vec<(K, T)>,
+}
+
+impl<K: PartialEq, T: Clone> ModelCache<K, T> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, key: K) -> Option<T> {
+        let pos = self.entries.iter().position(|(k, _)| *k == key)?;
+        let entry = self.entries.remove(pos);
+        let value = entry.1.clone();
+        self.entries.push(entry);
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.retain(|(k, _)| *k != key);
+        while self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, value));
+    }
+}
+
 // ---------- remote whisper-worker path ----------
 
 #[derive(Deserialize)]
 struct RemoteResponse {
     transcript: String,
     segments: Vec<RemoteSegment>,
+    /// Optional: workers that report what they auto-detected.
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    language_probability: Option<f32>,
 }
 
 #[derive(Deserialize)]
@@ -109,16 +356,26 @@ struct RemoteSegment {
     text: String,
 }
 
+/// MIME type to upload `audio_path` as. Usually our extracted mp3, but
+/// local audio files are sent as is.
+pub(super) fn audio_mime(audio_path: &Path) -> &'static str {
+    match audio_path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("wav") => "audio/wav",
+        Some(e) if e.eq_ignore_ascii_case("m4a") => "audio/mp4",
+        Some(e) if e.eq_ignore_ascii_case("flac") => "audio/flac",
+        Some(e) if e.eq_ignore_ascii_case("ogg") || e.eq_ignore_ascii_case("opus") => "audio/ogg",
+        Some(e) if e.eq_ignore_ascii_case("aac") => "audio/aac",
+        _ => "audio/mpeg",
+    }
+}
+
 async fn transcribe_remote(
     url: &str,
     audio_path: &Path,
     model: WhisperModel,
     language: Option<&str>,
-) -> Result<(String, Vec<Segment>)> {
-    info!(
-        "🛰  Transcribing via remote Whisper ({}): {:?}",
-        url, model
-    );
+) -> Result<WhisperOutput> {
+    info!("🛰  Transcribing via remote Whisper ({}): {}", url, model);
 
     let bytes = tokio::fs::read(audio_path)
         .await
@@ -132,12 +389,12 @@ async fn transcribe_remote(
 
     let part = reqwest::multipart::Part::bytes(bytes)
         .file_name(filename)
-        .mime_str("audio/mpeg")
+        .mime_str(audio_mime(audio_path))
         .context("Failed to build multipart part")?;
 
     let form = reqwest::multipart::Form::new()
         .part("audio", part)
-        .text("model", model.as_str().to_string())
+        .text("model", model.to_string())
         .text("language", language.unwrap_or("auto").to_string());
 
     let client = reqwest::Client::builder()
@@ -175,31 +432,578 @@ async fn transcribe_remote(
             start_ms: s.start_ms,
             end_ms: s.end_ms,
             text: s.text,
+            ..Default::default()
         })
         .collect();
 
-    Ok((r.transcript, segments))
+    Ok(WhisperOutput {
+        transcript: r.transcript,
+        segments,
+        gpu_used: None,
+        audio_quality: None,
+        non_speech: Vec::new(),
+        detected_language: r.language.map(|code| DetectedLanguage {
+            code,
+            probability: r.language_probability,
+            fallback_from: None,
+        }),
+    })
 }
 
 // ---------- local (whisper-rs) path ----------
 
+/// Fetch the context for `model` from the cache, loading it from disk on a
+/// miss. The lock isn't held across the load so a slow large-model load
+/// doesn't block lookups of models that are already resident. GPU and CPU
+/// contexts of the same model are cached separately.
+fn load_context(
+    contexts: &Mutex<ContextCache>,
+    model_path: &Path,
+    gpu: Option<u32>,
+) -> Result<Arc<WhisperContext>> {
+    let key = (model_path.to_path_buf(), gpu);
+    let name = model_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if let Some(ctx) = contexts.lock().unwrap().get(key.clone()) {
+        info!("Reusing loaded Whisper model: {}", name);
+        return Ok(ctx);
+    }
+
+    match (gpu_backend(), gpu) {
+        (Some(backend), Some(0)) => info!("Loading Whisper model: {} ({})", name, backend),
+        (Some(backend), Some(index)) => info!(
+            "Loading Whisper model: {} ({} GPU {})",
+            name, backend, index
+        ),
+        _ => info!("Loading Whisper model: {} (CPU)", name),
+    }
+
+    let mut params = WhisperContextParameters::default();
+    params.use_gpu(gpu.is_some());
+    if let Some(index) = gpu {
+        params.gpu_device(index as std::ffi::c_int);
+    }
+    let ctx = WhisperContext::new_with_params(model_path.to_str().unwrap(), params)
+        .context("Failed to load Whisper model")?;
+    let ctx = Arc::new(ctx);
+
+    contexts.lock().unwrap().insert(key, ctx.clone());
+    Ok(ctx)
+}
+
+/// whisper.cpp consumes 16 kHz mono PCM; every sample offset below is in
+/// this rate.
+const SAMPLE_RATE: usize = 16_000;
+
+/// Audio shared by neighbouring chunks on each side of a split, so a word
+/// that straddles the cut is heard whole by at least one chunk.
+const CHUNK_OVERLAP_SAMPLES: usize = 2 * SAMPLE_RATE;
+
+/// How far either side of the nominal chunk boundary to look for silence.
+const SPLIT_SEARCH_SAMPLES: usize = 10 * SAMPLE_RATE;
+
+/// Energy is measured over 100 ms frames when hunting for a quiet split.
+const SPLIT_FRAME_SAMPLES: usize = SAMPLE_RATE / 10;
+
+/// whisper.cpp threads given to each concurrently decoding chunk. Beyond ~4
+/// a single state scales poorly, so spare cores go to more chunks instead.
+const THREADS_PER_CHUNK: i32 = 4;
+
+/// Decode, assess and transcribe a local audio file. `gpu_used` in the
+/// result is left for the caller to fill in.
 fn transcribe_local(
-    models_dir: &Path,
+    ctx: &WhisperContext,
     audio_path: &Path,
-    model: WhisperModel,
-    language: Option<&str>,
-) -> Result<(String, Vec<Segment>)> {
-    info!("Loading Whisper model: {:?}", model);
+    options: &TranscriptionOptions,
+    checkpoint_path: Option<&Path>,
+    live: Option<&LiveOutput>,
+) -> Result<WhisperOutput> {
+    info!("Loading audio file...");
+    let mut audio_data = load_audio_as_pcm(audio_path, options.audio_filter.as_deref())?;
+
+    let audio_quality = quality::assess(&audio_data);
+    if let Some(q) = &audio_quality {
+        info!("🔊 Audio quality: {}", q.summary());
+        for w in &q.warnings {
+            warn!("⚠️  {}", w);
+        }
+        if let Some(min) = options.min_audio_quality
+            && q.score < min
+        {
+            anyhow::bail!(
+                "Audio quality {} is below the requested minimum of {}{}",
+                q.summary(),
+                min,
+                if q.warnings.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", q.warnings.join("; "))
+                }
+            );
+        }
+    }
+
+    let non_speech = classify::non_speech_regions(&audio_data);
+    let skip_non_speech = options.skip_non_speech.unwrap_or(false);
+    if !non_speech.is_empty() {
+        let total_ms: u64 = non_speech.iter().map(|r| r.end_ms - r.start_ms).sum();
+        info!(
+            "🎵 {} non-speech region(s), {}s total{}",
+            non_speech.len(),
+            total_ms / 1000,
+            if skip_non_speech { " (skipping)" } else { "" }
+        );
+        if skip_non_speech {
+            classify::silence_regions(&mut audio_data, &non_speech);
+        }
+    }
+
+    let trimmed = match options.skip_silence {
+        Some(true) => vad::trim_silence(&audio_data),
+        _ => None,
+    };
+    let (audio_data, timeline) = match trimmed {
+        Some(trimmed) => {
+            info!(
+                "✂️  Cutting {} long silence(s), {}s total",
+                trimmed.cuts,
+                trimmed.cut_ms / 1000
+            );
+            (trimmed.samples, Some(trimmed.timeline))
+        }
+        None => (audio_data, None),
+    };
+    if let (Some(live), Some(timeline)) = (live, &timeline) {
+        live.set_timeline(timeline.clone());
+    }
+
+    let threads = optimal_whisper_threads();
+    let requested = options.language.as_deref().filter(|l| *l != "auto");
+    let chunk_minutes = options
+        .chunk_minutes
+        .filter(|m| *m > 0)
+        .filter(|m| audio_data.len() > *m as usize * 60 * SAMPLE_RATE);
+    let mut checkpoint = chunk_minutes.map(|minutes| {
+        let fresh = Checkpoint::new(
+            &model_label(
+                options.model.unwrap_or_default(),
+                options.model_path.as_deref(),
+            ),
+            requested,
+            minutes,
+            chunk_bounds(&audio_data, minutes as usize * 60 * SAMPLE_RATE),
+        );
+        match checkpoint_path.and_then(|path| Checkpoint::resume(path, &fresh)) {
+            Some(saved) => {
+                info!(
+                    "⏯️  Resuming: {} of {} chunks already transcribed",
+                    saved.chunks.len(),
+                    saved.chunk_count()
+                );
+                saved
+            }
+            None => fresh,
+        }
+    });
+
+    let detected_language = match (requested, &checkpoint) {
+        (Some(_), _) => None,
+        (None, Some(saved)) if saved.detected_language.is_some() => saved.detected_language.clone(),
+        (None, _) if ctx.is_multilingual() => Some(settle_language(
+            ctx,
+            &audio_data,
+            options.language_fallback.as_ref(),
+            threads,
+        )?),
+        // English-only models can't detect anything; they always emit English.
+        (None, _) => Some(DetectedLanguage {
+            code: "en".to_string(),
+            probability: None,
+            fallback_from: None,
+        }),
+    };
+    // Decode with the detected language pinned, so every chunk of a long
+    // file agrees on it instead of each re-detecting from its own opening.
+    let language = requested.or(detected_language.as_ref().map(|d| d.code.as_str()));
+    let segments = match checkpoint.as_mut() {
+        Some(checkpoint) => {
+            checkpoint.detected_language = detected_language.clone();
+            transcribe_chunked(
+                ctx,
+                &audio_data,
+                language,
+                &options.decoding,
+                checkpoint,
+                checkpoint_path,
+                live,
+                options.progress.as_ref(),
+                threads,
+            )?
+        }
+        None => {
+            info!("Transcribing... (this may take a few minutes)");
+            transcribe_pcm(
+                ctx,
+                &audio_data,
+                language,
+                &options.decoding,
+                threads,
+                0,
+                live,
+                options.progress.as_ref(),
+            )?
+        }
+    };
+    let mut segments = segments;
+    if let Some(timeline) = &timeline {
+        timeline.remap(&mut segments);
+    }
+    let segments = if skip_non_speech {
+        classify::drop_non_speech_segments(segments, &non_speech)
+    } else {
+        segments
+    };
+
+    let transcript = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
 
-    let model_path = get_model_path(models_dir, model)?;
+    Ok(WhisperOutput {
+        transcript,
+        segments,
+        gpu_used: None,
+        audio_quality,
+        non_speech,
+        detected_language,
+    })
+}
+
+/// Chunk length for streamed audio without `chunk_minutes`: long enough
+/// for whisper's context, short enough that output starts soon.
+pub const STREAM_CHUNK_MINUTES: u32 = 5;
 
-    let ctx = WhisperContext::new_with_params(
-        model_path.to_str().unwrap(),
-        WhisperContextParameters::default(),
-    )
-    .context("Failed to load Whisper model")?;
+/// Chunk length for a live stream without `chunk_minutes`, so the
+/// transcript trails the stream by about a minute.
+pub const LIVE_CHUNK_MINUTES: u32 = 1;
 
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+/// Decode `stream` chunk by chunk. Each chunk is cut like
+/// [`chunk_bounds`] cuts whole audio and decoded with the same overlap.
+fn transcribe_streamed(
+    ctx: &WhisperContext,
+    stream: &mut impl std::io::Read,
+    options: &TranscriptionOptions,
+    live: Option<&LiveOutput>,
+) -> Result<WhisperOutput> {
+    let whole_audio = [
+        ("skip_non_speech", options.skip_non_speech == Some(true)),
+        ("skip_silence", options.skip_silence == Some(true)),
+        ("min_audio_quality", options.min_audio_quality.is_some()),
+    ];
+    for (name, _) in whole_audio.iter().filter(|(_, set)| *set) {
+        warn!(
+            "{} needs the whole audio; ignoring it while streaming",
+            name
+        );
+    }
+    let threads = optimal_whisper_threads();
+    let requested = options.language.as_deref().filter(|l| *l != "auto");
+    let minutes = options
+        .chunk_minutes
+        .filter(|m| *m > 0)
+        .unwrap_or(STREAM_CHUNK_MINUTES);
+    let mut chunks = StreamChunks::new(stream, minutes as usize * 60 * SAMPLE_RATE);
+    let mut detected_language = None;
+    let mut segments = Vec::new();
+    info!(
+        "Transcribing as the audio streams in ({} minute chunks)...",
+        minutes
+    );
+    while let Some(chunk) = chunks.next_chunk()? {
+        if detected_language.is_none() && requested.is_none() {
+            detected_language = Some(if ctx.is_multilingual() {
+                settle_language(
+                    ctx,
+                    &chunk.samples,
+                    options.language_fallback.as_ref(),
+                    threads,
+                )?
+            } else {
+                DetectedLanguage {
+                    code: "en".to_string(),
+                    probability: None,
+                    fallback_from: None,
+                }
+            });
+        }
+        let language = requested.or(detected_language.as_ref().map(|d| d.code.as_str()));
+        let (start, end) = chunk.owned;
+        let decoded = transcribe_pcm(
+            ctx,
+            &chunk.samples,
+            language,
+            &options.decoding,
+            threads,
+            samples_to_ms(chunk.start),
+            None,
+            None,
+        )?;
+        let decoded = keep_owned(decoded, samples_to_ms(start), samples_to_ms(end));
+        if let Some(live) = live {
+            live.append(&decoded);
+        }
+        if let Some(progress) = &options.progress {
+            progress.transcribed(None, decoded.last());
+        }
+        info!(
+            "Streamed audio up to {}s transcribed",
+            samples_to_ms(end) / 1000
+        );
+        segments.extend(decoded);
+    }
+
+    let transcript = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(WhisperOutput {
+        transcript,
+        segments,
+        gpu_used: None,
+        audio_quality: None,
+        non_speech: Vec::new(),
+        detected_language,
+    })
+}
+
+/// f32le PCM read into chunks as it arrives.
+struct StreamChunks<R> {
+    reader: R,
+    chunk_len: usize,
+    /// Samples read and not yet dropped…
+    buffer: Vec<f32>,
+    /// …starting at this sample of the stream.
+    buffer_start: usize,
+    /// Where the next chunk's own audio starts.
+    next: usize,
+    /// Bytes of a sample split across reads.
+    partial: Vec<u8>,
+    ended: bool,
+}
+
+/// Audio to decode: `samples` from sample `start` of the stream, of which
+/// the `owned` range (with overlap either side) is this chunk's.
+struct StreamChunk {
+    samples: Vec<f32>,
+    start: usize,
+    owned: (usize, usize),
+}
+
+impl<R: std::io::Read> StreamChunks<R> {
+    fn new(reader: R, chunk_len: usize) -> Self {
+        Self {
+            reader,
+            chunk_len,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            next: 0,
+            partial: Vec::new(),
+            ended: false,
+        }
+    }
+
+    fn next_chunk(&mut self) -> Result<Option<StreamChunk>> {
+        let search = SPLIT_SEARCH_SAMPLES.min(self.chunk_len / 2);
+        // Enough to choose a cut and hear past it.
+        let wanted = self.next + self.chunk_len + search + CHUNK_OVERLAP_SAMPLES;
+        while !self.ended && self.buffer_start + self.buffer.len() < wanted {
+            self.fill()?;
+        }
+        let end = self.buffer_start + self.buffer.len();
+        if self.next >= end {
+            return Ok(None);
+        }
+        let cut = if self.ended && end - self.next <= self.chunk_len + search {
+            end
+        } else {
+            let target = self.next + self.chunk_len;
+            let lo = target - search - self.buffer_start;
+            let hi = (target + search).min(end - 1) - self.buffer_start;
+            quietest_frame(&self.buffer, lo, hi).unwrap_or(target - self.buffer_start)
+                + self.buffer_start
+        };
+        let from = self
+            .next
+            .saturating_sub(CHUNK_OVERLAP_SAMPLES)
+            .max(self.buffer_start);
+        let to = (cut + CHUNK_OVERLAP_SAMPLES).min(end);
+        let chunk = StreamChunk {
+            samples: self.buffer[from - self.buffer_start..to - self.buffer_start].to_vec(),
+            start: from,
+            owned: (self.next, cut),
+        };
+        // Keep the overlap the next chunk starts with.
+        let keep_from = cut
+            .saturating_sub(CHUNK_OVERLAP_SAMPLES)
+            .max(self.buffer_start);
+        self.buffer.drain(..keep_from - self.buffer_start);
+        self.buffer_start = keep_from;
+        self.next = cut;
+        Ok(Some(chunk))
+    }
+
+    fn fill(&mut self) -> Result<()> {
+        let mut bytes = [0u8; 64 * 1024];
+        let n = self
+            .reader
+            .read(&mut bytes)
+            .context("Failed to read streamed audio")?;
+        if n == 0 {
+            self.ended = true;
+            return Ok(());
+        }
+        self.partial.extend_from_slice(&bytes[..n]);
+        let whole = self.partial.len() / 4 * 4;
+        self.buffer.extend(
+            self.partial[..whole]
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        );
+        self.partial.drain(..whole);
+        Ok(())
+    }
+}
+
+/// How much audio language detection listens to — whisper's own window.
+const LANGUAGE_DETECT_SAMPLES: usize = 30 * SAMPLE_RATE;
+
+/// Detect the language and, when `fallback` finds the result unsure,
+/// listen again to the middle of the audio and then fall back to its
+/// language.
+fn settle_language(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    fallback: Option<&LanguageFallback>,
+    threads: i32,
+) -> Result<DetectedLanguage> {
+    let mut detected = detect_language(ctx, samples, 0, threads)?;
+    let Some(fallback) = fallback else {
+        return Ok(detected);
+    };
+    if fallback.is_unsure(&detected)
+        && fallback.retry
+        && samples.len() > 2 * LANGUAGE_DETECT_SAMPLES
+    {
+        info!("🌐 Unsure of the language; listening to the middle of the audio");
+        let second = detect_language(ctx, samples, samples.len() / 2, threads)?;
+        if second.probability > detected.probability {
+            detected = second;
+        }
+    }
+    Ok(apply_fallback(detected, fallback))
+}
+
+/// `detected`, or the fallback language if there is one and `detected` is
+/// unsure.
+fn apply_fallback(mut detected: DetectedLanguage, fallback: &LanguageFallback) -> DetectedLanguage {
+    if fallback.is_unsure(&detected)
+        && let Some(language) = &fallback.language
+    {
+        warn!(
+            "Language detection is unsure ({}); transcribing as '{}' instead",
+            detected, language
+        );
+        detected.fallback_from = Some(std::mem::replace(&mut detected.code, language.clone()));
+    }
+    detected
+}
+
+/// Identify the spoken language from 30 s of sound at or after sample
+/// `from` (skipping silence), on a throwaway state.
+fn detect_language(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    from: usize,
+    threads: i32,
+) -> Result<DetectedLanguage> {
+    let start = samples[from..]
+        .iter()
+        .position(|s| s.abs() > 0.02)
+        .map_or(from, |i| from + i);
+    let end = (start + LANGUAGE_DETECT_SAMPLES).min(samples.len());
+    let threads = threads.max(1) as usize;
+
+    let mut state = ctx
+        .create_state()
+        .context("Failed to create Whisper state")?;
+    state
+        .pcm_to_mel(&samples[start..end], threads)
+        .context("Failed to compute mel spectrogram")?;
+    let (id, probs) = state
+        .lang_detect(0, threads)
+        .context("Language detection failed")?;
+    let code = whisper_rs::get_lang_str(id)
+        .context("Whisper returned an unknown language id")?
+        .to_string();
+    let probability = probs.get(id as usize).copied();
+
+    info!(
+        "🌐 Detected language: {} ({:.0}%)",
+        code,
+        probability.unwrap_or(0.0) * 100.0
+    );
+    Ok(DetectedLanguage {
+        code,
+        probability,
+        fallback_from: None,
+    })
+}
+
+/// Run one whisper pass over `samples` on a fresh state. Segment timestamps
+/// are shifted by `offset_ms` so chunk results land on the source timeline.
+/// Each segment is appended to `live` as soon as whisper has it, and
+/// reported to `progress` with how far through `samples` it ends.
+#[allow(clippy::too_many_arguments)]
+fn transcribe_pcm(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    language: Option<&str>,
+    decoding: &DecodingOptions,
+    threads: i32,
+    offset_ms: u64,
+    live: Option<&LiveOutput>,
+    progress: Option<&Progress>,
+) -> Result<Vec<Segment>> {
+    let strategy = match decoding.beam_size {
+        Some(beam_size) if beam_size > 1 => SamplingStrategy::BeamSearch {
+            beam_size: beam_size as i32,
+            // Not implemented by whisper.cpp; -1 is its "unset".
+            patience: -1.0,
+        },
+        _ => SamplingStrategy::Greedy {
+            best_of: decoding.best_of.unwrap_or(1).max(1) as i32,
+        },
+    };
+    let mut params = FullParams::new(strategy);
+    if let Some(t) = decoding.temperature {
+        params.set_temperature(t.max(0.0));
+    }
+    if let Some(t) = decoding.entropy_threshold {
+        params.set_entropy_thold(t);
+    }
+    if let Some(t) = decoding.no_speech_threshold {
+        params.set_no_speech_thold(t.clamp(0.0, 1.0));
+    }
+    params.set_tdrz_enable(decoding.speaker_turns);
+    if let Some(prompt) = &decoding.prompt {
+        params.set_initial_prompt(prompt);
+    }
 
     if let Some(lang) = language
         && lang != "auto"
@@ -212,47 +1016,367 @@ fn transcribe_local(
     params.set_print_progress(false);
     params.set_print_realtime(false);
     params.set_print_timestamps(false);
-    params.set_n_threads(optimal_whisper_threads());
+    params.set_n_threads(threads);
+    if live.is_some() || progress.is_some() {
+        let (live, progress) = (live.cloned(), progress.cloned());
+        let length_ms = samples_to_ms(samples.len());
+        params.set_segment_callback_safe_lossy(move |data: SegmentCallbackData| {
+            let segment = Segment {
+                start_ms: (data.start_timestamp.max(0) as u64) * 10 + offset_ms,
+                end_ms: (data.end_timestamp.max(0) as u64) * 10 + offset_ms,
+                text: data.text.trim().to_string(),
+                ..Default::default()
+            };
+            if let Some(progress) = &progress {
+                let done = segment.end_ms.saturating_sub(offset_ms);
+                progress.transcribed(progress::percent(done, length_ms), Some(&segment));
+            }
+            if let Some(live) = &live {
+                live.append(std::slice::from_ref(&segment));
+            }
+        });
+    }
 
-    info!("Loading audio file...");
-    let audio_data = load_audio_as_pcm(audio_path)?;
+    let grammar = decoding.grammar.as_deref().map(Grammar::new).transpose()?;
+    let filter = grammar.as_ref().map(|g| TokenFilter::new(g, ctx));
+    if let Some(filter) = &filter {
+        // SAFETY: `filter` is dropped at the end of this function, after
+        // `full` has consumed `params`.
+        unsafe { filter.install(&mut params) };
+    }
 
-    info!("Transcribing... (this may take a few minutes)");
     let mut state = ctx
         .create_state()
         .context("Failed to create Whisper state")?;
 
     state
-        .full(params, &audio_data[..])
+        .full(params, samples)
         .context("Failed to transcribe audio")?;
 
     let num_segments = state.full_n_segments();
+    let wanted_alternatives = decoding
+        .alternatives
+        .filter(|&n| n > 0)
+        .map(|n| n.min(alternatives::MAX_ALTERNATIVES));
 
-    let mut transcript = String::new();
     let mut segments = Vec::with_capacity(num_segments as usize);
+    // Each segment's words with their probabilities, for alternatives.
+    let mut words = Vec::new();
     for i in 0..num_segments {
         let segment = state
             .get_segment(i)
             .context(format!("Failed to get segment {}", i))?;
+        if wanted_alternatives.is_some() {
+            let eot = ctx.token_eot();
+            words.push(alternatives::words(
+                (0..segment.n_tokens())
+                    .filter_map(|j| segment.get_token(j))
+                    .filter(|token| token.token_id() < eot)
+                    .map(|token| {
+                        let bytes = token.to_bytes().map(<[u8]>::to_vec).unwrap_or_default();
+                        (bytes, token.token_probability())
+                    }),
+            ));
+        }
         let text = segment
             .to_str_lossy()
             .context(format!("Failed to get text for segment {}", i))?
             .to_string();
-        let start_ms = (segment.start_timestamp().max(0) as u64) * 10;
-        let end_ms = (segment.end_timestamp().max(0) as u64) * 10;
-        transcript.push_str(&text);
-        transcript.push(' ');
+        let start_ms = (segment.start_timestamp().max(0) as u64) * 10 + offset_ms;
+        let end_ms = (segment.end_timestamp().max(0) as u64) * 10 + offset_ms;
         segments.push(Segment {
             start_ms,
             end_ms,
             text: text.trim().to_string(),
+            speaker_turn: segment.next_segment_speaker_turn(),
+            ..Default::default()
         });
     }
 
-    Ok((transcript.trim().to_string(), segments))
+    if let Some(limit) = wanted_alternatives {
+        for (segment, words) in segments.iter_mut().zip(&words) {
+            if !alternatives::any_uncertain(words) {
+                continue;
+            }
+            let at = |ms: u64| ms.saturating_sub(offset_ms) as usize * SAMPLE_RATE / 1000;
+            let from = at(segment.start_ms).saturating_sub(REREAD_PAD_SAMPLES);
+            let to = (at(segment.end_ms) + REREAD_PAD_SAMPLES).min(samples.len());
+            if from >= to {
+                continue;
+            }
+            let readings = reread(
+                ctx,
+                &samples[from..to],
+                language,
+                decoding,
+                threads,
+                filter.as_ref(),
+                limit,
+            )?;
+            segment.uncertain_words =
+                alternatives::uncertain_words(words, &readings, limit as usize);
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Audio kept either side of a segment decoded again for alternatives.
+const REREAD_PAD_SAMPLES: usize = SAMPLE_RATE / 5;
+
+/// `samples` decoded `runs` more times as one segment, first with beam
+/// search and then sampling at rising temperatures, so unsure words come
+/// out differently where the audio allows.
+fn reread(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    language: Option<&str>,
+    decoding: &DecodingOptions,
+    threads: i32,
+    filter: Option<&TokenFilter>,
+    runs: u32,
+) -> Result<Vec<String>> {
+    let beam_size = decoding.beam_size.unwrap_or(1).max(5) as i32;
+    let mut readings = Vec::new();
+    for run in 0..runs {
+        let mut params = if run == 0 {
+            FullParams::new(SamplingStrategy::BeamSearch {
+                beam_size,
+                patience: -1.0,
+            })
+        } else {
+            FullParams::new(SamplingStrategy::Greedy { best_of: 1 })
+        };
+        params.set_temperature((0.2 * run as f32).min(1.0));
+        params.set_temperature_inc(0.0);
+        params.set_single_segment(true);
+        params.set_no_context(true);
+        if let Some(prompt) = &decoding.prompt {
+            params.set_initial_prompt(prompt);
+        }
+        if let Some(lang) = language
+            && lang != "auto"
+        {
+            params.set_language(Some(lang));
+        }
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+        params.set_n_threads(threads);
+        if let Some(filter) = filter {
+            // SAFETY: `filter` outlives this call, which consumes `params`.
+            unsafe { filter.install(&mut params) };
+        }
+        let mut state = ctx
+            .create_state()
+            .context("Failed to create Whisper state")?;
+        state
+            .full(params, samples)
+            .context("Failed to decode a segment again")?;
+        let text: Vec<String> = (0..state.full_n_segments())
+            .filter_map(|i| state.get_segment(i))
+            .filter_map(|segment| segment.to_str_lossy().ok().map(|t| t.trim().to_string()))
+            .collect();
+        readings.push(text.join(" "));
+    }
+    Ok(readings)
+}
+
+/// Decode the chunks `checkpoint` splits the audio into (at quiet points)
+/// and hasn't done yet, concurrently (one whisper state each, sharing the
+/// loaded context), and stitch the segments back together in order. Each
+/// finished chunk is saved to `checkpoint_path`, which is removed once all
+/// are done. Chunks are appended to `live` in order, each once every chunk
+/// before it is in, and each finished chunk is reported to `progress`.
+#[allow(clippy::too_many_arguments)]
+fn transcribe_chunked(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    language: Option<&str>,
+    decoding: &DecodingOptions,
+    checkpoint: &mut Checkpoint,
+    checkpoint_path: Option<&Path>,
+    live: Option<&LiveOutput>,
+    progress: Option<&Progress>,
+    threads: i32,
+) -> Result<Vec<Segment>> {
+    let chunks: Vec<(usize, usize)> = checkpoint.bounds.windows(2).map(|w| (w[0], w[1])).collect();
+    let todo: Vec<usize> = (0..chunks.len())
+        .filter(|i| !checkpoint.chunks.contains_key(i))
+        .collect();
+    let workers = ((threads / THREADS_PER_CHUNK).max(1) as usize).min(todo.len().max(1));
+    let threads_per_worker = (threads / workers as i32).max(1);
+
+    info!(
+        "Transcribing {} chunks across {} workers ({} threads each)...",
+        chunks.len(),
+        workers,
+        threads_per_worker
+    );
+
+    let next = AtomicUsize::new(0);
+    // Chunks before this one have been appended to `live`.
+    let flushed = Mutex::new(0);
+    let flush = |checkpoint: &Checkpoint| {
+        if let Some(live) = live {
+            let mut flushed = flushed.lock().unwrap_or_else(|e| e.into_inner());
+            while let Some(segments) = checkpoint.chunks.get(&*flushed) {
+                live.append(segments);
+                *flushed += 1;
+            }
+        }
+    };
+    // Chunks resumed from a checkpoint are already done.
+    flush(checkpoint);
+    let shared = Mutex::new(&mut *checkpoint);
+    let mut results: Vec<(usize, Result<Vec<Segment>>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    while let Some(&i) = todo.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        let (start, end) = chunks[i];
+                        let padded_start = start.saturating_sub(CHUNK_OVERLAP_SAMPLES);
+                        let padded_end = (end + CHUNK_OVERLAP_SAMPLES).min(samples.len());
+                        let segments = transcribe_pcm(
+                            ctx,
+                            &samples[padded_start..padded_end],
+                            language,
+                            decoding,
+                            threads_per_worker,
+                            samples_to_ms(padded_start),
+                            None,
+                            None,
+                        )
+                        .map(|segs| keep_owned(segs, samples_to_ms(start), samples_to_ms(end)));
+                        if let Ok(segments) = &segments {
+                            let mut checkpoint = shared.lock().unwrap_or_else(|e| e.into_inner());
+                            checkpoint.complete(i, segments.clone(), SAMPLE_RATE);
+                            if let Some(path) = checkpoint_path
+                                && let Err(e) = checkpoint.save(path)
+                            {
+                                warn!("Could not save chunk progress: {:#}", e);
+                            }
+                            flush(&checkpoint);
+                            if let Some(progress) = progress {
+                                progress.transcribed(
+                                    progress::percent(
+                                        checkpoint.chunks.len() as u64,
+                                        chunks.len() as u64,
+                                    ),
+                                    segments.last(),
+                                );
+                            }
+                        }
+                        info!("Chunk {}/{} done", i + 1, chunks.len());
+                        done.push((i, segments));
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("chunk worker panicked"))
+            .collect()
+    });
+
+    let checkpoint = shared.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|(i, _)| *i);
+    if let Some((i, Err(e))) = results.into_iter().find(|(_, r)| r.is_err()) {
+        let saved = match checkpoint_path {
+            Some(path) if !checkpoint.chunks.is_empty() => format!(
+                "; {} of {} chunks are saved in {}, and running the same request again resumes from them",
+                checkpoint.chunks.len(),
+                chunks.len(),
+                path.display()
+            ),
+            _ => String::new(),
+        };
+        return Err(e.context(format!("Chunk {} failed{}", i + 1, saved)));
+    }
+    if let Some(path) = checkpoint_path {
+        let _ = std::fs::remove_file(path);
+    }
+    Ok(checkpoint.chunks.values().flatten().cloned().collect())
+}
+
+fn samples_to_ms(samples: usize) -> u64 {
+    (samples as u64 * 1000) / SAMPLE_RATE as u64
+}
+
+/// Chunk boundaries (sample offsets, starting at 0 and ending at the total
+/// length). Each cut is placed at the quietest frame within
+/// `SPLIT_SEARCH_SAMPLES` of where a fixed-length split would fall, so we
+/// rarely cut mid-word.
+fn chunk_bounds(samples: &[f32], chunk_len: usize) -> Vec<usize> {
+    let search = SPLIT_SEARCH_SAMPLES.min(chunk_len / 2);
+    let mut bounds = vec![0];
+    let mut last = 0;
+    while samples.len() - last > chunk_len {
+        let target = last + chunk_len;
+        let lo = target.saturating_sub(search).max(last + 1);
+        let hi = (target + search).min(samples.len() - 1);
+        let cut = quietest_frame(samples, lo, hi).unwrap_or(target);
+        bounds.push(cut);
+        last = cut;
+    }
+    bounds.push(samples.len());
+    bounds
+}
+
+/// Midpoint of the lowest-energy `SPLIT_FRAME_SAMPLES` frame in `lo..hi`.
+fn quietest_frame(samples: &[f32], lo: usize, hi: usize) -> Option<usize> {
+    (lo..hi.saturating_sub(SPLIT_FRAME_SAMPLES))
+        .step_by(SPLIT_FRAME_SAMPLES)
+        .map(|start| {
+            let energy: f32 = samples[start..start + SPLIT_FRAME_SAMPLES]
+                .iter()
+                .map(|s| s * s)
+                .sum();
+            (start, energy)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(start, _)| start + SPLIT_FRAME_SAMPLES / 2)
+}
+
+/// Drop segments decoded from the overlap padding: a segment belongs to the
+/// chunk whose owned range `[start_ms, end_ms)` contains its midpoint, so
+/// each stretch of speech is emitted exactly once.
+fn keep_owned(segments: Vec<Segment>, start_ms: u64, end_ms: u64) -> Vec<Segment> {
+    segments
+        .into_iter()
+        .filter(|s| {
+            let mid = (s.start_ms + s.end_ms) / 2;
+            mid >= start_ms && mid < end_ms
+        })
+        .collect()
 }
 
-fn get_model_path(models_dir: &Path, model: WhisperModel) -> Result<PathBuf> {
+/// Resolve the model file to load: `custom_path` when given (absolute, or
+/// relative to the models directory), else the built-in model's file.
+/// Either way the file header is checked so a bad path fails here with a
+/// clear message instead of deep inside whisper.cpp.
+fn get_model_path(
+    models_dir: &Path,
+    model: WhisperModel,
+    custom_path: Option<&str>,
+) -> Result<PathBuf> {
+    if let Some(custom) = custom_path {
+        let path = Path::new(custom);
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            models_dir.join(path)
+        };
+        if !path.is_file() {
+            anyhow::bail!("Custom Whisper model not found: {}", path.display());
+        }
+        check_model_header(&path)?;
+        return Ok(path);
+    }
+
     let model_filename = model.model_filename();
     let model_path = models_dir.join(&model_filename);
 
@@ -264,21 +1388,61 @@ fn get_model_path(models_dir: &Path, model: WhisperModel) -> Result<PathBuf> {
             Or download manually from:\n\
               https://huggingface.co/ggerganov/whisper.cpp/resolve/main/{}",
             model_path.display(),
-            model.as_str(),
+            model,
             model_filename
         );
     }
+    check_model_header(&model_path)?;
 
     Ok(model_path)
 }
 
-fn load_audio_as_pcm(audio_path: &Path) -> Result<Vec<f32>> {
+/// whisper.cpp model files start with the ggml magic 0x67676d6c, stored
+/// little-endian.
+const GGML_MAGIC: [u8; 4] = *b"lmgg";
+
+/// Reject files whisper.cpp can't load: GGUF (the llama.cpp-era container,
+/// which whisper.cpp's loader doesn't read) and anything else that isn't a
+/// ggml model — typically an HTML error page saved by a failed download.
+fn check_model_header(path: &Path) -> Result<()> {
+    use std::io::Read;
+
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .with_context(|| format!("Failed to read model file {}", path.display()))?;
+    match &magic {
+        m if *m == GGML_MAGIC => Ok(()),
+        b"GGUF" => anyhow::bail!(
+            "{} is a GGUF file; whisper.cpp only loads ggml-format Whisper models \
+            (convert with whisper.cpp's models/convert-h5-to-ggml.py)",
+            path.display()
+        ),
+        _ => anyhow::bail!(
+            "{} is not a ggml Whisper model (bad file header)",
+            path.display()
+        ),
+    }
+}
+
+fn load_audio_as_pcm(audio_path: &Path, audio_filter: Option<&str>) -> Result<Vec<f32>> {
     info!("Converting audio to 16kHz mono PCM...");
 
-    let output = std::process::Command::new("ffmpeg")
+    let audio_path = std::path::absolute(audio_path)?;
+    let mut command = process::command(
+        "ffmpeg",
+        audio_path.parent().unwrap_or(Path::new("/")),
+        Access::Local,
+    )?;
+    command
+        .args(process::LOCAL_ONLY)
+        .args(["-i", audio_path.to_str().unwrap()]);
+    if let Some(filter) = audio_filter {
+        info!("Applying audio filters: {}", filter);
+        command.args(["-af", filter]);
+    }
+    let output = command
         .args([
-            "-i",
-            audio_path.to_str().unwrap(),
             "-ar",
             "16000",
             "-ac",
@@ -329,3 +1493,171 @@ fn optimal_whisper_threads() -> i32 {
         .map(|n| n.get() as i32)
         .unwrap_or(4)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CHUNK_OVERLAP_SAMPLES, GGML_MAGIC, ModelCache, SAMPLE_RATE, StreamChunks, apply_fallback,
+        chunk_bounds, get_model_path, keep_owned,
+    };
+    use crate::transcriber::types::{
+        DetectedLanguage, LanguageFallback, ModelSize, Segment, WhisperModel,
+    };
+
+    #[test]
+    fn cache_hit_returns_stored_value() {
+        let mut cache = ModelCache::new(2);
+        cache.insert(ModelSize::Base, 1);
+        assert_eq!(cache.get(ModelSize::Base), Some(1));
+        assert_eq!(cache.get(ModelSize::Tiny), None);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = ModelCache::new(2);
+        cache.insert(ModelSize::Tiny, 1);
+        cache.insert(ModelSize::Base, 2);
+        // Touch Tiny so Base becomes the eviction candidate.
+        assert_eq!(cache.get(ModelSize::Tiny), Some(1));
+        cache.insert(ModelSize::Small, 3);
+        assert_eq!(cache.get(ModelSize::Base), None);
+        assert_eq!(cache.get(ModelSize::Tiny), Some(1));
+        assert_eq!(cache.get(ModelSize::Small), Some(3));
+    }
+
+    #[test]
+    fn zero_capacity_disables_caching() {
+        let mut cache = ModelCache::new(0);
+        cache.insert(ModelSize::Base, 1);
+        assert_eq!(cache.get(ModelSize::Base), None);
+    }
+
+    #[test]
+    fn chunk_bounds_cut_at_silence() {
+        // 3 minutes of "speech" with a 1s silent gap just past the 1-minute
+        // mark: the first cut should snap into the gap.
+        let mut samples = vec![0.5f32; 180 * SAMPLE_RATE];
+        let gap = 63 * SAMPLE_RATE;
+        samples[gap..gap + SAMPLE_RATE].fill(0.0);
+        let bounds = chunk_bounds(&samples, 60 * SAMPLE_RATE);
+        assert_eq!(bounds.first(), Some(&0));
+        assert_eq!(bounds.last(), Some(&samples.len()));
+        assert!(bounds[1] >= gap && bounds[1] < gap + SAMPLE_RATE);
+        assert!(bounds.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn streamed_audio_is_cut_at_silence_too() {
+        let mut samples = vec![0.5f32; 180 * SAMPLE_RATE];
+        let gap = 63 * SAMPLE_RATE;
+        samples[gap..gap + SAMPLE_RATE].fill(0.0);
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        // Reads that split samples, as a pipe may.
+        struct Trickle<'a>(&'a [u8]);
+        impl std::io::Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = buf.len().min(4097).min(self.0.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let mut chunks = StreamChunks::new(Trickle(&bytes), 60 * SAMPLE_RATE);
+        let mut owned = Vec::new();
+        while let Some(chunk) = chunks.next_chunk().unwrap() {
+            let (start, end) = chunk.owned;
+            assert_eq!(chunk.start, start.saturating_sub(CHUNK_OVERLAP_SAMPLES));
+            assert_eq!(
+                chunk.samples,
+                samples[chunk.start..(end + CHUNK_OVERLAP_SAMPLES).min(samples.len())]
+            );
+            owned.push(chunk.owned);
+        }
+        assert_eq!(owned.first().map(|o| o.0), Some(0));
+        assert_eq!(owned.last().map(|o| o.1), Some(samples.len()));
+        assert!(owned.windows(2).all(|w| w[0].1 == w[1].0));
+        assert!(owned[0].1 >= gap && owned[0].1 < gap + SAMPLE_RATE);
+    }
+
+    #[test]
+    fn short_audio_is_a_single_chunk() {
+        let samples = vec![0.1f32; 30 * SAMPLE_RATE];
+        assert_eq!(
+            chunk_bounds(&samples, 60 * SAMPLE_RATE),
+            vec![0, samples.len()]
+        );
+    }
+
+    #[test]
+    fn keep_owned_drops_overlap_segments() {
+        let seg = |start_ms, end_ms| Segment {
+            start_ms,
+            end_ms,
+            text: String::new(),
+            ..Default::default()
+        };
+        let kept = keep_owned(
+            vec![seg(0, 900), seg(900, 1_400), seg(1_800, 2_600)],
+            1_000,
+            2_000,
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].start_ms, 900);
+    }
+
+    #[test]
+    fn custom_model_path_checks_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = WhisperModel::default();
+        std::fs::write(dir.path().join("distil.bin"), GGML_MAGIC).unwrap();
+        std::fs::write(dir.path().join("llm.gguf"), b"GGUF\x03\0\0\0").unwrap();
+        std::fs::write(dir.path().join("page.bin"), b"<!DOCTYPE html>").unwrap();
+
+        let abs = dir.path().join("distil.bin");
+        assert_eq!(
+            get_model_path(dir.path(), model, abs.to_str()).unwrap(),
+            abs
+        );
+        assert_eq!(
+            get_model_path(dir.path(), model, Some("distil.bin")).unwrap(),
+            abs
+        );
+        let gguf = get_model_path(dir.path(), model, Some("llm.gguf")).unwrap_err();
+        assert!(gguf.to_string().contains("GGUF"));
+        assert!(get_model_path(dir.path(), model, Some("page.bin")).is_err());
+        assert!(get_model_path(dir.path(), model, Some("missing.bin")).is_err());
+    }
+
+    #[test]
+    fn unsure_detection_falls_back_to_the_configured_language() {
+        let fallback = LanguageFallback {
+            min_confidence: 0.5,
+            retry: true,
+            language: Some("de".to_string()),
+        };
+        let detected = |p| DetectedLanguage {
+            code: "nl".to_string(),
+            probability: Some(p),
+            fallback_from: None,
+        };
+
+        let sure = apply_fallback(detected(0.8), &fallback);
+        assert_eq!(sure, detected(0.8));
+
+        let unsure = apply_fallback(detected(0.3), &fallback);
+        assert_eq!(unsure.code, "de");
+        assert_eq!(unsure.fallback_from.as_deref(), Some("nl"));
+        assert!(!unsure.is_detected());
+        assert_eq!(
+            unsure.to_string(),
+            "de (fallback; detection guessed nl at 30%)"
+        );
+
+        let keep_guess = LanguageFallback {
+            language: None,
+            ..fallback
+        };
+        assert_eq!(apply_fallback(detected(0.3), &keep_guess), detected(0.3));
+    }
+}
@@ -0,0 +1,266 @@
+//! Transcription through an OpenAI-compatible Whisper API.
+//!
+//! With `whisper_api` in the config file, audio is posted to the
+//! endpoint's `/audio/transcriptions` instead of running whisper.cpp:
+//! OpenAI itself, or a server that speaks its API such as Groq,
+//! faster-whisper-server or LocalAI. The response is asked for as
+//! `verbose_json`, which carries segment times and the detected language.
+//!
+//! OpenAI takes uploads of up to 25 MB. Larger audio is cut with ffmpeg
+//! into 20-minute mono mp3 chunks at 64 kbit/s (under 10 MB each), which
+//! are transcribed one after another and joined with their segment times
+//! moved to where the chunk starts.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+use tracing::info;
+
+use super::types::{DetectedLanguage, Segment};
+use super::whisper::{WhisperOutput, audio_mime};
+use crate::config::WhisperApiConfig;
+use crate::utils::process::{self, Access};
+
+/// Largest upload the endpoint takes.
+const MAX_UPLOAD_BYTES: u64 = 25 * 1000 * 1000;
+
+/// Length of the chunks larger audio is cut into.
+const CHUNK_SECONDS: u32 = 20 * 60;
+
+#[derive(Deserialize)]
+struct VerboseResponse {
+    text: String,
+    #[serde(default)]
+    segments: Vec<VerboseSegment>,
+    /// A language name ("english") for OpenAI, a code for some servers.
+    #[serde(default)]
+    language: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VerboseSegment {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+/// Transcribe `audio_path` with the endpoint in `config`, in chunks if it
+/// is too large for one upload.
+pub async fn transcribe(
+    config: &WhisperApiConfig,
+    audio_path: &Path,
+    language: Option<&str>,
+) -> Result<WhisperOutput> {
+    info!(
+        "🛰  Transcribing via Whisper API ({}): {}",
+        config.base_url, config.model
+    );
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(600))
+        .build()
+        .context("Failed to build reqwest client")?;
+    let api_key = config
+        .api_key
+        .clone()
+        .or_else(|| std::env::var("WHISPER_API_KEY").ok())
+        .filter(|key| !key.is_empty());
+    let language = language.filter(|l| !l.eq_ignore_ascii_case("auto"));
+
+    let size = std::fs::metadata(audio_path)
+        .with_context(|| format!("Failed to read audio file: {}", audio_path.display()))?
+        .len();
+    // Held until the chunks are sent.
+    let chunk_dir;
+    let chunks = if size <= MAX_UPLOAD_BYTES {
+        vec![(audio_path.to_path_buf(), 0.0)]
+    } else {
+        chunk_dir = tempfile::tempdir()?;
+        split(audio_path, chunk_dir.path()).await?
+    };
+
+    let mut transcript = Vec::new();
+    let mut segments = Vec::new();
+    let mut detected = None;
+    for (i, (path, offset)) in chunks.iter().enumerate() {
+        if chunks.len() > 1 {
+            info!("🛰  Sending chunk {}/{}", i + 1, chunks.len());
+        }
+        let response = post(&client, config, api_key.as_deref(), path, language).await?;
+        segments.extend(into_segments(&response.segments, *offset));
+        transcript.push(response.text.trim().to_string());
+        detected = detected.or(response.language);
+    }
+    info!(
+        "🛰  Whisper API transcription complete: {} segments",
+        segments.len()
+    );
+
+    // Only auto-detection reports a language.
+    let detected_language = match language {
+        None => detected.map(|name| DetectedLanguage {
+            code: language_code(&name),
+            probability: None,
+            fallback_from: None,
+        }),
+        Some(_) => None,
+    };
+    Ok(WhisperOutput {
+        transcript: transcript.join(" "),
+        segments,
+        gpu_used: None,
+        audio_quality: None,
+        non_speech: Vec::new(),
+        detected_language,
+    })
+}
+
+async fn post(
+    client: &reqwest::Client,
+    config: &WhisperApiConfig,
+    api_key: Option<&str>,
+    audio_path: &Path,
+    language: Option<&str>,
+) -> Result<VerboseResponse> {
+    let bytes = tokio::fs::read(audio_path)
+        .await
+        .with_context(|| format!("Failed to read audio file: {}", audio_path.display()))?;
+    let filename = audio_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("audio.mp3")
+        .to_string();
+    let part = reqwest::multipart::Part::bytes(bytes)
+        .file_name(filename)
+        .mime_str(audio_mime(audio_path))
+        .context("Failed to build multipart part")?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", part)
+        .text("model", config.model.clone())
+        .text("response_format", "verbose_json");
+    if let Some(language) = language {
+        form = form.text("language", language.to_string());
+    }
+
+    let mut request = client.post(endpoint(&config.base_url)).multipart(form);
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    let resp = request.send().await.context("Whisper API POST failed")?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Whisper API returned {}: {}", status, body);
+    }
+    resp.json()
+        .await
+        .context("Failed to parse Whisper API response")
+}
+
+fn endpoint(base_url: &str) -> String {
+    format!("{}/audio/transcriptions", base_url.trim_end_matches('/'))
+}
+
+/// Cut `audio_path` into chunks in `dir`. Returns each chunk with the
+/// second it starts at.
+async fn split(audio_path: &Path, dir: &Path) -> Result<Vec<(std::path::PathBuf, f64)>> {
+    info!(
+        "✂️  Audio is over {} MB; cutting it into {}-minute chunks",
+        MAX_UPLOAD_BYTES / 1_000_000,
+        CHUNK_SECONDS / 60
+    );
+    let audio_path = std::path::absolute(audio_path)?;
+    let list = dir.join("chunks.csv");
+    let output = process::async_command_reading("ffmpeg", dir, Access::Local, &[&audio_path])?
+        .args(process::LOCAL_ONLY)
+        .args(["-i", audio_path.to_str().unwrap()])
+        .args(["-vn", "-ac", "1", "-ar", "16000", "-c:a", "libmp3lame"])
+        .args(["-b:a", "64k", "-f", "segment", "-reset_timestamps", "1"])
+        .args(["-segment_time", &CHUNK_SECONDS.to_string()])
+        .args(["-segment_list", list.to_str().unwrap()])
+        .args(["-segment_list_type", "csv", "-y"])
+        .arg(dir.join("chunk%03d.mp3").to_str().unwrap())
+        .output()
+        .await
+        .context("Failed to run ffmpeg. Is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "ffmpeg failed to split audio: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let list = std::fs::read_to_string(&list).context("ffmpeg wrote no chunk list")?;
+    Ok(parse_chunk_list(&list)
+        .into_iter()
+        .map(|(name, start)| (dir.join(name), start))
+        .collect())
+}
+
+/// The chunks in ffmpeg's CSV segment list (`file,start,end` per line).
+fn parse_chunk_list(list: &str) -> Vec<(String, f64)> {
+    list.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',');
+            let name = fields.next()?.trim();
+            let start = fields.next()?.trim().parse().ok()?;
+            (!name.is_empty()).then(|| (name.to_string(), start))
+        })
+        .collect()
+}
+
+/// `segments` of a chunk starting at `offset` seconds, on the timeline of
+/// the whole audio.
+fn into_segments(segments: &[VerboseSegment], offset: f64) -> Vec<Segment> {
+    let ms = |seconds: f64| ((seconds + offset) * 1000.0).round().max(0.0) as u64;
+    segments
+        .iter()
+        .map(|s| Segment {
+            start_ms: ms(s.start),
+            end_ms: ms(s.end),
+            text: s.text.clone(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// whisper.cpp's code for a language given by name or code.
+fn language_code(language: &str) -> String {
+    let language = language.trim().to_lowercase();
+    whisper_rs::get_lang_id(&language)
+        .and_then(whisper_rs::get_lang_str)
+        .map(str::to_string)
+        .unwrap_or(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_chunks_on_one_timeline() {
+        let list = "chunk000.mp3,0.000000,1200.000000\nchunk001.mp3,1200.000000,1534.5\n";
+        assert_eq!(
+            parse_chunk_list(list),
+            vec![
+                ("chunk000.mp3".to_string(), 0.0),
+                ("chunk001.mp3".to_string(), 1200.0)
+            ]
+        );
+
+        let response: VerboseResponse = serde_json::from_str(
+            r#"{"task": "transcribe", "language": "english", "duration": 4.2,
+                "text": "Hello there.",
+                "segments": [{"id": 0, "start": 0.0, "end": 2.5, "text": " Hello there."}]}"#,
+        )
+        .unwrap();
+        let segments = into_segments(&response.segments, 1200.0);
+        assert_eq!(segments[0].start_ms, 1_200_000);
+        assert_eq!(segments[0].end_ms, 1_202_500);
+        assert_eq!(response.language.as_deref(), Some("english"));
+
+        assert_eq!(
+            endpoint("https://api.openai.com/v1/"),
+            "https://api.openai.com/v1/audio/transcriptions"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Optional encryption at rest for transcripts and batch reports.
+//!
+//! When `TRANSCRIPT_ENCRYPTION_KEY_FILE` points at a 32-byte key (raw, or 64
+//! hex characters), every output file is written encrypted, and the read
+//! paths here decrypt it again. Files written before the key was set, or
+//! while it was unset, stay plaintext and can still be read.
+//!
+//! Files are sealed with XChaCha20-Poly1305 under the key file's key:
+//!
+//! ```text
+//! "VTENC1\0\0" | nonce (24) | ciphertext | Poly1305 tag (16)
+//! ```
+//!
+//! Every file gets a fresh random 24-byte nonce, which is large enough
+//! that random nonces never repeat in practice. The header is the
+//! associated data, so it is authenticated along with the ciphertext.
+
+use anyhow::{Context, Result};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"VTENC1\0\0";
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+
+pub struct EncryptionKey {
+    cipher: XChaCha20Poly1305,
+}
+
+impl EncryptionKey {
+    pub fn from_bytes(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(key.into()),
+        }
+    }
+
+    /// Key from `TRANSCRIPT_ENCRYPTION_KEY_FILE`, or `None` when that's
+    /// unset. A set but unreadable or malformed key is an error, so nothing
+    /// is ever written in plaintext by mistake.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(path) = std::env::var("TRANSCRIPT_ENCRYPTION_KEY_FILE")
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let raw = std::fs::read(path.trim())
+            .with_context(|| format!("Failed to read encryption key file {}", path))?;
+        Self::parse(&raw).map(Some)
+    }
+
+    fn parse(raw: &[u8]) -> Result<Self> {
+        if let Ok(key) = <[u8; 32]>::try_from(raw) {
+            return Ok(Self::from_bytes(&key));
+        }
+        let text = std::str::from_utf8(raw).unwrap_or_default().trim();
+        let decoded = hex::decode(text).ok();
+        match decoded.as_deref().map(<[u8; 32]>::try_from) {
+            Some(Ok(key)) => Ok(Self::from_bytes(&key)),
+            _ => anyhow::bail!(
+                "Encryption key file must hold 32 raw bytes or 64 hex characters \
+                 (e.g. `openssl rand -hex 32 > key`)"
+            ),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::fill(&mut nonce).map_err(|e| anyhow::anyhow!("No OS randomness: {}", e))?;
+        let sealed = self
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: MAGIC,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt"))?;
+
+        let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if !is_encrypted(data) || data.len() < MAGIC.len() + NONCE_LEN + TAG_LEN {
+            anyhow::bail!("Not an encrypted transcript file");
+        }
+        let (nonce, sealed) = data[MAGIC.len()..].split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: MAGIC,
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Wrong encryption key, or the file was modified"))
+    }
+}
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// Write `contents` to `path`, encrypted if a key is configured.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let contents = contents.as_ref();
+    let data = match EncryptionKey::from_env()? {
+        Some(key) => key.encrypt(contents)?,
+        None => contents.to_vec(),
+    };
+    std::fs::write(path, data).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Like [`write`], but through a temporary file renamed over `path`, so a
+/// reader sees the old contents or the new, never half of either.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let temp = tempfile::NamedTempFile::new_in(dir)
+        .with_context(|| format!("Failed to create a temporary file in {}", dir.display()))?;
+    write(temp.path(), contents)?;
+    temp.persist(path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Read `path`, decrypting it if it was written encrypted.
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !is_encrypted(&data) {
+        return Ok(data);
+    }
+    let key = EncryptionKey::from_env()?.with_context(|| {
+        format!(
+            "{} is encrypted; set TRANSCRIPT_ENCRYPTION_KEY_FILE to read it",
+            path.display()
+        )
+    })?;
+    key.decrypt(&data)
+        .with_context(|| format!("Failed to decrypt {}", path.display()))
+}
+
+pub fn read_to_string(path: &Path) -> Result<String> {
+    String::from_utf8(read(path)?).with_context(|| format!("{} is not UTF-8", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_tampering() {
+        let key = EncryptionKey::from_bytes(&[7; 32]);
+        let plaintext = "Confidential meeting notes ".repeat(10);
+
+        let sealed = key.encrypt(plaintext.as_bytes()).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.windows(12).any(|w| w == b"Confidential"));
+        assert_eq!(key.decrypt(&sealed).unwrap(), plaintext.as_bytes());
+        // Fresh nonce each time.
+        assert_ne!(key.encrypt(plaintext.as_bytes()).unwrap(), sealed);
+
+        let mut tampered = sealed.clone();
+        tampered[MAGIC.len() + NONCE_LEN + 3] ^= 1;
+        assert!(key.decrypt(&tampered).is_err());
+        let other = EncryptionKey::from_bytes(&[8; 32]);
+        assert!(other.decrypt(&sealed).is_err());
+    }
+
+    #[test]
+    fn parses_raw_and_hex_key_files() {
+        assert!(EncryptionKey::parse(&[1; 32]).is_ok());
+        assert!(EncryptionKey::parse(format!("{}\n", "ab".repeat(32)).as_bytes()).is_ok());
+        assert!(EncryptionKey::parse(b"too short").is_err());
+    }
+}
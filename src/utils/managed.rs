@@ -0,0 +1,265 @@
+//! yt-dlp and Whisper models, fetched by the server itself in the
+//! `auto-download` build.
+//!
+//! A binary built with `--features auto-download` needs ffmpeg installed
+//! and nothing else: audio is decoded by ffmpeg, which isn't downloaded,
+//! so startup only warns when it is missing. When the server starts and
+//! yt-dlp isn't on `PATH`, it downloads yt-dlp's standalone build for the
+//! platform into `~/.cache/video-transcriber-mcp/bin`. A Whisper model
+//! that isn't in the models directory is downloaded from Hugging Face the
+//! first time a job asks for it, so the first transcription with `tiny`
+//! costs a 75 MB download and later ones none.
+//!
+//! Downloads are checked before they are kept: yt-dlp against the
+//! `SHA2-256SUMS` of the release it came from, and a model against the
+//! SHA-256 Hugging Face lists for it. A mismatch deletes the download.
+//!
+//! Once downloaded, the managed yt-dlp is used by the auto-download build
+//! in place of one on `PATH`. It updates itself with `yt-dlp -U`. Other
+//! builds only run what is on `PATH`, never a binary from the user-writable
+//! cache directory.
+
+// Only the auto-download build downloads anything.
+#![cfg_attr(not(feature = "auto-download"), allow(dead_code))]
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use crate::transcriber::types::WhisperModel;
+use crate::utils::paths::get_managed_bin_dir;
+
+/// Where ggml Whisper models are downloaded from.
+const MODELS_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// The models' file list, with the SHA-256 of each.
+const MODELS_TREE_URL: &str = "https://huggingface.co/api/models/ggerganov/whisper.cpp/tree/main";
+
+/// yt-dlp's GitHub releases.
+const YT_DLP_RELEASES_URL: &str = "https://github.com/yt-dlp/yt-dlp/releases";
+
+/// `program`, or in the auto-download build the managed copy of it if
+/// there is one.
+pub fn program(program: &str) -> String {
+    if cfg!(feature = "auto-download") {
+        resolve(&get_managed_bin_dir(), program)
+    } else {
+        program.to_string()
+    }
+}
+
+fn resolve(bin_dir: &Path, program: &str) -> String {
+    let managed = bin_dir.join(executable(program));
+    if managed.is_file() {
+        managed.to_string_lossy().into_owned()
+    } else {
+        program.to_string()
+    }
+}
+
+fn executable(program: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", program)
+    } else {
+        program.to_string()
+    }
+}
+
+/// The name of yt-dlp's standalone build for `os` and `arch`, as in
+/// `std::env::consts`. `None` where there is none.
+fn yt_dlp_asset(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("linux", "x86_64") => Some("yt-dlp_linux"),
+        ("linux", "aarch64") => Some("yt-dlp_linux_aarch64"),
+        ("macos", _) => Some("yt-dlp_macos"),
+        ("windows", "x86") => Some("yt-dlp_x86.exe"),
+        ("windows", _) => Some("yt-dlp.exe"),
+        _ => None,
+    }
+}
+
+/// Download yt-dlp unless it is on `PATH` or already managed. Returns what
+/// was done, for the log.
+pub async fn ensure_yt_dlp() -> Result<String> {
+    let installed = std::process::Command::new(program("yt-dlp"))
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if installed {
+        return Ok("yt-dlp found".to_string());
+    }
+    let asset = yt_dlp_asset(std::env::consts::OS, std::env::consts::ARCH).with_context(|| {
+        format!(
+            "yt-dlp has no standalone build for {}/{}; install it with pip",
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        )
+    })?;
+    // Pin the release first, so the checksums and the build match.
+    let latest = reqwest::get(format!("{}/latest", YT_DLP_RELEASES_URL))
+        .await
+        .and_then(|r| r.error_for_status())
+        .context("Failed to look up the latest yt-dlp release")?;
+    let tag = latest
+        .url()
+        .path()
+        .rsplit_once("/tag/")
+        .map(|(_, tag)| tag.to_string())
+        .context("Couldn't tell the latest yt-dlp release")?;
+    let release = format!("{}/download/{}", YT_DLP_RELEASES_URL, tag);
+    let sums = fetch_text(&format!("{}/SHA2-256SUMS", release)).await?;
+    let sha256 = release_checksum(&sums, asset)
+        .with_context(|| format!("yt-dlp {} lists no checksum for {}", tag, asset))?;
+
+    let path = get_managed_bin_dir().join(executable("yt-dlp"));
+    download(&format!("{}/{}", release, asset), &path, &sha256).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(format!("yt-dlp downloaded to {}", path.display()))
+}
+
+/// Check that ffmpeg runs, since nothing here can stand in for it.
+pub fn check_ffmpeg() -> Result<()> {
+    let runs = std::process::Command::new("ffmpeg")
+        .arg("-version")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !runs {
+        anyhow::bail!("ffmpeg isn't installed; install it from your package manager");
+    }
+    Ok(())
+}
+
+/// Download `model` into `models_dir` unless it is there.
+pub async fn fetch_model(models_dir: &Path, model: WhisperModel) -> Result<PathBuf> {
+    let filename = model.model_filename();
+    let path = models_dir.join(&filename);
+    if !path.exists() {
+        let tree: serde_json::Value = serde_json::from_str(&fetch_text(MODELS_TREE_URL).await?)
+            .context("Unreadable model list from Hugging Face")?;
+        let sha256 = model_checksum(&tree, &filename)
+            .with_context(|| format!("Hugging Face lists no checksum for {}", filename))?;
+        download(&format!("{}/{}", MODELS_URL, filename), &path, &sha256).await?;
+    }
+    Ok(path)
+}
+
+async fn fetch_text(url: &str) -> Result<String> {
+    reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("Failed to download {}", url))?
+        .text()
+        .await
+        .with_context(|| format!("Failed to download {}", url))
+}
+
+/// `asset`'s hex SHA-256 in a `sha256sum`-style list.
+fn release_checksum(sums: &str, asset: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        (name.trim_start().trim_start_matches('*') == asset).then(|| hash.to_lowercase())
+    })
+}
+
+/// `filename`'s hex SHA-256 in a Hugging Face file list: its LFS object id.
+fn model_checksum(tree: &serde_json::Value, filename: &str) -> Option<String> {
+    tree.as_array()?
+        .iter()
+        .find(|file| file["path"] == filename)?
+        .pointer("/lfs/oid")?
+        .as_str()
+        .map(str::to_lowercase)
+}
+
+/// Download `url` to `path`, through a `.part` file so an interrupted
+/// download is never taken for the whole. The file is only kept if its
+/// SHA-256 is `sha256` (hex).
+async fn download(url: &str, path: &Path, sha256: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Can't create {}", dir.display()))?;
+    }
+    info!("📥 Downloading {}...", url);
+    let mut response = reqwest::get(url)
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("Failed to download {}", url))?;
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let part = PathBuf::from(part);
+    let mut file = tokio::fs::File::create(&part)
+        .await
+        .with_context(|| format!("Can't create {}", part.display()))?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("Download of {} was cut short", url))?
+    {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    drop(file);
+    let actual = hex::encode(hasher.finalize());
+    if actual != sha256 {
+        let _ = tokio::fs::remove_file(&part).await;
+        anyhow::bail!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            url,
+            sha256,
+            actual
+        );
+    }
+    tokio::fs::rename(&part, path).await?;
+    info!("✅ Saved {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefers_the_managed_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(resolve(dir.path(), "yt-dlp"), "yt-dlp");
+        let managed = dir.path().join(executable("yt-dlp"));
+        std::fs::write(&managed, "").unwrap();
+        assert_eq!(resolve(dir.path(), "yt-dlp"), managed.to_string_lossy());
+        assert_eq!(resolve(dir.path(), "ffmpeg"), "ffmpeg");
+
+        assert_eq!(
+            yt_dlp_asset("linux", "aarch64"),
+            Some("yt-dlp_linux_aarch64")
+        );
+        assert_eq!(yt_dlp_asset("macos", "aarch64"), Some("yt-dlp_macos"));
+        assert_eq!(yt_dlp_asset("freebsd", "x86_64"), None);
+    }
+
+    #[test]
+    fn reads_published_checksums() {
+        let sums = "0a1b  yt-dlp\n2C3D  yt-dlp_linux\n4e5f  yt-dlp_linux.zip\n";
+        assert_eq!(
+            release_checksum(sums, "yt-dlp_linux").as_deref(),
+            Some("2c3d")
+        );
+        assert_eq!(release_checksum(sums, "yt-dlp_macos"), None);
+
+        let tree = serde_json::json!([
+            { "type": "file", "path": "README.md" },
+            { "type": "file", "path": "ggml-tiny.bin", "lfs": { "oid": "be07e0", "size": 77691713 } },
+        ]);
+        assert_eq!(
+            model_checksum(&tree, "ggml-tiny.bin").as_deref(),
+            Some("be07e0")
+        );
+        assert_eq!(model_checksum(&tree, "README.md"), None);
+        assert_eq!(model_checksum(&tree, "ggml-base.bin"), None);
+    }
+}
@@ -1 +1,4 @@
+pub mod encryption;
+pub mod managed;
 pub mod paths;
+pub mod process;
@@ -1,13 +1,143 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::retention::AUDIT_DIR;
+use crate::transcriber::batch::BATCH_DIR;
+use crate::transcriber::search::REPORTS_DIR;
+use crate::transcriber::types::OutputFormat;
 
 pub fn get_default_output_dir() -> PathBuf {
     let home = home::home_dir().expect("Could not find home directory");
     home.join("Downloads").join("video-transcripts")
 }
 
+/// Location of the optional JSON config file: `VIDEO_TRANSCRIBER_CONFIG` if
+/// set, else `~/.config/video-transcriber-mcp/config.json`.
+pub fn get_config_path() -> PathBuf {
+    if let Ok(path) = std::env::var("VIDEO_TRANSCRIBER_CONFIG")
+        && !path.trim().is_empty()
+    {
+        return PathBuf::from(path);
+    }
+    let home = home::home_dir().expect("Could not find home directory");
+    home.join(".config")
+        .join("video-transcriber-mcp")
+        .join("config.json")
+}
+
 pub fn get_models_dir() -> PathBuf {
     let home = home::home_dir().expect("Could not find home directory");
     home.join(".cache")
         .join("video-transcriber-mcp")
         .join("models")
 }
+
+/// Where the `auto-download` build keeps the yt-dlp it downloads.
+pub fn get_managed_bin_dir() -> PathBuf {
+    let home = home::home_dir().expect("Could not find home directory");
+    home.join(".cache")
+        .join("video-transcriber-mcp")
+        .join("bin")
+}
+
+/// Default SQLite file of the HTTP session store, when the config file
+/// asks for one.
+pub fn get_sessions_path() -> PathBuf {
+    let home = home::home_dir().expect("Could not find home directory");
+    home.join(".cache")
+        .join("video-transcriber-mcp")
+        .join("sessions.sqlite")
+}
+
+/// Transcript files under `dir` (any [`OutputFormat`] extension),
+/// including those in the subdirectories an `output_layout` creates.
+/// Hidden files and directories (such as the transcript cache index) and
+/// the directories holding batch reports, search reports and the retention
+/// audit log are skipped.
+pub fn transcript_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+            if file_type.is_dir() {
+                if name != BATCH_DIR && name != AUDIT_DIR && name != REPORTS_DIR {
+                    pending.push(path);
+                }
+            } else if file_type.is_file()
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| e.parse::<OutputFormat>().is_ok())
+            {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+/// Remove the directories under `root` (not `root` itself) that deleting
+/// the `deleted` files left empty, from each file's directory upwards.
+/// Hidden directories and what is inside them, and directories nothing
+/// was deleted from, are kept.
+pub fn remove_emptied_dirs(root: &Path, deleted: &[PathBuf]) {
+    for file in deleted {
+        let Some(parent) = file.parent() else {
+            continue;
+        };
+        let hidden = parent.strip_prefix(root).map_or(true, |relative| {
+            relative
+                .iter()
+                .any(|name| name.to_string_lossy().starts_with('.'))
+        });
+        if hidden {
+            continue;
+        }
+        let mut dir = Some(parent);
+        while let Some(current) = dir {
+            if current == root || !current.starts_with(root) {
+                break;
+            }
+            // Fails, as intended, unless the directory is now empty.
+            if std::fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_only_directories_a_deletion_emptied() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let nested = root.join("YouTube").join("2024");
+        let hidden = root.join(".cache").join("empty");
+        for d in [&nested, &hidden, &root.join("Vimeo"), &root.join("kept")] {
+            std::fs::create_dir_all(d).unwrap();
+        }
+        std::fs::write(root.join("kept").join("notes.txt"), "").unwrap();
+
+        remove_emptied_dirs(root, &[nested.join("abc-Talk.txt"), hidden.join("x.txt")]);
+        assert!(!root.join("YouTube").exists());
+        // Empty, but nothing was deleted from it.
+        assert!(root.join("Vimeo").exists());
+        assert!(hidden.exists());
+        assert!(root.join("kept").exists());
+        assert!(root.exists());
+    }
+}
@@ -0,0 +1,410 @@
+//! How yt-dlp and ffmpeg are started.
+//!
+//! Subprocesses don't inherit the server's environment, which in HTTP mode
+//! holds API keys, the encryption key and whatever else the deployment
+//! set: they get the variables in [`INHERITED`] plus the config file's
+//! `subprocess_env` (e.g. `http_proxy` for an egress proxy). Each runs in
+//! the temp directory of the work it does, so stray files land there
+//! rather than in the server's working directory. ffmpeg reading a local
+//! file takes [`LOCAL_ONLY`] so a crafted playlist can't make it fetch
+//! URLs.
+//!
+//! With `sandbox_subprocesses` in the config file they also run sandboxed,
+//! for servers fed untrusted URLs. They can only read the system
+//! directories programs need to run ([`SYSTEM_PATHS`]), the program itself,
+//! the server's cache directory, the files they are given as input and
+//! their working directory, and only write to their working directory.
+//! Only yt-dlp (and ffmpeg reading an HLS stream) gets the network.
+//! Linux uses bubblewrap (`bwrap`, which must be installed), macOS
+//! `sandbox-exec`.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::managed;
+use super::paths::get_managed_bin_dir;
+use crate::config::Config;
+
+/// Server environment variables subprocesses still see: what programs
+/// need to find themselves, their locale and certificates.
+pub const INHERITED: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LANG",
+    "LC_ALL",
+    "LC_CTYPE",
+    "TZ",
+    "TMPDIR",
+    "XDG_CACHE_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_RUNTIME_DIR",
+    "SSL_CERT_FILE",
+    "SSL_CERT_DIR",
+    // Windows programs don't start without these.
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "TEMP",
+    "TMP",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+];
+
+/// What a sandboxed subprocess may read besides its own files: libraries,
+/// interpreters, certificates and name resolution. Missing ones are
+/// skipped.
+#[cfg(not(target_os = "macos"))]
+pub const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/libx32",
+    "/etc/alternatives",
+    "/etc/ca-certificates",
+    "/etc/fonts",
+    "/etc/group",
+    "/etc/host.conf",
+    "/etc/hosts",
+    "/etc/ld.so.cache",
+    "/etc/ld.so.conf",
+    "/etc/ld.so.conf.d",
+    "/etc/localtime",
+    "/etc/nsswitch.conf",
+    "/etc/passwd",
+    "/etc/pki",
+    "/etc/resolv.conf",
+    "/etc/ssl",
+    "/nix/store",
+    "/home/linuxbrew/.linuxbrew",
+];
+
+#[cfg(target_os = "macos")]
+pub const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/System",
+    "/Library",
+    "/opt/homebrew",
+    "/private/etc",
+    "/private/var/db/timezone",
+    "/dev",
+];
+
+/// ffmpeg / ffprobe input options allowing only local files and pipes.
+pub const LOCAL_ONLY: [&str; 2] = ["-protocol_whitelist", "file,pipe"];
+
+/// What a subprocess needs besides its working directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Local files only.
+    Local,
+    /// Also the network (yt-dlp).
+    Network,
+}
+
+/// Sandbox wrapper for this platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sandbox {
+    Bubblewrap,
+    SandboxExec,
+}
+
+impl Sandbox {
+    fn for_platform() -> Result<Self> {
+        if cfg!(target_os = "linux") {
+            Ok(Self::Bubblewrap)
+        } else if cfg!(target_os = "macos") {
+            Ok(Self::SandboxExec)
+        } else {
+            anyhow::bail!("sandbox_subprocesses is only supported on Linux and macOS")
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Bubblewrap => "bwrap",
+            Self::SandboxExec => "sandbox-exec",
+        }
+    }
+
+    /// Arguments that run `program` in the sandbox, before its own. It may
+    /// read [`SYSTEM_PATHS`] and `reads` (files or directories).
+    fn args(
+        self,
+        program: &str,
+        workdir: &Path,
+        access: Access,
+        reads: &[PathBuf],
+    ) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        match self {
+            Self::Bubblewrap => {
+                args.extend(
+                    ["--die-with-parent", "--new-session", "--unshare-all"].map(OsString::from),
+                );
+                if access == Access::Network {
+                    args.push("--share-net".into());
+                }
+                for path in SYSTEM_PATHS
+                    .iter()
+                    .map(Path::new)
+                    .chain(reads.iter().map(|p| p.as_path()))
+                {
+                    args.extend(["--ro-bind-try".into(), path.into(), path.into()]);
+                }
+                args.extend(["--dev", "/dev", "--proc", "/proc", "--bind"].map(OsString::from));
+                args.extend([
+                    workdir.into(),
+                    workdir.into(),
+                    "--chdir".into(),
+                    workdir.into(),
+                ]);
+                args.push("--".into());
+            }
+            Self::SandboxExec => {
+                let quote = |path: &Path| {
+                    path.to_string_lossy()
+                        .replace('\\', "\\\\")
+                        .replace('"', "\\\"")
+                };
+                // macOS matches real paths: /var/folders is /private/var/folders.
+                let real = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.into());
+                let workdir = real(workdir);
+                // Metadata (not contents) stays readable, so paths resolve.
+                let mut profile = String::from(
+                    "(version 1)(allow default)(deny file-read* file-write*)\
+                     (allow file-read-metadata)(allow file-read* (literal \"/\")",
+                );
+                for path in SYSTEM_PATHS
+                    .iter()
+                    .map(Path::new)
+                    .chain(reads.iter().map(|p| p.as_path()))
+                {
+                    profile.push_str(&format!(" (subpath \"{}\")", quote(&real(path))));
+                }
+                profile.push_str(&format!(
+                    " (subpath \"{0}\"))\
+                     (allow file-write* (subpath \"{0}\") (literal \"/dev/null\"))",
+                    quote(&workdir)
+                ));
+                if access == Access::Local {
+                    profile.push_str("(deny network*)");
+                }
+                args.extend(["-p".into(), profile.into()]);
+            }
+        }
+        args.push(program.into());
+        args
+    }
+}
+
+/// `program` with a scrubbed environment, running in `workdir` (and
+/// sandboxed if configured). Paths passed to it must be absolute, and
+/// besides `workdir` a sandboxed program can only read files passed to
+/// [`command_reading`]. A managed copy of `program` is run if there is one
+/// (see [`super::managed`]).
+pub fn command(program: &str, workdir: &Path, access: Access) -> Result<std::process::Command> {
+    command_reading(program, workdir, access, &[])
+}
+
+/// [`command`] for a program that reads `inputs` outside `workdir`.
+pub fn command_reading(
+    program: &str,
+    workdir: &Path,
+    access: Access,
+    inputs: &[&Path],
+) -> Result<std::process::Command> {
+    let config = Config::load();
+    let program = &managed::program(program);
+    let mut command = if config.sandbox_subprocesses {
+        let sandbox = Sandbox::for_platform()?;
+        let mut reads = installed_paths(program);
+        reads.push(get_managed_bin_dir());
+        reads.extend(inputs.iter().filter_map(|p| std::path::absolute(p).ok()));
+        let mut command = std::process::Command::new(sandbox.program());
+        command.args(sandbox.args(program, workdir, access, &reads));
+        command
+    } else {
+        std::process::Command::new(program)
+    };
+    command
+        .env_clear()
+        .envs(environment(std::env::vars_os(), &config.subprocess_env))
+        .current_dir(workdir);
+    Ok(command)
+}
+
+/// [`command`] for async callers.
+pub fn async_command(
+    program: &str,
+    workdir: &Path,
+    access: Access,
+) -> Result<async_process::Command> {
+    command(program, workdir, access).map(Into::into)
+}
+
+/// [`command_reading`] for async callers.
+pub fn async_command_reading(
+    program: &str,
+    workdir: &Path,
+    access: Access,
+    inputs: &[&Path],
+) -> Result<async_process::Command> {
+    command_reading(program, workdir, access, inputs).map(Into::into)
+}
+
+/// Where `program` is installed, as found on `PATH`, and the file a
+/// symlink there points at.
+fn installed_paths(program: &str) -> Vec<PathBuf> {
+    let found = if program.contains(std::path::MAIN_SEPARATOR) {
+        Some(PathBuf::from(program))
+    } else {
+        std::env::var_os("PATH").and_then(|path| {
+            std::env::split_paths(&path)
+                .map(|dir| dir.join(program))
+                .find(|candidate| candidate.is_file())
+        })
+    };
+    let Some(found) = found else {
+        return Vec::new();
+    };
+    let mut paths = vec![found.clone()];
+    if let Ok(target) = found.canonicalize()
+        && target != found
+    {
+        paths.push(target);
+    }
+    paths
+}
+
+/// Whether the configured sandbox can run, for the dependency check.
+/// `None` when sandboxing is off.
+pub fn sandbox_status() -> Option<Result<&'static str>> {
+    if !Config::load().sandbox_subprocesses {
+        return None;
+    }
+    Some(Sandbox::for_platform().and_then(|sandbox| {
+        let program = sandbox.program();
+        let probe = match sandbox {
+            Sandbox::Bubblewrap => std::process::Command::new(program)
+                .arg("--version")
+                .output(),
+            // sandbox-exec has no version flag; running `true` in an empty
+            // profile shows it works.
+            Sandbox::SandboxExec => std::process::Command::new(program)
+                .args(["-p", "(version 1)(allow default)", "true"])
+                .output(),
+        };
+        probe
+            .with_context(|| format!("{} not found", program))
+            .map(|_| program)
+    }))
+}
+
+/// The [`INHERITED`] part of `server`, with `extra` on top.
+fn environment(
+    server: impl Iterator<Item = (OsString, OsString)>,
+    extra: &BTreeMap<String, String>,
+) -> Vec<(OsString, OsString)> {
+    let mut env: Vec<(OsString, OsString)> = server
+        .filter(|(name, _)| {
+            name.to_str()
+                .is_some_and(|name| INHERITED.iter().any(|kept| kept.eq_ignore_ascii_case(name)))
+        })
+        .filter(|(name, _)| !extra.contains_key(&*name.to_string_lossy()))
+        .collect();
+    env.extend(
+        extra
+            .iter()
+            .map(|(name, value)| (name.into(), value.into())),
+    );
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_in_the_sandbox() {
+        let workdir = Path::new("/tmp/vt-audio");
+        let args = |sandbox: Sandbox, access| -> Vec<String> {
+            sandbox
+                .args("ffmpeg", workdir, access, &[PathBuf::from("/srv/talk.mp4")])
+                .iter()
+                .map(|a| a.to_string_lossy().to_string())
+                .collect()
+        };
+        let bwrap = args(Sandbox::Bubblewrap, Access::Local);
+        assert_eq!(
+            bwrap[bwrap.len() - 7..],
+            [
+                "--bind",
+                "/tmp/vt-audio",
+                "/tmp/vt-audio",
+                "--chdir",
+                "/tmp/vt-audio",
+                "--",
+                "ffmpeg"
+            ]
+        );
+        assert!(!bwrap.contains(&"--share-net".to_string()));
+        assert!(!bwrap.windows(2).any(|w| w == ["/", "/"]));
+        assert!(
+            bwrap
+                .windows(3)
+                .any(|w| w == ["--ro-bind-try", "/usr", "/usr"])
+        );
+        assert!(
+            bwrap
+                .windows(3)
+                .any(|w| w == ["--ro-bind-try", "/srv/talk.mp4", "/srv/talk.mp4"])
+        );
+        assert!(args(Sandbox::Bubblewrap, Access::Network).contains(&"--share-net".to_string()));
+
+        let profile = &args(Sandbox::SandboxExec, Access::Local)[1];
+        assert!(profile.contains("(deny file-read* file-write*)"));
+        assert!(profile.contains("(subpath \"/usr\")"));
+        assert!(profile.contains("(subpath \"/srv/talk.mp4\")"));
+        assert!(profile.contains("(subpath \"/tmp/vt-audio\")"));
+        assert!(profile.ends_with("(deny network*)"));
+        assert!(!args(Sandbox::SandboxExec, Access::Network)[1].contains("network"));
+    }
+
+    #[test]
+    fn keeps_only_listed_variables() {
+        let server = [
+            ("PATH", "/usr/bin"),
+            ("OPENAI_API_KEY", "sk-secret"),
+            ("TRANSCRIPT_ENCRYPTION_KEY_FILE", "/etc/vt/key"),
+            ("LANG", "en_US.UTF-8"),
+        ]
+        .map(|(k, v)| (OsString::from(k), OsString::from(v)));
+        let extra = BTreeMap::from([
+            ("https_proxy".to_string(), "http://egress:3128".to_string()),
+            ("LANG".to_string(), "C.UTF-8".to_string()),
+        ]);
+        let env = environment(server.into_iter(), &extra);
+        let names: Vec<String> = env
+            .iter()
+            .map(|(k, v)| format!("{}={}", k.to_string_lossy(), v.to_string_lossy()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "PATH=/usr/bin",
+                "LANG=C.UTF-8",
+                "https_proxy=http://egress:3128"
+            ]
+        );
+    }
+}